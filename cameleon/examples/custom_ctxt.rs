@@ -6,6 +6,8 @@
 //!
 //! In this example, we'll define a context in which the cache can be dynamically switched on and off.
 
+use std::borrow::Cow;
+
 use cameleon::genapi::{
     CacheStore, DefaultCacheStore, DefaultGenApiCtxt, DefaultNodeStore, DefaultValueStore,
     GenApiCtxt, NodeId, ValueCtxt,
@@ -23,7 +25,7 @@ impl CacheStore for MyCacheStore {
             self.store.cache(nid, address, length, data)
         }
     }
-    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<&[u8]> {
+    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<Cow<'_, [u8]>> {
         if self.use_cache {
             self.store.get_cache(nid, address, length)
         } else {
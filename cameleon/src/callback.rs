@@ -0,0 +1,246 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains types for callback based payload delivery.
+//!
+//! Callbacks are registered with `StreamHandle::on_frame` and invoked on a thread pool managed by
+//! the stream, so that a slow callback doesn't block the streaming loop.
+
+use std::{
+    convert::TryFrom,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use async_channel::{Receiver, Sender};
+use tracing::error;
+
+use super::payload::Payload;
+
+/// Number of pending frames per worker thread before frames are dropped.
+const QUEUE_CAPACITY_PER_WORKER: usize = 2;
+
+type Callback = dyn Fn(&Payload) + Send + Sync + 'static;
+
+/// Timing statistics of a frame callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CallbackStats {
+    /// Number of times the callback has been invoked.
+    pub invocations: u64,
+    /// Number of invocations that panicked.
+    pub panics: u64,
+    /// Total time spent in the callback.
+    pub total: Duration,
+    /// Duration of the slowest invocation.
+    pub max: Duration,
+    /// Duration of the last invocation.
+    pub last: Option<Duration>,
+}
+
+impl CallbackStats {
+    /// Returns the mean duration of the invocations. `None` if the callback has never been
+    /// invoked.
+    #[must_use]
+    pub fn mean(&self) -> Option<Duration> {
+        if self.invocations == 0 {
+            None
+        } else {
+            let nanos = self.total.as_nanos() / u128::from(self.invocations);
+            Some(Duration::from_nanos(
+                u64::try_from(nanos).unwrap_or(u64::MAX),
+            ))
+        }
+    }
+
+    fn update(&mut self, elapsed: Duration, panicked: bool) {
+        self.invocations += 1;
+        if panicked {
+            self.panics += 1;
+        }
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        self.last = Some(elapsed);
+    }
+}
+
+/// A handle of a registered frame callback.
+///
+/// Dropping the handle doesn't unregister the callback, call [`FrameCallbackHandle::unregister`]
+/// explicitly.
+#[derive(Clone)]
+pub struct FrameCallbackHandle(Arc<Registered>);
+
+impl FrameCallbackHandle {
+    /// Returns the timing statistics of the callback.
+    #[must_use]
+    pub fn stats(&self) -> CallbackStats {
+        *self.0.stats.lock().unwrap()
+    }
+
+    /// Unregisters the callback. Frames which are already queued for the callback are discarded.
+    pub fn unregister(&self) {
+        self.0.active.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the callback is still registered.
+    #[must_use]
+    pub fn is_registered(&self) -> bool {
+        self.0.active.load(Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for FrameCallbackHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameCallbackHandle")
+            .field("registered", &self.is_registered())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+struct Registered {
+    callback: Box<Callback>,
+    active: AtomicBool,
+    stats: Mutex<CallbackStats>,
+}
+
+impl Registered {
+    fn invoke(&self, payload: &Payload) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let start = Instant::now();
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| (self.callback)(payload))).is_err();
+        let elapsed = start.elapsed();
+        if panicked {
+            error!("frame callback panicked");
+        }
+        self.stats.lock().unwrap().update(elapsed, panicked);
+    }
+}
+
+/// Registry of frame callbacks shared between a stream handle and its streaming loop.
+#[derive(Clone, Default)]
+pub(crate) struct FrameCallbacks(Arc<Mutex<Vec<Arc<Registered>>>>);
+
+impl FrameCallbacks {
+    pub(crate) fn register<F>(&self, callback: F) -> FrameCallbackHandle
+    where
+        F: Fn(&Payload) + Send + Sync + 'static,
+    {
+        let registered = Arc::new(Registered {
+            callback: Box::new(callback),
+            active: AtomicBool::new(true),
+            stats: Mutex::default(),
+        });
+        self.0.lock().unwrap().push(registered.clone());
+        FrameCallbackHandle(registered)
+    }
+
+    /// Returns registered callbacks, pruning unregistered ones.
+    fn active(&self) -> Vec<Arc<Registered>> {
+        let mut callbacks = self.0.lock().unwrap();
+        callbacks.retain(|cb| cb.active.load(Ordering::Relaxed));
+        callbacks.clone()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.active().is_empty()
+    }
+}
+
+/// A thread pool which invokes frame callbacks.
+///
+/// Worker threads exit after the queued frames are handled once the pool is dropped.
+pub(crate) struct CallbackPool {
+    tx: Sender<Arc<Payload>>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl CallbackPool {
+    pub(crate) fn new(callbacks: FrameCallbacks, num_threads: usize) -> Self {
+        let num_threads = num_threads.max(1);
+        let (tx, rx) = async_channel::bounded(num_threads * QUEUE_CAPACITY_PER_WORKER);
+        let workers = (0..num_threads)
+            .map(|_| {
+                let rx: Receiver<Arc<Payload>> = rx.clone();
+                let callbacks = callbacks.clone();
+                thread::spawn(move || {
+                    while let Ok(payload) = rx.recv_blocking() {
+                        for callback in callbacks.active() {
+                            callback.invoke(&payload);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        Self { tx, workers }
+    }
+
+    /// Queues `payload` to be passed to the registered callbacks.
+    ///
+    /// Returns the payload back if all workers are busy and the queue is full.
+    pub(crate) fn dispatch(&self, payload: Payload) -> Result<(), Box<Payload>> {
+        self.tx.try_send(Arc::new(payload)).map_err(|err| {
+            let payload = err.into_inner();
+            Box::new(Arc::try_unwrap(payload).unwrap_or_else(|payload| (*payload).clone()))
+        })
+    }
+}
+
+impl Drop for CallbackPool {
+    fn drop(&mut self) {
+        self.tx.close();
+        for worker in self.workers.drain(..) {
+            worker.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::payload::PayloadBuilder;
+
+    fn payload(id: u64) -> Payload {
+        PayloadBuilder::new(id).build()
+    }
+
+    #[test]
+    fn test_callback_pool() {
+        let callbacks = FrameCallbacks::default();
+        assert!(callbacks.is_empty());
+
+        let received = Arc::new(Mutex::new(vec![]));
+        let received_cloned = received.clone();
+        let handle = callbacks.register(move |payload| {
+            received_cloned.lock().unwrap().push(payload.id());
+        });
+        let panicking = callbacks.register(|_| panic!("callback failure"));
+
+        let pool = CallbackPool::new(callbacks.clone(), 1);
+        for id in 0..2 {
+            pool.dispatch(payload(id)).unwrap();
+        }
+        // Wait for the queued frames to be handled.
+        drop(pool);
+
+        assert_eq!(*received.lock().unwrap(), vec![0, 1]);
+        assert_eq!(handle.stats().invocations, 2);
+        assert!(handle.stats().mean().is_some());
+        assert_eq!(panicking.stats().panics, 2);
+
+        handle.unregister();
+        panicking.unregister();
+        assert!(callbacks.is_empty());
+    }
+}
@@ -57,13 +57,22 @@
 //! camera.close().unwrap();
 //! ```
 
+use std::{convert::TryFrom, time::Duration};
+
 use auto_impl::auto_impl;
 use tracing::info;
 
 use super::{
-    genapi::{DefaultGenApiCtxt, FromXml, GenApiCtxt, ParamsCtxt},
-    payload::{channel, PayloadReceiver, PayloadSender},
-    CameleonError, CameleonResult, ControlResult, StreamError, StreamResult,
+    focus::{self, FocusSweep},
+    genapi::{
+        DefaultGenApiCtxt, EnumerationNode, FileReader, FileWriter, FromXml, GenApiCtxt,
+        IntegerNode, LineConfig, LineStatus, ParamsCtxt, RefreshReport, Transaction, WriteJournal,
+    },
+    health::{
+        self, Health, HealthAlert, HealthMonitor, HealthThresholds, HealthWatcher, StreamStatistics,
+    },
+    payload::{channel, HostTimestamp, Payload, PayloadReceiver, PayloadSender},
+    CameleonError, CameleonResult, CancellationToken, ControlResult, StreamError, StreamResult,
 };
 
 /// Provides easy-to-use access to a `GenICam` compatible camera.
@@ -127,18 +136,8 @@ pub struct Camera<Ctrl, Strm, Ctxt = DefaultGenApiCtxt> {
     pub ctxt: Option<Ctxt>,
     /// Information of the camera.
     info: CameraInfo,
-}
-
-macro_rules! expect_node {
-    ($ctxt:expr, $name:expr, $as_type:ident) => {{
-        let err_msg = std::concat!("missing ", $name);
-        let err_msg2 = std::concat!($name, " has invalid interface");
-        $ctxt
-            .node($name)
-            .ok_or_else(|| CameleonError::InvalidGenApiXml(err_msg.into()))?
-            .$as_type($ctxt)
-            .ok_or_else(|| CameleonError::InvalidGenApiXml(err_msg2.into()))?
-    }};
+    /// Journal passed to [`ParamsCtxt`]s created by the camera.
+    journal: Option<WriteJournal>,
 }
 
 impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
@@ -245,7 +244,10 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         Ctxt: GenApiCtxt + FromXml,
     {
         let xml = self.ctrl.genapi()?;
-        self.ctxt = Some(Ctxt::from_xml(&xml)?);
+        let read_only = self.is_read_only();
+        let mut ctxt = Ctxt::from_xml(&xml)?;
+        ctxt.set_read_only(read_only);
+        self.ctxt = Some(ctxt);
         Ok(xml)
     }
 
@@ -257,6 +259,15 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
     /// NOTE: This method doesn't change `AcquisitionMode` which defined in `GenICam SFNC`.  
     /// We recommend you to set the node to `Continuous` if you don't know which mode is the best.
     ///
+    /// The payload size is negotiated with the current settings of the camera on each call, so
+    /// changes to e.g. `Width` or `PixelFormat` take effect from the next call. See
+    /// [`StreamError::PayloadSizeChanged`] for changes made while streaming.
+    ///
+    /// If the camera has `TLParamsLocked`, transport layer parameters are locked until
+    /// [`Self::stop_streaming`] is called. Writing to the locked nodes fails with an error of
+    /// [`GenApiErrorKind::AccessDenied`](crate::genapi::GenApiErrorKind::AccessDenied), see
+    /// [`GenApiError::kind`](crate::genapi::GenApiError::kind).
+    ///
     /// See the `GenICam SFNC` specification for more details.
     ///
     /// # Examples
@@ -306,8 +317,11 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         // Enable streaimng.
         self.ctrl.enable_streaming()?;
         let mut ctxt = self.params_ctxt()?;
-        expect_node!(&ctxt, "TLParamsLocked", as_integer).set_value(&mut ctxt, 1)?;
-        expect_node!(&ctxt, "AcquisitionStart", as_command).execute(&mut ctxt)?;
+        set_tl_params_locked(&mut ctxt, true)?;
+        if let Err(e) = expect_node!(&ctxt, "AcquisitionStart", as_command).execute(&mut ctxt) {
+            set_tl_params_locked(&mut ctxt, false).ok();
+            return Err(e.into());
+        }
 
         // Start streaming loop.
         let (sender, receiver) = channel(cap, DEFAULT_BUFFER_CAP);
@@ -364,17 +378,23 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
         // Disable streaming.
         let mut ctxt = self.params_ctxt()?;
         expect_node!(&ctxt, "AcquisitionStop", as_command).execute(&mut ctxt)?;
-        expect_node!(&ctxt, "TLParamsLocked", as_integer).set_value(&mut ctxt, 0)?;
+        set_tl_params_locked(&mut ctxt, false)?;
         self.ctrl.disable_streaming()?;
 
         info!("stop streaming successfully");
         Ok(())
     }
 
-    /// Returns the context of the camera params.
+    /// Pauses the streaming.
     ///
-    /// Make sure to load `GenApi` context before calling this method.
-    /// See [`load_context`](Self::load_context) and [`set_context`](Self::set_context) how to configure `GenApi` context.
+    /// Acquisition is stopped and the streaming loop stops receiving payloads, but the payload
+    /// receiver stays valid and the transport layer keeps its configuration and buffers, so
+    /// [`Self::resume_streaming`] restarts the streaming almost instantly. This is useful to
+    /// toggle a preview on and off.
+    ///
+    /// Transport layer parameters stay locked while the streaming is paused.
+    ///
+    /// Does nothing if the streaming isn't started or is already paused.
     ///
     /// # Examples
     /// ```
@@ -387,193 +407,1389 @@ impl<Ctrl, Strm, Ctxt> Camera<Ctrl, Strm, Ctxt> {
     /// camera.open().unwrap();
     /// camera.load_context().unwrap();
     ///
-    /// // Get params context.
-    /// let mut params_ctxt = camera.params_ctxt().unwrap();
-    ///
-    /// // Get `Gain` node of `GenApi`.
-    /// // `GenApi SFNC` defines that `Gain` node should have `IFloat` interface,
-    /// // so this conversion would be success if the camera follows that.
-    /// // Some vendors may define `Gain` node as `IInteger`, in that case, use
-    /// // `as_integer(&params_ctxt)` instead of `as_float(&params_ctxt).
-    /// let gain_node = params_ctxt.node("Gain").unwrap().as_float(&params_ctxt).unwrap();
-    ///
-    /// // Get the current value of `Gain`.
-    /// if gain_node.is_readable(&mut params_ctxt).unwrap() {
-    ///     let value = gain_node.value(&mut params_ctxt).unwrap();
-    ///     println!("{}", value);
-    /// }
+    /// let payload_rx = camera.start_streaming(3).unwrap();
+    /// camera.pause_streaming().unwrap();
+    /// camera.resume_streaming().unwrap();
+    /// let payload = payload_rx.recv_blocking().unwrap();
     ///
-    /// // Set `0.1` to `Gain`.
-    /// if gain_node.is_writable(&mut params_ctxt).unwrap() {
-    ///     gain_node.set_value(&mut params_ctxt, 0.1).unwrap();
-    /// }
     /// # camera.close().unwrap();
     /// ```
-    pub fn params_ctxt(&mut self) -> CameleonResult<ParamsCtxt<&mut Ctrl, &mut Ctxt>>
+    #[tracing::instrument(skip(self),
+                          level = "info",
+                          fields(camera = ?self.info()))]
+    pub fn pause_streaming(&mut self) -> CameleonResult<()>
     where
         Ctrl: DeviceControl,
         Strm: PayloadStream,
         Ctxt: GenApiCtxt,
     {
-        if let Some(ctxt) = self.ctxt.as_mut() {
-            Ok(ParamsCtxt {
-                ctrl: &mut self.ctrl,
-                ctxt,
-            })
-        } else {
-            Err(CameleonError::GenApiContextMissing)
+        info!("try pausing streaming");
+        if !self.strm.is_loop_running() || self.strm.is_loop_paused() {
+            return Ok(());
         }
+
+        // Pause the loop first so that it doesn't wait for payloads which never come.
+        self.strm.pause_streaming_loop()?;
+        let executed = self.params_ctxt().and_then(|mut ctxt| {
+            Ok(expect_node!(&ctxt, "AcquisitionStop", as_command).execute(&mut ctxt)?)
+        });
+        if let Err(e) = executed {
+            self.strm.resume_streaming_loop().ok();
+            return Err(e);
+        }
+
+        info!("pause streaming successfully");
+        Ok(())
     }
 
-    /// Returns basic information of the camera.
+    /// Resumes the streaming paused by [`Self::pause_streaming`].
+    ///
+    /// Payloads are sent to the receiver returned from the previous [`Self::start_streaming`]
+    /// call.
+    ///
+    /// Does nothing if the streaming isn't paused.
+    #[tracing::instrument(skip(self),
+                          level = "info",
+                          fields(camera = ?self.info()))]
+    pub fn resume_streaming(&mut self) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        info!("try resuming streaming");
+        if !self.strm.is_loop_running() || !self.strm.is_loop_paused() {
+            return Ok(());
+        }
+
+        // Resume the loop first so that it's ready for the first payload.
+        self.strm.resume_streaming_loop()?;
+        let executed = self.params_ctxt().and_then(|mut ctxt| {
+            Ok(expect_node!(&ctxt, "AcquisitionStart", as_command).execute(&mut ctxt)?)
+        });
+        if let Err(e) = executed {
+            self.strm.pause_streaming_loop().ok();
+            return Err(e);
+        }
+
+        info!("resume streaming successfully");
+        Ok(())
+    }
+
+    /// Returns `true` if the streaming is paused by [`Self::pause_streaming`].
+    pub fn is_streaming_paused(&self) -> bool
+    where
+        Strm: PayloadStream,
+    {
+        self.strm.is_loop_paused()
+    }
+
+    /// Acquires a single frame.
+    ///
+    /// This is a shorthand for [`Self::acquire_n`] with `n = 1`.
     ///
-    /// This information can be obtained without calling [`Self::open`].
     /// # Examples
     /// ```
+    /// # use std::time::Duration;
     /// # use cameleon::u3v;
     /// # let mut cameras = u3v::enumerate_cameras().unwrap();
     /// # if cameras.is_empty() {
     /// #     return;
     /// # }
     /// # let mut camera = cameras.pop().unwrap();
-    /// let info = camera.info();
-    /// ```
-    pub fn info(&self) -> &CameraInfo {
-        &self.info
-    }
-
-    /// Constructs a camera.
-    pub fn new(ctrl: Ctrl, strm: Strm, ctxt: Option<Ctxt>, info: CameraInfo) -> Self {
-        Self {
-            ctrl,
-            strm,
-            ctxt,
-            info,
-        }
-    }
-
-    /// Converts internal types.
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
     ///
-    /// This method works same as `std::convert::From`, just hack to avoid
-    /// `E0119`.
-    pub fn convert_from<Ctrl2, Strm2, Ctxt2>(from: Camera<Ctrl2, Strm2, Ctxt2>) -> Self
+    /// let payload = camera.acquire_one(Duration::from_secs(3)).unwrap();
+    /// println!("{:?}", payload.image_info());
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn acquire_one(&mut self, timeout: Duration) -> CameleonResult<Payload>
     where
-        Ctrl: From<Ctrl2>,
-        Strm: From<Strm2>,
-        Ctxt: From<Ctxt2>,
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
     {
-        Camera::new(
-            from.ctrl.into(),
-            from.strm.into(),
-            from.ctxt.map(|ctxt| ctxt.into()),
-            from.info,
-        )
+        Ok(self.acquire_n(1, timeout)?.remove(0))
     }
 
-    /// Converts internal types. This method work same as `std::convert::Into`, just hack to avoid
-    /// `E0119`.
+    /// Acquires exactly `n` frames, then stops streaming.
+    ///
+    /// `AcquisitionMode` is set to `SingleFrame` if `n` is 1, or to `MultiFrame` with
+    /// `AcquisitionFrameCount` set to `n` otherwise. If the camera doesn't support the mode, e.g.
+    /// `n` exceeds the maximum of `AcquisitionFrameCount`, `Continuous` is used instead and frames
+    /// after the `n`-th are discarded. `AcquisitionMode` is restored once the acquisition ends.
+    ///
+    /// Returns [`StreamError::Timeout`] if no frame arrives within `timeout`, which is applied to
+    /// each frame. A frame dropped by the device or the streaming loop is not retried, so `timeout`
+    /// should be set shorter than the time to acquire all frames.
     ///
     /// # Examples
-    /// ```rust
+    /// ```
+    /// # use std::time::Duration;
     /// # use cameleon::u3v;
     /// # let mut cameras = u3v::enumerate_cameras().unwrap();
     /// # if cameras.is_empty() {
     /// #     return;
     /// # }
-    /// # let camera = cameras.pop().unwrap();
-    /// use cameleon::{DeviceControl, PayloadStream, Camera};
-    /// use cameleon::genapi::NoCacheGenApiCtxt;
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
     ///
-    /// // Convert into `Camera<Box<dyn DeviceControl>, Box<dyn PayloadStream>, NoCacheGenApiCtxt>`.
-    /// let dyn_camera: Camera<Box<dyn DeviceControl>, Box<dyn PayloadStream>, NoCacheGenApiCtxt> =
-    ///     camera.convert_into();
+    /// let payloads = camera.acquire_n(10, Duration::from_secs(3)).unwrap();
+    /// assert_eq!(payloads.len(), 10);
+    ///
+    /// # camera.close().unwrap();
     /// ```
-    pub fn convert_into<Ctrl2, Strm2, Ctxt2>(self) -> Camera<Ctrl2, Strm2, Ctxt2>
+    pub fn acquire_n(&mut self, n: usize, timeout: Duration) -> CameleonResult<Vec<Payload>>
     where
-        Ctrl: Into<Ctrl2>,
-        Strm: Into<Strm2>,
-        Ctxt: Into<Ctxt2>,
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
     {
-        Camera::new(
-            self.ctrl.into(),
-            self.strm.into(),
-            self.ctxt.map(|ctxt| ctxt.into()),
-            self.info,
-        )
-    }
-
-    /// Set a context to the camera. It's recommended to use [`Self::load_context`] instead if `Self::Ctxt`
-    /// implements [`FromXml`] trait.
-    pub fn set_context<Ctxt2>(self, ctxt: Ctxt2) -> Camera<Ctrl, Strm, Ctxt2> {
-        Camera {
-            ctrl: self.ctrl,
-            strm: self.strm,
-            ctxt: Some(ctxt),
-            info: self.info,
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        if self.strm.is_loop_running() {
+            return Err(StreamError::InStreaming.into());
         }
-    }
-}
 
-/// Information of the camera.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct CameraInfo {
-    /// Vendor name of the camera.
-    pub vendor_name: String,
-    /// Model name of the camera.
-    pub model_name: String,
-    ///Serial number of the camera.
-    pub serial_number: String,
-}
+        let prev_mode = {
+            let mut ctxt = self.params_ctxt()?;
+            let mode_node = expect_node!(&ctxt, "AcquisitionMode", as_enumeration);
+            let prev_mode = mode_node
+                .current_entry(&mut ctxt)?
+                .symbolic(&ctxt)
+                .to_string();
+            set_acquisition_mode_for(&mut ctxt, mode_node, n)?;
+            prev_mode
+        };
 
-/// This trait provides operations on the device's memory.
-#[auto_impl(&mut, Box)]
-pub trait DeviceControl {
-    /// Opens the handle.
-    fn open(&mut self) -> ControlResult<()>;
+        let result = self.start_streaming(n).and_then(|receiver| {
+            let mut payloads = Vec::with_capacity(n);
+            while payloads.len() < n {
+                payloads.push(receiver.recv_timeout(timeout)?);
+            }
+            Ok(payloads)
+        });
 
-    /// Closes the handle.
-    fn close(&mut self) -> ControlResult<()>;
+        let stopped = self.stop_streaming();
+        let restored = self.params_ctxt().and_then(|mut ctxt| {
+            let mode_node = expect_node!(&ctxt, "AcquisitionMode", as_enumeration);
+            Ok(mode_node.set_entry_by_symbolic(&mut ctxt, &prev_mode)?)
+        });
 
-    /// Returns `true` if device is already opened.
-    fn is_opened(&self) -> bool;
+        let payloads = result?;
+        stopped?;
+        restored?;
+        Ok(payloads)
+    }
 
-    /// Reads data from the device's memory.
+    /// Sets `AcquisitionMode` to `Continuous` and starts streaming.
     ///
-    /// Reads length is same as `buf.len()`.
-    fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()>;
+    /// See [`Self::start_streaming`] for details.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let payload_rx = camera.start_continuous(3).unwrap();
+    /// let payload = payload_rx.recv_blocking().unwrap();
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn start_continuous(&mut self, cap: usize) -> CameleonResult<PayloadReceiver>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        if self.strm.is_loop_running() {
+            return Err(StreamError::InStreaming.into());
+        }
 
-    /// Writes data to the device's memory.
-    fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()>;
+        {
+            let mut ctxt = self.params_ctxt()?;
+            let mode_node = expect_node!(&ctxt, "AcquisitionMode", as_enumeration);
+            mode_node.set_entry_by_symbolic(&mut ctxt, "Continuous")?;
+        }
+        self.start_streaming(cap)
+    }
 
-    /// Returns `GenICam` xml string.
-    fn genapi(&mut self) -> ControlResult<String>;
+    /// Sets the region of interest of the image.
+    ///
+    /// Each value is rounded to the nearest valid value with respect to min, max and increment of
+    /// `Width`, `Height`, `OffsetX` and `OffsetY` nodes. The values are written in an order that
+    /// never violates their dependencies, i.e. the size is shrunk before the offset is moved, and
+    /// the offset is moved before the size is grown.
+    ///
+    /// Returns the actually applied [`Roi`]. If the camera doesn't have `OffsetX` or `OffsetY`,
+    /// the corresponding offset is left untouched.
+    ///
+    /// NOTE: Most cameras reject changing ROI while streaming.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let roi = camera.set_roi(100, 100, 640, 480).unwrap();
+    /// println!("applied ROI: {:?}", roi);
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn set_roi(&mut self, x: i64, y: i64, width: i64, height: i64) -> CameleonResult<Roi>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let mut ctxt = self.params_ctxt()?;
+        let width_node = expect_node!(&ctxt, "Width", as_integer);
+        let height_node = expect_node!(&ctxt, "Height", as_integer);
+        let offset_x_node = ctxt.node("OffsetX").and_then(|n| n.as_integer(&ctxt));
+        let offset_y_node = ctxt.node("OffsetY").and_then(|n| n.as_integer(&ctxt));
 
-    /// Enables streaming.
-    fn enable_streaming(&mut self) -> ControlResult<()>;
+        let (x, width) = set_roi_axis(&mut ctxt, offset_x_node, width_node, x, width)?;
+        let (y, height) = set_roi_axis(&mut ctxt, offset_y_node, height_node, y, height)?;
 
-    /// Disables streaming.
-    fn disable_streaming(&mut self) -> ControlResult<()>;
-}
+        Ok(Roi {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
 
-/// This trait provides streaming capability.
-#[auto_impl(&mut, Box)]
-pub trait PayloadStream {
-    /// Opens the handle.
-    fn open(&mut self) -> StreamResult<()>;
+    /// Latches the clock of the device, and returns the latched timestamp with the host times
+    /// just before and after the operation, which is useful to estimate the offset between the
+    /// device clock and the host clock.
+    ///
+    /// The clock is latched directly through the bootstrap registers if the control handle
+    /// supports it, see [`DeviceControl::latch_timestamp`]. Otherwise, `TimestampLatch` node is
+    /// executed and `TimestampLatchValue` node is read, which requires the `GenApi` context.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let latch = camera.latch_timestamp().unwrap();
+    /// println!(
+    ///     "device timestamp {} at {:?} (+/- {:?})",
+    ///     latch.device_timestamp,
+    ///     latch.host_midpoint().wall_clock,
+    ///     latch.round_trip() / 2
+    /// );
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn latch_timestamp(&mut self) -> CameleonResult<TimestampLatch>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let host_before = HostTimestamp::now();
+        let device_timestamp = match self.ctrl.latch_timestamp()? {
+            Some(timestamp) => timestamp,
+            None => {
+                let mut ctxt = self.params_ctxt()?;
+                let latch_node = expect_node!(&ctxt, "TimestampLatch", as_command);
+                let value_node = expect_node!(&ctxt, "TimestampLatchValue", as_integer);
+                latch_node.execute(&mut ctxt)?;
+                value_node.value(&mut ctxt)? as u64
+            }
+        };
+        let host_after = HostTimestamp::now();
 
-    /// Closes the handle.
-    fn close(&mut self) -> StreamResult<()>;
+        Ok(TimestampLatch {
+            device_timestamp,
+            host_before,
+            host_after,
+        })
+    }
 
-    /// Starts streaming.
-    fn start_streaming_loop(
-        &mut self,
-        sender: PayloadSender,
-        ctrl: &mut dyn DeviceControl,
-    ) -> StreamResult<()>;
+    /// Steps the focus node `node`, e.g. `FocusPos`, through `steps` positions evenly spaced from
+    /// `start` to `end`, and returns the position where [`Payload::focus_metric`] is the highest.
+    ///
+    /// A frame is acquired with [`Self::acquire_one`] at each position, so the lens must have
+    /// settled by the time the frame is exposed. The node is left at the best position. `IInteger`
+    /// nodes are set to rounded positions.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let sweep = camera
+    ///     .focus_sweep("FocusPos", 0.0, 1000.0, 21, Duration::from_secs(1))
+    ///     .unwrap();
+    /// println!("best position: {}", sweep.best_position);
+    /// ```
+    pub fn focus_sweep(
+        &mut self,
+        node: &str,
+        start: f64,
+        end: f64,
+        steps: usize,
+        timeout: Duration,
+    ) -> CameleonResult<FocusSweep>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        focus::sweep(self, node, start, end, steps, timeout)
+    }
+
+    /// Samples health of the camera.
+    ///
+    /// Temperatures are sampled from `DeviceTemperature` for each available entry of
+    /// `DeviceTemperatureSelector`, and the selector is restored after sampling.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let health = camera.health().unwrap();
+    /// for temp in &health.temperatures {
+    ///     println!("{:?}: {}", temp.selector, temp.value);
+    /// }
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn health(&mut self) -> CameleonResult<Health>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let stream = self.strm.statistics();
+        let mut ctxt = self.params_ctxt()?;
+        health::sample(&mut ctxt, stream)
+    }
+
+    /// Samples health of the camera and feeds it to `monitor`, returning the alerts raised or
+    /// cleared by the sample.
+    ///
+    /// This is a poll-based alternative to [`Camera::health_watcher`] that doesn't spawn a
+    /// thread, so the caller decides when to sample, e.g. between acquisitions.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// use cameleon::health::{HealthMonitor, HealthThresholds};
+    ///
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let mut monitor = HealthMonitor::new(HealthThresholds {
+    ///     max_temperature: Some(60.0),
+    ///     ..HealthThresholds::default()
+    /// });
+    /// for alert in camera.poll_health(&mut monitor).unwrap() {
+    ///     println!("{:?}", alert);
+    /// }
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn poll_health(&mut self, monitor: &mut HealthMonitor) -> CameleonResult<Vec<HealthAlert>>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        let health = self.health()?;
+        Ok(monitor.update(&health))
+    }
+
+    /// Spawns [`HealthWatcher`] that samples health of the camera every `interval`.
+    ///
+    /// The watcher works on clones of the control handle and the context, so they must be
+    /// sharable, e.g. `SharedControlHandle` and `SharedDefaultGenApiCtxt`.
+    /// Stream statistics are not sampled by the watcher.
+    pub fn health_watcher(
+        &self,
+        interval: std::time::Duration,
+        thresholds: HealthThresholds,
+    ) -> CameleonResult<HealthWatcher>
+    where
+        Ctrl: DeviceControl + Clone + Send + 'static,
+        Ctxt: GenApiCtxt + Clone + Send + 'static,
+    {
+        // Selector writes made while sampling are restored, so they aren't journaled.
+        let mut ctxt = ParamsCtxt::new(
+            self.ctrl.clone(),
+            self.ctxt
+                .clone()
+                .ok_or(CameleonError::GenApiContextMissing)?,
+        );
+        Ok(HealthWatcher::spawn(interval, thresholds, move || {
+            health::sample(&mut ctxt, None)
+        }))
+    }
+
+    /// Opens a file on the camera for reading through `GenApi File Access`.
+    ///
+    /// `file` is a symbolic name of an entry of `FileSelector`, e.g. `UserSet1`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// use std::io::Read;
+    ///
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let mut data = vec![];
+    /// camera.file_read("UserSet1").unwrap().read_to_end(&mut data).unwrap();
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn file_read(&mut self, file: &str) -> CameleonResult<FileReader<&mut Ctrl, &mut Ctxt>>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        FileReader::open(self.params_ctxt()?, file)
+    }
+
+    /// Opens a file on the camera for writing through `GenApi File Access`.
+    ///
+    /// `file` is a symbolic name of an entry of `FileSelector`, e.g. `DeviceFirmware`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// use std::io::Write;
+    ///
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let mut writer = camera.file_write("UserSet1").unwrap();
+    /// writer.write_all(&[0; 64]).unwrap();
+    /// writer.close().unwrap();
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn file_write(&mut self, file: &str) -> CameleonResult<FileWriter<&mut Ctrl, &mut Ctxt>>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        FileWriter::open(self.params_ctxt()?, file)
+    }
+
+    /// Selects `line` by the symbolic name of `LineSelector` entry, e.g. `Line0`, and applies
+    /// `LineMode`, `LineSource` and `LineInverter` in `cfg`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// use cameleon::genapi::LineConfig;
+    ///
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// // Use `Line0` as a hardware trigger input, and output strobe signal to `Line1`.
+    /// camera.configure_line("Line0", &LineConfig::input()).unwrap();
+    /// camera
+    ///     .configure_line("Line1", &LineConfig::output("ExposureActive"))
+    ///     .unwrap();
+    ///
+    /// let status = camera.line_status_all().unwrap();
+    /// println!("Line0 is high: {}", status.is_high(0));
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn configure_line(&mut self, line: &str, cfg: &LineConfig) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        cfg.apply(&mut self.params_ctxt()?, line)
+    }
+
+    /// Reads `LineStatusAll` that holds the status of all lines as a bitset.
+    pub fn line_status_all(&mut self) -> CameleonResult<LineStatus>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        LineStatus::read(&mut self.params_ctxt()?)
+    }
+
+    /// Invalidates all cache of the context and re-reads every readable streamable node, e.g.
+    /// after executing `UserSetLoad` or reconnecting to the camera.
+    ///
+    /// Nodes which fail to be read are reported in [`RefreshReport::failures`] without stopping
+    /// the refresh. See [`ParamsCtxt::refresh_all`] for details.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let report = camera.refresh_all().unwrap();
+    /// for (name, value) in &report.values {
+    ///     println!("{}: {:?}", name, value);
+    /// }
+    /// for (name, e) in &report.failures {
+    ///     println!("{}: {}", name, e);
+    /// }
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn refresh_all(&mut self) -> CameleonResult<RefreshReport>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        Ok(self.params_ctxt()?.refresh_all())
+    }
+
+    /// Runs `f` with `token` set to the control handle, so that long operations in `f`, e.g.
+    /// `GenApi` xml download, file access, `UserSetLoad` or [`Self::start_streaming`], are
+    /// aborted with [`ControlError::Cancelled`](crate::ControlError::Cancelled) once the token is
+    /// cancelled.
+    ///
+    /// The control handle checks the token before each transaction with the device and while
+    /// waiting for a pending acknowledge. A USB transfer which is already in flight isn't
+    /// interrupted, so cancellation takes effect after the transfer completes or times out. Use
+    /// [`DeviceControl::set_timeout_override`] to bound the latency. The setup of the stream
+    /// handle in [`Self::start_streaming`] isn't cancellable.
+    ///
+    /// The token is cleared when `f` returns or panics.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// use std::time::Duration;
+    ///
+    /// use cameleon::CancellationToken;
+    ///
+    /// camera.open().unwrap();
+    ///
+    /// // Cancel loading context if it takes more than 5 seconds.
+    /// let token = CancellationToken::new();
+    /// let cloned = token.clone();
+    /// std::thread::spawn(move || {
+    ///     std::thread::sleep(Duration::from_secs(5));
+    ///     cloned.cancel();
+    /// });
+    /// let result = camera.run_cancellable(&token, |camera| camera.load_context());
+    ///
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn run_cancellable<F, R>(&mut self, token: &CancellationToken, f: F) -> CameleonResult<R>
+    where
+        Ctrl: DeviceControl,
+        F: FnOnce(&mut Self) -> CameleonResult<R>,
+    {
+        self.ctrl.set_cancellation_token(Some(token.clone()));
+        let guard = ClearCancellationToken(self);
+        f(&mut *guard.0)
+    }
+
+    /// Returns the context of the camera params.
+    ///
+    /// Make sure to load `GenApi` context before calling this method.
+    /// See [`load_context`](Self::load_context) and [`set_context`](Self::set_context) how to configure `GenApi` context.
+    ///
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// // Get params context.
+    /// let mut params_ctxt = camera.params_ctxt().unwrap();
+    ///
+    /// // Get `Gain` node of `GenApi`.
+    /// // `GenApi SFNC` defines that `Gain` node should have `IFloat` interface,
+    /// // so this conversion would be success if the camera follows that.
+    /// // Some vendors may define `Gain` node as `IInteger`, in that case, use
+    /// // `as_integer(&params_ctxt)` instead of `as_float(&params_ctxt).
+    /// let gain_node = params_ctxt.node("Gain").unwrap().as_float(&params_ctxt).unwrap();
+    ///
+    /// // Get the current value of `Gain`.
+    /// if gain_node.is_readable(&mut params_ctxt).unwrap() {
+    ///     let value = gain_node.value(&mut params_ctxt).unwrap();
+    ///     println!("{}", value);
+    /// }
+    ///
+    /// // Set `0.1` to `Gain`.
+    /// if gain_node.is_writable(&mut params_ctxt).unwrap() {
+    ///     gain_node.set_value(&mut params_ctxt, 0.1).unwrap();
+    /// }
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn params_ctxt(&mut self) -> CameleonResult<ParamsCtxt<&mut Ctrl, &mut Ctxt>>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+    {
+        if let Some(ctxt) = self.ctxt.as_mut() {
+            let mut params_ctxt = ParamsCtxt::new(&mut self.ctrl, ctxt);
+            params_ctxt.set_journal(self.journal.clone());
+            Ok(params_ctxt)
+        } else {
+            Err(CameleonError::GenApiContextMissing)
+        }
+    }
+
+    /// Sets read-only mode of the `GenApi` context.
+    ///
+    /// In read-only mode, all writes through the context are rejected with an error of
+    /// [`GenApiErrorKind::AccessDenied`](crate::genapi::GenApiErrorKind::AccessDenied) before
+    /// reaching the device, so applications which only monitor the camera can't change its state
+    /// by mistake. Note that methods writing `GenApi` nodes, e.g. [`Self::start_streaming`], also
+    /// fail in read-only mode.
+    ///
+    /// The mode is kept when the context is reloaded by [`Self::load_context`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    /// camera.set_read_only(true).unwrap();
+    ///
+    /// let mut params_ctxt = camera.params_ctxt().unwrap();
+    /// let gain = params_ctxt.node("Gain").unwrap().as_float(&params_ctxt).unwrap();
+    /// assert!(gain.set_value(&mut params_ctxt, 0.1).is_err());
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn set_read_only(&mut self, read_only: bool) -> CameleonResult<()>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ctxt = self
+            .ctxt
+            .as_mut()
+            .ok_or(CameleonError::GenApiContextMissing)?;
+        ctxt.set_read_only(read_only);
+        Ok(())
+    }
+
+    /// Returns `true` if the `GenApi` context is in read-only mode. See [`Self::set_read_only`].
+    ///
+    /// Returns `false` if the context isn't loaded.
+    pub fn is_read_only(&mut self) -> bool
+    where
+        Ctxt: GenApiCtxt,
+    {
+        self.ctxt.as_mut().is_some_and(GenApiCtxt::is_read_only)
+    }
+
+    /// Starts recording successful writes to `GenApi` nodes into a journal which keeps the latest
+    /// `capacity` entries, and returns the journal.
+    ///
+    /// Each entry contains the name of the node, the value before the write if it was cached,
+    /// the value after the write and the time of the write. A journal enabled before replaces
+    /// the old one.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    /// let journal = camera.enable_write_journal(100);
+    ///
+    /// let mut params_ctxt = camera.params_ctxt().unwrap();
+    /// let gain = params_ctxt.node("Gain").unwrap().as_float(&params_ctxt).unwrap();
+    /// gain.set_value(&mut params_ctxt, 0.1).unwrap();
+    ///
+    /// for entry in journal.entries() {
+    ///     println!("{}: {:?} -> {:?}", entry.node, entry.old_value, entry.new_value);
+    /// }
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn enable_write_journal(&mut self, capacity: usize) -> WriteJournal {
+        let journal = WriteJournal::new(capacity);
+        self.journal = Some(journal.clone());
+        journal
+    }
+
+    /// Stops recording writes to `GenApi` nodes. See [`Self::enable_write_journal`].
+    ///
+    /// Journals returned before keep their entries.
+    pub fn disable_write_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Returns the journal enabled by [`Self::enable_write_journal`], or `None` if it's disabled.
+    pub fn write_journal(&self) -> Option<WriteJournal> {
+        self.journal.clone()
+    }
+
+    /// Writes multiple nodes in a transaction. If `f` returns an error, all writes applied so far
+    /// are rolled back in the reverse order, then the error is returned.
+    ///
+    /// See [`ParamsCtxt::transaction`] for details.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// // `Width` is restored if setting `Height` fails.
+    /// let res = camera.transaction(|tx| {
+    ///     tx.set("Width", 640)?;
+    ///     tx.set("Height", 480)
+    /// });
+    /// if let Err(e) = res {
+    ///     println!("failed to configure the camera: {}", e);
+    /// }
+    /// # camera.close().unwrap();
+    /// ```
+    pub fn transaction<F, R>(&mut self, f: F) -> CameleonResult<R>
+    where
+        Ctrl: DeviceControl,
+        Strm: PayloadStream,
+        Ctxt: GenApiCtxt,
+        F: FnOnce(&mut Transaction<&mut Ctrl, &mut Ctxt>) -> CameleonResult<R>,
+    {
+        self.params_ctxt()?.transaction(f)
+    }
+
+    /// Returns basic information of the camera.
+    ///
+    /// This information can be obtained without calling [`Self::open`].
+    /// # Examples
+    /// ```
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let mut camera = cameras.pop().unwrap();
+    /// let info = camera.info();
+    /// ```
+    pub fn info(&self) -> &CameraInfo {
+        &self.info
+    }
+
+    /// Constructs a camera.
+    pub fn new(ctrl: Ctrl, strm: Strm, ctxt: Option<Ctxt>, info: CameraInfo) -> Self {
+        Self {
+            ctrl,
+            strm,
+            ctxt,
+            info,
+            journal: None,
+        }
+    }
+
+    /// Converts internal types.
+    ///
+    /// This method works same as `std::convert::From`, just hack to avoid
+    /// `E0119`.
+    pub fn convert_from<Ctrl2, Strm2, Ctxt2>(from: Camera<Ctrl2, Strm2, Ctxt2>) -> Self
+    where
+        Ctrl: From<Ctrl2>,
+        Strm: From<Strm2>,
+        Ctxt: From<Ctxt2>,
+    {
+        Camera {
+            ctrl: from.ctrl.into(),
+            strm: from.strm.into(),
+            ctxt: from.ctxt.map(|ctxt| ctxt.into()),
+            info: from.info,
+            journal: from.journal,
+        }
+    }
+
+    /// Converts internal types. This method work same as `std::convert::Into`, just hack to avoid
+    /// `E0119`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # if cameras.is_empty() {
+    /// #     return;
+    /// # }
+    /// # let camera = cameras.pop().unwrap();
+    /// use cameleon::{DeviceControl, PayloadStream, Camera};
+    /// use cameleon::genapi::NoCacheGenApiCtxt;
+    ///
+    /// // Convert into `Camera<Box<dyn DeviceControl>, Box<dyn PayloadStream>, NoCacheGenApiCtxt>`.
+    /// let dyn_camera: Camera<Box<dyn DeviceControl>, Box<dyn PayloadStream>, NoCacheGenApiCtxt> =
+    ///     camera.convert_into();
+    /// ```
+    pub fn convert_into<Ctrl2, Strm2, Ctxt2>(self) -> Camera<Ctrl2, Strm2, Ctxt2>
+    where
+        Ctrl: Into<Ctrl2>,
+        Strm: Into<Strm2>,
+        Ctxt: Into<Ctxt2>,
+    {
+        Camera {
+            ctrl: self.ctrl.into(),
+            strm: self.strm.into(),
+            ctxt: self.ctxt.map(|ctxt| ctxt.into()),
+            info: self.info,
+            journal: self.journal,
+        }
+    }
+
+    /// Erases the concrete types of the control and stream handles.
+    ///
+    /// Cameras of different transport layers have the same type after erasing, so they can be
+    /// stored in one collection.
+    ///
+    /// # Examples
+    /// ```
+    /// use cameleon::{u3v, ErasedCamera};
+    ///
+    /// let cameras: Vec<ErasedCamera> = u3v::enumerate_cameras()
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(|camera| camera.into_erased())
+    ///     .collect();
+    /// ```
+    pub fn into_erased(self) -> ErasedCamera<Ctxt>
+    where
+        Ctrl: DeviceControl + Send + 'static,
+        Strm: PayloadStream + Send + 'static,
+    {
+        Camera {
+            ctrl: Box::new(self.ctrl),
+            strm: Box::new(self.strm),
+            ctxt: self.ctxt,
+            info: self.info,
+            journal: self.journal,
+        }
+    }
+
+    /// Set a context to the camera. It's recommended to use [`Self::load_context`] instead if `Self::Ctxt`
+    /// implements [`FromXml`] trait.
+    pub fn set_context<Ctxt2>(self, ctxt: Ctxt2) -> Camera<Ctrl, Strm, Ctxt2> {
+        Camera {
+            ctrl: self.ctrl,
+            strm: self.strm,
+            ctxt: Some(ctxt),
+            info: self.info,
+            journal: self.journal,
+        }
+    }
+}
+
+/// Clears the cancellation token set by [`Camera::run_cancellable`] even if the closure panics.
+struct ClearCancellationToken<'a, Ctrl: DeviceControl, Strm, Ctxt>(
+    &'a mut Camera<Ctrl, Strm, Ctxt>,
+);
+
+impl<Ctrl: DeviceControl, Strm, Ctxt> Drop for ClearCancellationToken<'_, Ctrl, Strm, Ctxt> {
+    fn drop(&mut self) {
+        self.0.ctrl.set_cancellation_token(None);
+    }
+}
+
+/// A camera whose control and stream handles are type-erased. See [`Camera::into_erased`].
+pub type ErasedCamera<Ctxt = DefaultGenApiCtxt> =
+    Camera<Box<dyn DeviceControl + Send>, Box<dyn PayloadStream + Send>, Ctxt>;
+
+// `ErasedCamera` is meant to be moved to worker threads, so make sure it stays `Send`.
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<ErasedCamera>();
+};
+
+/// Region of interest of the image. See [`Camera::set_roi`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Roi {
+    /// Horizontal offset from the origin of the sensor.
+    pub x: i64,
+    /// Vertical offset from the origin of the sensor.
+    pub y: i64,
+    /// Width of the image.
+    pub width: i64,
+    /// Height of the image.
+    pub height: i64,
+}
+
+/// A timestamp of the device clock latched by [`Camera::latch_timestamp`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimestampLatch {
+    /// The latched value of the device clock. The value is in ns for `U3V` devices.
+    pub device_timestamp: u64,
+    /// Host time just before latching the clock.
+    pub host_before: HostTimestamp,
+    /// Host time just after reading the latched value.
+    pub host_after: HostTimestamp,
+}
+
+impl TimestampLatch {
+    /// Returns the duration of the operation, which bounds the error of the host time at which
+    /// the clock was latched.
+    pub fn round_trip(&self) -> Duration {
+        self.host_after
+            .monotonic
+            .saturating_duration_since(self.host_before.monotonic)
+    }
+
+    /// Returns the host time at the middle of the operation, which is the best estimate of the
+    /// host time at which the clock was latched.
+    pub fn host_midpoint(&self) -> HostTimestamp {
+        let half = self.round_trip() / 2;
+        HostTimestamp {
+            monotonic: self.host_before.monotonic + half,
+            wall_clock: self.host_before.wall_clock + half,
+        }
+    }
+}
+
+/// Sets offset and size of an axis of ROI, and returns the applied values.
+fn set_roi_axis<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    offset_node: Option<IntegerNode>,
+    size_node: IntegerNode,
+    offset: i64,
+    size: i64,
+) -> CameleonResult<(i64, i64)>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let current_size = size_node.value(ctxt)?;
+    let offset_node = match offset_node {
+        Some(node) if node.is_writable(ctxt)? => node,
+        Some(node) => {
+            let size = set_rounded(ctxt, size_node, size)?;
+            return Ok((node.value(ctxt)?, size));
+        }
+        None => return Ok((0, set_rounded(ctxt, size_node, size)?)),
+    };
+
+    // Shrink before move, move before grow.
+    let (offset, size) = if size <= current_size {
+        let size = set_rounded(ctxt, size_node, size)?;
+        (set_rounded(ctxt, offset_node, offset)?, size)
+    } else {
+        let offset = set_rounded(ctxt, offset_node, offset)?;
+        (offset, set_rounded(ctxt, size_node, size)?)
+    };
+    Ok((offset, size))
+}
+
+/// Sets the nearest valid value to the node, and returns the value read back from the node.
+fn set_rounded<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    node: IntegerNode,
+    value: i64,
+) -> CameleonResult<i64>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let min = node.min(ctxt)?;
+    let max = node.max(ctxt)?;
+    let inc = node.inc(ctxt)?.filter(|inc| *inc > 0).unwrap_or(1);
+
+    let value = round_to_inc(value, min, max, inc);
+    if node.value(ctxt)? != value {
+        node.set_value(ctxt, value)?;
+    }
+    Ok(node.value(ctxt)?)
+}
+
+/// Returns the nearest value to `value` that is in `min..=max` and satisfies `min + n * inc`.
+fn round_to_inc(value: i64, min: i64, max: i64, inc: i64) -> i64 {
+    let clamped = value.clamp(min, max.max(min));
+    let steps = (clamped - min + inc / 2) / inc;
+    let rounded = min + steps * inc;
+    if rounded > max {
+        rounded - inc
+    } else {
+        rounded
+    }
+}
+
+/// Sets `AcquisitionMode` and `AcquisitionFrameCount` to acquire `n` frames.
+///
+/// Falls back to `Continuous` if the camera doesn't support the suitable mode.
+fn set_acquisition_mode_for<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    mode_node: EnumerationNode,
+    n: usize,
+) -> CameleonResult<()>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    if n == 1 && has_available_entry(ctxt, mode_node, "SingleFrame")? {
+        return Ok(mode_node.set_entry_by_symbolic(ctxt, "SingleFrame")?);
+    }
+
+    if has_available_entry(ctxt, mode_node, "MultiFrame")? {
+        let count_node = ctxt
+            .node("AcquisitionFrameCount")
+            .and_then(|node| node.as_integer(ctxt));
+        if let (Some(count_node), Ok(count)) = (count_node, i64::try_from(n)) {
+            if count_node.min(ctxt)? <= count && count <= count_node.max(ctxt)? {
+                mode_node.set_entry_by_symbolic(ctxt, "MultiFrame")?;
+                count_node.set_value(ctxt, count)?;
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(mode_node.set_entry_by_symbolic(ctxt, "Continuous")?)
+}
+
+/// Returns `true` if `node` has an available entry named `symbolic`.
+fn has_available_entry<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    node: EnumerationNode,
+    symbolic: &str,
+) -> CameleonResult<bool>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    match node
+        .entries(ctxt)
+        .into_iter()
+        .find(|ent| ent.symbolic(ctxt) == symbolic)
+    {
+        Some(ent) => Ok(ent.is_available(ctxt)?),
+        None => Ok(false),
+    }
+}
+
+/// Locks or unlocks transport layer parameters through `TLParamsLocked` if the camera has it.
+fn set_tl_params_locked<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    locked: bool,
+) -> CameleonResult<()>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    if let Some(node) = ctxt.node("TLParamsLocked").and_then(|n| n.as_integer(ctxt)) {
+        node.set_value(ctxt, i64::from(locked))?;
+    }
+    Ok(())
+}
+
+/// Information of the camera.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CameraInfo {
+    /// Vendor name of the camera.
+    pub vendor_name: String,
+    /// Model name of the camera.
+    pub model_name: String,
+    ///Serial number of the camera.
+    pub serial_number: String,
+}
+
+/// This trait provides operations on the device's memory.
+#[auto_impl(&mut, Box)]
+pub trait DeviceControl {
+    /// Opens the handle.
+    fn open(&mut self) -> ControlResult<()>;
+
+    /// Closes the handle.
+    fn close(&mut self) -> ControlResult<()>;
+
+    /// Returns `true` if device is already opened.
+    fn is_opened(&self) -> bool;
+
+    /// Reads data from the device's memory.
+    ///
+    /// Reads length is same as `buf.len()`.
+    fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()>;
+
+    /// Writes data to the device's memory.
+    fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()>;
+
+    /// Sets a token to cancel subsequent operations. `None` clears the token.
+    ///
+    /// A handle that supports cancellation checks the token between transfers and returns
+    /// [`ControlError::Cancelled`](crate::ControlError::Cancelled) once it's cancelled. A transfer
+    /// in flight isn't interrupted. The default implementation ignores the token.
+    fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+        let _ = token;
+    }
+
+    /// Overrides the timeout of each transaction with the device. `None` clears the override.
+    ///
+    /// This is used for operations which take much longer than usual, e.g. executing
+    /// `BalanceWhiteAuto` once or accessing files. The default implementation ignores the
+    /// timeout.
+    fn set_timeout_override(&mut self, timeout: Option<Duration>) {
+        let _ = timeout;
+    }
+
+    /// Returns the timeout set by [`Self::set_timeout_override`].
+    fn timeout_override(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Sets the timeout of each transaction while operating on the node named `name`. `None`
+    /// clears the timeout of the node.
+    ///
+    /// The timeout set by [`Self::set_timeout_override`] takes precedence over this. The default
+    /// implementation ignores the timeout.
+    fn set_node_timeout(&mut self, name: &str, timeout: Option<Duration>) {
+        let _ = (name, timeout);
+    }
+
+    /// Returns the timeout set by [`Self::set_node_timeout`] for the node named `name`.
+    fn node_timeout(&self, name: &str) -> Option<Duration> {
+        let _ = name;
+        None
+    }
+
+    /// Latches the clock of the device without `GenApi`, and returns the latched timestamp.
+    ///
+    /// Returns `None` if the handle can't access the clock directly, in which case
+    /// [`Camera::latch_timestamp`] falls back to `GenApi` nodes. The default implementation
+    /// returns `None`.
+    fn latch_timestamp(&mut self) -> ControlResult<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Reads multiple regions of the device's memory.
+    ///
+    /// Each buffer is filled with the data starting at the paired address. The default
+    /// implementation reads the regions one by one, a handle may override it to issue the
+    /// requests more efficiently.
+    fn read_batch(&mut self, requests: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        for (address, buf) in requests.iter_mut() {
+            self.read(*address, buf)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `GenICam` xml string.
+    fn genapi(&mut self) -> ControlResult<String>;
+
+    /// Enables streaming.
+    fn enable_streaming(&mut self) -> ControlResult<()>;
+
+    /// Disables streaming.
+    fn disable_streaming(&mut self) -> ControlResult<()>;
+}
+
+/// This trait provides streaming capability.
+#[auto_impl(&mut, Box)]
+pub trait PayloadStream {
+    /// Opens the handle.
+    fn open(&mut self) -> StreamResult<()>;
+
+    /// Closes the handle.
+    fn close(&mut self) -> StreamResult<()>;
+
+    /// Starts streaming.
+    fn start_streaming_loop(
+        &mut self,
+        sender: PayloadSender,
+        ctrl: &mut dyn DeviceControl,
+    ) -> StreamResult<()>;
 
     /// Stops streaming.
     fn stop_streaming_loop(&mut self) -> StreamResult<()>;
 
     /// Returns `true` if streaming loop is running.
     fn is_loop_running(&self) -> bool;
+
+    /// Pauses the running streaming loop without releasing its resources.
+    ///
+    /// The default implementation returns an error since the stream doesn't support pausing.
+    fn pause_streaming_loop(&mut self) -> StreamResult<()> {
+        Err(StreamError::Io(anyhow::Error::msg(
+            "the stream doesn't support pausing",
+        )))
+    }
+
+    /// Resumes the streaming loop paused by [`Self::pause_streaming_loop`].
+    fn resume_streaming_loop(&mut self) -> StreamResult<()> {
+        Err(StreamError::Io(anyhow::Error::msg(
+            "the stream doesn't support pausing",
+        )))
+    }
+
+    /// Returns `true` if streaming loop is paused.
+    fn is_loop_paused(&self) -> bool {
+        false
+    }
+
+    /// Returns statistics of the payloads sent from the streaming loop.
+    ///
+    /// Returns `None` if the stream doesn't collect statistics.
+    fn statistics(&self) -> Option<StreamStatistics> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A control handle which only keeps the cancellation token.
+    #[derive(Default)]
+    struct TokenHolder(Option<CancellationToken>);
+
+    impl DeviceControl for TokenHolder {
+        fn open(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn close(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn is_opened(&self) -> bool {
+            true
+        }
+
+        fn read(&mut self, _: u64, _: &mut [u8]) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn write(&mut self, _: u64, _: &[u8]) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+            self.0 = token;
+        }
+
+        fn genapi(&mut self) -> ControlResult<String> {
+            Ok(String::new())
+        }
+
+        fn enable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn disable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cancellation_token_is_cleared_on_panic() {
+        let info = CameraInfo {
+            vendor_name: String::new(),
+            model_name: String::new(),
+            serial_number: String::new(),
+        };
+        let mut camera: Camera<TokenHolder, (), DefaultGenApiCtxt> =
+            Camera::new(TokenHolder::default(), (), None, info);
+        let token = CancellationToken::new();
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            camera.run_cancellable(&token, |camera| -> CameleonResult<()> {
+                assert!(camera.ctrl.0.is_some());
+                panic!("operation failed")
+            })
+        }));
+        assert!(result.is_err());
+        assert!(camera.ctrl.0.is_none());
+    }
+
+    #[test]
+    fn test_round_to_inc() {
+        assert_eq!(round_to_inc(643, 16, 1920, 8), 640);
+        assert_eq!(round_to_inc(645, 16, 1920, 8), 648);
+        assert_eq!(round_to_inc(0, 16, 1920, 8), 16);
+        assert_eq!(round_to_inc(5000, 16, 1920, 8), 1920);
+        // `max` isn't aligned to the increment.
+        assert_eq!(round_to_inc(1919, 0, 1919, 4), 1916);
+    }
 }
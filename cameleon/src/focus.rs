@@ -0,0 +1,262 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a contrast based focus metric and an autofocus sweep.
+//!
+//! [`Payload::focus_metric`] evaluates sharpness of an image, and
+//! [`Camera::focus_sweep`](crate::Camera::focus_sweep) steps a focus node through a range and
+//! returns the position where the image is the sharpest.
+
+use std::time::Duration;
+
+use super::{
+    genapi::{FloatNode, GenApiCtxt, IntegerNode, ParamsCtxt},
+    payload::{Payload, PixelFormat},
+    CameleonError, CameleonResult, Camera, DeviceControl, PayloadStream, StreamError,
+};
+
+/// Result of [`Camera::focus_sweep`](crate::Camera::focus_sweep).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocusSweep {
+    /// Position where the focus metric is the highest. The focus node is left at this position.
+    pub best_position: f64,
+    /// Focus metric at `best_position`.
+    pub best_metric: f64,
+    /// Pairs of a position and the focus metric of the frame at the position, in the order of
+    /// the sweep.
+    pub samples: Vec<(f64, f64)>,
+}
+
+impl Payload {
+    /// Returns a contrast based focus metric of the image in the payload.
+    ///
+    /// The metric is the mean squared difference between neighboring pixels, with pixel values
+    /// normalized to `0.0..=1.0`. A higher value means a sharper image. The metric is only
+    /// comparable between images of the same scene and the same settings.
+    ///
+    /// `Bayer` images are evaluated between pixels of the same color, and color images are
+    /// evaluated on the mean of `R`, `G` and `B`.
+    ///
+    /// Supported pixel formats are `Mono8`, 8 bit `Bayer` formats, `Mono10`, `Mono12`, `Mono14`,
+    /// `Mono16`, `RGB8`, `BGR8`, `RGBa8`, `BGRa8` and `RGB16`.
+    ///
+    /// Returns `None` if the payload doesn't contain an image, the image is too small, or the
+    /// pixel format is not supported.
+    #[must_use]
+    pub fn focus_metric(&self) -> Option<f64> {
+        use PixelFormat::{
+            BGRa8, BayerBG8, BayerGB8, BayerGR8, BayerRG8, Mono10, Mono12, Mono14, Mono16, Mono8,
+            RGBa8, BGR8, RGB16, RGB8,
+        };
+
+        let info = self.image_info()?;
+        let image = self.image()?;
+        // Components per pixel, bytes per component, bit depth and distance between pixels to
+        // compare.
+        let (components, elem_size, bits, distance) = match info.pixel_format {
+            Mono8 => (1, 1, 8, 1),
+            BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => (1, 1, 8, 2),
+            Mono10 => (1, 2, 10, 1),
+            Mono12 => (1, 2, 12, 1),
+            Mono14 => (1, 2, 14, 1),
+            Mono16 => (1, 2, 16, 1),
+            RGB8 | BGR8 => (3, 1, 8, 1),
+            RGBa8 | BGRa8 => (4, 1, 8, 1),
+            RGB16 => (3, 2, 16, 1),
+            _ => return None,
+        };
+
+        let (width, height) = (info.width, info.height);
+        if width <= distance * 2 || height <= distance * 2 {
+            return None;
+        }
+        let row_bytes = width * components * elem_size;
+        let stride = image.len() / height;
+        if stride < row_bytes {
+            return None;
+        }
+
+        let color_components = components.min(3);
+        let scale = 1.0 / (((1_u32 << bits) - 1) as f64 * color_components as f64);
+        let luma = |x: usize, y: usize| -> f64 {
+            let offset = y * stride + x * components * elem_size;
+            let sum: u32 = (0..color_components)
+                .map(|c| {
+                    let i = offset + c * elem_size;
+                    if elem_size == 1 {
+                        u32::from(image[i])
+                    } else {
+                        u32::from(u16::from_le_bytes([image[i], image[i + 1]]))
+                    }
+                })
+                .sum();
+            f64::from(sum) * scale
+        };
+
+        let mut energy = 0.0;
+        let mut count = 0_u64;
+        for y in 0..height - distance {
+            for x in 0..width - distance {
+                let center = luma(x, y);
+                let dx = luma(x + distance, y) - center;
+                let dy = luma(x, y + distance) - center;
+                energy += dx * dx + dy * dy;
+                count += 1;
+            }
+        }
+
+        Some(energy / count as f64)
+    }
+}
+
+/// A focus node which has either `IFloat` or `IInteger` interface.
+#[derive(Debug, Clone, Copy)]
+enum FocusNode {
+    Float(FloatNode),
+    Integer(IntegerNode),
+}
+
+impl FocusNode {
+    fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>, name: &str) -> CameleonResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let node = ctxt
+            .node(name)
+            .ok_or_else(|| CameleonError::InvalidGenApiXml(format!("missing {}", name).into()))?;
+        if let Some(node) = node.as_float(ctxt) {
+            Ok(Self::Float(node))
+        } else if let Some(node) = node.as_integer(ctxt) {
+            Ok(Self::Integer(node))
+        } else {
+            Err(CameleonError::InvalidGenApiXml(
+                format!("{} has neither IFloat nor IInteger interface", name).into(),
+            ))
+        }
+    }
+
+    /// Sets the position and returns the actually written value.
+    fn set_position<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        position: f64,
+    ) -> CameleonResult<f64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match self {
+            Self::Float(node) => {
+                node.set_value(ctxt, position)?;
+                Ok(position)
+            }
+            Self::Integer(node) => {
+                let position = position.round() as i64;
+                node.set_value(ctxt, position)?;
+                Ok(position as f64)
+            }
+        }
+    }
+}
+
+/// Steps the focus node `node` through `steps` positions evenly spaced from `start` to `end`.
+///
+/// At least one position is evaluated even if `steps` is zero.
+pub(crate) fn sweep<Ctrl, Strm, Ctxt>(
+    camera: &mut Camera<Ctrl, Strm, Ctxt>,
+    node: &str,
+    start: f64,
+    end: f64,
+    steps: usize,
+    timeout: Duration,
+) -> CameleonResult<FocusSweep>
+where
+    Ctrl: DeviceControl,
+    Strm: PayloadStream,
+    Ctxt: GenApiCtxt,
+{
+    let focus_node = {
+        let ctxt = camera.params_ctxt()?;
+        FocusNode::new(&ctxt, node)?
+    };
+
+    let mut samples: Vec<(f64, f64)> = Vec::with_capacity(steps);
+    for position in positions(start, end, steps.max(1)) {
+        let position = focus_node.set_position(&mut camera.params_ctxt()?, position)?;
+        // Integer nodes may round different positions to the same value.
+        if samples.iter().any(|(p, _)| *p == position) {
+            continue;
+        }
+
+        let payload = camera.acquire_one(timeout)?;
+        let metric = payload.focus_metric().ok_or_else(|| {
+            StreamError::InvalidPayload("focus metric isn't available for the payload".into())
+        })?;
+        samples.push((position, metric));
+    }
+
+    // `samples` isn't empty since the first position is never skipped.
+    let mut best = samples[0];
+    for sample in &samples[1..] {
+        if sample.1 > best.1 {
+            best = *sample;
+        }
+    }
+    let (best_position, best_metric) = best;
+    focus_node.set_position(&mut camera.params_ctxt()?, best_position)?;
+
+    Ok(FocusSweep {
+        best_position,
+        best_metric,
+        samples,
+    })
+}
+
+/// Returns `steps` positions evenly spaced from `start` to `end`, both inclusive.
+fn positions(start: f64, end: f64, steps: usize) -> Vec<f64> {
+    match steps {
+        0 | 1 => vec![start],
+        _ => (0..steps)
+            .map(|i| start + (end - start) * i as f64 / (steps - 1) as f64)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::image_payload;
+
+    fn mono8_payload(width: usize, height: usize, payload: Vec<u8>) -> Payload {
+        image_payload(width, height, PixelFormat::Mono8, payload)
+    }
+
+    #[test]
+    fn test_focus_metric() {
+        // A sharp checkerboard and a blurred one.
+        let sharp: Vec<u8> = (0..64)
+            .map(|i| if (i % 8 + i / 8) % 2 == 0 { 0 } else { 255 })
+            .collect();
+        let blurred: Vec<u8> = (0..64)
+            .map(|i| if (i % 8 + i / 8) % 2 == 0 { 96 } else { 160 })
+            .collect();
+        let flat = vec![128; 64];
+
+        let sharp = mono8_payload(8, 8, sharp).focus_metric().unwrap();
+        let blurred = mono8_payload(8, 8, blurred).focus_metric().unwrap();
+        let flat = mono8_payload(8, 8, flat).focus_metric().unwrap();
+        assert!(sharp > blurred);
+        assert!(blurred > flat);
+        assert!((sharp - 2.0).abs() < 1e-9);
+        assert_eq!(flat, 0.0);
+
+        assert!(mono8_payload(2, 2, vec![0; 4]).focus_metric().is_none());
+    }
+
+    #[test]
+    fn test_positions() {
+        assert_eq!(positions(0.0, 10.0, 3), vec![0.0, 5.0, 10.0]);
+        assert_eq!(positions(5.0, 10.0, 1), vec![5.0]);
+    }
+}
@@ -0,0 +1,296 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains the category/feature hierarchy of `GenApi` used to build GUI.
+
+use std::collections::HashSet;
+
+use cameleon_genapi::prelude::*;
+
+use super::{GenApiCtxt, Node, NodeId, NodeStore, ParamsCtxt, Visibility};
+
+/// Category/feature hierarchy rooted at `Root` category.
+///
+/// Children are ordered as `pFeature` elements of their parent category.
+///
+/// # Examples
+/// ```rust
+/// # use cameleon::u3v;
+/// # let mut cameras = u3v::enumerate_cameras().unwrap();
+/// # if cameras.is_empty() {
+/// #     return;
+/// # }
+/// # let mut camera = cameras.pop().unwrap();
+/// # camera.open().unwrap();
+/// use cameleon::genapi::{CategoryTree, Visibility};
+///
+/// camera.load_context().unwrap();
+/// let params_ctxt = camera.params_ctxt().unwrap();
+///
+/// let tree = CategoryTree::new(&params_ctxt).unwrap();
+/// // Shows only features for beginners.
+/// for child in tree.view(Visibility::Beginner).root().children() {
+///     println!("{}", child.node().name(&params_ctxt));
+/// }
+///
+/// # camera.close().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryTree {
+    root: CategoryTreeNode,
+}
+
+/// A category or a feature in [`CategoryTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryTreeNode {
+    node: Node,
+    visibility: Visibility,
+    is_category: bool,
+    children: Vec<CategoryTreeNode>,
+}
+
+impl CategoryTree {
+    /// Builds the tree from `Root` category of the context.
+    ///
+    /// Returns `None` if the context doesn't have `Root` category.
+    pub fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        let root = ns.id_by_name("Root")?;
+        root.as_icategory_kind(ns)?;
+
+        let mut visited = HashSet::new();
+        Some(Self {
+            root: CategoryTreeNode::build(root, ns, &mut visited),
+        })
+    }
+
+    /// Returns `Root` category.
+    pub fn root(&self) -> &CategoryTreeNode {
+        &self.root
+    }
+
+    /// Returns the tree which only contains nodes visible at the given visibility level.
+    ///
+    /// e.g. `Visibility::Expert` keeps `Beginner` and `Expert` nodes. Categories that have no
+    /// visible feature are removed, though `Root` is always kept.
+    #[must_use]
+    pub fn view(&self, visibility: Visibility) -> Self {
+        let children = self.root.filtered_children(visibility);
+        Self {
+            root: CategoryTreeNode {
+                children,
+                ..self.root.shallow_clone()
+            },
+        }
+    }
+}
+
+impl CategoryTreeNode {
+    /// Returns the node.
+    pub fn node(&self) -> Node {
+        self.node
+    }
+
+    /// Returns visibility of the node.
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    /// Returns `true` if the node is a category.
+    pub fn is_category(&self) -> bool {
+        self.is_category
+    }
+
+    /// Returns children of the category. Returns an empty slice if the node is a feature.
+    pub fn children(&self) -> &[CategoryTreeNode] {
+        &self.children
+    }
+
+    fn build(nid: NodeId, ns: &impl NodeStore, visited: &mut HashSet<NodeId>) -> Self {
+        let visibility = nid.as_inode_kind(ns).map_or(Visibility::Invisible, |n| {
+            n.node_base_precise().visibility()
+        });
+        let category = nid.as_icategory_kind(ns);
+
+        // Guard against cyclic categories in malformed xml.
+        let children = match category {
+            Some(category) if visited.insert(nid) => category
+                .nodes(ns)
+                .iter()
+                .map(|child| Self::build(*child, ns, visited))
+                .collect(),
+            _ => vec![],
+        };
+
+        Self {
+            node: Node(nid),
+            visibility,
+            is_category: category.is_some(),
+            children,
+        }
+    }
+
+    fn filtered_children(&self, visibility: Visibility) -> Vec<Self> {
+        self.children
+            .iter()
+            .filter(|child| child.visibility <= visibility)
+            .filter_map(|child| {
+                if child.is_category {
+                    let children = child.filtered_children(visibility);
+                    (!children.is_empty()).then(|| Self {
+                        children,
+                        ..child.shallow_clone()
+                    })
+                } else {
+                    Some(child.clone())
+                }
+            })
+            .collect()
+    }
+
+    fn shallow_clone(&self) -> Self {
+        Self {
+            node: self.node,
+            visibility: self.visibility,
+            is_category: self.is_category,
+            children: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genapi::{DefaultGenApiCtxt, FromXml};
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Category Name="Root" NameSpace="Standard">
+                <pFeature>ImageFormat</pFeature>
+                <pFeature>Debug</pFeature>
+            </Category>
+
+            <Category Name="ImageFormat" NameSpace="Standard">
+                <pFeature>Width</pFeature>
+                <pFeature>Binning</pFeature>
+                <pFeature>TestPattern</pFeature>
+            </Category>
+
+            <Category Name="Debug" NameSpace="Standard">
+                <Visibility>Guru</Visibility>
+                <pFeature>Root</pFeature>
+                <pFeature>DebugCounter</pFeature>
+                <pFeature>DebugValue</pFeature>
+            </Category>
+
+            <Integer Name="Width" NameSpace="Standard">
+                <Visibility>Beginner</Visibility>
+                <Value>100</Value>
+            </Integer>
+
+            <Integer Name="Binning" NameSpace="Standard">
+                <Visibility>Expert</Visibility>
+                <Value>1</Value>
+            </Integer>
+
+            <Integer Name="TestPattern" NameSpace="Standard">
+                <Visibility>Guru</Visibility>
+                <Value>0</Value>
+            </Integer>
+
+            <Integer Name="DebugCounter" NameSpace="Standard">
+                <Visibility>Guru</Visibility>
+                <Value>0</Value>
+            </Integer>
+
+            <Integer Name="DebugValue" NameSpace="Standard">
+                <Visibility>Invisible</Visibility>
+                <Value>0</Value>
+            </Integer>
+        </RegisterDescription>
+        "#;
+
+    fn names(node: &CategoryTreeNode, ctxt: &ParamsCtxt<(), DefaultGenApiCtxt>) -> Vec<String> {
+        node.children()
+            .iter()
+            .map(|child| child.node().name(ctxt).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_category_tree_view() {
+        let ctxt = ParamsCtxt {
+            ctrl: (),
+            ctxt: DefaultGenApiCtxt::from_xml(&XML).unwrap(),
+            journal: None,
+        };
+        let tree = CategoryTree::new(&ctxt).unwrap();
+        assert_eq!(names(tree.root(), &ctxt), ["ImageFormat", "Debug"]);
+
+        let beginner = tree.view(Visibility::Beginner);
+        assert_eq!(names(beginner.root(), &ctxt), ["ImageFormat"]);
+        assert_eq!(names(&beginner.root().children()[0], &ctxt), ["Width"]);
+
+        let expert = tree.view(Visibility::Expert);
+        assert_eq!(
+            names(&expert.root().children()[0], &ctxt),
+            ["Width", "Binning"]
+        );
+
+        let guru = tree.view(Visibility::Guru);
+        assert_eq!(names(guru.root(), &ctxt), ["ImageFormat", "Debug"]);
+        assert_eq!(
+            names(&guru.root().children()[0], &ctxt),
+            ["Width", "Binning", "TestPattern"]
+        );
+        // The cyclic reference to `Root` has no children, so it's removed.
+        assert_eq!(names(&guru.root().children()[1], &ctxt), ["DebugCounter"]);
+    }
+
+    #[test]
+    fn test_features_recursive() {
+        let ctxt = ParamsCtxt {
+            ctrl: (),
+            ctxt: DefaultGenApiCtxt::from_xml(&XML).unwrap(),
+            journal: None,
+        };
+        let root = ctxt.node("Root").unwrap().as_category(&ctxt).unwrap();
+        let features: Vec<_> = root
+            .features_recursive(&ctxt)
+            .into_iter()
+            .map(|node| node.name(&ctxt).to_string())
+            .collect();
+        // The cyclic reference to `Root` is skipped.
+        assert_eq!(
+            features,
+            [
+                "ImageFormat",
+                "Width",
+                "Binning",
+                "TestPattern",
+                "Debug",
+                "DebugCounter",
+                "DebugValue"
+            ]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains cache invalidation driven by device events.
+
+use std::collections::HashSet;
+
+use cameleon_genapi::store::NodeData;
+
+use super::{GenApiCtxt, NodeId, NodeStore, ParamsCtxt};
+
+impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
+where
+    Ctxt: GenApiCtxt,
+{
+    /// Invalidates cache of nodes linked to the event whose id is `event_id`.
+    ///
+    /// A node is linked to the event if its `EventID` element equals to `event_id`. Registers
+    /// which read from a linked `Port` are also linked. Cache of the linked nodes and the nodes
+    /// they invalidate via `pInvalidator` is discarded, so subsequent reads reflect the event
+    /// without relying on `PollingTime`.
+    ///
+    /// Returns the number of linked nodes.
+    pub fn invalidate_by_event(&mut self, event_id: u64) -> usize {
+        let linked = event_linked_nodes(self.node_store(), event_id);
+        self.ctxt.enter(|_, vc| {
+            for nid in &linked {
+                vc.invalidate_cache_of(*nid);
+                vc.invalidate_cache_by(*nid);
+            }
+        });
+        linked.len()
+    }
+}
+
+fn event_linked_nodes(ns: &impl NodeStore, event_id: u64) -> Vec<NodeId> {
+    let mut linked = vec![];
+    let mut ports = HashSet::new();
+    ns.visit_nodes(|data| {
+        let node_base = data.node_base();
+        if node_base.event_id() == Some(event_id) {
+            if let NodeData::Port(_) = data {
+                ports.insert(node_base.id());
+            }
+            linked.push(node_base.id());
+        }
+    });
+
+    if !ports.is_empty() {
+        ns.visit_nodes(|data| {
+            let reg_base = match data {
+                NodeData::IntReg(n) => n.register_base(),
+                NodeData::MaskedIntReg(n) => n.register_base(),
+                NodeData::FloatReg(n) => n.register_base(),
+                NodeData::StringReg(n) => n.register_base(),
+                NodeData::Register(n) => n.register_base(),
+                _ => return,
+            };
+            let nid = data.node_base().id();
+            if ports.contains(&reg_base.p_port()) && !linked.contains(&nid) {
+                linked.push(nid);
+            }
+        });
+    }
+
+    linked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genapi::{DefaultGenApiCtxt, FromXml};
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Category Name="Root" NameSpace="Standard">
+                <pFeature>Width</pFeature>
+                <pFeature>EventExposureEndTimestamp</pFeature>
+            </Category>
+
+            <Integer Name="Width">
+                <pValue>WidthReg</pValue>
+            </Integer>
+
+            <IntReg Name="WidthReg">
+                <Address>0x0</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <pPort>Device</pPort>
+                <Cachable>WriteThrough</Cachable>
+                <Sign>Unsigned</Sign>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+
+            <Integer Name="EventExposureEndTimestamp">
+                <pValue>EventExposureEndTimestampReg</pValue>
+            </Integer>
+
+            <IntReg Name="EventExposureEndTimestampReg">
+                <Address>0x0</Address>
+                <Length>8</Length>
+                <AccessMode>RO</AccessMode>
+                <pPort>EventExposureEndPort</pPort>
+                <Cachable>WriteThrough</Cachable>
+                <Sign>Unsigned</Sign>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+
+            <Port Name="EventExposureEndPort">
+                <EventID>9001</EventID>
+            </Port>
+
+            <Port Name="Device"></Port>
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_invalidate_by_event() {
+        let mut ctxt = ParamsCtxt {
+            ctrl: (),
+            ctxt: DefaultGenApiCtxt::from_xml(&XML).unwrap(),
+            journal: None,
+        };
+        let ns = ctxt.node_store();
+        let width_reg = ns.id_by_name("WidthReg").unwrap();
+        let event_reg = ns.id_by_name("EventExposureEndTimestampReg").unwrap();
+        ctxt.ctxt.enter(|_, vc| {
+            vc.cache_data(width_reg, 0, 4, &[0; 4]);
+            vc.cache_data(event_reg, 0, 8, &[0; 8]);
+        });
+
+        assert_eq!(ctxt.invalidate_by_event(0x1234), 0);
+        // The port and the register reading from the port are linked.
+        assert_eq!(ctxt.invalidate_by_event(0x9001), 2);
+
+        ctxt.ctxt.enter(|_, vc| {
+            assert!(vc.get_cache(width_reg, 0, 4).is_some());
+            assert!(vc.get_cache(event_reg, 0, 8).is_none());
+        });
+    }
+}
@@ -0,0 +1,287 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains an implementation of `File Access` defined in `GenICam SFNC`.
+//!
+//! Files on the device are accessed through `FileSelector`, `FileOperationSelector`,
+//! `FileOperationExecute` and `FileAccessBuffer` nodes. [`FileReader`] and [`FileWriter`] wrap
+//! the protocol in [`std::io::Read`] and [`std::io::Write`].
+
+use std::{io, time::Duration};
+
+use tracing::error;
+
+use super::{CommandNode, EnumerationNode, GenApiCtxt, IntegerNode, ParamsCtxt, RegisterNode};
+use crate::{CameleonError, CameleonResult, ControlError, DeviceControl};
+
+/// Maximum duration to wait for a file operation to be done.
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reads a file on the device. The file is closed when the reader is dropped.
+///
+/// # Examples
+/// ```no_run
+/// # use cameleon::u3v;
+/// # let mut cameras = u3v::enumerate_cameras().unwrap();
+/// # let mut camera = cameras.pop().unwrap();
+/// use std::io::Read;
+///
+/// camera.open().unwrap();
+/// camera.load_context().unwrap();
+///
+/// let mut reader = camera.file_read("UserSet1").unwrap();
+/// let mut data = vec![];
+/// reader.read_to_end(&mut data).unwrap();
+/// reader.close().unwrap();
+/// ```
+pub struct FileReader<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    file: FileAccess<Ctrl, Ctxt>,
+}
+
+/// Writes a file on the device. The file is closed when the writer is dropped.
+///
+/// # Examples
+/// ```no_run
+/// # use cameleon::u3v;
+/// # let mut cameras = u3v::enumerate_cameras().unwrap();
+/// # let mut camera = cameras.pop().unwrap();
+/// use std::io::Write;
+///
+/// camera.open().unwrap();
+/// camera.load_context().unwrap();
+///
+/// let firmware = std::fs::read("firmware.bin").unwrap();
+/// let mut writer = camera.file_write("DeviceFirmware").unwrap();
+/// writer.write_all(&firmware).unwrap();
+/// writer.close().unwrap();
+/// ```
+pub struct FileWriter<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    file: FileAccess<Ctrl, Ctxt>,
+}
+
+impl<Ctrl, Ctxt> FileReader<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    /// Opens the file specified by the `FileSelector` entry name in read mode.
+    pub fn open(ctxt: ParamsCtxt<Ctrl, Ctxt>, file: &str) -> CameleonResult<Self> {
+        Ok(Self {
+            file: FileAccess::open(ctxt, file, "Read")?,
+        })
+    }
+
+    /// Returns the size of the file if the device has `FileSize` node.
+    pub fn size(&mut self) -> CameleonResult<Option<u64>> {
+        self.file.size()
+    }
+
+    /// Closes the file.
+    pub fn close(mut self) -> CameleonResult<()> {
+        self.file.close()
+    }
+}
+
+impl<Ctrl, Ctxt> FileWriter<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    /// Opens the file specified by the `FileSelector` entry name in write mode.
+    pub fn open(ctxt: ParamsCtxt<Ctrl, Ctxt>, file: &str) -> CameleonResult<Self> {
+        Ok(Self {
+            file: FileAccess::open(ctxt, file, "Write")?,
+        })
+    }
+
+    /// Closes the file. Some devices validate or apply the written file, e.g. firmware, on close.
+    pub fn close(mut self) -> CameleonResult<()> {
+        self.file.close()
+    }
+}
+
+impl<Ctrl, Ctxt> io::Read for FileReader<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf).map_err(into_io_error)
+    }
+}
+
+impl<Ctrl, Ctxt> io::Write for FileWriter<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf).map_err(into_io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct FileAccess<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    ctxt: ParamsCtxt<Ctrl, Ctxt>,
+    nodes: FileAccessNodes,
+    offset: i64,
+    is_opened: bool,
+}
+
+#[derive(Clone, Copy)]
+struct FileAccessNodes {
+    operation: EnumerationNode,
+    execute: CommandNode,
+    status: EnumerationNode,
+    result: IntegerNode,
+    buffer: RegisterNode,
+    offset: IntegerNode,
+    length: IntegerNode,
+    size: Option<IntegerNode>,
+}
+
+impl<Ctrl, Ctxt> FileAccess<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    fn open(mut ctxt: ParamsCtxt<Ctrl, Ctxt>, file: &str, mode: &str) -> CameleonResult<Self> {
+        let selector = expect_node!(&ctxt, "FileSelector", as_enumeration);
+        let open_mode = expect_node!(&ctxt, "FileOpenMode", as_enumeration);
+        let nodes = FileAccessNodes {
+            operation: expect_node!(&ctxt, "FileOperationSelector", as_enumeration),
+            execute: expect_node!(&ctxt, "FileOperationExecute", as_command),
+            status: expect_node!(&ctxt, "FileOperationStatus", as_enumeration),
+            result: expect_node!(&ctxt, "FileOperationResult", as_integer),
+            buffer: expect_node!(&ctxt, "FileAccessBuffer", as_register),
+            offset: expect_node!(&ctxt, "FileAccessOffset", as_integer),
+            length: expect_node!(&ctxt, "FileAccessLength", as_integer),
+            size: ctxt.node("FileSize").and_then(|n| n.as_integer(&ctxt)),
+        };
+
+        selector.set_entry_by_symbolic(&mut ctxt, file)?;
+        nodes.operation.set_entry_by_symbolic(&mut ctxt, "Open")?;
+        open_mode.set_entry_by_symbolic(&mut ctxt, mode)?;
+
+        let mut access = Self {
+            ctxt,
+            nodes,
+            offset: 0,
+            is_opened: false,
+        };
+        access.execute("Open")?;
+        access.is_opened = true;
+        Ok(access)
+    }
+
+    fn size(&mut self) -> CameleonResult<Option<u64>> {
+        match self.nodes.size {
+            Some(node) => Ok(Some(node.value(&mut self.ctxt)? as u64)),
+            None => Ok(None),
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> CameleonResult<usize> {
+        let ctxt = &mut self.ctxt;
+        let buffer_len = self.nodes.buffer.length(ctxt)? as usize;
+        let len = buf.len().min(buffer_len);
+        if len == 0 {
+            return Ok(0);
+        }
+
+        self.nodes.operation.set_entry_by_symbolic(ctxt, "Read")?;
+        self.nodes.offset.set_value(ctxt, self.offset)?;
+        self.nodes.length.set_value(ctxt, len as i64)?;
+        let read_len = (self.execute("Read")? as usize).min(len);
+
+        let mut buffer = vec![0; buffer_len];
+        self.nodes.buffer.read(&mut self.ctxt, &mut buffer)?;
+        buf[..read_len].copy_from_slice(&buffer[..read_len]);
+        self.offset += read_len as i64;
+        Ok(read_len)
+    }
+
+    fn write(&mut self, data: &[u8]) -> CameleonResult<usize> {
+        let ctxt = &mut self.ctxt;
+        let buffer_len = self.nodes.buffer.length(ctxt)? as usize;
+        let len = data.len().min(buffer_len);
+        if len == 0 {
+            return Ok(0);
+        }
+
+        // `FileAccessBuffer` must be written as a whole.
+        let mut buffer = vec![0; buffer_len];
+        buffer[..len].copy_from_slice(&data[..len]);
+        self.nodes.operation.set_entry_by_symbolic(ctxt, "Write")?;
+        self.nodes.offset.set_value(ctxt, self.offset)?;
+        self.nodes.length.set_value(ctxt, len as i64)?;
+        self.nodes.buffer.write(ctxt, &buffer)?;
+        let written_len = (self.execute("Write")? as usize).min(len);
+
+        self.offset += written_len as i64;
+        Ok(written_len)
+    }
+
+    fn close(&mut self) -> CameleonResult<()> {
+        if !self.is_opened {
+            return Ok(());
+        }
+        self.is_opened = false;
+        self.nodes
+            .operation
+            .set_entry_by_symbolic(&mut self.ctxt, "Close")?;
+        self.execute("Close")?;
+        Ok(())
+    }
+
+    /// Executes the selected operation and returns `FileOperationResult`.
+    fn execute(&mut self, operation: &str) -> CameleonResult<i64> {
+        let ctxt = &mut self.ctxt;
+        self.nodes
+            .execute
+            .execute_and_wait(ctxt, OPERATION_TIMEOUT)?;
+
+        let status = self.nodes.status.current_entry(ctxt)?;
+        if status.symbolic(ctxt) != "Success" {
+            return Err(ControlError::Io(anyhow::Error::msg(format!(
+                "file operation `{}` failed",
+                operation
+            )))
+            .into());
+        }
+
+        Ok(self.nodes.result.value(ctxt)?)
+    }
+}
+
+impl<Ctrl, Ctxt> Drop for FileAccess<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            error!(?e)
+        }
+    }
+}
+
+fn into_io_error(err: CameleonError) -> io::Error {
+    io::Error::other(err)
+}
@@ -0,0 +1,203 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a bounded journal of writes to `GenApi` nodes.
+//!
+//! The journal is attached to [`ParamsCtxt`]s created by a camera with
+//! [`Camera::enable_write_journal`], or set to [`ParamsCtxt::journal`] directly. Then every
+//! successful write through [`IntegerNode`](super::IntegerNode), [`FloatNode`](super::FloatNode),
+//! [`StringNode`](super::StringNode), [`EnumerationNode`](super::EnumerationNode) and
+//! [`BooleanNode`](super::BooleanNode) is recorded.
+//!
+//! [`Camera::enable_write_journal`]: crate::Camera::enable_write_journal
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use cameleon_genapi::{prelude::*, Device, GenApiResult};
+
+use super::{GenApiCtxt, Node, NodeValue, ParamsCtxt};
+use crate::DeviceControl;
+
+/// A write to a node recorded in [`WriteJournal`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// Name of the written node.
+    pub node: String,
+    /// Value of the node before the write. `None` if the value wasn't cached, i.e. it couldn't be
+    /// known without reading the device.
+    pub old_value: Option<NodeValue>,
+    /// Value of the node after the write.
+    pub new_value: NodeValue,
+    /// Time when the write was completed.
+    pub timestamp: SystemTime,
+}
+
+/// A journal which keeps the latest writes to nodes.
+///
+/// The journal is a cheap handle, clones of the journal share the same entries.
+#[derive(Debug, Clone)]
+pub struct WriteJournal {
+    inner: Arc<Mutex<JournalInner>>,
+}
+
+#[derive(Debug)]
+struct JournalInner {
+    capacity: usize,
+    entries: VecDeque<JournalEntry>,
+}
+
+impl WriteJournal {
+    /// Constructs a journal which keeps the latest `capacity` entries.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            inner: Arc::new(Mutex::new(JournalInner {
+                capacity,
+                entries: VecDeque::with_capacity(capacity),
+            })),
+        }
+    }
+
+    /// Returns the maximum number of entries kept in the journal.
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().unwrap().capacity
+    }
+
+    /// Returns the recorded entries from the oldest to the latest.
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.inner.lock().unwrap().entries.iter().cloned().collect()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+
+    /// Appends `entry`, and drops the oldest entry if the journal is full.
+    fn push(&self, entry: JournalEntry) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.entries.len() == inner.capacity {
+            inner.entries.pop_front();
+        }
+        inner.entries.push_back(entry);
+    }
+}
+
+/// Runs `write` on `node`, then records the write to the journal of the context if any.
+///
+/// `new_value` is recorded if the value after the write isn't cached.
+pub(super) fn record_write<Ctrl, Ctxt>(
+    node: Node,
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    new_value: NodeValue,
+    write: impl FnOnce(&mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<()>,
+) -> GenApiResult<()>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let journal = match ctxt.journal.clone() {
+        Some(journal) => journal,
+        None => return write(ctxt),
+    };
+
+    let old_value = cached_value(node, ctxt);
+    write(ctxt)?;
+    let new_value = cached_value(node, ctxt).unwrap_or(new_value);
+    journal.push(JournalEntry {
+        node: node.name(ctxt).to_string(),
+        old_value,
+        new_value,
+        timestamp: SystemTime::now(),
+    });
+    Ok(())
+}
+
+/// Returns the value of `node` if it can be evaluated without accessing the device.
+fn cached_value<Ctrl, Ctxt>(node: Node, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> Option<NodeValue>
+where
+    Ctxt: GenApiCtxt,
+{
+    let nid = node.0;
+    let mut device = CacheOnlyDevice;
+    ctxt.ctxt.enter(|ns, vc| {
+        let value = if let Some(n) = nid.as_iinteger_kind(ns) {
+            NodeValue::Integer(n.value(&mut device, ns, vc).ok()?)
+        } else if let Some(n) = nid.as_ifloat_kind(ns) {
+            NodeValue::Float(n.value(&mut device, ns, vc).ok()?)
+        } else if let Some(n) = nid.as_ienumeration_kind(ns) {
+            let entry = n.current_entry(&mut device, ns, vc).ok()?;
+            NodeValue::Enumeration(entry.expect_enum_entry(ns).ok()?.symbolic().to_string())
+        } else if let Some(n) = nid.as_iboolean_kind(ns) {
+            NodeValue::Boolean(n.value(&mut device, ns, vc).ok()?)
+        } else if let Some(n) = nid.as_istring_kind(ns) {
+            NodeValue::String(n.value(&mut device, ns, vc).ok()?)
+        } else {
+            return None;
+        };
+        Some(value)
+    })
+}
+
+/// A device which refuses all accesses, so that only cached values are evaluated.
+struct CacheOnlyDevice;
+
+impl Device for CacheOnlyDevice {
+    fn read_mem(
+        &mut self,
+        _: i64,
+        _: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Err("the register isn't cached".into())
+    }
+
+    fn write_mem(
+        &mut self,
+        _: i64,
+        _: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Err("writing isn't allowed while evaluating cached values".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(value: i64) -> JournalEntry {
+        JournalEntry {
+            node: "ExposureTime".into(),
+            old_value: None,
+            new_value: NodeValue::Integer(value),
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_bounded_journal() {
+        let journal = WriteJournal::new(2);
+        let shared = journal.clone();
+        for value in 0..3 {
+            journal.push(entry(value));
+        }
+
+        let values: Vec<_> = shared
+            .entries()
+            .into_iter()
+            .map(|entry| entry.new_value)
+            .collect();
+        assert_eq!(values, [NodeValue::Integer(1), NodeValue::Integer(2)]);
+
+        journal.clear();
+        assert!(shared.entries().is_empty());
+        assert_eq!(shared.capacity(), 2);
+    }
+}
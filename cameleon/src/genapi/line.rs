@@ -0,0 +1,152 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains typed access to `Digital IO Control` features defined in `GenICam SFNC`.
+
+use super::{GenApiCtxt, ParamsCtxt};
+use crate::{CameleonResult, DeviceControl};
+
+/// Direction of a physical line, corresponds to `LineMode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineMode {
+    /// The line is used as an input, e.g. a hardware trigger.
+    Input,
+    /// The line is used as an output, e.g. a strobe.
+    Output,
+}
+
+impl LineMode {
+    fn as_symbolic(self) -> &'static str {
+        match self {
+            Self::Input => "Input",
+            Self::Output => "Output",
+        }
+    }
+}
+
+/// Configuration of a physical line applied by [`Camera::configure_line`](crate::Camera::configure_line).
+///
+/// Fields set to `None` are left as is.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LineConfig {
+    /// Value of `LineMode`.
+    pub mode: Option<LineMode>,
+    /// Symbolic name of `LineSource` entry, e.g. `ExposureActive`.
+    /// Only meaningful when the line is an output.
+    pub source: Option<String>,
+    /// Value of `LineInverter`.
+    pub inverter: Option<bool>,
+}
+
+impl LineConfig {
+    /// Constructs a configuration of an input line.
+    pub fn input() -> Self {
+        Self {
+            mode: Some(LineMode::Input),
+            ..Self::default()
+        }
+    }
+
+    /// Constructs a configuration of an output line driven by `source`.
+    pub fn output(source: impl Into<String>) -> Self {
+        Self {
+            mode: Some(LineMode::Output),
+            source: Some(source.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets `LineInverter`.
+    pub fn inverted(mut self, inverter: bool) -> Self {
+        self.inverter = Some(inverter);
+        self
+    }
+
+    /// Selects `line` by the symbolic name of `LineSelector` entry and applies the configuration.
+    ///
+    /// `LineMode` is applied first because `LineSource` is usually writable only for output lines.
+    pub fn apply<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        line: &str,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let selector = expect_node!(ctxt, "LineSelector", as_enumeration);
+        selector.set_entry_by_symbolic(ctxt, line)?;
+
+        if let Some(mode) = self.mode {
+            let node = expect_node!(ctxt, "LineMode", as_enumeration);
+            node.set_entry_by_symbolic(ctxt, mode.as_symbolic())?;
+        }
+
+        if let Some(source) = &self.source {
+            let node = expect_node!(ctxt, "LineSource", as_enumeration);
+            node.set_entry_by_symbolic(ctxt, source)?;
+        }
+
+        if let Some(inverter) = self.inverter {
+            let node = expect_node!(ctxt, "LineInverter", as_boolean);
+            node.set_value(ctxt, inverter)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Status of all lines read from `LineStatusAll`.
+///
+/// Bit `n` holds the status of the line whose `LineSelector` entry has the value `n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LineStatus(u64);
+
+impl LineStatus {
+    /// Constructs `LineStatus` from the raw value of `LineStatusAll`.
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Reads `LineStatusAll` from the device.
+    pub fn read<Ctrl, Ctxt>(ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = expect_node!(ctxt, "LineStatusAll", as_integer);
+        Ok(Self::from_bits(node.value(ctxt)? as u64))
+    }
+
+    /// Returns the raw value of `LineStatusAll`.
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Returns `true` if the line at `index` is high.
+    /// Returns `false` if `index` is out of range.
+    pub fn is_high(self, index: u32) -> bool {
+        index < u64::BITS && self.0 & (1 << index) != 0
+    }
+
+    /// Returns an iterator over the indices of the lines which are high.
+    pub fn iter_high(self) -> impl Iterator<Item = u32> {
+        (0..u64::BITS).filter(move |i| self.is_high(*i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_status() {
+        let status = LineStatus::from_bits(0b1010);
+        assert!(!status.is_high(0));
+        assert!(status.is_high(1));
+        assert!(status.is_high(3));
+        assert!(!status.is_high(64));
+        assert_eq!(status.iter_high().collect::<Vec<_>>(), vec![1, 3]);
+    }
+}
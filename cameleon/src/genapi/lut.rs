@@ -0,0 +1,218 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains typed access to `LUT` (Look Up Table) features defined in `GenICam SFNC`.
+
+use std::convert::TryInto;
+
+use super::{BooleanNode, EnumerationNode, GenApiCtxt, IntegerNode, ParamsCtxt, RegisterNode};
+use crate::{CameleonError, CameleonResult, ControlError, DeviceControl};
+
+/// Provides access to `LUTSelector`, `LUTEnable`, `LUTIndex`, `LUTValue` and `LUTValueAll`.
+///
+/// A whole table is transferred with a single block transfer through `LUTValueAll` register if
+/// the device has it, otherwise each entry is accessed through `LUTIndex` and `LUTValue`.
+///
+/// NOTE: Entries of `LUTValueAll` are interpreted as little endian integers whose size is the
+/// register length divided by the number of entries.
+///
+/// # Examples
+/// ```no_run
+/// # use cameleon::u3v;
+/// # let mut cameras = u3v::enumerate_cameras().unwrap();
+/// # let mut camera = cameras.pop().unwrap();
+/// use cameleon::genapi::Lut;
+///
+/// camera.open().unwrap();
+/// camera.load_context().unwrap();
+///
+/// let mut ctxt = camera.params_ctxt().unwrap();
+/// let lut = Lut::new(&ctxt).unwrap();
+/// lut.select(&mut ctxt, "Luminance").unwrap();
+///
+/// // Inverts the table.
+/// let mut table = lut.read(&mut ctxt).unwrap();
+/// let max = *table.iter().max().unwrap();
+/// table.iter_mut().for_each(|v| *v = max - *v);
+/// lut.write(&mut ctxt, &table).unwrap();
+/// lut.set_enabled(&mut ctxt, true).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lut {
+    selector: Option<EnumerationNode>,
+    enable: Option<BooleanNode>,
+    index: IntegerNode,
+    value: IntegerNode,
+    value_all: Option<RegisterNode>,
+}
+
+impl Lut {
+    /// Constructs `Lut` from the nodes in the context.
+    ///
+    /// Returns an error if the device doesn't have `LUTIndex` or `LUTValue`.
+    pub fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        Ok(Self {
+            selector: ctxt
+                .node("LUTSelector")
+                .and_then(|n| n.as_enumeration(ctxt)),
+            enable: ctxt.node("LUTEnable").and_then(|n| n.as_boolean(ctxt)),
+            index: expect_node!(ctxt, "LUTIndex", as_integer),
+            value: expect_node!(ctxt, "LUTValue", as_integer),
+            value_all: ctxt.node("LUTValueAll").and_then(|n| n.as_register(ctxt)),
+        })
+    }
+
+    /// Selects the table to be accessed by the symbolic name of `LUTSelector` entry.
+    pub fn select<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        table: &str,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let selector = self
+            .selector
+            .ok_or_else(|| CameleonError::InvalidGenApiXml("missing LUTSelector".into()))?;
+        Ok(selector.set_entry_by_symbolic(ctxt, table)?)
+    }
+
+    /// Returns `true` if the selected table is enabled.
+    pub fn is_enabled<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<bool>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        Ok(self.enable_node()?.value(ctxt)?)
+    }
+
+    /// Enables or disables the selected table.
+    pub fn set_enabled<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        enable: bool,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        Ok(self.enable_node()?.set_value(ctxt, enable)?)
+    }
+
+    /// Returns the number of entries of the selected table.
+    pub fn len<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<usize>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let min = self.index.min(ctxt)?;
+        let max = self.index.max(ctxt)?;
+        Ok((max - min + 1).max(0) as usize)
+    }
+
+    /// Reads the whole selected table.
+    pub fn read<Ctrl, Ctxt>(&self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<Vec<i64>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let len = self.len(ctxt)?;
+        if let Some((node, elem_size)) = self.value_all_with_elem_size(ctxt, len)? {
+            let mut buf = vec![0; len * elem_size];
+            node.read(ctxt, &mut buf)?;
+            return Ok(buf.chunks_exact(elem_size).map(decode_le).collect());
+        }
+
+        let min = self.index.min(ctxt)?;
+        let mut table = Vec::with_capacity(len);
+        for i in 0..len as i64 {
+            self.index.set_value(ctxt, min + i)?;
+            table.push(self.value.value(ctxt)?);
+        }
+        Ok(table)
+    }
+
+    /// Writes the whole selected table.
+    ///
+    /// `table.len()` must be same as [`Self::len`].
+    pub fn write<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        table: &[i64],
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let len = self.len(ctxt)?;
+        if table.len() != len {
+            return Err(ControlError::InvalidData(
+                format!(
+                    "LUT length mismatch: expected {}, but got {}",
+                    len,
+                    table.len()
+                )
+                .into(),
+            )
+            .into());
+        }
+
+        if let Some((node, elem_size)) = self.value_all_with_elem_size(ctxt, len)? {
+            let mut buf = Vec::with_capacity(len * elem_size);
+            for value in table {
+                buf.extend_from_slice(&value.to_le_bytes()[..elem_size]);
+            }
+            return Ok(node.write(ctxt, &buf)?);
+        }
+
+        let min = self.index.min(ctxt)?;
+        for (i, value) in table.iter().enumerate() {
+            self.index.set_value(ctxt, min + i as i64)?;
+            self.value.set_value(ctxt, *value)?;
+        }
+        Ok(())
+    }
+
+    fn enable_node(&self) -> CameleonResult<BooleanNode> {
+        self.enable
+            .ok_or_else(|| CameleonError::InvalidGenApiXml("missing LUTEnable".into()))
+    }
+
+    /// Returns `LUTValueAll` and its entry size if block transfer is possible.
+    fn value_all_with_elem_size<Ctrl, Ctxt>(
+        &self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        len: usize,
+    ) -> CameleonResult<Option<(RegisterNode, usize)>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let node = match self.value_all {
+            Some(node) if len > 0 => node,
+            _ => return Ok(None),
+        };
+
+        let reg_len: usize = node
+            .length(ctxt)?
+            .try_into()
+            .map_err(|_| CameleonError::InvalidGenApiXml("invalid LUTValueAll length".into()))?;
+        let elem_size = reg_len / len;
+        if reg_len.is_multiple_of(len) && matches!(elem_size, 1 | 2 | 4 | 8) {
+            Ok(Some((node, elem_size)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn decode_le(bytes: &[u8]) -> i64 {
+    let mut buf = [0; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    i64::from_le_bytes(buf)
+}
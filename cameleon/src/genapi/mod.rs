@@ -38,30 +38,56 @@
 //! # camera.close().unwrap();
 //! ```
 
+mod category_tree;
+mod event;
+mod file_access;
+mod journal;
+mod line;
+mod lut;
 mod node_kind;
-
+mod pixel_format;
+mod refresh;
+mod transaction;
+#[cfg(feature = "uom-interop")]
+mod units;
+
+pub use category_tree::{CategoryTree, CategoryTreeNode};
+pub use file_access::{FileReader, FileWriter};
+pub use journal::{JournalEntry, WriteJournal};
+pub use line::{LineConfig, LineMode, LineStatus};
+pub use lut::Lut;
 pub use node_kind::{
     BooleanNode, CategoryNode, CommandNode, EnumEntryNode, EnumerationNode, FloatNode, IntegerNode,
     Node, PortNode, RegisterNode, StringNode,
 };
+pub use pixel_format::PixelFormatNode;
+pub use refresh::{NodeValue, RefreshReport};
+pub use transaction::Transaction;
+#[cfg(feature = "uom-interop")]
+pub use units::{FrequencyNode, TimeNode};
 
 use std::{
     convert::TryInto,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use auto_impl::auto_impl;
-use cameleon_genapi::{builder::GenApiBuilder, store};
+use cameleon_genapi::{
+    builder::{self, GenApiBuilder},
+    store,
+};
 
 use super::{ControlError, ControlResult, DeviceControl};
 
 pub use cameleon_genapi::{
     elem_type::{AccessMode, NameSpace, Visibility},
     store::{
-        CacheSink, CacheStore, DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeId,
-        NodeStore, ValueStore,
+        CacheSink, CacheStore, DefaultCacheStore, DefaultNodeStore, DefaultValueStore,
+        LazyCacheStore, LazyNodeStore, LazyValueStore, NodeId, NodeStore, ShardedCacheStore,
+        ShardedValueStore, ValueStore,
     },
-    GenApiError, RegisterDescription, ValueCtxt,
+    GenApiError, GenApiErrorKind, RegisterDescription, StringError, ValueCtxt,
 };
 
 /// Manages context of parameters of the device.
@@ -105,6 +131,31 @@ pub struct ParamsCtxt<Ctrl, Ctxt> {
     pub ctrl: Ctrl,
     /// `GenApi` context of the device.
     pub ctxt: Ctxt,
+    /// Journal which records writes to nodes through the context. `None` disables journaling.
+    journal: Option<WriteJournal>,
+}
+
+impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt> {
+    /// Creates a context whose writes aren't journaled.
+    pub fn new(ctrl: Ctrl, ctxt: Ctxt) -> Self {
+        Self {
+            ctrl,
+            ctxt,
+            journal: None,
+        }
+    }
+
+    /// Returns the journal which records writes to nodes through the context, or `None` if
+    /// journaling is disabled.
+    pub fn journal(&self) -> Option<&WriteJournal> {
+        self.journal.as_ref()
+    }
+
+    /// Sets the journal which records writes to nodes through the context. `None` disables
+    /// journaling.
+    pub fn set_journal(&mut self, journal: Option<WriteJournal>) {
+        self.journal = journal;
+    }
 }
 
 impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
@@ -117,6 +168,14 @@ where
         ns.id_by_name(name).map(Node)
     }
 
+    /// Same as [`Self::node`], but returns the replacement of the node if the node is deprecated
+    /// and has `pAlias`. A warning is emitted when the node is replaced.
+    ///
+    /// See also [`Node::resolve_deprecated`].
+    pub fn resolved_node(&self, name: &str) -> Option<Node> {
+        self.node(name).map(|node| node.resolve_deprecated(self))
+    }
+
     /// Returns [`NodeStore`] in the context.
     pub fn node_store(&self) -> &Ctxt::NS {
         self.ctxt.node_store()
@@ -147,6 +206,55 @@ where
     }
 }
 
+impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+{
+    /// Runs `f` with `timeout` as the timeout of each transaction with the device.
+    ///
+    /// Use this for operations which take much longer than the default timeout, e.g. executing
+    /// `BalanceWhiteAuto` once. The previous timeout is restored when `f` returns or panics. To
+    /// set a timeout of a node persistently, use [`DeviceControl::set_node_timeout`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # let mut cameras = cameleon::u3v::enumerate_cameras().unwrap();
+    /// # let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let mut ctxt = camera.params_ctxt().unwrap();
+    /// let node = ctxt.node("BalanceWhiteAuto").unwrap().as_enumeration(&ctxt).unwrap();
+    /// ctxt.with_timeout(Duration::from_secs(10), |ctxt| {
+    ///     node.set_entry_by_symbolic(ctxt, "Once")
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn with_timeout<F, R>(&mut self, timeout: Duration, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let prev = self.ctrl.timeout_override();
+        self.ctrl.set_timeout_override(Some(timeout));
+        let guard = RestoreTimeoutOverride { ctxt: self, prev };
+        f(&mut *guard.ctxt)
+    }
+}
+
+/// Restores the timeout override of the control handle on drop, see
+/// [`ParamsCtxt::with_timeout`].
+struct RestoreTimeoutOverride<'a, Ctrl: DeviceControl, Ctxt> {
+    ctxt: &'a mut ParamsCtxt<Ctrl, Ctxt>,
+    prev: Option<Duration>,
+}
+
+impl<Ctrl: DeviceControl, Ctxt> Drop for RestoreTimeoutOverride<'_, Ctrl, Ctxt> {
+    fn drop(&mut self) {
+        self.ctxt.ctrl.set_timeout_override(self.prev);
+    }
+}
+
 impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt> {
     /// Converts internal types. This method work same as `std::convert::From`, just hack to avoid
     /// `E0119`.
@@ -158,6 +266,7 @@ impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt> {
         ParamsCtxt {
             ctrl: from.ctrl.into(),
             ctxt: from.ctxt.into(),
+            journal: from.journal,
         }
     }
 
@@ -171,6 +280,7 @@ impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt> {
         ParamsCtxt {
             ctrl: self.ctrl.into(),
             ctxt: self.ctxt.into(),
+            journal: self.journal,
         }
     }
 }
@@ -197,6 +307,17 @@ pub trait GenApiCtxt {
     fn clear_cache(&mut self) {
         self.enter(|_, value_ctxt| value_ctxt.clear_cache())
     }
+
+    /// Sets read-only mode of the context. In read-only mode, all writes to nodes are rejected
+    /// with an error of [`GenApiErrorKind::AccessDenied`] without touching the device.
+    fn set_read_only(&mut self, read_only: bool) {
+        self.enter(|_, value_ctxt| value_ctxt.set_read_only(read_only))
+    }
+
+    /// Returns `true` if the context is in read-only mode.
+    fn is_read_only(&mut self) -> bool {
+        self.enter(|_, value_ctxt| value_ctxt.is_read_only())
+    }
 }
 
 /// A trait that provides directly conversion from `GenApi` string to a `GenApi` context.
@@ -212,6 +333,7 @@ pub trait FromXml {
 ///
 /// If you need no cache context, use [`NoCacheGenApiCtxt`].
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefaultGenApiCtxt {
     /// Node store.
     pub node_store: store::DefaultNodeStore,
@@ -244,9 +366,13 @@ impl FromXml for DefaultGenApiCtxt {
     where
         Self: Sized + GenApiCtxt,
     {
-        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
-            .build(xml)
-            .map_err(|e| ControlError::InvalidData(e.into()))?;
+        let builder = GenApiBuilder::<DefaultNodeStore>::default();
+        #[cfg(feature = "parallel-parse")]
+        let built = builder.build_parallel(xml);
+        #[cfg(not(feature = "parallel-parse"))]
+        let built = builder.build(xml);
+        let (reg_desc, node_store, value_ctxt) =
+            built.map_err(|e| ControlError::InvalidData(e.into()))?;
         Ok(Self {
             node_store,
             value_ctxt,
@@ -303,9 +429,113 @@ impl From<DefaultGenApiCtxt> for SharedDefaultGenApiCtxt {
     }
 }
 
+/// A sharable version of [`DefaultGenApiCtxt`] which doesn't serialize accesses from clones.
+///
+/// Unlike [`SharedDefaultGenApiCtxt`], which locks the whole value context, values and cache are
+/// stored in [`ShardedValueStore`] and [`ShardedCacheStore`], so clones of the context on multiple
+/// threads can evaluate independent nodes concurrently.
+#[derive(Clone, Debug)]
+pub struct ConcurrentGenApiCtxt {
+    /// Node store.
+    pub node_store: Arc<store::DefaultNodeStore>,
+    /// Value context.
+    pub value_ctxt: ValueCtxt<Arc<store::ShardedValueStore>, Arc<store::ShardedCacheStore>>,
+    /// Register description.
+    pub reg_desc: Arc<RegisterDescription>,
+}
+
+impl GenApiCtxt for ConcurrentGenApiCtxt {
+    type NS = store::DefaultNodeStore;
+    type VS = Arc<store::ShardedValueStore>;
+    type CS = Arc<store::ShardedCacheStore>;
+
+    fn enter<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&Self::NS, &mut ValueCtxt<Self::VS, Self::CS>) -> R,
+    {
+        f(&self.node_store, &mut self.value_ctxt)
+    }
+
+    fn node_store(&self) -> &Self::NS {
+        &self.node_store
+    }
+}
+
+impl FromXml for ConcurrentGenApiCtxt {
+    fn from_xml(xml: &impl AsRef<str>) -> ControlResult<Self>
+    where
+        Self: Sized + GenApiCtxt,
+    {
+        Ok(DefaultGenApiCtxt::from_xml(xml)?.into())
+    }
+}
+
+impl From<DefaultGenApiCtxt> for ConcurrentGenApiCtxt {
+    fn from(from: DefaultGenApiCtxt) -> Self {
+        let value_ctxt = ValueCtxt::new(
+            Arc::new(from.value_ctxt.value_store.into()),
+            Arc::new(from.value_ctxt.cache_store.into()),
+        )
+        .with_value_policy(from.value_ctxt.value_policy)
+        .with_read_only(from.value_ctxt.read_only);
+        Self {
+            node_store: Arc::new(from.node_store),
+            value_ctxt,
+            reg_desc: Arc::new(from.reg_desc),
+        }
+    }
+}
+
+/// `GenApi` context which parses nodes on first access.
+///
+/// Loading the context is faster than [`DefaultGenApiCtxt`] because only the nodes which are
+/// actually used are parsed, see [`LazyNodeStore`] for details.
+#[derive(Debug)]
+pub struct LazyGenApiCtxt {
+    /// Node store.
+    pub node_store: store::LazyNodeStore,
+    /// Value context.
+    pub value_ctxt: ValueCtxt<store::LazyValueStore, store::LazyCacheStore>,
+    /// Register description.
+    pub reg_desc: RegisterDescription,
+}
+
+impl GenApiCtxt for LazyGenApiCtxt {
+    type NS = store::LazyNodeStore;
+    type VS = store::LazyValueStore;
+    type CS = store::LazyCacheStore;
+
+    fn enter<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&Self::NS, &mut ValueCtxt<Self::VS, Self::CS>) -> R,
+    {
+        f(&self.node_store, &mut self.value_ctxt)
+    }
+
+    fn node_store(&self) -> &Self::NS {
+        &self.node_store
+    }
+}
+
+impl FromXml for LazyGenApiCtxt {
+    fn from_xml(xml: &impl AsRef<str>) -> ControlResult<Self>
+    where
+        Self: Sized + GenApiCtxt,
+    {
+        let (reg_desc, node_store, value_ctxt) =
+            builder::build_lazy(xml.as_ref()).map_err(|e| ControlError::InvalidData(e.into()))?;
+        Ok(Self {
+            node_store,
+            value_ctxt,
+            reg_desc,
+        })
+    }
+}
+
 /// `GenApi` context.  
 /// This context doesn't cache any value of `GenApi` nodes.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NoCacheGenApiCtxt {
     /// Node store.
     pub node_store: store::DefaultNodeStore,
@@ -338,10 +568,13 @@ impl FromXml for NoCacheGenApiCtxt {
     where
         Self: Sized + GenApiCtxt,
     {
-        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
-            .no_cache()
-            .build(xml)
-            .map_err(|e| ControlError::InvalidData(e.into()))?;
+        let builder = GenApiBuilder::<DefaultNodeStore>::default().no_cache();
+        #[cfg(feature = "parallel-parse")]
+        let built = builder.build_parallel(xml);
+        #[cfg(not(feature = "parallel-parse"))]
+        let built = builder.build(xml);
+        let (reg_desc, node_store, value_ctxt) =
+            built.map_err(|e| ControlError::InvalidData(e.into()))?;
         Ok(Self {
             node_store,
             value_ctxt,
@@ -354,7 +587,9 @@ impl From<DefaultGenApiCtxt> for NoCacheGenApiCtxt {
     fn from(from: DefaultGenApiCtxt) -> Self {
         Self {
             node_store: from.node_store,
-            value_ctxt: ValueCtxt::new(from.value_ctxt.value_store, store::CacheSink::default()),
+            value_ctxt: ValueCtxt::new(from.value_ctxt.value_store, store::CacheSink::default())
+                .with_value_policy(from.value_ctxt.value_policy)
+                .with_read_only(from.value_ctxt.read_only),
             reg_desc: from.reg_desc,
         }
     }
@@ -423,13 +658,44 @@ pub enum CompressionType {
     Zip,
 }
 
-struct GenApiDevice<'a, T> {
+struct GenApiDevice<'a, T: DeviceControl> {
     inner: &'a mut T,
+    /// Set if the timeout is overridden by the node timeout, which is cleared on drop.
+    node_timeout_applied: bool,
 }
 
-impl<'a, T> GenApiDevice<'a, T> {
+impl<'a, T: DeviceControl> GenApiDevice<'a, T> {
     fn new(inner: &'a mut T) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            node_timeout_applied: false,
+        }
+    }
+
+    /// Same as [`Self::new`], but applies the timeout set by
+    /// [`DeviceControl::set_node_timeout`] for the node `nid` while the device is alive.
+    fn for_node(inner: &'a mut T, nid: NodeId, ns: &impl NodeStore) -> Self {
+        let timeout = ns.name_by_id(nid).and_then(|name| inner.node_timeout(name));
+        // The timeout of the whole operation takes precedence.
+        let node_timeout_applied = match timeout {
+            Some(timeout) if inner.timeout_override().is_none() => {
+                inner.set_timeout_override(Some(timeout));
+                true
+            }
+            _ => false,
+        };
+        Self {
+            inner,
+            node_timeout_applied,
+        }
+    }
+}
+
+impl<'a, T: DeviceControl> Drop for GenApiDevice<'a, T> {
+    fn drop(&mut self) {
+        if self.node_timeout_applied {
+            self.inner.set_timeout_override(None);
+        }
     }
 }
 
@@ -9,10 +9,22 @@ use cameleon_genapi::{
     elem_type::{DisplayNotation, FloatRepresentation, IntegerRepresentation},
     interface::IncrementMode,
     prelude::*,
-    GenApiError, GenApiResult, NodeId,
+    FromBytes, GenApiError, GenApiResult, NodeId, ToBytes,
 };
+use tracing::warn;
 
-use super::{DeviceControl, GenApiCtxt, GenApiDevice, ParamsCtxt};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use super::{journal, DeviceControl, GenApiCtxt, GenApiDevice, NodeValue, ParamsCtxt};
+use crate::{CameleonResult, ControlError};
+
+/// Interval used to poll completion of a command if the node doesn't specify `PollingTime`.
+const DEFAULT_COMMAND_POLLING_TIME: Duration = Duration::from_millis(1);
 
 /// A node that has `IInteger` interface
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -77,11 +89,42 @@ macro_rules! delegate {
                   $Ctxt: GenApiCtxt
             {
                 ctxt.enter2(|ctrl, ns, vc| {
-                    let mut device = GenApiDevice::new(ctrl);
+                    let mut device = GenApiDevice::for_node(ctrl, $self.0, ns);
                     $self.0
                         .$expect_kind(ns)
                         .unwrap()
                         .$method($($arg,)* &mut device, ns, vc)
+                        .map_err(|e| e.with_node($self.0, ns))
+                })
+            }
+        )*
+    };
+
+    (
+        journaled,
+        $expect_kind:ident,
+        $(
+            $(#[$meta:meta])*
+            $vis:vis fn $method:ident<$Ctrl:ident, $Ctxt:ident>($self:ident, ctxt: &mut ParamsCtxt<Ctrl, Ctxt> $(,$arg:ident: $arg_ty:ty)*) -> GenApiResult<()> => |$value_ctxt:ident| $new_value:expr,)*) => {
+        $(
+            $(#[$meta])*
+            $vis fn $method<$Ctrl, $Ctxt>($self, ctxt: &mut ParamsCtxt<$Ctrl, $Ctxt> $(,$arg: $arg_ty)*) -> GenApiResult<()>
+            where $Ctrl: DeviceControl,
+                  $Ctxt: GenApiCtxt
+            {
+                let new_value = {
+                    let $value_ctxt: &ParamsCtxt<$Ctrl, $Ctxt> = &*ctxt;
+                    $new_value
+                };
+                journal::record_write(Node($self.0), ctxt, new_value, |ctxt| {
+                    ctxt.enter2(|ctrl, ns, vc| {
+                        let mut device = GenApiDevice::for_node(ctrl, $self.0, ns);
+                        $self.0
+                            .$expect_kind(ns)
+                            .unwrap()
+                            .$method($($arg,)* &mut device, ns, vc)
+                            .map_err(|e| e.with_node($self.0, ns))
+                    })
                 })
             }
         )*
@@ -111,8 +154,13 @@ impl IntegerNode {
         expect_iinteger_kind,
         /// Returns the value of the node.
         pub fn value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<i64>,
-        /// Sets the value of the node.
-        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: i64) -> GenApiResult<()>,
+        /// Same as [`Self::value`], but supports `IntReg` and `MaskedIntReg` nodes of registers
+        /// up to 16 bytes, e.g. `GUID` registers. Use [`RegisterNode::read`] to read longer
+        /// registers as bytes.
+        pub fn value_i128<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<i128>,
+        /// Same as [`Self::set_value`], but supports `IntReg` and `MaskedIntReg` nodes of
+        /// registers up to 16 bytes.
+        pub fn set_value_i128<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: i128) -> GenApiResult<()>,
         /// Returns the minimum value which the node can take.
         pub fn min<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<i64>,
         /// Restricts minimum value of the node.
@@ -132,6 +180,13 @@ impl IntegerNode {
         /// Returns `true` if the node is writable.
         pub fn is_writable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
     }
+    delegate! {
+        journaled,
+        expect_iinteger_kind,
+        /// Sets the value of the node.
+        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: i64) -> GenApiResult<()>
+            => |_ctxt| NodeValue::Integer(value),
+    }
     delegate! {
        no_vc,
        expect_iinteger_kind,
@@ -154,6 +209,20 @@ impl IntegerNode {
             .map(String::from)
     }
 
+    /// Returns the sorted list of valid values if `inc_mode` returns
+    /// `IncrementMode::ListIncrement`, otherwise returns an empty list.
+    pub fn valid_value_set<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Vec<i64>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        self.0
+            .expect_iinteger_kind(ns)
+            .unwrap()
+            .valid_value_set(ns)
+            .to_vec()
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -165,8 +234,6 @@ impl FloatNode {
         expect_ifloat_kind,
         /// Returns the value of the node.
         pub fn value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<f64>,
-        /// Sets the value of the node.
-        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: f64) -> GenApiResult<()>,
         /// Returns minimum value which the node can take.
         pub fn min<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<f64>,
         /// Returns maximum value which the node can take.
@@ -183,6 +250,14 @@ impl FloatNode {
         pub fn is_writable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
     }
 
+    delegate! {
+        journaled,
+        expect_ifloat_kind,
+        /// Sets the value of the node.
+        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: f64) -> GenApiResult<()>
+            => |_ctxt| NodeValue::Float(value),
+    }
+
     delegate! {
        no_vc,
        expect_ifloat_kind,
@@ -207,6 +282,20 @@ impl FloatNode {
             .map(String::from)
     }
 
+    /// Returns the sorted list of valid values if `inc_mode` returns
+    /// `IncrementMode::ListIncrement`, otherwise returns an empty list.
+    pub fn valid_value_set<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Vec<f64>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        self.0
+            .expect_ifloat_kind(ns)
+            .unwrap()
+            .valid_value_set(ns)
+            .to_vec()
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -218,9 +307,8 @@ impl StringNode {
         expect_istring_kind,
         /// Returns the value of the node.
         pub fn value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<String>,
-        /// Sets the value of the node.
-        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: String) -> GenApiResult<()>,
-        /// Returns the maximum length of the string.
+        /// Returns the maximum length of the string in bytes, which is the length of the
+        /// underlying register.
         pub fn max_length<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<i64>,
         /// Returns `true` if the node is readable.
         pub fn is_readable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
@@ -228,6 +316,17 @@ impl StringNode {
         pub fn is_writable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
     }
 
+    delegate! {
+        journaled,
+        expect_istring_kind,
+        /// Sets the value of the node.
+        ///
+        /// Returns an error of [`GenApiErrorKind::InvalidString`](super::GenApiErrorKind) if the
+        /// value isn't an ASCII string, contains NUL, or exceeds [`Self::max_length`].
+        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: String) -> GenApiResult<()>
+            => |_ctxt| NodeValue::String(value.clone()),
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -236,11 +335,33 @@ impl StringNode {
 
 impl EnumerationNode {
     delegate! {
+        journaled,
         expect_ienumeration_kind,
         /// Sets entry to the enumeration node by the entry symbolic name.
-        pub fn set_entry_by_symbolic<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, name: &str) -> GenApiResult<()>,
+        pub fn set_entry_by_symbolic<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, name: &str) -> GenApiResult<()>
+            => |_ctxt| NodeValue::Enumeration(name.to_string()),
         /// Sets entry to the enumeration node by the entry value.
-        pub fn set_entry_by_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: i64) -> GenApiResult<()>,
+        pub fn set_entry_by_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: i64) -> GenApiResult<()>
+            => |ctxt| self.entry_value(ctxt, |entry| entry.value(ctxt) == value)
+                .unwrap_or(NodeValue::Integer(value)),
+        /// Sets the entry whose `NumericValue` is the nearest to `value` within `tolerance`.
+        ///
+        /// The integer value of an entry is used if the entry has no `NumericValue`.
+        pub fn set_entry_by_numeric<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: f64, tolerance: f64) -> GenApiResult<()>
+            => |ctxt| {
+                let ns = ctxt.node_store();
+                let nearest = self.0.expect_ienumeration_kind(ns).unwrap().entry_by_numeric(value, tolerance, ns);
+                self.entry_value(ctxt, |entry| Some(entry.0) == nearest)
+                    .unwrap_or(NodeValue::Float(value))
+            },
+    }
+
+    delegate! {
+        expect_ienumeration_kind,
+        /// Returns `NumericValue` of the current entry.
+        ///
+        /// The integer value of the entry is returned if the entry has no `NumericValue`.
+        pub fn current_numeric<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<f64>,
         /// Returns `true` if the node is readable.
         pub fn is_readable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
         /// Returns `true` if the node is writable.
@@ -263,6 +384,22 @@ impl EnumerationNode {
             .collect()
     }
 
+    /// Returns the symbolic name of the first entry which satisfies `pred` as a [`NodeValue`].
+    fn entry_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &ParamsCtxt<Ctrl, Ctxt>,
+        mut pred: impl FnMut(EnumEntryNode) -> bool,
+    ) -> Option<NodeValue>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        self.entries(ctxt)
+            .into_iter()
+            .find(|entry| pred(*entry))
+            .map(|entry| NodeValue::Enumeration(entry.symbolic(ctxt).to_string()))
+    }
+
     /// Returns current entry of the node.
     pub fn current_entry<Ctrl, Ctxt>(
         self,
@@ -273,11 +410,12 @@ impl EnumerationNode {
         Ctxt: GenApiCtxt,
     {
         let value = ctxt.enter2(|ctrl, ns, vc| {
-            let mut device = GenApiDevice::new(ctrl);
+            let mut device = GenApiDevice::for_node(ctrl, self.0, ns);
             self.0
                 .expect_ienumeration_kind(ns)
                 .unwrap()
                 .current_value(&mut device, ns, vc)
+                .map_err(|e| e.with_node(self.0, ns))
         })?;
         let entries = self.entries(ctxt);
         entries
@@ -320,6 +458,16 @@ impl EnumEntryNode {
         self.0.expect_enum_entry(ns).unwrap().value()
     }
 
+    /// Returns `NumericValue` of the entry, or the integer value if the entry has no
+    /// `NumericValue`.
+    pub fn numeric_value<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> f64
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        self.0.expect_enum_entry(ns).unwrap().numeric_value()
+    }
+
     /// Returns symbolic name of the entry.
     pub fn symbolic<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> &str
     where
@@ -346,6 +494,66 @@ impl CommandNode {
         pub fn is_writable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
     }
 
+    /// Executes the command and blocks until the device completes it.
+    ///
+    /// Completion is polled at the interval specified by `PollingTime` of the node.
+    /// Returns [`ControlError::Timeout`] if the command isn't completed within `timeout`.
+    pub fn execute_and_wait<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        timeout: Duration,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let interval = self.polling_interval(ctxt);
+        self.execute(ctxt)?;
+
+        let start = Instant::now();
+        while !self.is_done(ctxt)? {
+            if start.elapsed() > timeout {
+                return Err(ControlError::Timeout.into());
+            }
+            std::thread::sleep(interval);
+        }
+        Ok(())
+    }
+
+    /// Executes the command and returns a future which resolves when the device completes it.
+    ///
+    /// Completion is polled at the interval specified by `PollingTime` of the node. The future
+    /// doesn't depend on any async runtime, so wrap it with the timeout of the runtime in use if
+    /// needed.
+    pub async fn execute_async<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let interval = self.polling_interval(ctxt);
+        self.execute(ctxt)?;
+
+        while !self.is_done(ctxt)? {
+            Delay::new(interval).await;
+        }
+        Ok(())
+    }
+
+    fn polling_interval<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Duration
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        self.0
+            .expect_icommand_kind(ns)
+            .unwrap()
+            .polling_time(ns)
+            .map_or(DEFAULT_COMMAND_POLLING_TIME, Duration::from_millis)
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -357,14 +565,29 @@ impl BooleanNode {
         expect_iboolean_kind,
         /// Returns the value of the node.
         pub fn value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
-        /// Sets the value of the node.
-        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: bool) -> GenApiResult<()>,
         /// Returns `true` if the node is readable.
         pub fn is_readable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
         /// Returns `true` if the node is writable.
         pub fn is_writable<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<bool>,
     }
 
+    delegate! {
+        journaled,
+        expect_iboolean_kind,
+        /// Sets the value of the node.
+        pub fn set_value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>, value: bool) -> GenApiResult<()>
+            => |_ctxt| NodeValue::Boolean(value),
+    }
+
+    delegate! {
+        no_vc,
+        expect_iboolean_kind,
+        /// Returns the raw integer value which represents `true`, i.e. `OnValue` of the node.
+        pub fn on_value<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> i64,
+        /// Returns the raw integer value which represents `false`, i.e. `OffValue` of the node.
+        pub fn off_value<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> i64,
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -387,6 +610,46 @@ impl RegisterNode {
         pub fn length<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<i64>,
     }
 
+    /// Reads the register as a typed value honoring the endianness of the register.
+    /// The register length must be same as the size of `V`.
+    pub fn read_as<V, Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> GenApiResult<V>
+    where
+        V: FromBytes,
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.enter2(|ctrl, ns, vc| {
+            let mut device = GenApiDevice::for_node(ctrl, self.0, ns);
+            self.0
+                .expect_iregister_kind(ns)
+                .unwrap()
+                .read_as(&mut device, ns, vc)
+                .map_err(|e| e.with_node(self.0, ns))
+        })
+    }
+
+    /// Writes a typed value to the register honoring the endianness of the register.
+    /// The register length must be same as the size of `V`.
+    pub fn write_as<V, Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        value: &V,
+    ) -> GenApiResult<()>
+    where
+        V: ToBytes,
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        ctxt.enter2(|ctrl, ns, vc| {
+            let mut device = GenApiDevice::for_node(ctrl, self.0, ns);
+            self.0
+                .expect_iregister_kind(ns)
+                .unwrap()
+                .write_as(value, &mut device, ns, vc)
+                .map_err(|e| e.with_node(self.0, ns))
+        })
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -411,6 +674,24 @@ impl CategoryNode {
         })
     }
 
+    /// Returns all nodes in the subtree of the category in depth-first pre-order.
+    ///
+    /// Each node appears only once, and the category itself is never included even if the xml
+    /// has a cyclic category.
+    pub fn features_recursive<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Vec<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        self.0
+            .expect_icategory_kind(ns)
+            .unwrap()
+            .features_recursive(ns)
+            .into_iter()
+            .map(Node)
+            .collect()
+    }
+
     /// Upcast to [`Node`].
     pub fn as_node(self) -> Node {
         Node(self.0)
@@ -502,6 +783,56 @@ impl Node {
         self.0.as_inode_kind(ns).unwrap().name(ns)
     }
 
+    /// Returns the node which `pAlias` of the node points to if exists.
+    ///
+    /// A deprecated feature usually points to the feature replacing it through `pAlias`.
+    pub fn alias<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        let node_base = self.0.as_inode_kind(ns).unwrap().node_base_precise();
+        node_base.p_alias().map(Node)
+    }
+
+    /// Returns the node which `pCastAlias` of the node points to if exists.
+    pub fn cast_alias<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<Node>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let ns = ctxt.node_store();
+        let node_base = self.0.as_inode_kind(ns).unwrap().node_base_precise();
+        node_base.p_cast_alias().map(Node)
+    }
+
+    /// Returns the replacement of the node if the node is deprecated and has `pAlias`, otherwise
+    /// returns the node itself.
+    ///
+    /// A warning is emitted when the node is replaced.
+    pub fn resolve_deprecated<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Node
+    where
+        Ctxt: GenApiCtxt,
+    {
+        if !self.is_deprecated(ctxt) {
+            return self;
+        }
+
+        match self.alias(ctxt) {
+            Some(alias) => {
+                warn!(
+                    "`{}` is deprecated, `{}` is used instead",
+                    self.name(ctxt),
+                    alias.name(ctxt)
+                );
+                alias
+            }
+            None => {
+                warn!("`{}` is deprecated", self.name(ctxt));
+                self
+            }
+        }
+    }
+
     /// Returns display name of the node. This method is mainly for GUI.
     pub fn display_name<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> &str
     where
@@ -532,5 +863,35 @@ impl Node {
         pub fn event_id<Ctlr, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<u64>,
         /// Returns tooltip of the node. This method is mainly for GUI.
         pub fn tooltip<Ctlr, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<&str>,
+        /// Returns URL of the documentation of the node if exists. This method is mainly for GUI.
+        pub fn docu_url<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<&str>,
+    }
+}
+
+/// A runtime agnostic future which resolves after the given duration.
+///
+/// The future wakes itself up on every poll until the deadline passes.
+struct Delay {
+    deadline: Instant,
+}
+
+impl Delay {
+    fn new(duration: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + duration,
+        }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
     }
 }
@@ -0,0 +1,130 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains typed access to `PixelFormat` feature defined in `GenICam SFNC`.
+
+use std::convert::TryFrom;
+
+use super::{EnumEntryNode, EnumerationNode, GenApiCtxt, GenApiError, ParamsCtxt};
+use crate::{payload::PixelFormat, CameleonError, CameleonResult, DeviceControl};
+
+/// Provides access to `PixelFormat` enumeration as [`PixelFormat`].
+///
+/// An entry is mapped by its value, which is the `PFNC` code of the format on devices conforming
+/// to `GenICam SFNC`. Entries whose values aren't `PFNC` codes are mapped by their symbolic names.
+///
+/// # Examples
+/// ```no_run
+/// # use cameleon::u3v;
+/// # let mut cameras = u3v::enumerate_cameras().unwrap();
+/// # let mut camera = cameras.pop().unwrap();
+/// use cameleon::{genapi::PixelFormatNode, payload::PixelFormat};
+///
+/// camera.open().unwrap();
+/// camera.load_context().unwrap();
+///
+/// let mut ctxt = camera.params_ctxt().unwrap();
+/// let node = PixelFormatNode::new(&ctxt).unwrap();
+/// let formats = node.available_formats(&mut ctxt).unwrap();
+/// if formats.contains(&PixelFormat::Mono12p) {
+///     node.set_value(&mut ctxt, PixelFormat::Mono12p).unwrap();
+/// }
+/// println!("{}", node.value(&mut ctxt).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormatNode(EnumerationNode);
+
+impl PixelFormatNode {
+    /// Constructs `PixelFormatNode` from the node in the context.
+    pub fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        Ok(Self(expect_node!(ctxt, "PixelFormat", as_enumeration)))
+    }
+
+    /// Returns the underlying enumeration node.
+    pub fn as_enumeration(self) -> EnumerationNode {
+        self.0
+    }
+
+    /// Returns the current pixel format.
+    ///
+    /// Returns an error if the current entry doesn't correspond to any format defined in `PFNC`.
+    pub fn value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<PixelFormat>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let entry = self.0.current_entry(ctxt)?;
+        to_pixel_format(ctxt, entry).ok_or_else(|| {
+            CameleonError::InvalidGenApiXml(
+                format!("unknown PixelFormat entry: {}", entry.symbolic(ctxt)).into(),
+            )
+        })
+    }
+
+    /// Sets the pixel format.
+    ///
+    /// Returns an error if the device has no entry corresponding to `pixel_format`.
+    pub fn set_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        pixel_format: PixelFormat,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let entry = self
+            .0
+            .entries(ctxt)
+            .into_iter()
+            .find(|entry| to_pixel_format(ctxt, *entry) == Some(pixel_format))
+            .ok_or_else(|| {
+                GenApiError::InvalidData(
+                    format!("PixelFormat has no entry for {}", pixel_format).into(),
+                )
+            })?;
+        let value = entry.value(ctxt);
+        Ok(self.0.set_entry_by_value(ctxt, value)?)
+    }
+
+    /// Returns the pixel formats whose entries are currently available, in the order of the
+    /// entries. Entries which don't correspond to any format defined in `PFNC` are skipped.
+    pub fn available_formats<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    ) -> CameleonResult<Vec<PixelFormat>>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let mut formats = vec![];
+        for entry in self.0.entries(ctxt) {
+            if !entry.is_available(ctxt)? {
+                continue;
+            }
+            if let Some(pixel_format) = to_pixel_format(ctxt, entry) {
+                formats.push(pixel_format);
+            }
+        }
+        Ok(formats)
+    }
+}
+
+/// Maps an entry to [`PixelFormat`] by its value, then by its symbolic name.
+fn to_pixel_format<Ctrl, Ctxt>(
+    ctxt: &ParamsCtxt<Ctrl, Ctxt>,
+    entry: EnumEntryNode,
+) -> Option<PixelFormat>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    u32::try_from(entry.value(ctxt))
+        .ok()
+        .and_then(|code| PixelFormat::try_from(code).ok())
+        .or_else(|| entry.symbolic(ctxt).parse().ok())
+}
@@ -0,0 +1,273 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains an implementation of bulk refresh of streamable nodes.
+
+use std::collections::HashMap;
+
+use cameleon_genapi::{
+    elem_type::{AccessMode, CachingMode},
+    interface::IPortKind,
+    prelude::*,
+    store::NodeData,
+    GenApiError, RegisterBase,
+};
+use tracing::warn;
+
+use super::{GenApiCtxt, GenApiDevice, Node, NodeId, NodeStore, ParamsCtxt};
+use crate::{CameleonError, CameleonResult, DeviceControl};
+
+/// A value of a node read by [`ParamsCtxt::refresh_all`] or written by
+/// [`Transaction::set`](super::Transaction::set).
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeValue {
+    /// A value of `IInteger` node.
+    Integer(i64),
+    /// A value of `IFloat` node.
+    Float(f64),
+    /// A value of `IString` node.
+    String(String),
+    /// Symbolic name of the current entry of `IEnumeration` node.
+    Enumeration(String),
+    /// A value of `IBoolean` node.
+    Boolean(bool),
+}
+
+/// Values read by [`ParamsCtxt::refresh_all`].
+#[derive(Debug, Default)]
+pub struct RefreshReport {
+    /// Map from node name to its value.
+    pub values: HashMap<String, NodeValue>,
+    /// Nodes which failed to be read, paired with the errors. Values of other nodes are still
+    /// available in [`Self::values`].
+    pub failures: Vec<(String, CameleonError)>,
+}
+
+impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    /// Invalidates all cache and re-reads every readable streamable node.
+    ///
+    /// Registers are prefetched with [`DeviceControl::read_batch`] and stored in the cache before
+    /// nodes are evaluated, so the number of round trips is reduced if the context caches values.
+    ///
+    /// A node which fails to be read, e.g. because it's temporarily unavailable, doesn't stop the
+    /// refresh. The failure is recorded in [`RefreshReport::failures`] instead.
+    pub fn refresh_all(&mut self) -> RefreshReport {
+        self.ctxt.clear_cache();
+        self.prefetch_registers();
+
+        let ns = self.node_store();
+        let mut nids = vec![];
+        ns.visit_nodes(|data| {
+            let nid = data.node_base().id();
+            if matches!(nid.as_inode_kind(ns), Some(n) if n.streamable()) {
+                nids.push(nid);
+            }
+        });
+
+        let mut report = RefreshReport {
+            values: HashMap::with_capacity(nids.len()),
+            failures: vec![],
+        };
+        for nid in nids {
+            let name = Node(nid).name(self).to_string();
+            match self.read_node_value(Node(nid)) {
+                Ok(Some(value)) => {
+                    report.values.insert(name, value);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!(?e, node = %name, "failed to refresh node");
+                    report.failures.push((name, e));
+                }
+            }
+        }
+        report
+    }
+
+    /// Reads all cacheable registers on device ports at once and stores them in the cache.
+    /// Failure is not fatal because nodes are read one by one afterwards.
+    fn prefetch_registers(&mut self) {
+        let mut targets = vec![];
+        self.node_store().visit_nodes(|data| {
+            if let Some((nid, reg_base)) = prefetchable_register(data) {
+                targets.push((nid, reg_base.p_port()));
+            }
+        });
+
+        self.enter2(|ctrl, ns, vc| {
+            let mut regs = vec![];
+            {
+                let mut device = GenApiDevice::new(ctrl);
+                for (nid, p_port) in targets {
+                    if !is_device_port(p_port, ns) {
+                        continue;
+                    }
+                    let reg = nid.expect_iregister_kind(ns).unwrap();
+                    let address = reg.address(&mut device, ns, vc);
+                    let length = reg.length(&mut device, ns, vc);
+                    match (address, length) {
+                        (Ok(address), Ok(length)) if address >= 0 && length > 0 => {
+                            regs.push((nid, address, vec![0; length as usize]));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            let mut requests: Vec<_> = regs
+                .iter_mut()
+                .map(|(_, address, buf)| (*address as u64, buf.as_mut_slice()))
+                .collect();
+            if let Err(e) = ctrl.read_batch(&mut requests) {
+                warn!(?e, "failed to prefetch registers");
+                return;
+            }
+
+            for (nid, address, buf) in &regs {
+                vc.cache_data(*nid, *address, buf.len() as i64, buf);
+            }
+        });
+    }
+
+    pub(crate) fn read_node_value(&mut self, node: Node) -> CameleonResult<Option<NodeValue>> {
+        let value = if let Some(node) = node.as_integer(self) {
+            if !node.is_readable(self)? {
+                return Ok(None);
+            }
+            NodeValue::Integer(node.value(self)?)
+        } else if let Some(node) = node.as_float(self) {
+            if !node.is_readable(self)? {
+                return Ok(None);
+            }
+            NodeValue::Float(node.value(self)?)
+        } else if let Some(node) = node.as_enumeration(self) {
+            if !node.is_readable(self)? {
+                return Ok(None);
+            }
+            let entry = node.current_entry(self)?;
+            NodeValue::Enumeration(entry.symbolic(self).to_string())
+        } else if let Some(node) = node.as_boolean(self) {
+            if !node.is_readable(self)? {
+                return Ok(None);
+            }
+            NodeValue::Boolean(node.value(self)?)
+        } else if let Some(node) = node.as_string(self) {
+            if !node.is_readable(self)? {
+                return Ok(None);
+            }
+            NodeValue::String(node.value(self)?)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(value))
+    }
+
+    /// Writes `value` to `node`. Enumerations are set by the symbolic name of the entry, and an
+    /// integer is accepted by `IFloat` nodes.
+    pub(crate) fn write_node_value(&mut self, node: Node, value: NodeValue) -> CameleonResult<()> {
+        match value {
+            NodeValue::Integer(v) if node.as_integer(self).is_some() => {
+                node.as_integer(self).unwrap().set_value(self, v)?;
+            }
+            NodeValue::Integer(v) if node.as_float(self).is_some() => {
+                node.as_float(self).unwrap().set_value(self, v as f64)?;
+            }
+            NodeValue::Float(v) if node.as_float(self).is_some() => {
+                node.as_float(self).unwrap().set_value(self, v)?;
+            }
+            NodeValue::Boolean(v) if node.as_boolean(self).is_some() => {
+                node.as_boolean(self).unwrap().set_value(self, v)?;
+            }
+            NodeValue::String(v) | NodeValue::Enumeration(v) if node.as_string(self).is_some() => {
+                node.as_string(self).unwrap().set_value(self, v)?;
+            }
+            NodeValue::String(v) | NodeValue::Enumeration(v)
+                if node.as_enumeration(self).is_some() =>
+            {
+                node.as_enumeration(self)
+                    .unwrap()
+                    .set_entry_by_symbolic(self, &v)?;
+            }
+            value => {
+                return Err(GenApiError::InvalidData(
+                    format!("{:?} can't be written to {}", value, node.name(self)).into(),
+                )
+                .into())
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<i64> for NodeValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+/// Integer literals default to `i32`, e.g. `tx.set("Width", 640)`.
+impl From<i32> for NodeValue {
+    fn from(value: i32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<f64> for NodeValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<bool> for NodeValue {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<String> for NodeValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for NodeValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+/// Returns the register base of the node if its value may be prefetched.
+///
+/// `Register` nodes are excluded because they are often large blocks with side effects on read,
+/// e.g. `FileAccessBuffer`.
+fn prefetchable_register(data: &NodeData) -> Option<(NodeId, &RegisterBase)> {
+    let reg_base = match data {
+        NodeData::IntReg(n) => n.register_base(),
+        NodeData::MaskedIntReg(n) => n.register_base(),
+        NodeData::FloatReg(n) => n.register_base(),
+        NodeData::StringReg(n) => n.register_base(),
+        _ => return None,
+    };
+
+    if reg_base.cacheable() == CachingMode::NoCache
+        || reg_base.access_mode() == AccessMode::WO
+        || reg_base.polling_time().is_some()
+    {
+        None
+    } else {
+        Some((data.node_base().id(), reg_base))
+    }
+}
+
+fn is_device_port(p_port: NodeId, ns: &impl NodeStore) -> bool {
+    match p_port.as_iport_kind(ns) {
+        Some(IPortKind::Port(port)) => port.chunk_id().is_none(),
+        None => false,
+    }
+}
@@ -0,0 +1,225 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains transactions which write multiple nodes and roll them back on failure.
+
+use cameleon_genapi::GenApiError;
+use tracing::warn;
+
+use super::{GenApiCtxt, Node, NodeValue, ParamsCtxt};
+use crate::{CameleonResult, DeviceControl};
+
+/// A set of writes to nodes which are rolled back if any of them fails.
+///
+/// See [`ParamsCtxt::transaction`].
+#[derive(Debug)]
+pub struct Transaction<'a, Ctrl, Ctxt> {
+    ctxt: &'a mut ParamsCtxt<Ctrl, Ctxt>,
+    /// Written nodes and their values before the writes, in the order of the writes.
+    applied: Vec<(Node, Option<NodeValue>)>,
+}
+
+impl<'a, Ctrl, Ctxt> Transaction<'a, Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    /// Writes `value` to the node named `name`.
+    ///
+    /// Enumerations are set by the symbolic name of the entry, and an integer is accepted by
+    /// `IFloat` nodes.
+    pub fn set(&mut self, name: &str, value: impl Into<NodeValue>) -> CameleonResult<()> {
+        let node = self.ctxt.node(name).ok_or_else(|| {
+            GenApiError::InvalidNode(format!("no node named `{}` is found", name).into())
+        })?;
+        let old_value = self.ctxt.read_node_value(node)?;
+        self.ctxt.write_node_value(node, value.into())?;
+        self.applied.push((node, old_value));
+        Ok(())
+    }
+
+    /// Reads the value of the node named `name`, which reflects the writes applied so far.
+    ///
+    /// Returns `None` if the node isn't readable or doesn't have a value.
+    pub fn get(&mut self, name: &str) -> CameleonResult<Option<NodeValue>> {
+        match self.ctxt.node(name) {
+            Some(node) => self.ctxt.read_node_value(node),
+            None => Err(GenApiError::InvalidNode(
+                format!("no node named `{}` is found", name).into(),
+            )
+            .into()),
+        }
+    }
+
+    /// Returns the context of the transaction. Writes through the returned context are not
+    /// rolled back.
+    pub fn params_ctxt(&mut self) -> &mut ParamsCtxt<Ctrl, Ctxt> {
+        self.ctxt
+    }
+
+    /// Restores the values of the written nodes in the reverse order of the writes.
+    ///
+    /// Failures are logged and don't stop the rollback. Nodes whose value couldn't be read
+    /// before the write, e.g. write-only nodes, are left as they are.
+    fn rollback(self) {
+        for (node, old_value) in self.applied.into_iter().rev() {
+            let name = node.name(self.ctxt).to_string();
+            match old_value {
+                Some(value) => {
+                    if let Err(e) = self.ctxt.write_node_value(node, value) {
+                        warn!(?e, node = %name, "failed to roll back the node");
+                    }
+                }
+                None => warn!(node = %name, "can't roll back the node without its old value"),
+            }
+        }
+    }
+}
+
+impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    /// Runs `f` in a transaction, and rolls back all writes applied by [`Transaction::set`] if
+    /// `f` returns an error.
+    ///
+    /// The values of the written nodes are restored in the reverse order of the writes, then the
+    /// error returned by `f` is returned. Note that the atomicity is best-effort, e.g. side
+    /// effects of a write on other nodes are not rolled back and the rollback itself may fail.
+    pub fn transaction<F, R>(&mut self, f: F) -> CameleonResult<R>
+    where
+        F: FnOnce(&mut Transaction<Ctrl, Ctxt>) -> CameleonResult<R>,
+    {
+        let mut tx = Transaction {
+            ctxt: self,
+            applied: vec![],
+        };
+        match f(&mut tx) {
+            Ok(res) => Ok(res),
+            Err(e) => {
+                tx.rollback();
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        genapi::{DefaultGenApiCtxt, FromXml, WriteJournal},
+        ControlError, ControlResult,
+    };
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Integer Name="Width" NameSpace="Standard">
+                <Value>100</Value>
+                <Min>1</Min>
+                <Max>1000</Max>
+            </Integer>
+
+            <Integer Name="Height" NameSpace="Standard">
+                <Value>100</Value>
+                <Min>1</Min>
+                <Max>500</Max>
+            </Integer>
+        </RegisterDescription>
+        "#;
+
+    /// A control handle without device. All nodes in [`XML`] are evaluated without it.
+    struct NoDevice;
+
+    impl DeviceControl for NoDevice {
+        fn open(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn close(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn is_opened(&self) -> bool {
+            true
+        }
+
+        fn read(&mut self, _: u64, _: &mut [u8]) -> ControlResult<()> {
+            Err(ControlError::NotOpened)
+        }
+
+        fn write(&mut self, _: u64, _: &[u8]) -> ControlResult<()> {
+            Err(ControlError::NotOpened)
+        }
+
+        fn genapi(&mut self) -> ControlResult<String> {
+            Ok(XML.into())
+        }
+
+        fn enable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+
+        fn disable_streaming(&mut self) -> ControlResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut ctxt = ParamsCtxt {
+            ctrl: NoDevice,
+            ctxt: DefaultGenApiCtxt::from_xml(&XML).unwrap(),
+            journal: None,
+        };
+
+        let res = ctxt.transaction(|tx| {
+            tx.set("Width", 640)?;
+            tx.set("Height", 600)
+        });
+        assert!(res.is_err());
+        let width = ctxt.transaction(|tx| tx.get("Width")).unwrap();
+        assert_eq!(width, Some(NodeValue::Integer(100)));
+
+        ctxt.transaction(|tx| {
+            tx.set("Width", 640)?;
+            tx.set("Height", 480)
+        })
+        .unwrap();
+        let size = ctxt
+            .transaction(|tx| Ok((tx.get("Width")?, tx.get("Height")?)))
+            .unwrap();
+        assert_eq!(
+            size,
+            (Some(NodeValue::Integer(640)), Some(NodeValue::Integer(480)))
+        );
+
+        // Writes are journaled by the context.
+        let journal = WriteJournal::new(4);
+        ctxt.journal = Some(journal.clone());
+        let width = ctxt.node("Width").unwrap().as_integer(&ctxt).unwrap();
+        width.set_value(&mut ctxt, 320).unwrap();
+        let entries = journal.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].node, "Width");
+        assert_eq!(entries[0].old_value, Some(NodeValue::Integer(640)));
+        assert_eq!(entries[0].new_value, NodeValue::Integer(320));
+    }
+}
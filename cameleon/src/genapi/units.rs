@@ -0,0 +1,298 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains unit-aware typed access to `IFloat` and `IInteger` nodes based on `uom`.
+
+use uom::si::{
+    f64::{Frequency, Time},
+    frequency::hertz,
+    time::second,
+};
+
+use super::{FloatNode, GenApiCtxt, IntegerNode, ParamsCtxt};
+use crate::{CameleonError, CameleonResult, DeviceControl};
+
+/// A node which has either `IFloat` or `IInteger` interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumericNode {
+    Float(FloatNode),
+    Integer(IntegerNode),
+}
+
+impl NumericNode {
+    fn new<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>, name: &str) -> CameleonResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let node = ctxt
+            .node(name)
+            .ok_or_else(|| CameleonError::InvalidGenApiXml(format!("missing {}", name).into()))?;
+        if let Some(node) = node.as_float(ctxt) {
+            Ok(Self::Float(node))
+        } else if let Some(node) = node.as_integer(ctxt) {
+            Ok(Self::Integer(node))
+        } else {
+            Err(CameleonError::InvalidGenApiXml(
+                format!("{} has neither IFloat nor IInteger interface", name).into(),
+            ))
+        }
+    }
+
+    fn unit<Ctrl, Ctxt>(self, ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> Option<String>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        match self {
+            Self::Float(node) => node.unit(ctxt),
+            Self::Integer(node) => node.unit(ctxt),
+        }
+    }
+
+    fn value<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<f64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        Ok(match self {
+            Self::Float(node) => node.value(ctxt)?,
+            Self::Integer(node) => node.value(ctxt)? as f64,
+        })
+    }
+
+    fn set_value<Ctrl, Ctxt>(
+        self,
+        ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+        value: f64,
+    ) -> CameleonResult<()>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        match self {
+            Self::Float(node) => node.set_value(ctxt, value)?,
+            Self::Integer(node) => node.set_value(ctxt, value.round() as i64)?,
+        }
+        Ok(())
+    }
+
+    fn min<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<f64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        Ok(match self {
+            Self::Float(node) => node.min(ctxt)?,
+            Self::Integer(node) => node.min(ctxt)? as f64,
+        })
+    }
+
+    fn max<Ctrl, Ctxt>(self, ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<f64>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        Ok(match self {
+            Self::Float(node) => node.max(ctxt)?,
+            Self::Integer(node) => node.max(ctxt)? as f64,
+        })
+    }
+}
+
+macro_rules! quantity_node {
+    (
+        $(#[$meta:meta])*
+        $name:ident,
+        $quantity:ident,
+        $base_unit:ident,
+        $scale_fn:ident
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct $name {
+            node: NumericNode,
+            /// Factor to convert the value of the node to the base unit.
+            scale: f64,
+        }
+
+        impl $name {
+            /// Constructs the wrapper of the node named `name`.
+            ///
+            /// `default_unit` is used if the node doesn't have `Unit` element. Returns an error if
+            /// the node doesn't exist, doesn't have `IFloat` nor `IInteger` interface, or its unit
+            /// isn't supported.
+            pub fn new<Ctrl, Ctxt>(
+                ctxt: &ParamsCtxt<Ctrl, Ctxt>,
+                name: &str,
+                default_unit: &str,
+            ) -> CameleonResult<Self>
+            where
+                Ctxt: GenApiCtxt,
+            {
+                let node = NumericNode::new(ctxt, name)?;
+                let unit = node.unit(ctxt);
+                let unit = unit.as_deref().unwrap_or(default_unit);
+                let scale = $scale_fn(unit).ok_or_else(|| {
+                    CameleonError::InvalidGenApiXml(
+                        format!("unsupported unit of {}: {}", name, unit).into(),
+                    )
+                })?;
+                Ok(Self { node, scale })
+            }
+
+            /// Returns the value of the node.
+            pub fn value<Ctrl, Ctxt>(
+                &self,
+                ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+            ) -> CameleonResult<$quantity>
+            where
+                Ctrl: DeviceControl,
+                Ctxt: GenApiCtxt,
+            {
+                Ok(self.to_quantity(self.node.value(ctxt)?))
+            }
+
+            /// Sets the value of the node. The value is rounded if the node has `IInteger`
+            /// interface.
+            pub fn set_value<Ctrl, Ctxt>(
+                &self,
+                ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+                value: $quantity,
+            ) -> CameleonResult<()>
+            where
+                Ctrl: DeviceControl,
+                Ctxt: GenApiCtxt,
+            {
+                self.node
+                    .set_value(ctxt, value.get::<$base_unit>() / self.scale)
+            }
+
+            /// Returns minimum value which the node can take.
+            pub fn min<Ctrl, Ctxt>(
+                &self,
+                ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+            ) -> CameleonResult<$quantity>
+            where
+                Ctrl: DeviceControl,
+                Ctxt: GenApiCtxt,
+            {
+                Ok(self.to_quantity(self.node.min(ctxt)?))
+            }
+
+            /// Returns maximum value which the node can take.
+            pub fn max<Ctrl, Ctxt>(
+                &self,
+                ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+            ) -> CameleonResult<$quantity>
+            where
+                Ctrl: DeviceControl,
+                Ctxt: GenApiCtxt,
+            {
+                Ok(self.to_quantity(self.node.max(ctxt)?))
+            }
+
+            fn to_quantity(self, value: f64) -> $quantity {
+                $quantity::new::<$base_unit>(value * self.scale)
+            }
+        }
+    };
+}
+
+quantity_node! {
+    /// Unit-aware wrapper of a node which represents time, e.g. `ExposureTime`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use cameleon::u3v;
+    /// # let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// # let mut camera = cameras.pop().unwrap();
+    /// use cameleon::genapi::TimeNode;
+    /// use uom::si::{f64::Time, time::millisecond};
+    ///
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let mut ctxt = camera.params_ctxt().unwrap();
+    /// let exposure_time = TimeNode::exposure_time(&ctxt).unwrap();
+    /// exposure_time
+    ///     .set_value(&mut ctxt, Time::new::<millisecond>(10.0))
+    ///     .unwrap();
+    /// println!(
+    ///     "{} ms",
+    ///     exposure_time.value(&mut ctxt).unwrap().get::<millisecond>()
+    /// );
+    /// ```
+    TimeNode,
+    Time,
+    second,
+    time_scale
+}
+
+quantity_node! {
+    /// Unit-aware wrapper of a node which represents frequency, e.g. `AcquisitionFrameRate`.
+    FrequencyNode,
+    Frequency,
+    hertz,
+    frequency_scale
+}
+
+impl TimeNode {
+    /// Constructs the wrapper of `ExposureTime`. Microseconds are assumed if the node doesn't have
+    /// `Unit` element, as defined in `GenICam SFNC`.
+    pub fn exposure_time<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        Self::new(ctxt, "ExposureTime", "us")
+    }
+}
+
+impl FrequencyNode {
+    /// Constructs the wrapper of `AcquisitionFrameRate`. Hertz is assumed if the node doesn't
+    /// have `Unit` element, as defined in `GenICam SFNC`.
+    pub fn acquisition_frame_rate<Ctrl, Ctxt>(ctxt: &ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<Self>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        Self::new(ctxt, "AcquisitionFrameRate", "Hz")
+    }
+}
+
+/// Returns the factor to convert a value in `unit` to seconds.
+fn time_scale(unit: &str) -> Option<f64> {
+    match unit.trim() {
+        "s" | "sec" => Some(1.0),
+        "ms" => Some(1e-3),
+        "us" | "\u{b5}s" | "\u{3bc}s" => Some(1e-6),
+        "ns" => Some(1e-9),
+        _ => None,
+    }
+}
+
+/// Returns the factor to convert a value in `unit` to hertz.
+fn frequency_scale(unit: &str) -> Option<f64> {
+    match unit.trim() {
+        "Hz" | "fps" => Some(1.0),
+        "kHz" => Some(1e3),
+        "MHz" => Some(1e6),
+        "mHz" => Some(1e-3),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_scale() {
+        assert_eq!(time_scale("us"), Some(1e-6));
+        assert_eq!(time_scale("\u{b5}s"), Some(1e-6));
+        assert_eq!(time_scale("ms"), Some(1e-3));
+        assert_eq!(time_scale("min"), None);
+
+        assert_eq!(frequency_scale("Hz"), Some(1.0));
+        assert_eq!(frequency_scale("kHz"), Some(1e3));
+        assert_eq!(frequency_scale("rpm"), None);
+    }
+}
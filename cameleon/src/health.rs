@@ -0,0 +1,548 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains types for device health telemetry.
+//!
+//! [`Camera::health`](crate::Camera::health) samples a [`Health`] snapshot of the camera.
+//! [`HealthMonitor`] compares snapshots against [`HealthThresholds`] and emits a [`HealthAlert`]
+//! once when a value crosses its threshold, and [`HealthAlert::Cleared`] once it recovers.
+//!
+//! The monitor can be driven from the caller's own loop with
+//! [`Camera::poll_health`](crate::Camera::poll_health), or by [`HealthWatcher`] which samples
+//! the camera periodically in a background thread. The watcher implements `Stream`, so alerts
+//! can also be awaited.
+//!
+//! # Examples
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use cameleon::{u3v, Camera};
+//! use cameleon::health::HealthThresholds;
+//! use cameleon::genapi::SharedDefaultGenApiCtxt;
+//! use cameleon::u3v::{SharedControlHandle, StreamHandle};
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! if cameras.is_empty() {
+//!     return;
+//! }
+//!
+//! let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! println!("{:?}", camera.health().unwrap());
+//!
+//! // The watcher requires a sharable camera.
+//! let camera: Camera<SharedControlHandle, StreamHandle, SharedDefaultGenApiCtxt> =
+//!     camera.convert_into();
+//! let thresholds = HealthThresholds {
+//!     max_temperature: Some(60.0),
+//!     ..HealthThresholds::default()
+//! };
+//! let watcher = camera.health_watcher(Duration::from_secs(1), thresholds).unwrap();
+//! while let Ok(alert) = watcher.recv_blocking() {
+//!     println!("{:?}", alert);
+//! }
+//! ```
+
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::mpsc::{self, RecvTimeoutError},
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+use futures_core::Stream;
+use tracing::{error, warn};
+
+use super::{
+    genapi::{EnumerationNode, FloatNode, GenApiCtxt, ParamsCtxt},
+    CameleonResult, DeviceControl, StreamError, StreamResult,
+};
+
+/// Integer nodes that are sampled as link error counters if the device exposes them.
+const LINK_ERROR_COUNTERS: &[&str] = &[
+    "DeviceLinkErrorCount",
+    "DeviceLinkCommandErrorCount",
+    "DeviceLinkStreamErrorCount",
+];
+
+/// A snapshot of the health of the camera.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Health {
+    /// Temperatures of the device in degrees Celsius.
+    pub temperatures: Vec<Temperature>,
+    /// Link error counters exposed by the device, paired with their node names.
+    pub link_error_counters: Vec<(String, i64)>,
+    /// Statistics of the payload stream. `None` if the stream doesn't collect statistics.
+    pub stream: Option<StreamStatistics>,
+}
+
+/// A temperature sampled from `DeviceTemperature`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Temperature {
+    /// Symbolic of `DeviceTemperatureSelector` entry, e.g. `Sensor` or `Mainboard`.
+    /// `None` if the device doesn't have the selector.
+    pub selector: Option<String>,
+    /// Temperature in degrees Celsius.
+    pub value: f64,
+}
+
+/// Statistics of payload stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StreamStatistics {
+    /// The number of payloads successfully delivered to the receiver.
+    pub delivered: u64,
+    /// The number of payloads that failed to be received from the device.
+    pub failed: u64,
+    /// The number of payloads dropped because the receiver was full.
+    pub dropped: u64,
+    /// The number of payloads skipped by [`Decimation`](crate::payload::Decimation).
+    pub decimated: u64,
+    /// The number of violations of the specification found in leaders and trailers, including
+    /// the violations tolerated by the strictness of the stream, e.g.
+    /// [`u3v::Strictness`](crate::u3v::Strictness).
+    pub protocol_violations: u64,
+}
+
+/// Thresholds of [`HealthWatcher`]. `None` disables the corresponding check.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HealthThresholds {
+    /// Maximum temperature in degrees Celsius.
+    pub max_temperature: Option<f64>,
+    /// Maximum value of each link error counter.
+    pub max_link_errors: Option<i64>,
+    /// Maximum number of failed payloads.
+    pub max_stream_failures: Option<u64>,
+}
+
+/// A value monitored by [`HealthMonitor`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HealthSensor {
+    /// A temperature, identified by the symbolic of `DeviceTemperatureSelector` entry.
+    Temperature(Option<String>),
+    /// A link error counter, identified by its node name.
+    LinkErrors(String),
+    /// The number of failed payloads.
+    StreamFailures,
+    /// Sampling of the camera itself.
+    Sampling,
+}
+
+/// An alert emitted from [`HealthMonitor`] and [`HealthWatcher`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthAlert {
+    /// A temperature exceeds [`HealthThresholds::max_temperature`].
+    Temperature(Temperature),
+    /// A link error counter exceeds [`HealthThresholds::max_link_errors`].
+    LinkErrors {
+        /// Node name of the counter.
+        name: String,
+        /// Value of the counter.
+        count: i64,
+    },
+    /// The number of failed payloads exceeds [`HealthThresholds::max_stream_failures`].
+    StreamFailures(u64),
+    /// Failed to sample health of the camera.
+    SamplingFailed(String),
+    /// The sensor which raised an alert is back within its threshold.
+    Cleared(HealthSensor),
+}
+
+impl HealthAlert {
+    /// Returns the sensor which the alert is about.
+    pub fn sensor(&self) -> HealthSensor {
+        match self {
+            Self::Temperature(temp) => HealthSensor::Temperature(temp.selector.clone()),
+            Self::LinkErrors { name, .. } => HealthSensor::LinkErrors(name.clone()),
+            Self::StreamFailures(_) => HealthSensor::StreamFailures,
+            Self::SamplingFailed(_) => HealthSensor::Sampling,
+            Self::Cleared(sensor) => sensor.clone(),
+        }
+    }
+}
+
+impl HealthThresholds {
+    /// Returns alerts for the values in `health` exceeding the thresholds.
+    pub fn check(&self, health: &Health) -> Vec<HealthAlert> {
+        let mut alerts = vec![];
+
+        if let Some(max) = self.max_temperature {
+            alerts.extend(
+                health
+                    .temperatures
+                    .iter()
+                    .filter(|temp| temp.value > max)
+                    .cloned()
+                    .map(HealthAlert::Temperature),
+            );
+        }
+
+        if let Some(max) = self.max_link_errors {
+            alerts.extend(
+                health
+                    .link_error_counters
+                    .iter()
+                    .filter(|(_, count)| *count > max)
+                    .map(|(name, count)| HealthAlert::LinkErrors {
+                        name: name.clone(),
+                        count: *count,
+                    }),
+            );
+        }
+
+        if let (Some(max), Some(stream)) = (self.max_stream_failures, health.stream) {
+            if stream.failed > max {
+                alerts.push(HealthAlert::StreamFailures(stream.failed));
+            }
+        }
+
+        alerts
+    }
+}
+
+/// Turns health samples into debounced alerts.
+///
+/// An alert is emitted only when a sensor crosses its threshold, and [`HealthAlert::Cleared`]
+/// is emitted when the sensor is back within the threshold. While a threshold stays exceeded,
+/// no further alert is emitted for the sensor.
+#[derive(Debug, Clone, Default)]
+pub struct HealthMonitor {
+    thresholds: HealthThresholds,
+    raised: HashSet<HealthSensor>,
+}
+
+impl HealthMonitor {
+    /// Creates a monitor with no alert raised.
+    pub fn new(thresholds: HealthThresholds) -> Self {
+        Self {
+            thresholds,
+            raised: HashSet::new(),
+        }
+    }
+
+    /// Returns the thresholds of the monitor.
+    pub fn thresholds(&self) -> &HealthThresholds {
+        &self.thresholds
+    }
+
+    /// Returns `true` if an alert is currently raised for `sensor`.
+    pub fn is_raised(&self, sensor: &HealthSensor) -> bool {
+        self.raised.contains(sensor)
+    }
+
+    /// Feeds a new sample and returns the alerts which are raised or cleared by it.
+    pub fn update(&mut self, health: &Health) -> Vec<HealthAlert> {
+        self.update_with(self.thresholds.check(health))
+    }
+
+    /// Records a failure of sampling, and returns an alert if it's a new failure.
+    pub fn sampling_failed(&mut self, reason: String) -> Option<HealthAlert> {
+        if self.raised.insert(HealthSensor::Sampling) {
+            Some(HealthAlert::SamplingFailed(reason))
+        } else {
+            None
+        }
+    }
+
+    fn update_with(&mut self, exceeded: Vec<HealthAlert>) -> Vec<HealthAlert> {
+        let mut current = HashSet::with_capacity(exceeded.len());
+        let mut alerts = vec![];
+        for alert in exceeded {
+            let sensor = alert.sensor();
+            if !self.raised.contains(&sensor) {
+                alerts.push(alert);
+            }
+            current.insert(sensor);
+        }
+
+        let mut cleared: Vec<_> = self.raised.difference(&current).cloned().collect();
+        // Make the order of alerts deterministic.
+        cleared.sort_by_key(|sensor| format!("{:?}", sensor));
+        alerts.extend(cleared.into_iter().map(HealthAlert::Cleared));
+
+        self.raised = current;
+        alerts
+    }
+}
+
+/// Samples health of the camera periodically in a background thread.
+///
+/// Samples are fed to [`HealthMonitor`], so an alert is emitted once when a threshold is
+/// crossed and cleared once the value recovers. The thread stops when the watcher is dropped.
+/// Alerts can be received with the methods of the watcher, or through its `Stream`
+/// implementation.
+pub struct HealthWatcher {
+    rx: async_channel::Receiver<HealthAlert>,
+    cancellation_tx: Option<mpsc::SyncSender<()>>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl HealthWatcher {
+    /// Spawns a watcher thread that calls `sampler` every `interval`.
+    pub fn spawn<F>(interval: Duration, thresholds: HealthThresholds, mut sampler: F) -> Self
+    where
+        F: FnMut() -> CameleonResult<Health> + Send + 'static,
+    {
+        let (tx, rx) = async_channel::unbounded();
+        let (cancellation_tx, cancellation_rx) = mpsc::sync_channel(0);
+        let mut monitor = HealthMonitor::new(thresholds);
+
+        let handle = thread::spawn(move || {
+            // Stop the loop when `cancellation_tx` sends signal or is dropped.
+            while let Err(RecvTimeoutError::Timeout) = cancellation_rx.recv_timeout(interval) {
+                let alerts = match sampler() {
+                    Ok(health) => monitor.update(&health),
+                    Err(e) => {
+                        warn!(?e);
+                        monitor.sampling_failed(e.to_string()).into_iter().collect()
+                    }
+                };
+                for alert in alerts {
+                    if tx.try_send(alert).is_err() {
+                        // All receivers are dropped.
+                        return;
+                    }
+                }
+            }
+        });
+
+        Self {
+            rx,
+            cancellation_tx: Some(cancellation_tx),
+            handle: Some(handle),
+        }
+    }
+
+    /// Receives [`HealthAlert`].
+    pub async fn recv(&self) -> StreamResult<HealthAlert> {
+        Ok(self.rx.recv().await?)
+    }
+
+    /// Tries to receive [`HealthAlert`].
+    /// This method doesn't wait arrival of an alert and immediately returns `StreamError` if
+    /// there is no alert.
+    pub fn try_recv(&self) -> StreamResult<HealthAlert> {
+        Ok(self.rx.try_recv()?)
+    }
+
+    /// Receives [`HealthAlert`].
+    /// If there is no alert, this method blocks until the watcher emits an alert.
+    pub fn recv_blocking(&self) -> StreamResult<HealthAlert> {
+        Ok(self.rx.recv_blocking()?)
+    }
+}
+
+/// Yields [`HealthAlert`]s emitted by the watcher. The stream ends when the watcher thread
+/// stops.
+impl Stream for HealthWatcher {
+    type Item = HealthAlert;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rx.size_hint()
+    }
+}
+
+impl Drop for HealthWatcher {
+    fn drop(&mut self) {
+        // Dropping the sender wakes up the watcher thread.
+        self.cancellation_tx.take();
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!(
+                    "{}",
+                    StreamError::Poisoned("a panic has occurred in health watcher".into())
+                );
+            }
+        }
+    }
+}
+
+/// Samples temperatures and link error counters of the device.
+pub(crate) fn sample<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    stream: Option<StreamStatistics>,
+) -> CameleonResult<Health>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let temperatures = sample_temperatures(ctxt)?;
+
+    let mut link_error_counters = vec![];
+    for name in LINK_ERROR_COUNTERS {
+        if let Some(node) = ctxt.node(name).and_then(|n| n.as_integer(ctxt)) {
+            if node.is_readable(ctxt)? {
+                link_error_counters.push(((*name).to_string(), node.value(ctxt)?));
+            }
+        }
+    }
+
+    Ok(Health {
+        temperatures,
+        link_error_counters,
+        stream,
+    })
+}
+
+fn sample_temperatures<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+) -> CameleonResult<Vec<Temperature>>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let temp_node = match ctxt
+        .node("DeviceTemperature")
+        .and_then(|n| n.as_float(ctxt))
+    {
+        Some(node) => node,
+        None => return Ok(vec![]),
+    };
+    let selector = ctxt
+        .node("DeviceTemperatureSelector")
+        .and_then(|n| n.as_enumeration(ctxt));
+
+    // Switching the selector is a write, so only the currently selected temperature is sampled
+    // in read-only mode.
+    let read_only = ctxt.ctxt.is_read_only();
+    let selector = match selector {
+        Some(selector) if !read_only && selector.is_writable(ctxt)? => selector,
+        _ => {
+            if !temp_node.is_readable(ctxt)? {
+                return Ok(vec![]);
+            }
+            let current = match selector {
+                Some(selector) if selector.is_readable(ctxt)? => {
+                    Some(selector.current_entry(ctxt)?.symbolic(ctxt).to_string())
+                }
+                _ => None,
+            };
+            return Ok(vec![Temperature {
+                selector: current,
+                value: temp_node.value(ctxt)?,
+            }]);
+        }
+    };
+
+    // Restore the selector even if sampling fails so that sampling has no side effect.
+    let original = selector.current_entry(ctxt)?;
+    let sampled = sample_each_entry(ctxt, selector, temp_node);
+    let restored = selector.set_entry_by_value(ctxt, original.value(ctxt));
+    let temperatures = sampled?;
+    restored?;
+
+    Ok(temperatures)
+}
+
+/// Samples the temperature of each available entry of `selector`, leaving the last one selected.
+fn sample_each_entry<Ctrl, Ctxt>(
+    ctxt: &mut ParamsCtxt<Ctrl, Ctxt>,
+    selector: EnumerationNode,
+    temp_node: FloatNode,
+) -> CameleonResult<Vec<Temperature>>
+where
+    Ctrl: DeviceControl,
+    Ctxt: GenApiCtxt,
+{
+    let mut temperatures = vec![];
+    for entry in selector.entries(ctxt) {
+        if !entry.is_available(ctxt)? {
+            continue;
+        }
+        selector.set_entry_by_value(ctxt, entry.value(ctxt))?;
+        if temp_node.is_readable(ctxt)? {
+            temperatures.push(Temperature {
+                selector: Some(entry.symbolic(ctxt).to_string()),
+                value: temp_node.value(ctxt)?,
+            });
+        }
+    }
+    Ok(temperatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds() {
+        let health = Health {
+            temperatures: vec![
+                Temperature {
+                    selector: Some("Sensor".into()),
+                    value: 65.0,
+                },
+                Temperature {
+                    selector: Some("Mainboard".into()),
+                    value: 40.0,
+                },
+            ],
+            link_error_counters: vec![("DeviceLinkErrorCount".into(), 3)],
+            stream: Some(StreamStatistics {
+                delivered: 100,
+                failed: 1,
+                dropped: 0,
+                decimated: 0,
+                protocol_violations: 0,
+            }),
+        };
+
+        let thresholds = HealthThresholds {
+            max_temperature: Some(60.0),
+            max_link_errors: Some(0),
+            max_stream_failures: Some(1),
+        };
+        let alerts = thresholds.check(&health);
+        assert_eq!(alerts.len(), 2);
+        assert!(
+            matches!(&alerts[0], HealthAlert::Temperature(temp) if temp.selector.as_deref() == Some("Sensor"))
+        );
+        assert!(matches!(
+            &alerts[1],
+            HealthAlert::LinkErrors { count: 3, .. }
+        ));
+
+        assert!(HealthThresholds::default().check(&health).is_empty());
+    }
+
+    #[test]
+    fn test_monitor_debounce() {
+        let health = |temp: f64| Health {
+            temperatures: vec![Temperature {
+                selector: None,
+                value: temp,
+            }],
+            ..Health::default()
+        };
+        let mut monitor = HealthMonitor::new(HealthThresholds {
+            max_temperature: Some(60.0),
+            ..HealthThresholds::default()
+        });
+
+        assert!(monitor.update(&health(50.0)).is_empty());
+        let alerts = monitor.update(&health(65.0));
+        assert!(matches!(&alerts[..], [HealthAlert::Temperature(_)]));
+        assert!(monitor.update(&health(70.0)).is_empty());
+        assert!(monitor.is_raised(&HealthSensor::Temperature(None)));
+        assert_eq!(
+            monitor.update(&health(55.0)),
+            vec![HealthAlert::Cleared(HealthSensor::Temperature(None))]
+        );
+        assert!(monitor.update(&health(55.0)).is_empty());
+
+        assert!(monitor.sampling_failed("timeout".into()).is_some());
+        assert!(monitor.sampling_failed("timeout".into()).is_none());
+        assert_eq!(
+            monitor.update(&health(55.0)),
+            vec![HealthAlert::Cleared(HealthSensor::Sampling)]
+        );
+    }
+}
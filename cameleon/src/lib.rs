@@ -143,13 +143,40 @@
     clippy::module_name_repetitions
 )]
 
+/// Returns the node with the given name as the given interface, or returns
+/// [`CameleonError::InvalidGenApiXml`] from the enclosing function.
+macro_rules! expect_node {
+    ($ctxt:expr, $name:expr, $as_type:ident) => {{
+        let err_msg = std::concat!("missing ", $name);
+        let err_msg2 = std::concat!($name, " has invalid interface");
+        $ctxt
+            .node($name)
+            .ok_or_else(|| $crate::CameleonError::InvalidGenApiXml(err_msg.into()))?
+            .$as_type($ctxt)
+            .ok_or_else(|| $crate::CameleonError::InvalidGenApiXml(err_msg2.into()))?
+    }};
+}
+
+pub mod callback;
 pub mod camera;
+pub mod cancellation;
+pub mod focus;
 pub mod genapi;
+pub mod health;
+pub mod logging;
 pub mod payload;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod recorder;
+#[cfg(feature = "thread-tuning")]
+pub mod scheduling;
 #[cfg(feature = "libusb")]
 pub mod u3v;
 
-pub use camera::{Camera, CameraInfo, DeviceControl, PayloadStream};
+pub use camera::{
+    Camera, CameraInfo, DeviceControl, ErasedCamera, PayloadStream, Roi, TimestampLatch,
+};
+pub use cancellation::CancellationToken;
 
 use std::{borrow::Cow, num::TryFromIntError};
 
@@ -218,6 +245,39 @@ pub enum ControlError {
     /// e.g. try to write too large data that will overrun register.
     #[error("try to write invalid data to the device: {0}")]
     InvalidData(Box<dyn std::error::Error + Send + Sync>),
+
+    /// The device returned an acknowledge with non-success `GenCP` status.
+    #[error("the device returned status {code:#06x} for request {request_id}")]
+    Status {
+        /// `GenCP` status code. The most significant bit is set if the error is fatal.
+        code: u16,
+        /// Request id of the command.
+        request_id: u16,
+    },
+
+    /// The operation is cancelled by [`CancellationToken`].
+    #[error("operation is cancelled")]
+    Cancelled,
+
+    /// The request id of an acknowledge doesn't match the command.
+    #[error("request id mismatch: expected {expected}, but got {actual}")]
+    RequestIdMismatch {
+        /// Request id of the command.
+        expected: u16,
+        /// Request id of the received acknowledge.
+        actual: u16,
+    },
+
+    /// An acknowledge received from the device violates the protocol.
+    ///
+    /// Reported only when `u3v::AckValidation::Strict` is set.
+    #[error("protocol violation in acknowledge for request {request_id}: {reason}")]
+    ProtocolViolation {
+        /// Request id of the command.
+        request_id: u16,
+        /// Description of the violation.
+        reason: Cow<'static, str>,
+    },
 }
 
 /// A specialized `Result` type for streaming.
@@ -258,6 +318,11 @@ pub enum StreamError {
     #[error("buffer is too small to recieve data")]
     BufferTooSmall,
 
+    /// Payload size has been changed since the streaming started, e.g. by writing to `Width` or
+    /// `PixelFormat`. Restart the streaming to renegotiate the payload size.
+    #[error("payload size has been changed since the streaming started: {0}")]
+    PayloadSizeChanged(Cow<'static, str>),
+
     /// Streaming is already started.
     #[error(
         "streaming is already started. can't use the handle from the outside of streaming loop"
@@ -0,0 +1,148 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains helpers for `tracing` based logging.
+//!
+//! Device operations are recorded in spans carrying `serial`, `model` and `channel` fields, so
+//! logs of multiple cameras can be told apart by any `tracing` subscriber.
+//!
+//! Raw `GenCP` packets are emitted as hex dumps at `TRACE` level with [`GENCP_TARGET`] target.
+//! [`GenCpDumpSubscriber`] is a minimal subscriber which only renders those dumps, which is
+//! useful to debug the communication with a device without setting up a full featured subscriber.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon::{logging, u3v};
+//!
+//! logging::init_gencp_dump().unwrap();
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! if cameras.is_empty() {
+//!     return;
+//! }
+//!
+//! let mut camera = cameras.pop().unwrap();
+//! // Packets sent and received while opening the camera are dumped to stderr.
+//! camera.open().unwrap();
+//! ```
+
+use std::{
+    fmt::{self, Write as _},
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Metadata, Subscriber,
+};
+
+/// Target of the events which contain hex dumps of `GenCP` packets.
+pub const GENCP_TARGET: &str = "cameleon::gencp";
+
+/// Sets [`GenCpDumpSubscriber`] writing to stderr as the global default subscriber.
+pub fn init_gencp_dump() -> Result<(), tracing::subscriber::SetGlobalDefaultError> {
+    tracing::subscriber::set_global_default(GenCpDumpSubscriber::new(io::stderr()))
+}
+
+/// A subscriber which only renders hex dumps of `GenCP` packets. All other events and spans are
+/// disabled.
+pub struct GenCpDumpSubscriber<W> {
+    writer: Mutex<W>,
+    next_span_id: AtomicU64,
+}
+
+impl<W> GenCpDumpSubscriber<W>
+where
+    W: Write + Send + 'static,
+{
+    /// Creates a subscriber which writes dumps to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            next_span_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl<W> Subscriber for GenCpDumpSubscriber<W>
+where
+    W: Write + Send + 'static,
+{
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_event()
+            && metadata.target() == GENCP_TARGET
+            && *metadata.level() == Level::TRACE
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(self.next_span_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = DumpVisitor::default();
+        event.record(&mut visitor);
+
+        if let Ok(mut writer) = self.writer.lock() {
+            writeln!(writer, "{}{}", visitor.fields, visitor.message).ok();
+        }
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[derive(Default)]
+struct DumpVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for DumpVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            write!(self.message, "{:?}", value).ok();
+        } else {
+            write!(self.fields, "{}={:?} ", field.name(), value).ok();
+        }
+    }
+}
+
+/// Renders bytes as lines of 16 bytes prefixed by their offset.
+#[cfg_attr(not(feature = "libusb"), allow(dead_code))]
+pub(crate) struct HexDump<'a>(pub(crate) &'a [u8]);
+
+impl fmt::Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.0.chunks(16).enumerate() {
+            write!(f, "\n{:08x}:", i * 16)?;
+            for byte in line {
+                write!(f, " {:02x}", byte)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_dump() {
+        let bytes: Vec<u8> = (0..18).collect();
+        assert_eq!(
+            HexDump(&bytes).to_string(),
+            "\n00000000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n00000010: 10 11"
+        );
+    }
+}
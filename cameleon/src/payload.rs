@@ -7,11 +7,25 @@
 //! `Payload` is an abstracted container that is mainly used to transfer an image, but also meta data of the image.
 //! See [`Payload`] and [`ImageInfo`] for more details.
 
-pub use cameleon_device::PixelFormat;
+#[cfg(feature = "payload-stats")]
+pub mod stats;
 
-use std::time;
+pub mod correction;
+pub mod line_scan;
+pub mod point_cloud;
+pub mod unpack;
+
+pub use cameleon_device::{BayerPattern, ComponentLayout, PixelFormat};
+
+use std::{
+    convert::TryInto,
+    pin::Pin,
+    task::{Context, Poll},
+    time,
+};
 
 use async_channel::{Receiver, Sender};
+use futures_core::Stream;
 
 use super::{StreamError, StreamResult};
 
@@ -31,8 +45,12 @@ pub enum PayloadType {
 pub struct ImageInfo {
     /// Width of the image.
     pub width: usize,
-    /// Height of the image.
+    /// Height of the image actually delivered.
     pub height: usize,
+    /// Height of the image announced by the device before the transfer. Devices which send
+    /// variable size frames, e.g. line-scan cameras, may end the image early, then `height` is
+    /// smaller than this.
+    pub max_height: usize,
     /// X offset in pixels from the whole image origin. Some devices have capability of
     /// sending multiple extracted image regions, this fields used for the purpose.
     pub x_offset: usize,
@@ -45,6 +63,32 @@ pub struct ImageInfo {
     pub image_size: usize,
 }
 
+impl ImageInfo {
+    /// Returns `true` if the device ended the image before the announced height.
+    pub fn is_partial(&self) -> bool {
+        self.height < self.max_height
+    }
+}
+
+/// Host time at which a payload was received.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HostTimestamp {
+    /// Monotonic clock of the host. Use this to measure elapsed time.
+    pub monotonic: time::Instant,
+    /// Wall clock of the host. Use this to correlate the payload with external events.
+    pub wall_clock: time::SystemTime,
+}
+
+impl HostTimestamp {
+    /// Returns the current time of the host.
+    pub fn now() -> Self {
+        Self {
+            monotonic: time::Instant::now(),
+            wall_clock: time::SystemTime::now(),
+        }
+    }
+}
+
 /// A payload sent from the device.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Payload {
@@ -54,6 +98,11 @@ pub struct Payload {
     pub(crate) payload: Vec<u8>,
     pub(crate) valid_payload_size: usize,
     pub(crate) timestamp: time::Duration,
+    pub(crate) host_timestamp: HostTimestamp,
+    pub(crate) status: PayloadStatus,
+    pub(crate) checksum: Option<u32>,
+    pub(crate) leader: Vec<u8>,
+    pub(crate) trailer: Vec<u8>,
 }
 
 impl Payload {
@@ -92,11 +141,471 @@ impl Payload {
         self.timestamp
     }
 
+    /// Returns the code of the pixel format defined in `PFNC` if the payload contains an image.
+    ///
+    /// This is the raw value of [`ImageInfo::pixel_format`].
+    pub fn pixel_format_code(&self) -> Option<u32> {
+        self.image_info
+            .as_ref()
+            .map(|info| info.pixel_format.into())
+    }
+
+    /// Returns the raw bytes of the leader which is sent before the payload data.
+    ///
+    /// The layout is transport specific. For `U3V`, the bytes can be parsed with
+    /// `cameleon_device::u3v::protocol::stream::Leader` to access the fields that aren't exposed
+    /// by [`Payload`], e.g. `x_padding` of the image.
+    pub fn leader_raw(&self) -> &[u8] {
+        &self.leader
+    }
+
+    /// Returns the raw bytes of the trailer which is sent after the payload data.
+    ///
+    /// The layout is transport specific. For `U3V`, the bytes can be parsed with
+    /// `cameleon_device::u3v::protocol::stream::Trailer`.
+    pub fn trailer_raw(&self) -> &[u8] {
+        &self.trailer
+    }
+
+    /// Time of the host when the transfer of the payload is completed.
+    pub fn host_timestamp(&self) -> HostTimestamp {
+        self.host_timestamp
+    }
+
     /// Returns the payload as `Vec<u8>`.
     pub fn into_vec(mut self) -> Vec<u8> {
         self.payload.resize(self.valid_payload_size, 0);
         self.payload
     }
+
+    /// Returns [`PayloadStatus`] of the payload, which is set by [`IntegrityCheck`].
+    pub fn status(&self) -> PayloadStatus {
+        self.status
+    }
+
+    /// Returns CRC-32 of the payload computed on the host. `None` unless
+    /// [`IntegrityCheck::HostChecksum`] is enabled.
+    ///
+    /// Comparing the checksum with the one computed on the device side, e.g. on a reference
+    /// setup, helps to locate where the data gets corrupted.
+    pub fn checksum(&self) -> Option<u32> {
+        self.checksum
+    }
+
+    /// Verifies the payload according to `check`, and sets the result to the status and the
+    /// checksum of the payload.
+    pub(crate) fn verify_integrity(&mut self, check: IntegrityCheck) {
+        match check {
+            IntegrityCheck::Disabled => {}
+            IntegrityCheck::HostChecksum => self.checksum = Some(crc32(self.payload())),
+            IntegrityCheck::ChunkCrc32 { chunk_id } => {
+                let payload = self.payload();
+                self.status = match find_chunk(payload, chunk_id) {
+                    Some((offset, data)) if data.len() == 4 => {
+                        let expected = u32::from_le_bytes(data.try_into().unwrap());
+                        let computed = crc32(&payload[..offset]);
+                        if expected == computed {
+                            PayloadStatus::Verified
+                        } else {
+                            PayloadStatus::CrcMismatch { expected, computed }
+                        }
+                    }
+                    _ => PayloadStatus::Unverified,
+                };
+            }
+        }
+    }
+}
+
+/// Integrity status of a payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PayloadStatus {
+    /// The payload isn't verified, i.e. [`IntegrityCheck::ChunkCrc32`] is disabled or the payload
+    /// doesn't carry the CRC chunk.
+    #[default]
+    Unverified,
+    /// CRC carried in the payload matches the payload data.
+    Verified,
+    /// CRC carried in the payload doesn't match the payload data, i.e. the data is corrupted.
+    CrcMismatch {
+        /// CRC carried in the payload.
+        expected: u32,
+        /// CRC computed on the host.
+        computed: u32,
+    },
+}
+
+/// Integrity check applied to each payload on receipt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IntegrityCheck {
+    /// No check is applied.
+    #[default]
+    Disabled,
+    /// Verifies CRC-32 carried in the chunk whose id is `chunk_id`, and sets the result to
+    /// [`Payload::status`].
+    ///
+    /// The chunk data must be a 4 bytes little endian CRC-32 (IEEE 802.3) of all the bytes
+    /// preceding the chunk. The chunk id is vendor specific, refer to the `ChunkID` of the CRC
+    /// chunk in `GenApi` xml of the camera.
+    ChunkCrc32 {
+        /// Id of the chunk which carries the CRC.
+        chunk_id: u32,
+    },
+    /// Computes CRC-32 (IEEE 802.3) of the whole payload on the host, see [`Payload::checksum`].
+    ///
+    /// This is meant for debugging data corruption, e.g. on flaky links.
+    HostChecksum,
+}
+
+/// Finds the chunk with `chunk_id` in `payload`, and returns the offset and the data of the chunk.
+///
+/// Chunk data is designed to be decoded from the last byte to the first byte, each chunk is
+/// followed by its id and size.
+fn find_chunk(payload: &[u8], chunk_id: u32) -> Option<(usize, &[u8])> {
+    const CHUNK_ID_LEN: usize = 4;
+    const CHUNK_SIZE_LEN: usize = 4;
+
+    let mut end = payload.len();
+    while end > 0 {
+        let size_offset = end.checked_sub(CHUNK_SIZE_LEN)?;
+        let id_offset = size_offset.checked_sub(CHUNK_ID_LEN)?;
+        let size = u32::from_be_bytes(payload[size_offset..end].try_into().unwrap()) as usize;
+        let id = u32::from_be_bytes(payload[id_offset..size_offset].try_into().unwrap());
+        let offset = id_offset.checked_sub(size)?;
+        if id == chunk_id {
+            return Some((offset, &payload[offset..id_offset]));
+        }
+        end = offset;
+    }
+    None
+}
+
+/// Computes CRC-32 (IEEE 802.3) of `data`.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    const POLY: u32 = 0xEDB8_8320;
+
+    let mut crc = !0_u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (POLY & mask);
+        }
+    }
+    !crc
+}
+
+/// Latency of a payload estimated by [`LatencyEstimator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Latency {
+    /// Delay from the device timestamp to the host receive time.
+    ///
+    /// The device clock and the host clock aren't synchronized, so this is measured relative to
+    /// the fastest transfer observed by the estimator, i.e. the fastest transfer is regarded as
+    /// zero latency.
+    pub transport: time::Duration,
+    /// Delay from the host receive time to the time when the payload is passed to the estimator.
+    pub pipeline: time::Duration,
+}
+
+impl Latency {
+    /// Returns the end-to-end latency of the payload.
+    pub fn total(&self) -> time::Duration {
+        self.transport + self.pipeline
+    }
+}
+
+/// Estimates end-to-end latency of payloads.
+///
+/// Pass each payload to [`Self::update`] at the point of the pipeline where the latency should be
+/// measured, e.g. after the image is processed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use cameleon::u3v;
+/// # use cameleon::payload::LatencyEstimator;
+/// # let mut cameras = u3v::enumerate_cameras().unwrap();
+/// # let mut camera = cameras.pop().unwrap();
+/// # camera.open().unwrap();
+/// # camera.load_context().unwrap();
+/// let payload_rx = camera.start_streaming(3).unwrap();
+/// let mut estimator = LatencyEstimator::new();
+///
+/// for _ in 0..10 {
+///     let payload = payload_rx.recv_blocking().unwrap();
+///     // Process the payload here.
+///     let latency = estimator.update(&payload);
+///     println!("latency: {:?}", latency.total());
+///     payload_rx.send_back(payload);
+/// }
+///
+/// println!("mean latency: {:?}", estimator.mean());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LatencyEstimator {
+    /// Minimum of `host receive time - device timestamp` observed so far.
+    min_offset: Option<i128>,
+    count: u64,
+    total_sum: time::Duration,
+    max: Option<Latency>,
+}
+
+impl LatencyEstimator {
+    /// Creates a new estimator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the estimator with `payload` and returns the estimated latency of it.
+    pub fn update(&mut self, payload: &Payload) -> Latency {
+        let host = payload.host_timestamp();
+        let pipeline = host.monotonic.elapsed();
+
+        // Wall clock is used because `Instant` has no fixed origin.
+        let host_ns = match host.wall_clock.duration_since(time::UNIX_EPOCH) {
+            Ok(d) => d.as_nanos() as i128,
+            Err(e) => -(e.duration().as_nanos() as i128),
+        };
+        let offset = host_ns - payload.timestamp().as_nanos() as i128;
+        let min_offset = match self.min_offset {
+            Some(min_offset) if min_offset <= offset => min_offset,
+            _ => {
+                self.min_offset = Some(offset);
+                offset
+            }
+        };
+        let transport = time::Duration::from_nanos((offset - min_offset) as u64);
+
+        let latency = Latency {
+            transport,
+            pipeline,
+        };
+        self.count += 1;
+        self.total_sum += latency.total();
+        if self.max.is_none_or(|max| max.total() < latency.total()) {
+            self.max = Some(latency);
+        }
+
+        latency
+    }
+
+    /// Returns the mean of end-to-end latencies, or `None` if no payload has been observed.
+    pub fn mean(&self) -> Option<time::Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.total_sum / self.count as u32)
+        }
+    }
+
+    /// Returns the payload latency with the largest end-to-end latency observed so far.
+    pub fn max(&self) -> Option<Latency> {
+        self.max
+    }
+
+    /// Returns the number of payloads observed.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Resets the estimator.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(feature = "image-interop")]
+impl Payload {
+    /// Converts the image in the payload into [`image::DynamicImage`].
+    ///
+    /// Supported pixel formats are
+    /// * `Mono8` and 8 bit `Bayer` formats: converted to `ImageLuma8`. `Bayer` images are not
+    ///   demosaiced.
+    /// * `Mono10`, `Mono12`, `Mono14`, `Mono16` and packed `Mono10p`, `Mono12p`, `Mono10Packed`,
+    ///   `Mono12Packed`: converted to `ImageLuma16`. Pixel values are scaled to 16 bit range.
+    /// * `RGB8`, `BGR8`: converted to `ImageRgb8`.
+    /// * `RGBa8`, `BGRa8`: converted to `ImageRgba8`.
+    /// * `RGB16`: converted to `ImageRgb16`.
+    ///
+    /// Returns `None` if the payload doesn't contain an image, or the pixel format is not
+    /// supported.
+    pub fn to_image(&self) -> Option<image::DynamicImage> {
+        use image::{DynamicImage, ImageBuffer};
+        use PixelFormat::{
+            BGRa8, BayerBG8, BayerGB8, BayerGR8, BayerRG8, Mono10, Mono10Packed, Mono10p, Mono12,
+            Mono12Packed, Mono12p, Mono14, Mono16, Mono8, RGBa8, BGR8, RGB16, RGB8,
+        };
+
+        let info = self.image_info()?;
+        let image = self.image()?;
+        let width = info.width as u32;
+        let height = info.height as u32;
+        let pixel_count = info.width * info.height;
+
+        let le_u16 = |bits: u32| -> Vec<u16> {
+            image
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]) << (16 - bits))
+                .collect()
+        };
+        let swap_rb = |channels: usize| -> Vec<u8> {
+            let mut buf = image.to_vec();
+            buf.chunks_exact_mut(channels).for_each(|px| px.swap(0, 2));
+            buf
+        };
+
+        match info.pixel_format {
+            Mono8 | BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => {
+                ImageBuffer::from_raw(width, height, image.to_vec()).map(DynamicImage::ImageLuma8)
+            }
+            Mono10 => {
+                ImageBuffer::from_raw(width, height, le_u16(10)).map(DynamicImage::ImageLuma16)
+            }
+            Mono12 => {
+                ImageBuffer::from_raw(width, height, le_u16(12)).map(DynamicImage::ImageLuma16)
+            }
+            Mono14 => {
+                ImageBuffer::from_raw(width, height, le_u16(14)).map(DynamicImage::ImageLuma16)
+            }
+            Mono16 => {
+                ImageBuffer::from_raw(width, height, le_u16(16)).map(DynamicImage::ImageLuma16)
+            }
+            Mono10p | Mono12p | Mono10Packed | Mono12Packed => {
+                let packing = unpack::Packing::from_pixel_format(info.pixel_format)?;
+                ImageBuffer::from_raw(width, height, unpack_scaled(image, packing, pixel_count)?)
+                    .map(DynamicImage::ImageLuma16)
+            }
+            RGB8 => {
+                ImageBuffer::from_raw(width, height, image.to_vec()).map(DynamicImage::ImageRgb8)
+            }
+            BGR8 => ImageBuffer::from_raw(width, height, swap_rb(3)).map(DynamicImage::ImageRgb8),
+            RGBa8 => {
+                ImageBuffer::from_raw(width, height, image.to_vec()).map(DynamicImage::ImageRgba8)
+            }
+            BGRa8 => ImageBuffer::from_raw(width, height, swap_rb(4)).map(DynamicImage::ImageRgba8),
+            RGB16 => ImageBuffer::from_raw(width, height, le_u16(16)).map(DynamicImage::ImageRgb16),
+            _ => None,
+        }
+    }
+}
+
+/// Unpacks packed pixels and scales the values to 16 bit range.
+#[cfg(feature = "image-interop")]
+fn unpack_scaled(src: &[u8], packing: unpack::Packing, pixel_count: usize) -> Option<Vec<u16>> {
+    if src.len() < packing.packed_len(pixel_count) {
+        return None;
+    }
+
+    let mut dst = vec![0; pixel_count];
+    unpack::unpack(packing, src, &mut dst);
+    let shift = 16 - packing.bits();
+    dst.iter_mut().for_each(|v| *v <<= shift);
+    Some(dst)
+}
+
+/// A zero-copy view of the image in [`Payload`] as `ndarray`.
+///
+/// The first axis is row, the second axis is column, and the third axis is component if exists.
+#[cfg(feature = "ndarray-interop")]
+#[derive(Debug, Clone)]
+pub enum ImageView<'a> {
+    /// 8 bit single component image, e.g. `Mono8` or `BayerRG8`.
+    Mono8(ndarray::ArrayView2<'a, u8>),
+    /// 16 bit single component image, e.g. `Mono12` or `Mono16`.
+    Mono16(ndarray::ArrayView2<'a, u16>),
+    /// 8 bit multi component image, e.g. `RGB8` or `BGRa8`. Components are kept in the order of
+    /// the pixel format.
+    Color8(ndarray::ArrayView3<'a, u8>),
+    /// 16 bit multi component image, e.g. `RGB16`.
+    Color16(ndarray::ArrayView3<'a, u16>),
+}
+
+#[cfg(feature = "ndarray-interop")]
+impl Payload {
+    /// Returns a zero-copy `ndarray` view of the image in the payload.
+    ///
+    /// If the image has padding at the end of each row, the padding is skipped by the row stride.
+    ///
+    /// Returns `None` if the payload doesn't contain an image, the pixel format is packed or not
+    /// supported, or 16 bit data can't be viewed in place because of the buffer alignment or the
+    /// host endianness.
+    pub fn as_ndarray(&self) -> Option<ImageView<'_>> {
+        use ndarray::{ArrayView, ShapeBuilder};
+        use PixelFormat::{
+            BGRa8, BayerBG8, BayerGB8, BayerGR8, BayerRG8, Mono10, Mono12, Mono14, Mono16, Mono8,
+            RGBa8, BGR8, RGB16, RGB8,
+        };
+
+        let info = self.image_info()?;
+        let image = self.image()?;
+        let (components, is_16bit) = match info.pixel_format {
+            Mono8 | BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => (1, false),
+            Mono10 | Mono12 | Mono14 | Mono16 => (1, true),
+            RGB8 | BGR8 => (3, false),
+            RGBa8 | BGRa8 => (4, false),
+            RGB16 => (3, true),
+            _ => return None,
+        };
+
+        let (height, width) = (info.height, info.width);
+        let elem_size = if is_16bit { 2 } else { 1 };
+        let row_bytes = width * components * elem_size;
+        if height == 0 || row_bytes == 0 {
+            return None;
+        }
+        // Some devices pad each row, in that case `image_size` is larger than the packed size.
+        let stride_bytes = if image.len() / height >= row_bytes {
+            image.len() / height
+        } else {
+            return None;
+        };
+        if stride_bytes % elem_size != 0 {
+            return None;
+        }
+        let row_stride = stride_bytes / elem_size;
+        let len = row_stride * (height - 1) + width * components;
+
+        macro_rules! view {
+            ($data:expr, $variant:ident, 2) => {
+                ArrayView::from_shape((height, width).strides((row_stride, 1)), &$data[..len])
+                    .ok()
+                    .map(ImageView::$variant)
+            };
+            ($data:expr, $variant:ident, 3) => {
+                ArrayView::from_shape(
+                    (height, width, components).strides((row_stride, components, 1)),
+                    &$data[..len],
+                )
+                .ok()
+                .map(ImageView::$variant)
+            };
+        }
+
+        match (components, is_16bit) {
+            (1, false) => view!(image, Mono8, 2),
+            (_, false) => view!(image, Color8, 3),
+            (1, true) => view!(as_u16_slice(image)?, Mono16, 2),
+            (_, true) => view!(as_u16_slice(image)?, Color16, 3),
+        }
+    }
+}
+
+/// Reinterprets little endian bytes as `u16` slice without copy.
+#[cfg(feature = "ndarray-interop")]
+fn as_u16_slice(bytes: &[u8]) -> Option<&[u16]> {
+    if cfg!(target_endian = "big") {
+        return None;
+    }
+
+    // SAFETY: Any bit pattern is valid for `u16`, and `align_to` guarantees the middle slice is
+    // properly aligned.
+    let (prefix, data, suffix) = unsafe { bytes.align_to::<u16>() };
+    if prefix.is_empty() && suffix.is_empty() {
+        Some(data)
+    } else {
+        None
+    }
 }
 
 /// An Receiver of the `Payload` which is sent from a device.
@@ -128,6 +637,24 @@ impl PayloadReceiver {
         self.rx.recv_blocking()?
     }
 
+    /// Receives [`Payload`] sent from the device, waiting at most `timeout`.
+    /// Returns [`StreamError::Timeout`] if no payload arrives in time.
+    pub fn recv_timeout(&self, timeout: time::Duration) -> StreamResult<Payload> {
+        const POLL_INTERVAL: time::Duration = time::Duration::from_millis(1);
+
+        let deadline = time::Instant::now() + timeout;
+        loop {
+            match self.rx.try_recv() {
+                Ok(payload) => return payload,
+                Err(async_channel::TryRecvError::Empty) if time::Instant::now() < deadline => {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                Err(async_channel::TryRecvError::Empty) => return Err(StreamError::Timeout),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
     /// Sends back [`Payload`] to the device to reuse already allocated `payload`.
     ///
     /// Sending back `payload` may improve performance of streaming, but not required to call this
@@ -137,6 +664,123 @@ impl PayloadReceiver {
     }
 }
 
+/// Yields [`Payload`]s sent from the device, so that the receiver can be composed with `Stream`
+/// combinators. The stream ends when the streaming loop is stopped.
+///
+/// # Examples
+/// ```no_run
+/// # async fn run() {
+/// use futures_util::StreamExt;
+///
+/// # let mut cameras = cameleon::u3v::enumerate_cameras().unwrap();
+/// # let mut camera = cameras.pop().unwrap();
+/// camera.open().unwrap();
+/// camera.load_context().unwrap();
+/// let receiver = camera.start_streaming(3).unwrap();
+///
+/// let mut payloads = receiver.take(10);
+/// while let Some(payload) = payloads.next().await {
+///     match payload {
+///         Ok(payload) => println!("{}", payload.id()),
+///         Err(e) => println!("{}", e),
+///     }
+/// }
+/// # }
+/// ```
+impl Stream for PayloadReceiver {
+    type Item = StreamResult<Payload>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rx.size_hint()
+    }
+}
+
+/// Policy applied when a payload arrives while the channel to the host is full, i.e. the
+/// receiver consumes payloads slower than the device produces them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackpressurePolicy {
+    /// Drops the oldest payload in the channel to make room for the new one.
+    DropOldest,
+    /// Drops the new payload.
+    #[default]
+    DropNewest,
+    /// Blocks the streaming loop until the receiver makes room.
+    ///
+    /// No payload is dropped on the host side, but the device may discard frames while the loop
+    /// is blocked because its internal buffer overflows.
+    Block,
+}
+
+/// Decimation of payloads delivered to the host.
+///
+/// Skipped payloads are neither delivered to the receiver nor to the frame callbacks, and their
+/// buffers are reused for the next frame immediately. This keeps acquisition running at the full
+/// rate of the camera while a slow consumer, e.g. a preview, receives only a part of the frames.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Decimation {
+    /// Delivers every payload.
+    #[default]
+    Disabled,
+    /// Delivers every `n`th payload, starting from the first one. `0` is treated as `1`.
+    EveryNth(u32),
+    /// Delivers at most the given number of payloads per second. Every payload is delivered if
+    /// the value isn't a positive finite number.
+    MaxFps(f64),
+}
+
+/// Decides whether each payload is delivered according to [`Decimation`].
+#[derive(Debug, Clone)]
+pub(crate) struct Decimator {
+    decimation: Decimation,
+    /// The number of payloads seen so far.
+    count: u64,
+    /// Payloads arriving before this instant are skipped.
+    next_deadline: Option<time::Instant>,
+}
+
+impl Decimator {
+    pub(crate) fn new(decimation: Decimation) -> Self {
+        Self {
+            decimation,
+            count: 0,
+            next_deadline: None,
+        }
+    }
+
+    /// Returns `true` if the payload arriving at `now` should be delivered.
+    pub(crate) fn accept(&mut self, now: time::Instant) -> bool {
+        match self.decimation {
+            Decimation::Disabled => true,
+
+            Decimation::EveryNth(n) => {
+                let accepted = self.count.is_multiple_of(u64::from(n.max(1)));
+                self.count += 1;
+                accepted
+            }
+
+            Decimation::MaxFps(fps) if fps.is_finite() && fps > 0.0 => {
+                if matches!(self.next_deadline, Some(deadline) if now < deadline) {
+                    return false;
+                }
+                // Schedule from the previous deadline to keep the average rate, but don't let a
+                // long gap accumulate into a burst.
+                let interval = time::Duration::from_secs_f64(1.0 / fps);
+                let next = self
+                    .next_deadline
+                    .map_or(now, |deadline| deadline + interval);
+                self.next_deadline = Some(if next <= now { now + interval } else { next });
+                true
+            }
+
+            Decimation::MaxFps(_) => true,
+        }
+    }
+}
+
 /// A sender of the [`Payload`] which is sent to the host.
 #[derive(Debug, Clone)]
 pub struct PayloadSender {
@@ -144,6 +788,8 @@ pub struct PayloadSender {
     tx: Sender<StreamResult<Payload>>,
     /// Sends back payload to reuse it.
     rx: Receiver<Payload>,
+    /// Receiver of the host side, used to evict the oldest payload.
+    host_rx: Receiver<StreamResult<Payload>>,
 }
 
 impl PayloadSender {
@@ -158,6 +804,50 @@ impl PayloadSender {
         Ok(self.tx.try_send(payload)?)
     }
 
+    /// Sends [`Payload`] to the host, evicting the oldest one if the channel is full.
+    ///
+    /// Returns the evicted payload if any.
+    pub fn force_send(
+        &self,
+        mut payload: StreamResult<Payload>,
+    ) -> StreamResult<Option<StreamResult<Payload>>> {
+        let mut evicted = None;
+        loop {
+            match self.tx.try_send(payload) {
+                Ok(()) => return Ok(evicted),
+                Err(async_channel::TrySendError::Full(returned)) => {
+                    payload = returned;
+                    // The receiver may take a payload in the meantime, then just retry.
+                    if let Ok(oldest) = self.host_rx.try_recv() {
+                        evicted = Some(oldest);
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Tries to send [`Payload`] to the host, returns the payload back if the channel is full.
+    pub(crate) fn try_send_or_return(
+        &self,
+        payload: StreamResult<Payload>,
+    ) -> StreamResult<Option<StreamResult<Payload>>> {
+        match self.tx.try_send(payload) {
+            Ok(()) => Ok(None),
+            // Each sender holds a `host_rx` which keeps the channel open, so check whether any
+            // receiver is left on the host side.
+            Err(async_channel::TrySendError::Full(_))
+                if self.tx.receiver_count() <= self.tx.sender_count() =>
+            {
+                Err(StreamError::SendError(
+                    "payload receiver has been dropped".into(),
+                ))
+            }
+            Err(async_channel::TrySendError::Full(payload)) => Ok(Some(payload)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// Tries to receive [`Payload`].
     /// This method doesn't wait arrival of `payload` and immediately returns `StreamError` if
     /// the channel is empty.
@@ -174,6 +864,7 @@ pub fn channel(payload_cap: usize, buffer_cap: usize) -> (PayloadSender, Payload
         PayloadSender {
             tx: device_tx,
             rx: device_rx,
+            host_rx: host_rx.clone(),
         },
         PayloadReceiver {
             tx: host_tx,
@@ -205,3 +896,283 @@ impl<T> From<async_channel::TrySendError<T>> for StreamError {
         StreamError::ReceiveError(err.to_string().into())
     }
 }
+
+/// Builds [`Payload`]s for tests of the crate.
+#[cfg(test)]
+pub(crate) struct PayloadBuilder(Payload);
+
+#[cfg(test)]
+impl PayloadBuilder {
+    /// Starts with an empty chunk payload.
+    pub(crate) fn new(id: u64) -> Self {
+        Self(Payload {
+            id,
+            payload_type: PayloadType::Chunk,
+            image_info: None,
+            payload: vec![],
+            valid_payload_size: 0,
+            timestamp: time::Duration::default(),
+            host_timestamp: HostTimestamp::now(),
+            status: PayloadStatus::default(),
+            checksum: None,
+            leader: vec![],
+            trailer: vec![],
+        })
+    }
+
+    /// Makes the payload an image whose pixels are `data`.
+    pub(crate) fn image(
+        mut self,
+        width: usize,
+        height: usize,
+        pixel_format: PixelFormat,
+        data: Vec<u8>,
+    ) -> Self {
+        self.0.payload_type = PayloadType::Image;
+        self.0.image_info = Some(ImageInfo {
+            width,
+            height,
+            max_height: height,
+            x_offset: 0,
+            y_offset: 0,
+            pixel_format,
+            image_size: data.len(),
+        });
+        self.data(data)
+    }
+
+    /// Sets the whole payload as valid data.
+    pub(crate) fn data(mut self, data: Vec<u8>) -> Self {
+        self.0.valid_payload_size = data.len();
+        self.0.payload = data;
+        self
+    }
+
+    pub(crate) fn timestamp(mut self, timestamp: time::Duration) -> Self {
+        self.0.timestamp = timestamp;
+        self
+    }
+
+    pub(crate) fn host_timestamp(mut self, host_timestamp: HostTimestamp) -> Self {
+        self.0.host_timestamp = host_timestamp;
+        self
+    }
+
+    pub(crate) fn build(self) -> Payload {
+        self.0
+    }
+}
+
+/// Shorthand of [`PayloadBuilder`] for an image payload with id `0`.
+#[cfg(test)]
+pub(crate) fn image_payload(
+    width: usize,
+    height: usize,
+    pixel_format: PixelFormat,
+    data: Vec<u8>,
+) -> Payload {
+    PayloadBuilder::new(0)
+        .image(width, height, pixel_format, data)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_estimator() {
+        let host_timestamp = HostTimestamp::now();
+        let payload = |device_ns: u64, host_delay_ns: u64| {
+            PayloadBuilder::new(0)
+                .timestamp(time::Duration::from_nanos(device_ns))
+                .host_timestamp(HostTimestamp {
+                    wall_clock: host_timestamp.wall_clock
+                        + time::Duration::from_nanos(host_delay_ns),
+                    ..host_timestamp
+                })
+                .build()
+        };
+
+        let mut estimator = LatencyEstimator::new();
+        assert!(estimator.mean().is_none());
+
+        // Device clock runs 1000ns behind the host clock.
+        let latency = estimator.update(&payload(1000, 2000));
+        assert_eq!(latency.transport, time::Duration::ZERO);
+        let latency = estimator.update(&payload(2000, 3500));
+        assert_eq!(latency.transport, time::Duration::from_nanos(500));
+        // Faster transfer is regarded as the new baseline.
+        let latency = estimator.update(&payload(3000, 3800));
+        assert_eq!(latency.transport, time::Duration::ZERO);
+
+        assert_eq!(estimator.count(), 3);
+        assert!(estimator.mean().is_some());
+        assert!(estimator.max().is_some());
+
+        estimator.reset();
+        assert_eq!(estimator.count(), 0);
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_verify_integrity() {
+        const CRC_CHUNK_ID: u32 = 0x1234;
+
+        let data = [1_u8, 2, 3, 4, 5, 6, 7, 8];
+        let mut buf = data.to_vec();
+        // Image chunk.
+        buf.extend_from_slice(&1_u32.to_be_bytes());
+        buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        // CRC chunk.
+        buf.extend_from_slice(&crc32(&buf).to_le_bytes());
+        buf.extend_from_slice(&CRC_CHUNK_ID.to_be_bytes());
+        buf.extend_from_slice(&4_u32.to_be_bytes());
+
+        let mut payload = PayloadBuilder::new(0).data(buf).build();
+
+        payload.verify_integrity(IntegrityCheck::ChunkCrc32 {
+            chunk_id: CRC_CHUNK_ID,
+        });
+        assert_eq!(payload.status(), PayloadStatus::Verified);
+
+        payload.verify_integrity(IntegrityCheck::ChunkCrc32 { chunk_id: 0xffff });
+        assert_eq!(payload.status(), PayloadStatus::Unverified);
+
+        payload.payload[0] = 0xff;
+        payload.verify_integrity(IntegrityCheck::ChunkCrc32 {
+            chunk_id: CRC_CHUNK_ID,
+        });
+        assert!(matches!(
+            payload.status(),
+            PayloadStatus::CrcMismatch { .. }
+        ));
+
+        payload.verify_integrity(IntegrityCheck::HostChecksum);
+        assert_eq!(payload.checksum(), Some(crc32(payload.payload())));
+    }
+
+    #[test]
+    fn test_force_send() {
+        let payload = |id: u64| PayloadBuilder::new(id).build();
+
+        let (sender, receiver) = channel(2, 2);
+        assert!(sender.force_send(Ok(payload(0))).unwrap().is_none());
+        assert!(sender.force_send(Ok(payload(1))).unwrap().is_none());
+        let evicted = sender.force_send(Ok(payload(2))).unwrap().unwrap();
+        assert_eq!(evicted.unwrap().id, 0);
+
+        assert_eq!(receiver.try_recv().unwrap().id, 1);
+        assert_eq!(receiver.try_recv().unwrap().id, 2);
+
+        // The payload is handed back when the channel is full.
+        sender.try_send(Ok(payload(3))).unwrap();
+        sender.try_send(Ok(payload(4))).unwrap();
+        let returned = sender.try_send_or_return(Ok(payload(5))).unwrap().unwrap();
+        assert_eq!(returned.unwrap().id, 5);
+
+        drop(receiver);
+        assert!(sender.try_send_or_return(Ok(payload(6))).is_err());
+    }
+
+    #[test]
+    fn test_decimator() {
+        let now = time::Instant::now();
+        let mut decimator = Decimator::new(Decimation::EveryNth(3));
+        let accepted: Vec<_> = (0..7).map(|_| decimator.accept(now)).collect();
+        assert_eq!(accepted, [true, false, false, true, false, false, true]);
+
+        // 10 fps from 40 fps.
+        let mut decimator = Decimator::new(Decimation::MaxFps(10.0));
+        let accepted = (0..40)
+            .filter(|i| decimator.accept(now + time::Duration::from_millis(25 * i)))
+            .count();
+        assert_eq!(accepted, 10);
+
+        let mut decimator = Decimator::new(Decimation::MaxFps(0.0));
+        assert!(decimator.accept(now) && decimator.accept(now));
+    }
+
+    #[test]
+    fn test_receiver_stream() {
+        use std::task::{RawWaker, RawWakerVTable, Waker};
+
+        fn noop_raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                noop_raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(std::ptr::null(), &VTABLE)
+        }
+        let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+        let mut cx = Context::from_waker(&waker);
+
+        let (sender, mut receiver) = channel(2, 2);
+        assert!(Pin::new(&mut receiver).poll_next(&mut cx).is_pending());
+
+        sender.try_send(Err(StreamError::Timeout)).unwrap();
+        match Pin::new(&mut receiver).poll_next(&mut cx) {
+            Poll::Ready(Some(Err(StreamError::Timeout))) => {}
+            _ => panic!("expected a timeout error"),
+        }
+
+        // The stream ends once the sender is dropped.
+        drop(sender);
+        assert!(matches!(
+            Pin::new(&mut receiver).poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+
+    #[cfg(feature = "ndarray-interop")]
+    #[test]
+    fn test_as_ndarray() {
+        // 3x2 `Mono8` image with 1 byte padding for each row.
+        let payload = image_payload(3, 2, PixelFormat::Mono8, vec![0, 1, 2, 0xff, 3, 4, 5, 0xff]);
+        let view = match payload.as_ndarray().unwrap() {
+            ImageView::Mono8(view) => view,
+            other => panic!("unexpected view: {:?}", other),
+        };
+        assert_eq!(view.shape(), &[2, 3]);
+        assert_eq!(view[[1, 0]], 3);
+        assert_eq!(view[[1, 2]], 5);
+
+        // 2x1 `RGB8` image.
+        let payload = image_payload(2, 1, PixelFormat::RGB8, vec![1, 2, 3, 4, 5, 6]);
+        let view = match payload.as_ndarray().unwrap() {
+            ImageView::Color8(view) => view,
+            other => panic!("unexpected view: {:?}", other),
+        };
+        assert_eq!(view.shape(), &[1, 2, 3]);
+        assert_eq!(view[[0, 1, 2]], 6);
+
+        // Packed format can't be viewed.
+        let payload = image_payload(2, 1, PixelFormat::Mono12p, vec![0, 0, 0]);
+        assert!(payload.as_ndarray().is_none());
+    }
+
+    #[cfg(feature = "image-interop")]
+    #[test]
+    fn test_unpack() {
+        // Mono12p: 0x321, 0x654.
+        let unpacked = unpack_scaled(&[0x21, 0x43, 0x65], unpack::Packing::Lsb12, 2).unwrap();
+        assert_eq!(unpacked, &[0x321 << 4, 0x654 << 4]);
+
+        // Mono10p: 0x3ff, 0x001, 0x200, 0x155.
+        let unpacked =
+            unpack_scaled(&[0xff, 0x07, 0x00, 0x60, 0x55], unpack::Packing::Lsb10, 4).unwrap();
+        assert_eq!(unpacked, &[0x3ff << 6, 0x001 << 6, 0x200 << 6, 0x155 << 6]);
+
+        // Mono12Packed: 0xab1, 0xcd2.
+        let unpacked = unpack_scaled(&[0xab, 0x21, 0xcd], unpack::Packing::Legacy12, 2).unwrap();
+        assert_eq!(unpacked, &[0xab1 << 4, 0xcd2 << 4]);
+
+        assert!(unpack_scaled(&[0x21, 0x43], unpack::Packing::Lsb12, 2).is_none());
+    }
+}
@@ -0,0 +1,513 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains dark-frame and flat-field correction of images in payloads.
+//!
+//! [`Calibration`] holds a dark frame subtracted from each pixel and a gain map multiplied to
+//! each pixel afterwards. It's applied in place to raw sensor images, so it must be applied before
+//! the image is converted to another format, e.g. by `Payload::to_image` or demosaicing.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon::payload::correction::Calibration;
+//!
+//! # let mut cameras = cameleon::u3v::enumerate_cameras().unwrap();
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//!
+//! // Capture frames with the lens capped, then frames of a uniformly lit target.
+//! let dark: Vec<_> = (0..8).map(|_| payload_rx.recv_blocking().unwrap()).collect();
+//! let flat: Vec<_> = (0..8).map(|_| payload_rx.recv_blocking().unwrap()).collect();
+//! let calibration = Calibration::from_frames(&dark, &flat).unwrap();
+//! calibration.save("calibration.bin").unwrap();
+//!
+//! let mut payload = payload_rx.recv_blocking().unwrap();
+//! calibration.apply(&mut payload).unwrap();
+//! ```
+
+use std::{
+    borrow::Cow,
+    convert::TryInto,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use super::{Payload, PixelFormat};
+
+/// Magic bytes at the head of serialized [`Calibration`].
+const MAGIC: &[u8; 8] = b"CMLCAL01";
+
+/// An error of [`Calibration`].
+#[derive(Debug, thiserror::Error)]
+pub enum CalibrationError {
+    /// The payload doesn't contain an image.
+    #[error("the payload doesn't contain an image")]
+    NoImage,
+
+    /// The pixel format of the image isn't supported.
+    #[error("unsupported pixel format: {0:?}")]
+    UnsupportedPixelFormat(PixelFormat),
+
+    /// The size of the image doesn't match the calibration.
+    #[error("image size mismatch: expected {expected:?}, but got {actual:?}")]
+    SizeMismatch {
+        /// Width and height of the calibration.
+        expected: (usize, usize),
+        /// Width and height of the image.
+        actual: (usize, usize),
+    },
+
+    /// Calibration data is malformed.
+    #[error("invalid calibration data: {0}")]
+    InvalidData(Cow<'static, str>),
+
+    /// Failed to read or write calibration data.
+    #[error("i/o error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A Result type of [`Calibration`].
+pub type CalibrationResult<T> = std::result::Result<T, CalibrationError>;
+
+/// Calibration data of dark-frame and flat-field correction.
+///
+/// Each pixel is corrected as `(value - dark) * gain`, rounded and clamped to the range of the
+/// pixel format. Both maps are in row-major order with `width * height` elements, and are
+/// identities if not set.
+///
+/// Supported pixel formats are `Mono8`, `Mono10`, `Mono12`, `Mono14`, `Mono16` and unpacked
+/// `Bayer` formats of the same bit depths. `Bayer` images are corrected before demosaicing, so
+/// the maps are applied to each color site as is.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    width: usize,
+    height: usize,
+    dark: Option<Vec<f32>>,
+    gain: Option<Vec<f32>>,
+}
+
+impl Calibration {
+    /// Constructs a calibration of the image size which doesn't change any pixel.
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            dark: None,
+            gain: None,
+        }
+    }
+
+    /// Sets the dark frame in pixel values.
+    ///
+    /// Returns an error if the length of `dark` isn't `width * height`.
+    pub fn with_dark_frame(mut self, dark: Vec<f32>) -> CalibrationResult<Self> {
+        self.check_len(&dark)?;
+        self.dark = Some(dark);
+        Ok(self)
+    }
+
+    /// Sets the gain map.
+    ///
+    /// Returns an error if the length of `gain` isn't `width * height`.
+    pub fn with_gain_map(mut self, gain: Vec<f32>) -> CalibrationResult<Self> {
+        self.check_len(&gain)?;
+        self.gain = Some(gain);
+        Ok(self)
+    }
+
+    /// Computes a calibration from frames captured with no light and frames of a uniformly lit
+    /// target.
+    ///
+    /// The dark frame is the mean of `dark_frames`. The gain map equalizes the mean of
+    /// `flat_frames` after dark subtraction to its average over the image. Pixels which don't
+    /// respond to light are left as is. Either of the slices may be empty to leave the
+    /// corresponding map unset.
+    ///
+    /// Returns an error if both slices are empty, or the frames differ in size.
+    pub fn from_frames(
+        dark_frames: &[Payload],
+        flat_frames: &[Payload],
+    ) -> CalibrationResult<Self> {
+        let first = dark_frames
+            .first()
+            .or_else(|| flat_frames.first())
+            .ok_or_else(|| CalibrationError::InvalidData("no frame is given".into()))?;
+        let layout = Layout::new(first)?;
+        let calibration = Self::new(layout.width, layout.height);
+
+        let dark = if dark_frames.is_empty() {
+            None
+        } else {
+            Some(calibration.mean_frame(dark_frames)?)
+        };
+
+        let gain = if flat_frames.is_empty() {
+            None
+        } else {
+            let mut flat = calibration.mean_frame(flat_frames)?;
+            if let Some(dark) = &dark {
+                for (f, d) in flat.iter_mut().zip(dark) {
+                    *f -= d;
+                }
+            }
+
+            let (sum, count) = flat
+                .iter()
+                .filter(|v| **v > 0.0)
+                .fold((0.0_f64, 0_usize), |(sum, count), v| {
+                    (sum + f64::from(*v), count + 1)
+                });
+            if count == 0 {
+                return Err(CalibrationError::InvalidData(
+                    "flat frames contain no signal".into(),
+                ));
+            }
+            let target = (sum / count as f64) as f32;
+            Some(
+                flat.into_iter()
+                    .map(|v| if v > 0.0 { target / v } else { 1.0 })
+                    .collect(),
+            )
+        };
+
+        Ok(Self {
+            dark,
+            gain,
+            ..calibration
+        })
+    }
+
+    /// Returns the width of the image the calibration is applied to.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the image the calibration is applied to.
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the dark frame if set.
+    #[must_use]
+    pub fn dark_frame(&self) -> Option<&[f32]> {
+        self.dark.as_deref()
+    }
+
+    /// Returns the gain map if set.
+    #[must_use]
+    pub fn gain_map(&self) -> Option<&[f32]> {
+        self.gain.as_deref()
+    }
+
+    /// Corrects the image in the payload in place.
+    ///
+    /// Returns an error if the image size doesn't match the calibration, or the pixel format
+    /// isn't supported.
+    pub fn apply(&self, payload: &mut Payload) -> CalibrationResult<()> {
+        let layout = self.check_layout(payload)?;
+        for map in self.dark.iter().chain(&self.gain) {
+            self.check_len(map)?;
+        }
+        if self.dark.is_none() && self.gain.is_none() {
+            return Ok(());
+        }
+
+        let image_size = payload.image_info.as_ref().unwrap().image_size;
+        let image = &mut payload.payload[..image_size];
+        for (y, row) in image
+            .chunks_mut(layout.stride)
+            .take(layout.height)
+            .enumerate()
+        {
+            for x in 0..layout.width {
+                let i = y * layout.width + x;
+                let mut value = layout.read(row, x);
+                if let Some(dark) = &self.dark {
+                    value -= dark[i];
+                }
+                if let Some(gain) = &self.gain {
+                    value *= gain[i];
+                }
+                layout.write(row, x, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the calibration in a binary format which [`Self::read_from`] reads.
+    ///
+    /// The format is `CMLCAL01` magic, width and height as `u32`, a flag byte whose bit 0 and 1
+    /// indicate presence of the dark frame and the gain map, then the maps as `f32`. All values
+    /// are little endian.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> CalibrationResult<()> {
+        let width = to_u32(self.width)?;
+        let height = to_u32(self.height)?;
+        let flags = u8::from(self.dark.is_some()) | u8::from(self.gain.is_some()) << 1;
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        writer.write_all(&[flags])?;
+
+        let mut buf = Vec::with_capacity(self.width * self.height * 4);
+        for map in self.dark.iter().chain(&self.gain) {
+            self.check_len(map)?;
+            buf.clear();
+            for value in map {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            writer.write_all(&buf)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a calibration written by [`Self::write_to`].
+    pub fn read_from<R: Read>(mut reader: R) -> CalibrationResult<Self> {
+        let mut header = [0; 17];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(CalibrationError::InvalidData("unknown magic".into()));
+        }
+        let width = u32::from_le_bytes([header[8], header[9], header[10], header[11]]) as usize;
+        let height = u32::from_le_bytes([header[12], header[13], header[14], header[15]]) as usize;
+        let flags = header[16];
+        if flags & !0b11 != 0 {
+            return Err(CalibrationError::InvalidData("unknown flags".into()));
+        }
+
+        let mut read_map = |present: bool| -> CalibrationResult<Option<Vec<f32>>> {
+            if !present {
+                return Ok(None);
+            }
+            let mut buf = vec![0; width * height * 4];
+            reader.read_exact(&mut buf)?;
+            Ok(Some(
+                buf.chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect(),
+            ))
+        };
+        let dark = read_map(flags & 0b01 != 0)?;
+        let gain = read_map(flags & 0b10 != 0)?;
+
+        Ok(Self {
+            width,
+            height,
+            dark,
+            gain,
+        })
+    }
+
+    /// Saves the calibration to the file at `path`. See [`Self::write_to`] for the format.
+    pub fn save(&self, path: impl AsRef<Path>) -> CalibrationResult<()> {
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads a calibration saved by [`Self::save`].
+    pub fn load(path: impl AsRef<Path>) -> CalibrationResult<Self> {
+        Self::read_from(io::BufReader::new(fs::File::open(path)?))
+    }
+
+    fn check_len(&self, map: &[f32]) -> CalibrationResult<()> {
+        if map.len() == self.width * self.height {
+            Ok(())
+        } else {
+            Err(CalibrationError::InvalidData(
+                format!(
+                    "map length is {}, but {}x{} image is expected",
+                    map.len(),
+                    self.width,
+                    self.height
+                )
+                .into(),
+            ))
+        }
+    }
+
+    fn check_layout(&self, payload: &Payload) -> CalibrationResult<Layout> {
+        let layout = Layout::new(payload)?;
+        if (layout.width, layout.height) == (self.width, self.height) {
+            Ok(layout)
+        } else {
+            Err(CalibrationError::SizeMismatch {
+                expected: (self.width, self.height),
+                actual: (layout.width, layout.height),
+            })
+        }
+    }
+
+    /// Returns the mean of pixel values of `frames`.
+    fn mean_frame(&self, frames: &[Payload]) -> CalibrationResult<Vec<f32>> {
+        let mut sum = vec![0.0_f64; self.width * self.height];
+        for frame in frames {
+            let layout = self.check_layout(frame)?;
+            let image = frame.image().unwrap();
+            for (y, row) in image.chunks(layout.stride).take(layout.height).enumerate() {
+                for x in 0..layout.width {
+                    sum[y * layout.width + x] += f64::from(layout.read(row, x));
+                }
+            }
+        }
+        let count = frames.len() as f64;
+        Ok(sum.into_iter().map(|v| (v / count) as f32).collect())
+    }
+}
+
+fn to_u32(size: usize) -> CalibrationResult<u32> {
+    size.try_into()
+        .map_err(|_| CalibrationError::InvalidData("image size is too large".into()))
+}
+
+/// Memory layout of a single component image.
+struct Layout {
+    width: usize,
+    height: usize,
+    stride: usize,
+    elem_size: usize,
+    max: f32,
+}
+
+impl Layout {
+    fn new(payload: &Payload) -> CalibrationResult<Self> {
+        use PixelFormat::{
+            BayerBG10, BayerBG12, BayerBG16, BayerBG8, BayerGB10, BayerGB12, BayerGB16, BayerGB8,
+            BayerGR10, BayerGR12, BayerGR16, BayerGR8, BayerRG10, BayerRG12, BayerRG16, BayerRG8,
+            Mono10, Mono12, Mono14, Mono16, Mono8,
+        };
+
+        let info = payload.image_info().ok_or(CalibrationError::NoImage)?;
+        let image = payload.image().ok_or(CalibrationError::NoImage)?;
+        let bits = match info.pixel_format {
+            Mono8 | BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => 8,
+            Mono10 | BayerRG10 | BayerGR10 | BayerGB10 | BayerBG10 => 10,
+            Mono12 | BayerRG12 | BayerGR12 | BayerGB12 | BayerBG12 => 12,
+            Mono14 => 14,
+            Mono16 | BayerRG16 | BayerGR16 | BayerGB16 | BayerBG16 => 16,
+            other => return Err(CalibrationError::UnsupportedPixelFormat(other)),
+        };
+
+        let elem_size = if bits > 8 { 2 } else { 1 };
+        let row_bytes = info.width * elem_size;
+        // Some devices pad each row, in that case `image_size` is larger than the packed size.
+        let stride = image.len().checked_div(info.height).unwrap_or(row_bytes);
+        if stride < row_bytes {
+            return Err(CalibrationError::InvalidData(
+                "image is smaller than its size".into(),
+            ));
+        }
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            stride,
+            elem_size,
+            max: ((1_u32 << bits) - 1) as f32,
+        })
+    }
+
+    fn read(&self, row: &[u8], x: usize) -> f32 {
+        if self.elem_size == 1 {
+            f32::from(row[x])
+        } else {
+            f32::from(u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]))
+        }
+    }
+
+    fn write(&self, row: &mut [u8], x: usize, value: f32) {
+        let value = value.round().clamp(0.0, self.max);
+        if self.elem_size == 1 {
+            row[x] = value as u8;
+        } else {
+            row[x * 2..x * 2 + 2].copy_from_slice(&(value as u16).to_le_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::image_payload;
+
+    #[test]
+    fn test_apply() {
+        let calibration = Calibration::new(2, 2)
+            .with_dark_frame(vec![10.0, 0.0, 20.0, 5.0])
+            .unwrap()
+            .with_gain_map(vec![1.0, 2.0, 0.5, 1.0])
+            .unwrap();
+
+        let mut payload = image_payload(2, 2, PixelFormat::Mono8, vec![15, 200, 10, 100]);
+        calibration.apply(&mut payload).unwrap();
+        // The third pixel is clamped to zero, and the second one is saturated.
+        assert_eq!(payload.image().unwrap(), &[5, 255, 0, 95]);
+
+        let mut payload = image_payload(
+            2,
+            2,
+            PixelFormat::Mono12,
+            [110_u16, 300, 420, 4095]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+        );
+        calibration.apply(&mut payload).unwrap();
+        let image: Vec<_> = payload
+            .image()
+            .unwrap()
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(image, vec![100, 600, 200, 4090]);
+
+        let mut payload = image_payload(1, 4, PixelFormat::Mono8, vec![0; 4]);
+        assert!(matches!(
+            calibration.apply(&mut payload),
+            Err(CalibrationError::SizeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_frames() {
+        let dark = [
+            image_payload(2, 1, PixelFormat::Mono8, vec![10, 20]),
+            image_payload(2, 1, PixelFormat::Mono8, vec![12, 20]),
+        ];
+        let flat = [image_payload(2, 1, PixelFormat::Mono8, vec![111, 170])];
+
+        let calibration = Calibration::from_frames(&dark, &flat).unwrap();
+        assert_eq!(calibration.dark_frame(), Some(&[11.0, 20.0][..]));
+        assert_eq!(calibration.gain_map(), Some(&[1.25, 125.0 / 150.0][..]));
+
+        let mut payload = flat[0].clone();
+        calibration.apply(&mut payload).unwrap();
+        assert_eq!(payload.image().unwrap(), &[125, 125]);
+
+        assert!(Calibration::from_frames(&[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let calibration = Calibration::new(3, 1)
+            .with_gain_map(vec![1.0, 0.5, 2.0])
+            .unwrap();
+        let mut buf = vec![];
+        calibration.write_to(&mut buf).unwrap();
+        assert_eq!(buf.len(), 17 + 3 * 4);
+        assert_eq!(Calibration::read_from(&buf[..]).unwrap(), calibration);
+
+        buf[0] = b'X';
+        assert!(Calibration::read_from(&buf[..]).is_err());
+    }
+}
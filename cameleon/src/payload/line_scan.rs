@@ -0,0 +1,236 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains assembly of images from blocks of lines sent by line-scan cameras.
+//!
+//! A line-scan camera sends an image as a sequence of payloads, each of which contains a block
+//! of lines. [`LineScanAssembler`] concatenates the blocks into images of a fixed height.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon::payload::line_scan::LineScanAssembler;
+//!
+//! # let mut cameras = cameleon::u3v::enumerate_cameras().unwrap();
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//!
+//! let mut assembler = LineScanAssembler::new(1024);
+//! for _ in 0..100 {
+//!     let block = payload_rx.recv_blocking().unwrap();
+//!     for image in assembler.push(&block) {
+//!         println!("image of {} lines", image.image_info().unwrap().height);
+//!     }
+//!     payload_rx.send_back(block);
+//! }
+//!
+//! // Take the lines received so far as a partial image.
+//! if let Some(image) = assembler.flush() {
+//!     assert!(image.image_info().unwrap().is_partial());
+//! }
+//! ```
+
+use super::{ImageInfo, Payload, PayloadStatus, PayloadType};
+
+/// Concatenates blocks of lines into images of a fixed height.
+#[derive(Debug, Clone)]
+pub struct LineScanAssembler {
+    height: usize,
+    frame: Option<Frame>,
+}
+
+/// An image being assembled.
+#[derive(Debug, Clone)]
+struct Frame {
+    /// The first block of the image, whose meta data is inherited by the image.
+    first: Payload,
+    line_size: usize,
+    data: Vec<u8>,
+}
+
+impl Frame {
+    fn lines(&self) -> usize {
+        self.data.len() / self.line_size
+    }
+
+    fn is_compatible(&self, info: &ImageInfo) -> bool {
+        let first = self.first.image_info.as_ref().unwrap();
+        first.width == info.width && first.pixel_format == info.pixel_format
+    }
+
+    fn into_payload(self, max_height: usize) -> Payload {
+        let height = self.lines();
+        let first = self.first;
+        let first_info = first.image_info.unwrap();
+        let image_size = self.data.len();
+        Payload {
+            id: first.id,
+            payload_type: PayloadType::Image,
+            image_info: Some(ImageInfo {
+                height,
+                max_height,
+                image_size,
+                ..first_info
+            }),
+            payload: self.data,
+            valid_payload_size: image_size,
+            timestamp: first.timestamp,
+            host_timestamp: first.host_timestamp,
+            status: PayloadStatus::default(),
+            checksum: None,
+            leader: vec![],
+            trailer: vec![],
+        }
+    }
+}
+
+impl LineScanAssembler {
+    /// Constructs an assembler which emits images of `height` lines.
+    ///
+    /// # Panics
+    /// Panics if `height` is zero.
+    pub fn new(height: usize) -> Self {
+        assert!(height > 0, "height must be positive");
+        Self {
+            height,
+            frame: None,
+        }
+    }
+
+    /// Returns the height of the emitted images.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of lines assembled so far for the next image.
+    pub fn lines(&self) -> usize {
+        self.frame.as_ref().map_or(0, Frame::lines)
+    }
+
+    /// Appends the lines in `block`, and returns the images completed by the block.
+    ///
+    /// Each image inherits the id and the timestamps of the block containing its first line. If
+    /// the width or the pixel format of `block` differs from the lines assembled so far, the
+    /// assembled lines are emitted as a partial image first.
+    ///
+    /// Payloads which don't contain an image are ignored.
+    pub fn push(&mut self, block: &Payload) -> Vec<Payload> {
+        let mut images = vec![];
+        let (info, image) = match (block.image_info(), block.image()) {
+            (Some(info), Some(image)) => (info, image),
+            _ => return images,
+        };
+        let line_size = (info.width * info.pixel_format.bits_per_pixel() as usize).div_ceil(8);
+        if line_size == 0 {
+            return images;
+        }
+
+        if self
+            .frame
+            .as_ref()
+            .is_some_and(|frame| !frame.is_compatible(info))
+        {
+            images.extend(self.flush());
+        }
+
+        let lines = info.height.min(image.len() / line_size);
+        for chunk in image[..lines * line_size].chunks(line_size) {
+            let height = self.height;
+            let frame = self.frame.get_or_insert_with(|| Frame {
+                first: block.clone_meta(),
+                line_size,
+                data: Vec::with_capacity(height * line_size),
+            });
+            frame.data.extend_from_slice(chunk);
+            if frame.lines() == height {
+                images.extend(self.frame.take().map(|frame| frame.into_payload(height)));
+            }
+        }
+
+        images
+    }
+
+    /// Emits the lines assembled so far as a partial image, whose
+    /// [`ImageInfo::max_height`] is the height of the assembler.
+    ///
+    /// Returns `None` if no line is assembled.
+    pub fn flush(&mut self) -> Option<Payload> {
+        let height = self.height;
+        self.frame.take().map(|frame| frame.into_payload(height))
+    }
+}
+
+impl Payload {
+    /// Returns a payload which has the meta data of `self`, but no data.
+    fn clone_meta(&self) -> Self {
+        Self {
+            id: self.id,
+            payload_type: self.payload_type,
+            image_info: self.image_info.clone(),
+            payload: vec![],
+            valid_payload_size: 0,
+            timestamp: self.timestamp,
+            host_timestamp: self.host_timestamp,
+            status: self.status,
+            checksum: self.checksum,
+            leader: vec![],
+            trailer: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::{PayloadBuilder, PixelFormat};
+
+    fn image_payload(id: u64, width: usize, height: usize, payload: Vec<u8>) -> Payload {
+        PayloadBuilder::new(id)
+            .image(width, height, PixelFormat::Mono8, payload)
+            .build()
+    }
+
+    #[test]
+    fn test_assemble() {
+        let mut assembler = LineScanAssembler::new(4);
+
+        // Blocks of 3 lines of 2 pixels.
+        assert!(assembler
+            .push(&image_payload(0, 2, 3, vec![0; 6]))
+            .is_empty());
+        assert_eq!(assembler.lines(), 3);
+
+        let images = assembler.push(&image_payload(1, 2, 3, vec![1; 6]));
+        assert_eq!(images.len(), 1);
+        let info = images[0].image_info().unwrap();
+        assert_eq!(images[0].id(), 0);
+        assert_eq!((info.width, info.height, info.max_height), (2, 4, 4));
+        assert!(!info.is_partial());
+        assert_eq!(images[0].image().unwrap(), &[0, 0, 0, 0, 0, 0, 1, 1]);
+        assert_eq!(assembler.lines(), 2);
+
+        // The rest of the lines are inherited from the second block.
+        let image = assembler.flush().unwrap();
+        let info = image.image_info().unwrap();
+        assert_eq!(image.id(), 1);
+        assert_eq!((info.height, info.max_height), (2, 4));
+        assert!(info.is_partial());
+        assert_eq!(image.image().unwrap(), &[1, 1, 1, 1]);
+        assert!(assembler.flush().is_none());
+    }
+
+    #[test]
+    fn test_width_change() {
+        let mut assembler = LineScanAssembler::new(4);
+        assembler.push(&image_payload(0, 2, 1, vec![0; 2]));
+
+        let images = assembler.push(&image_payload(1, 4, 4, vec![1; 16]));
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].image_info().unwrap().height, 1);
+        assert_eq!(images[1].image_info().unwrap().width, 4);
+        assert_eq!(images[1].image_info().unwrap().height, 4);
+        assert_eq!(assembler.lines(), 0);
+    }
+}
@@ -0,0 +1,388 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains conversion of 3D payloads sent by ToF and structured-light cameras into
+//! point clouds.
+//!
+//! Supported range formats are `Coord3D_ABC`, `Coord3D_AC` and `Coord3D_C` formats of 8 bit, 16
+//! bit and 32 bit float, both interleaved and planar, e.g. `Coord3D_ABC32f` or
+//! `Coord3D_C16`. A coordinate which isn't transferred is derived from the pixel position, i.e.
+//! `A` from the column and `B` from the row, as defined in `GenICam SFNC`.
+//!
+//! Range and intensity are given as separate payloads, e.g. consecutive payloads of a device which
+//! alternates the transferred component.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon::payload::point_cloud::{Coord3DTransform, PointCloud};
+//!
+//! # let mut cameras = cameleon::u3v::enumerate_cameras().unwrap();
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//! let transform = Coord3DTransform::read(&mut camera.params_ctxt().unwrap()).unwrap();
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//!
+//! let range = payload_rx.recv_blocking().unwrap();
+//! let intensity = payload_rx.recv_blocking().unwrap();
+//! let cloud = PointCloud::from_payloads(&range, Some(&intensity), &transform).unwrap();
+//! for [x, y, z, intensity] in cloud.iter().filter(|p| !p[2].is_nan()) {
+//!     println!("({}, {}, {}): {}", x, y, z, intensity);
+//! }
+//! ```
+
+use super::{Payload, PixelFormat};
+use crate::{
+    genapi::{GenApiCtxt, ParamsCtxt},
+    CameleonResult, DeviceControl,
+};
+
+/// Scale and offset converting transferred coordinates to physical units, corresponding to
+/// `Scan3dCoordinateScale` and `Scan3dCoordinateOffset` of each `Scan3dCoordinateSelector` entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coord3DTransform {
+    /// Scale of coordinate `A`, `B` and `C`.
+    pub scale: [f64; 3],
+
+    /// Offset of coordinate `A`, `B` and `C`, added after scaling.
+    pub offset: [f64; 3],
+
+    /// Transferred value of coordinate `C` which marks the point as invalid, corresponding to
+    /// `Scan3dInvalidDataValue`. `None` if the device doesn't mark invalid points.
+    pub invalid_value: Option<f64>,
+}
+
+impl Default for Coord3DTransform {
+    fn default() -> Self {
+        Self {
+            scale: [1.0; 3],
+            offset: [0.0; 3],
+            invalid_value: None,
+        }
+    }
+}
+
+impl Coord3DTransform {
+    /// Reads the transform from `Scan3dCoordinateScale`, `Scan3dCoordinateOffset`,
+    /// `Scan3dInvalidDataFlag` and `Scan3dInvalidDataValue`.
+    ///
+    /// `Scan3dCoordinateSelector` is restored to the original entry after reading.
+    pub fn read<Ctrl, Ctxt>(ctxt: &mut ParamsCtxt<Ctrl, Ctxt>) -> CameleonResult<Self>
+    where
+        Ctrl: DeviceControl,
+        Ctxt: GenApiCtxt,
+    {
+        let selector = expect_node!(ctxt, "Scan3dCoordinateSelector", as_enumeration);
+        let scale = expect_node!(ctxt, "Scan3dCoordinateScale", as_float);
+        let offset = expect_node!(ctxt, "Scan3dCoordinateOffset", as_float);
+        let invalid_flag = ctxt
+            .node("Scan3dInvalidDataFlag")
+            .and_then(|n| n.as_boolean(ctxt));
+        let invalid_value = ctxt
+            .node("Scan3dInvalidDataValue")
+            .and_then(|n| n.as_float(ctxt));
+
+        let original = selector.current_entry(ctxt)?.value(ctxt);
+        let mut transform = Self::default();
+        for (i, coordinate) in ["CoordinateA", "CoordinateB", "CoordinateC"]
+            .iter()
+            .enumerate()
+        {
+            selector.set_entry_by_symbolic(ctxt, coordinate)?;
+            transform.scale[i] = scale.value(ctxt)?;
+            transform.offset[i] = offset.value(ctxt)?;
+        }
+        // `CoordinateC` is selected here, which is the range.
+        if let (Some(flag), Some(value)) = (invalid_flag, invalid_value) {
+            if flag.value(ctxt)? {
+                transform.invalid_value = Some(value.value(ctxt)?);
+            }
+        }
+        selector.set_entry_by_value(ctxt, original)?;
+
+        Ok(transform)
+    }
+
+    fn apply(&self, coordinate: usize, value: f64) -> f32 {
+        (value * self.scale[coordinate] + self.offset[coordinate]) as f32
+    }
+}
+
+/// A point cloud converted from a range payload, and optionally an intensity payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointCloud {
+    /// Width of the range image.
+    pub width: usize,
+
+    /// Height of the range image.
+    pub height: usize,
+
+    /// Interleaved `x`, `y`, `z` and intensity of each pixel in row-major order, i.e. the length
+    /// is `width * height * 4`.
+    ///
+    /// All components of an invalid point are `NaN`. Intensity is `0.0` if no intensity payload
+    /// is given.
+    pub points: Vec<f32>,
+}
+
+impl PointCloud {
+    /// Number of `f32` elements of a point in [`Self::points`].
+    pub const STRIDE: usize = 4;
+
+    /// Converts `range` into a point cloud, attaching intensity from `intensity` if given.
+    ///
+    /// `intensity` must be a single component image of the same size as `range`, e.g. `Mono8`,
+    /// `Mono16` or `Confidence8`. Intensity values are kept as transferred.
+    ///
+    /// Returns `None` if the payloads don't contain images, the pixel formats are not supported,
+    /// or the image sizes differ.
+    #[must_use]
+    pub fn from_payloads(
+        range: &Payload,
+        intensity: Option<&Payload>,
+        transform: &Coord3DTransform,
+    ) -> Option<Self> {
+        let info = range.image_info()?;
+        let image = range.image()?;
+        let layout = RangeLayout::new(info.pixel_format)?;
+        let (width, height) = (info.width, info.height);
+        let pixel_count = width * height;
+        let coordinates = layout.coordinates.len();
+        if image.len() < pixel_count * coordinates * layout.elem.size() {
+            return None;
+        }
+
+        let intensity = match intensity {
+            Some(payload) => Some(read_intensity(payload, width, height)?),
+            None => None,
+        };
+
+        let mut points = vec![0.0; pixel_count * Self::STRIDE];
+        for (i, point) in points.chunks_exact_mut(Self::STRIDE).enumerate() {
+            let (x, y) = (i % width, i / width);
+            let raw = |coordinate: usize| -> Option<f64> {
+                let position = layout.coordinates.iter().position(|c| *c == coordinate)?;
+                let index = if layout.planar {
+                    position * pixel_count + i
+                } else {
+                    i * coordinates + position
+                };
+                Some(layout.elem.read(image, index))
+            };
+
+            let c = raw(2).unwrap();
+            if c.is_nan() || transform.invalid_value == Some(c) {
+                point.iter_mut().for_each(|v| *v = f32::NAN);
+                continue;
+            }
+            let a = raw(0).unwrap_or(x as f64);
+            let b = raw(1).unwrap_or(y as f64);
+            point[0] = transform.apply(0, a);
+            point[1] = transform.apply(1, b);
+            point[2] = transform.apply(2, c);
+            point[3] = intensity.as_ref().map_or(0.0, |intensity| intensity[i]);
+        }
+
+        Some(Self {
+            width,
+            height,
+            points,
+        })
+    }
+
+    /// Returns the number of points, including invalid ones.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.points.len() / Self::STRIDE
+    }
+
+    /// Returns `true` if the point cloud has no points.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns an iterator over `[x, y, z, intensity]` of each point.
+    pub fn iter(&self) -> impl Iterator<Item = [f32; 4]> + '_ {
+        self.points
+            .chunks_exact(Self::STRIDE)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+    }
+}
+
+/// Element type of a coordinate.
+#[derive(Debug, Clone, Copy)]
+enum Elem {
+    U8,
+    U16,
+    F32,
+}
+
+impl Elem {
+    fn size(self) -> usize {
+        match self {
+            Self::U8 => 1,
+            Self::U16 => 2,
+            Self::F32 => 4,
+        }
+    }
+
+    /// Reads the `index`-th element of `data` in little endian.
+    fn read(self, data: &[u8], index: usize) -> f64 {
+        let offset = index * self.size();
+        let b = &data[offset..offset + self.size()];
+        match self {
+            Self::U8 => f64::from(b[0]),
+            Self::U16 => f64::from(u16::from_le_bytes([b[0], b[1]])),
+            Self::F32 => f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        }
+    }
+}
+
+/// Layout of a `Coord3D` format.
+struct RangeLayout {
+    /// Indices of the transferred coordinates, `0` for `A`, `1` for `B` and `2` for `C`.
+    coordinates: &'static [usize],
+    elem: Elem,
+    planar: bool,
+}
+
+impl RangeLayout {
+    fn new(pixel_format: PixelFormat) -> Option<Self> {
+        use PixelFormat::{
+            Coord3D_ABC16, Coord3D_ABC16_Planar, Coord3D_ABC32f, Coord3D_ABC32f_Planar,
+            Coord3D_ABC8, Coord3D_ABC8_Planar, Coord3D_AC16, Coord3D_AC16_Planar, Coord3D_AC32f,
+            Coord3D_AC32f_Planar, Coord3D_AC8, Coord3D_AC8_Planar, Coord3D_C16, Coord3D_C32f,
+            Coord3D_C8,
+        };
+
+        const ABC: &[usize] = &[0, 1, 2];
+        const AC: &[usize] = &[0, 2];
+        const C: &[usize] = &[2];
+
+        let (coordinates, elem, planar) = match pixel_format {
+            Coord3D_ABC8 => (ABC, Elem::U8, false),
+            Coord3D_ABC8_Planar => (ABC, Elem::U8, true),
+            Coord3D_ABC16 => (ABC, Elem::U16, false),
+            Coord3D_ABC16_Planar => (ABC, Elem::U16, true),
+            Coord3D_ABC32f => (ABC, Elem::F32, false),
+            Coord3D_ABC32f_Planar => (ABC, Elem::F32, true),
+            Coord3D_AC8 => (AC, Elem::U8, false),
+            Coord3D_AC8_Planar => (AC, Elem::U8, true),
+            Coord3D_AC16 => (AC, Elem::U16, false),
+            Coord3D_AC16_Planar => (AC, Elem::U16, true),
+            Coord3D_AC32f => (AC, Elem::F32, false),
+            Coord3D_AC32f_Planar => (AC, Elem::F32, true),
+            Coord3D_C8 => (C, Elem::U8, false),
+            Coord3D_C16 => (C, Elem::U16, false),
+            Coord3D_C32f => (C, Elem::F32, false),
+            _ => return None,
+        };
+        Some(Self {
+            coordinates,
+            elem,
+            planar,
+        })
+    }
+}
+
+/// Reads a single component intensity image of the given size.
+fn read_intensity(payload: &Payload, width: usize, height: usize) -> Option<Vec<f32>> {
+    use PixelFormat::{
+        Confidence16, Confidence32f, Confidence8, Mono10, Mono12, Mono14, Mono16, Mono8,
+    };
+
+    let info = payload.image_info()?;
+    let image = payload.image()?;
+    if (info.width, info.height) != (width, height) {
+        return None;
+    }
+    let elem = match info.pixel_format {
+        Mono8 | Confidence8 => Elem::U8,
+        Mono10 | Mono12 | Mono14 | Mono16 | Confidence16 => Elem::U16,
+        Confidence32f => Elem::F32,
+        _ => return None,
+    };
+
+    let pixel_count = width * height;
+    if image.len() < pixel_count * elem.size() {
+        return None;
+    }
+    Some(
+        (0..pixel_count)
+            .map(|i| elem.read(image, i) as f32)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::image_payload;
+
+    fn f32_bytes(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_abc32f() {
+        let range = image_payload(
+            2,
+            1,
+            PixelFormat::Coord3D_ABC32f,
+            f32_bytes(&[1.0, 2.0, 3.0, 4.0, 5.0, f32::NAN]),
+        );
+        let intensity = image_payload(2, 1, PixelFormat::Mono8, vec![10, 20]);
+        let cloud =
+            PointCloud::from_payloads(&range, Some(&intensity), &Coord3DTransform::default())
+                .unwrap();
+
+        assert_eq!(cloud.len(), 2);
+        let points: Vec<_> = cloud.iter().collect();
+        assert_eq!(points[0], [1.0, 2.0, 3.0, 10.0]);
+        assert!(points[1].iter().all(|v| v.is_nan()));
+
+        // Intensity of a different size is rejected.
+        let intensity = image_payload(1, 2, PixelFormat::Mono8, vec![10, 20]);
+        assert!(
+            PointCloud::from_payloads(&range, Some(&intensity), &Coord3DTransform::default())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_c16_with_transform() {
+        // 2x2 range image, the last pixel is invalid.
+        let range = image_payload(
+            2,
+            2,
+            PixelFormat::Coord3D_C16,
+            [100_u16, 200, 300, 0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+        );
+        let transform = Coord3DTransform {
+            scale: [0.5, 0.25, 0.01],
+            offset: [-1.0, 0.0, 1.0],
+            invalid_value: Some(0.0),
+        };
+        let cloud = PointCloud::from_payloads(&range, None, &transform).unwrap();
+        let points: Vec<_> = cloud.iter().collect();
+
+        // `A` and `B` are derived from the column and the row.
+        assert_eq!(points[0], [-1.0, 0.0, 2.0, 0.0]);
+        assert_eq!(points[1], [-0.5, 0.0, 3.0, 0.0]);
+        assert_eq!(points[2], [-1.0, 0.25, 4.0, 0.0]);
+        assert!(points[3][2].is_nan());
+    }
+
+    #[test]
+    fn test_planar() {
+        let range = image_payload(2, 1, PixelFormat::Coord3D_AC8_Planar, vec![1, 2, 30, 40]);
+        let cloud = PointCloud::from_payloads(&range, None, &Coord3DTransform::default()).unwrap();
+        let points: Vec<_> = cloud.iter().collect();
+        assert_eq!(points, vec![[1.0, 0.0, 30.0, 0.0], [2.0, 0.0, 40.0, 0.0]]);
+    }
+}
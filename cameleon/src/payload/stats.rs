@@ -0,0 +1,321 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains statistics of images in payloads, e.g. for auto exposure or focus UIs.
+//!
+//! # Examples
+//! ```no_run
+//! # let mut cameras = cameleon::u3v::enumerate_cameras().unwrap();
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//!
+//! let payload = payload_rx.recv_blocking().unwrap();
+//! if let Some(stats) = payload.stats() {
+//!     for channel in &stats.channels {
+//!         println!(
+//!             "min: {}, max: {}, mean: {:.1}, saturation: {:.2}%",
+//!             channel.min,
+//!             channel.max,
+//!             channel.mean,
+//!             channel.saturation()
+//!         );
+//!     }
+//! }
+//! ```
+
+use super::{Payload, PixelFormat};
+
+/// Statistics of a component of an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelStats {
+    /// The number of pixels of each value. The length is `2^bits` where `bits` is the bit depth
+    /// of the pixel format, e.g. 4096 for `Mono12`.
+    pub histogram: Vec<u64>,
+    /// Minimum pixel value.
+    pub min: u16,
+    /// Maximum pixel value.
+    pub max: u16,
+    /// Mean pixel value.
+    pub mean: f64,
+}
+
+impl ChannelStats {
+    /// Returns the number of pixels.
+    #[must_use]
+    pub fn pixel_count(&self) -> u64 {
+        self.histogram.iter().sum()
+    }
+
+    /// Returns the percentage of saturated pixels, i.e. pixels of the maximum value of the bit
+    /// depth.
+    #[must_use]
+    pub fn saturation(&self) -> f64 {
+        let pixel_count = self.pixel_count();
+        if pixel_count == 0 {
+            0.0
+        } else {
+            let saturated = self.histogram.last().copied().unwrap_or_default();
+            saturated as f64 * 100.0 / pixel_count as f64
+        }
+    }
+}
+
+/// Statistics of an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageStats {
+    /// [`PixelFormat`] of the image.
+    pub pixel_format: PixelFormat,
+    /// Statistics of each component in the order of the pixel format, e.g. `R`, `G` and `B` for
+    /// `RGB8`. Alpha component isn't included.
+    pub channels: Vec<ChannelStats>,
+}
+
+impl Payload {
+    /// Computes statistics of the image in the payload.
+    ///
+    /// Supported pixel formats are `Mono8`, 8 bit `Bayer` formats, `Mono10`, `Mono12`, `Mono14`,
+    /// `Mono16`, `RGB8`, `BGR8`, `RGBa8`, `BGRa8` and `RGB16`. `Bayer` images are treated as a
+    /// single component.
+    ///
+    /// Returns `None` if the payload doesn't contain an image, or the pixel format is not
+    /// supported.
+    #[must_use]
+    pub fn stats(&self) -> Option<ImageStats> {
+        use PixelFormat::{
+            BGRa8, BayerBG8, BayerGB8, BayerGR8, BayerRG8, Mono10, Mono12, Mono14, Mono16, Mono8,
+            RGBa8, BGR8, RGB16, RGB8,
+        };
+
+        let info = self.image_info()?;
+        let image = self.image()?;
+        let (components, color_components, bits) = match info.pixel_format {
+            Mono8 | BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => (1, 1, 8),
+            Mono10 => (1, 1, 10),
+            Mono12 => (1, 1, 12),
+            Mono14 => (1, 1, 14),
+            Mono16 => (1, 1, 16),
+            RGB8 | BGR8 => (3, 3, 8),
+            RGBa8 | BGRa8 => (4, 3, 8),
+            RGB16 => (3, 3, 16),
+            _ => return None,
+        };
+
+        let elem_size = if bits > 8 { 2 } else { 1 };
+        let row_bytes = info.width * components * elem_size;
+        if info.height == 0 || row_bytes == 0 {
+            return None;
+        }
+        // Some devices pad each row, in that case `image_size` is larger than the packed size.
+        let stride = image.len() / info.height;
+        if stride < row_bytes {
+            return None;
+        }
+
+        let mut accs: Vec<_> = (0..color_components)
+            .map(|_| Accumulator::new(bits))
+            .collect();
+        for row in image.chunks(stride).take(info.height) {
+            let row = &row[..row_bytes];
+            match (components, elem_size) {
+                (1, 1) => accs[0].add_u8_slice(row),
+                (_, 1) => {
+                    for px in row.chunks_exact(components) {
+                        for (acc, value) in accs.iter_mut().zip(px) {
+                            acc.add(u16::from(*value));
+                        }
+                    }
+                }
+                _ => {
+                    for px in row.chunks_exact(components * 2) {
+                        for (acc, value) in accs.iter_mut().zip(px.chunks_exact(2)) {
+                            acc.add(u16::from_le_bytes([value[0], value[1]]));
+                        }
+                    }
+                }
+            }
+        }
+
+        Some(ImageStats {
+            pixel_format: info.pixel_format,
+            channels: accs.into_iter().map(Accumulator::finish).collect(),
+        })
+    }
+}
+
+struct Accumulator {
+    histogram: Vec<u64>,
+    min: u16,
+    max: u16,
+    sum: u64,
+    count: u64,
+}
+
+impl Accumulator {
+    fn new(bits: u32) -> Self {
+        Self {
+            histogram: vec![0; 1 << bits],
+            min: u16::MAX,
+            max: 0,
+            sum: 0,
+            count: 0,
+        }
+    }
+
+    fn add(&mut self, value: u16) {
+        // Values exceeding the bit depth are counted as saturated.
+        let bin = usize::from(value).min(self.histogram.len() - 1);
+        self.histogram[bin] += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += u64::from(value);
+        self.count += 1;
+    }
+
+    /// Adds 8 bit single component pixels.
+    fn add_u8_slice(&mut self, data: &[u8]) {
+        let (min, max, sum) = min_max_sum_u8(data);
+        self.min = self.min.min(u16::from(min));
+        self.max = self.max.max(u16::from(max));
+        self.sum += sum;
+        self.count += data.len() as u64;
+
+        // Counting into interleaved histograms avoids stalls on consecutive pixels of the same
+        // value.
+        let mut partial = [[0_u32; 256]; 4];
+        let mut chunks = data.chunks_exact(4);
+        for chunk in &mut chunks {
+            partial[0][usize::from(chunk[0])] += 1;
+            partial[1][usize::from(chunk[1])] += 1;
+            partial[2][usize::from(chunk[2])] += 1;
+            partial[3][usize::from(chunk[3])] += 1;
+        }
+        for value in chunks.remainder() {
+            partial[0][usize::from(*value)] += 1;
+        }
+        for (i, bin) in self.histogram.iter_mut().enumerate() {
+            *bin += partial.iter().map(|p| u64::from(p[i])).sum::<u64>();
+        }
+    }
+
+    fn finish(self) -> ChannelStats {
+        let (min, max, mean) = if self.count == 0 {
+            (0, 0, 0.0)
+        } else {
+            (self.min, self.max, self.sum as f64 / self.count as f64)
+        };
+        ChannelStats {
+            histogram: self.histogram,
+            min,
+            max,
+            mean,
+        }
+    }
+}
+
+/// Returns minimum, maximum and sum of `data`.
+#[cfg(target_arch = "x86_64")]
+fn min_max_sum_u8(data: &[u8]) -> (u8, u8, u64) {
+    use std::arch::x86_64::{
+        __m128i, _mm_add_epi64, _mm_loadu_si128, _mm_max_epu8, _mm_min_epu8, _mm_sad_epu8,
+        _mm_set1_epi8, _mm_setzero_si128, _mm_storeu_si128,
+    };
+
+    let mut chunks = data.chunks_exact(16);
+    // SAFETY: SSE2 is always available on `x86_64`, and only unaligned loads and stores are used.
+    let (mut min, mut max, mut sum) = unsafe {
+        let zero = _mm_setzero_si128();
+        let mut vmin = _mm_set1_epi8(-1);
+        let mut vmax = zero;
+        let mut vsum = zero;
+        for chunk in &mut chunks {
+            let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+            vmin = _mm_min_epu8(vmin, v);
+            vmax = _mm_max_epu8(vmax, v);
+            // Sums of absolute differences from zero are the sums of each 8 bytes.
+            vsum = _mm_add_epi64(vsum, _mm_sad_epu8(v, zero));
+        }
+
+        let mut mins = [0_u8; 16];
+        let mut maxs = [0_u8; 16];
+        let mut sums = [0_u64; 2];
+        _mm_storeu_si128(mins.as_mut_ptr() as *mut __m128i, vmin);
+        _mm_storeu_si128(maxs.as_mut_ptr() as *mut __m128i, vmax);
+        _mm_storeu_si128(sums.as_mut_ptr() as *mut __m128i, vsum);
+        (
+            mins.iter().copied().min().unwrap(),
+            maxs.iter().copied().max().unwrap(),
+            sums[0] + sums[1],
+        )
+    };
+
+    for value in chunks.remainder() {
+        min = min.min(*value);
+        max = max.max(*value);
+        sum += u64::from(*value);
+    }
+    (min, max, sum)
+}
+
+/// Returns minimum, maximum and sum of `data`.
+#[cfg(not(target_arch = "x86_64"))]
+fn min_max_sum_u8(data: &[u8]) -> (u8, u8, u64) {
+    data.iter().fold((u8::MAX, 0, 0), |(min, max, sum), value| {
+        (min.min(*value), max.max(*value), sum + u64::from(*value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::image_payload;
+
+    #[test]
+    fn test_min_max_sum_u8() {
+        let data: Vec<u8> = (0..100_u32).map(|i| (i * 37 % 251) as u8 + 1).collect();
+        let expected = (
+            *data.iter().min().unwrap(),
+            *data.iter().max().unwrap(),
+            data.iter().map(|v| u64::from(*v)).sum(),
+        );
+        assert_eq!(min_max_sum_u8(&data), expected);
+        assert_eq!(min_max_sum_u8(&[]), (u8::MAX, 0, 0));
+    }
+
+    #[test]
+    fn test_mono8_stats() {
+        // 3x2 image with a padding byte at the end of each row.
+        let payload = image_payload(
+            3,
+            2,
+            PixelFormat::Mono8,
+            vec![0, 10, 255, 99, 20, 255, 30, 99],
+        );
+        let stats = payload.stats().unwrap();
+        assert_eq!(stats.channels.len(), 1);
+
+        let channel = &stats.channels[0];
+        assert_eq!(channel.pixel_count(), 6);
+        assert_eq!((channel.min, channel.max), (0, 255));
+        assert!((channel.mean - 95.0).abs() < 1e-9);
+        assert!((channel.saturation() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(channel.histogram[10], 1);
+    }
+
+    #[test]
+    fn test_color_stats() {
+        let payload = image_payload(2, 1, PixelFormat::RGBa8, vec![1, 2, 3, 0, 5, 6, 7, 0]);
+        let stats = payload.stats().unwrap();
+        let means: Vec<_> = stats.channels.iter().map(|c| c.mean).collect();
+        assert_eq!(means, vec![3.0, 4.0, 5.0]);
+
+        let payload = image_payload(2, 1, PixelFormat::Mono12, vec![0xff, 0x0f, 0x00, 0x01]);
+        let stats = payload.stats().unwrap();
+        let channel = &stats.channels[0];
+        assert_eq!(channel.histogram.len(), 4096);
+        assert_eq!((channel.min, channel.max), (0x100, 0xfff));
+        assert!((channel.saturation() - 50.0).abs() < 1e-9);
+    }
+}
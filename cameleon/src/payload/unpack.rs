@@ -0,0 +1,429 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains unpacking of 10 and 12 bit packed pixel formats into `u16` buffers.
+//!
+//! Unpacking runs 8 pixels at a time with `SSSE3` on `x86_64` if the CPU supports it, and with
+//! `NEON` on `aarch64`. The rest of the pixels and other architectures fall back to a scalar
+//! implementation.
+//!
+//! # Examples
+//! ```no_run
+//! # let mut cameras = cameleon::u3v::enumerate_cameras().unwrap();
+//! # let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//!
+//! // Reuse the buffer between frames.
+//! let mut pixels = vec![];
+//! loop {
+//!     let payload = payload_rx.recv_blocking().unwrap();
+//!     if payload.unpack_into(&mut pixels) {
+//!         println!("first pixel: {}", pixels[0]);
+//!     }
+//!     payload_rx.send_back(payload);
+//! }
+//! ```
+
+use super::{Payload, PixelFormat};
+
+/// Packing scheme of a packed pixel format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packing {
+    /// `GenICam PFNC` 10 bit packing, e.g. `Mono10p`. Four pixels are packed into five bytes LSB
+    /// first.
+    Lsb10,
+
+    /// `GenICam PFNC` 12 bit packing, e.g. `Mono12p`. Two pixels are packed into three bytes LSB
+    /// first.
+    Lsb12,
+
+    /// `GigE Vision` legacy 10 bit packing, e.g. `Mono10Packed`. Two pixels are packed into three
+    /// bytes, the first and the third bytes hold the high 8 bits of each pixel and the second byte
+    /// holds the low bits of both.
+    Legacy10,
+
+    /// `GigE Vision` legacy 12 bit packing, e.g. `Mono12Packed`. The layout is the same as
+    /// [`Packing::Legacy10`] except for the number of the low bits.
+    Legacy12,
+}
+
+impl Packing {
+    /// Returns the packing of the pixel format, or `None` if the format isn't a supported
+    /// single component packed format.
+    ///
+    /// Supported formats are `Mono10p`, `Mono12p`, `Mono10Packed`, `Mono12Packed` and `Bayer`
+    /// formats of the same packings, e.g. `BayerRG12p`.
+    #[must_use]
+    pub fn from_pixel_format(pixel_format: PixelFormat) -> Option<Self> {
+        use PixelFormat::{
+            BayerBG10Packed, BayerBG10p, BayerBG12Packed, BayerBG12p, BayerGB10Packed, BayerGB10p,
+            BayerGB12Packed, BayerGB12p, BayerGR10Packed, BayerGR10p, BayerGR12Packed, BayerGR12p,
+            BayerRG10Packed, BayerRG10p, BayerRG12Packed, BayerRG12p, Mono10Packed, Mono10p,
+            Mono12Packed, Mono12p,
+        };
+
+        match pixel_format {
+            Mono10p | BayerRG10p | BayerGR10p | BayerGB10p | BayerBG10p => Some(Self::Lsb10),
+            Mono12p | BayerRG12p | BayerGR12p | BayerGB12p | BayerBG12p => Some(Self::Lsb12),
+            Mono10Packed | BayerRG10Packed | BayerGR10Packed | BayerGB10Packed
+            | BayerBG10Packed => Some(Self::Legacy10),
+            Mono12Packed | BayerRG12Packed | BayerGR12Packed | BayerGB12Packed
+            | BayerBG12Packed => Some(Self::Legacy12),
+            _ => None,
+        }
+    }
+
+    /// Returns the bit depth of the unpacked pixels.
+    #[must_use]
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::Lsb10 | Self::Legacy10 => 10,
+            Self::Lsb12 | Self::Legacy12 => 12,
+        }
+    }
+
+    /// Returns the number of bytes of `pixel_count` packed pixels.
+    #[must_use]
+    pub fn packed_len(self, pixel_count: usize) -> usize {
+        match self {
+            Self::Lsb10 | Self::Lsb12 => (pixel_count * self.bits() as usize).div_ceil(8),
+            // The last pair is padded even if the number of pixels is odd.
+            Self::Legacy10 | Self::Legacy12 => pixel_count.div_ceil(2) * 3,
+        }
+    }
+
+    /// Returns the number of bytes and pixels of the smallest repeating unit.
+    fn group(self) -> (usize, usize) {
+        match self {
+            Self::Lsb10 => (5, 4),
+            Self::Lsb12 | Self::Legacy10 | Self::Legacy12 => (3, 2),
+        }
+    }
+
+    /// Unpacks a group, the bytes past the end of the group are ignored.
+    fn unpack_group(self, b: [u8; 5]) -> [u16; 4] {
+        let [b0, b1, b2, b3, b4] = [
+            u16::from(b[0]),
+            u16::from(b[1]),
+            u16::from(b[2]),
+            u16::from(b[3]),
+            u16::from(b[4]),
+        ];
+        match self {
+            Self::Lsb10 => [
+                b0 | (b1 & 0x03) << 8,
+                b1 >> 2 | (b2 & 0x0f) << 6,
+                b2 >> 4 | (b3 & 0x3f) << 4,
+                b3 >> 6 | b4 << 2,
+            ],
+            Self::Lsb12 => [b0 | (b1 & 0x0f) << 8, b1 >> 4 | b2 << 4, 0, 0],
+            Self::Legacy10 => [b0 << 2 | b1 & 0x03, b2 << 2 | b1 >> 4 & 0x03, 0, 0],
+            Self::Legacy12 => [b0 << 4 | b1 & 0x0f, b2 << 4 | b1 >> 4, 0, 0],
+        }
+    }
+}
+
+/// Unpacks `dst.len()` pixels packed in `src`. The unpacked values are in the bit depth of the
+/// packing, i.e. they aren't scaled to 16 bit range.
+///
+/// # Panics
+/// Panics if `src` is shorter than [`Packing::packed_len`] of `dst.len()`.
+pub fn unpack(packing: Packing, src: &[u8], dst: &mut [u16]) {
+    assert!(
+        src.len() >= packing.packed_len(dst.len()),
+        "`src` is too short to unpack {} pixels",
+        dst.len()
+    );
+
+    let done = simd::unpack(packing, src, dst);
+    let (group_bytes, group_pixels) = packing.group();
+    let src = &src[done / group_pixels * group_bytes..];
+    for (pixels, bytes) in dst[done..]
+        .chunks_mut(group_pixels)
+        .zip(src.chunks(group_bytes))
+    {
+        let mut group = [0; 5];
+        group[..bytes.len()].copy_from_slice(bytes);
+        pixels.copy_from_slice(&packing.unpack_group(group)[..pixels.len()]);
+    }
+}
+
+impl Payload {
+    /// Unpacks the image in the payload into `dst`, which is resized to the number of pixels.
+    ///
+    /// The unpacked values are in the bit depth of the pixel format, e.g. `0..=4095` for
+    /// `Mono12p`. See [`Packing::from_pixel_format`] for supported pixel formats.
+    ///
+    /// Returns `false` without modifying `dst` if the payload doesn't contain an image, the pixel
+    /// format isn't supported, or the image is shorter than its size.
+    pub fn unpack_into(&self, dst: &mut Vec<u16>) -> bool {
+        let (info, image) = match (self.image_info(), self.image()) {
+            (Some(info), Some(image)) => (info, image),
+            _ => return false,
+        };
+        let packing = match Packing::from_pixel_format(info.pixel_format) {
+            Some(packing) => packing,
+            None => return false,
+        };
+        let pixel_count = info.width * info.height;
+        if image.len() < packing.packed_len(pixel_count) {
+            return false;
+        }
+
+        dst.resize(pixel_count, 0);
+        unpack(packing, image, dst);
+        true
+    }
+
+    /// Returns the unpacked image in the payload. See [`Self::unpack_into`] for details.
+    #[must_use]
+    pub fn unpack(&self) -> Option<Vec<u16>> {
+        let mut dst = vec![];
+        if self.unpack_into(&mut dst) {
+            Some(dst)
+        } else {
+            None
+        }
+    }
+}
+
+/// A vectorized kernel which unpacks 8 pixels from 16 loaded bytes.
+///
+/// The bytes are shuffled so that each 16 bit lane contains the two bytes which the pixel spans,
+/// then each term shifts the lanes right and masks them. A pixel is the union of its terms.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+struct Kernel {
+    /// Number of bytes consumed by 8 pixels.
+    consumed: usize,
+    shuffle: [u8; 16],
+    terms: &'static [(u16, [u16; 8])],
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+impl Kernel {
+    fn of(packing: Packing) -> &'static Self {
+        const M: u16 = 0x3ff;
+        const LSB10: Kernel = Kernel {
+            consumed: 10,
+            shuffle: [0, 1, 1, 2, 2, 3, 3, 4, 5, 6, 6, 7, 7, 8, 8, 9],
+            terms: &[
+                (0, [M, 0, 0, 0, M, 0, 0, 0]),
+                (2, [0, M, 0, 0, 0, M, 0, 0]),
+                (4, [0, 0, M, 0, 0, 0, M, 0]),
+                (6, [0, 0, 0, M, 0, 0, 0, M]),
+            ],
+        };
+        const LSB12: Kernel = Kernel {
+            consumed: 12,
+            shuffle: [0, 1, 1, 2, 3, 4, 4, 5, 6, 7, 7, 8, 9, 10, 10, 11],
+            terms: &[
+                (0, [0xfff, 0, 0xfff, 0, 0xfff, 0, 0xfff, 0]),
+                (4, [0, 0xfff, 0, 0xfff, 0, 0xfff, 0, 0xfff]),
+            ],
+        };
+        // Even lanes hold the high byte in the upper half, odd lanes hold the third byte there.
+        const LEGACY10: Kernel = Kernel {
+            consumed: 12,
+            shuffle: [1, 0, 1, 2, 4, 3, 4, 5, 7, 6, 7, 8, 10, 9, 10, 11],
+            terms: &[
+                (6, [0x3fc; 8]),
+                (0, [0x3, 0, 0x3, 0, 0x3, 0, 0x3, 0]),
+                (4, [0, 0x3, 0, 0x3, 0, 0x3, 0, 0x3]),
+            ],
+        };
+        const LEGACY12: Kernel = Kernel {
+            consumed: 12,
+            shuffle: [1, 0, 1, 2, 4, 3, 4, 5, 7, 6, 7, 8, 10, 9, 10, 11],
+            terms: &[
+                (4, [0xff0, 0xfff, 0xff0, 0xfff, 0xff0, 0xfff, 0xff0, 0xfff]),
+                (0, [0xf, 0, 0xf, 0, 0xf, 0, 0xf, 0]),
+            ],
+        };
+
+        match packing {
+            Packing::Lsb10 => &LSB10,
+            Packing::Lsb12 => &LSB12,
+            Packing::Legacy10 => &LEGACY10,
+            Packing::Legacy12 => &LEGACY12,
+        }
+    }
+
+    /// Returns the number of iterations which don't read past the end of `src` nor write past
+    /// the end of `dst`.
+    fn iterations(&self, src: &[u8], dst: &[u16]) -> usize {
+        let by_src = if src.len() < 16 {
+            0
+        } else {
+            (src.len() - 16) / self.consumed + 1
+        };
+        by_src.min(dst.len() / 8)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod simd {
+    use std::arch::x86_64::{
+        __m128i, _mm_and_si128, _mm_cvtsi32_si128, _mm_loadu_si128, _mm_or_si128,
+        _mm_setzero_si128, _mm_shuffle_epi8, _mm_srl_epi16, _mm_storeu_si128,
+    };
+
+    use super::{Kernel, Packing};
+
+    /// Unpacks as many leading pixels as possible and returns the number of unpacked pixels.
+    pub(super) fn unpack(packing: Packing, src: &[u8], dst: &mut [u16]) -> usize {
+        if is_x86_feature_detected!("ssse3") {
+            // SAFETY: `SSSE3` is available.
+            unsafe { unpack_ssse3(Kernel::of(packing), src, dst) }
+        } else {
+            0
+        }
+    }
+
+    #[target_feature(enable = "ssse3")]
+    unsafe fn unpack_ssse3(kernel: &Kernel, src: &[u8], dst: &mut [u16]) -> usize {
+        let iterations = kernel.iterations(src, dst);
+        let shuffle = _mm_loadu_si128(kernel.shuffle.as_ptr() as *const __m128i);
+        for i in 0..iterations {
+            // `Kernel::iterations` guarantees 16 bytes are readable and 8 pixels are writable.
+            let v = _mm_loadu_si128(src.as_ptr().add(i * kernel.consumed) as *const __m128i);
+            let lanes = _mm_shuffle_epi8(v, shuffle);
+            let mut pixels = _mm_setzero_si128();
+            for (shift, mask) in kernel.terms {
+                let shifted = _mm_srl_epi16(lanes, _mm_cvtsi32_si128(i32::from(*shift)));
+                let mask = _mm_loadu_si128(mask.as_ptr() as *const __m128i);
+                pixels = _mm_or_si128(pixels, _mm_and_si128(shifted, mask));
+            }
+            _mm_storeu_si128(dst.as_mut_ptr().add(i * 8) as *mut __m128i, pixels);
+        }
+        iterations * 8
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod simd {
+    use std::arch::aarch64::{
+        vandq_u16, vdupq_n_s16, vdupq_n_u16, vld1q_u16, vld1q_u8, vorrq_u16, vqtbl1q_u8,
+        vreinterpretq_u16_u8, vshlq_u16, vst1q_u16,
+    };
+
+    use super::{Kernel, Packing};
+
+    /// Unpacks as many leading pixels as possible and returns the number of unpacked pixels.
+    pub(super) fn unpack(packing: Packing, src: &[u8], dst: &mut [u16]) -> usize {
+        let kernel = Kernel::of(packing);
+        let iterations = kernel.iterations(src, dst);
+        // SAFETY: `NEON` is always available on `aarch64`, and `Kernel::iterations` guarantees
+        // 16 bytes are readable and 8 pixels are writable in each iteration.
+        unsafe {
+            let shuffle = vld1q_u8(kernel.shuffle.as_ptr());
+            for i in 0..iterations {
+                let v = vld1q_u8(src.as_ptr().add(i * kernel.consumed));
+                let lanes = vreinterpretq_u16_u8(vqtbl1q_u8(v, shuffle));
+                let mut pixels = vdupq_n_u16(0);
+                for (shift, mask) in kernel.terms {
+                    // Negative shift amounts shift right.
+                    let shifted = vshlq_u16(lanes, vdupq_n_s16(-(*shift as i16)));
+                    pixels = vorrq_u16(pixels, vandq_u16(shifted, vld1q_u16(mask.as_ptr())));
+                }
+                vst1q_u16(dst.as_mut_ptr().add(i * 8), pixels);
+            }
+        }
+        iterations * 8
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod simd {
+    use super::Packing;
+
+    pub(super) fn unpack(_packing: Packing, _src: &[u8], _dst: &mut [u16]) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs `pixels` bit by bit to compare the results with an independent implementation.
+    fn pack(packing: Packing, pixels: &[u16]) -> Vec<u8> {
+        let mut dst = vec![];
+        match packing {
+            Packing::Lsb10 | Packing::Lsb12 => {
+                let bits = packing.bits();
+                let (mut acc, mut acc_bits) = (0_u32, 0);
+                for pixel in pixels {
+                    acc |= u32::from(*pixel) << acc_bits;
+                    acc_bits += bits;
+                    while acc_bits >= 8 {
+                        dst.push(acc as u8);
+                        acc >>= 8;
+                        acc_bits -= 8;
+                    }
+                }
+                if acc_bits > 0 {
+                    dst.push(acc as u8);
+                }
+            }
+            Packing::Legacy10 | Packing::Legacy12 => {
+                let low_bits = packing.bits() - 8;
+                for pair in pixels.chunks(2) {
+                    let (p0, p1) = (pair[0], pair.get(1).copied().unwrap_or(0));
+                    let low_mask = (1 << low_bits) - 1;
+                    dst.push((p0 >> low_bits) as u8);
+                    dst.push((p0 & low_mask | (p1 & low_mask) << 4) as u8);
+                    dst.push((p1 >> low_bits) as u8);
+                }
+            }
+        }
+        dst.resize(packing.packed_len(pixels.len()), 0);
+        dst
+    }
+
+    #[test]
+    fn test_unpack_group() {
+        // Mono12p: 0x321, 0x654.
+        assert_eq!(
+            Packing::Lsb12.unpack_group([0x21, 0x43, 0x65, 0, 0])[..2],
+            [0x321, 0x654]
+        );
+        // Mono10p: 0x3ff, 0x001, 0x200, 0x155.
+        assert_eq!(
+            Packing::Lsb10.unpack_group([0xff, 0x07, 0x00, 0x60, 0x55]),
+            [0x3ff, 0x001, 0x200, 0x155]
+        );
+        // Mono12Packed: 0xab1, 0xcd2.
+        assert_eq!(
+            Packing::Legacy12.unpack_group([0xab, 0x21, 0xcd, 0, 0])[..2],
+            [0xab1, 0xcd2]
+        );
+    }
+
+    #[test]
+    fn test_unpack() {
+        for packing in [
+            Packing::Lsb10,
+            Packing::Lsb12,
+            Packing::Legacy10,
+            Packing::Legacy12,
+        ] {
+            let max = (1_u32 << packing.bits()) - 1;
+            // Odd length exercises both the vectorized kernel and the scalar tail.
+            let pixels: Vec<u16> = (0..101_u32).map(|i| (i * 2_654_435 % max) as u16).collect();
+            let src = pack(packing, &pixels);
+
+            let mut dst = vec![0; pixels.len()];
+            unpack(packing, &src, &mut dst);
+            assert_eq!(dst, pixels, "{:?}", packing);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unpack_short_src() {
+        unpack(Packing::Lsb12, &[0x21, 0x43], &mut [0; 2]);
+    }
+}
@@ -0,0 +1,229 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains Python bindings of `U3V` cameras.
+//!
+//! The bindings are exposed as `cameleon` Python module by [`cameleon`]. To build an extension
+//! module, create a `cdylib` crate that enables `python` feature and re-exports the function,
+//! e.g. with `maturin`.
+//!
+//! ```python
+//! import cameleon
+//!
+//! camera = cameleon.enumerate_cameras()[0]
+//! camera.open()
+//! camera.load_context()
+//!
+//! camera.set("ExposureTime", 1000.0)
+//! print(camera.get("PixelFormat"))
+//!
+//! camera.start_streaming(3)
+//! image = camera.receive()  # `numpy.ndarray` of shape (height, width) or (height, width, components).
+//! camera.stop_streaming()
+//! camera.close()
+//! ```
+
+use numpy::{PyArray1, PyArrayMethods};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyKeyError, PyTypeError},
+    prelude::*,
+};
+
+use crate::{
+    genapi::{Node, NodeValue},
+    payload::{ImageView, PayloadReceiver},
+    u3v::{self, ControlHandle, StreamHandle},
+    Camera, StreamError,
+};
+
+create_exception!(
+    cameleon,
+    CameleonError,
+    PyException,
+    "Raised when an operation on a camera fails."
+);
+
+impl From<crate::CameleonError> for PyErr {
+    fn from(err: crate::CameleonError) -> Self {
+        CameleonError::new_err(err.to_string())
+    }
+}
+
+/// A `U3V` camera.
+#[pyclass(name = "Camera", unsendable)]
+pub struct PyCamera {
+    inner: Camera<ControlHandle, StreamHandle>,
+    receiver: Option<PayloadReceiver>,
+}
+
+#[pymethods]
+impl PyCamera {
+    /// Opens the camera.
+    fn open(&mut self) -> PyResult<()> {
+        Ok(self.inner.open()?)
+    }
+
+    /// Closes the camera.
+    fn close(&mut self) -> PyResult<()> {
+        self.receiver = None;
+        Ok(self.inner.close()?)
+    }
+
+    /// Loads `GenApi` context, this is required to get or set features.
+    fn load_context(&mut self) -> PyResult<()> {
+        self.inner.load_context()?;
+        Ok(())
+    }
+
+    #[getter]
+    fn vendor_name(&self) -> &str {
+        &self.inner.info().vendor_name
+    }
+
+    #[getter]
+    fn model_name(&self) -> &str {
+        &self.inner.info().model_name
+    }
+
+    #[getter]
+    fn serial_number(&self) -> &str {
+        &self.inner.info().serial_number
+    }
+
+    /// Returns the value of the feature, or the symbolic name of the current entry if the feature
+    /// is an enumeration.
+    fn get(&mut self, py: Python<'_>, name: &str) -> PyResult<Py<PyAny>> {
+        let mut ctxt = self.inner.params_ctxt()?;
+        let node = find_node(&ctxt, name)?;
+        let value = ctxt
+            .read_node_value(node)?
+            .ok_or_else(|| PyTypeError::new_err(format!("`{}` is not readable", name)))?;
+
+        Ok(match value {
+            NodeValue::Integer(v) => v.into_pyobject(py)?.into_any().unbind(),
+            NodeValue::Float(v) => v.into_pyobject(py)?.into_any().unbind(),
+            NodeValue::Boolean(v) => v.into_pyobject(py)?.to_owned().into_any().unbind(),
+            NodeValue::String(v) | NodeValue::Enumeration(v) => {
+                v.into_pyobject(py)?.into_any().unbind()
+            }
+        })
+    }
+
+    /// Sets the value of the feature. Enumerations are set by the symbolic name of the entry.
+    fn set(&mut self, name: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let mut ctxt = self.inner.params_ctxt()?;
+        let node = find_node(&ctxt, name)?;
+
+        if let Some(node) = node.as_integer(&ctxt) {
+            node.set_value(&mut ctxt, value.extract()?)
+        } else if let Some(node) = node.as_float(&ctxt) {
+            node.set_value(&mut ctxt, value.extract()?)
+        } else if let Some(node) = node.as_boolean(&ctxt) {
+            node.set_value(&mut ctxt, value.extract()?)
+        } else if let Some(node) = node.as_string(&ctxt) {
+            node.set_value(&mut ctxt, value.extract()?)
+        } else if let Some(node) = node.as_enumeration(&ctxt) {
+            node.set_entry_by_symbolic(&mut ctxt, &value.extract::<String>()?)
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "`{}` doesn't have a settable value",
+                name
+            )));
+        }
+        .map_err(crate::CameleonError::from)?;
+
+        Ok(())
+    }
+
+    /// Executes the command feature.
+    fn execute(&mut self, name: &str) -> PyResult<()> {
+        let mut ctxt = self.inner.params_ctxt()?;
+        let node = find_node(&ctxt, name)?
+            .as_command(&ctxt)
+            .ok_or_else(|| PyTypeError::new_err(format!("`{}` is not a command", name)))?;
+        node.execute(&mut ctxt)
+            .map_err(crate::CameleonError::from)?;
+        Ok(())
+    }
+
+    /// Starts streaming, `cap` is the number of frames buffered in the receiver.
+    #[pyo3(signature = (cap = 3))]
+    fn start_streaming(&mut self, cap: usize) -> PyResult<()> {
+        self.receiver = Some(self.inner.start_streaming(cap)?);
+        Ok(())
+    }
+
+    fn stop_streaming(&mut self) -> PyResult<()> {
+        self.receiver = None;
+        Ok(self.inner.stop_streaming()?)
+    }
+
+    /// Waits for the next frame and returns its image as `numpy.ndarray`.
+    ///
+    /// The shape is `(height, width)` for single component formats, and
+    /// `(height, width, components)` otherwise.
+    fn receive(&mut self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let receiver = self
+            .receiver
+            .as_ref()
+            .ok_or_else(|| CameleonError::new_err("streaming is not started"))?;
+        let payload = py
+            .detach(|| receiver.recv_blocking())
+            .map_err(crate::CameleonError::from)?;
+
+        let image = match payload.as_ndarray() {
+            Some(ImageView::Mono8(view)) => to_pyarray(py, view.iter().copied(), view.shape()),
+            Some(ImageView::Mono16(view)) => to_pyarray(py, view.iter().copied(), view.shape()),
+            Some(ImageView::Color8(view)) => to_pyarray(py, view.iter().copied(), view.shape()),
+            Some(ImageView::Color16(view)) => to_pyarray(py, view.iter().copied(), view.shape()),
+            None => Err(CameleonError::new_err(
+                StreamError::InvalidPayload("payload doesn't contain a supported image".into())
+                    .to_string(),
+            )),
+        };
+        receiver.send_back(payload);
+        image
+    }
+}
+
+/// Enumerates all `U3V` cameras connected to the host.
+#[pyfunction]
+fn enumerate_cameras() -> PyResult<Vec<PyCamera>> {
+    Ok(u3v::enumerate_cameras()?
+        .into_iter()
+        .map(|inner| PyCamera {
+            inner,
+            receiver: None,
+        })
+        .collect())
+}
+
+/// Initializes `cameleon` Python module.
+#[pymodule]
+pub fn cameleon(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyCamera>()?;
+    m.add_function(wrap_pyfunction!(enumerate_cameras, m)?)?;
+    m.add("CameleonError", m.py().get_type::<CameleonError>())?;
+    Ok(())
+}
+
+fn find_node<Ctrl, Ctxt>(ctxt: &crate::genapi::ParamsCtxt<Ctrl, Ctxt>, name: &str) -> PyResult<Node>
+where
+    Ctxt: crate::genapi::GenApiCtxt,
+{
+    ctxt.node(name)
+        .ok_or_else(|| PyKeyError::new_err(name.to_string()))
+}
+
+fn to_pyarray<T: numpy::Element>(
+    py: Python<'_>,
+    data: impl Iterator<Item = T>,
+    shape: &[usize],
+) -> PyResult<Py<PyAny>> {
+    Ok(PyArray1::from_iter(py, data)
+        .reshape(shape)?
+        .into_any()
+        .unbind())
+}
@@ -0,0 +1,711 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides [`Recorder`] which writes received payloads to disk.
+//!
+//! Encoding and disk IO are done in a dedicated writer thread, and payloads are handed to the
+//! thread through a bounded queue. When the queue is full, [`DropPolicy`] decides whether the
+//! caller waits or a payload is dropped, so that recording doesn't need to block acquisition.
+//!
+//! Supported formats are
+//! * [`RecordFormat::Raw`]: Concatenated raw payloads with an index file. Always available.
+//! * [`RecordFormat::Tiff`]: Multipage TIFF. Requires `tiff` feature.
+//! * [`RecordFormat::Mjpeg`]: Motion JPEG, a sequence of concatenated JPEG images. Requires
+//!   `mjpeg` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cameleon::u3v;
+//! use cameleon::recorder::{Recorder, RecordFormat, RecorderConfig};
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! if cameras.is_empty() {
+//!     return;
+//! }
+//!
+//! let mut camera = cameras.pop().unwrap();
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//!
+//! let payload_rx = camera.start_streaming(3).unwrap();
+//! let recorder = Recorder::start_with_receiver(
+//!     "record.raw",
+//!     RecordFormat::Raw,
+//!     RecorderConfig::default(),
+//!     &payload_rx,
+//! )
+//! .unwrap();
+//!
+//! for _ in 0..100 {
+//!     let payload = payload_rx.recv_blocking().unwrap();
+//!     recorder.push(payload).unwrap();
+//! }
+//!
+//! let stats = recorder.finish().unwrap();
+//! println!("written: {}, dropped: {}", stats.written, stats.dropped);
+//!
+//! camera.close().unwrap();
+//! ```
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+use tracing::error;
+
+use crate::payload::{Payload, PayloadReceiver, PixelFormat};
+
+/// A specialized `Result` type for [`Recorder`].
+pub type RecorderResult<T> = std::result::Result<T, RecorderError>;
+
+/// An error type returned from [`Recorder`].
+#[derive(Debug, thiserror::Error)]
+pub enum RecorderError {
+    /// IO error.
+    #[error("input/output error: {0}")]
+    Io(#[from] io::Error),
+
+    /// Failed to encode a payload.
+    #[error("failed to encode payload: {0}")]
+    Encode(Cow<'static, str>),
+
+    /// The pixel format of a payload isn't supported by the record format.
+    #[error("pixel format `{0:?}` is not supported by the record format")]
+    UnsupportedPixelFormat(PixelFormat),
+
+    /// The payload doesn't contain an image though the record format requires it.
+    #[error("payload doesn't contain an image")]
+    NoImage,
+
+    /// The writer thread has been stopped, probably due to a previous error.
+    #[error("writer thread has been stopped")]
+    WriterStopped,
+
+    /// A panic has occurred in the writer thread.
+    #[error("a panic has occurred in writer thread")]
+    Poisoned,
+}
+
+/// Format of the recorded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Concatenates raw payloads into a single file.
+    ///
+    /// An index file is written next to the data file with `idx` extension.
+    /// Each line of the index file is
+    /// `frame,block_id,timestamp_ns,offset,size,width,height,pixel_format`, where `offset` and
+    /// `size` locate the payload in the data file. `width`, `height` and `pixel_format` are empty
+    /// if the payload doesn't contain an image.
+    Raw,
+
+    /// Multipage TIFF. Each payload is written as a page.
+    ///
+    /// Supports `Mono8`, `Mono10`, `Mono12`, `Mono14`, `Mono16`, `RGB8` and 8 bit `Bayer` formats.
+    #[cfg(feature = "tiff")]
+    Tiff,
+
+    /// Motion JPEG, a sequence of JPEG images concatenated into a single file.
+    ///
+    /// Supports `Mono8`, `RGB8` and 8 bit `Bayer` formats. `Bayer` images are encoded as is
+    /// without demosaicing.
+    #[cfg(feature = "mjpeg")]
+    Mjpeg {
+        /// JPEG quality between 1 and 100.
+        quality: u8,
+    },
+}
+
+/// Determines what happens when a payload is pushed to the full queue of [`Recorder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Blocks until the writer thread makes room in the queue.
+    Block,
+    /// Drops the pushed payload.
+    DropNewest,
+    /// Drops the oldest payload in the queue and enqueues the pushed payload.
+    DropOldest,
+}
+
+/// Configuration of [`Recorder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecorderConfig {
+    /// Maximum number of payloads waiting to be written.
+    pub queue_capacity: usize,
+    /// Policy applied when the queue is full.
+    pub drop_policy: DropPolicy,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 16,
+            drop_policy: DropPolicy::DropNewest,
+        }
+    }
+}
+
+/// Statistics of the recording.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecorderStats {
+    /// The number of payloads written to disk.
+    pub written: u64,
+    /// The number of payloads dropped due to the full queue.
+    pub dropped: u64,
+}
+
+/// Writes payloads to disk in a dedicated thread.
+///
+/// The recording is finalized by [`Recorder::finish`], which waits for all queued payloads to be
+/// written. Dropping the recorder also finalizes the recording, but errors are only logged.
+pub struct Recorder {
+    queue: Arc<Queue>,
+    handle: Option<thread::JoinHandle<RecorderResult<()>>>,
+    path: PathBuf,
+}
+
+impl Recorder {
+    /// Creates the file(s) at `path` and starts the writer thread.
+    ///
+    /// Payloads pushed to the recorder are dropped after they are written. Use
+    /// [`Recorder::start_with_receiver`] to record payloads received from a camera, otherwise
+    /// the buffer pool of the stream is drained by the recording.
+    pub fn start(
+        path: impl AsRef<Path>,
+        format: RecordFormat,
+        config: RecorderConfig,
+    ) -> RecorderResult<Self> {
+        Self::start_impl(path.as_ref(), format, config, None)
+    }
+
+    /// Same as [`Recorder::start`], but payloads are sent back to `payload_rx` for reuse once
+    /// they are written or dropped.
+    pub fn start_with_receiver(
+        path: impl AsRef<Path>,
+        format: RecordFormat,
+        config: RecorderConfig,
+        payload_rx: &PayloadReceiver,
+    ) -> RecorderResult<Self> {
+        Self::start_impl(path.as_ref(), format, config, Some(payload_rx.clone()))
+    }
+
+    fn start_impl(
+        path: &Path,
+        format: RecordFormat,
+        config: RecorderConfig,
+        payload_rx: Option<PayloadReceiver>,
+    ) -> RecorderResult<Self> {
+        let path = path.to_path_buf();
+        let writer = frame_writer(&path, format)?;
+        let queue = Arc::new(Queue::new(config, payload_rx));
+
+        let thread_queue = queue.clone();
+        let handle = thread::spawn(move || write_loop(&thread_queue, writer));
+
+        Ok(Self {
+            queue,
+            handle: Some(handle),
+            path,
+        })
+    }
+
+    /// Pushes a payload to the queue.
+    ///
+    /// Returns `false` if the payload or an older payload in the queue is dropped according to
+    /// [`DropPolicy`].
+    pub fn push(&self, payload: Payload) -> RecorderResult<bool> {
+        self.queue.push(payload)
+    }
+
+    /// Returns the current statistics of the recording.
+    pub fn stats(&self) -> RecorderStats {
+        self.queue.stats()
+    }
+
+    /// Returns the path of the recorded file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits for all queued payloads to be written and finalizes the file(s).
+    pub fn finish(mut self) -> RecorderResult<RecorderStats> {
+        self.join()?;
+        Ok(self.stats())
+    }
+
+    fn join(&mut self) -> RecorderResult<()> {
+        self.queue.close();
+        match self.handle.take() {
+            Some(handle) => handle.join().map_err(|_| RecorderError::Poisoned)?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.join() {
+            error!(?e)
+        }
+    }
+}
+
+struct Queue {
+    state: Mutex<QueueState>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    config: RecorderConfig,
+    /// Receives payloads which are no longer needed, so that the stream can reuse them.
+    payload_rx: Option<PayloadReceiver>,
+}
+
+struct QueueState {
+    payloads: VecDeque<Payload>,
+    closed: bool,
+    stats: RecorderStats,
+}
+
+impl Queue {
+    fn new(config: RecorderConfig, payload_rx: Option<PayloadReceiver>) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                payloads: VecDeque::with_capacity(config.queue_capacity),
+                closed: false,
+                stats: RecorderStats::default(),
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            config,
+            payload_rx,
+        }
+    }
+
+    fn push(&self, payload: Payload) -> RecorderResult<bool> {
+        let mut state = self.state.lock().map_err(|_| RecorderError::Poisoned)?;
+        let mut accepted = true;
+
+        while !state.closed && state.payloads.len() >= self.config.queue_capacity.max(1) {
+            match self.config.drop_policy {
+                DropPolicy::Block => {
+                    state = self
+                        .not_full
+                        .wait(state)
+                        .map_err(|_| RecorderError::Poisoned)?;
+                }
+                DropPolicy::DropNewest => {
+                    state.stats.dropped += 1;
+                    drop(state);
+                    self.send_back(payload);
+                    return Ok(false);
+                }
+                DropPolicy::DropOldest => {
+                    if let Some(oldest) = state.payloads.pop_front() {
+                        self.send_back(oldest);
+                    }
+                    state.stats.dropped += 1;
+                    accepted = false;
+                }
+            }
+        }
+
+        if state.closed {
+            drop(state);
+            self.send_back(payload);
+            return Err(RecorderError::WriterStopped);
+        }
+
+        state.payloads.push_back(payload);
+        self.not_empty.notify_one();
+        Ok(accepted)
+    }
+
+    /// Returns `None` if the queue is closed and all payloads are consumed.
+    fn pop(&self) -> RecorderResult<Option<Payload>> {
+        let mut state = self.state.lock().map_err(|_| RecorderError::Poisoned)?;
+        loop {
+            if let Some(payload) = state.payloads.pop_front() {
+                self.not_full.notify_one();
+                return Ok(Some(payload));
+            }
+            if state.closed {
+                return Ok(None);
+            }
+            state = self
+                .not_empty
+                .wait(state)
+                .map_err(|_| RecorderError::Poisoned)?;
+        }
+    }
+
+    fn close(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+        }
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    fn discard_all(&self) {
+        let mut discarded = VecDeque::new();
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+            discarded = std::mem::take(&mut state.payloads);
+            state.stats.dropped += discarded.len() as u64;
+        }
+        self.not_full.notify_all();
+        for payload in discarded {
+            self.send_back(payload);
+        }
+    }
+
+    fn send_back(&self, payload: Payload) {
+        if let Some(payload_rx) = &self.payload_rx {
+            payload_rx.send_back(payload);
+        }
+    }
+
+    fn count_written(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.stats.written += 1;
+        }
+    }
+
+    fn stats(&self) -> RecorderStats {
+        self.state
+            .lock()
+            .map(|state| state.stats)
+            .unwrap_or_default()
+    }
+}
+
+fn write_loop(queue: &Queue, mut writer: Box<dyn FrameWriter>) -> RecorderResult<()> {
+    let result = (|| {
+        while let Some(payload) = queue.pop()? {
+            let written = writer.write_frame(&payload);
+            queue.send_back(payload);
+            written?;
+            queue.count_written();
+        }
+        writer.finish()
+    })();
+
+    if let Err(e) = &result {
+        error!(?e);
+        // Reject further payloads so that the caller notices the failure.
+        queue.discard_all();
+    }
+    result
+}
+
+trait FrameWriter: Send {
+    fn write_frame(&mut self, payload: &Payload) -> RecorderResult<()>;
+
+    fn finish(&mut self) -> RecorderResult<()>;
+}
+
+fn frame_writer(path: &Path, format: RecordFormat) -> RecorderResult<Box<dyn FrameWriter>> {
+    Ok(match format {
+        RecordFormat::Raw => Box::new(RawWriter::new(path)?),
+        #[cfg(feature = "tiff")]
+        RecordFormat::Tiff => Box::new(tiff_writer::TiffWriter::new(path)?),
+        #[cfg(feature = "mjpeg")]
+        RecordFormat::Mjpeg { quality } => Box::new(mjpeg_writer::MjpegWriter::new(path, quality)?),
+    })
+}
+
+struct RawWriter {
+    data: BufWriter<File>,
+    index: BufWriter<File>,
+    frame: u64,
+    offset: u64,
+}
+
+impl RawWriter {
+    fn new(path: &Path) -> RecorderResult<Self> {
+        let data = BufWriter::new(File::create(path)?);
+        let mut index = BufWriter::new(File::create(path.with_extension("idx"))?);
+        writeln!(
+            index,
+            "frame,block_id,timestamp_ns,offset,size,width,height,pixel_format"
+        )?;
+
+        Ok(Self {
+            data,
+            index,
+            frame: 0,
+            offset: 0,
+        })
+    }
+}
+
+impl FrameWriter for RawWriter {
+    fn write_frame(&mut self, payload: &Payload) -> RecorderResult<()> {
+        let bytes = payload.payload();
+        self.data.write_all(bytes)?;
+
+        write!(
+            self.index,
+            "{},{},{},{},{},",
+            self.frame,
+            payload.id(),
+            payload.timestamp().as_nanos(),
+            self.offset,
+            bytes.len()
+        )?;
+        match payload.image_info() {
+            Some(info) => writeln!(
+                self.index,
+                "{},{},{:#010x}",
+                info.width,
+                info.height,
+                u32::from(info.pixel_format)
+            )?,
+            None => writeln!(self.index, ",,")?,
+        }
+
+        self.frame += 1;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> RecorderResult<()> {
+        self.data.flush()?;
+        self.index.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tiff")]
+mod tiff_writer {
+    use std::{fs::File, path::Path};
+
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    use super::{FrameWriter, Payload, PixelFormat, RecorderError, RecorderResult};
+
+    pub(super) struct TiffWriter {
+        encoder: TiffEncoder<File>,
+        /// `TiffEncoder` doesn't give back the inner writer, so keep a handle to sync the file
+        /// in `finish`.
+        file: File,
+    }
+
+    impl TiffWriter {
+        pub(super) fn new(path: &Path) -> RecorderResult<Self> {
+            let file = File::create(path)?;
+            let encoder = TiffEncoder::new(file.try_clone()?).map_err(encode_err)?;
+            Ok(Self { encoder, file })
+        }
+    }
+
+    impl FrameWriter for TiffWriter {
+        fn write_frame(&mut self, payload: &Payload) -> RecorderResult<()> {
+            use PixelFormat::{
+                BayerBG8, BayerGB8, BayerGR8, BayerRG8, Mono10, Mono12, Mono14, Mono16, Mono8, RGB8,
+            };
+
+            let info = payload.image_info().ok_or(RecorderError::NoImage)?;
+            let image = payload.image().ok_or(RecorderError::NoImage)?;
+            let width = info.width as u32;
+            let height = info.height as u32;
+
+            match info.pixel_format {
+                Mono8 | BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => self
+                    .encoder
+                    .write_image::<colortype::Gray8>(width, height, image),
+                RGB8 => self
+                    .encoder
+                    .write_image::<colortype::RGB8>(width, height, image),
+                Mono10 | Mono12 | Mono14 | Mono16 => {
+                    let image: Vec<u16> = image
+                        .chunks_exact(2)
+                        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                        .collect();
+                    self.encoder
+                        .write_image::<colortype::Gray16>(width, height, &image)
+                }
+                other => return Err(RecorderError::UnsupportedPixelFormat(other)),
+            }
+            .map_err(encode_err)
+        }
+
+        fn finish(&mut self) -> RecorderResult<()> {
+            Ok(self.file.sync_all()?)
+        }
+    }
+
+    fn encode_err(err: tiff::TiffError) -> RecorderError {
+        match err {
+            tiff::TiffError::IoError(err) => RecorderError::Io(err),
+            other => RecorderError::Encode(other.to_string().into()),
+        }
+    }
+}
+
+#[cfg(feature = "mjpeg")]
+mod mjpeg_writer {
+    use std::{
+        convert::TryInto,
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+    };
+
+    use jpeg_encoder::{ColorType, Encoder};
+
+    use super::{FrameWriter, Payload, PixelFormat, RecorderError, RecorderResult};
+
+    pub(super) struct MjpegWriter {
+        file: BufWriter<File>,
+        quality: u8,
+    }
+
+    impl MjpegWriter {
+        pub(super) fn new(path: &Path, quality: u8) -> RecorderResult<Self> {
+            let file = BufWriter::new(File::create(path)?);
+            Ok(Self { file, quality })
+        }
+    }
+
+    impl FrameWriter for MjpegWriter {
+        fn write_frame(&mut self, payload: &Payload) -> RecorderResult<()> {
+            use PixelFormat::{BayerBG8, BayerGB8, BayerGR8, BayerRG8, Mono8, RGB8};
+
+            let info = payload.image_info().ok_or(RecorderError::NoImage)?;
+            let image = payload.image().ok_or(RecorderError::NoImage)?;
+            let color_type = match info.pixel_format {
+                Mono8 | BayerRG8 | BayerGR8 | BayerGB8 | BayerBG8 => ColorType::Luma,
+                RGB8 => ColorType::Rgb,
+                other => return Err(RecorderError::UnsupportedPixelFormat(other)),
+            };
+            let width: u16 = info
+                .width
+                .try_into()
+                .map_err(|_| RecorderError::Encode("image width exceeds JPEG limitation".into()))?;
+            let height: u16 = info.height.try_into().map_err(|_| {
+                RecorderError::Encode("image height exceeds JPEG limitation".into())
+            })?;
+
+            Encoder::new(&mut self.file, self.quality)
+                .encode(image, width, height, color_type)
+                .map_err(|e| RecorderError::Encode(e.to_string().into()))
+        }
+
+        fn finish(&mut self) -> RecorderResult<()> {
+            Ok(self.file.flush()?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::payload::PayloadBuilder;
+
+    fn mono8_payload(id: u64, width: usize, height: usize) -> Payload {
+        PayloadBuilder::new(id)
+            .image(
+                width,
+                height,
+                PixelFormat::Mono8,
+                vec![id as u8; width * height],
+            )
+            .timestamp(Duration::from_nanos(id * 10))
+            .build()
+    }
+
+    #[test]
+    fn test_raw_recorder() {
+        let path =
+            std::env::temp_dir().join(format!("cameleon_recorder_{}.raw", std::process::id()));
+        let config = RecorderConfig {
+            queue_capacity: 4,
+            drop_policy: DropPolicy::Block,
+        };
+        let recorder = Recorder::start(&path, RecordFormat::Raw, config).unwrap();
+        for id in 0..3 {
+            assert!(recorder.push(mono8_payload(id, 4, 2)).unwrap());
+        }
+        let stats = recorder.finish().unwrap();
+        assert_eq!(stats.written, 3);
+        assert_eq!(stats.dropped, 0);
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), 24);
+        assert_eq!(&data[8..16], &[1; 8]);
+
+        let index_path = path.with_extension("idx");
+        let index = std::fs::read_to_string(&index_path).unwrap();
+        let lines: Vec<_> = index.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "1,1,10,8,8,4,2,0x01080001");
+
+        std::fs::remove_file(path).ok();
+        std::fs::remove_file(index_path).ok();
+    }
+
+    #[test]
+    fn test_payloads_are_sent_back() {
+        let path = std::env::temp_dir().join(format!(
+            "cameleon_recorder_send_back_{}.raw",
+            std::process::id()
+        ));
+        let (sender, payload_rx) = crate::payload::channel(4, 4);
+        let config = RecorderConfig {
+            queue_capacity: 4,
+            drop_policy: DropPolicy::Block,
+        };
+        let recorder =
+            Recorder::start_with_receiver(&path, RecordFormat::Raw, config, &payload_rx).unwrap();
+        for id in 0..3 {
+            assert!(recorder.push(mono8_payload(id, 4, 2)).unwrap());
+        }
+        recorder.finish().unwrap();
+
+        for id in 0..3 {
+            assert_eq!(sender.try_recv().unwrap().id(), id);
+        }
+
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(path.with_extension("idx")).ok();
+    }
+
+    #[test]
+    fn test_drop_policy() {
+        let config = RecorderConfig {
+            queue_capacity: 2,
+            drop_policy: DropPolicy::DropOldest,
+        };
+        let queue = Queue::new(config, None);
+        assert!(queue.push(mono8_payload(0, 1, 1)).unwrap());
+        assert!(queue.push(mono8_payload(1, 1, 1)).unwrap());
+        assert!(!queue.push(mono8_payload(2, 1, 1)).unwrap());
+        assert_eq!(queue.pop().unwrap().unwrap().id(), 1);
+        assert_eq!(queue.stats().dropped, 1);
+
+        let config = RecorderConfig {
+            queue_capacity: 1,
+            drop_policy: DropPolicy::DropNewest,
+        };
+        let queue = Queue::new(config, None);
+        assert!(queue.push(mono8_payload(0, 1, 1)).unwrap());
+        assert!(!queue.push(mono8_payload(1, 1, 1)).unwrap());
+        assert_eq!(queue.pop().unwrap().unwrap().id(), 0);
+
+        queue.close();
+        assert!(queue.pop().unwrap().is_none());
+        assert!(matches!(
+            queue.push(mono8_payload(2, 1, 1)),
+            Err(RecorderError::WriterStopped)
+        ));
+    }
+}
@@ -0,0 +1,339 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains CPU affinity and priority controls of stream threads.
+//!
+//! Pinning the stream receive thread to dedicated cores and raising its priority reduces frame
+//! drops on a loaded system, where the thread may otherwise be preempted while the device is
+//! waiting for the host to read its buffer.
+//!
+//! Affinity, priority and CPU time are only supported on Linux. On other platforms, applying a
+//! non-default [`ThreadConfig`] fails and no CPU usage is reported.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon::u3v;
+//! use cameleon::scheduling::{ThreadConfig, ThreadPriority};
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! let mut camera = cameras.pop().unwrap();
+//! camera.strm.set_thread_config(ThreadConfig {
+//!     cpus: vec![2, 3],
+//!     priority: Some(ThreadPriority::Nice(-10)),
+//! });
+//!
+//! camera.open().unwrap();
+//! camera.load_context().unwrap();
+//! let _receiver = camera.start_streaming(3).unwrap();
+//! for usage in camera.strm.thread_cpu_usage() {
+//!     println!("{}: {:.1}%", usage.name, usage.usage() * 100.0);
+//! }
+//! ```
+
+use std::{
+    io,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Scheduling configuration applied to a stream thread when it's spawned.
+///
+/// The default configuration leaves the scheduling of the thread to the OS.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadConfig {
+    /// Indices of cores the thread is pinned to. The thread may run on any core if empty.
+    pub cpus: Vec<usize>,
+
+    /// Priority of the thread. The priority inherited from the process is used if `None`.
+    pub priority: Option<ThreadPriority>,
+}
+
+/// Priority of a thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// Nice value of the thread, from -20 (highest) to 19 (lowest).
+    ///
+    /// Negative values usually require `CAP_SYS_NICE`.
+    Nice(i32),
+
+    /// Static priority under `SCHED_FIFO` real-time policy, from 1 (lowest) to 99 (highest).
+    ///
+    /// Usually requires `CAP_SYS_NICE`. A real-time thread which never blocks can starve the
+    /// whole system, use with care.
+    RealTime(i32),
+}
+
+/// CPU time consumed by a stream thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadCpuUsage {
+    /// Name of the thread, e.g. `stream`.
+    pub name: String,
+
+    /// CPU time consumed by the thread in both user and kernel mode.
+    pub cpu_time: Duration,
+
+    /// Elapsed time since the thread is spawned.
+    pub elapsed: Duration,
+}
+
+impl ThreadCpuUsage {
+    /// Returns the average usage of a core by the thread since it's spawned, from 0.0 to 1.0.
+    #[must_use]
+    pub fn usage(&self) -> f64 {
+        if self.elapsed.as_nanos() == 0 {
+            0.0
+        } else {
+            self.cpu_time.as_secs_f64() / self.elapsed.as_secs_f64()
+        }
+    }
+}
+
+impl ThreadConfig {
+    /// Spawns a thread running `f` after applying the configuration to it.
+    ///
+    /// The thread is registered to `registry` while running. Returns an error without running `f`
+    /// if the configuration can't be applied.
+    pub(crate) fn spawn<F>(
+        &self,
+        name: &str,
+        registry: &ThreadRegistry,
+        f: F,
+    ) -> io::Result<thread::JoinHandle<()>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let config = self.clone();
+        let registry = registry.clone();
+        let name = name.to_string();
+        let (applied_tx, applied_rx) = mpsc::sync_channel(1);
+
+        let handle = thread::Builder::new().name(name.clone()).spawn(move || {
+            let applied = config.apply_to_current();
+            let failed = applied.is_err();
+            applied_tx.send(applied).ok();
+            if failed {
+                return;
+            }
+
+            let _guard = registry.register(name);
+            f();
+        })?;
+
+        applied_rx
+            .recv()
+            .map_err(|_| io::Error::other("stream thread exited abruptly"))??;
+        Ok(handle)
+    }
+
+    fn apply_to_current(&self) -> io::Result<()> {
+        if !self.cpus.is_empty() {
+            imp::set_affinity(&self.cpus)?;
+        }
+        if let Some(priority) = self.priority {
+            imp::set_priority(priority)?;
+        }
+        Ok(())
+    }
+}
+
+/// Registry of running stream threads to query their CPU usage.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ThreadRegistry(Arc<Mutex<Vec<ThreadEntry>>>);
+
+#[derive(Debug)]
+struct ThreadEntry {
+    id: u64,
+    name: String,
+    os_id: Option<i64>,
+    started: Instant,
+}
+
+/// Unregisters the thread from the registry on drop.
+struct RegistryGuard {
+    registry: ThreadRegistry,
+    id: u64,
+}
+
+impl Drop for RegistryGuard {
+    fn drop(&mut self) {
+        if let Ok(mut entries) = self.registry.0.lock() {
+            entries.retain(|entry| entry.id != self.id);
+        }
+    }
+}
+
+impl ThreadRegistry {
+    /// Registers the current thread.
+    fn register(&self, name: String) -> RegistryGuard {
+        let mut entries = self.0.lock().unwrap();
+        let id = entries.iter().map(|entry| entry.id + 1).max().unwrap_or(0);
+        entries.push(ThreadEntry {
+            id,
+            name,
+            os_id: imp::current_thread_id(),
+            started: Instant::now(),
+        });
+        RegistryGuard {
+            registry: self.clone(),
+            id,
+        }
+    }
+
+    /// Returns CPU usage of the running threads.
+    pub(crate) fn cpu_usage(&self) -> Vec<ThreadCpuUsage> {
+        let entries = self.0.lock().unwrap();
+        entries
+            .iter()
+            .filter_map(|entry| {
+                Some(ThreadCpuUsage {
+                    name: entry.name.clone(),
+                    cpu_time: imp::thread_cpu_time(entry.os_id?)?,
+                    elapsed: entry.started.elapsed(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::mem;
+
+    use super::{io, Duration, ThreadPriority};
+
+    pub(super) fn set_affinity(cpus: &[usize]) -> io::Result<()> {
+        unsafe {
+            let mut set: libc::cpu_set_t = mem::zeroed();
+            libc::CPU_ZERO(&mut set);
+            for &cpu in cpus {
+                if cpu >= libc::CPU_SETSIZE as usize {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid cpu index: {}", cpu),
+                    ));
+                }
+                libc::CPU_SET(cpu, &mut set);
+            }
+            // `0` specifies the calling thread.
+            if libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn set_priority(priority: ThreadPriority) -> io::Result<()> {
+        match priority {
+            ThreadPriority::Nice(nice) => {
+                // On Linux, `PRIO_PROCESS` with a thread id changes the nice value of the thread.
+                let tid = current_thread_id().unwrap_or(0);
+                let res = unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) };
+                if res != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            ThreadPriority::RealTime(priority) => {
+                let param = libc::sched_param {
+                    sched_priority: priority,
+                };
+                let res = unsafe {
+                    libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param)
+                };
+                if res != 0 {
+                    return Err(io::Error::from_raw_os_error(res));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn current_thread_id() -> Option<i64> {
+        Some(unsafe { libc::syscall(libc::SYS_gettid) })
+    }
+
+    pub(super) fn thread_cpu_time(tid: i64) -> Option<Duration> {
+        let stat = std::fs::read_to_string(format!("/proc/self/task/{}/stat", tid)).ok()?;
+        let ticks = parse_cpu_ticks(&stat)?;
+        let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks_per_sec <= 0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(ticks as f64 / ticks_per_sec as f64))
+    }
+
+    /// Returns the sum of `utime` and `stime` in `/proc/<pid>/task/<tid>/stat`.
+    pub(super) fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+        // `comm` field may contain spaces and parentheses, so fields are counted from the last
+        // `)`. `state` is the first field after it, and `utime` and `stime` are 12th and 13th.
+        let mut fields = stat[stat.rfind(')')? + 1..].split_whitespace().skip(11);
+        let utime: u64 = fields.next()?.parse().ok()?;
+        let stime: u64 = fields.next()?.parse().ok()?;
+        Some(utime + stime)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use super::{io, Duration, ThreadPriority};
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "thread scheduling isn't supported on this platform",
+        )
+    }
+
+    pub(super) fn set_affinity(_cpus: &[usize]) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(super) fn set_priority(_priority: ThreadPriority) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(super) fn current_thread_id() -> Option<i64> {
+        None
+    }
+
+    pub(super) fn thread_cpu_time(_tid: i64) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn() {
+        let registry = ThreadRegistry::default();
+        let (tx, rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let handle = ThreadConfig::default()
+            .spawn("stream", &registry, move || {
+                tx.send(()).unwrap();
+                done_rx.recv().ok();
+            })
+            .unwrap();
+
+        rx.recv().unwrap();
+        let usage = registry.cpu_usage();
+        if cfg!(target_os = "linux") {
+            assert_eq!(usage.len(), 1);
+            assert_eq!(usage[0].name, "stream");
+        }
+
+        done_tx.send(()).unwrap();
+        handle.join().unwrap();
+        assert!(registry.cpu_usage().is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_cpu_ticks() {
+        let stat = "1234 (stream (1)) S 1 1234 1234 0 -1 4194560 100 0 0 0 25 17 0 0 20 0 1 0";
+        assert_eq!(imp::parse_cpu_ticks(stat), Some(42));
+    }
+}
@@ -0,0 +1,292 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains bandwidth management of U3V cameras streaming concurrently.
+//!
+//! Cameras connected to the same USB bus share the bandwidth of its host controller. Without
+//! limits, a camera which starts streaming first can occupy the controller and starve the others.
+//! [`BandwidthManager`] splits the bandwidth of each bus between its cameras through
+//! `DeviceLinkThroughputLimit`, and sizes payload transfers in proportion to the allocated
+//! throughput so that transfers of the cameras interleave.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon::u3v::{self, BandwidthManager};
+//!
+//! let mut cameras = u3v::enumerate_cameras().unwrap();
+//! for camera in &mut cameras {
+//!     camera.open().unwrap();
+//!     camera.load_context().unwrap();
+//! }
+//!
+//! let allocations = BandwidthManager::new().distribute(&mut cameras).unwrap();
+//! for allocation in allocations {
+//!     println!("{:?}", allocation);
+//! }
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+use cameleon_device::u3v::{BusSpeed, UsbTopology};
+
+use super::{register_map::Abrm, ControlHandle, SirmOverrides, StreamHandle};
+use crate::{genapi::GenApiCtxt, CameleonResult, Camera, ControlError};
+
+/// Duration of the stream which a payload transfer carries at the allocated throughput.
+const TRANSFER_WINDOW: Duration = Duration::from_millis(2);
+
+/// Upper bound of the payload transfer size set by [`BandwidthManager`].
+const MAX_PAYLOAD_TRANSFER_SIZE: u64 = 1 << 20;
+
+/// Distributes the bandwidth of USB host controllers between U3V cameras.
+///
+/// Cameras are grouped by the bus they are connected to, and the bandwidth of each bus is shared
+/// equally between its cameras. If a camera can't use its share because of the range of
+/// `DeviceLinkThroughputLimit`, the rest is shared between the other cameras on the bus.
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthManager {
+    /// Bandwidth of each bus in bytes per second, overriding the default one.
+    bus_bandwidth: HashMap<u8, u64>,
+}
+
+/// Bandwidth allocated to a camera by [`BandwidthManager::distribute`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BandwidthAllocation {
+    /// Port path of the camera, see [`UsbTopology::port_path`].
+    pub port_path: String,
+
+    /// Value written to `DeviceLinkThroughputLimit` in bytes per second.
+    /// `None` if the camera doesn't have the node.
+    pub throughput_limit: Option<u64>,
+
+    /// Payload transfer size used when the camera starts streaming.
+    pub payload_transfer_size: u32,
+}
+
+impl BandwidthManager {
+    /// Constructs a manager which uses the default bandwidth of each bus.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the usable bandwidth of the bus in bytes per second.
+    ///
+    /// By default, the bandwidth is estimated from the negotiated speed of the cameras on the bus,
+    /// which may be too optimistic for a host controller shared with other devices.
+    pub fn set_bus_bandwidth(&mut self, bus_number: u8, bandwidth: u64) {
+        self.bus_bandwidth.insert(bus_number, bandwidth);
+    }
+
+    /// Returns the usable bandwidth of the bus the device is connected to in bytes per second.
+    #[must_use]
+    pub fn bus_bandwidth(&self, topology: &UsbTopology) -> u64 {
+        if let Some(bandwidth) = self.bus_bandwidth.get(&topology.bus_number) {
+            return *bandwidth;
+        }
+
+        match topology.negotiated_speed {
+            Some(BusSpeed::LowSpeed) => 150_000,
+            Some(BusSpeed::FullSpeed) => 1_000_000,
+            Some(BusSpeed::HighSpeed) => 40_000_000,
+            Some(BusSpeed::SuperSpeed) | None => 400_000_000,
+            Some(BusSpeed::SuperSpeedPlus) => 900_000_000,
+        }
+    }
+
+    /// Distributes the bandwidth between `cameras`.
+    ///
+    /// `DeviceLinkThroughputLimitMode` is turned on and `DeviceLinkThroughputLimit` is set to the
+    /// allocated throughput if the camera has the nodes. The payload transfer size is overridden
+    /// through [`ControlHandle::set_sirm_overrides`], so it takes effect when the camera starts
+    /// streaming.
+    ///
+    /// The cameras must be opened, have their contexts loaded, and not be streaming.
+    pub fn distribute<Ctxt>(
+        &self,
+        cameras: &mut [Camera<ControlHandle, StreamHandle, Ctxt>],
+    ) -> CameleonResult<Vec<BandwidthAllocation>>
+    where
+        Ctxt: GenApiCtxt,
+    {
+        let mut demands = Vec::with_capacity(cameras.len());
+        for camera in cameras.iter_mut() {
+            demands.push(throughput_range(camera)?);
+        }
+
+        let mut buses: HashMap<u8, Vec<usize>> = HashMap::new();
+        for (i, camera) in cameras.iter().enumerate() {
+            let bus_number = camera.ctrl.device_info().topology.bus_number;
+            buses.entry(bus_number).or_default().push(i);
+        }
+
+        let mut shares = vec![0; cameras.len()];
+        for indices in buses.values() {
+            let bandwidth = self.bus_bandwidth(&cameras[indices[0]].ctrl.device_info().topology);
+            let ranges: Vec<_> = indices
+                .iter()
+                .map(|i| demands[*i].map_or((0, u64::MAX), |d| (d.min, d.max)))
+                .collect();
+            for (i, share) in indices.iter().zip(share(bandwidth, &ranges)) {
+                shares[*i] = share;
+            }
+        }
+
+        let mut allocations = Vec::with_capacity(cameras.len());
+        for ((camera, demand), share) in cameras.iter_mut().zip(demands).zip(shares) {
+            let throughput_limit = match demand {
+                Some(demand) => Some(set_throughput_limit(camera, demand, share)?),
+                None => None,
+            };
+            let throughput = throughput_limit.unwrap_or(share);
+
+            let ctrl = &mut camera.ctrl;
+            let max_packet_size = u64::from(ctrl.device_info().topology.max_packet_size);
+            let alignment = Abrm::new(ctrl)?
+                .sbrm(ctrl)?
+                .sirm(ctrl)?
+                .ok_or_else(|| {
+                    ControlError::InvalidDevice("the U3V device doesn't have `SIRM`".into())
+                })?
+                .payload_size_alignment(ctrl)? as u64;
+            let payload_transfer_size =
+                payload_transfer_size(throughput, lcm(max_packet_size.max(1), alignment));
+            ctrl.set_sirm_overrides(SirmOverrides {
+                payload_transfer_size: Some(payload_transfer_size),
+                ..ctrl.sirm_overrides()
+            });
+
+            allocations.push(BandwidthAllocation {
+                port_path: ctrl.device_info().topology.port_path(),
+                throughput_limit,
+                payload_transfer_size,
+            });
+        }
+
+        Ok(allocations)
+    }
+}
+
+/// Range of `DeviceLinkThroughputLimit` of a camera.
+#[derive(Debug, Clone, Copy)]
+struct ThroughputRange {
+    min: u64,
+    max: u64,
+    inc: u64,
+}
+
+fn throughput_range<Ctxt>(
+    camera: &mut Camera<ControlHandle, StreamHandle, Ctxt>,
+) -> CameleonResult<Option<ThroughputRange>>
+where
+    Ctxt: GenApiCtxt,
+{
+    let mut ctxt = camera.params_ctxt()?;
+    let node = match ctxt
+        .node("DeviceLinkThroughputLimit")
+        .and_then(|n| n.as_integer(&ctxt))
+    {
+        Some(node) => node,
+        None => return Ok(None),
+    };
+
+    let min = node.min(&mut ctxt)?.max(0) as u64;
+    let max = (node.max(&mut ctxt)?.max(0) as u64).max(min);
+    let inc = node.inc(&mut ctxt)?.unwrap_or(1).max(1) as u64;
+    Ok(Some(ThroughputRange { min, max, inc }))
+}
+
+/// Writes the throughput limit and returns the written value.
+fn set_throughput_limit<Ctxt>(
+    camera: &mut Camera<ControlHandle, StreamHandle, Ctxt>,
+    range: ThroughputRange,
+    share: u64,
+) -> CameleonResult<u64>
+where
+    Ctxt: GenApiCtxt,
+{
+    let mut ctxt = camera.params_ctxt()?;
+    if let Some(mode) = ctxt
+        .node("DeviceLinkThroughputLimitMode")
+        .and_then(|n| n.as_enumeration(&ctxt))
+    {
+        mode.set_entry_by_symbolic(&mut ctxt, "On")?;
+    }
+
+    // Round down to the increment, but never below the minimum.
+    let limit = share.clamp(range.min, range.max);
+    let limit = range.min + (limit - range.min) / range.inc * range.inc;
+    let node = expect_node!(&ctxt, "DeviceLinkThroughputLimit", as_integer);
+    node.set_value(&mut ctxt, limit as i64)?;
+    Ok(limit)
+}
+
+/// Shares `bandwidth` between demands whose allocation must be in `(min, max)`.
+///
+/// Demands whose maximum is below the equal share are satisfied first, and the rest is shared
+/// between the others.
+fn share(bandwidth: u64, ranges: &[(u64, u64)]) -> Vec<u64> {
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by_key(|i| ranges[*i].1);
+
+    let mut shares = vec![0; ranges.len()];
+    let mut remaining = bandwidth;
+    for (served, i) in order.iter().enumerate() {
+        let (min, max) = ranges[*i];
+        let fair = remaining / (ranges.len() - served) as u64;
+        let share = fair.clamp(min, max.max(min));
+        shares[*i] = share;
+        remaining = remaining.saturating_sub(share);
+    }
+    shares
+}
+
+/// Returns the payload transfer size which carries the stream of `TRANSFER_WINDOW` at
+/// `throughput`, aligned to `unit`.
+fn payload_transfer_size(throughput: u64, unit: u64) -> u32 {
+    let size = u128::from(throughput) * TRANSFER_WINDOW.as_micros() / 1_000_000;
+    let size = (size.min(u128::from(MAX_PAYLOAD_TRANSFER_SIZE)) as u64) / unit * unit;
+    size.max(unit) as u32
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        let r = x % y;
+        x = y;
+        y = r;
+    }
+    a / x * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share() {
+        assert_eq!(share(300, &[(0, 1000); 3]), vec![100, 100, 100]);
+
+        // Unused share of the first camera is given to the others.
+        assert_eq!(
+            share(300, &[(0, 50), (0, 1000), (0, 1000)]),
+            vec![50, 125, 125]
+        );
+
+        // Minimum is always allocated.
+        assert_eq!(share(100, &[(80, 1000), (0, 1000)]), vec![80, 20]);
+    }
+
+    #[test]
+    fn test_payload_transfer_size() {
+        assert_eq!(lcm(1024, 64), 1024);
+        // 200 MB/s for 2 ms.
+        assert_eq!(payload_transfer_size(200_000_000, 1024), 399_360);
+        assert_eq!(payload_transfer_size(1_000, 1024), 1024);
+        assert_eq!(
+            payload_transfer_size(u64::MAX, 1024),
+            MAX_PAYLOAD_TRANSFER_SIZE as u32
+        );
+    }
+}
@@ -5,6 +5,7 @@
 //! This module contains low level device control implementation for `U3V` device.
 
 use std::{
+    collections::HashMap,
     convert::TryInto,
     io::Read,
     sync::{Arc, Mutex},
@@ -15,11 +16,19 @@ use cameleon_device::{
     u3v,
     u3v::protocol::{ack, cmd},
 };
-use tracing::error;
+use tracing::{debug, error, trace, warn};
 
-use super::register_map::{self, Abrm, ManifestTable, Sbrm, Sirm};
+use super::{
+    device_lock::DeviceLock,
+    register_map::{self, Abrm, ManifestTable, Sbrm, Sirm},
+};
 
-use crate::{camera::DeviceControl, genapi::CompressionType, ControlError, ControlResult};
+use crate::{
+    camera::DeviceControl,
+    genapi::CompressionType,
+    logging::{HexDump, GENCP_TARGET},
+    CancellationToken, ControlError, ControlResult,
+};
 
 /// Initial timeout duration for transaction between device and host.
 /// This value is temporarily used until the device's bootstrap register value is read.
@@ -35,6 +44,19 @@ const INITIAL_MAXIMUM_ACK_LENGTH: u32 = 128;
 
 const PAYLOAD_TRANSFER_SIZE: u32 = 1024 * 64;
 
+/// Length of prefix and `CCD` of an acknowledge.
+const ACK_HEADER_LENGTH: usize = 12;
+
+/// Length of `SCD` of a pending acknowledge.
+const PENDING_ACK_SCD_LENGTH: u16 = 4;
+
+/// Offset of the request id in a command packet, i.e. prefix(4bytes) + flags(2bytes) +
+/// command_id(2bytes) + scd_len(2bytes).
+const CMD_REQUEST_ID_OFFSET: usize = 10;
+
+/// Maximum number of stale acknowledges discarded while waiting for an acknowledge.
+const MAX_STALE_ACKS: usize = 16;
+
 /// This handle provides low level API to read and write data from the device.  
 /// See [`ControlHandle::abrm`] and [`register_map`] which provide more
 /// convenient way to communicate with `u3v` specific registers.
@@ -82,6 +104,15 @@ pub struct ControlHandle {
     sirm: Option<Sirm>,
     /// Cache for `ManifestTable`.
     manifest_table: Option<ManifestTable>,
+
+    /// Token to cancel operations.
+    cancellation_token: Option<CancellationToken>,
+
+    /// Timeout which overrides `config.timeout_duration`.
+    timeout_override: Option<Duration>,
+
+    /// Advisory lock held while the device is opened.
+    device_lock: Option<DeviceLock>,
 }
 
 impl ControlHandle {
@@ -132,6 +163,81 @@ impl ControlHandle {
         self.config.retry_count = count;
     }
 
+    /// Returns [`RetryPolicy`] applied to each command sent to the device.
+    #[must_use]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.config.retry_policy
+    }
+
+    /// Set [`RetryPolicy`] applied to each command sent to the device.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.config.retry_policy = policy;
+    }
+
+    /// The maximum number of `ReadMem` commands issued by [`DeviceControl::read_batch`] before
+    /// waiting for their acknowledges.
+    #[must_use]
+    pub fn max_outstanding_requests(&self) -> u16 {
+        self.config.max_outstanding_requests
+    }
+
+    /// Set the maximum number of `ReadMem` commands issued by [`DeviceControl::read_batch`] before
+    /// waiting for their acknowledges.
+    ///
+    /// Pipelining is opt-in. `GenCP` doesn't require a device to accept a command before the
+    /// acknowledge of the previous one is sent, so the default value is `1`, which issues commands
+    /// one by one like [`DeviceControl::read`]. Increase the value only if the device is known to
+    /// queue multiple commands. `0` is treated as `1`.
+    ///
+    /// If a window of commands fails, the remaining acknowledges are drained and the whole window
+    /// is retried according to [`ControlHandle::retry_policy`].
+    pub fn set_max_outstanding_requests(&mut self, count: u16) {
+        self.config.max_outstanding_requests = count;
+    }
+
+    /// Returns [`SirmOverrides`] applied when streaming is enabled.
+    #[must_use]
+    pub fn sirm_overrides(&self) -> SirmOverrides {
+        self.config.sirm_overrides
+    }
+
+    /// Set [`SirmOverrides`] applied when streaming is enabled.
+    ///
+    /// Values not overridden are computed from the required sizes and the payload size alignment
+    /// reported by the device. See [`SirmLayout::compute`].
+    pub fn set_sirm_overrides(&mut self, overrides: SirmOverrides) {
+        self.config.sirm_overrides = overrides;
+    }
+
+    /// Returns [`AckValidation`] applied to each acknowledge received from the device.
+    #[must_use]
+    pub fn ack_validation(&self) -> AckValidation {
+        self.config.ack_validation
+    }
+
+    /// Set [`AckValidation`] applied to each acknowledge received from the device.
+    ///
+    /// Default is [`AckValidation::Disabled`].
+    pub fn set_ack_validation(&mut self, validation: AckValidation) {
+        self.config.ack_validation = validation;
+    }
+
+    /// Returns `true` if the device is opened even when another process holds the device lock.
+    #[must_use]
+    pub fn force_open(&self) -> bool {
+        self.config.force_open
+    }
+
+    /// Set whether the device is opened even when another process holds the device lock.
+    ///
+    /// When the device is opened, the handle takes an advisory lock keyed by the device GUID, so
+    /// that another process trying to open the same device fails with [`ControlError::Busy`]
+    /// instead of fighting over the control channel. Set `true` to ignore the lock, e.g. when a
+    /// crashed process is known to have left the device behind.
+    pub fn set_force_open(&mut self, force: bool) {
+        self.config.force_open = force;
+    }
+
     /// Returns the device info of the handle.
     pub fn device_info(&self) -> &u3v::DeviceInfo {
         &self.info
@@ -199,9 +305,25 @@ impl ControlHandle {
             sbrm: None,
             sirm: None,
             manifest_table: None,
+            cancellation_token: None,
+            timeout_override: None,
+            device_lock: None,
         })
     }
 
+    /// Returns the timeout of each transaction, see [`DeviceControl::set_timeout_override`].
+    fn transaction_timeout(&self) -> Duration {
+        self.timeout_override
+            .unwrap_or(self.config.timeout_duration)
+    }
+
+    fn assert_not_cancelled(&self) -> ControlResult<()> {
+        match &self.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(ControlError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
     fn assert_open(&self) -> ControlResult<()> {
         if self.is_opened() {
             Ok(())
@@ -210,6 +332,18 @@ impl ControlHandle {
         }
     }
 
+    /// Acquires the device lock, see [`ControlHandle::set_force_open`].
+    fn acquire_device_lock(&self) -> ControlResult<Option<DeviceLock>> {
+        match DeviceLock::try_acquire(&self.info.guid) {
+            Ok(lock) => Ok(Some(lock)),
+            Err(ControlError::Busy) if self.config.force_open => {
+                warn!("the device is locked by another process, but opening it forcibly");
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
     fn initialize_config(&mut self) -> ControlResult<()> {
         let abrm = self.abrm()?;
         let sbrm = abrm.sbrm(self)?;
@@ -230,6 +364,7 @@ impl ControlHandle {
         T: cmd::CommandScd,
         U: ack::ParseScd<'a>,
     {
+        let expected = ExpectedAck::of(&cmd);
         let cmd = cmd.finalize(self.next_req_id);
         let cmd_len = cmd.cmd_len();
         let ack_len = cmd.maximum_ack_len();
@@ -237,64 +372,316 @@ impl ControlHandle {
             self.buffer.resize(std::cmp::max(cmd_len, ack_len), 0);
         }
 
-        // Serialize and send command.
+        // Serialize command.
         cmd.serialize(self.buffer.as_mut_slice())?;
-        self.inner
-            .send(&self.buffer[..cmd_len], self.config.timeout_duration)?;
+        let mut cmd_buf = self.buffer[..cmd_len].to_vec();
+
+        // Send command and receive ack, retry according to the retry policy.
+        let is_write = matches!(
+            expected.kind,
+            ack::ScdKind::WriteMem | ack::ScdKind::WriteMemStacked
+        );
+        let mut attempt = 1;
+        let recv_len = loop {
+            self.assert_not_cancelled()?;
+            match self.transact(&cmd_buf, expected) {
+                Ok(recv_len) => break recv_len,
+                Err(err)
+                    if self
+                        .config
+                        .retry_policy
+                        .should_retry(attempt, &err, is_write) =>
+                {
+                    let backoff = self.config.retry_policy.backoff(attempt);
+                    warn!(?err, attempt, ?backoff, "retry command");
+                    std::thread::sleep(backoff);
+                    attempt += 1;
+
+                    // Resend with a new request id so that a late acknowledge of the previous
+                    // attempt is discarded as stale instead of being taken for this one.
+                    self.next_req_id = self.next_req_id.wrapping_add(1);
+                    cmd_buf[CMD_REQUEST_ID_OFFSET..CMD_REQUEST_ID_OFFSET + 2]
+                        .copy_from_slice(&self.next_req_id.to_le_bytes());
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        self.next_req_id = self.next_req_id.wrapping_add(1);
+
+        // This codes seems weird due to a lifetime problem.
+        // `ack::AckPacket::parse` is a fast operation, so it's ok to call it repeatedly.
+        Ok(ack::AckPacket::parse(&self.buffer[0..recv_len])
+            .unwrap()
+            .scd_as()?)
+    }
+
+    /// Sends a serialized command and receives its ack into the buffer.
+    /// Returns the length of the received ack.
+    fn transact(
+        &mut self,
+        cmd_buf: &[u8],
+        expected: ExpectedAck,
+    ) -> Result<usize, TransactionError> {
+        self.trace_packet("cmd", cmd_buf);
+        self.inner.send(cmd_buf, self.transaction_timeout())?;
 
         // Receive ack and interpret the packet.
+        let timeout = self.transaction_timeout();
         let mut retry_count = self.config.retry_count;
-        let mut ok = None;
+        let mut stale_acks = 0;
         while retry_count > 0 {
-            let recv_len = self
-                .inner
-                .recv(&mut self.buffer, self.config.timeout_duration)?;
+            let recv_len = self.inner.recv(&mut self.buffer, timeout)?;
+            self.trace_packet("ack", &self.buffer[0..recv_len]);
 
             let ack = ack::AckPacket::parse(&self.buffer[0..recv_len])?;
+            // Resynchronize by discarding acknowledges of earlier requests, e.g. a late
+            // acknowledge of an attempt that timed out.
+            if is_stale_request_id(self.next_req_id, ack.request_id())
+                && stale_acks < MAX_STALE_ACKS
+            {
+                debug!(request_id = ack.request_id(), "discard stale acknowledge");
+                stale_acks += 1;
+                continue;
+            }
             self.verify_ack(&ack)?;
+            self.validate_ack(&ack, recv_len, expected)?;
 
             // Retry up to retry count.
             if ack.scd_kind() == ack::ScdKind::Pending {
                 let pending_ack: ack::Pending = ack.scd_as()?;
                 std::thread::sleep(pending_ack.timeout);
+                self.assert_not_cancelled()?;
                 retry_count -= 1;
                 continue;
             }
 
-            self.next_req_id = self.next_req_id.wrapping_add(1);
-            ok = Some(recv_len);
-            break;
+            return Ok(recv_len);
         }
 
-        // This codes seems weird due to a lifetime problem.
-        // `ack::AckPacket::parse` is a fast operation, so it's ok to call it repeatedly.
-        if let Some(recv_len) = ok {
-            Ok(ack::AckPacket::parse(&self.buffer[0..recv_len])
-                .unwrap()
-                .scd_as()?)
-        } else {
-            Err(ControlError::Io(anyhow::Error::msg(
-                "the number of times pending was returned exceeds the retry_count.",
-            )))
-        }
+        Err(ControlError::Io(anyhow::Error::msg(
+            "the number of times pending was returned exceeds the retry_count.",
+        ))
+        .into())
     }
 
-    fn verify_ack(&self, ack: &ack::AckPacket) -> ControlResult<()> {
-        let status = ack.status().kind();
-        if status != ack::StatusKind::GenCp(ack::GenCpStatus::Success) {
-            return Err(ControlError::Io(anyhow::Error::msg(format!(
-                "invalid status: {:?}",
-                ack.status().kind()
-            ))));
+    /// Emits a hex dump of a `GenCP` packet, see [`crate::logging`].
+    fn trace_packet(&self, kind: &str, packet: &[u8]) {
+        trace!(
+            target: GENCP_TARGET,
+            serial = %self.info.serial_number,
+            channel = "control",
+            "{}{}",
+            kind,
+            HexDump(packet)
+        );
+    }
+
+    fn verify_ack(&self, ack: &ack::AckPacket) -> Result<(), TransactionError> {
+        let status = ack.status();
+        if !status.is_success() {
+            return Err(TransactionError::Status {
+                code: status.code(),
+                kind: status.kind(),
+                request_id: ack.request_id(),
+            });
         }
 
         if ack.request_id() != self.next_req_id {
-            return Err(ControlError::Io(anyhow::Error::msg("request id mismatch")));
+            return Err(ControlError::RequestIdMismatch {
+                expected: self.next_req_id,
+                actual: ack.request_id(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks the acknowledge against `expected` according to [`ControlHandle::ack_validation`].
+    fn validate_ack(
+        &self,
+        ack: &ack::AckPacket,
+        recv_len: usize,
+        expected: ExpectedAck,
+    ) -> ControlResult<()> {
+        let validation = self.config.ack_validation;
+        if validation == AckValidation::Disabled {
+            return Ok(());
+        }
+
+        match expected.check(ack, recv_len) {
+            Ok(()) => Ok(()),
+            Err(reason) if validation == AckValidation::Lenient => {
+                warn!(
+                    request_id = ack.request_id(),
+                    %reason,
+                    "protocol violation in acknowledge"
+                );
+                Ok(())
+            }
+            Err(reason) => Err(ControlError::ProtocolViolation {
+                request_id: ack.request_id(),
+                reason: reason.into(),
+            }),
+        }
+    }
+
+    /// Issues up to `max_outstanding_requests` `ReadMem` commands at once, then receives their
+    /// acknowledges and matches them by request id.
+    fn read_pipelined(&mut self, chunks: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        let window = std::cmp::max(self.config.max_outstanding_requests, 1) as usize;
+
+        for window_chunks in chunks.chunks_mut(window) {
+            // Retry the whole window according to the retry policy. `ReadMem` is idempotent.
+            let mut attempt = 1;
+            loop {
+                self.assert_not_cancelled()?;
+
+                let base_req_id = self.next_req_id;
+                let mut outstanding = HashMap::with_capacity(window_chunks.len());
+                let result = self.read_window(window_chunks, &mut outstanding);
+
+                // Advance the request id past the whole window even if the transaction failed, so
+                // that acknowledges still in flight are discarded as stale afterwards.
+                self.next_req_id = base_req_id.wrapping_add(window_chunks.len() as u16);
+                let err = match result {
+                    Ok(()) => break,
+                    Err(err) => err,
+                };
+                self.drain_acks(outstanding.len());
+
+                if !self.config.retry_policy.should_retry(attempt, &err, false) {
+                    return Err(err.into());
+                }
+                let backoff = self.config.retry_policy.backoff(attempt);
+                warn!(?err, attempt, ?backoff, "retry pipelined read");
+                std::thread::sleep(backoff);
+                attempt += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends `ReadMem` commands for `chunks` starting from the current request id and receives
+    /// their acknowledges.
+    ///
+    /// Request ids of which the acknowledge isn't received yet are left in `outstanding`.
+    fn read_window(
+        &mut self,
+        chunks: &mut [(u64, &mut [u8])],
+        outstanding: &mut HashMap<u16, usize>,
+    ) -> Result<(), TransactionError> {
+        use cmd::CommandScd;
+
+        // Serialize and send commands.
+        for (i, (address, buf)) in chunks.iter().enumerate() {
+            let req_id = self.next_req_id.wrapping_add(i as u16);
+            let cmd = cmd::ReadMem::new(*address, buf.len().try_into().unwrap()).finalize(req_id);
+            let cmd_len = cmd.cmd_len();
+            let buf_len = std::cmp::max(cmd_len, cmd.maximum_ack_len());
+            if self.buffer.len() < buf_len {
+                self.buffer.resize(buf_len, 0);
+            }
+            cmd.serialize(self.buffer.as_mut_slice())?;
+            self.trace_packet("cmd", &self.buffer[..cmd_len]);
+            self.inner
+                .send(&self.buffer[..cmd_len], self.transaction_timeout())?;
+            outstanding.insert(req_id, i);
+        }
+
+        // Receive acks in any order.
+        let mut retry_count = self.config.retry_count;
+        let mut recv_timeout = self.transaction_timeout();
+        let mut stale_acks = 0;
+        while !outstanding.is_empty() {
+            let recv_len = self.inner.recv(&mut self.buffer, recv_timeout)?;
+            self.trace_packet("ack", &self.buffer[0..recv_len]);
+            let ack = ack::AckPacket::parse(&self.buffer[0..recv_len])?;
+
+            let idx = match outstanding.get(&ack.request_id()) {
+                Some(idx) => *idx,
+                None if is_stale_request_id(self.next_req_id, ack.request_id())
+                    && stale_acks < MAX_STALE_ACKS =>
+                {
+                    debug!(request_id = ack.request_id(), "discard stale acknowledge");
+                    stale_acks += 1;
+                    continue;
+                }
+                None => {
+                    return Err(ControlError::RequestIdMismatch {
+                        expected: self.next_req_id,
+                        actual: ack.request_id(),
+                    }
+                    .into())
+                }
+            };
+
+            let status = ack.status();
+            if !status.is_success() {
+                outstanding.remove(&ack.request_id());
+                return Err(TransactionError::Status {
+                    code: status.code(),
+                    kind: status.kind(),
+                    request_id: ack.request_id(),
+                });
+            }
+
+            let expected = ExpectedAck {
+                kind: ack::ScdKind::ReadMem,
+                scd_len: chunks[idx].1.len().try_into().unwrap(),
+            };
+            self.validate_ack(&ack, recv_len, expected)?;
+
+            // The device sends the acknowledge within the timeout specified in the pending
+            // acknowledge.
+            if ack.scd_kind() == ack::ScdKind::Pending {
+                if retry_count == 0 {
+                    return Err(ControlError::Io(anyhow::Error::msg(
+                        "the number of times pending was returned exceeds the retry_count.",
+                    ))
+                    .into());
+                }
+                let pending_ack: ack::Pending = ack.scd_as()?;
+                recv_timeout = std::cmp::max(recv_timeout, pending_ack.timeout);
+                retry_count -= 1;
+                continue;
+            }
+
+            outstanding.remove(&ack.request_id());
+            let read_ack: ack::ReadMem = ack.scd_as()?;
+            let buf = &mut chunks[idx].1;
+            if read_ack.data.len() != buf.len() {
+                return Err(ControlError::Io(anyhow::Error::msg(
+                    "read mem failed: read length mismatch",
+                ))
+                .into());
+            }
+            buf.copy_from_slice(read_ack.data);
         }
 
         Ok(())
     }
 
+    /// Receives and discards up to `count` acknowledges left after a failed transaction.
+    ///
+    /// Gives up when no acknowledge arrives within the transaction timeout.
+    fn drain_acks(&mut self, mut count: usize) {
+        let timeout = self.transaction_timeout();
+        while count > 0 {
+            let recv_len = match self.inner.recv(&mut self.buffer, timeout) {
+                Ok(recv_len) => recv_len,
+                Err(_) => return,
+            };
+            self.trace_packet("discard ack", &self.buffer[0..recv_len]);
+            // A pending acknowledge is followed by the actual one.
+            match ack::AckPacket::parse(&self.buffer[0..recv_len]) {
+                Ok(ack) if ack.scd_kind() == ack::ScdKind::Pending => {}
+                _ => count -= 1,
+            }
+        }
+    }
+
     fn verify_xml(&mut self, xml: &[u8], ent: register_map::ManifestEntry) -> ControlResult<()> {
         use sha1::Digest;
 
@@ -326,16 +713,24 @@ macro_rules! unwrap_or_log {
 }
 
 impl DeviceControl for ControlHandle {
+    #[tracing::instrument(skip(self),
+                          level = "debug",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
     fn open(&mut self) -> ControlResult<()> {
         if self.is_opened() {
             return Ok(());
         }
 
+        // The lock is released if opening the device fails.
+        let device_lock = unwrap_or_log!(self.acquire_device_lock());
         unwrap_or_log!(self.inner.open());
         // Clean up control channel state.
         unwrap_or_log!(self.inner.set_halt(self.config.timeout_duration));
         unwrap_or_log!(self.inner.clear_halt());
         unwrap_or_log!(self.initialize_config());
+        self.device_lock = device_lock;
 
         Ok(())
     }
@@ -344,13 +739,25 @@ impl DeviceControl for ControlHandle {
         self.inner.is_opened()
     }
 
+    #[tracing::instrument(skip(self),
+                          level = "debug",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
     fn close(&mut self) -> ControlResult<()> {
         if self.is_opened() {
             unwrap_or_log!(self.inner.close());
         }
+        // Release the lock so that other processes can open the device.
+        drop(self.device_lock.take());
         Ok(())
     }
 
+    #[tracing::instrument(skip(self, data),
+                          level = "trace",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
     fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()> {
         unwrap_or_log!(self.assert_open());
 
@@ -370,6 +777,11 @@ impl DeviceControl for ControlHandle {
         Ok(())
     }
 
+    #[tracing::instrument(skip(self, buf),
+                          level = "trace",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
     fn read(&mut self, mut address: u64, buf: &mut [u8]) -> ControlResult<()> {
         unwrap_or_log!(self.assert_open());
 
@@ -383,6 +795,10 @@ impl DeviceControl for ControlHandle {
 
             let cmd = cmd::ReadMem::new(address, read_len);
             let ack: ack::ReadMem = unwrap_or_log!(self.send_cmd(cmd));
+            if ack.data.len() != buf_chunk.len() {
+                let err_msg = "read mem failed: read length mismatch";
+                return Err(ControlError::Io(anyhow::Error::msg(err_msg)));
+            }
             buf_chunk.copy_from_slice(ack.data);
             address += read_len as u64;
         }
@@ -390,6 +806,62 @@ impl DeviceControl for ControlHandle {
         Ok(())
     }
 
+    fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+        self.cancellation_token = token;
+    }
+
+    fn set_timeout_override(&mut self, timeout: Option<Duration>) {
+        self.timeout_override = timeout;
+    }
+
+    fn timeout_override(&self) -> Option<Duration> {
+        self.timeout_override
+    }
+
+    fn set_node_timeout(&mut self, name: &str, timeout: Option<Duration>) {
+        match timeout {
+            Some(timeout) => self.config.node_timeouts.insert(name.to_string(), timeout),
+            None => self.config.node_timeouts.remove(name),
+        };
+    }
+
+    fn node_timeout(&self, name: &str) -> Option<Duration> {
+        self.config.node_timeouts.get(name).copied()
+    }
+
+    fn latch_timestamp(&mut self) -> ControlResult<Option<u64>> {
+        let abrm = self.abrm()?;
+        abrm.set_timestamp_latch_bit(self)?;
+        Ok(Some(abrm.timestamp(self)?))
+    }
+
+    #[tracing::instrument(skip(self, requests),
+                          level = "trace",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
+    fn read_batch(&mut self, requests: &mut [(u64, &mut [u8])]) -> ControlResult<()> {
+        unwrap_or_log!(self.assert_open());
+
+        // Split requests into chunks that fit in an ack.
+        let max_read_len =
+            cmd::ReadMem::maximum_read_length(self.config.maximum_ack_length as usize) as usize;
+        let mut chunks = vec![];
+        for (address, buf) in requests.iter_mut() {
+            for (i, buf_chunk) in buf.chunks_mut(max_read_len).enumerate() {
+                chunks.push((*address + (i * max_read_len) as u64, buf_chunk));
+            }
+        }
+
+        unwrap_or_log!(self.read_pipelined(&mut chunks));
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self),
+                          level = "debug",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
     fn genapi(&mut self) -> ControlResult<String> {
         fn zip_err(err: impl std::fmt::Debug) -> ControlError {
             ControlError::InvalidDevice(format!("zipped xml file is broken: {:?}", err).into())
@@ -445,6 +917,11 @@ impl DeviceControl for ControlHandle {
         }
     }
 
+    #[tracing::instrument(skip(self),
+                          level = "debug",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
     fn enable_streaming(&mut self) -> ControlResult<()> {
         let sirm = unwrap_or_log!(self.sirm());
 
@@ -456,45 +933,37 @@ impl DeviceControl for ControlHandle {
         }
 
         let payload_alignment = unwrap_or_log!(sirm.payload_size_alignment(self));
-        macro_rules! align {
-            ($expr:expr, $ty: ty) => {
-                // Payload alignment is always power of two.
-                ($expr + (payload_alignment as $ty - 1)) & !(payload_alignment as $ty - 1)
-            };
-        }
-
         let required_leader_size = unwrap_or_log!(sirm.required_leader_size(self));
         let required_payload_size = unwrap_or_log!(sirm.required_payload_size(self));
-        let required_trailer_size = unwrap_or_log!(sirm.required_leader_size(self));
-
-        let payload_transfer_size = align!(PAYLOAD_TRANSFER_SIZE, u32);
-        let payload_transfer_count = (required_payload_size / payload_transfer_size as u64) as u32;
-        let payload_final_transfer1_size =
-            align!(required_payload_size % payload_transfer_size as u64, u64) as u32;
-        let payload_final_transfer2_size = 0;
-
-        let maximum_leader_size = if required_leader_size == 0 {
-            payload_transfer_size
-        } else {
-            align!(required_leader_size, u32)
-        };
-        let maximum_trailer_size = if required_trailer_size == 0 {
-            payload_transfer_size
-        } else {
-            align!(required_trailer_size, u32)
-        };
-
-        unwrap_or_log!(sirm.set_payload_transfer_size(self, payload_transfer_size));
-        unwrap_or_log!(sirm.set_payload_transfer_count(self, payload_transfer_count));
-        unwrap_or_log!(sirm.set_payload_final_transfer1_size(self, payload_final_transfer1_size));
-        unwrap_or_log!(sirm.set_payload_final_transfer2_size(self, payload_final_transfer2_size));
-        unwrap_or_log!(sirm.set_maximum_leader_size(self, maximum_leader_size));
-        unwrap_or_log!(sirm.set_maximum_trailer_size(self, maximum_trailer_size));
+        let required_trailer_size = unwrap_or_log!(sirm.required_trailer_size(self));
+        let layout = unwrap_or_log!(SirmLayout::compute(
+            required_leader_size,
+            required_payload_size,
+            required_trailer_size,
+            payload_alignment,
+            &self.config.sirm_overrides,
+        ));
+
+        unwrap_or_log!(sirm.set_payload_transfer_size(self, layout.payload_transfer_size));
+        unwrap_or_log!(sirm.set_payload_transfer_count(self, layout.payload_transfer_count));
+        unwrap_or_log!(
+            sirm.set_payload_final_transfer1_size(self, layout.payload_final_transfer1_size)
+        );
+        unwrap_or_log!(
+            sirm.set_payload_final_transfer2_size(self, layout.payload_final_transfer2_size)
+        );
+        unwrap_or_log!(sirm.set_maximum_leader_size(self, layout.maximum_leader_size));
+        unwrap_or_log!(sirm.set_maximum_trailer_size(self, layout.maximum_trailer_size));
         unwrap_or_log!(sirm.enable_stream(self));
 
         Ok(())
     }
 
+    #[tracing::instrument(skip(self),
+                          level = "debug",
+                          fields(serial = %self.info.serial_number,
+                                 model = %self.info.model_name,
+                                 channel = "control"))]
     fn disable_streaming(&mut self) -> ControlResult<()> {
         let sirm = unwrap_or_log!(self.sirm());
         sirm.disable_stream(self)
@@ -559,9 +1028,37 @@ impl SharedControlHandle {
         #[must_use]
         pub fn retry_count(&self) -> u16,
         /// Thread safe version of [`ControlHandle::set_retry_count`].
-        pub fn set_retry_count(&self, count: u16) -> ()
+        pub fn set_retry_count(&self, count: u16) -> (),
+        /// Thread safe version of [`ControlHandle::set_retry_policy`].
+        pub fn set_retry_policy(&self, policy: RetryPolicy) -> (),
+        /// Thread safe version of [`ControlHandle::max_outstanding_requests`].
+        #[must_use]
+        pub fn max_outstanding_requests(&self) -> u16,
+        /// Thread safe version of [`ControlHandle::set_max_outstanding_requests`].
+        pub fn set_max_outstanding_requests(&self, count: u16) -> (),
+        /// Thread safe version of [`ControlHandle::sirm_overrides`].
+        #[must_use]
+        pub fn sirm_overrides(&self) -> SirmOverrides,
+        /// Thread safe version of [`ControlHandle::set_sirm_overrides`].
+        pub fn set_sirm_overrides(&self, overrides: SirmOverrides) -> (),
+        /// Thread safe version of [`ControlHandle::ack_validation`].
+        #[must_use]
+        pub fn ack_validation(&self) -> AckValidation,
+        /// Thread safe version of [`ControlHandle::set_ack_validation`].
+        pub fn set_ack_validation(&self, validation: AckValidation) -> (),
+        /// Thread safe version of [`ControlHandle::force_open`].
+        #[must_use]
+        pub fn force_open(&self) -> bool,
+        /// Thread safe version of [`ControlHandle::set_force_open`].
+        pub fn set_force_open(&self, force: bool) -> ()
     );
 
+    /// Thread safe version of [`ControlHandle::retry_policy`].
+    #[must_use]
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.0.lock().unwrap().retry_policy().clone()
+    }
+
     /// Returns the device info of the handle.
     pub fn device_info(&self) -> u3v::DeviceInfo {
         self.0.lock().unwrap().device_info().clone()
@@ -570,7 +1067,9 @@ impl SharedControlHandle {
 
 impl DeviceControl for SharedControlHandle {
     impl_shared_control_handle! {
-        fn is_opened(&self) -> bool
+        fn is_opened(&self) -> bool,
+        fn timeout_override(&self) -> Option<Duration>,
+        fn node_timeout(&self, name: &str) -> Option<Duration>
     }
 
     impl_shared_control_handle! {
@@ -578,12 +1077,349 @@ impl DeviceControl for SharedControlHandle {
         fn close(&mut self) -> ControlResult<()>,
         fn read(&mut self, address: u64, buf: &mut [u8]) -> ControlResult<()>,
         fn write(&mut self, address: u64, data: &[u8]) -> ControlResult<()>,
+        fn read_batch(&mut self, requests: &mut [(u64, &mut [u8])]) -> ControlResult<()>,
+        fn set_cancellation_token(&mut self, token: Option<CancellationToken>) -> (),
+        fn set_timeout_override(&mut self, timeout: Option<Duration>) -> (),
+        fn set_node_timeout(&mut self, name: &str, timeout: Option<Duration>) -> (),
+        fn latch_timestamp(&mut self) -> ControlResult<Option<u64>>,
         fn genapi(&mut self) -> ControlResult<String>,
         fn enable_streaming(&mut self) -> ControlResult<()>,
         fn disable_streaming(&mut self) -> ControlResult<()>
     }
 }
 
+/// How strictly acknowledges received from the device are validated.
+///
+/// When validation is enabled, the length of each acknowledge is checked against the length
+/// declared in its `CCD`, and the command id and `SCD` length are checked against the command
+/// which the acknowledge responds to. This helps to debug flaky cables and buggy firmware.
+///
+/// # Examples
+/// ```no_run
+/// use cameleon::u3v::{self, AckValidation};
+///
+/// let mut cameras = u3v::enumerate_cameras().unwrap();
+/// let mut camera = cameras.pop().unwrap();
+///
+/// camera.ctrl.set_ack_validation(AckValidation::Strict);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AckValidation {
+    /// Acknowledges are trusted as long as they can be parsed.
+    #[default]
+    Disabled,
+    /// Violations are logged as warnings, and the acknowledge is used as is.
+    Lenient,
+    /// Violations are reported as [`ControlError::ProtocolViolation`].
+    Strict,
+}
+
+/// Properties of the acknowledge expected for a command.
+#[derive(Debug, Clone, Copy)]
+struct ExpectedAck {
+    kind: ack::ScdKind,
+    scd_len: u16,
+}
+
+impl ExpectedAck {
+    fn of(cmd: &impl cmd::CommandScd) -> Self {
+        let kind = match cmd.scd_kind() {
+            cmd::ScdKind::ReadMem => ack::ScdKind::ReadMem,
+            cmd::ScdKind::WriteMem => ack::ScdKind::WriteMem,
+            cmd::ScdKind::ReadMemStacked => ack::ScdKind::ReadMemStacked,
+            cmd::ScdKind::WriteMemStacked => ack::ScdKind::WriteMemStacked,
+        };
+        Self {
+            kind,
+            scd_len: cmd.ack_scd_len(),
+        }
+    }
+
+    /// Returns the description of the violation if `ack` doesn't match the expectation.
+    fn check(self, ack: &ack::AckPacket, recv_len: usize) -> Result<(), String> {
+        let scd_len = ack.ccd().scd_len();
+        let declared_len = ACK_HEADER_LENGTH + scd_len as usize;
+        if recv_len != declared_len {
+            return Err(format!(
+                "received {} bytes, but CCD declares {} bytes",
+                recv_len, declared_len
+            ));
+        }
+
+        let (kind, expected_scd_len) = match ack.scd_kind() {
+            ack::ScdKind::Pending => (ack::ScdKind::Pending, PENDING_ACK_SCD_LENGTH),
+            kind => (kind, self.scd_len),
+        };
+        if kind != ack::ScdKind::Pending && kind != self.kind {
+            return Err(format!(
+                "expected {:?} acknowledge, but got {:?}",
+                self.kind, kind
+            ));
+        }
+        if scd_len != expected_scd_len {
+            return Err(format!(
+                "expected SCD length {} for {:?} acknowledge, but got {}",
+                expected_scd_len, kind, scd_len
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Policy to retry a command when a transient error occurs.
+///
+/// A command is retried when the device returns one of [`RetryPolicy::retryable_statuses`], or
+/// when a transport error, e.g. timeout, occurs and [`RetryPolicy::retry_on_transport_error`] is
+/// `true`. The interval between attempts grows exponentially from
+/// [`RetryPolicy::initial_backoff`] up to [`RetryPolicy::max_backoff`].
+///
+/// A write command may have reached the device even when its acknowledge is lost, so writes are
+/// retried after a transport error only if [`RetryPolicy::retry_writes`] is `true`. Each retry is
+/// sent with a new request id, and a late acknowledge of the previous attempt is discarded.
+///
+/// NOTE: This policy is independent of [`ControlHandle::retry_count`] which is only for pending
+/// acknowledge.
+///
+/// # Examples
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use cameleon::u3v::{self, RetryPolicy};
+///
+/// let mut cameras = u3v::enumerate_cameras().unwrap();
+/// let mut camera = cameras.pop().unwrap();
+///
+/// camera.ctrl.set_retry_policy(RetryPolicy {
+///     max_attempts: 5,
+///     initial_backoff: Duration::from_millis(20),
+///     ..RetryPolicy::default()
+/// });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts including the first one. `1` disables retry.
+    pub max_attempts: u32,
+    /// Interval before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper limit of the interval between attempts.
+    pub max_backoff: Duration,
+    /// `GenCP` statuses returned from the device that are considered transient.
+    pub retryable_statuses: Vec<ack::GenCpStatus>,
+    /// Retry when a transport error, e.g. timeout or USB IO error, occurs.
+    pub retry_on_transport_error: bool,
+    /// Also retry write commands when a transport error occurs. Enable this only if all the
+    /// registers written through the handle are idempotent.
+    pub retry_writes: bool,
+}
+
+impl RetryPolicy {
+    /// Returns a policy that never retries.
+    #[must_use]
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the interval before the retry following `attempt`. `attempt` starts from 1.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1_u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    fn should_retry(&self, attempt: u32, err: &TransactionError, is_write: bool) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+
+        match err {
+            TransactionError::Status {
+                kind: ack::StatusKind::GenCp(status),
+                ..
+            } => self.retryable_statuses.contains(status),
+            TransactionError::Status { .. } => false,
+            TransactionError::Control(ControlError::Timeout | ControlError::Io(_)) => {
+                self.retry_on_transport_error && (!is_write || self.retry_writes)
+            }
+            TransactionError::Control(_) => false,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(200),
+            retryable_statuses: vec![ack::GenCpStatus::Busy],
+            retry_on_transport_error: true,
+            retry_writes: false,
+        }
+    }
+}
+
+/// Overrides of the values written to `SIRM` when streaming is enabled.
+///
+/// `None` means the value is computed automatically. Overridden values must be multiples of the
+/// payload size alignment of the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SirmOverrides {
+    /// Size of each payload transfer.
+    pub payload_transfer_size: Option<u32>,
+    /// Maximum size of leader.
+    pub maximum_leader_size: Option<u32>,
+    /// Maximum size of trailer.
+    pub maximum_trailer_size: Option<u32>,
+}
+
+/// Values written to `SIRM` when streaming is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SirmLayout {
+    /// Maximum size of leader.
+    pub maximum_leader_size: u32,
+    /// Maximum size of trailer.
+    pub maximum_trailer_size: u32,
+    /// Size of each payload transfer.
+    pub payload_transfer_size: u32,
+    /// The number of payload transfers of `payload_transfer_size`.
+    pub payload_transfer_count: u32,
+    /// Size of the first final transfer.
+    pub payload_final_transfer1_size: u32,
+    /// Size of the second final transfer.
+    pub payload_final_transfer2_size: u32,
+}
+
+impl SirmLayout {
+    /// Computes the layout from the required sizes and the payload size alignment read from
+    /// `SIRM`.
+    ///
+    /// Every size is aligned to `alignment`, and the sum of the payload transfers covers
+    /// `required_payload_size`. Returns an error if `alignment` isn't a power of two or an
+    /// overridden value isn't aligned.
+    pub fn compute(
+        required_leader_size: u32,
+        required_payload_size: u64,
+        required_trailer_size: u32,
+        alignment: usize,
+        overrides: &SirmOverrides,
+    ) -> ControlResult<Self> {
+        if !alignment.is_power_of_two() {
+            return Err(ControlError::InvalidDevice(
+                format!(
+                    "payload size alignment must be a power of two: {}",
+                    alignment
+                )
+                .into(),
+            ));
+        }
+        let alignment = alignment as u64;
+        let align = |size: u64| (size + (alignment - 1)) & !(alignment - 1);
+        let to_u32 = |size: u64| -> ControlResult<u32> {
+            size.try_into().map_err(|_| {
+                ControlError::InvalidData(format!("SIRM value overflows: {}", size).into())
+            })
+        };
+        let verify = |name: &str, size: Option<u32>| -> ControlResult<Option<u64>> {
+            match size {
+                Some(size) if size == 0 || u64::from(size) % alignment != 0 => {
+                    Err(ControlError::InvalidData(
+                        format!(
+                            "{} must be a non-zero multiple of {}: {}",
+                            name, alignment, size
+                        )
+                        .into(),
+                    ))
+                }
+                size => Ok(size.map(u64::from)),
+            }
+        };
+
+        let payload_transfer_size =
+            verify("payload transfer size", overrides.payload_transfer_size)?
+                .unwrap_or_else(|| align(u64::from(PAYLOAD_TRANSFER_SIZE)));
+        let maximum_leader_size = verify("maximum leader size", overrides.maximum_leader_size)?
+            .unwrap_or_else(|| match required_leader_size {
+                0 => payload_transfer_size,
+                size => align(u64::from(size)),
+            });
+        let maximum_trailer_size = verify("maximum trailer size", overrides.maximum_trailer_size)?
+            .unwrap_or_else(|| match required_trailer_size {
+                0 => payload_transfer_size,
+                size => align(u64::from(size)),
+            });
+
+        Ok(Self {
+            maximum_leader_size: to_u32(maximum_leader_size)?,
+            maximum_trailer_size: to_u32(maximum_trailer_size)?,
+            payload_transfer_size: to_u32(payload_transfer_size)?,
+            payload_transfer_count: to_u32(required_payload_size / payload_transfer_size)?,
+            payload_final_transfer1_size: to_u32(align(
+                required_payload_size % payload_transfer_size,
+            ))?,
+            payload_final_transfer2_size: 0,
+        })
+    }
+}
+
+/// Returns `true` if `actual` is the request id of a request issued before `expected`.
+fn is_stale_request_id(expected: u16, actual: u16) -> bool {
+    let behind = expected.wrapping_sub(actual);
+    behind != 0 && behind < 0x8000
+}
+
+/// An error occurred in a single transaction.
+enum TransactionError {
+    /// The device returned an ack with non-success status.
+    Status {
+        code: u16,
+        kind: ack::StatusKind,
+        request_id: u16,
+    },
+    Control(ControlError),
+}
+
+impl From<ControlError> for TransactionError {
+    fn from(err: ControlError) -> Self {
+        Self::Control(err)
+    }
+}
+
+impl From<u3v::Error> for TransactionError {
+    fn from(err: u3v::Error) -> Self {
+        Self::Control(err.into())
+    }
+}
+
+impl From<TransactionError> for ControlError {
+    fn from(err: TransactionError) -> Self {
+        match err {
+            TransactionError::Status {
+                code, request_id, ..
+            } => ControlError::Status { code, request_id },
+            TransactionError::Control(err) => err,
+        }
+    }
+}
+
+impl std::fmt::Debug for TransactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Status {
+                kind, request_id, ..
+            } => write!(f, "invalid status: {:?}, request id: {}", kind, request_id),
+            Self::Control(err) => write!(f, "{}", err),
+        }
+    }
+}
+
 struct ConnectionConfig {
     /// Timeout duration of each transaction between device.
     timeout_duration: Duration,
@@ -597,6 +1433,24 @@ struct ConnectionConfig {
 
     /// Maximum length of a acknowledge sent to host from device. Unit is byte.
     maximum_ack_length: u32,
+
+    /// Policy to retry a command when a transient error occurs.
+    retry_policy: RetryPolicy,
+
+    /// The maximum number of commands issued before waiting for their acknowledges.
+    max_outstanding_requests: u16,
+
+    /// Overrides of the values written to `SIRM`.
+    sirm_overrides: SirmOverrides,
+
+    /// How strictly acknowledges from the device are validated.
+    ack_validation: AckValidation,
+
+    /// Opens the device even when another process holds the device lock.
+    force_open: bool,
+
+    /// Timeouts of transactions while operating on the nodes, keyed by the node name.
+    node_timeouts: HashMap<String, Duration>,
 }
 
 impl Default for ConnectionConfig {
@@ -606,6 +1460,12 @@ impl Default for ConnectionConfig {
             retry_count: 3,
             maximum_cmd_length: INITIAL_MAXIMUM_CMD_LENGTH,
             maximum_ack_length: INITIAL_MAXIMUM_ACK_LENGTH,
+            retry_policy: RetryPolicy::default(),
+            max_outstanding_requests: 1,
+            sirm_overrides: SirmOverrides::default(),
+            ack_validation: AckValidation::default(),
+            force_open: false,
+            node_timeouts: HashMap::new(),
         }
     }
 }
@@ -621,3 +1481,127 @@ impl From<ControlHandle> for Box<dyn DeviceControl> {
         Box::new(ctrl)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sirm_layout() {
+        let layout =
+            SirmLayout::compute(40, 1024 * 64 * 3 + 100, 0, 64, &SirmOverrides::default()).unwrap();
+        assert_eq!(
+            layout,
+            SirmLayout {
+                maximum_leader_size: 64,
+                maximum_trailer_size: 1024 * 64,
+                payload_transfer_size: 1024 * 64,
+                payload_transfer_count: 3,
+                payload_final_transfer1_size: 128,
+                payload_final_transfer2_size: 0,
+            }
+        );
+
+        let overrides = SirmOverrides {
+            payload_transfer_size: Some(4096),
+            ..SirmOverrides::default()
+        };
+        let layout = SirmLayout::compute(0, 8192, 32, 8, &overrides).unwrap();
+        assert_eq!(layout.payload_transfer_count, 2);
+        assert_eq!(layout.payload_final_transfer1_size, 0);
+        assert_eq!(layout.maximum_leader_size, 4096);
+        assert_eq!(layout.maximum_trailer_size, 32);
+
+        let overrides = SirmOverrides {
+            payload_transfer_size: Some(100),
+            ..SirmOverrides::default()
+        };
+        assert!(SirmLayout::compute(0, 8192, 0, 64, &overrides).is_err());
+        assert!(SirmLayout::compute(0, 8192, 0, 48, &SirmOverrides::default()).is_err());
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff(10), Duration::from_millis(200));
+        assert_eq!(policy.backoff(100), Duration::from_millis(200));
+
+        let busy = TransactionError::Status {
+            code: 0x8007,
+            kind: ack::StatusKind::GenCp(ack::GenCpStatus::Busy),
+            request_id: 0,
+        };
+        let denied = TransactionError::Status {
+            code: 0x8006,
+            kind: ack::StatusKind::GenCp(ack::GenCpStatus::AccessDenied),
+            request_id: 0,
+        };
+        let timeout = TransactionError::Control(ControlError::Timeout);
+        assert!(policy.should_retry(1, &busy, false));
+        assert!(policy.should_retry(2, &timeout, false));
+        assert!(!policy.should_retry(3, &busy, false));
+        assert!(!policy.should_retry(1, &denied, false));
+        assert!(!policy.should_retry(
+            1,
+            &TransactionError::Control(ControlError::NotOpened),
+            false
+        ));
+        assert!(!RetryPolicy::never().should_retry(1, &busy, false));
+
+        // Writes are retried only when the device rejected the command.
+        assert!(policy.should_retry(1, &busy, true));
+        assert!(!policy.should_retry(1, &timeout, true));
+        let policy = RetryPolicy {
+            retry_writes: true,
+            ..RetryPolicy::default()
+        };
+        assert!(policy.should_retry(1, &timeout, true));
+    }
+
+    #[test]
+    fn test_stale_request_id() {
+        assert!(is_stale_request_id(10, 9));
+        assert!(is_stale_request_id(1, u16::MAX));
+        assert!(!is_stale_request_id(10, 10));
+        assert!(!is_stale_request_id(10, 11));
+    }
+
+    fn ack_packet(command_id: u16, scd: &[u8]) -> Vec<u8> {
+        let mut packet = vec![];
+        packet.extend(0x4356_3355_u32.to_le_bytes());
+        packet.extend(0_u16.to_le_bytes()); // Status.
+        packet.extend(command_id.to_le_bytes());
+        packet.extend((scd.len() as u16).to_le_bytes());
+        packet.extend(0_u16.to_le_bytes()); // Request id.
+        packet.extend(scd);
+        packet
+    }
+
+    #[test]
+    fn test_expected_ack() {
+        let expected = ExpectedAck::of(&cmd::ReadMem::new(0, 4));
+
+        let packet = ack_packet(0x0801, &[1, 2, 3, 4]);
+        let ack = ack::AckPacket::parse(&packet).unwrap();
+        assert!(expected.check(&ack, packet.len()).is_ok());
+        // Trailing bytes which aren't declared in CCD.
+        assert!(expected.check(&ack, packet.len() + 1).is_err());
+
+        // Pending acknowledge is allowed for any command.
+        let packet = ack_packet(0x0805, &[0, 0, 10, 0]);
+        let ack = ack::AckPacket::parse(&packet).unwrap();
+        assert!(expected.check(&ack, packet.len()).is_ok());
+
+        // Command id mismatch.
+        let packet = ack_packet(0x0803, &[0, 0, 4, 0]);
+        let ack = ack::AckPacket::parse(&packet).unwrap();
+        assert!(expected.check(&ack, packet.len()).is_err());
+
+        // SCD length mismatch.
+        let packet = ack_packet(0x0801, &[1, 2]);
+        let ack = ack::AckPacket::parse(&packet).unwrap();
+        assert!(expected.check(&ack, packet.len()).is_err());
+    }
+}
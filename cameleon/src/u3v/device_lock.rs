@@ -0,0 +1,81 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Advisory lock which prevents multiple processes from opening the same device.
+
+use std::{
+    fs::{File, OpenOptions},
+    path::PathBuf,
+};
+
+use crate::{ControlError, ControlResult};
+
+/// An OS-level file lock keyed by the device GUID. The lock is released when dropped, or when the
+/// process exits.
+#[derive(Debug)]
+pub(super) struct DeviceLock {
+    _file: File,
+}
+
+impl DeviceLock {
+    /// Tries to acquire the lock of the device.
+    ///
+    /// Returns [`ControlError::Busy`] if another process holds the lock.
+    pub(super) fn try_acquire(guid: &str) -> ControlResult<Self> {
+        let path = lock_path(guid);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| {
+                ControlError::Io(anyhow::Error::new(e).context(format!(
+                    "failed to open the device lock file `{}`",
+                    path.display()
+                )))
+            })?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: file }),
+            Err(std::fs::TryLockError::WouldBlock) => Err(ControlError::Busy),
+            Err(std::fs::TryLockError::Error(e)) => Err(ControlError::Io(
+                anyhow::Error::new(e).context("failed to lock the device lock file"),
+            )),
+        }
+    }
+}
+
+fn lock_path(guid: &str) -> PathBuf {
+    // GUID is reported by the device, so characters which may be invalid in a file name are
+    // replaced.
+    let guid: String = guid
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    std::env::temp_dir().join(format!("cameleon-u3v-{}.lock", guid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_lock() {
+        let guid = format!("cameleon-test/{}", std::process::id());
+        assert!(!lock_path(&guid)
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .contains('/'));
+
+        let lock = DeviceLock::try_acquire(&guid).unwrap();
+        // Locks of separately opened files conflict even in the same process.
+        assert!(matches!(
+            DeviceLock::try_acquire(&guid),
+            Err(ControlError::Busy)
+        ));
+        drop(lock);
+        assert!(DeviceLock::try_acquire(&guid).is_ok());
+    }
+}
@@ -45,19 +45,27 @@
 //! ```
 #![allow(clippy::missing_panics_doc)]
 
+pub mod bandwidth;
 pub mod control_handle;
 pub mod register_map;
 pub mod stream_handle;
 
-pub use control_handle::{ControlHandle, SharedControlHandle};
+mod device_lock;
+
+pub use bandwidth::{BandwidthAllocation, BandwidthManager};
+pub use control_handle::{
+    AckValidation, ControlHandle, RetryPolicy, SharedControlHandle, SirmLayout, SirmOverrides,
+};
 pub use stream_handle::{StreamHandle, StreamParams};
 
-pub use cameleon_device::u3v::DeviceInfo;
+pub use cameleon_device::u3v::protocol::stream::Strictness;
+pub use cameleon_device::u3v::{BusSpeed, DeviceInfo, UsbTopology};
 
 use cameleon_device::u3v;
 
 use super::{
-    genapi::DefaultGenApiCtxt, CameleonResult, Camera, CameraInfo, ControlError, StreamError,
+    genapi::{DefaultGenApiCtxt, GenApiCtxt, ParamsCtxt},
+    CameleonResult, Camera, CameraInfo, ControlError, StreamError,
 };
 
 /// Enumerate all U3V compatible cameras connected to the host.
@@ -101,9 +109,52 @@ pub fn enumerate_cameras() -> CameleonResult<Vec<Camera<ControlHandle, StreamHan
     Ok(cameras)
 }
 
+/// Enumerates U3V compatible cameras and returns the one connected to the USB port at
+/// `port_path`, see [`UsbTopology::port_path`] for the format.
+///
+/// This is useful to distinguish identical cameras, e.g. in a rack, by the port they are plugged
+/// into. Returns `None` if no camera is connected to the port.
+///
+/// # Examples
+///
+/// ```no_run
+/// use cameleon::u3v;
+///
+/// if let Some(camera) = u3v::find_camera_by_port_path("2-1.4").unwrap() {
+///     println!("{}", camera.ctrl.device_info());
+/// }
+/// ```
+pub fn find_camera_by_port_path(
+    port_path: &str,
+) -> CameleonResult<Option<Camera<ControlHandle, StreamHandle>>> {
+    Ok(enumerate_cameras()?
+        .into_iter()
+        .find(|camera| camera.ctrl.device_info().topology.port_path() == port_path))
+}
+
+impl<Ctrl, Ctxt> ParamsCtxt<Ctrl, Ctxt>
+where
+    Ctxt: GenApiCtxt,
+{
+    /// Parses an event packet received from the event channel of a U3V device and invalidates
+    /// cache of the nodes linked to each event in the packet.
+    ///
+    /// See [`ParamsCtxt::invalidate_by_event`] for how nodes are linked to an event.
+    /// Returns the number of linked nodes.
+    pub fn invalidate_by_u3v_event(&mut self, packet: &[u8]) -> CameleonResult<usize> {
+        let packet =
+            u3v::protocol::event::EventPacket::parse(packet).map_err(ControlError::from)?;
+        Ok(packet
+            .scd
+            .iter()
+            .map(|scd| self.invalidate_by_event(scd.event_id.into()))
+            .sum())
+    }
+}
+
 impl From<u3v::Error> for ControlError {
     fn from(err: u3v::Error) -> ControlError {
-        use u3v::Error::{BufferIo, InvalidDevice, InvalidPacket, LibUsb};
+        use u3v::Error::{InvalidDevice, LibUsb};
         use u3v::LibUsbError::{
             Access, BadDescriptor, Busy, Interrupted, InvalidParam, Io, NoDevice, NoMem, NotFound,
             NotSupported, Other, Overflow, Pipe, Timeout,
@@ -118,9 +169,11 @@ impl From<u3v::Error> for ControlError {
                 Timeout => ControlError::Timeout,
             },
 
-            BufferIo(_) | InvalidPacket(_) => ControlError::Io(err.into()),
-
             InvalidDevice => ControlError::InvalidDevice("invalid device".into()),
+
+            // `BufferIo`, `InvalidPacket`, and `WebUsb` when `cameleon-device` is built with
+            // `webusb` feature.
+            _ => ControlError::Io(err.into()),
         }
     }
 }
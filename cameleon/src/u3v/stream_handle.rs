@@ -7,28 +7,75 @@
 use std::{
     convert::TryInto,
     sync::mpsc,
-    sync::{mpsc::TryRecvError, Arc, Mutex},
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{RecvTimeoutError, TryRecvError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use cameleon_device::u3v::{self, async_read::AsyncPool, protocol::stream as u3v_stream};
-use tracing::{error, info, warn};
+use tracing::{debug_span, error, info, warn, Span};
 
 use crate::{
+    callback::{CallbackPool, FrameCallbackHandle, FrameCallbacks},
     camera::PayloadStream,
-    payload::{ImageInfo, Payload, PayloadSender, PayloadType},
+    health::StreamStatistics,
+    payload::{
+        BackpressurePolicy, Decimation, Decimator, HostTimestamp, ImageInfo, IntegrityCheck,
+        Payload, PayloadSender, PayloadStatus, PayloadType,
+    },
     ControlError, ControlResult, DeviceControl, StreamError, StreamResult,
 };
 
 use super::register_map::Abrm;
 
+#[cfg(feature = "thread-tuning")]
+use crate::scheduling::{ThreadConfig, ThreadCpuUsage, ThreadRegistry};
+
 /// This type is used to receive stream packets from the device.
 pub struct StreamHandle {
     /// Inner channel to receive payload data.
     pub inner: Arc<Mutex<u3v::ReceiveChannel>>,
     /// Parameters for streaming.
     params: StreamParams,
+    /// Policy applied when the payload receiver can't keep up with the device.
+    backpressure_policy: BackpressurePolicy,
+    /// Decimation of payloads delivered to the host.
+    decimation: Decimation,
+    /// Integrity check applied to each payload.
+    integrity_check: IntegrityCheck,
+    /// Strictness of checks on leaders and trailers.
+    strictness: u3v_stream::Strictness,
+    /// Callbacks registered by [`StreamHandle::on_frame`].
+    frame_callbacks: FrameCallbacks,
+    /// Number of threads which invoke the frame callbacks.
+    callback_threads: usize,
+    /// Scheduling configuration applied to the streaming loop thread.
+    #[cfg(feature = "thread-tuning")]
+    thread_config: ThreadConfig,
+    /// Streaming loop threads which are running.
+    #[cfg(feature = "thread-tuning")]
+    threads: ThreadRegistry,
     cancellation_tx: Option<mpsc::SyncSender<()>>,
+    /// Set while the streaming loop is paused by [`StreamHandle::pause`].
+    paused: Arc<AtomicBool>,
+    counters: Arc<StreamCounters>,
+    /// Span which identifies the camera in logs of the streaming loop.
+    span: Span,
+}
+
+/// Counters shared with the streaming loop.
+#[derive(Default)]
+struct StreamCounters {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    decimated: AtomicU64,
+    protocol_violations: AtomicU64,
+    /// Set when the payload size announced by the device exceeds the negotiated one.
+    payload_size_changed: AtomicBool,
 }
 
 macro_rules! unwrap_or_poisoned {
@@ -44,10 +91,29 @@ macro_rules! unwrap_or_poisoned {
 impl StreamHandle {
     pub(super) fn new(device: &u3v::Device) -> ControlResult<Option<Self>> {
         let inner = device.stream_channel()?;
+        let info = &device.device_info;
         Ok(inner.map(|inner| Self {
             inner: Arc::new(Mutex::new(inner)),
             params: StreamParams::default(),
+            backpressure_policy: BackpressurePolicy::default(),
+            decimation: Decimation::default(),
+            integrity_check: IntegrityCheck::default(),
+            strictness: u3v_stream::Strictness::default(),
+            frame_callbacks: FrameCallbacks::default(),
+            callback_threads: 1,
+            #[cfg(feature = "thread-tuning")]
+            thread_config: ThreadConfig::default(),
+            #[cfg(feature = "thread-tuning")]
+            threads: ThreadRegistry::default(),
             cancellation_tx: None,
+            paused: Arc::default(),
+            counters: Arc::default(),
+            span: debug_span!(
+                "stream",
+                serial = %info.serial_number,
+                model = %info.model_name,
+                channel = "stream"
+            ),
         }))
     }
 
@@ -61,6 +127,183 @@ impl StreamHandle {
     pub fn params_mut(&mut self) -> &mut StreamParams {
         &mut self.params
     }
+
+    /// Returns the policy applied when the payload receiver can't keep up with the device.
+    #[must_use]
+    pub fn backpressure_policy(&self) -> BackpressurePolicy {
+        self.backpressure_policy
+    }
+
+    /// Sets the policy applied when the payload receiver can't keep up with the device.
+    ///
+    /// The policy takes effect from the next call of `start_streaming_loop`.
+    /// [`BackpressurePolicy::DropNewest`] is used by default.
+    pub fn set_backpressure_policy(&mut self, policy: BackpressurePolicy) {
+        self.backpressure_policy = policy;
+    }
+
+    /// Returns the decimation of payloads delivered to the host.
+    #[must_use]
+    pub fn decimation(&self) -> Decimation {
+        self.decimation
+    }
+
+    /// Sets the decimation of payloads delivered to the host.
+    ///
+    /// The decimation takes effect from the next call of `start_streaming_loop`.
+    /// [`Decimation::Disabled`] is used by default.
+    pub fn set_decimation(&mut self, decimation: Decimation) {
+        self.decimation = decimation;
+    }
+
+    /// Returns the integrity check applied to each payload.
+    #[must_use]
+    pub fn integrity_check(&self) -> IntegrityCheck {
+        self.integrity_check
+    }
+
+    /// Sets the integrity check applied to each payload, see [`IntegrityCheck`].
+    ///
+    /// The check takes effect from the next call of `start_streaming_loop`.
+    /// [`IntegrityCheck::Disabled`] is used by default.
+    pub fn set_integrity_check(&mut self, check: IntegrityCheck) {
+        self.integrity_check = check;
+    }
+
+    /// Returns the strictness of checks on leaders and trailers.
+    #[must_use]
+    pub fn strictness(&self) -> u3v_stream::Strictness {
+        self.strictness
+    }
+
+    /// Sets the strictness of checks on leaders and trailers, see
+    /// [`Strictness`](u3v_stream::Strictness).
+    ///
+    /// Payloads with a fatal violation are reported as [`StreamError::InvalidPayload`]. All
+    /// violations, including tolerated ones, are counted in
+    /// [`StreamStatistics::protocol_violations`]. The strictness takes effect from the next call
+    /// of `start_streaming_loop`. [`Strictness::Permissive`](u3v_stream::Strictness::Permissive)
+    /// is used by default.
+    pub fn set_strictness(&mut self, strictness: u3v_stream::Strictness) {
+        self.strictness = strictness;
+    }
+
+    /// Returns `true` if the device has started sending payloads larger than the size negotiated
+    /// when the streaming started, e.g. because `Width` or `PixelFormat` is written while
+    /// streaming.
+    ///
+    /// Such payloads are reported as [`StreamError::PayloadSizeChanged`]. Restarting the
+    /// streaming renegotiates the payload size, i.e. `SIRM` and the payload buffers are
+    /// reconfigured with the current settings of the device.
+    #[must_use]
+    pub fn is_payload_size_changed(&self) -> bool {
+        self.counters.payload_size_changed.load(Ordering::Relaxed)
+    }
+
+    /// Pauses the running streaming loop.
+    ///
+    /// The loop stops submitting transfers, but keeps `SIRM` configuration and the payload
+    /// buffers, so [`StreamHandle::resume`] restarts receiving payloads without renegotiation.
+    /// Acquisition of the device isn't stopped by this method, use
+    /// [`Camera::pause_streaming`](crate::Camera::pause_streaming) to stop both.
+    ///
+    /// Has no effect if the loop isn't running, the loop always starts unpaused.
+    pub fn pause(&self) {
+        if self.is_loop_running() {
+            self.paused.store(true, Ordering::Release);
+            info!("pause streaming loop");
+        }
+    }
+
+    /// Resumes the streaming loop paused by [`StreamHandle::pause`].
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::AcqRel) {
+            info!("resume streaming loop");
+        }
+    }
+
+    /// Returns `true` if the streaming loop is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Registers a callback which is invoked with each payload sent from the device.
+    ///
+    /// While at least one callback is registered, payloads are dispatched to the callbacks instead
+    /// of the channel passed to `start_streaming_loop`. Errors are still sent to the channel.
+    /// Callbacks are invoked on a thread pool owned by the streaming loop, see
+    /// [`StreamHandle::set_callback_threads`]. If all the threads are busy and the pool queue is
+    /// full, the payload is dropped.
+    ///
+    /// Callbacks can be registered while streaming. Use the returned handle to get the timing
+    /// statistics of the callback or to unregister it.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use cameleon::u3v;
+    ///
+    /// let mut cameras = u3v::enumerate_cameras().unwrap();
+    /// if cameras.is_empty() {
+    ///     return;
+    /// }
+    ///
+    /// let mut camera = cameras.pop().unwrap();
+    /// camera.open().unwrap();
+    /// camera.load_context().unwrap();
+    ///
+    /// let handle = camera.strm.on_frame(|payload| println!("{}", payload.id()));
+    /// let _payload_rx = camera.start_streaming(3).unwrap();
+    /// std::thread::sleep(std::time::Duration::from_secs(1));
+    /// camera.stop_streaming().unwrap();
+    ///
+    /// println!("{:?}", handle.stats());
+    /// ```
+    pub fn on_frame<F>(&self, callback: F) -> FrameCallbackHandle
+    where
+        F: Fn(&Payload) + Send + Sync + 'static,
+    {
+        self.frame_callbacks.register(callback)
+    }
+
+    /// Returns the number of threads which invoke the frame callbacks.
+    #[must_use]
+    pub fn callback_threads(&self) -> usize {
+        self.callback_threads
+    }
+
+    /// Sets the number of threads which invoke the frame callbacks.
+    ///
+    /// The number takes effect from the next call of `start_streaming_loop`. Callbacks may be
+    /// invoked out of order if more than one thread is used. One thread is used by default.
+    pub fn set_callback_threads(&mut self, num_threads: usize) {
+        self.callback_threads = num_threads.max(1);
+    }
+
+    /// Returns the scheduling configuration applied to the streaming loop thread.
+    #[cfg(feature = "thread-tuning")]
+    #[must_use]
+    pub fn thread_config(&self) -> &ThreadConfig {
+        &self.thread_config
+    }
+
+    /// Sets the scheduling configuration applied to the streaming loop thread.
+    ///
+    /// The configuration takes effect from the next call of `start_streaming_loop`, which fails if
+    /// the configuration can't be applied, e.g. because of missing privileges.
+    #[cfg(feature = "thread-tuning")]
+    pub fn set_thread_config(&mut self, config: ThreadConfig) {
+        self.thread_config = config;
+    }
+
+    /// Returns CPU usage of the running streaming loop thread.
+    ///
+    /// Returns an empty vector if the loop isn't running or the platform doesn't support it.
+    #[cfg(feature = "thread-tuning")]
+    #[must_use]
+    pub fn thread_cpu_usage(&self) -> Vec<ThreadCpuUsage> {
+        self.threads.cpu_usage()
+    }
 }
 
 impl PayloadStream for StreamHandle {
@@ -98,6 +341,11 @@ impl PayloadStream for StreamHandle {
         if self.is_loop_running() {
             return Err(StreamError::InStreaming);
         }
+        // `params` has just been renegotiated with the current settings of the device.
+        self.counters
+            .payload_size_changed
+            .store(false, Ordering::Relaxed);
+        self.paused.store(false, Ordering::Relaxed);
 
         // Sync channel of capacity 0 is a special rendez-vous mode, where every send() blocks.
         let (cancellation_tx, cancellation_rx) = mpsc::sync_channel(0);
@@ -106,12 +354,35 @@ impl PayloadStream for StreamHandle {
         let strm_loop = StreamingLoop {
             inner: self.inner.clone(),
             params: self.params.clone(),
+            backpressure_policy: self.backpressure_policy,
+            decimation: self.decimation,
+            integrity_check: self.integrity_check,
+            strictness: self.strictness,
+            frame_callbacks: self.frame_callbacks.clone(),
+            callback_threads: self.callback_threads,
             sender,
             cancellation_rx,
+            paused: self.paused.clone(),
+            counters: self.counters.clone(),
         };
-        std::thread::spawn(|| {
+        let span = self.span.clone();
+        let run = move || {
+            let _enter = span.enter();
             strm_loop.run();
-        });
+        };
+        #[cfg(feature = "thread-tuning")]
+        self.thread_config
+            .spawn("stream", &self.threads, run)
+            .map_err(|e| {
+                self.cancellation_tx = None;
+                error!(?e);
+                StreamError::Io(anyhow::Error::msg(format!(
+                    "failed to apply thread config to streaming loop: {}",
+                    e
+                )))
+            })?;
+        #[cfg(not(feature = "thread-tuning"))]
+        std::thread::spawn(run);
 
         info!("start streaming loop successfully");
         Ok(())
@@ -126,6 +397,7 @@ impl PayloadStream for StreamHandle {
                 StreamError::Poisoned("failed to send cancellation signal to streaming loop".into())
             })?;
         }
+        self.paused.store(false, Ordering::Relaxed);
 
         info!("stop streaming loop successfully");
         Ok(())
@@ -134,6 +406,30 @@ impl PayloadStream for StreamHandle {
     fn is_loop_running(&self) -> bool {
         self.cancellation_tx.is_some()
     }
+
+    fn pause_streaming_loop(&mut self) -> StreamResult<()> {
+        self.pause();
+        Ok(())
+    }
+
+    fn resume_streaming_loop(&mut self) -> StreamResult<()> {
+        self.resume();
+        Ok(())
+    }
+
+    fn is_loop_paused(&self) -> bool {
+        self.is_paused()
+    }
+
+    fn statistics(&self) -> Option<StreamStatistics> {
+        Some(StreamStatistics {
+            delivered: self.counters.delivered.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            decimated: self.counters.decimated.load(Ordering::Relaxed),
+            protocol_violations: self.counters.protocol_violations.load(Ordering::Relaxed),
+        })
+    }
 }
 
 impl Drop for StreamHandle {
@@ -153,16 +449,122 @@ impl From<StreamHandle> for Box<dyn PayloadStream> {
 struct StreamingLoop {
     inner: Arc<Mutex<u3v::ReceiveChannel>>,
     params: StreamParams,
+    backpressure_policy: BackpressurePolicy,
+    decimation: Decimation,
+    integrity_check: IntegrityCheck,
+    strictness: u3v_stream::Strictness,
+    frame_callbacks: FrameCallbacks,
+    callback_threads: usize,
     sender: PayloadSender,
     cancellation_rx: mpsc::Receiver<()>,
+    paused: Arc<AtomicBool>,
+    counters: Arc<StreamCounters>,
 }
 
+/// Interval to poll the channel while the loop is blocked by [`BackpressurePolicy::Block`].
+const BLOCKING_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Interval to check whether the loop is resumed while it's paused.
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
 impl StreamingLoop {
+    fn send_err(&self, err: StreamError) {
+        self.counters.failed.fetch_add(1, Ordering::Relaxed);
+        self.sender.try_send(Err(err)).ok();
+    }
+
+    /// Sends `payload` to the host according to the backpressure policy.
+    ///
+    /// Returns `false` if the loop is cancelled while waiting for the receiver.
+    fn send_payload(&self, payload: Payload) -> bool {
+        match self.backpressure_policy {
+            BackpressurePolicy::DropNewest => {
+                if let Err(err) = self.sender.try_send(Ok(payload)) {
+                    warn!(?err);
+                    self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            BackpressurePolicy::DropOldest => match self.sender.force_send(Ok(payload)) {
+                Ok(evicted) => {
+                    if evicted.is_some() {
+                        warn!("payload receiver is too slow, dropped the oldest payload");
+                        self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(err) => {
+                    warn!(?err);
+                    self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            },
+
+            BackpressurePolicy::Block => {
+                let mut payload = Ok(payload);
+                loop {
+                    match self.sender.try_send_or_return(payload) {
+                        Ok(None) => {
+                            self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                            break;
+                        }
+                        Ok(Some(returned)) => payload = returned,
+                        Err(err) => {
+                            warn!(?err);
+                            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                            break;
+                        }
+                    }
+
+                    // Keep the loop cancellable while blocked, otherwise `stop_streaming_loop`
+                    // would wait forever for a receiver that never drains the channel.
+                    match self.cancellation_rx.try_recv() {
+                        Ok(()) | Err(TryRecvError::Disconnected) => return false,
+                        Err(TryRecvError::Empty) => std::thread::sleep(BLOCKING_POLL_INTERVAL),
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Counts violations of the specification in `leader` and `trailer`, and returns an error if
+    /// any of them is fatal under the strictness of the loop.
+    fn check_violations(
+        &self,
+        leader: &u3v_stream::Leader,
+        trailer: &u3v_stream::Trailer,
+    ) -> StreamResult<()> {
+        let mut violations = leader.violations();
+        violations.extend(trailer.violations(leader));
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        self.counters
+            .protocol_violations
+            .fetch_add(violations.len() as u64, Ordering::Relaxed);
+        match violations
+            .iter()
+            .find(|violation| violation.is_fatal(self.strictness))
+        {
+            Some(violation) => Err(StreamError::InvalidPayload(
+                format!("protocol violation: {}", violation).into(),
+            )),
+            None => Ok(()),
+        }
+    }
+
     fn run(self) {
         let mut trailer_buf = vec![0; self.params.trailer_size];
         let mut payload_buf_opt = None;
         let mut leader_buf = vec![0; self.params.leader_size];
         let inner = self.inner.lock().unwrap();
+        // Created when the first callback is registered, and joined when the loop ends.
+        let mut callback_pool = None;
+        let mut decimator = Decimator::new(self.decimation);
 
         'outer: loop {
             // Stop the loop when
@@ -173,6 +575,14 @@ impl StreamingLoop {
                 Err(TryRecvError::Empty) => {}
             }
 
+            // Don't submit transfers while paused, but keep the buffers for resumption.
+            if self.paused.load(Ordering::Acquire) {
+                match self.cancellation_rx.recv_timeout(PAUSED_POLL_INTERVAL) {
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                }
+            }
+
             let maximum_payload_size = self.params.maximum_payload_size();
             let mut payload_buf = match payload_buf_opt.take() {
                 Some(payload_buf) => payload_buf,
@@ -195,7 +605,7 @@ impl StreamingLoop {
                 // Report and send error if the error is fatal.
                 if matches!(err, StreamError::Io(..) | StreamError::Disconnected) {
                     error!(?err);
-                    self.sender.try_send(Err(err)).ok();
+                    self.send_err(err);
                 }
                 payload_buf_opt = Some(payload_buf);
                 continue;
@@ -205,7 +615,7 @@ impl StreamingLoop {
                 warn!(?err);
                 // Reuse `payload_buf`.
                 payload_buf_opt = Some(payload_buf);
-                self.sender.try_send(Err(err)).ok();
+                self.send_err(err);
                 continue;
             };
 
@@ -213,36 +623,26 @@ impl StreamingLoop {
                 warn!(?err);
                 // Reuse `payload_buf`.
                 payload_buf_opt = Some(payload_buf);
-                self.sender.try_send(Err(err)).ok();
+                self.send_err(err);
                 continue;
             };
 
             // We've submitted the bulk transfers, now wait for them.
-            let mut first_buf_len = None;
-            let mut last_buf_len = None;
-            let mut payload_len = 0;
-
-            while !async_pool.is_empty() {
-                let len = match async_pool.poll(self.params.timeout) {
-                    Ok(len) => len,
-                    Err(err) => {
-                        warn!(?err);
-                        // Can't reuse `payload_buf` because we're in a loop.
-                        self.sender.try_send(Err(err.into())).ok();
-                        continue 'outer;
-                    }
-                };
-
-                if first_buf_len.is_none() {
-                    first_buf_len = Some(len);
-                } else {
-                    payload_len += len;
+            let payload_len = match wait_transfers(
+                &mut async_pool,
+                &self.params,
+                &payload_buf,
+                &mut trailer_buf,
+            ) {
+                Ok(len) => len,
+                Err(err) => {
+                    warn!(?err);
+                    // Can't reuse `payload_buf` because transfers may still be pending.
+                    self.send_err(err.into());
+                    continue 'outer;
                 }
-
-                last_buf_len = Some(len);
-            }
-
-            let payload_len = payload_len - last_buf_len.unwrap();
+            };
+            let host_timestamp = HostTimestamp::now();
 
             // We received the data from the bulk transfers, try to parse stuff now.
             let leader = match u3v_stream::Leader::parse(&leader_buf)
@@ -253,11 +653,24 @@ impl StreamingLoop {
                     warn!(?err);
                     // Reuse `payload_buf`.
                     payload_buf_opt = Some(payload_buf);
-                    self.sender.try_send(Err(err)).ok();
+                    self.send_err(err);
                     continue;
                 }
             };
 
+            if let Err(err) = check_payload_size(&leader, maximum_payload_size) {
+                if !self
+                    .counters
+                    .payload_size_changed
+                    .swap(true, Ordering::Relaxed)
+                {
+                    warn!(?err, "restart streaming to renegotiate the payload size");
+                }
+                payload_buf_opt = Some(payload_buf);
+                self.send_err(err);
+                continue;
+            }
+
             let trailer = match u3v_stream::Trailer::parse(&trailer_buf)
                 .map_err(|e| StreamError::InvalidPayload(format!("invalid trailer: {}", e).into()))
             {
@@ -266,33 +679,75 @@ impl StreamingLoop {
                     warn!(?err);
                     // Reuse `payload_buf`.
                     payload_buf_opt = Some(payload_buf);
-                    self.sender.try_send(Err(err)).ok();
+                    self.send_err(err);
                     continue;
                 }
             };
 
+            if let Err(err) = self.check_violations(&leader, &trailer) {
+                warn!(?err);
+                payload_buf_opt = Some(payload_buf);
+                self.send_err(err);
+                continue;
+            }
+
+            if !decimator.accept(Instant::now()) {
+                // Reuse `payload_buf` without building the skipped payload.
+                payload_buf_opt = Some(payload_buf);
+                self.counters.decimated.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+
+            // Keep the raw bytes for users who need the fields `Payload` doesn't expose.
+            let leader_raw = raw_bytes(&leader_buf, leader.leader_size());
+            let trailer_raw = raw_bytes(&trailer_buf, trailer.trailer_size());
+
             let builder_result = PayloadBuilder {
                 leader,
                 payload_buf,
                 read_payload_size: payload_len,
                 trailer,
+                host_timestamp,
             }
             .build();
 
-            let payload = match builder_result {
+            let mut payload = match builder_result {
                 Ok(payload) => payload,
                 Err(e) => {
                     warn!(?e);
                     // Can't reuse `payload_buf` because we moved it
                     // into PayloadBuilder above.
                     payload_buf_opt = None;
-                    self.sender.try_send(Err(e)).ok();
+                    self.send_err(e);
                     continue;
                 }
             };
 
-            if let Err(err) = self.sender.try_send(Ok(payload)) {
-                warn!(?err);
+            payload.leader = leader_raw;
+            payload.trailer = trailer_raw;
+            payload.verify_integrity(self.integrity_check);
+            if let PayloadStatus::CrcMismatch { expected, computed } = payload.status() {
+                warn!(
+                    id = payload.id(),
+                    expected, computed, "CRC of the payload doesn't match"
+                );
+            }
+
+            if !self.frame_callbacks.is_empty() {
+                let pool = callback_pool.get_or_insert_with(|| {
+                    CallbackPool::new(self.frame_callbacks.clone(), self.callback_threads)
+                });
+                if pool.dispatch(payload).is_err() {
+                    warn!("frame callbacks are too slow, dropped the payload");
+                    self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+                }
+                continue;
+            }
+
+            if !self.send_payload(payload) {
+                break;
             }
         }
     }
@@ -303,6 +758,7 @@ struct PayloadBuilder<'a> {
     payload_buf: Vec<u8>,
     read_payload_size: usize,
     trailer: u3v_stream::Trailer<'a>,
+    host_timestamp: HostTimestamp,
 }
 
 impl<'a> PayloadBuilder<'a> {
@@ -338,6 +794,7 @@ impl<'a> PayloadBuilder<'a> {
         let image_info = Some(ImageInfo {
             width: leader.width() as usize,
             height: trailer.actual_height() as usize,
+            max_height: leader.height() as usize,
             x_offset: leader.x_offset() as usize,
             y_offset: leader.y_offset() as usize,
             pixel_format: leader.pixel_format(),
@@ -351,6 +808,11 @@ impl<'a> PayloadBuilder<'a> {
             payload: self.payload_buf,
             valid_payload_size,
             timestamp: leader.timestamp(),
+            host_timestamp: self.host_timestamp,
+            status: PayloadStatus::default(),
+            checksum: None,
+            leader: vec![],
+            trailer: vec![],
         })
     }
 
@@ -391,6 +853,7 @@ impl<'a> PayloadBuilder<'a> {
         let image_info = Some(ImageInfo {
             width: leader.width() as usize,
             height: trailer.actual_height() as usize,
+            max_height: leader.height() as usize,
             x_offset: leader.x_offset() as usize,
             y_offset: leader.y_offset() as usize,
             pixel_format: leader.pixel_format(),
@@ -404,6 +867,11 @@ impl<'a> PayloadBuilder<'a> {
             payload: self.payload_buf,
             valid_payload_size,
             timestamp: leader.timestamp(),
+            host_timestamp: self.host_timestamp,
+            status: PayloadStatus::default(),
+            checksum: None,
+            leader: vec![],
+            trailer: vec![],
         })
     }
 
@@ -421,6 +889,11 @@ impl<'a> PayloadBuilder<'a> {
             payload: self.payload_buf,
             valid_payload_size,
             timestamp: leader.timestamp(),
+            host_timestamp: self.host_timestamp,
+            status: PayloadStatus::default(),
+            checksum: None,
+            leader: vec![],
+            trailer: vec![],
         })
     }
 
@@ -525,6 +998,50 @@ impl StreamParams {
     }
 }
 
+/// Returns the first `size` bytes of `buf`, or the whole `buf` if it's shorter than `size`.
+fn raw_bytes(buf: &[u8], size: u16) -> Vec<u8> {
+    buf[..usize::from(size).min(buf.len())].to_vec()
+}
+
+/// Returns an error if the image announced by `leader` doesn't fit in the negotiated payload size.
+fn check_payload_size(
+    leader: &u3v_stream::Leader,
+    maximum_payload_size: usize,
+) -> StreamResult<()> {
+    let (width, height, pixel_format) = match leader.payload_type() {
+        u3v_stream::PayloadType::Image => {
+            let leader: u3v_stream::ImageLeader = match leader.specific_leader_as() {
+                Ok(leader) => leader,
+                Err(_) => return Ok(()),
+            };
+            (leader.width(), leader.height(), leader.pixel_format())
+        }
+        u3v_stream::PayloadType::ImageExtendedChunk => {
+            let leader: u3v_stream::ImageExtendedChunkLeader = match leader.specific_leader_as() {
+                Ok(leader) => leader,
+                Err(_) => return Ok(()),
+            };
+            (leader.width(), leader.height(), leader.pixel_format())
+        }
+        u3v_stream::PayloadType::Chunk => return Ok(()),
+    };
+
+    // Bits 16-23 of `PFNC` pixel format value hold the number of bits per pixel.
+    let bits_per_pixel = ((u32::from(pixel_format) >> 16) & 0xff) as usize;
+    let image_size = (width as usize * height as usize * bits_per_pixel).div_ceil(8);
+    if image_size > maximum_payload_size {
+        Err(StreamError::PayloadSizeChanged(
+            format!(
+                "image of {}x{} {:?} requires {} bytes, but the negotiated size is {} bytes",
+                width, height, pixel_format, image_size, maximum_payload_size
+            )
+            .into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 fn read_leader(
     async_pool: &mut AsyncPool,
     params: &StreamParams,
@@ -541,22 +1058,62 @@ fn read_payload(
     params: &StreamParams,
     buf: &mut [u8],
 ) -> StreamResult<()> {
-    let payload_size = params.payload_size;
     let mut cursor = 0;
-    for _ in 0..params.payload_count {
-        async_pool.submit(&mut buf[cursor..cursor + payload_size])?;
-        cursor += payload_size;
+    for size in payload_transfer_sizes(params) {
+        async_pool.submit(&mut buf[cursor..cursor + size])?;
+        cursor += size;
     }
 
-    if params.payload_final1_size != 0 {
-        async_pool.submit(&mut buf[cursor..cursor + params.payload_final1_size])?;
-        cursor += params.payload_final1_size;
-    }
-    if params.payload_final2_size != 0 {
-        async_pool.submit(&mut buf[cursor..cursor + params.payload_final2_size])?;
+    Ok(())
+}
+
+/// Returns sizes of the bulk transfers submitted for a payload, in the order of submission.
+fn payload_transfer_sizes(params: &StreamParams) -> impl Iterator<Item = usize> {
+    std::iter::repeat_n(params.payload_size, params.payload_count)
+        .chain(Some(params.payload_final1_size))
+        .chain(Some(params.payload_final2_size))
+        .filter(|size| *size != 0)
+}
+
+/// Waits for the transfers submitted by `read_leader`, `read_payload` and `read_trailer`, and
+/// returns the length of the received payload.
+///
+/// Devices which send variable size frames, e.g. line-scan cameras, terminate the payload early
+/// with a short packet and send the trailer right after it. In that case, the trailer is received
+/// by the transfer submitted for the next payload block, so it's copied to `trailer_buf` and the
+/// rest of the transfers are cancelled.
+fn wait_transfers(
+    async_pool: &mut AsyncPool,
+    params: &StreamParams,
+    payload_buf: &[u8],
+    trailer_buf: &mut [u8],
+) -> u3v::Result<usize> {
+    // Leader.
+    async_pool.poll(params.timeout)?;
+
+    let sizes: Vec<_> = payload_transfer_sizes(params).collect();
+    let mut cursor = 0;
+    for (i, &size) in sizes.iter().enumerate() {
+        let len = async_pool.poll(params.timeout)?;
+        if len < size && i + 1 < sizes.len() {
+            let trailer_len = async_pool.poll(params.timeout)?.min(trailer_buf.len());
+            async_pool.cancel_all();
+            while !async_pool.is_empty() {
+                // Cancelled transfers complete with errors.
+                async_pool.poll(params.timeout).ok();
+            }
+
+            let trailer_start = cursor + size;
+            trailer_buf[..trailer_len]
+                .copy_from_slice(&payload_buf[trailer_start..trailer_start + trailer_len]);
+            return Ok(cursor + len);
+        }
+        cursor += len;
     }
 
-    Ok(())
+    // Trailer.
+    async_pool.poll(params.timeout)?;
+    Ok(cursor)
 }
 
 fn read_trailer(
@@ -0,0 +1,79 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Benchmarks of parsing `U3V` stream leaders and trailers, which is done for every payload.
+//!
+//! Run with `cargo bench -p cameleon-device`.
+
+use cameleon_device::{
+    u3v::protocol::stream::{ImageLeader, ImageTrailer, Leader, Trailer},
+    PixelFormat,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Returns bytes of a leader of `Image` payload.
+fn image_leader_bytes() -> Vec<u8> {
+    let mut buf = vec![];
+    // Leader magic, reserved, leader size, block ID, reserved and payload type.
+    buf.extend_from_slice(&0x4C56_3355_u32.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    buf.extend_from_slice(&50_u16.to_le_bytes());
+    buf.extend_from_slice(&51_u64.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    buf.extend_from_slice(&0x0001_u16.to_le_bytes());
+    // Timestamp, pixel format, width, height, x offset, y offset, x padding and reserved.
+    buf.extend_from_slice(&100_u64.to_le_bytes());
+    let pixel_format: u32 = PixelFormat::Mono8.into();
+    buf.extend_from_slice(&pixel_format.to_le_bytes());
+    buf.extend_from_slice(&3840_u32.to_le_bytes());
+    buf.extend_from_slice(&2160_u32.to_le_bytes());
+    buf.extend_from_slice(&0_u32.to_le_bytes());
+    buf.extend_from_slice(&0_u32.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    buf
+}
+
+/// Returns bytes of a trailer of `Image` payload.
+fn image_trailer_bytes() -> Vec<u8> {
+    let mut buf = vec![];
+    // Trailer magic, reserved, trailer size, block ID, status, reserved and valid payload size.
+    buf.extend_from_slice(&0x5456_3355_u32.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    buf.extend_from_slice(&32_u16.to_le_bytes());
+    buf.extend_from_slice(&51_u64.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    buf.extend_from_slice(&0_u16.to_le_bytes());
+    let valid_payload_size: u64 = 3840 * 2160;
+    buf.extend_from_slice(&valid_payload_size.to_le_bytes());
+    // Actual height and reserved.
+    buf.extend_from_slice(&2160_u32.to_le_bytes());
+    buf.extend_from_slice(&0_u32.to_le_bytes());
+    buf
+}
+
+fn bench_leader(c: &mut Criterion) {
+    let buf = image_leader_bytes();
+    c.bench_function("leader/image", |b| {
+        b.iter(|| {
+            let leader = Leader::parse(black_box(&buf)).unwrap();
+            let image_leader: ImageLeader = leader.specific_leader_as().unwrap();
+            black_box(image_leader.width())
+        });
+    });
+}
+
+fn bench_trailer(c: &mut Criterion) {
+    let buf = image_trailer_bytes();
+    c.bench_function("trailer/image", |b| {
+        b.iter(|| {
+            let trailer = Trailer::parse(black_box(&buf)).unwrap();
+            let image_trailer: ImageTrailer = trailer.specific_trailer_as().unwrap();
+            black_box(image_trailer.actual_height())
+        });
+    });
+}
+
+criterion_group!(benches, bench_leader, bench_trailer);
+criterion_main!(benches);
@@ -0,0 +1,326 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains the control channel of `GigE Vision` devices, which accesses registers
+//! with `GVCP` commands under the privilege given by `CCP` (Control Channel Privilege) register.
+
+use std::{
+    io::{self, Cursor},
+    net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+    time::Duration,
+};
+
+use cameleon_impl::bytes_io::{ReadBytes, WriteBytes};
+
+use super::{
+    protocol::{AckPacket, CommandHeader},
+    Error, Result, GVCP_PORT,
+};
+
+/// Address of `CCP` register.
+const CCP_ADDRESS: u32 = 0x0A00;
+
+/// Bits of `CCP` register.
+const CCP_EXCLUSIVE_ACCESS: u32 = 0b001;
+const CCP_CONTROL_ACCESS: u32 = 0b010;
+const CCP_CONTROL_SWITCHOVER_ENABLE: u32 = 0b100;
+
+/// `GEV_STATUS_ACCESS_DENIED`.
+const STATUS_ACCESS_DENIED: u16 = 0x8006;
+
+/// Maximum length of `GVCP` acknowledge packets.
+const MAX_ACK_LEN: usize = 576;
+
+/// Privilege of the application on the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Privilege {
+    /// Reads registers without requesting privilege, so that the device can be monitored while
+    /// another host controls it. Writes are rejected by the channel.
+    ///
+    /// NOTE: A device denies even reads if another application has exclusive access.
+    Monitor,
+
+    /// Control access. Other applications can still read registers.
+    Control,
+
+    /// Exclusive access. Other applications can't access registers at all.
+    Exclusive,
+}
+
+/// Configuration of [`ControlChannel`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ControlChannelConfig {
+    /// Privilege requested on open.
+    pub privilege: Privilege,
+
+    /// Key to take over the control privilege from another application, which must be the key
+    /// the device is configured with. Takeover succeeds only if the current primary application
+    /// has enabled switchover.
+    pub switchover_key: Option<u16>,
+
+    /// Allows other applications to take over the control privilege with the switchover key.
+    /// Ignored for [`Privilege::Monitor`] and [`Privilege::Exclusive`].
+    pub enable_switchover: bool,
+
+    /// Time to wait for each acknowledge.
+    pub timeout: Duration,
+
+    /// Number of retransmissions when an acknowledge isn't received in time.
+    pub retries: u32,
+
+    /// UDP port on which the device listens to `GVCP` commands. Defaults to [`GVCP_PORT`].
+    pub port: u16,
+}
+
+impl Default for ControlChannelConfig {
+    fn default() -> Self {
+        Self {
+            privilege: Privilege::Control,
+            switchover_key: None,
+            enable_switchover: false,
+            timeout: Duration::from_millis(500),
+            retries: 3,
+            port: GVCP_PORT,
+        }
+    }
+}
+
+impl ControlChannelConfig {
+    /// Opens the control channel to the device at `device`, and requests the privilege.
+    ///
+    /// Returns [`Error::AccessDenied`] if the device refuses the privilege, e.g. another
+    /// application already has it and the takeover isn't possible.
+    pub fn open(&self, device: Ipv4Addr) -> Result<ControlChannel> {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.connect(SocketAddrV4::new(device, self.port))?;
+        socket.set_read_timeout(Some(self.timeout))?;
+
+        let mut channel = ControlChannel {
+            socket,
+            privilege: Privilege::Monitor,
+            request_id: 0,
+            retries: self.retries,
+        };
+        let ccp = match self.privilege {
+            Privilege::Monitor => return Ok(channel),
+            Privilege::Control if self.enable_switchover => {
+                CCP_CONTROL_ACCESS | CCP_CONTROL_SWITCHOVER_ENABLE
+            }
+            Privilege::Control => CCP_CONTROL_ACCESS,
+            Privilege::Exclusive => CCP_EXCLUSIVE_ACCESS,
+        };
+        let key = u32::from(self.switchover_key.unwrap_or(0)) << 16;
+        channel.send_write_reg(CCP_ADDRESS, key | ccp)?;
+        channel.privilege = self.privilege;
+        Ok(channel)
+    }
+}
+
+/// A control channel to a `GigE Vision` device.
+///
+/// The device releases the privilege if no command arrives within its heartbeat timeout, so
+/// [`Self::heartbeat`] must be called periodically while the channel is idle.
+/// The privilege is released when the channel is dropped.
+#[derive(Debug)]
+pub struct ControlChannel {
+    socket: UdpSocket,
+    privilege: Privilege,
+    request_id: u16,
+    retries: u32,
+}
+
+impl ControlChannel {
+    /// Returns the privilege the channel holds.
+    #[must_use]
+    pub fn privilege(&self) -> Privilege {
+        self.privilege
+    }
+
+    /// Reads the register at `address`.
+    pub fn read_reg(&mut self, address: u32) -> Result<u32> {
+        let mut payload = Vec::with_capacity(4);
+        payload.write_bytes_be(address)?;
+        let cmd = CommandHeader::read_reg(self.next_request_id(), 1);
+        let ack = self.transact(cmd, &payload)?;
+        let mut cursor = Cursor::new(ack);
+        Ok(cursor.read_bytes_be()?)
+    }
+
+    /// Writes `value` to the register at `address`.
+    ///
+    /// Returns [`Error::AccessDenied`] if the channel is opened with [`Privilege::Monitor`].
+    pub fn write_reg(&mut self, address: u32, value: u32) -> Result<()> {
+        if self.privilege == Privilege::Monitor {
+            return Err(Error::AccessDenied(
+                "can't write registers in monitor mode".into(),
+            ));
+        }
+        self.send_write_reg(address, value)
+    }
+
+    /// Sends a command to keep the privilege alive. It reads `CCP` register.
+    pub fn heartbeat(&mut self) -> Result<()> {
+        self.read_reg(CCP_ADDRESS).map(|_| ())
+    }
+
+    /// Releases the privilege, then closes the channel.
+    pub fn close(mut self) -> Result<()> {
+        self.release()
+    }
+
+    fn release(&mut self) -> Result<()> {
+        if self.privilege == Privilege::Monitor {
+            return Ok(());
+        }
+        self.send_write_reg(CCP_ADDRESS, 0)?;
+        self.privilege = Privilege::Monitor;
+        Ok(())
+    }
+
+    fn send_write_reg(&mut self, address: u32, value: u32) -> Result<()> {
+        let mut payload = Vec::with_capacity(8);
+        payload.write_bytes_be(address)?;
+        payload.write_bytes_be(value)?;
+        let cmd = CommandHeader::write_reg(self.next_request_id(), 1);
+        self.transact(cmd, &payload).map(|_| ())
+    }
+
+    /// Sends a command and returns the payload of the acknowledge. The command is resent with
+    /// the same request id if the acknowledge isn't received in time.
+    fn transact(&mut self, cmd: CommandHeader, payload: &[u8]) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(CommandHeader::LEN + payload.len());
+        cmd.serialize(&mut buf)?;
+        buf.extend_from_slice(payload);
+
+        let mut ack_buf = vec![0; MAX_ACK_LEN];
+        for _ in 0..=self.retries {
+            self.socket.send(&buf)?;
+            loop {
+                let len = match self.socket.recv(&mut ack_buf) {
+                    Ok(len) => len,
+                    Err(e)
+                        if e.kind() == io::ErrorKind::WouldBlock
+                            || e.kind() == io::ErrorKind::TimedOut =>
+                    {
+                        break
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                let ack = AckPacket::parse(&ack_buf[..len])?;
+                // Stale acknowledges of retransmitted commands are skipped.
+                if ack.ack_id() != cmd.request_id() {
+                    continue;
+                }
+                return match ack.status() {
+                    0 => Ok(ack.payload().to_vec()),
+                    STATUS_ACCESS_DENIED => Err(Error::AccessDenied(
+                        "device denied the access to the register".into(),
+                    )),
+                    status => Err(Error::Status(status)),
+                };
+            }
+        }
+
+        Err(io::Error::new(io::ErrorKind::TimedOut, "no acknowledge from the device").into())
+    }
+
+    fn next_request_id(&mut self) -> u16 {
+        // Request id `0` is reserved.
+        self.request_id = self.request_id.checked_add(1).unwrap_or(1);
+        self.request_id
+    }
+}
+
+impl Drop for ControlChannel {
+    fn drop(&mut self) {
+        if let Err(e) = self.release() {
+            log::warn!("failed to release the control channel privilege: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replies to `count` commands from a host as a device listening on an ephemeral port, and
+    /// returns the port and the received commands.
+    fn spawn_device(count: usize, status: u16) -> (u16, std::thread::JoinHandle<Vec<Vec<u8>>>) {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let mut cmds = vec![];
+            let mut buf = [0; 64];
+            for _ in 0..count {
+                let (len, from) = socket.recv_from(&mut buf).unwrap();
+                let cmd = buf[..len].to_vec();
+                let mut ack = vec![];
+                ack.write_bytes_be(status).unwrap();
+                // Acknowledge code is the command code plus one.
+                ack.write_bytes_be(u16::from_be_bytes([cmd[2], cmd[3]]) + 1)
+                    .unwrap();
+                ack.write_bytes_be(4_u16).unwrap();
+                ack.extend_from_slice(&cmd[6..8]);
+                ack.write_bytes_be(0xdead_beef_u32).unwrap();
+                socket.send_to(&ack, from).unwrap();
+                cmds.push(cmd);
+            }
+            cmds
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_control_channel() {
+        let device = Ipv4Addr::LOCALHOST;
+        let (port, handle) = spawn_device(4, 0);
+        let config = ControlChannelConfig {
+            switchover_key: Some(0x1234),
+            port,
+            ..ControlChannelConfig::default()
+        };
+        let mut channel = config.open(device).unwrap();
+        assert_eq!(channel.privilege(), Privilege::Control);
+        assert_eq!(channel.read_reg(0x0938).unwrap(), 0xdead_beef);
+        channel.write_reg(0x0938, 1000).unwrap();
+        channel.close().unwrap();
+
+        let cmds = handle.join().unwrap();
+        // Takeover with the switchover key.
+        assert_eq!(
+            &cmds[0][8..],
+            &[0x00, 0x00, 0x0A, 0x00, 0x12, 0x34, 0x00, 0x02]
+        );
+        assert_eq!(&cmds[1][8..], &[0x00, 0x00, 0x09, 0x38]);
+        assert_eq!(
+            &cmds[2][8..],
+            &[0x00, 0x00, 0x09, 0x38, 0x00, 0x00, 0x03, 0xE8]
+        );
+        // Release.
+        assert_eq!(
+            &cmds[3][8..],
+            &[0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+
+        let (port, handle) = spawn_device(1, STATUS_ACCESS_DENIED);
+        let config = ControlChannelConfig {
+            port,
+            ..ControlChannelConfig::default()
+        };
+        let res = config.open(device);
+        assert!(matches!(res, Err(Error::AccessDenied(_))));
+        handle.join().unwrap();
+
+        let config = ControlChannelConfig {
+            privilege: Privilege::Monitor,
+            port,
+            ..ControlChannelConfig::default()
+        };
+        let mut channel = config.open(device).unwrap();
+        assert!(matches!(
+            channel.write_reg(0x0938, 1000),
+            Err(Error::AccessDenied(_))
+        ));
+    }
+}
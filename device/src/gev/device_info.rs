@@ -0,0 +1,308 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{convert::TryInto, fmt, net::Ipv4Addr};
+
+use semver::Version;
+
+use super::{Error, Result};
+
+/// Device information reported in `DISCOVERY_ACK`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// `GigE Vision` version the device provides.
+    pub gev_version: Version,
+
+    /// Mode of the device, e.g. the device class and the endianness of the bootstrap registers.
+    pub device_mode: DeviceMode,
+
+    /// MAC address of the network interface of the device.
+    pub mac_address: [u8; 6],
+
+    /// IP configuration schemes supported by the device.
+    pub ip_config_options: IpConfig,
+
+    /// IP configuration schemes currently enabled on the device.
+    ///
+    /// NOTE: The persistent IP address itself isn't reported in `DISCOVERY_ACK`, it must be read
+    /// from the bootstrap register of the device.
+    pub ip_config_current: IpConfig,
+
+    /// Current IP address of the device.
+    pub current_ip: Ipv4Addr,
+
+    /// Current subnet mask of the device.
+    pub current_subnet_mask: Ipv4Addr,
+
+    /// Default gateway of the device.
+    pub default_gateway: Ipv4Addr,
+
+    /// Manufacturer name of the device.
+    pub manufacturer_name: String,
+
+    /// Model name of the device.
+    pub model_name: String,
+
+    /// Manufacturer specific device version.
+    pub device_version: String,
+
+    /// Manufacturer specific information.
+    pub manufacturer_info: String,
+
+    /// Serial number of the device.
+    pub serial_number: String,
+
+    /// User defined name.
+    /// This field is optional.
+    pub user_defined_name: Option<String>,
+
+    /// IP address of the host network interface which received the acknowledge.
+    pub host_address: Ipv4Addr,
+}
+
+impl DeviceInfo {
+    /// Length of the payload of `DISCOVERY_ACK`.
+    pub(super) const DISCOVERY_ACK_LEN: usize = 248;
+
+    /// Parses the payload of `DISCOVERY_ACK` received on the host interface of `host_address`.
+    pub(super) fn parse_discovery_ack(payload: &[u8], host_address: Ipv4Addr) -> Result<Self> {
+        if payload.len() < Self::DISCOVERY_ACK_LEN {
+            return Err(Error::InvalidPacket(
+                format!(
+                    "payload of DISCOVERY_ACK must be {} bytes, but got {} bytes",
+                    Self::DISCOVERY_ACK_LEN,
+                    payload.len()
+                )
+                .into(),
+            ));
+        }
+
+        let user_defined_name = parse_string(&payload[0xE8..0xF8]);
+        Ok(Self {
+            gev_version: Version::new(
+                u64::from(read_u16(payload, 0x00)),
+                u64::from(read_u16(payload, 0x02)),
+                0,
+            ),
+            device_mode: DeviceMode(read_u32(payload, 0x04)),
+            mac_address: payload[0x0A..0x10].try_into().unwrap(),
+            ip_config_options: IpConfig(read_u32(payload, 0x10)),
+            ip_config_current: IpConfig(read_u32(payload, 0x14)),
+            current_ip: read_u32(payload, 0x24).into(),
+            current_subnet_mask: read_u32(payload, 0x34).into(),
+            default_gateway: read_u32(payload, 0x44).into(),
+            manufacturer_name: parse_string(&payload[0x48..0x68]),
+            model_name: parse_string(&payload[0x68..0x88]),
+            device_version: parse_string(&payload[0x88..0xA8]),
+            manufacturer_info: parse_string(&payload[0xA8..0xD8]),
+            serial_number: parse_string(&payload[0xD8..0xE8]),
+            user_defined_name: if user_defined_name.is_empty() {
+                None
+            } else {
+                Some(user_defined_name)
+            },
+            host_address,
+        })
+    }
+
+    /// Returns the MAC address in the form of `00:11:22:33:44:55`.
+    #[must_use]
+    pub fn mac_address_string(&self) -> String {
+        let octets: Vec<String> = self
+            .mac_address
+            .iter()
+            .map(|octet| format!("{:02x}", octet))
+            .collect();
+        octets.join(":")
+    }
+}
+
+/// Mode of a device reported in `DISCOVERY_ACK`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceMode(u32);
+
+impl DeviceMode {
+    /// Returns `true` if the bootstrap registers of the device are big endian.
+    #[must_use]
+    pub fn is_big_endian(self) -> bool {
+        self.0 & 0x8000_0000 != 0
+    }
+
+    /// Returns the class of the device.
+    #[must_use]
+    pub fn device_class(self) -> DeviceClass {
+        match (self.0 >> 28) & 0b111 {
+            0 => DeviceClass::Transmitter,
+            1 => DeviceClass::Receiver,
+            2 => DeviceClass::Transceiver,
+            3 => DeviceClass::Peripheral,
+            other => DeviceClass::Reserved(other as u8),
+        }
+    }
+
+    /// Returns the character set of the string fields, `1` for `UTF-8` and `2` for `ASCII`.
+    #[must_use]
+    pub fn character_set(self) -> u8 {
+        self.0 as u8
+    }
+
+    /// Returns the raw value of the mode.
+    #[must_use]
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Class of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceClass {
+    /// A device which transmits streams, e.g. a camera.
+    Transmitter,
+
+    /// A device which receives streams.
+    Receiver,
+
+    /// A device which both transmits and receives streams.
+    Transceiver,
+
+    /// A device which neither transmits nor receives streams.
+    Peripheral,
+
+    /// A class reserved for future use.
+    Reserved(u8),
+}
+
+/// Set of IP configuration schemes, which is used both for the supported schemes and the enabled
+/// schemes of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpConfig(u32);
+
+impl IpConfig {
+    /// Returns `true` if the persistent IP address is included.
+    #[must_use]
+    pub fn persistent_ip(self) -> bool {
+        self.0 & 0b001 != 0
+    }
+
+    /// Returns `true` if `DHCP` is included.
+    #[must_use]
+    pub fn dhcp(self) -> bool {
+        self.0 & 0b010 != 0
+    }
+
+    /// Returns `true` if link-local address is included.
+    #[must_use]
+    pub fn link_local(self) -> bool {
+        self.0 & 0b100 != 0
+    }
+
+    /// Returns the raw value of the register.
+    #[must_use]
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "### Device Information ###")?;
+
+        writeln!(f, "GEV Version: {}", self.gev_version)?;
+
+        writeln!(f, "Device Class: {:?}", self.device_mode.device_class())?;
+
+        writeln!(f, "MAC Address: {}", self.mac_address_string())?;
+
+        writeln!(f, "Current IP: {}", self.current_ip)?;
+
+        writeln!(f, "Current Subnet Mask: {}", self.current_subnet_mask)?;
+
+        writeln!(f, "Default Gateway: {}", self.default_gateway)?;
+
+        writeln!(
+            f,
+            "IP Config: Persistent IP: {}, DHCP: {}, LLA: {}",
+            self.ip_config_current.persistent_ip(),
+            self.ip_config_current.dhcp(),
+            self.ip_config_current.link_local()
+        )?;
+
+        writeln!(f, "Manufacturer Name: {}", self.manufacturer_name)?;
+
+        writeln!(f, "Model Name: {}", self.model_name)?;
+
+        writeln!(f, "Device Version: {}", self.device_version)?;
+
+        writeln!(f, "Manufacturer Information: {}", self.manufacturer_info)?;
+
+        writeln!(f, "Serial Number: {}", self.serial_number)?;
+
+        if let Some(user_defined_name) = &self.user_defined_name {
+            writeln!(f, "User Defined Name: {}", user_defined_name)
+        } else {
+            writeln!(f, "User Defined Name: N/A")
+        }?;
+
+        write!(f, "Host Interface: {}", self.host_address)?;
+
+        Ok(())
+    }
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+/// Parses a NUL-terminated string field. Invalid UTF-8 sequences are replaced.
+fn parse_string(field: &[u8]) -> String {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..len]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_discovery_ack() {
+        let mut payload = vec![0; DeviceInfo::DISCOVERY_ACK_LEN];
+        // Version 2.1.
+        payload[0x01] = 2;
+        payload[0x03] = 1;
+        // Big endian transmitter, UTF-8.
+        payload[0x04] = 0x80;
+        payload[0x07] = 1;
+        payload[0x0A..0x10].copy_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        // Supports all schemes, and persistent IP is enabled.
+        payload[0x13] = 0b111;
+        payload[0x17] = 0b101;
+        payload[0x24..0x28].copy_from_slice(&[192, 168, 0, 10]);
+        payload[0x34..0x38].copy_from_slice(&[255, 255, 255, 0]);
+        payload[0x48..0x50].copy_from_slice(b"Cameleon");
+        payload[0xD8..0xDC].copy_from_slice(b"1234");
+
+        let info =
+            DeviceInfo::parse_discovery_ack(&payload, Ipv4Addr::new(192, 168, 0, 1)).unwrap();
+        assert_eq!(info.gev_version, Version::new(2, 1, 0));
+        assert!(info.device_mode.is_big_endian());
+        assert_eq!(info.device_mode.device_class(), DeviceClass::Transmitter);
+        assert_eq!(info.device_mode.character_set(), 1);
+        assert_eq!(info.mac_address_string(), "00:11:22:33:44:55");
+        assert!(info.ip_config_options.dhcp());
+        assert!(info.ip_config_current.persistent_ip());
+        assert!(!info.ip_config_current.dhcp());
+        assert_eq!(info.current_ip, Ipv4Addr::new(192, 168, 0, 10));
+        assert_eq!(info.current_subnet_mask, Ipv4Addr::new(255, 255, 255, 0));
+        assert_eq!(info.default_gateway, Ipv4Addr::UNSPECIFIED);
+        assert_eq!(info.manufacturer_name, "Cameleon");
+        assert_eq!(info.serial_number, "1234");
+        assert_eq!(info.user_defined_name, None);
+
+        assert!(DeviceInfo::parse_discovery_ack(&payload[..100], Ipv4Addr::UNSPECIFIED).is_err());
+    }
+}
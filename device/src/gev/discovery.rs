@@ -0,0 +1,174 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use super::{
+    protocol::{AckPacket, CommandHeader},
+    DeviceInfo, Result, GVCP_PORT,
+};
+
+/// Interval to poll sockets while waiting for acknowledges.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// An IPv4 network interface of the host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkInterface {
+    /// Name of the interface, e.g. `eth0`.
+    pub name: String,
+
+    /// IP address of the interface.
+    pub address: Ipv4Addr,
+
+    /// Subnet mask of the interface.
+    pub netmask: Ipv4Addr,
+}
+
+/// Returns the IPv4 network interfaces of the host except loopback interfaces.
+pub fn network_interfaces() -> Result<Vec<NetworkInterface>> {
+    Ok(if_addrs::get_if_addrs()?
+        .into_iter()
+        .filter(|iface| !iface.is_loopback())
+        .filter_map(|iface| match iface.addr {
+            if_addrs::IfAddr::V4(addr) => Some(NetworkInterface {
+                name: iface.name,
+                address: addr.ip,
+                netmask: addr.netmask,
+            }),
+            if_addrs::IfAddr::V6(_) => None,
+        })
+        .collect())
+}
+
+/// Destination of `DISCOVERY_CMD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiscoveryTarget {
+    /// Broadcasts the command to all devices on the link of each interface.
+    Broadcast,
+
+    /// Sends the command to the device with the address, which may be behind routers.
+    Unicast(Ipv4Addr),
+}
+
+/// Options of [`enumerate_devices`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveryOptions {
+    /// Addresses of the host interfaces on which devices are discovered. `None` means all
+    /// interfaces returned by [`network_interfaces`] for [`DiscoveryTarget::Broadcast`], and the
+    /// interface chosen by the routing table for [`DiscoveryTarget::Unicast`].
+    pub interfaces: Option<Vec<Ipv4Addr>>,
+
+    /// Destination of the command.
+    pub target: DiscoveryTarget,
+
+    /// Allows devices to broadcast their acknowledges. This lets the host discover devices whose
+    /// IP address is on another subnet, e.g. misconfigured devices.
+    pub allow_broadcast_ack: bool,
+
+    /// Time to wait for acknowledges.
+    pub timeout: Duration,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            interfaces: None,
+            target: DiscoveryTarget::Broadcast,
+            allow_broadcast_ack: true,
+            timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Discovers devices according to `options`.
+///
+/// A device connected to multiple interfaces is reported once per interface, which can be
+/// distinguished by [`DeviceInfo::host_address`]. Malformed acknowledges are ignored.
+pub fn enumerate_devices(options: &DiscoveryOptions) -> Result<Vec<DeviceInfo>> {
+    let dest = match options.target {
+        DiscoveryTarget::Broadcast => Ipv4Addr::BROADCAST,
+        DiscoveryTarget::Unicast(addr) => addr,
+    };
+    let dest = SocketAddrV4::new(dest, GVCP_PORT);
+
+    let interfaces = match (&options.interfaces, options.target) {
+        (Some(interfaces), _) => interfaces.clone(),
+        (None, DiscoveryTarget::Broadcast) => network_interfaces()?
+            .into_iter()
+            .map(|iface| iface.address)
+            .collect(),
+        (None, DiscoveryTarget::Unicast(_)) => vec![Ipv4Addr::UNSPECIFIED],
+    };
+
+    let cmd = CommandHeader::discovery(1, options.allow_broadcast_ack);
+    let mut cmd_buf = Vec::with_capacity(CommandHeader::LEN);
+    cmd.serialize(&mut cmd_buf)?;
+
+    let mut sockets = Vec::with_capacity(interfaces.len());
+    for address in interfaces {
+        let socket = UdpSocket::bind(SocketAddrV4::new(address, 0))?;
+        socket.set_broadcast(true)?;
+        socket.set_nonblocking(true)?;
+        socket.send_to(&cmd_buf, dest)?;
+        let host_address = host_address(&socket, dest);
+        sockets.push((socket, host_address));
+    }
+
+    let mut devices = vec![];
+    let mut buf = vec![0; AckPacket::HEADER_LEN + DeviceInfo::DISCOVERY_ACK_LEN];
+    let deadline = Instant::now() + options.timeout;
+    while Instant::now() < deadline {
+        let mut received = false;
+        for (socket, host_address) in &sockets {
+            let len = match socket.recv_from(&mut buf) {
+                Ok((len, _)) => len,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e.into()),
+            };
+            received = true;
+
+            let info = AckPacket::parse(&buf[..len]).and_then(|ack| {
+                if ack.is_discovery_ack() && ack.status() == 0 && ack.ack_id() == cmd.request_id() {
+                    DeviceInfo::parse_discovery_ack(ack.payload(), *host_address).map(Some)
+                } else {
+                    Ok(None)
+                }
+            });
+            match info {
+                Ok(Some(info)) if !devices.contains(&info) => devices.push(info),
+                Ok(_) => {}
+                Err(e) => log::warn!("ignored malformed acknowledge: {}", e),
+            }
+        }
+
+        if !received {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    Ok(devices)
+}
+
+/// Returns the address of the host interface `socket` sends packets to `dest` from.
+fn host_address(socket: &UdpSocket, dest: SocketAddrV4) -> Ipv4Addr {
+    if let Ok(SocketAddr::V4(addr)) = socket.local_addr() {
+        if !addr.ip().is_unspecified() {
+            return *addr.ip();
+        }
+    }
+
+    // Ask the routing table which interface is used, without sending any packet.
+    let probe = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)).and_then(|probe| {
+        probe.connect(dest)?;
+        probe.local_addr()
+    });
+    match probe {
+        Ok(SocketAddr::V4(addr)) => *addr.ip(),
+        _ => Ipv4Addr::UNSPECIFIED,
+    }
+}
@@ -0,0 +1,211 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains an adaptive controller of inter-packet delay (`GevSCPD`), which shares
+//! the bandwidth of a host interface among multiple devices.
+
+use std::{collections::HashMap, hash::Hash, time::Duration};
+
+/// Configuration of [`InterPacketDelayController`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterPacketDelayConfig {
+    /// Link speed of the host interface in Mbps.
+    pub link_speed_mbps: u32,
+
+    /// Size of stream packets in bytes, see [`super::StreamTuning::packet_size`].
+    pub packet_size: u32,
+
+    /// Ratio of lost packets above which the link is regarded as congested.
+    pub loss_threshold: f64,
+
+    /// Rate added to each device per update without congestion, in Mbps.
+    pub additive_increase_mbps: f64,
+
+    /// Factor multiplied to the rate of each device per update with congestion. Must be in
+    /// `(0, 1)`.
+    pub multiplicative_decrease: f64,
+
+    /// Upper bound of the delay.
+    pub max_delay: Duration,
+}
+
+impl Default for InterPacketDelayConfig {
+    fn default() -> Self {
+        Self {
+            link_speed_mbps: 1000,
+            packet_size: 1500,
+            loss_threshold: 0.001,
+            additive_increase_mbps: 10.0,
+            multiplicative_decrease: 0.75,
+            max_delay: Duration::from_millis(1),
+        }
+    }
+}
+
+/// Packet statistics of a device observed since the previous update.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PacketStatistics {
+    /// Number of received packets.
+    pub received: u64,
+
+    /// Number of lost packets, e.g. detected by gaps of packet ids.
+    pub lost: u64,
+}
+
+impl PacketStatistics {
+    fn loss_ratio(self) -> f64 {
+        let total = self.received + self.lost;
+        if total == 0 {
+            0.0
+        } else {
+            self.lost as f64 / total as f64
+        }
+    }
+}
+
+/// An adaptive controller of inter-packet delay for devices sharing a host interface.
+///
+/// The controller assigns a rate to each device and converts it to the delay. Rates increase
+/// additively while no packet is lost, and decrease multiplicatively when the link is congested,
+/// i.e. the loss ratio of either the device or all devices exceeds the threshold. As the AIMD
+/// scheme of TCP, this converges to the fair share of the link bandwidth.
+///
+/// `K` identifies devices, e.g. their IP addresses.
+#[derive(Clone, Debug)]
+pub struct InterPacketDelayController<K> {
+    config: InterPacketDelayConfig,
+    rates_mbps: HashMap<K, f64>,
+}
+
+impl<K> InterPacketDelayController<K>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Constructs a controller without devices.
+    #[must_use]
+    pub fn new(config: InterPacketDelayConfig) -> Self {
+        Self {
+            config,
+            rates_mbps: HashMap::new(),
+        }
+    }
+
+    /// Adds a device, whose initial rate is the even share of the link among the devices.
+    /// Returns the initial delay of the device.
+    pub fn add_device(&mut self, device: K) -> Duration {
+        let share = f64::from(self.config.link_speed_mbps) / (self.rates_mbps.len() + 1) as f64;
+        self.rates_mbps.insert(device.clone(), share);
+        self.delay(&device).unwrap()
+    }
+
+    /// Removes a device.
+    pub fn remove_device(&mut self, device: &K) {
+        self.rates_mbps.remove(device);
+    }
+
+    /// Returns the current delay of the device.
+    #[must_use]
+    pub fn delay(&self, device: &K) -> Option<Duration> {
+        self.rates_mbps
+            .get(device)
+            .map(|&rate| self.delay_from_rate(rate))
+    }
+
+    /// Updates rates with statistics observed since the previous update, and returns the new
+    /// delays of the devices in `stats` which should be written to the devices, e.g. by
+    /// [`super::ControlChannel::set_inter_packet_delay`].
+    ///
+    /// Devices which aren't added to the controller are ignored.
+    pub fn update(&mut self, stats: &[(K, PacketStatistics)]) -> Vec<(K, Duration)> {
+        let total = stats
+            .iter()
+            .fold(PacketStatistics::default(), |acc, (_, stat)| {
+                PacketStatistics {
+                    received: acc.received + stat.received,
+                    lost: acc.lost + stat.lost,
+                }
+            });
+        let link_congested = total.loss_ratio() > self.config.loss_threshold;
+
+        let link_speed = f64::from(self.config.link_speed_mbps);
+        let mut delays = Vec::with_capacity(stats.len());
+        for (device, stat) in stats {
+            let rate = match self.rates_mbps.get_mut(device) {
+                Some(rate) => rate,
+                None => continue,
+            };
+            if link_congested || stat.loss_ratio() > self.config.loss_threshold {
+                *rate *= self.config.multiplicative_decrease;
+            } else {
+                *rate = (*rate + self.config.additive_increase_mbps).min(link_speed);
+            }
+            let rate = *rate;
+            delays.push((device.clone(), self.delay_from_rate(rate)));
+        }
+        delays
+    }
+
+    /// Converts a rate to the delay, which is the interval of packets at the rate minus the
+    /// transmission time of a packet at the link speed.
+    fn delay_from_rate(&self, rate_mbps: f64) -> Duration {
+        let bits = f64::from(self.config.packet_size) * 8.0;
+        let interval_ns = bits * 1000.0 / rate_mbps.max(f64::MIN_POSITIVE);
+        let transmission_ns = bits * 1000.0 / f64::from(self.config.link_speed_mbps.max(1));
+        let delay_ns = (interval_ns - transmission_ns).max(0.0);
+        let max_ns = self.config.max_delay.as_nanos() as f64;
+        Duration::from_nanos(delay_ns.min(max_ns) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_controller() {
+        let mut controller = InterPacketDelayController::new(InterPacketDelayConfig::default());
+        // A single device may use the whole link.
+        assert_eq!(controller.add_device(0), Duration::default());
+        // A 1500 bytes packet takes 12 us on 1 GigE, and the second device gets a half share.
+        assert_eq!(controller.add_device(1), Duration::from_micros(12));
+
+        let ok = PacketStatistics {
+            received: 1000,
+            lost: 0,
+        };
+        let lossy = PacketStatistics {
+            received: 900,
+            lost: 100,
+        };
+
+        // Loss on a device is a congestion of the shared link, so both devices back off.
+        let before = controller.delay(&0).unwrap();
+        let delays: HashMap<_, _> = controller
+            .update(&[(0, ok), (1, lossy)])
+            .into_iter()
+            .collect();
+        assert!(delays[&0] > before);
+        assert!(delays[&1] > Duration::from_micros(12));
+
+        // Without loss, the delays shrink.
+        let before = controller.delay(&1).unwrap();
+        let delays: HashMap<_, _> = controller.update(&[(0, ok), (1, ok)]).into_iter().collect();
+        assert!(delays[&1] < before);
+
+        // Rates converge to the fair share.
+        for _ in 0..1000 {
+            let stats = [(0, ok), (1, ok)];
+            controller.update(&stats);
+            let total: f64 = controller.rates_mbps.values().sum();
+            if total > 1000.0 {
+                controller.update(&[(0, lossy), (1, lossy)]);
+            }
+        }
+        let rates: Vec<f64> = controller.rates_mbps.values().copied().collect();
+        assert!((rates[0] - rates[1]).abs() < 20.0);
+
+        // Unknown devices are ignored.
+        assert!(controller.update(&[(2, ok)]).is_empty());
+    }
+}
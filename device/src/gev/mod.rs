@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains `GigE Vision` protocol, device discovery, the control channel and a
+//! socket for the stream channel.
+//!
+//! # Examples
+//! ```no_run
+//! use cameleon_device::gev::{self, DiscoveryOptions};
+//!
+//! // Discover devices on all network interfaces.
+//! let devices = gev::enumerate_devices(&DiscoveryOptions::default()).unwrap();
+//! for device in devices {
+//!     println!("{}", device);
+//! }
+//! ```
+
+pub mod protocol;
+
+mod control;
+mod device_info;
+mod discovery;
+mod ipd;
+mod nic;
+#[cfg(target_os = "linux")]
+mod packet_socket;
+mod stream;
+
+pub use control::{ControlChannel, ControlChannelConfig, Privilege};
+pub use device_info::{DeviceClass, DeviceInfo, DeviceMode, IpConfig};
+pub use discovery::{
+    enumerate_devices, network_interfaces, DiscoveryOptions, DiscoveryTarget, NetworkInterface,
+};
+pub use ipd::{InterPacketDelayConfig, InterPacketDelayController, PacketStatistics};
+pub use nic::{NicCapabilities, StreamTuning};
+pub use stream::{ReceivePath, StreamSocket, StreamSocketConfig};
+
+use std::borrow::Cow;
+
+use thiserror::Error;
+
+/// UDP port on which devices listen to `GVCP` commands.
+pub const GVCP_PORT: u16 = 3956;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("network io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("packet is broken: {0}")]
+    InvalidPacket(Cow<'static, str>),
+
+    #[error("access denied: {0}")]
+    AccessDenied(Cow<'static, str>),
+
+    #[error("device returned error status: {0:#06x}")]
+    Status(u16),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
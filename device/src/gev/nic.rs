@@ -0,0 +1,260 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a probe of host network interfaces and advice of stream configuration
+//! derived from it.
+
+use std::{io, time::Duration};
+
+use super::{network_interfaces, ControlChannel, DeviceInfo, NetworkInterface, Result};
+
+/// Address of `Timestamp Tick Frequency (high part)` register.
+const TIMESTAMP_TICK_FREQUENCY_HIGH: u32 = 0x093C;
+
+/// Address of `Timestamp Tick Frequency (low part)` register.
+const TIMESTAMP_TICK_FREQUENCY_LOW: u32 = 0x0940;
+
+/// Address of `SCPS0` (Stream Channel Packet Size) register. Registers of the `n`-th stream
+/// channel are placed at `0x40 * n` offset.
+const SCPS_BASE: u32 = 0x0D04;
+
+/// Address of `SCPD0` (Stream Channel Packet Delay) register.
+const SCPD_BASE: u32 = 0x0D08;
+
+/// Bits of the packet size field in `SCPS` register.
+const SCPS_PACKET_SIZE_MASK: u32 = 0xFFFF;
+
+/// Length of IP and UDP headers included in the packet size.
+const IP_UDP_HEADER_LEN: u32 = 28;
+
+/// Length of `GVSP` header included in the packet size.
+const GVSP_HEADER_LEN: u32 = 8;
+
+/// Packet size used when the MTU of the interface is unknown, which fits standard Ethernet.
+const DEFAULT_PACKET_SIZE: u32 = 1500;
+
+/// Capabilities of a host network interface.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NicCapabilities {
+    /// The interface.
+    pub interface: NetworkInterface,
+
+    /// MTU of the interface in bytes. `None` if it can't be obtained.
+    pub mtu: Option<u32>,
+
+    /// Link speed of the interface in Mbps. `None` if it can't be obtained, e.g. the link is
+    /// down or the platform doesn't report it.
+    pub speed_mbps: Option<u32>,
+
+    /// Name of the kernel driver of the interface. `None` if it can't be obtained.
+    pub driver: Option<String>,
+}
+
+impl NicCapabilities {
+    /// Probes `interface`.
+    ///
+    /// NOTE: Only Linux is supported for now. On other platforms, all capabilities are `None`.
+    pub fn probe(interface: NetworkInterface) -> Self {
+        let (mtu, speed_mbps, driver) = imp::probe(&interface.name);
+        Self {
+            interface,
+            mtu,
+            speed_mbps,
+            driver,
+        }
+    }
+
+    /// Probes the host interface through which `device` is discovered.
+    pub fn probe_for_device(device: &DeviceInfo) -> Result<Self> {
+        let interface = network_interfaces()?
+            .into_iter()
+            .find(|iface| iface.address == device.host_address)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::AddrNotAvailable,
+                    format!("no interface has address {}", device.host_address),
+                )
+            })?;
+        Ok(Self::probe(interface))
+    }
+
+    /// Advises stream configuration of each of `device_count` devices sharing the interface.
+    #[must_use]
+    pub fn advise(&self, device_count: u32) -> StreamTuning {
+        let device_count = device_count.max(1);
+        let mut advice = vec![];
+
+        let packet_size = match self.mtu {
+            Some(mtu) => {
+                if mtu < 9000 {
+                    advice.push(format!(
+                        "MTU of {} is {}, jumbo frames (MTU 9000) reduce per-packet overhead",
+                        self.interface.name, mtu
+                    ));
+                }
+                mtu.min(SCPS_PACKET_SIZE_MASK)
+            }
+            None => {
+                advice.push(format!(
+                    "MTU of {} is unknown, packet size is set to {}",
+                    self.interface.name, DEFAULT_PACKET_SIZE
+                ));
+                DEFAULT_PACKET_SIZE
+            }
+        };
+
+        // Spaces packets of each device so that devices share the link bandwidth evenly.
+        let inter_packet_delay = match self.speed_mbps {
+            Some(speed) if speed > 0 => {
+                if speed < 1000 {
+                    advice.push(format!(
+                        "link speed of {} is {} Mbps, which is below 1 GigE",
+                        self.interface.name, speed
+                    ));
+                }
+                let packet_time =
+                    Duration::from_nanos(u64::from(packet_size) * 8 * 1000 / u64::from(speed));
+                packet_time * (device_count - 1)
+            }
+            _ => {
+                if device_count > 1 {
+                    advice.push(format!(
+                        "link speed of {} is unknown, inter-packet delay isn't advised",
+                        self.interface.name
+                    ));
+                }
+                Duration::default()
+            }
+        };
+
+        StreamTuning {
+            packet_size,
+            inter_packet_delay,
+            advice,
+        }
+    }
+}
+
+/// Stream configuration of a device, which is applied before the stream starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamTuning {
+    /// Size of stream packets in bytes, including IP, UDP and `GVSP` headers.
+    pub packet_size: u32,
+
+    /// Delay between stream packets.
+    pub inter_packet_delay: Duration,
+
+    /// Human readable advice about the host configuration, e.g. enabling jumbo frames.
+    pub advice: Vec<String>,
+}
+
+impl StreamTuning {
+    /// Size of the payload carried by each stream packet.
+    #[must_use]
+    pub fn payload_size(&self) -> u32 {
+        self.packet_size
+            .saturating_sub(IP_UDP_HEADER_LEN + GVSP_HEADER_LEN)
+    }
+
+    /// Writes the configuration to the `stream_channel`-th stream channel registers of the
+    /// device. Must be called before the stream starts.
+    ///
+    /// The inter-packet delay is converted to ticks of the device timestamp.
+    pub fn apply(&self, channel: &mut ControlChannel, stream_channel: u32) -> Result<()> {
+        let offset = stream_channel * 0x40;
+
+        let scps = channel.read_reg(SCPS_BASE + offset)?;
+        let scps = (scps & !SCPS_PACKET_SIZE_MASK) | (self.packet_size & SCPS_PACKET_SIZE_MASK);
+        channel.write_reg(SCPS_BASE + offset, scps)?;
+
+        channel.set_inter_packet_delay(stream_channel, self.inter_packet_delay)
+    }
+}
+
+impl ControlChannel {
+    /// Sets the delay between stream packets of the `stream_channel`-th stream channel. It can
+    /// be changed while streaming.
+    ///
+    /// The delay is converted to ticks of the device timestamp.
+    pub fn set_inter_packet_delay(&mut self, stream_channel: u32, delay: Duration) -> Result<()> {
+        let high = self.read_reg(TIMESTAMP_TICK_FREQUENCY_HIGH)?;
+        let low = self.read_reg(TIMESTAMP_TICK_FREQUENCY_LOW)?;
+        let frequency = (u128::from(high) << 32) | u128::from(low);
+        let ticks = delay.as_nanos() * frequency / 1_000_000_000;
+        self.write_reg(
+            SCPD_BASE + stream_channel * 0x40,
+            ticks.min(u128::from(u32::MAX)) as u32,
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::{fs, path::Path};
+
+    pub(super) fn probe(name: &str) -> (Option<u32>, Option<u32>, Option<String>) {
+        let dir = Path::new("/sys/class/net").join(name);
+        let read_u32 = |file: &str| {
+            fs::read_to_string(dir.join(file))
+                .ok()?
+                .trim()
+                .parse::<i64>()
+                .ok()
+                // `speed` is `-1` if the link is down.
+                .filter(|&v| v > 0)
+                .map(|v| v as u32)
+        };
+        let driver = fs::read_link(dir.join("device/driver"))
+            .ok()
+            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()));
+        (read_u32("mtu"), read_u32("speed"), driver)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    pub(super) fn probe(_name: &str) -> (Option<u32>, Option<u32>, Option<String>) {
+        (None, None, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn nic(mtu: Option<u32>, speed_mbps: Option<u32>) -> NicCapabilities {
+        NicCapabilities {
+            interface: NetworkInterface {
+                name: "eth0".into(),
+                address: Ipv4Addr::new(192, 168, 0, 1),
+                netmask: Ipv4Addr::new(255, 255, 255, 0),
+            },
+            mtu,
+            speed_mbps,
+            driver: None,
+        }
+    }
+
+    #[test]
+    fn test_advise() {
+        let tuning = nic(Some(9000), Some(10000)).advise(1);
+        assert_eq!(tuning.packet_size, 9000);
+        assert_eq!(tuning.payload_size(), 9000 - 36);
+        assert_eq!(tuning.inter_packet_delay, Duration::default());
+        assert!(tuning.advice.is_empty());
+
+        // A 1500 bytes packet takes 12 us on 1 GigE, so each of 4 devices waits for the others.
+        let tuning = nic(Some(1500), Some(1000)).advise(4);
+        assert_eq!(tuning.packet_size, 1500);
+        assert_eq!(tuning.inter_packet_delay, Duration::from_micros(36));
+        assert_eq!(tuning.advice.len(), 1);
+
+        let tuning = nic(None, None).advise(2);
+        assert_eq!(tuning.packet_size, DEFAULT_PACKET_SIZE);
+        assert_eq!(tuning.inter_packet_delay, Duration::default());
+        assert_eq!(tuning.advice.len(), 2);
+    }
+}
@@ -0,0 +1,338 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a `AF_PACKET` socket which receives UDP datagrams of the stream channel
+//! below the UDP layer of the kernel.
+//!
+//! A classic BPF filter attached to the socket drops unrelated packets in the kernel, and UDP
+//! payloads are extracted in the user space. Opening the socket requires `CAP_NET_RAW`.
+
+use std::{
+    convert::TryFrom,
+    ffi::CString,
+    io, mem,
+    net::{Ipv4Addr, SocketAddrV4},
+    os::unix::io::RawFd,
+    time::Duration,
+};
+
+/// Length of UDP header.
+const UDP_HEADER_LEN: usize = 8;
+
+/// Minimum length of IPv4 header.
+const MIN_IP_HEADER_LEN: usize = 20;
+
+/// Instructions of classic BPF used in the filter.
+const BPF_LD_W_ABS: u16 = 0x20;
+const BPF_LD_H_ABS: u16 = 0x28;
+const BPF_LD_B_ABS: u16 = 0x30;
+const BPF_LD_H_IND: u16 = 0x48;
+const BPF_LDX_B_MSH: u16 = 0xb1;
+const BPF_JEQ_K: u16 = 0x15;
+const BPF_JSET_K: u16 = 0x45;
+const BPF_RET_K: u16 = 0x06;
+
+#[derive(Debug)]
+pub(super) struct PacketSocket {
+    fd: RawFd,
+    timeout: Option<Duration>,
+}
+
+impl PacketSocket {
+    /// Opens a socket which receives UDP datagrams sent to `dest`.
+    ///
+    /// `dest.ip()` must be the address of a host interface, and the socket is bound to the
+    /// interface. If the address is unspecified, datagrams received on any interface are accepted.
+    pub(super) fn open(
+        dest: SocketAddrV4,
+        recv_buffer_size: Option<usize>,
+        timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        let protocol = (libc::ETH_P_IP as u16).to_be();
+        let fd = unsafe { libc::socket(libc::AF_PACKET, libc::SOCK_DGRAM, i32::from(protocol)) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = Self { fd, timeout };
+
+        socket.attach_filter(dest)?;
+        if let Some(size) = recv_buffer_size {
+            let size = libc::c_int::try_from(size).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("receive buffer size is too large: {}", size),
+                )
+            })?;
+            socket.set_option(libc::SO_RCVBUF, size)?;
+        }
+        if !dest.ip().is_unspecified() {
+            socket.bind(interface_index(*dest.ip())?, protocol)?;
+        }
+        Ok(socket)
+    }
+
+    /// Receives a UDP payload into `buf`, and returns the length of the payload.
+    ///
+    /// `buf` must have room for IPv4 and UDP headers in addition to the payload, otherwise the
+    /// payload is truncated.
+    pub(super) fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.wait_readable()?;
+        self.recv_with_flags(buf, 0)
+    }
+
+    /// Receives UDP payloads same as [`Self::recv`] until no packet is queued.
+    pub(super) fn recv_batch(
+        &self,
+        bufs: &mut [&mut [u8]],
+        lens: &mut [usize],
+    ) -> io::Result<usize> {
+        lens[0] = self.recv(&mut *bufs[0])?;
+
+        let mut n = 1;
+        while n < bufs.len() {
+            match self.recv_with_flags(&mut *bufs[n], libc::MSG_DONTWAIT) {
+                Ok(len) => {
+                    lens[n] = len;
+                    n += 1;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(n)
+    }
+
+    /// Returns the actual size of the receive buffer in bytes.
+    pub(super) fn recv_buffer_size(&self) -> io::Result<usize> {
+        let mut size: libc::c_int = 0;
+        let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let res = unsafe {
+            libc::getsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                (&mut size as *mut libc::c_int).cast(),
+                &mut len,
+            )
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(size as usize)
+    }
+
+    fn recv_with_flags(&self, buf: &mut [u8], flags: libc::c_int) -> io::Result<usize> {
+        loop {
+            let res = unsafe { libc::recv(self.fd, buf.as_mut_ptr().cast(), buf.len(), flags) };
+            if res < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            // The filter only accepts UDP datagrams, but a datagram truncated by a short buffer
+            // may still lack its header.
+            if let Some(len) = strip_headers(buf, res as usize) {
+                return Ok(len);
+            }
+        }
+    }
+
+    fn wait_readable(&self) -> io::Result<()> {
+        let timeout = match self.timeout {
+            Some(timeout) => i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX),
+            None => -1,
+        };
+        let mut pollfd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut pollfd, 1, timeout) } {
+            0 => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "no packet arrived before timeout",
+            )),
+            res if res < 0 => Err(io::Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_option(&self, name: libc::c_int, value: libc::c_int) -> io::Result<()> {
+        let res = unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                name,
+                (&value as *const libc::c_int).cast(),
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn attach_filter(&self, dest: SocketAddrV4) -> io::Result<()> {
+        let mut filter = build_filter(dest);
+        let prog = libc::sock_fprog {
+            len: filter.len() as libc::c_ushort,
+            filter: filter.as_mut_ptr(),
+        };
+        let res = unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_FILTER,
+                (&prog as *const libc::sock_fprog).cast(),
+                mem::size_of::<libc::sock_fprog>() as libc::socklen_t,
+            )
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn bind(&self, ifindex: libc::c_int, protocol: u16) -> io::Result<()> {
+        let mut addr: libc::sockaddr_ll = unsafe { mem::zeroed() };
+        addr.sll_family = libc::AF_PACKET as libc::c_ushort;
+        addr.sll_protocol = protocol;
+        addr.sll_ifindex = ifindex;
+        let res = unsafe {
+            libc::bind(
+                self.fd,
+                (&addr as *const libc::sockaddr_ll).cast(),
+                mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PacketSocket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Builds a filter which accepts unfragmented UDP datagrams sent to `dest`.
+fn build_filter(dest: SocketAddrV4) -> Vec<libc::sock_filter> {
+    fn stmt(code: u16, k: u32) -> libc::sock_filter {
+        jump(code, k, 0, 0)
+    }
+
+    fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter { code, jt, jf, k }
+    }
+
+    // Offsets of jumps are relative to the next instruction, and the last instruction drops the
+    // packet.
+    let mut filter = vec![
+        // Protocol must be UDP.
+        stmt(BPF_LD_B_ABS, 9),
+        jump(BPF_JEQ_K, u32::from(libc::IPPROTO_UDP as u8), 0, 0),
+        // Fragment offset and MF flag must be zero.
+        stmt(BPF_LD_H_ABS, 6),
+        jump(BPF_JSET_K, 0x3fff, 0, 0),
+    ];
+    if !dest.ip().is_unspecified() {
+        filter.push(stmt(BPF_LD_W_ABS, 16));
+        filter.push(jump(BPF_JEQ_K, u32::from(*dest.ip()), 0, 0));
+    }
+    filter.extend_from_slice(&[
+        // Loads the length of IP header into X.
+        stmt(BPF_LDX_B_MSH, 0),
+        stmt(BPF_LD_H_IND, 2),
+        jump(BPF_JEQ_K, u32::from(dest.port()), 0, 0),
+        stmt(BPF_RET_K, u32::from(u16::MAX)),
+        stmt(BPF_RET_K, 0),
+    ]);
+
+    // Fills jump offsets to the drop instruction.
+    let drop = filter.len() - 1;
+    for (i, inst) in filter[..drop].iter_mut().enumerate() {
+        let offset = (drop - i - 1) as u8;
+        match inst.code {
+            BPF_JEQ_K => inst.jf = offset,
+            BPF_JSET_K => inst.jt = offset,
+            _ => {}
+        }
+    }
+    filter
+}
+
+/// Moves the UDP payload in `buf[..len]` to the head of `buf`, and returns the length of the
+/// payload.
+fn strip_headers(buf: &mut [u8], len: usize) -> Option<usize> {
+    let ip_header_len = usize::from(*buf.first()? & 0x0f) * 4;
+    let offset = ip_header_len + UDP_HEADER_LEN;
+    if ip_header_len < MIN_IP_HEADER_LEN || len < offset {
+        return None;
+    }
+    buf.copy_within(offset..len, 0);
+    Some(len - offset)
+}
+
+fn interface_index(address: Ipv4Addr) -> io::Result<libc::c_int> {
+    let iface = if_addrs::get_if_addrs()?
+        .into_iter()
+        .find(|iface| iface.ip() == address)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::AddrNotAvailable,
+                format!("no interface has address {}", address),
+            )
+        })?;
+    let name = CString::new(iface.name)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => Err(io::Error::last_os_error()),
+        index => Ok(index as libc::c_int),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_headers() {
+        let mut buf = vec![0; 64];
+        buf[0] = 0x45;
+        buf[28..32].copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(strip_headers(&mut buf, 32), Some(4));
+        assert_eq!(&buf[..4], &[1, 2, 3, 4]);
+
+        // Header with options.
+        let mut buf = vec![0; 64];
+        buf[0] = 0x46;
+        buf[32..34].copy_from_slice(&[5, 6]);
+        assert_eq!(strip_headers(&mut buf, 34), Some(2));
+        assert_eq!(&buf[..2], &[5, 6]);
+
+        // Truncated header.
+        assert_eq!(strip_headers(&mut buf, 20), None);
+    }
+
+    #[test]
+    fn test_build_filter() {
+        let filter = build_filter(SocketAddrV4::new(Ipv4Addr::new(192, 168, 0, 1), 50000));
+        let drop = filter.len() - 1;
+        assert_eq!(filter[drop].code, BPF_RET_K);
+        assert_eq!(filter[drop].k, 0);
+        for (i, inst) in filter.iter().enumerate() {
+            if inst.code == BPF_JEQ_K {
+                assert_eq!(i + 1 + inst.jf as usize, drop);
+            }
+        }
+        assert!(filter
+            .iter()
+            .any(|inst| inst.code == BPF_JEQ_K && inst.k == 50000));
+    }
+}
@@ -0,0 +1,232 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module provides serializer and parser for `GVCP`, the control protocol of
+//! `GigE Vision`.
+//!
+//! All fields of `GVCP` packets are big endian.
+
+use std::io::{Cursor, Write};
+
+use cameleon_impl::bytes_io::{ReadBytes, WriteBytes};
+
+use super::{Error, Result};
+
+/// Header of a `GVCP` command packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandHeader {
+    flags: u8,
+    command: u16,
+    length: u16,
+    request_id: u16,
+}
+
+impl CommandHeader {
+    /// Length of the header in bytes.
+    pub const LEN: usize = 8;
+
+    const KEY_CODE: u8 = 0x42;
+
+    /// The device must return an acknowledge.
+    const FLAG_ACK_REQUIRED: u8 = 0x01;
+
+    /// The device may broadcast the acknowledge of `DISCOVERY_CMD`, which reaches the host even
+    /// if the IP address of the device is on another subnet.
+    const FLAG_ALLOW_BROADCAST_ACK: u8 = 0x10;
+
+    const DISCOVERY_CMD: u16 = 0x0002;
+    const READREG_CMD: u16 = 0x0080;
+    const WRITEREG_CMD: u16 = 0x0082;
+
+    /// Constructs the header of `DISCOVERY_CMD`, which has no payload.
+    #[must_use]
+    pub fn discovery(request_id: u16, allow_broadcast_ack: bool) -> Self {
+        let mut flags = Self::FLAG_ACK_REQUIRED;
+        if allow_broadcast_ack {
+            flags |= Self::FLAG_ALLOW_BROADCAST_ACK;
+        }
+        Self {
+            flags,
+            command: Self::DISCOVERY_CMD,
+            length: 0,
+            request_id,
+        }
+    }
+
+    /// Constructs the header of `READREG_CMD` which reads `count` registers.
+    ///
+    /// The payload consists of the addresses of the registers.
+    #[must_use]
+    pub fn read_reg(request_id: u16, count: u16) -> Self {
+        Self {
+            flags: Self::FLAG_ACK_REQUIRED,
+            command: Self::READREG_CMD,
+            length: count * 4,
+            request_id,
+        }
+    }
+
+    /// Constructs the header of `WRITEREG_CMD` which writes `count` registers.
+    ///
+    /// The payload consists of pairs of the address and the value of the registers.
+    #[must_use]
+    pub fn write_reg(request_id: u16, count: u16) -> Self {
+        Self {
+            flags: Self::FLAG_ACK_REQUIRED,
+            command: Self::WRITEREG_CMD,
+            length: count * 8,
+            request_id,
+        }
+    }
+
+    /// Request id of the command, which is echoed in the acknowledge.
+    #[must_use]
+    pub fn request_id(&self) -> u16 {
+        self.request_id
+    }
+
+    pub fn serialize(&self, mut buf: impl Write) -> Result<()> {
+        buf.write_bytes_be(Self::KEY_CODE)?;
+        buf.write_bytes_be(self.flags)?;
+        buf.write_bytes_be(self.command)?;
+        buf.write_bytes_be(self.length)?;
+        buf.write_bytes_be(self.request_id)?;
+        Ok(())
+    }
+}
+
+/// A `GVCP` acknowledge packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AckPacket<'a> {
+    status: u16,
+    acknowledge: u16,
+    ack_id: u16,
+    payload: &'a [u8],
+}
+
+impl<'a> AckPacket<'a> {
+    /// Length of the header in bytes.
+    pub const HEADER_LEN: usize = 8;
+
+    const DISCOVERY_ACK: u16 = 0x0003;
+    const READREG_ACK: u16 = 0x0081;
+    const WRITEREG_ACK: u16 = 0x0083;
+
+    /// Parses bytes as an acknowledge packet.
+    pub fn parse(buf: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self> {
+        let buf = buf.as_ref();
+        if buf.len() < Self::HEADER_LEN {
+            return Err(Error::InvalidPacket(
+                format!("acknowledge of {} bytes is too short", buf.len()).into(),
+            ));
+        }
+        let mut cursor = Cursor::new(buf);
+        let status = cursor.read_bytes_be()?;
+        let acknowledge = cursor.read_bytes_be()?;
+        let length: u16 = cursor.read_bytes_be()?;
+        let ack_id = cursor.read_bytes_be()?;
+
+        let payload = buf
+            .get(Self::HEADER_LEN..Self::HEADER_LEN + usize::from(length))
+            .ok_or_else(|| {
+                Error::InvalidPacket(
+                    format!(
+                        "payload length is {}, but only {} bytes are received",
+                        length,
+                        buf.len() - Self::HEADER_LEN
+                    )
+                    .into(),
+                )
+            })?;
+
+        Ok(Self {
+            status,
+            acknowledge,
+            ack_id,
+            payload,
+        })
+    }
+
+    /// `GEV` status code. `0` means success.
+    #[must_use]
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Id of the acknowledge, which is the same as the request id of the command.
+    #[must_use]
+    pub fn ack_id(&self) -> u16 {
+        self.ack_id
+    }
+
+    /// Returns `true` if the packet is `DISCOVERY_ACK`.
+    #[must_use]
+    pub fn is_discovery_ack(&self) -> bool {
+        self.acknowledge == Self::DISCOVERY_ACK
+    }
+
+    /// Returns `true` if the packet is `READREG_ACK`.
+    #[must_use]
+    pub fn is_read_reg_ack(&self) -> bool {
+        self.acknowledge == Self::READREG_ACK
+    }
+
+    /// Returns `true` if the packet is `WRITEREG_ACK`.
+    #[must_use]
+    pub fn is_write_reg_ack(&self) -> bool {
+        self.acknowledge == Self::WRITEREG_ACK
+    }
+
+    /// Payload of the acknowledge.
+    #[must_use]
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_cmd() {
+        let mut buf = vec![];
+        CommandHeader::discovery(1, true)
+            .serialize(&mut buf)
+            .unwrap();
+        assert_eq!(buf, [0x42, 0x11, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01]);
+
+        let mut buf = vec![];
+        CommandHeader::discovery(2, false)
+            .serialize(&mut buf)
+            .unwrap();
+        assert_eq!(buf[1], 0x01);
+    }
+
+    #[test]
+    fn test_reg_cmd() {
+        let mut buf = vec![];
+        CommandHeader::read_reg(3, 2).serialize(&mut buf).unwrap();
+        assert_eq!(buf, [0x42, 0x01, 0x00, 0x80, 0x00, 0x08, 0x00, 0x03]);
+
+        let mut buf = vec![];
+        CommandHeader::write_reg(4, 1).serialize(&mut buf).unwrap();
+        assert_eq!(buf, [0x42, 0x01, 0x00, 0x82, 0x00, 0x08, 0x00, 0x04]);
+    }
+
+    #[test]
+    fn test_parse_ack() {
+        let buf = [0x00, 0x00, 0x00, 0x03, 0x00, 0x02, 0x00, 0x05, 0xab, 0xcd];
+        let ack = AckPacket::parse(&buf).unwrap();
+        assert_eq!(ack.status(), 0);
+        assert!(ack.is_discovery_ack());
+        assert_eq!(ack.ack_id(), 5);
+        assert_eq!(ack.payload(), &[0xab, 0xcd]);
+
+        // Truncated payload.
+        assert!(AckPacket::parse(&buf[..9]).is_err());
+        // Truncated header.
+        assert!(AckPacket::parse(&buf[..4]).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains a socket dedicated to the stream channel, which receives `GVSP` packets.
+
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket},
+    time::Duration,
+};
+
+#[cfg(target_os = "linux")]
+use super::packet_socket::PacketSocket;
+use super::Result;
+
+/// Path through which [`StreamSocket`] receives packets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReceivePath {
+    /// Receives packets through the UDP socket.
+    Udp,
+
+    /// Receives packets through a `AF_PACKET` socket, which bypasses the UDP layer of the kernel
+    /// and reduces per-packet overhead for 10 GigE devices.
+    ///
+    /// This path is only available on Linux and requires `CAP_NET_RAW`. If it's unavailable,
+    /// [`StreamSocketConfig::bind`] falls back to [`ReceivePath::Udp`].
+    PacketSocket,
+}
+
+/// Configuration of [`StreamSocket`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamSocketConfig {
+    /// Local address to bind the socket to. The port `0` lets the OS choose a free port, which
+    /// can be obtained by [`StreamSocket::local_addr`] to set to the stream channel of the device.
+    pub local_addr: SocketAddrV4,
+
+    /// Size of the receive buffer of the socket (`SO_RCVBUF`) in bytes. `None` keeps the default
+    /// of the OS.
+    ///
+    /// Default buffers are too small for streams faster than 1 Gbps, which leads to dropped
+    /// packets. The OS may cap the size, e.g. by `net.core.rmem_max` on Linux, see
+    /// [`StreamSocket::recv_buffer_size`] for the actual size.
+    pub recv_buffer_size: Option<usize>,
+
+    /// Maximum number of packets received by one [`StreamSocket::recv_batch`] call.
+    ///
+    /// Batches are received by a single `recvmmsg` system call on Linux, and by one `recv` per
+    /// packet on other platforms.
+    pub batch_size: usize,
+
+    /// Timeout of receive calls. `None` blocks until a packet arrives.
+    pub read_timeout: Option<Duration>,
+
+    /// Path through which packets are received.
+    pub receive_path: ReceivePath,
+}
+
+impl Default for StreamSocketConfig {
+    fn default() -> Self {
+        Self {
+            local_addr: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+            recv_buffer_size: Some(16 * 1024 * 1024),
+            batch_size: 64,
+            read_timeout: Some(Duration::from_millis(100)),
+            receive_path: ReceivePath::Udp,
+        }
+    }
+}
+
+impl StreamSocketConfig {
+    /// Creates a socket bound to [`Self::local_addr`].
+    pub fn bind(&self) -> Result<StreamSocket> {
+        if self.batch_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "batch size must be greater than 0",
+            )
+            .into());
+        }
+
+        let socket = UdpSocket::bind(self.local_addr)?;
+        socket.set_read_timeout(self.read_timeout)?;
+        if let Some(size) = self.recv_buffer_size {
+            imp::set_recv_buffer_size(&socket, size)?;
+            match imp::recv_buffer_size(&socket) {
+                Ok(actual) if actual < size => log::warn!(
+                    "receive buffer is capped to {} bytes, though {} bytes are requested",
+                    actual,
+                    size
+                ),
+                _ => {}
+            }
+        }
+
+        let packet_socket = match self.receive_path {
+            ReceivePath::Udp => None,
+            ReceivePath::PacketSocket => self.open_packet_socket(&socket),
+        };
+
+        Ok(StreamSocket {
+            socket,
+            packet_socket,
+            batch_size: self.batch_size,
+        })
+    }
+
+    /// Opens a packet socket which receives datagrams sent to `socket`. Returns `None` if the
+    /// packet socket is unavailable.
+    #[cfg(target_os = "linux")]
+    fn open_packet_socket(&self, socket: &UdpSocket) -> Option<PacketSocket> {
+        let local_addr = match socket.local_addr() {
+            Ok(SocketAddr::V4(addr)) => addr,
+            _ => return None,
+        };
+        match PacketSocket::open(local_addr, self.recv_buffer_size, self.read_timeout) {
+            Ok(packet_socket) => {
+                // The UDP socket is kept to reserve the port, but its buffer is minimized
+                // because packets are also delivered to it and never read.
+                imp::set_recv_buffer_size(socket, 0).ok();
+                Some(packet_socket)
+            }
+            Err(e) => {
+                log::warn!(
+                    "packet socket is unavailable, falls back to UDP socket: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn open_packet_socket(&self, _socket: &UdpSocket) -> Option<PacketSocket> {
+        log::warn!("packet socket is only available on Linux, falls back to UDP socket");
+        None
+    }
+}
+
+/// A UDP socket dedicated to the stream channel.
+#[derive(Debug)]
+pub struct StreamSocket {
+    socket: UdpSocket,
+    packet_socket: Option<PacketSocket>,
+    batch_size: usize,
+}
+
+impl StreamSocket {
+    /// Returns the local address the socket is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddrV4> {
+        match self.socket.local_addr()? {
+            SocketAddr::V4(addr) => Ok(addr),
+            SocketAddr::V6(addr) => {
+                Err(io::Error::other(format!("socket is bound to IPv6 address {}", addr)).into())
+            }
+        }
+    }
+
+    /// Returns the actual size of the receive buffer in bytes.
+    ///
+    /// NOTE: Linux reports the doubled value of the requested size because it reserves space for
+    /// bookkeeping.
+    pub fn recv_buffer_size(&self) -> Result<usize> {
+        match &self.packet_socket {
+            Some(packet_socket) => Ok(packet_socket.recv_buffer_size()?),
+            None => Ok(imp::recv_buffer_size(&self.socket)?),
+        }
+    }
+
+    /// Returns the path through which packets are actually received.
+    #[must_use]
+    pub fn receive_path(&self) -> ReceivePath {
+        if self.packet_socket.is_some() {
+            ReceivePath::PacketSocket
+        } else {
+            ReceivePath::Udp
+        }
+    }
+
+    /// Maximum number of packets received by one [`Self::recv_batch`] call.
+    #[must_use]
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Receives a packet into `buf`, and returns the length of the packet.
+    ///
+    /// NOTE: With [`ReceivePath::PacketSocket`], `buf` must have 28 extra bytes or more for IP and
+    /// UDP headers, otherwise the packet is truncated.
+    pub fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        match &self.packet_socket {
+            Some(packet_socket) => Ok(packet_socket.recv(buf)?),
+            None => Ok(self.socket.recv(buf)?),
+        }
+    }
+
+    /// Receives up to `min(bufs.len(), batch_size)` packets, and returns the number of received
+    /// packets. The length of the `i`-th packet is written to `lens[i]`.
+    ///
+    /// Blocks until at least one packet arrives or the read timeout expires, then returns the
+    /// packets already queued without waiting for more.
+    ///
+    /// # Panics
+    /// Panics if `lens` is shorter than `bufs`.
+    pub fn recv_batch(&self, bufs: &mut [&mut [u8]], lens: &mut [usize]) -> Result<usize> {
+        assert!(lens.len() >= bufs.len());
+        let n = bufs.len().min(self.batch_size);
+        if n == 0 {
+            return Ok(0);
+        }
+        match &self.packet_socket {
+            Some(packet_socket) => Ok(packet_socket.recv_batch(&mut bufs[..n], &mut lens[..n])?),
+            None => Ok(imp::recv_batch(
+                &self.socket,
+                &mut bufs[..n],
+                &mut lens[..n],
+            )?),
+        }
+    }
+
+    /// Returns the underlying UDP socket.
+    #[must_use]
+    pub fn as_udp_socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl From<StreamSocket> for UdpSocket {
+    fn from(socket: StreamSocket) -> Self {
+        socket.socket
+    }
+}
+
+/// Stub of the packet socket, which can't be constructed on platforms other than Linux.
+#[cfg(not(target_os = "linux"))]
+#[derive(Debug)]
+enum PacketSocket {}
+
+#[cfg(not(target_os = "linux"))]
+impl PacketSocket {
+    fn recv_buffer_size(&self) -> io::Result<usize> {
+        match *self {}
+    }
+
+    fn recv(&self, _buf: &mut [u8]) -> io::Result<usize> {
+        match *self {}
+    }
+
+    fn recv_batch(&self, _bufs: &mut [&mut [u8]], _lens: &mut [usize]) -> io::Result<usize> {
+        match *self {}
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{convert::TryFrom, mem, net::UdpSocket, os::unix::io::AsRawFd};
+
+    use super::io;
+
+    pub(super) fn set_recv_buffer_size(socket: &UdpSocket, size: usize) -> io::Result<()> {
+        let size = libc::c_int::try_from(size).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("receive buffer size is too large: {}", size),
+            )
+        })?;
+        let res = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                (&size as *const libc::c_int).cast(),
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub(super) fn recv_buffer_size(socket: &UdpSocket) -> io::Result<usize> {
+        let mut size: libc::c_int = 0;
+        let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let res = unsafe {
+            libc::getsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                (&mut size as *mut libc::c_int).cast(),
+                &mut len,
+            )
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(size as usize)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(super) fn recv_batch(
+        socket: &UdpSocket,
+        bufs: &mut [&mut [u8]],
+        lens: &mut [usize],
+    ) -> io::Result<usize> {
+        let mut iovecs: Vec<libc::iovec> = bufs
+            .iter_mut()
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_mut_ptr().cast(),
+                iov_len: buf.len(),
+            })
+            .collect();
+        let mut msgs: Vec<libc::mmsghdr> = iovecs
+            .iter_mut()
+            .map(|iovec| {
+                let mut msg: libc::mmsghdr = unsafe { mem::zeroed() };
+                msg.msg_hdr.msg_iov = iovec;
+                msg.msg_hdr.msg_iovlen = 1;
+                msg
+            })
+            .collect();
+
+        // `MSG_WAITFORONE` turns on `MSG_DONTWAIT` after the first packet, so that the call
+        // respects the read timeout of the socket and returns without filling all buffers.
+        let res = unsafe {
+            libc::recvmmsg(
+                socket.as_raw_fd(),
+                msgs.as_mut_ptr(),
+                msgs.len() as libc::c_uint,
+                libc::MSG_WAITFORONE as _,
+                std::ptr::null_mut(),
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let n = res as usize;
+        for (len, msg) in lens.iter_mut().zip(&msgs[..n]) {
+            *len = msg.msg_len as usize;
+        }
+        Ok(n)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn recv_batch(
+        socket: &UdpSocket,
+        bufs: &mut [&mut [u8]],
+        lens: &mut [usize],
+    ) -> io::Result<usize> {
+        super::recv_batch_fallback(socket, bufs, lens)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::net::UdpSocket;
+
+    use super::io;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "receive buffer configuration isn't supported on this platform",
+        )
+    }
+
+    pub(super) fn set_recv_buffer_size(_socket: &UdpSocket, _size: usize) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(super) fn recv_buffer_size(_socket: &UdpSocket) -> io::Result<usize> {
+        Err(unsupported())
+    }
+
+    pub(super) fn recv_batch(
+        socket: &UdpSocket,
+        bufs: &mut [&mut [u8]],
+        lens: &mut [usize],
+    ) -> io::Result<usize> {
+        super::recv_batch_fallback(socket, bufs, lens)
+    }
+}
+
+/// Receives packets one by one until no packet is queued.
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn recv_batch_fallback(
+    socket: &UdpSocket,
+    bufs: &mut [&mut [u8]],
+    lens: &mut [usize],
+) -> io::Result<usize> {
+    lens[0] = socket.recv(&mut *bufs[0])?;
+
+    socket.set_nonblocking(true)?;
+    let mut n = 1;
+    let res = loop {
+        if n == bufs.len() {
+            break Ok(());
+        }
+        match socket.recv(&mut *bufs[n]) {
+            Ok(len) => {
+                lens[n] = len;
+                n += 1;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+            Err(e) => break Err(e),
+        }
+    };
+    socket.set_nonblocking(false)?;
+
+    res.map(|_| n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recv_batch() {
+        let config = StreamSocketConfig {
+            local_addr: SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0),
+            recv_buffer_size: Some(1024 * 1024),
+            batch_size: 4,
+            read_timeout: Some(Duration::from_secs(1)),
+            receive_path: ReceivePath::Udp,
+        };
+        let socket = config.bind().unwrap();
+        assert_eq!(socket.receive_path(), ReceivePath::Udp);
+        if cfg!(unix) {
+            assert!(socket.recv_buffer_size().unwrap() > 0);
+        }
+
+        let sender = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = socket.local_addr().unwrap();
+        for i in 0..6_u8 {
+            sender.send_to(&[i; 8], addr).unwrap();
+        }
+
+        let mut storage = [[0_u8; 16]; 6];
+        let mut bufs: Vec<&mut [u8]> = storage.iter_mut().map(|buf| &mut buf[..]).collect();
+        let mut lens = [0; 6];
+        let mut received = 0;
+        while received < 6 {
+            let n = socket
+                .recv_batch(&mut bufs[received..], &mut lens[received..])
+                .unwrap();
+            assert!(n <= 4);
+            received += n;
+        }
+        for (i, (buf, len)) in bufs.iter().zip(&lens).enumerate() {
+            assert_eq!(&buf[..*len], &[i as u8; 8]);
+        }
+    }
+}
@@ -10,9 +10,11 @@
     clippy::cast_possible_truncation
 )]
 
-#[cfg(feature = "libusb")]
+#[cfg(feature = "gev")]
+pub mod gev;
+#[cfg(any(feature = "libusb", feature = "webusb"))]
 pub mod u3v;
 
 mod pixel_format;
 
-pub use pixel_format::PixelFormat;
+pub use pixel_format::{BayerPattern, ComponentLayout, PixelFormat};
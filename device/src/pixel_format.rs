@@ -1361,3 +1361,1013 @@ impl From<PixelFormat> for u32 {
         }
     }
 }
+
+impl std::str::FromStr for PixelFormat {
+    type Err = String;
+
+    #[allow(clippy::too_many_lines)]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Mono8" => Ok(Mono8),
+            "Mono8s" => Ok(Mono8s),
+            "Mono10" => Ok(Mono10),
+            "Mono10Packed" => Ok(Mono10Packed),
+            "Mono12" => Ok(Mono12),
+            "Mono12Packed" => Ok(Mono12Packed),
+            "Mono16" => Ok(Mono16),
+            "BayerGR8" => Ok(BayerGR8),
+            "BayerRG8" => Ok(BayerRG8),
+            "BayerGB8" => Ok(BayerGB8),
+            "BayerBG8" => Ok(BayerBG8),
+            "BayerGR10" => Ok(BayerGR10),
+            "BayerRG10" => Ok(BayerRG10),
+            "BayerGB10" => Ok(BayerGB10),
+            "BayerBG10" => Ok(BayerBG10),
+            "BayerGR12" => Ok(BayerGR12),
+            "BayerRG12" => Ok(BayerRG12),
+            "BayerGB12" => Ok(BayerGB12),
+            "BayerBG12" => Ok(BayerBG12),
+            "RGB8" => Ok(RGB8),
+            "BGR8" => Ok(BGR8),
+            "RGBa8" => Ok(RGBa8),
+            "BGRa8" => Ok(BGRa8),
+            "RGB10" => Ok(RGB10),
+            "BGR10" => Ok(BGR10),
+            "RGB12" => Ok(RGB12),
+            "BGR12" => Ok(BGR12),
+            "YUV8_UYV" => Ok(YUV8_UYV),
+            "RGB8_Planar" => Ok(RGB8_Planar),
+            "RGB10_Planar" => Ok(RGB10_Planar),
+            "RGB12_Planar" => Ok(RGB12_Planar),
+            "RGB16_Planar" => Ok(RGB16_Planar),
+            "Mono14" => Ok(Mono14),
+            "BayerGR10Packed" => Ok(BayerGR10Packed),
+            "BayerRG10Packed" => Ok(BayerRG10Packed),
+            "BayerGB10Packed" => Ok(BayerGB10Packed),
+            "BayerBG10Packed" => Ok(BayerBG10Packed),
+            "BayerGR12Packed" => Ok(BayerGR12Packed),
+            "BayerRG12Packed" => Ok(BayerRG12Packed),
+            "BayerGB12Packed" => Ok(BayerGB12Packed),
+            "BayerBG12Packed" => Ok(BayerBG12Packed),
+            "BayerGR16" => Ok(BayerGR16),
+            "BayerRG16" => Ok(BayerRG16),
+            "BayerGB16" => Ok(BayerGB16),
+            "BayerBG16" => Ok(BayerBG16),
+            "YUV422_8" => Ok(YUV422_8),
+            "RGB16" => Ok(RGB16),
+            "RGB12V1Packed" => Ok(RGB12V1Packed),
+            "RGB565p" => Ok(RGB565p),
+            "BGR565p" => Ok(BGR565p),
+            "Mono1p" => Ok(Mono1p),
+            "Mono2p" => Ok(Mono2p),
+            "Mono4p" => Ok(Mono4p),
+            "YCbCr8_CbYCr" => Ok(YCbCr8_CbYCr),
+            "YCbCr422_8" => Ok(YCbCr422_8),
+            "YCbCr411_8_CbYYCrYY" => Ok(YCbCr411_8_CbYYCrYY),
+            "YCbCr601_8_CbYCr" => Ok(YCbCr601_8_CbYCr),
+            "YCbCr601_422_8" => Ok(YCbCr601_422_8),
+            "YCbCr601_411_8_CbYYCrYY" => Ok(YCbCr601_411_8_CbYYCrYY),
+            "YCbCr709_8_CbYCr" => Ok(YCbCr709_8_CbYCr),
+            "YCbCr709_422_8" => Ok(YCbCr709_422_8),
+            "YCbCr709_411_8_CbYYCrYY" => Ok(YCbCr709_411_8_CbYYCrYY),
+            "YCbCr422_8_CbYCrY" => Ok(YCbCr422_8_CbYCrY),
+            "YCbCr601_422_8_CbYCrY" => Ok(YCbCr601_422_8_CbYCrY),
+            "YCbCr709_422_8_CbYCrY" => Ok(YCbCr709_422_8_CbYCrY),
+            "Mono10p" => Ok(Mono10p),
+            "Mono12p" => Ok(Mono12p),
+            "BGR10p" => Ok(BGR10p),
+            "BGR12p" => Ok(BGR12p),
+            "BGR14" => Ok(BGR14),
+            "BGR16" => Ok(BGR16),
+            "BGRa10" => Ok(BGRa10),
+            "BGRa10p" => Ok(BGRa10p),
+            "BGRa12" => Ok(BGRa12),
+            "BGRa12p" => Ok(BGRa12p),
+            "BGRa14" => Ok(BGRa14),
+            "BGRa16" => Ok(BGRa16),
+            "BayerBG10p" => Ok(BayerBG10p),
+            "BayerBG12p" => Ok(BayerBG12p),
+            "BayerGB10p" => Ok(BayerGB10p),
+            "BayerGB12p" => Ok(BayerGB12p),
+            "BayerGR10p" => Ok(BayerGR10p),
+            "BayerGR12p" => Ok(BayerGR12p),
+            "BayerRG10p" => Ok(BayerRG10p),
+            "BayerRG12p" => Ok(BayerRG12p),
+            "YCbCr411_8" => Ok(YCbCr411_8),
+            "YCbCr8" => Ok(YCbCr8),
+            "RGB10p" => Ok(RGB10p),
+            "RGB12p" => Ok(RGB12p),
+            "RGB14" => Ok(RGB14),
+            "RGBa10" => Ok(RGBa10),
+            "RGBa10p" => Ok(RGBa10p),
+            "RGBa12" => Ok(RGBa12),
+            "RGBa12p" => Ok(RGBa12p),
+            "RGBa14" => Ok(RGBa14),
+            "RGBa16" => Ok(RGBa16),
+            "YCbCr422_10" => Ok(YCbCr422_10),
+            "YCbCr422_12" => Ok(YCbCr422_12),
+            "SCF1WBWG8" => Ok(SCF1WBWG8),
+            "SCF1WBWG10" => Ok(SCF1WBWG10),
+            "SCF1WBWG10p" => Ok(SCF1WBWG10p),
+            "SCF1WBWG12" => Ok(SCF1WBWG12),
+            "SCF1WBWG12p" => Ok(SCF1WBWG12p),
+            "SCF1WBWG14" => Ok(SCF1WBWG14),
+            "SCF1WBWG16" => Ok(SCF1WBWG16),
+            "SCF1WGWB8" => Ok(SCF1WGWB8),
+            "SCF1WGWB10" => Ok(SCF1WGWB10),
+            "SCF1WGWB10p" => Ok(SCF1WGWB10p),
+            "SCF1WGWB12" => Ok(SCF1WGWB12),
+            "SCF1WGWB12p" => Ok(SCF1WGWB12p),
+            "SCF1WGWB14" => Ok(SCF1WGWB14),
+            "SCF1WGWB16" => Ok(SCF1WGWB16),
+            "SCF1WGWR8" => Ok(SCF1WGWR8),
+            "SCF1WGWR10" => Ok(SCF1WGWR10),
+            "SCF1WGWR10p" => Ok(SCF1WGWR10p),
+            "SCF1WGWR12" => Ok(SCF1WGWR12),
+            "SCF1WGWR12p" => Ok(SCF1WGWR12p),
+            "SCF1WGWR14" => Ok(SCF1WGWR14),
+            "SCF1WGWR16" => Ok(SCF1WGWR16),
+            "SCF1WRWG8" => Ok(SCF1WRWG8),
+            "SCF1WRWG10" => Ok(SCF1WRWG10),
+            "SCF1WRWG10p" => Ok(SCF1WRWG10p),
+            "SCF1WRWG12" => Ok(SCF1WRWG12),
+            "SCF1WRWG12p" => Ok(SCF1WRWG12p),
+            "SCF1WRWG14" => Ok(SCF1WRWG14),
+            "SCF1WRWG16" => Ok(SCF1WRWG16),
+            "YCbCr10_CbYCr" => Ok(YCbCr10_CbYCr),
+            "YCbCr10p_CbYCr" => Ok(YCbCr10p_CbYCr),
+            "YCbCr12_CbYCr" => Ok(YCbCr12_CbYCr),
+            "YCbCr12p_CbYCr" => Ok(YCbCr12p_CbYCr),
+            "YCbCr422_10p" => Ok(YCbCr422_10p),
+            "YCbCr422_12p" => Ok(YCbCr422_12p),
+            "YCbCr601_10_CbYCr" => Ok(YCbCr601_10_CbYCr),
+            "YCbCr601_10p_CbYCr" => Ok(YCbCr601_10p_CbYCr),
+            "YCbCr601_12_CbYCr" => Ok(YCbCr601_12_CbYCr),
+            "YCbCr601_12p_CbYCr" => Ok(YCbCr601_12p_CbYCr),
+            "YCbCr601_422_10" => Ok(YCbCr601_422_10),
+            "YCbCr601_422_10p" => Ok(YCbCr601_422_10p),
+            "YCbCr601_422_12" => Ok(YCbCr601_422_12),
+            "YCbCr601_422_12p" => Ok(YCbCr601_422_12p),
+            "YCbCr709_10_CbYCr" => Ok(YCbCr709_10_CbYCr),
+            "YCbCr709_10p_CbYCr" => Ok(YCbCr709_10p_CbYCr),
+            "YCbCr709_12_CbYCr" => Ok(YCbCr709_12_CbYCr),
+            "YCbCr709_12p_CbYCr" => Ok(YCbCr709_12p_CbYCr),
+            "YCbCr709_422_10" => Ok(YCbCr709_422_10),
+            "YCbCr709_422_10p" => Ok(YCbCr709_422_10p),
+            "YCbCr709_422_12" => Ok(YCbCr709_422_12),
+            "YCbCr709_422_12p" => Ok(YCbCr709_422_12p),
+            "YCbCr422_10_CbYCrY" => Ok(YCbCr422_10_CbYCrY),
+            "YCbCr422_10p_CbYCrY" => Ok(YCbCr422_10p_CbYCrY),
+            "YCbCr422_12_CbYCrY" => Ok(YCbCr422_12_CbYCrY),
+            "YCbCr422_12p_CbYCrY" => Ok(YCbCr422_12p_CbYCrY),
+            "YCbCr601_422_10_CbYCrY" => Ok(YCbCr601_422_10_CbYCrY),
+            "YCbCr601_422_10p_CbYCrY" => Ok(YCbCr601_422_10p_CbYCrY),
+            "YCbCr601_422_12_CbYCrY" => Ok(YCbCr601_422_12_CbYCrY),
+            "YCbCr601_422_12p_CbYCrY" => Ok(YCbCr601_422_12p_CbYCrY),
+            "YCbCr709_422_10_CbYCrY" => Ok(YCbCr709_422_10_CbYCrY),
+            "YCbCr709_422_10p_CbYCrY" => Ok(YCbCr709_422_10p_CbYCrY),
+            "YCbCr709_422_12_CbYCrY" => Ok(YCbCr709_422_12_CbYCrY),
+            "YCbCr709_422_12p_CbYCrY" => Ok(YCbCr709_422_12p_CbYCrY),
+            "BiColorRGBG8" => Ok(BiColorRGBG8),
+            "BiColorBGRG8" => Ok(BiColorBGRG8),
+            "BiColorRGBG10" => Ok(BiColorRGBG10),
+            "BiColorRGBG10p" => Ok(BiColorRGBG10p),
+            "BiColorBGRG10" => Ok(BiColorBGRG10),
+            "BiColorBGRG10p" => Ok(BiColorBGRG10p),
+            "BiColorRGBG12" => Ok(BiColorRGBG12),
+            "BiColorRGBG12p" => Ok(BiColorRGBG12p),
+            "BiColorBGRG12" => Ok(BiColorBGRG12),
+            "BiColorBGRG12p" => Ok(BiColorBGRG12p),
+            "Coord3D_A8" => Ok(Coord3D_A8),
+            "Coord3D_B8" => Ok(Coord3D_B8),
+            "Coord3D_C8" => Ok(Coord3D_C8),
+            "Coord3D_ABC8" => Ok(Coord3D_ABC8),
+            "Coord3D_ABC8_Planar" => Ok(Coord3D_ABC8_Planar),
+            "Coord3D_AC8" => Ok(Coord3D_AC8),
+            "Coord3D_AC8_Planar" => Ok(Coord3D_AC8_Planar),
+            "Coord3D_A16" => Ok(Coord3D_A16),
+            "Coord3D_B16" => Ok(Coord3D_B16),
+            "Coord3D_C16" => Ok(Coord3D_C16),
+            "Coord3D_ABC16" => Ok(Coord3D_ABC16),
+            "Coord3D_ABC16_Planar" => Ok(Coord3D_ABC16_Planar),
+            "Coord3D_AC16" => Ok(Coord3D_AC16),
+            "Coord3D_AC16_Planar" => Ok(Coord3D_AC16_Planar),
+            "Coord3D_A32f" => Ok(Coord3D_A32f),
+            "Coord3D_B32f" => Ok(Coord3D_B32f),
+            "Coord3D_C32f" => Ok(Coord3D_C32f),
+            "Coord3D_ABC32f" => Ok(Coord3D_ABC32f),
+            "Coord3D_ABC32f_Planar" => Ok(Coord3D_ABC32f_Planar),
+            "Coord3D_AC32f" => Ok(Coord3D_AC32f),
+            "Coord3D_AC32f_Planar" => Ok(Coord3D_AC32f_Planar),
+            "Confidence1" => Ok(Confidence1),
+            "Confidence1p" => Ok(Confidence1p),
+            "Confidence8" => Ok(Confidence8),
+            "Confidence16" => Ok(Confidence16),
+            "Confidence32f" => Ok(Confidence32f),
+            "R8" => Ok(R8),
+            "R10" => Ok(R10),
+            "R12" => Ok(R12),
+            "R16" => Ok(R16),
+            "G8" => Ok(G8),
+            "G10" => Ok(G10),
+            "G12" => Ok(G12),
+            "G16" => Ok(G16),
+            "B8" => Ok(B8),
+            "B10" => Ok(B10),
+            "B12" => Ok(B12),
+            "B16" => Ok(B16),
+            "Coord3D_A10p" => Ok(Coord3D_A10p),
+            "Coord3D_B10p" => Ok(Coord3D_B10p),
+            "Coord3D_C10p" => Ok(Coord3D_C10p),
+            "Coord3D_A12p" => Ok(Coord3D_A12p),
+            "Coord3D_B12p" => Ok(Coord3D_B12p),
+            "Coord3D_C12p" => Ok(Coord3D_C12p),
+            "Coord3D_ABC10p" => Ok(Coord3D_ABC10p),
+            "Coord3D_ABC10p_Planar" => Ok(Coord3D_ABC10p_Planar),
+            "Coord3D_ABC12p" => Ok(Coord3D_ABC12p),
+            "Coord3D_ABC12p_Planar" => Ok(Coord3D_ABC12p_Planar),
+            "Coord3D_AC10p" => Ok(Coord3D_AC10p),
+            "Coord3D_AC10p_Planar" => Ok(Coord3D_AC10p_Planar),
+            "Coord3D_AC12p" => Ok(Coord3D_AC12p),
+            "Coord3D_AC12p_Planar" => Ok(Coord3D_AC12p_Planar),
+            "YCbCr2020_8_CbYCr" => Ok(YCbCr2020_8_CbYCr),
+            "YCbCr2020_10_CbYCr" => Ok(YCbCr2020_10_CbYCr),
+            "YCbCr2020_10p_CbYCr" => Ok(YCbCr2020_10p_CbYCr),
+            "YCbCr2020_12_CbYCr" => Ok(YCbCr2020_12_CbYCr),
+            "YCbCr2020_12p_CbYCr" => Ok(YCbCr2020_12p_CbYCr),
+            "YCbCr2020_411_8_CbYYCrYY" => Ok(YCbCr2020_411_8_CbYYCrYY),
+            "YCbCr2020_422_8" => Ok(YCbCr2020_422_8),
+            "YCbCr2020_422_8_CbYCrY" => Ok(YCbCr2020_422_8_CbYCrY),
+            "YCbCr2020_422_10" => Ok(YCbCr2020_422_10),
+            "YCbCr2020_422_10_CbYCrY" => Ok(YCbCr2020_422_10_CbYCrY),
+            "YCbCr2020_422_10p" => Ok(YCbCr2020_422_10p),
+            "YCbCr2020_422_10p_CbYCrY" => Ok(YCbCr2020_422_10p_CbYCrY),
+            "YCbCr2020_422_12" => Ok(YCbCr2020_422_12),
+            "YCbCr2020_422_12_CbYCrY" => Ok(YCbCr2020_422_12_CbYCrY),
+            "YCbCr2020_422_12p" => Ok(YCbCr2020_422_12p),
+            "YCbCr2020_422_12p_CbYCrY" => Ok(YCbCr2020_422_12p_CbYCrY),
+            "Mono14p" => Ok(Mono14p),
+            "BayerGR14p" => Ok(BayerGR14p),
+            "BayerRG14p" => Ok(BayerRG14p),
+            "BayerGB14p" => Ok(BayerGB14p),
+            "BayerBG14p" => Ok(BayerBG14p),
+            "BayerGR14" => Ok(BayerGR14),
+            "BayerRG14" => Ok(BayerRG14),
+            "BayerGB14" => Ok(BayerGB14),
+            "BayerBG14" => Ok(BayerBG14),
+            "BayerGR4p" => Ok(BayerGR4p),
+            "BayerRG4p" => Ok(BayerRG4p),
+            "BayerGB4p" => Ok(BayerGB4p),
+            "BayerBG4p" => Ok(BayerBG4p),
+            "Mono32" => Ok(Mono32),
+            "YCbCr420_8_YY_CbCr_Semiplanar" => Ok(YCbCr420_8_YY_CbCr_Semiplanar),
+            "YCbCr422_8_YY_CbCr_Semiplanar" => Ok(YCbCr422_8_YY_CbCr_Semiplanar),
+            "YCbCr420_8_YY_CrCb_Semiplanar" => Ok(YCbCr420_8_YY_CrCb_Semiplanar),
+            "YCbCr422_8_YY_CrCb_Semiplanar" => Ok(YCbCr422_8_YY_CrCb_Semiplanar),
+            "Data8" => Ok(Data8),
+            "Data8s" => Ok(Data8s),
+            "Data16" => Ok(Data16),
+            "Data16s" => Ok(Data16s),
+            "Data32" => Ok(Data32),
+            "Data32s" => Ok(Data32s),
+            "Data32f" => Ok(Data32f),
+            "Data64" => Ok(Data64),
+            "Data64s" => Ok(Data64s),
+            "Data64f" => Ok(Data64f),
+            _ => Err(format!("{} is not a valid PixelFormat name", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for PixelFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl PixelFormat {
+    /// All pixel formats defined in `GenICam PFNC`, in the order of their codes.
+    pub const ALL: &'static [PixelFormat] = &[
+        Mono8,
+        Mono8s,
+        Mono10,
+        Mono10Packed,
+        Mono12,
+        Mono12Packed,
+        Mono16,
+        BayerGR8,
+        BayerRG8,
+        BayerGB8,
+        BayerBG8,
+        BayerGR10,
+        BayerRG10,
+        BayerGB10,
+        BayerBG10,
+        BayerGR12,
+        BayerRG12,
+        BayerGB12,
+        BayerBG12,
+        RGB8,
+        BGR8,
+        RGBa8,
+        BGRa8,
+        RGB10,
+        BGR10,
+        RGB12,
+        BGR12,
+        YUV8_UYV,
+        RGB8_Planar,
+        RGB10_Planar,
+        RGB12_Planar,
+        RGB16_Planar,
+        Mono14,
+        BayerGR10Packed,
+        BayerRG10Packed,
+        BayerGB10Packed,
+        BayerBG10Packed,
+        BayerGR12Packed,
+        BayerRG12Packed,
+        BayerGB12Packed,
+        BayerBG12Packed,
+        BayerGR16,
+        BayerRG16,
+        BayerGB16,
+        BayerBG16,
+        YUV422_8,
+        RGB16,
+        RGB12V1Packed,
+        RGB565p,
+        BGR565p,
+        Mono1p,
+        Mono2p,
+        Mono4p,
+        YCbCr8_CbYCr,
+        YCbCr422_8,
+        YCbCr411_8_CbYYCrYY,
+        YCbCr601_8_CbYCr,
+        YCbCr601_422_8,
+        YCbCr601_411_8_CbYYCrYY,
+        YCbCr709_8_CbYCr,
+        YCbCr709_422_8,
+        YCbCr709_411_8_CbYYCrYY,
+        YCbCr422_8_CbYCrY,
+        YCbCr601_422_8_CbYCrY,
+        YCbCr709_422_8_CbYCrY,
+        Mono10p,
+        Mono12p,
+        BGR10p,
+        BGR12p,
+        BGR14,
+        BGR16,
+        BGRa10,
+        BGRa10p,
+        BGRa12,
+        BGRa12p,
+        BGRa14,
+        BGRa16,
+        BayerBG10p,
+        BayerBG12p,
+        BayerGB10p,
+        BayerGB12p,
+        BayerGR10p,
+        BayerGR12p,
+        BayerRG10p,
+        BayerRG12p,
+        YCbCr411_8,
+        YCbCr8,
+        RGB10p,
+        RGB12p,
+        RGB14,
+        RGBa10,
+        RGBa10p,
+        RGBa12,
+        RGBa12p,
+        RGBa14,
+        RGBa16,
+        YCbCr422_10,
+        YCbCr422_12,
+        SCF1WBWG8,
+        SCF1WBWG10,
+        SCF1WBWG10p,
+        SCF1WBWG12,
+        SCF1WBWG12p,
+        SCF1WBWG14,
+        SCF1WBWG16,
+        SCF1WGWB8,
+        SCF1WGWB10,
+        SCF1WGWB10p,
+        SCF1WGWB12,
+        SCF1WGWB12p,
+        SCF1WGWB14,
+        SCF1WGWB16,
+        SCF1WGWR8,
+        SCF1WGWR10,
+        SCF1WGWR10p,
+        SCF1WGWR12,
+        SCF1WGWR12p,
+        SCF1WGWR14,
+        SCF1WGWR16,
+        SCF1WRWG8,
+        SCF1WRWG10,
+        SCF1WRWG10p,
+        SCF1WRWG12,
+        SCF1WRWG12p,
+        SCF1WRWG14,
+        SCF1WRWG16,
+        YCbCr10_CbYCr,
+        YCbCr10p_CbYCr,
+        YCbCr12_CbYCr,
+        YCbCr12p_CbYCr,
+        YCbCr422_10p,
+        YCbCr422_12p,
+        YCbCr601_10_CbYCr,
+        YCbCr601_10p_CbYCr,
+        YCbCr601_12_CbYCr,
+        YCbCr601_12p_CbYCr,
+        YCbCr601_422_10,
+        YCbCr601_422_10p,
+        YCbCr601_422_12,
+        YCbCr601_422_12p,
+        YCbCr709_10_CbYCr,
+        YCbCr709_10p_CbYCr,
+        YCbCr709_12_CbYCr,
+        YCbCr709_12p_CbYCr,
+        YCbCr709_422_10,
+        YCbCr709_422_10p,
+        YCbCr709_422_12,
+        YCbCr709_422_12p,
+        YCbCr422_10_CbYCrY,
+        YCbCr422_10p_CbYCrY,
+        YCbCr422_12_CbYCrY,
+        YCbCr422_12p_CbYCrY,
+        YCbCr601_422_10_CbYCrY,
+        YCbCr601_422_10p_CbYCrY,
+        YCbCr601_422_12_CbYCrY,
+        YCbCr601_422_12p_CbYCrY,
+        YCbCr709_422_10_CbYCrY,
+        YCbCr709_422_10p_CbYCrY,
+        YCbCr709_422_12_CbYCrY,
+        YCbCr709_422_12p_CbYCrY,
+        BiColorRGBG8,
+        BiColorBGRG8,
+        BiColorRGBG10,
+        BiColorRGBG10p,
+        BiColorBGRG10,
+        BiColorBGRG10p,
+        BiColorRGBG12,
+        BiColorRGBG12p,
+        BiColorBGRG12,
+        BiColorBGRG12p,
+        Coord3D_A8,
+        Coord3D_B8,
+        Coord3D_C8,
+        Coord3D_ABC8,
+        Coord3D_ABC8_Planar,
+        Coord3D_AC8,
+        Coord3D_AC8_Planar,
+        Coord3D_A16,
+        Coord3D_B16,
+        Coord3D_C16,
+        Coord3D_ABC16,
+        Coord3D_ABC16_Planar,
+        Coord3D_AC16,
+        Coord3D_AC16_Planar,
+        Coord3D_A32f,
+        Coord3D_B32f,
+        Coord3D_C32f,
+        Coord3D_ABC32f,
+        Coord3D_ABC32f_Planar,
+        Coord3D_AC32f,
+        Coord3D_AC32f_Planar,
+        Confidence1,
+        Confidence1p,
+        Confidence8,
+        Confidence16,
+        Confidence32f,
+        R8,
+        R10,
+        R12,
+        R16,
+        G8,
+        G10,
+        G12,
+        G16,
+        B8,
+        B10,
+        B12,
+        B16,
+        Coord3D_A10p,
+        Coord3D_B10p,
+        Coord3D_C10p,
+        Coord3D_A12p,
+        Coord3D_B12p,
+        Coord3D_C12p,
+        Coord3D_ABC10p,
+        Coord3D_ABC10p_Planar,
+        Coord3D_ABC12p,
+        Coord3D_ABC12p_Planar,
+        Coord3D_AC10p,
+        Coord3D_AC10p_Planar,
+        Coord3D_AC12p,
+        Coord3D_AC12p_Planar,
+        YCbCr2020_8_CbYCr,
+        YCbCr2020_10_CbYCr,
+        YCbCr2020_10p_CbYCr,
+        YCbCr2020_12_CbYCr,
+        YCbCr2020_12p_CbYCr,
+        YCbCr2020_411_8_CbYYCrYY,
+        YCbCr2020_422_8,
+        YCbCr2020_422_8_CbYCrY,
+        YCbCr2020_422_10,
+        YCbCr2020_422_10_CbYCrY,
+        YCbCr2020_422_10p,
+        YCbCr2020_422_10p_CbYCrY,
+        YCbCr2020_422_12,
+        YCbCr2020_422_12_CbYCrY,
+        YCbCr2020_422_12p,
+        YCbCr2020_422_12p_CbYCrY,
+        Mono14p,
+        BayerGR14p,
+        BayerRG14p,
+        BayerGB14p,
+        BayerBG14p,
+        BayerGR14,
+        BayerRG14,
+        BayerGB14,
+        BayerBG14,
+        BayerGR4p,
+        BayerRG4p,
+        BayerGB4p,
+        BayerBG4p,
+        Mono32,
+        YCbCr420_8_YY_CbCr_Semiplanar,
+        YCbCr422_8_YY_CbCr_Semiplanar,
+        YCbCr420_8_YY_CrCb_Semiplanar,
+        YCbCr422_8_YY_CrCb_Semiplanar,
+        Data8,
+        Data8s,
+        Data16,
+        Data16s,
+        Data32,
+        Data32s,
+        Data32f,
+        Data64,
+        Data64s,
+        Data64f,
+    ];
+
+    /// Returns the name of the pixel format defined in `GenICam PFNC`, e.g. `Mono8`.
+    ///
+    /// The name is the symbolic name of the corresponding `PixelFormat` enumeration entry of
+    /// devices conforming to `GenICam SFNC`.
+    #[allow(clippy::too_many_lines)]
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Mono8 => "Mono8",
+            Mono8s => "Mono8s",
+            Mono10 => "Mono10",
+            Mono10Packed => "Mono10Packed",
+            Mono12 => "Mono12",
+            Mono12Packed => "Mono12Packed",
+            Mono16 => "Mono16",
+            BayerGR8 => "BayerGR8",
+            BayerRG8 => "BayerRG8",
+            BayerGB8 => "BayerGB8",
+            BayerBG8 => "BayerBG8",
+            BayerGR10 => "BayerGR10",
+            BayerRG10 => "BayerRG10",
+            BayerGB10 => "BayerGB10",
+            BayerBG10 => "BayerBG10",
+            BayerGR12 => "BayerGR12",
+            BayerRG12 => "BayerRG12",
+            BayerGB12 => "BayerGB12",
+            BayerBG12 => "BayerBG12",
+            RGB8 => "RGB8",
+            BGR8 => "BGR8",
+            RGBa8 => "RGBa8",
+            BGRa8 => "BGRa8",
+            RGB10 => "RGB10",
+            BGR10 => "BGR10",
+            RGB12 => "RGB12",
+            BGR12 => "BGR12",
+            YUV8_UYV => "YUV8_UYV",
+            RGB8_Planar => "RGB8_Planar",
+            RGB10_Planar => "RGB10_Planar",
+            RGB12_Planar => "RGB12_Planar",
+            RGB16_Planar => "RGB16_Planar",
+            Mono14 => "Mono14",
+            BayerGR10Packed => "BayerGR10Packed",
+            BayerRG10Packed => "BayerRG10Packed",
+            BayerGB10Packed => "BayerGB10Packed",
+            BayerBG10Packed => "BayerBG10Packed",
+            BayerGR12Packed => "BayerGR12Packed",
+            BayerRG12Packed => "BayerRG12Packed",
+            BayerGB12Packed => "BayerGB12Packed",
+            BayerBG12Packed => "BayerBG12Packed",
+            BayerGR16 => "BayerGR16",
+            BayerRG16 => "BayerRG16",
+            BayerGB16 => "BayerGB16",
+            BayerBG16 => "BayerBG16",
+            YUV422_8 => "YUV422_8",
+            RGB16 => "RGB16",
+            RGB12V1Packed => "RGB12V1Packed",
+            RGB565p => "RGB565p",
+            BGR565p => "BGR565p",
+            Mono1p => "Mono1p",
+            Mono2p => "Mono2p",
+            Mono4p => "Mono4p",
+            YCbCr8_CbYCr => "YCbCr8_CbYCr",
+            YCbCr422_8 => "YCbCr422_8",
+            YCbCr411_8_CbYYCrYY => "YCbCr411_8_CbYYCrYY",
+            YCbCr601_8_CbYCr => "YCbCr601_8_CbYCr",
+            YCbCr601_422_8 => "YCbCr601_422_8",
+            YCbCr601_411_8_CbYYCrYY => "YCbCr601_411_8_CbYYCrYY",
+            YCbCr709_8_CbYCr => "YCbCr709_8_CbYCr",
+            YCbCr709_422_8 => "YCbCr709_422_8",
+            YCbCr709_411_8_CbYYCrYY => "YCbCr709_411_8_CbYYCrYY",
+            YCbCr422_8_CbYCrY => "YCbCr422_8_CbYCrY",
+            YCbCr601_422_8_CbYCrY => "YCbCr601_422_8_CbYCrY",
+            YCbCr709_422_8_CbYCrY => "YCbCr709_422_8_CbYCrY",
+            Mono10p => "Mono10p",
+            Mono12p => "Mono12p",
+            BGR10p => "BGR10p",
+            BGR12p => "BGR12p",
+            BGR14 => "BGR14",
+            BGR16 => "BGR16",
+            BGRa10 => "BGRa10",
+            BGRa10p => "BGRa10p",
+            BGRa12 => "BGRa12",
+            BGRa12p => "BGRa12p",
+            BGRa14 => "BGRa14",
+            BGRa16 => "BGRa16",
+            BayerBG10p => "BayerBG10p",
+            BayerBG12p => "BayerBG12p",
+            BayerGB10p => "BayerGB10p",
+            BayerGB12p => "BayerGB12p",
+            BayerGR10p => "BayerGR10p",
+            BayerGR12p => "BayerGR12p",
+            BayerRG10p => "BayerRG10p",
+            BayerRG12p => "BayerRG12p",
+            YCbCr411_8 => "YCbCr411_8",
+            YCbCr8 => "YCbCr8",
+            RGB10p => "RGB10p",
+            RGB12p => "RGB12p",
+            RGB14 => "RGB14",
+            RGBa10 => "RGBa10",
+            RGBa10p => "RGBa10p",
+            RGBa12 => "RGBa12",
+            RGBa12p => "RGBa12p",
+            RGBa14 => "RGBa14",
+            RGBa16 => "RGBa16",
+            YCbCr422_10 => "YCbCr422_10",
+            YCbCr422_12 => "YCbCr422_12",
+            SCF1WBWG8 => "SCF1WBWG8",
+            SCF1WBWG10 => "SCF1WBWG10",
+            SCF1WBWG10p => "SCF1WBWG10p",
+            SCF1WBWG12 => "SCF1WBWG12",
+            SCF1WBWG12p => "SCF1WBWG12p",
+            SCF1WBWG14 => "SCF1WBWG14",
+            SCF1WBWG16 => "SCF1WBWG16",
+            SCF1WGWB8 => "SCF1WGWB8",
+            SCF1WGWB10 => "SCF1WGWB10",
+            SCF1WGWB10p => "SCF1WGWB10p",
+            SCF1WGWB12 => "SCF1WGWB12",
+            SCF1WGWB12p => "SCF1WGWB12p",
+            SCF1WGWB14 => "SCF1WGWB14",
+            SCF1WGWB16 => "SCF1WGWB16",
+            SCF1WGWR8 => "SCF1WGWR8",
+            SCF1WGWR10 => "SCF1WGWR10",
+            SCF1WGWR10p => "SCF1WGWR10p",
+            SCF1WGWR12 => "SCF1WGWR12",
+            SCF1WGWR12p => "SCF1WGWR12p",
+            SCF1WGWR14 => "SCF1WGWR14",
+            SCF1WGWR16 => "SCF1WGWR16",
+            SCF1WRWG8 => "SCF1WRWG8",
+            SCF1WRWG10 => "SCF1WRWG10",
+            SCF1WRWG10p => "SCF1WRWG10p",
+            SCF1WRWG12 => "SCF1WRWG12",
+            SCF1WRWG12p => "SCF1WRWG12p",
+            SCF1WRWG14 => "SCF1WRWG14",
+            SCF1WRWG16 => "SCF1WRWG16",
+            YCbCr10_CbYCr => "YCbCr10_CbYCr",
+            YCbCr10p_CbYCr => "YCbCr10p_CbYCr",
+            YCbCr12_CbYCr => "YCbCr12_CbYCr",
+            YCbCr12p_CbYCr => "YCbCr12p_CbYCr",
+            YCbCr422_10p => "YCbCr422_10p",
+            YCbCr422_12p => "YCbCr422_12p",
+            YCbCr601_10_CbYCr => "YCbCr601_10_CbYCr",
+            YCbCr601_10p_CbYCr => "YCbCr601_10p_CbYCr",
+            YCbCr601_12_CbYCr => "YCbCr601_12_CbYCr",
+            YCbCr601_12p_CbYCr => "YCbCr601_12p_CbYCr",
+            YCbCr601_422_10 => "YCbCr601_422_10",
+            YCbCr601_422_10p => "YCbCr601_422_10p",
+            YCbCr601_422_12 => "YCbCr601_422_12",
+            YCbCr601_422_12p => "YCbCr601_422_12p",
+            YCbCr709_10_CbYCr => "YCbCr709_10_CbYCr",
+            YCbCr709_10p_CbYCr => "YCbCr709_10p_CbYCr",
+            YCbCr709_12_CbYCr => "YCbCr709_12_CbYCr",
+            YCbCr709_12p_CbYCr => "YCbCr709_12p_CbYCr",
+            YCbCr709_422_10 => "YCbCr709_422_10",
+            YCbCr709_422_10p => "YCbCr709_422_10p",
+            YCbCr709_422_12 => "YCbCr709_422_12",
+            YCbCr709_422_12p => "YCbCr709_422_12p",
+            YCbCr422_10_CbYCrY => "YCbCr422_10_CbYCrY",
+            YCbCr422_10p_CbYCrY => "YCbCr422_10p_CbYCrY",
+            YCbCr422_12_CbYCrY => "YCbCr422_12_CbYCrY",
+            YCbCr422_12p_CbYCrY => "YCbCr422_12p_CbYCrY",
+            YCbCr601_422_10_CbYCrY => "YCbCr601_422_10_CbYCrY",
+            YCbCr601_422_10p_CbYCrY => "YCbCr601_422_10p_CbYCrY",
+            YCbCr601_422_12_CbYCrY => "YCbCr601_422_12_CbYCrY",
+            YCbCr601_422_12p_CbYCrY => "YCbCr601_422_12p_CbYCrY",
+            YCbCr709_422_10_CbYCrY => "YCbCr709_422_10_CbYCrY",
+            YCbCr709_422_10p_CbYCrY => "YCbCr709_422_10p_CbYCrY",
+            YCbCr709_422_12_CbYCrY => "YCbCr709_422_12_CbYCrY",
+            YCbCr709_422_12p_CbYCrY => "YCbCr709_422_12p_CbYCrY",
+            BiColorRGBG8 => "BiColorRGBG8",
+            BiColorBGRG8 => "BiColorBGRG8",
+            BiColorRGBG10 => "BiColorRGBG10",
+            BiColorRGBG10p => "BiColorRGBG10p",
+            BiColorBGRG10 => "BiColorBGRG10",
+            BiColorBGRG10p => "BiColorBGRG10p",
+            BiColorRGBG12 => "BiColorRGBG12",
+            BiColorRGBG12p => "BiColorRGBG12p",
+            BiColorBGRG12 => "BiColorBGRG12",
+            BiColorBGRG12p => "BiColorBGRG12p",
+            Coord3D_A8 => "Coord3D_A8",
+            Coord3D_B8 => "Coord3D_B8",
+            Coord3D_C8 => "Coord3D_C8",
+            Coord3D_ABC8 => "Coord3D_ABC8",
+            Coord3D_ABC8_Planar => "Coord3D_ABC8_Planar",
+            Coord3D_AC8 => "Coord3D_AC8",
+            Coord3D_AC8_Planar => "Coord3D_AC8_Planar",
+            Coord3D_A16 => "Coord3D_A16",
+            Coord3D_B16 => "Coord3D_B16",
+            Coord3D_C16 => "Coord3D_C16",
+            Coord3D_ABC16 => "Coord3D_ABC16",
+            Coord3D_ABC16_Planar => "Coord3D_ABC16_Planar",
+            Coord3D_AC16 => "Coord3D_AC16",
+            Coord3D_AC16_Planar => "Coord3D_AC16_Planar",
+            Coord3D_A32f => "Coord3D_A32f",
+            Coord3D_B32f => "Coord3D_B32f",
+            Coord3D_C32f => "Coord3D_C32f",
+            Coord3D_ABC32f => "Coord3D_ABC32f",
+            Coord3D_ABC32f_Planar => "Coord3D_ABC32f_Planar",
+            Coord3D_AC32f => "Coord3D_AC32f",
+            Coord3D_AC32f_Planar => "Coord3D_AC32f_Planar",
+            Confidence1 => "Confidence1",
+            Confidence1p => "Confidence1p",
+            Confidence8 => "Confidence8",
+            Confidence16 => "Confidence16",
+            Confidence32f => "Confidence32f",
+            R8 => "R8",
+            R10 => "R10",
+            R12 => "R12",
+            R16 => "R16",
+            G8 => "G8",
+            G10 => "G10",
+            G12 => "G12",
+            G16 => "G16",
+            B8 => "B8",
+            B10 => "B10",
+            B12 => "B12",
+            B16 => "B16",
+            Coord3D_A10p => "Coord3D_A10p",
+            Coord3D_B10p => "Coord3D_B10p",
+            Coord3D_C10p => "Coord3D_C10p",
+            Coord3D_A12p => "Coord3D_A12p",
+            Coord3D_B12p => "Coord3D_B12p",
+            Coord3D_C12p => "Coord3D_C12p",
+            Coord3D_ABC10p => "Coord3D_ABC10p",
+            Coord3D_ABC10p_Planar => "Coord3D_ABC10p_Planar",
+            Coord3D_ABC12p => "Coord3D_ABC12p",
+            Coord3D_ABC12p_Planar => "Coord3D_ABC12p_Planar",
+            Coord3D_AC10p => "Coord3D_AC10p",
+            Coord3D_AC10p_Planar => "Coord3D_AC10p_Planar",
+            Coord3D_AC12p => "Coord3D_AC12p",
+            Coord3D_AC12p_Planar => "Coord3D_AC12p_Planar",
+            YCbCr2020_8_CbYCr => "YCbCr2020_8_CbYCr",
+            YCbCr2020_10_CbYCr => "YCbCr2020_10_CbYCr",
+            YCbCr2020_10p_CbYCr => "YCbCr2020_10p_CbYCr",
+            YCbCr2020_12_CbYCr => "YCbCr2020_12_CbYCr",
+            YCbCr2020_12p_CbYCr => "YCbCr2020_12p_CbYCr",
+            YCbCr2020_411_8_CbYYCrYY => "YCbCr2020_411_8_CbYYCrYY",
+            YCbCr2020_422_8 => "YCbCr2020_422_8",
+            YCbCr2020_422_8_CbYCrY => "YCbCr2020_422_8_CbYCrY",
+            YCbCr2020_422_10 => "YCbCr2020_422_10",
+            YCbCr2020_422_10_CbYCrY => "YCbCr2020_422_10_CbYCrY",
+            YCbCr2020_422_10p => "YCbCr2020_422_10p",
+            YCbCr2020_422_10p_CbYCrY => "YCbCr2020_422_10p_CbYCrY",
+            YCbCr2020_422_12 => "YCbCr2020_422_12",
+            YCbCr2020_422_12_CbYCrY => "YCbCr2020_422_12_CbYCrY",
+            YCbCr2020_422_12p => "YCbCr2020_422_12p",
+            YCbCr2020_422_12p_CbYCrY => "YCbCr2020_422_12p_CbYCrY",
+            Mono14p => "Mono14p",
+            BayerGR14p => "BayerGR14p",
+            BayerRG14p => "BayerRG14p",
+            BayerGB14p => "BayerGB14p",
+            BayerBG14p => "BayerBG14p",
+            BayerGR14 => "BayerGR14",
+            BayerRG14 => "BayerRG14",
+            BayerGB14 => "BayerGB14",
+            BayerBG14 => "BayerBG14",
+            BayerGR4p => "BayerGR4p",
+            BayerRG4p => "BayerRG4p",
+            BayerGB4p => "BayerGB4p",
+            BayerBG4p => "BayerBG4p",
+            Mono32 => "Mono32",
+            YCbCr420_8_YY_CbCr_Semiplanar => "YCbCr420_8_YY_CbCr_Semiplanar",
+            YCbCr422_8_YY_CbCr_Semiplanar => "YCbCr422_8_YY_CbCr_Semiplanar",
+            YCbCr420_8_YY_CrCb_Semiplanar => "YCbCr420_8_YY_CrCb_Semiplanar",
+            YCbCr422_8_YY_CrCb_Semiplanar => "YCbCr422_8_YY_CrCb_Semiplanar",
+            Data8 => "Data8",
+            Data8s => "Data8s",
+            Data16 => "Data16",
+            Data16s => "Data16s",
+            Data32 => "Data32",
+            Data32s => "Data32s",
+            Data32f => "Data32f",
+            Data64 => "Data64",
+            Data64s => "Data64s",
+            Data64f => "Data64f",
+        }
+    }
+
+    /// Returns the 32 bit code of the pixel format defined in `GenICam PFNC`.
+    ///
+    /// The code is the value of the corresponding `PixelFormat` enumeration entry of devices
+    /// conforming to `GenICam SFNC`.
+    #[must_use]
+    pub fn code(self) -> u32 {
+        self.into()
+    }
+
+    /// Returns the number of bits occupied by a pixel, including padding bits.
+    ///
+    /// For formats with sub-sampled chroma, e.g. `YCbCr422_8`, this is the average over a macro
+    /// pixel.
+    #[must_use]
+    pub fn bits_per_pixel(self) -> u32 {
+        // Bits 16 to 23 of the code hold the effective size of a pixel.
+        (self.code() >> 16) & 0xff
+    }
+
+    /// Returns `true` if pixels are packed without padding bits, e.g. `Mono12p` or
+    /// `Mono12Packed`.
+    #[must_use]
+    pub fn is_packed(self) -> bool {
+        let name = self.name();
+        name.ends_with('p') || name.contains("p_") || name.contains("Packed")
+    }
+
+    /// Returns the arrangement of components in the pixel format.
+    #[must_use]
+    pub fn component_layout(self) -> ComponentLayout {
+        let name = self.name();
+        let starts_with = |prefix: &str| name.starts_with(prefix);
+        if starts_with("Mono") {
+            ComponentLayout::Mono
+        } else if starts_with("BayerRG") {
+            ComponentLayout::Bayer(BayerPattern::RG)
+        } else if starts_with("BayerGR") {
+            ComponentLayout::Bayer(BayerPattern::GR)
+        } else if starts_with("BayerGB") {
+            ComponentLayout::Bayer(BayerPattern::GB)
+        } else if starts_with("BayerBG") {
+            ComponentLayout::Bayer(BayerPattern::BG)
+        } else if starts_with("BiColor") {
+            ComponentLayout::BiColor
+        } else if starts_with("SCF") {
+            ComponentLayout::SparseColorFilter
+        } else if starts_with("RGBa") {
+            ComponentLayout::Rgba
+        } else if starts_with("BGRa") {
+            ComponentLayout::Bgra
+        } else if starts_with("RGB") {
+            ComponentLayout::Rgb
+        } else if starts_with("BGR") {
+            ComponentLayout::Bgr
+        } else if starts_with("R") || starts_with("G") || starts_with("B") {
+            ComponentLayout::SingleColor
+        } else if starts_with("YCbCr") || starts_with("YUV") {
+            ComponentLayout::YCbCr
+        } else if starts_with("Coord3D") {
+            ComponentLayout::Coord3D
+        } else if starts_with("Confidence") {
+            ComponentLayout::Confidence
+        } else {
+            ComponentLayout::Data
+        }
+    }
+}
+
+/// Arrangement of components in a [`PixelFormat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentLayout {
+    /// A luminance component, e.g. `Mono8`.
+    Mono,
+
+    /// A component filtered by a Bayer color filter array, e.g. `BayerRG8`.
+    Bayer(BayerPattern),
+
+    /// A component filtered by a bi-color filter array, e.g. `BiColorRGBG8`.
+    BiColor,
+
+    /// A component filtered by a sparse color filter array, e.g. `SCF1WBWG8`.
+    SparseColorFilter,
+
+    /// A single color component, e.g. `R8`.
+    SingleColor,
+
+    /// Red, green and blue components, e.g. `RGB8` or `RGB8_Planar`.
+    Rgb,
+
+    /// Blue, green and red components, e.g. `BGR8`.
+    Bgr,
+
+    /// Red, green, blue and alpha components, e.g. `RGBa8`.
+    Rgba,
+
+    /// Blue, green, red and alpha components, e.g. `BGRa8`.
+    Bgra,
+
+    /// Luma and chroma components, e.g. `YCbCr422_8` or `YUV422_8`.
+    YCbCr,
+
+    /// 3D coordinates, e.g. `Coord3D_ABC32f`.
+    Coord3D,
+
+    /// Confidence of 3D data, e.g. `Confidence8`.
+    Confidence,
+
+    /// Data without specific meaning, e.g. `Data8`.
+    Data,
+}
+
+impl ComponentLayout {
+    /// Returns `true` if each pixel holds a single component of a color filter array, i.e. the
+    /// image needs to be demosaiced to get colors.
+    #[must_use]
+    pub fn is_color_filter_array(self) -> bool {
+        matches!(
+            self,
+            Self::Bayer(_) | Self::BiColor | Self::SparseColorFilter
+        )
+    }
+}
+
+/// Arrangement of the upper-left 2x2 pixels of a Bayer color filter array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BayerPattern {
+    /// Red and green on the first row, green and blue on the second row.
+    RG,
+
+    /// Green and red on the first row, blue and green on the second row.
+    GR,
+
+    /// Green and blue on the first row, red and green on the second row.
+    GB,
+
+    /// Blue and green on the first row, green and red on the second row.
+    BG,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_format_registry() {
+        for &pixel_format in PixelFormat::ALL {
+            assert_eq!(PixelFormat::try_from(pixel_format.code()), Ok(pixel_format));
+            assert_eq!(pixel_format.name().parse(), Ok(pixel_format));
+        }
+        assert!("Mono9".parse::<PixelFormat>().is_err());
+    }
+
+    #[test]
+    fn test_pixel_format_properties() {
+        assert_eq!(Mono8.bits_per_pixel(), 8);
+        assert_eq!(Mono12p.bits_per_pixel(), 12);
+        assert_eq!(Mono12Packed.bits_per_pixel(), 12);
+        assert_eq!(RGBa8.bits_per_pixel(), 32);
+        assert_eq!(YCbCr422_8.bits_per_pixel(), 16);
+
+        assert!(Mono12p.is_packed());
+        assert!(BayerRG10Packed.is_packed());
+        assert!(Coord3D_ABC10p_Planar.is_packed());
+        assert!(!Mono12.is_packed());
+        assert!(!RGB8_Planar.is_packed());
+
+        assert_eq!(
+            BayerGB12p.component_layout(),
+            ComponentLayout::Bayer(BayerPattern::GB)
+        );
+        assert_eq!(BGRa8.component_layout(), ComponentLayout::Bgra);
+        assert_eq!(B8.component_layout(), ComponentLayout::SingleColor);
+        assert_eq!(BiColorBGRG8.component_layout(), ComponentLayout::BiColor);
+        assert_eq!(YUV422_8.component_layout(), ComponentLayout::YCbCr);
+        assert!(SCF1WBWG8.component_layout().is_color_filter_array());
+        assert_eq!(Data32f.to_string(), "Data32f");
+    }
+}
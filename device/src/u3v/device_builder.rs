@@ -5,7 +5,7 @@
 use cameleon_impl::bytes_io::ReadBytes;
 use semver::Version;
 
-use crate::u3v::{BusSpeed, DeviceInfo, Error, Result};
+use crate::u3v::{BusSpeed, DeviceInfo, Error, Result, UsbTopology};
 
 use super::{
     channel::{ControlIfaceInfo, ReceiveIfaceInfo},
@@ -84,7 +84,6 @@ impl DeviceBuilder {
             .ok_or(Error::InvalidDevice)?;
         let device_info_desc = ctrl_iface_desc.extra();
         let device_info_desc = DeviceInfoDescriptor::from_bytes(device_info_desc)?;
-        let device_info = device_info_desc.interpret(&dev_channel)?;
 
         // Retrieve event and stream interface information if exists.
         let receive_ifaces = interfaces.filter_map(|iface| ReceiveIfaceInfo::new(&iface));
@@ -113,6 +112,21 @@ impl DeviceBuilder {
             None => (None, None),
         };
 
+        // Negotiated max packet size is reported by the bulk in endpoint of the stream interface,
+        // fall back to the control interface if the device doesn't stream.
+        let max_packet_size_iface = stream_iface
+            .as_ref()
+            .map_or(ctrl_iface_info.iface_number, |iface| iface.iface_number);
+        let topology = UsbTopology {
+            bus_number: self.device.bus_number(),
+            port_numbers: self.device.port_numbers()?,
+            negotiated_speed: bus_speed(self.device.speed()),
+            max_packet_size: self
+                .bulk_in_max_packet_size(max_packet_size_iface)
+                .ok_or(Error::InvalidDevice)?,
+        };
+        let device_info = device_info_desc.interpret(&dev_channel, topology)?;
+
         Ok(Device::new(
             self.device,
             ctrl_iface_info,
@@ -122,6 +136,24 @@ impl DeviceBuilder {
         ))
     }
 
+    fn bulk_in_max_packet_size(&self, iface_number: u8) -> Option<u16> {
+        let iface = self
+            .config_desc
+            .interfaces()
+            .find(|iface| iface.number() == iface_number)?;
+        let desc = iface
+            .descriptors()
+            .find(|desc| desc.setting_number() == 0)?;
+        let max_packet_size = desc
+            .endpoint_descriptors()
+            .find(|ep| {
+                ep.direction() == rusb::Direction::In
+                    && ep.transfer_type() == rusb::TransferType::Bulk
+            })?
+            .max_packet_size();
+        Some(max_packet_size)
+    }
+
     fn find_u3v_iad(
         device: &RusbDevice,
         device_desc: &rusb::DeviceDescriptor,
@@ -323,7 +355,7 @@ impl DeviceInfoDescriptor {
         })
     }
 
-    fn interpret(&self, channel: &RusbDeviceHandle) -> Result<DeviceInfo> {
+    fn interpret(&self, channel: &RusbDeviceHandle, topology: UsbTopology) -> Result<DeviceInfo> {
         let gencp_version = Version::new(
             self.gencp_version_major.into(),
             self.gencp_version_minor.into(),
@@ -379,10 +411,22 @@ impl DeviceInfoDescriptor {
             serial_number,
             user_defined_name,
             supported_speed,
+            topology,
         })
     }
 }
 
+fn bus_speed(speed: rusb::Speed) -> Option<BusSpeed> {
+    match speed {
+        rusb::Speed::Low => Some(BusSpeed::LowSpeed),
+        rusb::Speed::Full => Some(BusSpeed::FullSpeed),
+        rusb::Speed::High => Some(BusSpeed::HighSpeed),
+        rusb::Speed::Super => Some(BusSpeed::SuperSpeed),
+        rusb::Speed::SuperPlus => Some(BusSpeed::SuperSpeedPlus),
+        _ => None,
+    }
+}
+
 impl ControlIfaceInfo {
     const CONTROL_IFACE_PROTOCOL: u8 = 0x00;
 
@@ -45,10 +45,45 @@ pub struct DeviceInfo {
 
     /// Bus speed supported by the device.
     pub supported_speed: BusSpeed,
+
+    /// Location of the device on the USB bus and the negotiated link parameters.
+    pub topology: UsbTopology,
+}
+
+/// Location of a device on the USB bus and parameters negotiated with the host.
+///
+/// The location is stable as long as the device is plugged into the same port, so it can be used
+/// to distinguish identical cameras, e.g. in a rack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsbTopology {
+    /// Number of the bus the device is connected to.
+    pub bus_number: u8,
+
+    /// Port numbers from the root hub to the device.
+    pub port_numbers: Vec<u8>,
+
+    /// Bus speed negotiated with the host.
+    /// `None` if the speed is unknown to the OS.
+    pub negotiated_speed: Option<BusSpeed>,
+
+    /// Maximum packet size of the bulk in endpoint used for streaming, or the control endpoint if
+    /// the device doesn't have a stream interface.
+    pub max_packet_size: u16,
+}
+
+impl UsbTopology {
+    /// Returns the port path of the device in the form of `<bus>-<port>.<port>...`, e.g. `2-1.4`.
+    ///
+    /// The format is the same as the one used by the Linux kernel to name USB devices.
+    #[must_use]
+    pub fn port_path(&self) -> String {
+        let ports: Vec<String> = self.port_numbers.iter().map(u8::to_string).collect();
+        format!("{}-{}", self.bus_number, ports.join("."))
+    }
 }
 
 /// Bus speed supported by each USB device.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BusSpeed {
     /// USB 1.0/Low-Speed: 1.5 Mbps
     LowSpeed,
@@ -96,8 +131,34 @@ impl fmt::Display for DeviceInfo {
             writeln!(f, "User Defined Name: N/A")
         }?;
 
-        write!(f, "Supported Speed: {:?}", self.supported_speed)?;
+        writeln!(f, "Supported Speed: {:?}", self.supported_speed)?;
+
+        writeln!(f, "Port Path: {}", self.topology.port_path())?;
+
+        if let Some(negotiated_speed) = self.topology.negotiated_speed {
+            writeln!(f, "Negotiated Speed: {:?}", negotiated_speed)
+        } else {
+            writeln!(f, "Negotiated Speed: N/A")
+        }?;
+
+        write!(f, "Max Packet Size: {}", self.topology.max_packet_size)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_path() {
+        let topology = UsbTopology {
+            bus_number: 2,
+            port_numbers: vec![1, 4],
+            negotiated_speed: Some(BusSpeed::SuperSpeed),
+            max_packet_size: 1024,
+        };
+        assert_eq!(topology.port_path(), "2-1.4");
+    }
+}
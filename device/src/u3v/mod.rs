@@ -2,9 +2,14 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+#[cfg(feature = "libusb")]
 pub mod async_read;
 pub mod protocol;
 pub mod register_map;
+// `web-sys` provides `WebUSB` API only if `web_sys_unstable_apis` is set, which isn't the case
+// e.g. when examples are built by `trybuild`.
+#[cfg(all(feature = "webusb", web_sys_unstable_apis))]
+pub mod webusb;
 pub mod prelude {
     pub use protocol::ack::ParseScd;
     pub use protocol::cmd::CommandScd;
@@ -12,15 +17,21 @@ pub mod prelude {
     use super::protocol;
 }
 
+#[cfg(feature = "libusb")]
 mod channel;
+#[cfg(feature = "libusb")]
 mod device;
+#[cfg(feature = "libusb")]
 mod device_builder;
 mod device_info;
 
+#[cfg(feature = "libusb")]
 pub use channel::{ControlChannel, ReceiveChannel};
+#[cfg(feature = "libusb")]
 pub use device::Device;
+#[cfg(feature = "libusb")]
 pub use device_builder::enumerate_devices;
-pub use device_info::{BusSpeed, DeviceInfo};
+pub use device_info::{BusSpeed, DeviceInfo, UsbTopology};
 
 use std::borrow::Cow;
 
@@ -28,9 +39,14 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    #[cfg(feature = "libusb")]
     #[error("libusb error: {0}")]
     LibUsb(#[from] LibUsbError),
 
+    #[cfg(feature = "webusb")]
+    #[error("WebUSB error: {0}")]
+    WebUsb(Cow<'static, str>),
+
     #[error("packet is broken: {0}")]
     InvalidPacket(Cow<'static, str>),
 
@@ -42,6 +58,7 @@ pub enum Error {
 }
 
 /// Errors raised from libusb.
+#[cfg(feature = "libusb")]
 #[derive(Debug, Error)]
 pub enum LibUsbError {
     #[error("input/output error")]
@@ -76,6 +93,7 @@ pub enum LibUsbError {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[cfg(feature = "libusb")]
 impl From<rusb::Error> for Error {
     fn from(err: rusb::Error) -> Error {
         use LibUsbError::{
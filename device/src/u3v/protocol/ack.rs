@@ -396,6 +396,11 @@ impl<'a> ParseScd<'a> for WriteMemStacked {
     fn parse(buf: &'a [u8], ccd: &AckCcd) -> Result<Self> {
         let mut cursor = Cursor::new(buf);
         let mut to_read = ccd.scd_len as usize;
+        if !to_read.is_multiple_of(4) {
+            return Err(Error::InvalidPacket(
+                "SCD length of WriteMemStackedAck must be a multiple of 4".into(),
+            ));
+        }
         let mut lengths = Vec::with_capacity(to_read / 4);
 
         while to_read > 0 {
@@ -498,6 +503,16 @@ mod tests {
         assert_eq!(&parsed_scd.lengths, &[3, 10]);
     }
 
+    #[test]
+    fn test_write_mem_stacked_ack_with_truncated_scd() {
+        let scd = [0x00, 0x00, 0x03];
+        let mut raw_packet = serialize_header(0x0000, 0x0809, scd.len() as u16, 1);
+        raw_packet.extend(&scd);
+
+        let ack = AckPacket::parse(&raw_packet).unwrap();
+        assert!(ack.scd_as::<WriteMemStacked>().is_err());
+    }
+
     #[test]
     fn test_pending_ack() {
         use std::time::Duration;
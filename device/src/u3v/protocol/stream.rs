@@ -5,6 +5,7 @@
 //! This module provides parser for U3V stream protocol.
 use std::{
     convert::{TryFrom, TryInto},
+    fmt,
     io::Cursor,
     time,
 };
@@ -54,6 +55,7 @@ pub struct Leader<'a> {
     leader_size: u16,
     block_id: u64,
     payload_type: PayloadType,
+    reserved: [u16; 2],
 
     /// The raw bytes represents specific leader.
     raw_specfic_leader: &'a [u8],
@@ -61,16 +63,18 @@ pub struct Leader<'a> {
 
 impl<'a> Leader<'a> {
     const LEADER_MAGIC: u32 = 0x4C56_3355;
+    const IMAGE_LEADER_SIZE: u16 = 52;
+    const CHUNK_LEADER_SIZE: u16 = 28;
 
     /// Parse bytes as Leader.
     pub fn parse(buf: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self> {
         let mut cursor = Cursor::new(buf.as_ref());
 
         Self::parse_prefix(&mut cursor)?;
-        let _reserved1: u16 = cursor.read_bytes_le()?;
+        let reserved1 = cursor.read_bytes_le()?;
         let leader_size = cursor.read_bytes_le()?;
         let block_id = cursor.read_bytes_le()?;
-        let _reserved2: u16 = cursor.read_bytes_le()?;
+        let reserved2 = cursor.read_bytes_le()?;
         let payload_type = cursor.read_bytes_le::<u16>()?.try_into()?;
 
         let raw_specfic_leader = &cursor.get_ref()[cursor.position() as usize..];
@@ -79,10 +83,46 @@ impl<'a> Leader<'a> {
             leader_size,
             block_id,
             payload_type,
+            reserved: [reserved1, reserved2],
             raw_specfic_leader,
         })
     }
 
+    /// Returns violations of the specification found in the leader.
+    ///
+    /// Only the violations which don't prevent parsing are reported, see [`Strictness`].
+    #[must_use]
+    pub fn violations(&self) -> Vec<Violation> {
+        let mut violations = reserved_violations(&[
+            ("leader reserved field", self.reserved[0]),
+            ("leader reserved field", self.reserved[1]),
+        ]);
+
+        let expected = match self.payload_type {
+            PayloadType::Image | PayloadType::ImageExtendedChunk => {
+                // The last field of the image specific leader is reserved.
+                if let Some(reserved) = self.raw_specfic_leader.get(30..32) {
+                    let value = u16::from_le_bytes([reserved[0], reserved[1]]);
+                    violations.extend(reserved_violations(&[(
+                        "image leader reserved field",
+                        value,
+                    )]));
+                }
+                Self::IMAGE_LEADER_SIZE
+            }
+            PayloadType::Chunk => Self::CHUNK_LEADER_SIZE,
+        };
+        if self.leader_size != expected {
+            violations.push(Violation::UnexpectedSize {
+                field: "leader size",
+                expected,
+                actual: self.leader_size,
+            });
+        }
+
+        violations
+    }
+
     /// Return a specific part of leader.
     ///
     /// # Example
@@ -384,22 +424,27 @@ pub struct Trailer<'a> {
     block_id: u64,
     payload_status: PayloadStatus,
     valid_payload_size: u64,
+    reserved: [u16; 2],
     raw_specfic_trailer: &'a [u8],
 }
 
 impl<'a> Trailer<'a> {
     const TRAILER_MAGIC: u32 = 0x5456_3355;
+    /// Size of the trailer of `Image` and `Chunk` payloads, both of which have a 4 bytes
+    /// specific trailer.
+    const IMAGE_TRAILER_SIZE: u16 = 32;
+    const IMAGE_EXTENDED_CHUNK_TRAILER_SIZE: u16 = 36;
 
     /// Parse bytes as Leader.
     pub fn parse(buf: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self> {
         let mut cursor = Cursor::new(buf.as_ref());
 
         Self::parse_prefix(&mut cursor)?;
-        let _reserved1: u16 = cursor.read_bytes_le()?;
+        let reserved1 = cursor.read_bytes_le()?;
         let trailer_size = cursor.read_bytes_le()?;
         let block_id = cursor.read_bytes_le()?;
         let payload_status = cursor.read_bytes_le::<u16>()?.try_into()?;
-        let _reserved2: u16 = cursor.read_bytes_le()?;
+        let reserved2 = cursor.read_bytes_le()?;
         let valid_payload_size = cursor.read_bytes_le()?;
 
         let raw_specfic_trailer = &cursor.get_ref()[cursor.position() as usize..];
@@ -409,10 +454,44 @@ impl<'a> Trailer<'a> {
             block_id,
             payload_status,
             valid_payload_size,
+            reserved: [reserved1, reserved2],
             raw_specfic_trailer,
         })
     }
 
+    /// Returns violations of the specification found in the trailer of the block which `leader`
+    /// belongs to.
+    ///
+    /// Only the violations which don't prevent parsing are reported, see [`Strictness`].
+    #[must_use]
+    pub fn violations(&self, leader: &Leader) -> Vec<Violation> {
+        let mut violations = reserved_violations(&[
+            ("trailer reserved field", self.reserved[0]),
+            ("trailer reserved field", self.reserved[1]),
+        ]);
+
+        let expected = match leader.payload_type() {
+            PayloadType::Image | PayloadType::Chunk => Self::IMAGE_TRAILER_SIZE,
+            PayloadType::ImageExtendedChunk => Self::IMAGE_EXTENDED_CHUNK_TRAILER_SIZE,
+        };
+        if self.trailer_size != expected {
+            violations.push(Violation::UnexpectedSize {
+                field: "trailer size",
+                expected,
+                actual: self.trailer_size,
+            });
+        }
+
+        if self.block_id != leader.block_id() {
+            violations.push(Violation::BlockIdMismatch {
+                leader: leader.block_id(),
+                trailer: self.block_id,
+            });
+        }
+
+        violations
+    }
+
     /// Return a specific part of trailer.
     pub fn specific_trailer_as<T: SpecificTrailer>(&self) -> Result<T> {
         T::from_bytes(self.raw_specfic_trailer)
@@ -579,6 +658,91 @@ impl TryFrom<u16> for PayloadStatus {
     }
 }
 
+/// How strictly leaders and trailers are checked against the `U3V` specification.
+///
+/// Some cameras emit slightly non-conforming leaders and trailers, e.g. with non-zero reserved
+/// fields or odd sizes. The strictness determines which [`Violation`]s are fatal. Violations
+/// which prevent parsing, e.g. an invalid magic, are always fatal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// All violations are fatal.
+    Strict,
+    /// Non-zero reserved fields are tolerated, other violations are fatal.
+    Lenient,
+    /// All violations are tolerated.
+    #[default]
+    Permissive,
+}
+
+/// A violation of the `U3V` specification found in a leader or a trailer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// A reserved field isn't zero.
+    ReservedField {
+        /// Name of the field.
+        field: &'static str,
+        /// Value of the field.
+        value: u16,
+    },
+
+    /// The size declared in a leader or a trailer differs from the size defined for the payload
+    /// type.
+    UnexpectedSize {
+        /// Name of the field.
+        field: &'static str,
+        /// Size defined in the specification.
+        expected: u16,
+        /// Size declared by the device.
+        actual: u16,
+    },
+
+    /// The block ID of a trailer differs from the block ID of the leader.
+    BlockIdMismatch {
+        /// Block ID of the leader.
+        leader: u64,
+        /// Block ID of the trailer.
+        trailer: u64,
+    },
+}
+
+impl Violation {
+    /// Returns `true` if the violation is fatal under `strictness`.
+    #[must_use]
+    pub fn is_fatal(self, strictness: Strictness) -> bool {
+        match strictness {
+            Strictness::Strict => true,
+            Strictness::Lenient => !matches!(self, Self::ReservedField { .. }),
+            Strictness::Permissive => false,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ReservedField { field, value } => write!(f, "{} is {:#x}", field, value),
+            Self::UnexpectedSize {
+                field,
+                expected,
+                actual,
+            } => write!(f, "{} is {}, but expected {}", field, actual, expected),
+            Self::BlockIdMismatch { leader, trailer } => write!(
+                f,
+                "block ID of the trailer is {}, but the leader's is {}",
+                trailer, leader
+            ),
+        }
+    }
+}
+
+fn reserved_violations(fields: &[(&'static str, u16)]) -> Vec<Violation> {
+    fields
+        .iter()
+        .filter(|(_, value)| *value != 0)
+        .map(|&(field, value)| Violation::ReservedField { field, value })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -736,6 +900,49 @@ mod tests {
         assert_eq!(image_leader.timestamp(), time::Duration::from_nanos(100));
     }
 
+    #[test]
+    fn test_violations() {
+        let mut buf = generic_leader_bytes(PayloadType::Chunk);
+        // Timestamp.
+        buf.write_bytes_le(100_u64).unwrap();
+        // Non-zero reserved field.
+        buf[4] = 1;
+        let leader = Leader::parse(&buf).unwrap();
+        let violations = leader.violations();
+        assert_eq!(
+            violations,
+            [
+                Violation::ReservedField {
+                    field: "leader reserved field",
+                    value: 1
+                },
+                Violation::UnexpectedSize {
+                    field: "leader size",
+                    expected: 28,
+                    actual: 20
+                }
+            ]
+        );
+        assert!(violations[0].is_fatal(Strictness::Strict));
+        assert!(!violations[0].is_fatal(Strictness::Lenient));
+        assert!(violations[1].is_fatal(Strictness::Lenient));
+        assert!(!violations[1].is_fatal(Strictness::Permissive));
+
+        let mut buf = generic_trailer_bytes(PayloadType::Chunk);
+        // Chunk layout ID.
+        buf.write_bytes_le(0_u32).unwrap();
+        // Block ID.
+        buf[8] = 52;
+        let trailer = Trailer::parse(&buf).unwrap();
+        assert_eq!(
+            trailer.violations(&leader),
+            [Violation::BlockIdMismatch {
+                leader: 51,
+                trailer: 52
+            }]
+        );
+    }
+
     #[test]
     fn test_parse_generic_trailer() {
         let mut buf = vec![];
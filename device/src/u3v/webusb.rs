@@ -0,0 +1,560 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains `WebUSB` backend of U3V channels, which is intended to be used on
+//! `wasm32-unknown-unknown` target, e.g. in browser based camera configuration tools.
+//!
+//! `WebUSB` API is asynchronous, so all I/O operations in this module are `async` and must be
+//! driven by the JavaScript event loop, e.g. with `wasm_bindgen_futures::spawn_local`.
+//! Also, `WebUSB` doesn't support timeout of a transfer, so operations don't take timeout duration
+//! unlike the libusb backend.
+//!
+//! NOTE: `WebUSB` API is unstable in `web-sys`, so `--cfg=web_sys_unstable_apis` must be passed to
+//! rustc.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # async fn run() -> cameleon_device::u3v::Result<()> {
+//! use cameleon_device::u3v::webusb;
+//!
+//! // Shows a device chooser to the user. This must be called in response to user gesture.
+//! let device = webusb::request_device().await?;
+//! device.open().await?;
+//!
+//! let mut ctrl = webusb::WebUsbControlHandle::new(device.control_channel());
+//! ctrl.open().await?;
+//!
+//! // Read serial number from ABRM.
+//! let mut buf = vec![0; 64];
+//! ctrl.read(0x0144, &mut buf).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::convert::TryInto;
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Usb, UsbAlternateInterface, UsbConfiguration, UsbDevice, UsbDeviceFilter,
+    UsbDeviceRequestOptions, UsbDirection, UsbEndpoint, UsbInTransferResult, UsbInterface,
+    UsbOutTransferResult, UsbTransferStatus,
+};
+
+use super::{
+    protocol::{
+        ack,
+        cmd::{self, CommandScd},
+    },
+    register_map::{abrm, sbrm},
+    Error, Result,
+};
+
+const MISCELLANEOUS_CLASS: u8 = 0xEF;
+const USB3V_SUBCLASS: u8 = 0x05;
+const CONTROL_IFACE_PROTOCOL: u8 = 0x00;
+const EVENT_IFACE_PROTOCOL: u8 = 0x01;
+const STREAM_IFACE_PROTOCOL: u8 = 0x02;
+
+/// Initial maximum command/acknowledge packet length used until SBRM is read.
+const INITIAL_MAXIMUM_PACKET_LENGTH: u32 = 128;
+
+/// Shows a device chooser filtered to U3V devices and returns the device selected by the user.
+///
+/// Browsers allow to call this only in response to user gesture, e.g. a button click.
+pub async fn request_device() -> Result<WebUsbDevice> {
+    let filter = UsbDeviceFilter::new();
+    filter.set_class_code(MISCELLANEOUS_CLASS);
+    filter.set_subclass_code(USB3V_SUBCLASS);
+    let options = UsbDeviceRequestOptions::new(&[filter]);
+
+    let device = JsFuture::from(usb()?.request_device(&options))
+        .await
+        .map_err(js_err)?;
+    WebUsbDevice::new(device.unchecked_into())
+}
+
+/// Enumerates U3V devices which the user has already granted access to.
+pub async fn enumerate_devices() -> Result<Vec<WebUsbDevice>> {
+    let devices: Array = JsFuture::from(usb()?.get_devices())
+        .await
+        .map_err(js_err)?
+        .unchecked_into();
+
+    Ok(devices
+        .iter()
+        .filter_map(|device| WebUsbDevice::new(device.unchecked_into()).ok())
+        .collect())
+}
+
+/// Entry point to the device granted by the user.
+///
+/// As with `Device` of the libusb backend, channels provided by this device can be used even after
+/// dropping this instance.
+#[derive(Clone, Debug)]
+pub struct WebUsbDevice {
+    device: UsbDevice,
+    ctrl_iface_info: ControlIfaceInfo,
+    event_iface_info: Option<ReceiveIfaceInfo>,
+    stream_iface_info: Option<ReceiveIfaceInfo>,
+}
+
+impl WebUsbDevice {
+    /// Opens the device and selects the first configuration if the device is not configured.
+    pub async fn open(&self) -> Result<()> {
+        if !self.device.opened() {
+            JsFuture::from(self.device.open()).await.map_err(js_err)?;
+        }
+        if self.device.configuration().is_none() {
+            JsFuture::from(self.device.select_configuration(1))
+                .await
+                .map_err(js_err)?;
+        }
+        Ok(())
+    }
+
+    /// Closes the device.
+    pub async fn close(&self) -> Result<()> {
+        if self.device.opened() {
+            JsFuture::from(self.device.close()).await.map_err(js_err)?;
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn control_channel(&self) -> WebUsbControlChannel {
+        WebUsbControlChannel {
+            device: self.device.clone(),
+            iface_info: self.ctrl_iface_info.clone(),
+            is_opened: false,
+        }
+    }
+
+    #[must_use]
+    pub fn event_channel(&self) -> Option<WebUsbReceiveChannel> {
+        self.event_iface_info
+            .clone()
+            .map(|iface_info| WebUsbReceiveChannel::new(self.device.clone(), iface_info))
+    }
+
+    #[must_use]
+    pub fn stream_channel(&self) -> Option<WebUsbReceiveChannel> {
+        self.stream_iface_info
+            .clone()
+            .map(|iface_info| WebUsbReceiveChannel::new(self.device.clone(), iface_info))
+    }
+
+    /// Returns the underlying `USBDevice`, e.g. to read its string descriptors.
+    #[must_use]
+    pub fn usb_device(&self) -> &UsbDevice {
+        &self.device
+    }
+
+    fn new(device: UsbDevice) -> Result<Self> {
+        let config: UsbConfiguration = match device.configuration() {
+            Some(config) => config,
+            None => device
+                .configurations()
+                .get(0)
+                .dyn_into()
+                .map_err(|_| Error::InvalidDevice)?,
+        };
+
+        let mut ctrl_iface_info = None;
+        let mut event_iface_info = None;
+        let mut stream_iface_info = None;
+        for iface in config.interfaces().iter() {
+            let iface: UsbInterface = iface.unchecked_into();
+            let alt: UsbAlternateInterface = iface.alternate();
+            if alt.interface_class() != MISCELLANEOUS_CLASS
+                || alt.interface_subclass() != USB3V_SUBCLASS
+            {
+                continue;
+            }
+
+            let iface_number = iface.interface_number();
+            let bulk_in_ep = find_endpoint(&alt, UsbDirection::In);
+            let bulk_out_ep = find_endpoint(&alt, UsbDirection::Out);
+            match (alt.interface_protocol(), bulk_in_ep, bulk_out_ep) {
+                (CONTROL_IFACE_PROTOCOL, Some(bulk_in_ep), Some(bulk_out_ep)) => {
+                    ctrl_iface_info = Some(ControlIfaceInfo {
+                        iface_number,
+                        bulk_in_ep,
+                        bulk_out_ep,
+                    });
+                }
+                (EVENT_IFACE_PROTOCOL, Some(bulk_in_ep), _) => {
+                    event_iface_info = Some(ReceiveIfaceInfo {
+                        iface_number,
+                        bulk_in_ep,
+                    });
+                }
+                (STREAM_IFACE_PROTOCOL, Some(bulk_in_ep), _) => {
+                    stream_iface_info = Some(ReceiveIfaceInfo {
+                        iface_number,
+                        bulk_in_ep,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            device,
+            ctrl_iface_info: ctrl_iface_info.ok_or(Error::InvalidDevice)?,
+            event_iface_info,
+            stream_iface_info,
+        })
+    }
+}
+
+/// `WebUSB` version of `ControlChannel`.
+#[derive(Clone, Debug)]
+pub struct WebUsbControlChannel {
+    device: UsbDevice,
+    pub iface_info: ControlIfaceInfo,
+    pub is_opened: bool,
+}
+
+impl WebUsbControlChannel {
+    pub async fn open(&mut self) -> Result<()> {
+        if !self.is_opened() {
+            claim_interface(&self.device, self.iface_info.iface_number).await?;
+            self.is_opened = true;
+        }
+
+        Ok(())
+    }
+
+    pub async fn close(&mut self) -> Result<()> {
+        if self.is_opened() {
+            release_interface(&self.device, self.iface_info.iface_number).await?;
+            self.is_opened = false;
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_opened(&self) -> bool {
+        self.is_opened
+    }
+
+    pub async fn send(&self, buf: &[u8]) -> Result<usize> {
+        transfer_out(&self.device, self.iface_info.bulk_out_ep, buf).await
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        transfer_in(&self.device, self.iface_info.bulk_in_ep, buf).await
+    }
+
+    pub async fn clear_halt(&mut self) -> Result<()> {
+        clear_halt(&self.device, UsbDirection::In, self.iface_info.bulk_in_ep).await?;
+        clear_halt(&self.device, UsbDirection::Out, self.iface_info.bulk_out_ep).await
+    }
+}
+
+/// `WebUSB` version of `ReceiveChannel`.
+#[derive(Clone, Debug)]
+pub struct WebUsbReceiveChannel {
+    device: UsbDevice,
+    pub iface_info: ReceiveIfaceInfo,
+    pub is_opened: bool,
+}
+
+impl WebUsbReceiveChannel {
+    pub async fn open(&mut self) -> Result<()> {
+        if !self.is_opened() {
+            claim_interface(&self.device, self.iface_info.iface_number).await?;
+            self.is_opened = true;
+        }
+
+        Ok(())
+    }
+
+    pub async fn close(&mut self) -> Result<()> {
+        if self.is_opened() {
+            release_interface(&self.device, self.iface_info.iface_number).await?;
+        }
+
+        self.is_opened = false;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_opened(&self) -> bool {
+        self.is_opened
+    }
+
+    pub async fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        transfer_in(&self.device, self.iface_info.bulk_in_ep, buf).await
+    }
+
+    pub async fn clear_halt(&mut self) -> Result<()> {
+        clear_halt(&self.device, UsbDirection::In, self.iface_info.bulk_in_ep).await
+    }
+
+    fn new(device: UsbDevice, iface_info: ReceiveIfaceInfo) -> Self {
+        Self {
+            device,
+            iface_info,
+            is_opened: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ControlIfaceInfo {
+    pub iface_number: u8,
+    pub bulk_in_ep: u8,
+    pub bulk_out_ep: u8,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReceiveIfaceInfo {
+    pub iface_number: u8,
+    pub bulk_in_ep: u8,
+}
+
+/// Provides `ReadMem` and `WriteMem` commands of U3V control protocol over
+/// [`WebUsbControlChannel`].
+#[derive(Debug)]
+pub struct WebUsbControlHandle {
+    inner: WebUsbControlChannel,
+    /// Request id of the next packet.
+    next_req_id: u16,
+    maximum_cmd_length: u32,
+    maximum_ack_length: u32,
+    /// Buffer for serializing/deserializing a packet.
+    buffer: Vec<u8>,
+}
+
+impl WebUsbControlHandle {
+    #[must_use]
+    pub fn new(inner: WebUsbControlChannel) -> Self {
+        Self {
+            inner,
+            next_req_id: 0,
+            maximum_cmd_length: INITIAL_MAXIMUM_PACKET_LENGTH,
+            maximum_ack_length: INITIAL_MAXIMUM_PACKET_LENGTH,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Opens the control channel and reads maximum packet lengths from SBRM.
+    pub async fn open(&mut self) -> Result<()> {
+        if self.inner.is_opened() {
+            return Ok(());
+        }
+
+        self.inner.open().await?;
+        self.inner.clear_halt().await?;
+
+        let mut buf = [0; 8];
+        self.read(abrm::SBRM_ADDRESS.0, &mut buf).await?;
+        let sbrm_address = u64::from_le_bytes(buf);
+
+        let mut buf = [0; 4];
+        self.read(
+            sbrm_address + sbrm::MAXIMUM_COMMAND_TRANSFER_LENGTH.0,
+            &mut buf,
+        )
+        .await?;
+        self.maximum_cmd_length = u32::from_le_bytes(buf);
+        self.read(
+            sbrm_address + sbrm::MAXIMUM_ACKNOWLEDGE_TRANSFER_LENGTH.0,
+            &mut buf,
+        )
+        .await?;
+        self.maximum_ack_length = u32::from_le_bytes(buf);
+
+        Ok(())
+    }
+
+    pub async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    #[must_use]
+    pub fn is_opened(&self) -> bool {
+        self.inner.is_opened()
+    }
+
+    pub async fn read(&mut self, mut address: u64, buf: &mut [u8]) -> Result<()> {
+        let max_read_len = cmd::ReadMem::maximum_read_length(self.maximum_ack_length as usize);
+        for buf_chunk in buf.chunks_mut(max_read_len as usize) {
+            let read_len: u16 = buf_chunk.len().try_into().unwrap();
+            let cmd = cmd::ReadMem::new(address, read_len).finalize(self.next_req_id);
+            let recv_len = self.transact(&cmd, cmd.maximum_ack_len()).await?;
+
+            let ack: ack::ReadMem = ack::AckPacket::parse(&self.buffer[..recv_len])?.scd_as()?;
+            buf_chunk.copy_from_slice(ack.data);
+            address += u64::from(read_len);
+        }
+
+        Ok(())
+    }
+
+    pub async fn write(&mut self, address: u64, data: &[u8]) -> Result<()> {
+        let cmd = cmd::WriteMem::new(address, data)?;
+        for chunk in cmd.chunks(self.maximum_cmd_length as usize)? {
+            let chunk_data_len = chunk.data_len();
+            let cmd = chunk.finalize(self.next_req_id);
+            let recv_len = self.transact(&cmd, cmd.maximum_ack_len()).await?;
+
+            let ack: ack::WriteMem = ack::AckPacket::parse(&self.buffer[..recv_len])?.scd_as()?;
+            if ack.length as usize != chunk_data_len {
+                return Err(Error::InvalidPacket(
+                    "write mem failed: written length mismatch".into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends a command and receives its ack into the buffer.
+    /// Returns the length of the received ack.
+    async fn transact<T: CommandScd>(
+        &mut self,
+        cmd: &cmd::CommandPacket<T>,
+        ack_len: usize,
+    ) -> Result<usize> {
+        let cmd_len = cmd.cmd_len();
+        let buf_len = std::cmp::max(cmd_len, ack_len);
+        if self.buffer.len() < buf_len {
+            self.buffer.resize(buf_len, 0);
+        }
+        cmd.serialize(self.buffer.as_mut_slice())?;
+        self.inner.send(&self.buffer[..cmd_len]).await?;
+
+        loop {
+            let recv_len = self.inner.recv(&mut self.buffer[..ack_len]).await?;
+            let ack = ack::AckPacket::parse(&self.buffer[..recv_len])?;
+            let status = ack.status();
+            if !status.is_success() {
+                return Err(Error::InvalidPacket(
+                    format!("device returned error status: {:?}", status.kind()).into(),
+                ));
+            }
+            if ack.request_id() != self.next_req_id {
+                return Err(Error::InvalidPacket(
+                    format!(
+                        "request id mismatch: expected {}, but got {}",
+                        self.next_req_id,
+                        ack.request_id()
+                    )
+                    .into(),
+                ));
+            }
+
+            // `WebUSB` transfers have no timeout, so just wait for the next ack after pending.
+            if ack.scd_kind() != ack::ScdKind::Pending {
+                self.next_req_id = self.next_req_id.wrapping_add(1);
+                return Ok(recv_len);
+            }
+        }
+    }
+}
+
+fn usb() -> Result<Usb> {
+    let global = js_sys::global();
+    if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+        Ok(window.navigator().usb())
+    } else if let Some(worker) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
+        Ok(worker.navigator().usb())
+    } else {
+        Err(Error::WebUsb(
+            "WebUSB is not available in this context".into(),
+        ))
+    }
+}
+
+fn find_endpoint(alt: &UsbAlternateInterface, direction: UsbDirection) -> Option<u8> {
+    alt.endpoints().iter().find_map(|ep| {
+        let ep: UsbEndpoint = ep.unchecked_into();
+        if ep.direction() == direction {
+            Some(ep.endpoint_number())
+        } else {
+            None
+        }
+    })
+}
+
+async fn claim_interface(device: &UsbDevice, iface_number: u8) -> Result<()> {
+    JsFuture::from(device.claim_interface(iface_number))
+        .await
+        .map_err(js_err)?;
+    Ok(())
+}
+
+async fn release_interface(device: &UsbDevice, iface_number: u8) -> Result<()> {
+    JsFuture::from(device.release_interface(iface_number))
+        .await
+        .map_err(js_err)?;
+    Ok(())
+}
+
+async fn clear_halt(device: &UsbDevice, direction: UsbDirection, endpoint: u8) -> Result<()> {
+    JsFuture::from(device.clear_halt(direction, endpoint))
+        .await
+        .map_err(js_err)?;
+    Ok(())
+}
+
+async fn transfer_out(device: &UsbDevice, endpoint: u8, buf: &[u8]) -> Result<usize> {
+    let promise = device
+        .transfer_out_with_u8_slice(endpoint, buf)
+        .map_err(js_err)?;
+    let result: UsbOutTransferResult = JsFuture::from(promise)
+        .await
+        .map_err(js_err)?
+        .unchecked_into();
+
+    check_status(result.status())?;
+    Ok(result.bytes_written() as usize)
+}
+
+async fn transfer_in(device: &UsbDevice, endpoint: u8, buf: &mut [u8]) -> Result<usize> {
+    let promise = device.transfer_in(endpoint, buf.len() as u32);
+    let result: UsbInTransferResult = JsFuture::from(promise)
+        .await
+        .map_err(js_err)?
+        .unchecked_into();
+
+    check_status(result.status())?;
+    let data = match result.data() {
+        Some(data) => data,
+        None => return Ok(0),
+    };
+    let len = data.byte_length();
+    if len > buf.len() {
+        return Err(Error::WebUsb("received data overflows the buffer".into()));
+    }
+    Uint8Array::new_with_byte_offset_and_length(
+        &data.buffer(),
+        data.byte_offset() as u32,
+        len as u32,
+    )
+    .copy_to(&mut buf[..len]);
+    Ok(len)
+}
+
+fn check_status(status: UsbTransferStatus) -> Result<()> {
+    match status {
+        UsbTransferStatus::Ok => Ok(()),
+        UsbTransferStatus::Stall => Err(Error::WebUsb("endpoint stalled".into())),
+        UsbTransferStatus::Babble => {
+            Err(Error::WebUsb("device sent more data than expected".into()))
+        }
+        _ => Err(Error::WebUsb("unknown transfer status".into())),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)]
+fn js_err(err: JsValue) -> Error {
+    Error::WebUsb(format!("{:?}", err).into())
+}
@@ -0,0 +1,19 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+#![no_main]
+
+use std::collections::HashMap;
+
+use cameleon_genapi::formula::{self, Expr};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(src) = std::str::from_utf8(data) {
+        if let Ok(expr) = formula::parse(src) {
+            let env: HashMap<&str, Expr> = HashMap::new();
+            let _ = expr.eval(&env);
+        }
+    }
+});
@@ -0,0 +1,23 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+#![no_main]
+
+use cameleon_device::u3v::protocol::{
+    ack::{AckPacket, Pending, ReadMem, ReadMemStacked, WriteMem, WriteMemStacked},
+    event::EventPacket,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(ack) = AckPacket::parse(data) {
+        let _ = ack.scd_as::<ReadMem>();
+        let _ = ack.scd_as::<WriteMem>();
+        let _ = ack.scd_as::<ReadMemStacked>();
+        let _ = ack.scd_as::<WriteMemStacked>();
+        let _ = ack.scd_as::<Pending>();
+    }
+
+    let _ = EventPacket::parse(data);
+});
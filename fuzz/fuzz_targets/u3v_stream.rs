@@ -0,0 +1,25 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+#![no_main]
+
+use cameleon_device::u3v::protocol::stream::{
+    ChunkLeader, ChunkTrailer, ImageExtendedChunkLeader, ImageExtendedChunkTrailer, ImageLeader,
+    ImageTrailer, Leader, Trailer,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(leader) = Leader::parse(data) {
+        let _ = leader.specific_leader_as::<ImageLeader>();
+        let _ = leader.specific_leader_as::<ImageExtendedChunkLeader>();
+        let _ = leader.specific_leader_as::<ChunkLeader>();
+    }
+
+    if let Ok(trailer) = Trailer::parse(data) {
+        let _ = trailer.specific_trailer_as::<ImageTrailer>();
+        let _ = trailer.specific_trailer_as::<ImageExtendedChunkTrailer>();
+        let _ = trailer.specific_trailer_as::<ChunkTrailer>();
+    }
+});
@@ -0,0 +1,215 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Benchmarks of parsing and evaluating `GenApi` nodes.
+//!
+//! Vendor XMLs can't be redistributed, so XMLs with a representative node composition are
+//! generated synthetically. Run with `cargo bench -p cameleon-genapi`.
+
+use std::{collections::HashMap, fmt::Write};
+
+use cameleon_genapi::{
+    builder::GenApiBuilder,
+    formula::{self, Expr},
+    prelude::*,
+    store::{CacheStore, DefaultNodeStore, NodeStore, ValueStore},
+    Device, ValueCtxt,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Number of features in the generated XMLs.
+const FEATURE_COUNTS: &[usize] = &[100, 1000, 5000];
+
+/// A device backed by memory.
+struct MemoryDevice(Vec<u8>);
+
+impl Device for MemoryDevice {
+    fn read_mem(
+        &mut self,
+        address: i64,
+        buf: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let address = address as usize;
+        buf.copy_from_slice(&self.0[address..address + buf.len()]);
+        Ok(())
+    }
+
+    fn write_mem(
+        &mut self,
+        address: i64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let address = address as usize;
+        self.0[address..address + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+/// Generates an XML which has `count` features, each of them is an `Integer` backed by an
+/// `IntReg`, and `SwissKnife` nodes which combine them.
+fn synthetic_xml(count: usize) -> String {
+    let mut xml = String::from(
+        r#"<RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="0"
+          SubMinorVersion="0"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+        <Category Name="Root" NameSpace="Standard">
+"#,
+    );
+    for i in 0..count {
+        writeln!(xml, "<pFeature>Int{}</pFeature>", i).unwrap();
+    }
+    xml.push_str("</Category>\n<Port Name=\"Device\"></Port>\n");
+
+    for i in 0..count {
+        write!(
+            xml,
+            r#"<Integer Name="Int{i}">
+                <ToolTip>Synthetic feature {i}</ToolTip>
+                <pValue>Reg{i}</pValue>
+                <Min>0</Min>
+                <Max>65535</Max>
+            </Integer>
+            <IntReg Name="Reg{i}">
+                <Address>{address:#x}</Address>
+                <Length>4</Length>
+                <AccessMode>RW</AccessMode>
+                <pPort>Device</pPort>
+                <Cachable>WriteThrough</Cachable>
+                <Sign>Unsigned</Sign>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+            <SwissKnife Name="Formula{i}">
+                <pVariable Name="A">Int{i}</pVariable>
+                <pVariable Name="B">Int{next}</pVariable>
+                <Formula>(A + B) * 2 / (B + 1) + (A &gt; B ? A : B)</Formula>
+            </SwissKnife>
+            "#,
+            i = i,
+            next = (i + 1) % count,
+            address = i * 4,
+        )
+        .unwrap();
+    }
+    xml.push_str("</RegisterDescription>\n");
+    xml
+}
+
+fn read_integers<T: ValueStore, U: CacheStore>(
+    names: &[String],
+    ns: &impl NodeStore,
+    vc: &mut ValueCtxt<T, U>,
+    device: &mut MemoryDevice,
+) {
+    for name in names {
+        let nid = ns.id_by_name(name).unwrap();
+        let node = nid.expect_iinteger_kind(ns).unwrap();
+        black_box(node.value(device, ns, vc).unwrap());
+    }
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for &count in FEATURE_COUNTS {
+        let xml = synthetic_xml(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &xml, |b, xml| {
+            b.iter(|| {
+                GenApiBuilder::<DefaultNodeStore>::default()
+                    .build(xml)
+                    .unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup");
+    for &count in FEATURE_COUNTS {
+        let (_, ns, _) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&synthetic_xml(count))
+            .unwrap();
+        let names: Vec<_> = (0..count).map(|i| format!("Int{}", i)).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(count), &names, |b, names| {
+            b.iter(|| {
+                for name in names {
+                    black_box(ns.id_by_name(name).unwrap());
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_formula(c: &mut Criterion) {
+    let mut group = c.benchmark_group("formula");
+    let src = "(A + B) * 2 / (B + 1) + (A > B ? A : B) - SQRT(A * A + B * B)";
+    group.bench_function("parse", |b| b.iter(|| formula::parse(black_box(src))));
+
+    let expr = formula::parse(src).unwrap();
+    let env: HashMap<&str, Expr> = vec![("A", Expr::from(10)), ("B", Expr::from(3))]
+        .into_iter()
+        .collect();
+    group.bench_function("eval", |b| b.iter(|| expr.eval(black_box(&env)).unwrap()));
+
+    let count = 1000;
+    let (_, ns, mut vc) = GenApiBuilder::<DefaultNodeStore>::default()
+        .build(&synthetic_xml(count))
+        .unwrap();
+    let mut device = MemoryDevice(vec![0; count * 4]);
+    let nid = ns.id_by_name("Formula0").unwrap();
+    group.bench_function("swiss_knife", |b| {
+        b.iter(|| {
+            let node = nid.expect_ifloat_kind(&ns).unwrap();
+            black_box(node.value(&mut device, &ns, &mut vc).unwrap())
+        });
+    });
+    group.finish();
+}
+
+fn bench_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read");
+    let count = 1000;
+    let xml = synthetic_xml(count);
+    let names: Vec<_> = (0..count).map(|i| format!("Int{}", i)).collect();
+
+    let (_, ns, mut vc) = GenApiBuilder::<DefaultNodeStore>::default()
+        .build(&xml)
+        .unwrap();
+    let mut device = MemoryDevice(vec![0; count * 4]);
+    // Fill the cache before measurement.
+    read_integers(&names, &ns, &mut vc, &mut device);
+    group.bench_function("cached", |b| {
+        b.iter(|| read_integers(&names, &ns, &mut vc, &mut device));
+    });
+
+    let (_, ns, mut vc) = GenApiBuilder::<DefaultNodeStore>::default()
+        .no_cache()
+        .build(&xml)
+        .unwrap();
+    group.bench_function("uncached", |b| {
+        b.iter(|| read_integers(&names, &ns, &mut vc, &mut device));
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_lookup,
+    bench_formula,
+    bench_read
+);
+criterion_main!(benches);
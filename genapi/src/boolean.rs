@@ -11,7 +11,10 @@ use super::{
     Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::Vec;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BooleanNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -29,16 +32,6 @@ impl BooleanNode {
         self.value
     }
 
-    #[must_use]
-    pub fn on_value(&self) -> i64 {
-        self.on_value
-    }
-
-    #[must_use]
-    pub fn off_value(&self) -> i64 {
-        self.off_value
-    }
-
     #[must_use]
     pub fn p_selected(&self) -> &[NodeId] {
         &self.p_selected
@@ -72,7 +65,11 @@ impl IBoolean for BooleanNode {
             Ok(false)
         } else {
             Err(GenApiError::invalid_node(
-                "the internal integer value cannot be interpreted as boolean".into(),
+                format!(
+                    "the internal integer value `{}` matches neither `OnValue`: `{}` nor `OffValue`: `{}`",
+                    value, self.on_value, self.off_value
+                )
+                .into(),
             ))
         }
     }
@@ -87,11 +84,20 @@ impl IBoolean for BooleanNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
         cx.invalidate_cache_by(self.node_base().id());
         let value = if value { self.on_value } else { self.off_value };
         self.value.set_value(value, device, store, cx)
     }
 
+    fn on_value(&self, _: &impl NodeStore) -> i64 {
+        self.on_value
+    }
+
+    fn off_value(&self, _: &impl NodeStore) -> i64 {
+        self.off_value
+    }
+
     #[tracing::instrument(skip(self, device, store, cx),
                           level = "trace",
                           fields(node = store.name_by_id(self.node_base().id()).unwrap()))]
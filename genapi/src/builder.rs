@@ -5,12 +5,15 @@
 use super::{
     parser,
     store::{
-        CacheSink, DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeData, NodeId,
-        ValueData, ValueId,
+        CacheSink, DefaultCacheStore, DefaultNodeStore, DefaultValueStore, DuplicateNode,
+        DuplicatePolicy, NodeData, NodeId, ValueData, ValueId, ValueInfo,
     },
-    RegisterDescription, ValueCtxt,
+    RegisterDescription, SchemaVersion, ValueCtxt,
 };
 
+#[cfg(feature = "std")]
+use super::store::{LazyCacheStore, LazyNodeStore, LazyValueStore};
+
 #[derive(Default)]
 pub struct GenApiBuilder<T = DefaultNodeStore, U = DefaultValueStore, S = DefaultCacheStore> {
     node_store: T,
@@ -41,6 +44,117 @@ impl<T, U, S> GenApiBuilder<T, U, S> {
         ))
     }
 
+    /// Same as [`Self::build`], but external documents included by `xml` are requested from
+    /// `resolver` and merged into the same stores.
+    pub fn build_with_resolver(
+        mut self,
+        xml: &impl AsRef<str>,
+        resolver: &mut impl parser::DocumentResolver,
+    ) -> BuildResult<T::Store, U::Store, S::Store>
+    where
+        T: NodeStoreBuilder,
+        U: ValueStoreBuilder,
+        S: CacheStoreBuilder,
+    {
+        let reg_desc = parser::parse_with_resolver(
+            xml,
+            resolver,
+            &mut self.node_store,
+            &mut self.value_store,
+            &mut self.cache_store,
+        )?;
+
+        Ok((
+            reg_desc,
+            self.node_store.build(),
+            ValueCtxt::new(self.value_store.build(), self.cache_store.build()),
+        ))
+    }
+
+    /// Same as [`Self::build_with_resolver`], but non-fatal problems found while parsing, e.g.
+    /// skipped unknown elements, are reported to `diagnostics`.
+    pub fn build_with_diagnostics(
+        mut self,
+        xml: &impl AsRef<str>,
+        resolver: &mut impl parser::DocumentResolver,
+        diagnostics: &mut parser::ParseDiagnostics,
+    ) -> BuildResult<T::Store, U::Store, S::Store>
+    where
+        T: NodeStoreBuilder,
+        U: ValueStoreBuilder,
+        S: CacheStoreBuilder,
+    {
+        let reg_desc = parser::parse_with_diagnostics(
+            xml,
+            resolver,
+            diagnostics,
+            &mut self.node_store,
+            &mut self.value_store,
+            &mut self.cache_store,
+        )?;
+
+        Ok((
+            reg_desc,
+            self.node_store.build(),
+            ValueCtxt::new(self.value_store.build(), self.cache_store.build()),
+        ))
+    }
+
+    /// Same as [`Self::build`], but parses top-level nodes in parallel to reduce the latency of
+    /// large description files.
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel(
+        mut self,
+        xml: &impl AsRef<str>,
+    ) -> BuildResult<T::Store, U::Store, S::Store>
+    where
+        T: NodeStoreBuilder,
+        U: ValueStoreBuilder,
+        S: CacheStoreBuilder,
+    {
+        let reg_desc = parser::parse_parallel(
+            xml,
+            &mut self.node_store,
+            &mut self.value_store,
+            &mut self.cache_store,
+        )?;
+
+        Ok((
+            reg_desc,
+            self.node_store.build(),
+            ValueCtxt::new(self.value_store.build(), self.cache_store.build()),
+        ))
+    }
+
+    /// Same as [`Self::build_with_diagnostics`], but parses top-level nodes in parallel.
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel_with_diagnostics(
+        mut self,
+        xml: &impl AsRef<str>,
+        resolver: &mut impl parser::DocumentResolver,
+        diagnostics: &mut parser::ParseDiagnostics,
+    ) -> BuildResult<T::Store, U::Store, S::Store>
+    where
+        T: NodeStoreBuilder,
+        U: ValueStoreBuilder,
+        S: CacheStoreBuilder,
+    {
+        let reg_desc = parser::parse_parallel_with_diagnostics(
+            xml,
+            resolver,
+            diagnostics,
+            &mut self.node_store,
+            &mut self.value_store,
+            &mut self.cache_store,
+        )?;
+
+        Ok((
+            reg_desc,
+            self.node_store.build(),
+            ValueCtxt::new(self.value_store.build(), self.cache_store.build()),
+        ))
+    }
+
     pub fn no_cache(self) -> GenApiBuilder<T, U, CacheSink> {
         GenApiBuilder {
             node_store: self.node_store,
@@ -74,6 +188,35 @@ impl<T, U, S> GenApiBuilder<T, U, S> {
     }
 }
 
+/// Indexes node elements of `xml` and returns stores which parse a node on first access.
+///
+/// Building is faster than [`GenApiBuilder::build`] because element bodies aren't parsed, see
+/// [`LazyNodeStore`] for details.
+#[cfg(feature = "std")]
+pub fn build_lazy(
+    xml: impl Into<String>,
+) -> BuildResult<LazyNodeStore, LazyValueStore, LazyCacheStore> {
+    build_lazy_with_diagnostics(xml, &mut parser::ParseDiagnostics::new())
+}
+
+/// Same as [`build_lazy`], but elements are parsed strictly if `diagnostics` is strict.
+///
+/// Warnings found while indexing are reported to `diagnostics`, and warnings found while parsing
+/// elements on access are collected by [`LazyNodeStore::take_warnings`].
+#[cfg(feature = "std")]
+pub fn build_lazy_with_diagnostics(
+    xml: impl Into<String>,
+    diagnostics: &mut parser::ParseDiagnostics,
+) -> BuildResult<LazyNodeStore, LazyValueStore, LazyCacheStore> {
+    let (reg_desc, node_store, value_store, cache_store) =
+        LazyNodeStore::index(xml.into(), diagnostics)?;
+    Ok((
+        reg_desc,
+        node_store,
+        ValueCtxt::new(value_store, cache_store),
+    ))
+}
+
 pub trait NodeStoreBuilder {
     type Store;
 
@@ -90,6 +233,23 @@ pub trait NodeStoreBuilder {
 
     /// Returns fresh id for each call.
     fn fresh_id(&mut self) -> u32;
+
+    /// Notifies that nodes of a new document are going to be stored.
+    /// Used to report the source documents of duplicated nodes.
+    fn begin_document(&mut self) {}
+
+    /// Returns the policy to resolve nodes which have the same name.
+    fn duplicate_policy(&self) -> DuplicatePolicy {
+        DuplicatePolicy::default()
+    }
+
+    /// Returns nodes which are stored more than once.
+    fn duplicates(&self) -> &[DuplicateNode] {
+        &[]
+    }
+
+    /// Notifies the schema version of the parsed document.
+    fn set_schema_version(&mut self, _version: SchemaVersion) {}
 }
 
 pub trait ValueStoreBuilder {
@@ -103,6 +263,17 @@ pub trait ValueStoreBuilder {
     where
         T: Into<ValueData>,
         U: From<ValueId>;
+
+    /// Store the `ValueData` along with its [`ValueInfo`] and return the corresponding [`ValueId`].
+    ///
+    /// The default implementation discards `info`.
+    fn store_with_info<T, U>(&mut self, data: T, _info: ValueInfo) -> U
+    where
+        T: Into<ValueData>,
+        U: From<ValueId>,
+    {
+        self.store(data)
+    }
 }
 
 pub trait CacheStoreBuilder {
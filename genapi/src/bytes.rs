@@ -0,0 +1,120 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Conversion between register bytes and typed values, used by [`IRegister::read_as`] and
+//! [`IRegister::write_as`].
+//!
+//! [`IRegister::read_as`]: crate::interface::IRegister::read_as
+//! [`IRegister::write_as`]: crate::interface::IRegister::write_as
+
+use core::convert::TryInto;
+
+use super::elem_type::Endianness;
+
+/// A type which can be decoded from register bytes.
+pub trait FromBytes: Sized {
+    /// Number of bytes the type occupies in the register.
+    const SIZE: usize;
+
+    /// Decodes the value from `bytes`.
+    ///
+    /// `bytes.len()` must be same as [`Self::SIZE`].
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self;
+}
+
+/// A type which can be encoded into register bytes.
+pub trait ToBytes {
+    /// Number of bytes the type occupies in the register.
+    const SIZE: usize;
+
+    /// Encodes the value into `buf`.
+    ///
+    /// `buf.len()` must be same as [`Self::SIZE`].
+    fn to_bytes(&self, buf: &mut [u8], endianness: Endianness);
+}
+
+macro_rules! impl_bytes_for_primitive {
+    ($($ty:ty),*) => {
+        $(
+            impl FromBytes for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self {
+                    let bytes = bytes.try_into().unwrap();
+                    match endianness {
+                        Endianness::LE => <$ty>::from_le_bytes(bytes),
+                        Endianness::BE => <$ty>::from_be_bytes(bytes),
+                    }
+                }
+            }
+
+            impl ToBytes for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                fn to_bytes(&self, buf: &mut [u8], endianness: Endianness) {
+                    match endianness {
+                        Endianness::LE => buf.copy_from_slice(&self.to_le_bytes()),
+                        Endianness::BE => buf.copy_from_slice(&self.to_be_bytes()),
+                    }
+                }
+            }
+        )*
+    };
+}
+impl_bytes_for_primitive!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+/// Elements are laid out in order, each of them is decoded with the register endianness.
+impl<T: FromBytes + Copy + Default, const N: usize> FromBytes for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self {
+        let mut arr = [T::default(); N];
+        for (elem, chunk) in arr.iter_mut().zip(bytes.chunks_exact(T::SIZE)) {
+            *elem = T::from_bytes(chunk, endianness);
+        }
+        arr
+    }
+}
+
+/// Elements are laid out in order, each of them is encoded with the register endianness.
+impl<T: ToBytes, const N: usize> ToBytes for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn to_bytes(&self, buf: &mut [u8], endianness: Endianness) {
+        for (elem, chunk) in self.iter().zip(buf.chunks_exact_mut(T::SIZE)) {
+            elem.to_bytes(chunk, endianness);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitive() {
+        let bytes = [0x01, 0x02, 0x03, 0x04];
+        assert_eq!(u32::from_bytes(&bytes, Endianness::LE), 0x0403_0201);
+        assert_eq!(u32::from_bytes(&bytes, Endianness::BE), 0x0102_0304);
+
+        let mut buf = [0; 4];
+        0x0102_0304_u32.to_bytes(&mut buf, Endianness::LE);
+        assert_eq!(buf, [0x04, 0x03, 0x02, 0x01]);
+
+        1.5_f32.to_bytes(&mut buf, Endianness::BE);
+        assert!((f32::from_bytes(&buf, Endianness::BE) - 1.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_array() {
+        let bytes = [0x00, 0x01, 0x00, 0x02];
+        let arr = <[u16; 2]>::from_bytes(&bytes, Endianness::BE);
+        assert_eq!(arr, [1, 2]);
+        assert_eq!(<[u16; 2] as FromBytes>::SIZE, 4);
+
+        let mut buf = [0; 4];
+        [1_u16, 2].to_bytes(&mut buf, Endianness::LE);
+        assert_eq!(buf, [0x01, 0x00, 0x02, 0x00]);
+    }
+}
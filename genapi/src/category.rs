@@ -8,7 +8,10 @@ use super::{
     store::{NodeId, NodeStore},
 };
 
+use crate::compat::{HashSet, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CategoryNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -37,4 +40,22 @@ impl ICategory for CategoryNode {
     fn nodes(&self, _: &impl NodeStore) -> &[NodeId] {
         self.p_features()
     }
+
+    fn features_recursive(&self, store: &impl NodeStore) -> Vec<NodeId> {
+        let mut visited = HashSet::new();
+        visited.insert(self.node_base().id());
+
+        let mut features = vec![];
+        let mut stack: Vec<NodeId> = self.p_features().iter().rev().copied().collect();
+        while let Some(nid) = stack.pop() {
+            if !visited.insert(nid) {
+                continue;
+            }
+            features.push(nid);
+            if let Some(category) = nid.as_icategory_kind(store) {
+                stack.extend(category.nodes(store).iter().rev());
+            }
+        }
+        features
+    }
 }
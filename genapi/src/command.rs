@@ -4,7 +4,7 @@
 
 use super::{
     elem_type::ImmOrPNode,
-    interface::{ICommand, INode},
+    interface::{ICommand, IEnumeration, IEnumerationKind, INode},
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, IntegerId, NodeStore, ValueStore},
@@ -12,6 +12,7 @@ use super::{
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CommandNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -36,6 +37,20 @@ impl CommandNode {
     pub fn polling_time(&self) -> Option<u64> {
         self.polling_time
     }
+
+    /// Returns `PollingTime` to use when waiting for the command to complete.
+    ///
+    /// If the command doesn't specify `PollingTime` and is backed by an enumeration, the
+    /// `PollingTime` of the enumeration is used instead.
+    #[must_use]
+    pub fn effective_polling_time(&self, store: &impl NodeStore) -> Option<u64> {
+        self.polling_time.or_else(|| match self.value {
+            ImmOrPNode::PNode(nid) => match nid.as_ienumeration_kind(store)? {
+                IEnumerationKind::Enumeration(n) => n.polling_time(),
+            },
+            ImmOrPNode::Imm(..) => None,
+        })
+    }
 }
 
 impl INode for CommandNode {
@@ -57,6 +72,7 @@ impl ICommand for CommandNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
         cx.invalidate_cache_by(self.node_base().id());
 
         let value: i64 = self.command_value.value(device, store, cx)?;
@@ -76,6 +92,21 @@ impl ICommand for CommandNode {
             ImmOrPNode::PNode(nid) => nid,
         };
 
+        // An enumeration entry which is not self clearing is never reset by the device, so the
+        // command is regarded as done once it's written.
+        if let Some(e) = node.as_ienumeration_kind(store) {
+            let command_value: i64 = self.command_value.value(device, store, cx)?;
+            let is_self_clearing = e
+                .entries(store)
+                .iter()
+                .map(|nid| nid.expect_enum_entry(store).unwrap())
+                .find(|ent| ent.value() == command_value)
+                .is_none_or(|ent| ent.is_self_clearing());
+            if !is_self_clearing {
+                return Ok(true);
+            }
+        }
+
         cx.invalidate_cache_of(node);
         if IValue::<i64>::is_readable(&node, device, store, cx)? {
             let command_value: i64 = self.command_value.value(device, store, cx)?;
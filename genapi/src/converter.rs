@@ -4,14 +4,19 @@
 
 use super::{
     elem_type::{DisplayNotation, FloatRepresentation, NamedValue, Slope},
-    formula::{Expr, Formula},
-    interface::{IFloat, INode, IncrementMode},
+    formula::{EvaluationResult, Expr, Formula},
+    interface::{IFloat, IInteger, INode, IncrementMode},
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, NodeId, NodeStore, ValueStore},
     utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use alloc::borrow::Cow;
+
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConverterNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -20,7 +25,7 @@ pub struct ConverterNode {
     pub(crate) p_variables: Vec<NamedValue<NodeId>>,
     pub(crate) constants: Vec<NamedValue<f64>>,
     pub(crate) expressions: Vec<NamedValue<Expr>>,
-    pub(crate) formula_to: Formula,
+    pub(crate) formula_to: Option<Formula>,
     pub(crate) formula_from: Formula,
     pub(crate) p_value: NodeId,
     pub(crate) unit: Option<String>,
@@ -47,9 +52,11 @@ impl ConverterNode {
         &self.expressions
     }
 
+    /// Returns `None` if `FormulaTo` is omitted. In that case, a value is written by solving
+    /// `FormulaFrom` inversely.
     #[must_use]
-    pub fn formula_to(&self) -> &Formula {
-        &self.formula_to
+    pub fn formula_to(&self) -> Option<&Formula> {
+        self.formula_to.as_ref()
     }
 
     #[must_use]
@@ -91,6 +98,39 @@ impl ConverterNode {
     pub fn is_linear(&self) -> bool {
         self.is_linear
     }
+
+    /// Finds the value of `pValue` which `FormulaFrom` converts to `value` with bisection over
+    /// `[pValue.Min, pValue.Max]`.
+    fn solve_inverse<T: ValueStore, U: CacheStore>(
+        &self,
+        value: f64,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<EvaluationResult> {
+        let mut var_env =
+            utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions)
+                .collect(device, store, cx)?;
+        let formula_from = &self.formula_from;
+        let mut eval_from = |to: Expr| {
+            var_env.insert("TO", Cow::Owned(to));
+            Ok(formula_from.eval(&var_env)?.as_float())
+        };
+
+        if let Some(node) = self.p_value.as_iinteger_kind(store) {
+            let (min, max) = (node.min(device, store, cx)?, node.max(device, store, cx)?);
+            utils::solve_inverse_int(value, min, max, self.slope, |to| eval_from(to.into()))
+                .map(Into::into)
+        } else if let Some(node) = self.p_value.as_ifloat_kind(store) {
+            let (min, max) = (node.min(device, store, cx)?, node.max(device, store, cx)?);
+            utils::solve_inverse_float(value, min, max, self.slope, |to| eval_from(to.into()))
+                .map(Into::into)
+        } else {
+            Err(GenApiError::invalid_node(
+                "`pValue` must be `IInteger` or `IFloat` to be solved without `FormulaTo`".into(),
+            ))
+        }
+    }
 }
 
 impl INode for ConverterNode {
@@ -118,7 +158,12 @@ impl IFloat for ConverterNode {
         collector.insert("TO", self.p_value(), device, store, cx)?;
         let var_env = collector.collect(device, store, cx)?;
 
-        let eval_result = self.formula_from.eval(&var_env)?;
+        let eval_result = cx.eval_formula(
+            self.node_base().id(),
+            "FormulaFrom",
+            &self.formula_from,
+            &var_env,
+        )?;
         Ok(eval_result.as_float())
     }
 
@@ -132,14 +177,21 @@ impl IFloat for ConverterNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
         cx.invalidate_cache_by(self.node_base().id());
 
-        let mut collector =
-            utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions);
-        collector.insert_imm("FROM", value);
-        let var_env = collector.collect(device, store, cx)?;
-
-        let eval_result = self.formula_to.eval(&var_env)?;
+        let eval_result = if let Some(formula_to) = &self.formula_to {
+            let mut collector = utils::FormulaEnvCollector::new(
+                &self.p_variables,
+                &self.constants,
+                &self.expressions,
+            );
+            collector.insert_imm("FROM", value);
+            let var_env = collector.collect(device, store, cx)?;
+            cx.eval_formula(self.node_base().id(), "FormulaTo", formula_to, &var_env)?
+        } else {
+            self.solve_inverse(value, device, store, cx)?
+        };
         utils::set_eval_result(self.p_value, eval_result, device, store, cx)?;
         Ok(())
     }
@@ -175,6 +227,10 @@ impl IFloat for ConverterNode {
         Ok(None)
     }
 
+    fn valid_value_set(&self, _: &impl NodeStore) -> &[f64] {
+        &[]
+    }
+
     fn representation(&self, _: &impl NodeStore) -> FloatRepresentation {
         self.representation
     }
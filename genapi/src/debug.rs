@@ -0,0 +1,267 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Helpers to visualize the node tree of a `GenApi` description file.
+
+use core::fmt::Write;
+
+use super::{
+    interface::{ICategory, ISelector},
+    store::{NodeData, NodeId, NodeStore},
+};
+
+use crate::compat::{HashSet, String, ToString, Vec};
+
+/// Name of the root category defined by `GenApi` standard.
+const ROOT_CATEGORY: &str = "Root";
+
+/// Returns an indented text tree of categories and features reachable from `Root` category.
+///
+/// A category that appears more than once is expanded only at its first appearance.
+#[must_use]
+pub fn dump_tree(store: &impl NodeStore) -> String {
+    let mut out = String::new();
+    if let Some(root) = store.id_by_name(ROOT_CATEGORY) {
+        let mut visited = HashSet::new();
+        dump_node(store, root, 0, &mut visited, &mut out);
+    }
+    out
+}
+
+/// Returns a graph of the nodes in Graphviz `dot` format.
+///
+/// Category membership is drawn as solid edges, `pInvalidator` as dashed edges from the
+/// invalidated node to the invalidator, and `pSelected` as dotted edges from the selector to the
+/// selected node.
+#[must_use]
+pub fn export_dot(store: &impl NodeStore) -> String {
+    let mut nodes = vec![];
+    store.visit_nodes(|data| nodes.push(data.node_base().id()));
+    nodes.sort_by_key(|nid| nid.name(store).to_string());
+
+    let mut out = String::from("digraph GenApi {\n    rankdir=LR;\n    node [shape=box];\n");
+    for &nid in &nodes {
+        let data = store.node(nid);
+        let shape = match data {
+            NodeData::Category(_) => "folder",
+            NodeData::Port(_) => "cds",
+            _ => "box",
+        };
+        writeln!(
+            out,
+            "    {} [label=\"{}\\n<{}>\", shape={}];",
+            quote(nid.name(store)),
+            escape(nid.name(store)),
+            kind_name(data),
+            shape
+        )
+        .unwrap();
+    }
+
+    for &nid in &nodes {
+        let from = quote(nid.name(store));
+        let data = store.node(nid);
+
+        if let Some(category) = nid.as_icategory_kind(store) {
+            for child in category.nodes(store) {
+                writeln!(out, "    {} -> {};", from, quote(child.name(store))).unwrap();
+            }
+        }
+
+        for invalidator in p_invalidators(data) {
+            writeln!(
+                out,
+                "    {} -> {} [style=dashed, label=\"pInvalidator\"];",
+                from,
+                quote(invalidator.name(store))
+            )
+            .unwrap();
+        }
+
+        if let Some(selector) = nid.as_iselector_kind(store) {
+            for selected in selector.selecting_nodes(store).unwrap_or(&[]) {
+                writeln!(
+                    out,
+                    "    {} -> {} [style=dotted, label=\"pSelected\"];",
+                    from,
+                    quote(selected.name(store))
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn dump_node(
+    store: &impl NodeStore,
+    nid: NodeId,
+    depth: usize,
+    visited: &mut HashSet<NodeId>,
+    out: &mut String,
+) {
+    let indent = "  ".repeat(depth);
+    let data = match store.node_opt(nid) {
+        Some(data) => data,
+        None => {
+            writeln!(out, "{}{} <Missing>", indent, nid.name(store)).unwrap();
+            return;
+        }
+    };
+    write!(out, "{}{} <{}>", indent, nid.name(store), kind_name(data)).unwrap();
+
+    let category = match nid.as_icategory_kind(store) {
+        Some(category) => category,
+        None => {
+            out.push('\n');
+            return;
+        }
+    };
+    if !visited.insert(nid) {
+        out.push_str(" ...\n");
+        return;
+    }
+    out.push('\n');
+
+    for &child in category.nodes(store) {
+        dump_node(store, child, depth + 1, visited, out);
+    }
+}
+
+pub(crate) fn p_invalidators(data: &NodeData) -> Vec<NodeId> {
+    let mut nids = data.node_base().p_invalidators().to_vec();
+    let reg_base = match data {
+        NodeData::IntReg(n) => Some(n.register_base()),
+        NodeData::MaskedIntReg(n) => Some(n.register_base()),
+        NodeData::FloatReg(n) => Some(n.register_base()),
+        NodeData::StringReg(n) => Some(n.register_base()),
+        NodeData::Register(n) => Some(n.register_base()),
+        _ => None,
+    };
+    if let Some(reg_base) = reg_base {
+        for nid in reg_base.p_invalidators() {
+            if !nids.contains(nid) {
+                nids.push(*nid);
+            }
+        }
+    }
+    nids
+}
+
+fn kind_name(data: &NodeData) -> &'static str {
+    match data {
+        NodeData::Node(_) => "Node",
+        NodeData::Category(_) => "Category",
+        NodeData::Integer(_) => "Integer",
+        NodeData::IntReg(_) => "IntReg",
+        NodeData::MaskedIntReg(_) => "MaskedIntReg",
+        NodeData::Boolean(_) => "Boolean",
+        NodeData::Command(_) => "Command",
+        NodeData::Enumeration(_) => "Enumeration",
+        NodeData::EnumEntry(_) => "EnumEntry",
+        NodeData::Float(_) => "Float",
+        NodeData::FloatReg(_) => "FloatReg",
+        NodeData::String(_) => "String",
+        NodeData::StringReg(_) => "StringReg",
+        NodeData::Register(_) => "Register",
+        NodeData::Converter(_) => "Converter",
+        NodeData::IntConverter(_) => "IntConverter",
+        NodeData::SwissKnife(_) => "SwissKnife",
+        NodeData::IntSwissKnife(_) => "IntSwissKnife",
+        NodeData::Port(_) => "Port",
+        NodeData::ConfRom(_) => "ConfRom",
+        NodeData::TextDesc(_) => "TextDesc",
+        NodeData::IntKey(_) => "IntKey",
+        NodeData::AdvFeatureLock(_) => "AdvFeatureLock",
+        NodeData::SmartFeature(_) => "SmartFeature",
+    }
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", escape(name))
+}
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{builder::GenApiBuilder, store::DefaultNodeStore},
+        *,
+    };
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Category Name="Root" NameSpace="Standard">
+                <pFeature>Sub</pFeature>
+                <pFeature>Selector</pFeature>
+            </Category>
+
+            <Category Name="Sub" NameSpace="Standard">
+                <pFeature>Selected</pFeature>
+            </Category>
+
+            <Integer Name="Selector">
+                <Value>0</Value>
+                <pSelected>Selected</pSelected>
+            </Integer>
+
+            <IntReg Name="Selected">
+                <Address>0x10</Address>
+                <Length>4</Length>
+                <pPort>Device</pPort>
+                <pInvalidator>Selector</pInvalidator>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+
+            <Port Name="Device" NameSpace="Standard">
+            </Port>
+
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_dump_tree() {
+        let (_, store, _) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        assert_eq!(
+            dump_tree(&store),
+            "Root <Category>\n  Sub <Category>\n    Selected <IntReg>\n  Selector <Integer>\n"
+        );
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let (_, store, _) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        let dot = export_dot(&store);
+        assert!(dot.starts_with("digraph GenApi {"));
+        assert!(dot.contains("\"Root\" -> \"Sub\";"));
+        assert!(
+            dot.contains("\"Selected\" -> \"Selector\" [style=dashed, label=\"pInvalidator\"];")
+        );
+        assert!(dot.contains("\"Selector\" -> \"Selected\" [style=dotted, label=\"pSelected\"];"));
+    }
+}
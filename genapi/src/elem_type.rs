@@ -3,7 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 #![allow(clippy::upper_case_acronyms)]
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 use super::{
     ivalue::IValue,
@@ -11,13 +11,18 @@ use super::{
     Device, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NameSpace {
     Standard,
     Custom,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Ordered from the most to the least visible, i.e. `Beginner < Expert < Guru < Invisible`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Visibility {
     Beginner,
     Expert,
@@ -26,6 +31,7 @@ pub enum Visibility {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MergePriority {
     High,
     Mid,
@@ -33,6 +39,7 @@ pub enum MergePriority {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AccessMode {
     RO,
     WO,
@@ -40,6 +47,7 @@ pub enum AccessMode {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ImmOrPNode<T> {
     Imm(T),
     PNode(NodeId),
@@ -62,6 +70,7 @@ impl<T> ImmOrPNode<T> {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IntegerRepresentation {
     Linear,
     Logarithmic,
@@ -98,6 +107,7 @@ impl IntegerRepresentation {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FloatRepresentation {
     Linear,
     Logarithmic,
@@ -105,6 +115,7 @@ pub enum FloatRepresentation {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Slope {
     Increasing,
     Decreasing,
@@ -113,6 +124,7 @@ pub enum Slope {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayNotation {
     Automatic,
     Fixed,
@@ -120,6 +132,7 @@ pub enum DisplayNotation {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StandardNameSpace {
     None,
     IIDC,
@@ -129,6 +142,7 @@ pub enum StandardNameSpace {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CachingMode {
     /// Allow to caching on read/write.
     WriteThrough,
@@ -139,6 +153,7 @@ pub enum CachingMode {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NamedValue<T> {
     pub(crate) name: String,
     pub(crate) value: T,
@@ -162,6 +177,7 @@ impl<T> NamedValue<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueKind<T> {
     Value(T),
     PValue(PValue<T>),
@@ -198,6 +214,7 @@ impl<T> ValueKind<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PValue<T> {
     pub(crate) p_value: NodeId,
     pub(crate) p_value_copies: Vec<NodeId>,
@@ -217,6 +234,7 @@ impl<T> PValue<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PIndex<T> {
     pub(crate) p_index: NodeId,
     pub(crate) value_indexed: Vec<ValueIndexed<T>>,
@@ -244,6 +262,7 @@ impl<T> PIndex<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueIndexed<T> {
     pub(crate) index: i64,
     pub(crate) indexed: ImmOrPNode<T>,
@@ -265,6 +284,7 @@ impl<T> ValueIndexed<T> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AddressKind {
     Address(ImmOrPNode<i64>),
     IntSwissKnife(NodeId),
@@ -287,6 +307,7 @@ impl AddressKind {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegPIndex {
     pub(crate) offset: Option<ImmOrPNode<i64>>,
     pub(crate) p_index: NodeId,
@@ -320,18 +341,21 @@ impl RegPIndex {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Endianness {
     LE,
     BE,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Sign {
     Signed,
     Unsigned,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BitMask {
     SingleBit(u64),
     Range { lsb: u64, msb: u64 },
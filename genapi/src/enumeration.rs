@@ -11,7 +11,10 @@ use super::{
     Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumerationNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -38,6 +41,40 @@ impl EnumerationNode {
     pub fn polling_time(&self) -> Option<u64> {
         self.polling_time
     }
+
+    /// Writes the value of `entry`, which must be one of the entries of the node.
+    ///
+    /// Entries which are not implemented or not available at the moment are rejected.
+    fn set_entry<T: ValueStore, U: CacheStore>(
+        &self,
+        entry: &EnumEntryNode,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
+        if !entry.is_settable(device, store, cx)? {
+            let mut valid = Vec::new();
+            for nid in self.entries(store) {
+                let ent = nid.expect_enum_entry(store).unwrap();
+                if ent.is_settable(device, store, cx)? {
+                    valid.push(ent.symbolic());
+                }
+            }
+            return Err(GenApiError::invalid_data(
+                format!(
+                    "entry `{}` of `{}` is not available now, valid entries are [{}]",
+                    entry.symbolic(),
+                    store.name_by_id(self.node_base().id()).unwrap(),
+                    valid.join(", ")
+                )
+                .into(),
+            ));
+        }
+
+        cx.invalidate_cache_by(self.node_base().id());
+        self.value.set_value(entry.value(), device, store, cx)
+    }
 }
 
 impl INode for EnumerationNode {
@@ -103,7 +140,7 @@ impl IEnumeration for EnumerationNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
-        let value = self
+        let entry = self
             .entries(store)
             .iter()
             .map(|nid| nid.expect_enum_entry(store).unwrap())
@@ -115,10 +152,9 @@ impl IEnumeration for EnumerationNode {
                     store.name_by_id(self.node_base().id()).unwrap()}
                     .into(),
                 )
-            })?
-            .value();
+            })?;
 
-        self.set_entry_by_value(value, device, store, cx)
+        self.set_entry(entry, device, store, cx)
     }
 
     fn set_entry_by_value<T: ValueStore, U: CacheStore>(
@@ -128,18 +164,18 @@ impl IEnumeration for EnumerationNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
-        if !self
+        let entry = self
             .entries(store)
             .iter()
             .map(|nid| nid.expect_enum_entry(store).unwrap())
-            .any(|ent| ent.value() == value)
-        {
-            return Err(GenApiError::invalid_data(
-                format!("not found entry with the value `{}`", value).into(),
-            ));
-        };
-        cx.invalidate_cache_by(self.node_base().id());
-        self.value.set_value(value, device, store, cx)
+            .find(|ent| ent.value() == value)
+            .ok_or_else(|| {
+                GenApiError::invalid_data(
+                    format!("not found entry with the value `{}`", value).into(),
+                )
+            })?;
+
+        self.set_entry(entry, device, store, cx)
     }
 
     #[tracing::instrument(skip(self, device, store, cx),
@@ -176,6 +212,7 @@ impl ISelector for EnumerationNode {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumEntryNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -234,6 +271,16 @@ impl EnumEntryNode {
     ) -> GenApiResult<bool> {
         self.elem_base.is_available(device, store, cx)
     }
+
+    /// Returns `true` if the entry is both implemented and available.
+    fn is_settable<T: ValueStore, U: CacheStore>(
+        &self,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<bool> {
+        Ok(self.is_implemented(device, store, cx)? && self.is_available(device, store, cx)?)
+    }
 }
 
 impl INode for EnumEntryNode {
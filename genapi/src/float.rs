@@ -8,10 +8,13 @@ use super::{
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, FloatId, NodeStore, ValueStore},
-    Device, GenApiResult, ValueCtxt,
+    utils, Device, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -21,6 +24,7 @@ pub struct FloatNode {
     pub(crate) min: ImmOrPNode<FloatId>,
     pub(crate) max: ImmOrPNode<FloatId>,
     pub(crate) inc: Option<ImmOrPNode<f64>>,
+    pub(crate) valid_value_set: Vec<f64>,
     pub(crate) unit: Option<String>,
     pub(crate) representation: FloatRepresentation,
     pub(crate) display_notation: DisplayNotation,
@@ -48,6 +52,12 @@ impl FloatNode {
         self.inc.as_ref()
     }
 
+    /// Returns the sorted list of valid values, empty if the node doesn't restrict its value to a set.
+    #[must_use]
+    pub fn valid_value_set_elem(&self) -> &[f64] {
+        &self.valid_value_set
+    }
+
     #[must_use]
     pub fn unit_elem(&self) -> Option<&str> {
         self.unit.as_deref()
@@ -102,6 +112,15 @@ impl IFloat for FloatNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
+        let value = utils::constrain_float(
+            value,
+            self.min(device, store, cx)?,
+            self.max(device, store, cx)?,
+            self.inc(device, store, cx)?,
+            &self.valid_value_set,
+            cx.value_policy(),
+        )?;
         cx.invalidate_cache_by(self.node_base().id());
         self.value_kind.set_value(value, device, store, cx)
     }
@@ -131,7 +150,11 @@ impl IFloat for FloatNode {
     }
 
     fn inc_mode(&self, _store: &impl NodeStore) -> Option<IncrementMode> {
-        Some(IncrementMode::FixedIncrement)
+        if self.valid_value_set.is_empty() {
+            Some(IncrementMode::FixedIncrement)
+        } else {
+            Some(IncrementMode::ListIncrement)
+        }
     }
 
     #[tracing::instrument(skip(self, device, store, cx),
@@ -146,6 +169,10 @@ impl IFloat for FloatNode {
         self.inc.map(|n| n.value(device, store, cx)).transpose()
     }
 
+    fn valid_value_set(&self, _store: &impl NodeStore) -> &[f64] {
+        self.valid_value_set_elem()
+    }
+
     fn representation(&self, _store: &impl NodeStore) -> FloatRepresentation {
         self.representation_elem()
     }
@@ -10,7 +10,10 @@ use super::{
     utils, Device, GenApiError, GenApiResult, RegisterBase, ValueCtxt,
 };
 
+use crate::compat::String;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FloatRegNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) register_base: RegisterBase,
@@ -135,6 +138,10 @@ impl IFloat for FloatRegNode {
         Ok(None)
     }
 
+    fn valid_value_set(&self, _: &impl NodeStore) -> &[f64] {
+        &[]
+    }
+
     fn representation(&self, _: &impl NodeStore) -> FloatRepresentation {
         self.representation
     }
@@ -251,4 +258,8 @@ impl IRegister for FloatRegNode {
     ) -> GenApiResult<i64> {
         self.register_base().length(device, store, cx)
     }
+
+    fn endianness(&self) -> Endianness {
+        self.endianness
+    }
 }
@@ -8,13 +8,26 @@
     clippy::cast_possible_truncation
 )]
 
-use std::{borrow::Borrow, collections::HashMap, fmt, hash::Hash, str::FromStr};
+use core::{borrow::Borrow, fmt, hash::Hash, str::FromStr};
 
 use tracing::debug;
 
-use super::{GenApiError, GenApiResult};
+use super::{
+    parser::{ParseError, ParseResult},
+    store::NodeId,
+    GenApiError, GenApiResult,
+};
+
+#[cfg(not(feature = "std"))]
+use crate::compat::FloatExt;
+use crate::compat::{Box, HashMap, String, ToString, Vec};
+
+/// Maximum nesting depth of parentheses and unary operators, deeper formulas are rejected to
+/// avoid stack overflow.
+const MAX_NESTING_DEPTH: usize = 32;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Formula {
     pub(crate) expr: Expr,
 }
@@ -32,9 +45,51 @@ impl Formula {
     {
         self.expr.eval(var_env)
     }
+
+    /// Same as [`Self::eval`], but records variable resolutions and intermediate results to
+    /// `trace`.
+    pub fn eval_traced<K, V>(
+        &self,
+        var_env: &HashMap<K, V>,
+        trace: &mut Vec<TraceStep>,
+    ) -> GenApiResult<EvaluationResult>
+    where
+        K: Borrow<str> + Eq + Hash + fmt::Debug,
+        V: Borrow<Expr> + fmt::Debug,
+    {
+        self.expr.eval_traced(var_env, trace)
+    }
 }
 
+/// A step of a traced formula evaluation.
 #[derive(Debug, Clone, PartialEq)]
+pub enum TraceStep {
+    /// A variable is resolved to `value`.
+    Variable {
+        name: String,
+        value: EvaluationResult,
+    },
+    /// An operation or a conditional expression is evaluated to `value`. Steps of its operands are
+    /// recorded before this step.
+    Intermediate { expr: Expr, value: EvaluationResult },
+}
+
+/// Trace of a formula evaluation of a node, recorded while formula tracing of
+/// [`ValueCtxt`](crate::ValueCtxt) is enabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormulaTrace {
+    /// The node which owns the formula.
+    pub node: NodeId,
+    /// Element name of the formula, e.g. `FormulaFrom`.
+    pub formula_name: &'static str,
+    /// Steps in evaluation order.
+    pub steps: Vec<TraceStep>,
+    /// Result of the evaluation. `None` if the evaluation failed.
+    pub result: Option<EvaluationResult>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     BinOp {
         kind: BinOpKind,
@@ -141,28 +196,73 @@ impl Expr {
         K: Borrow<str> + Eq + Hash + fmt::Debug,
         V: Borrow<Expr> + fmt::Debug,
     {
-        match self {
-            Self::BinOp { kind, lhs, rhs } => lhs.eval_binop(*kind, rhs, var_env),
-            Self::UnOp { kind, expr } => expr.eval_unop(*kind, var_env),
+        self.eval_impl(var_env, None)
+    }
+
+    /// Same as [`Self::eval`], but records variable resolutions and intermediate results to
+    /// `trace`.
+    pub fn eval_traced<K, V>(
+        &self,
+        var_env: &HashMap<K, V>,
+        trace: &mut Vec<TraceStep>,
+    ) -> GenApiResult<EvaluationResult>
+    where
+        K: Borrow<str> + Eq + Hash + fmt::Debug,
+        V: Borrow<Expr> + fmt::Debug,
+    {
+        self.eval_impl(var_env, Some(trace))
+    }
+
+    fn eval_impl<K, V>(
+        &self,
+        var_env: &HashMap<K, V>,
+        mut trace: Option<&mut Vec<TraceStep>>,
+    ) -> GenApiResult<EvaluationResult>
+    where
+        K: Borrow<str> + Eq + Hash + fmt::Debug,
+        V: Borrow<Expr> + fmt::Debug,
+    {
+        let res = match self {
+            Self::BinOp { kind, lhs, rhs } => {
+                lhs.eval_binop(*kind, rhs, var_env, trace.as_deref_mut())?
+            }
+            Self::UnOp { kind, expr } => expr.eval_unop(*kind, var_env, trace.as_deref_mut())?,
             Self::If { cond, then, else_ } => {
-                if cond.eval(var_env)?.as_bool() {
-                    then.eval(var_env)
+                if cond.eval_impl(var_env, trace.as_deref_mut())?.as_bool() {
+                    then.eval_impl(var_env, trace.as_deref_mut())?
                 } else {
-                    else_.eval(var_env)
+                    else_.eval_impl(var_env, trace.as_deref_mut())?
+                }
+            }
+            &Self::Integer(i) => return Ok(i.into()),
+            &Self::Float(f) => return Ok(f.into()),
+            Self::Ident(s) => {
+                let value = var_env
+                    .get(s.as_str())
+                    .ok_or_else(|| {
+                        GenApiError::invalid_node(
+                            format!("ident not found in variable env: {} not found", s).into(),
+                        )
+                    })?
+                    .borrow()
+                    .eval_impl(var_env, trace.as_deref_mut())?;
+                if let Some(trace) = trace {
+                    trace.push(TraceStep::Variable {
+                        name: s.clone(),
+                        value,
+                    });
                 }
+                return Ok(value);
             }
-            &Self::Integer(i) => Ok(i.into()),
-            &Self::Float(f) => Ok(f.into()),
-            Self::Ident(s) => var_env
-                .get(s.as_str())
-                .ok_or_else(|| {
-                    GenApiError::invalid_node(
-                        format!("ident not found in variable env: {} not found", s).into(),
-                    )
-                })?
-                .borrow()
-                .eval(var_env),
+        };
+
+        if let Some(trace) = trace {
+            trace.push(TraceStep::Intermediate {
+                expr: self.clone(),
+                value: res,
+            });
         }
+        Ok(res)
     }
 
     fn eval_binop<K, V>(
@@ -170,22 +270,25 @@ impl Expr {
         op: BinOpKind,
         rhs: &Self,
         var_env: &HashMap<K, V>,
+        mut trace: Option<&mut Vec<TraceStep>>,
     ) -> GenApiResult<EvaluationResult>
     where
         K: Borrow<str> + Eq + Hash + fmt::Debug,
         V: Borrow<Expr> + fmt::Debug,
     {
-        use std::ops::{Add, Mul, Rem, Sub};
+        use core::ops::{Add, Mul, Rem, Sub};
 
         Ok(match op {
-            BinOpKind::And => {
-                (self.eval(var_env)?.as_bool() && rhs.eval(var_env)?.as_bool()).into()
-            }
-            BinOpKind::Or => (self.eval(var_env)?.as_bool() || rhs.eval(var_env)?.as_bool()).into(),
+            BinOpKind::And => (self.eval_impl(var_env, trace.as_deref_mut())?.as_bool()
+                && rhs.eval_impl(var_env, trace)?.as_bool())
+            .into(),
+            BinOpKind::Or => (self.eval_impl(var_env, trace.as_deref_mut())?.as_bool()
+                || rhs.eval_impl(var_env, trace)?.as_bool())
+            .into(),
 
             _ => {
-                let lhs = self.eval(var_env)?;
-                let rhs = rhs.eval(var_env)?;
+                let lhs = self.eval_impl(var_env, trace.as_deref_mut())?;
+                let rhs = rhs.eval_impl(var_env, trace)?;
 
                 macro_rules! apply_arithmetic_op {
                     ($fint:ident, $ffloat:ident) => {{
@@ -215,7 +318,14 @@ impl Expr {
                         // e.g. Converter node with `<FormulaFrom>TO/(1&lt;&lt;P1)</FormulaFrom>` where `P1` points to integer node are commonplace.
                         (lhs.as_float() / rhs.as_float()).into()
                     }
-                    BinOpKind::Rem => apply_arithmetic_op!(overflowing_rem, rem),
+                    BinOpKind::Rem => {
+                        if lhs.is_integer() && rhs.is_integer() && rhs.as_integer() == 0 {
+                            return Err(GenApiError::invalid_data(
+                                "remainder with a divisor of zero".into(),
+                            ));
+                        }
+                        apply_arithmetic_op!(overflowing_rem, rem)
+                    }
                     BinOpKind::Pow => {
                         if lhs.is_integer() && rhs.is_integer() && rhs.as_integer() >= 0 {
                             lhs.as_integer()
@@ -255,28 +365,29 @@ impl Expr {
         &self,
         op: UnOpKind,
         var_env: &HashMap<K, V>,
+        trace: Option<&mut Vec<TraceStep>>,
     ) -> GenApiResult<EvaluationResult>
     where
         K: Borrow<str> + Eq + Hash + fmt::Debug,
         V: Borrow<Expr> + fmt::Debug,
     {
-        use std::ops::Neg;
+        use core::ops::Neg;
 
-        let res = self.eval(var_env)?;
+        let res = self.eval_impl(var_env, trace)?;
         macro_rules! apply_op {
-            ($f:ident) => {
+            ($fint:ident, $ffloat:ident) => {
                 match res {
-                    EvaluationResult::Integer(i) => EvaluationResult::from(i.$f()),
-                    EvaluationResult::Float(f) => EvaluationResult::from(f.$f()),
+                    EvaluationResult::Integer(i) => EvaluationResult::from(i.$fint()),
+                    EvaluationResult::Float(f) => EvaluationResult::from(f.$ffloat()),
                 }
             };
         }
 
         Ok(match op {
             UnOpKind::Not => (!res.as_integer()).into(),
-            UnOpKind::Abs => apply_op!(abs),
-            UnOpKind::Sgn => apply_op!(signum),
-            UnOpKind::Neg => apply_op!(neg),
+            UnOpKind::Abs => apply_op!(wrapping_abs, abs),
+            UnOpKind::Sgn => apply_op!(signum, signum),
+            UnOpKind::Neg => apply_op!(wrapping_neg, neg),
             UnOpKind::Sin => res.as_float().sin().into(),
             UnOpKind::Cos => res.as_float().cos().into(),
             UnOpKind::Tan => res.as_float().tan().into(),
@@ -296,6 +407,7 @@ impl Expr {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinOpKind {
     Add,
     Sub,
@@ -319,6 +431,7 @@ pub enum BinOpKind {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnOpKind {
     Not,
     Abs,
@@ -340,27 +453,35 @@ pub enum UnOpKind {
     Round,
 }
 
-#[must_use]
+/// Parses a formula.
+///
+/// Returns [`ParseError::InvalidFormula`] if `s` is malformed.
 #[tracing::instrument(level = "trace")]
-pub fn parse(s: &str) -> Expr {
+pub fn parse(s: &str) -> ParseResult<Expr> {
     debug!("start parsing expression in `formula`");
     let lexer = Lexer::new(s);
-    Parser { lexer }.expr()
+    Parser { lexer, depth: 0 }.expr()
+}
+
+fn invalid_formula(msg: impl Into<String>) -> ParseError {
+    ParseError::InvalidFormula(msg.into())
 }
 
 struct Parser<'a> {
     lexer: Lexer<'a>,
+    /// Current nesting depth of parentheses and unary operators.
+    depth: usize,
 }
 
 macro_rules! parse_binop {
     ($self:ident.$f:ident, ($token:expr, $op:expr) $(,($token_rep:expr, $op_rep:expr))*) => {
         {
-        let mut expr = $self.$f();
+        let mut expr = $self.$f()?;
         loop {
-            let (op_kind, rhs) = if $self.eat(&$token) {
-                ($op, $self.$f())
-            } $(else if $self.eat(&$token_rep) {
-                ($op_rep, $self.$f())
+            let (op_kind, rhs) = if $self.eat(&$token)? {
+                ($op, $self.$f()?)
+            } $(else if $self.eat(&$token_rep)? {
+                ($op_rep, $self.$f()?)
             })* else {
                 break;
             };
@@ -370,49 +491,49 @@ macro_rules! parse_binop {
                 rhs: rhs.into(),
             };
         }
-        expr
+        Ok(expr)
         }
     }
 }
 
 impl<'a> Parser<'a> {
-    fn expr(&mut self) -> Expr {
-        let expr = self.logical_or();
-        if self.eat(&Token::Question) {
-            let then = self.expr();
-            self.expect(&Token::Colon);
-            let else_ = self.expr();
-            Expr::If {
+    fn expr(&mut self) -> ParseResult<Expr> {
+        let expr = self.logical_or()?;
+        if self.eat(&Token::Question)? {
+            let then = self.nested(Self::expr)?;
+            self.expect(&Token::Colon)?;
+            let else_ = self.nested(Self::expr)?;
+            Ok(Expr::If {
                 cond: expr.into(),
                 then: then.into(),
                 else_: else_.into(),
-            }
+            })
         } else {
-            expr
+            Ok(expr)
         }
     }
 
-    fn logical_or(&mut self) -> Expr {
+    fn logical_or(&mut self) -> ParseResult<Expr> {
         parse_binop!(self.logical_and, (Token::DoubleOr, BinOpKind::Or))
     }
 
-    fn logical_and(&mut self) -> Expr {
+    fn logical_and(&mut self) -> ParseResult<Expr> {
         parse_binop!(self.bitwise_or, (Token::DoubleAnd, BinOpKind::And))
     }
 
-    fn bitwise_or(&mut self) -> Expr {
+    fn bitwise_or(&mut self) -> ParseResult<Expr> {
         parse_binop!(self.bitwise_xor, (Token::Or, BinOpKind::BitOr))
     }
 
-    fn bitwise_xor(&mut self) -> Expr {
+    fn bitwise_xor(&mut self) -> ParseResult<Expr> {
         parse_binop!(self.bitwise_and, (Token::Caret, BinOpKind::Xor))
     }
 
-    fn bitwise_and(&mut self) -> Expr {
+    fn bitwise_and(&mut self) -> ParseResult<Expr> {
         parse_binop!(self.eq, (Token::And, BinOpKind::BitAnd))
     }
 
-    fn eq(&mut self) -> Expr {
+    fn eq(&mut self) -> ParseResult<Expr> {
         parse_binop!(
             self.rel,
             (Token::Eq, BinOpKind::Eq),
@@ -420,7 +541,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn rel(&mut self) -> Expr {
+    fn rel(&mut self) -> ParseResult<Expr> {
         parse_binop!(
             self.bit_shift,
             (Token::Lt, BinOpKind::Lt),
@@ -430,7 +551,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn bit_shift(&mut self) -> Expr {
+    fn bit_shift(&mut self) -> ParseResult<Expr> {
         parse_binop!(
             self.term,
             (Token::Shl, BinOpKind::Shl),
@@ -438,7 +559,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn term(&mut self) -> Expr {
+    fn term(&mut self) -> ParseResult<Expr> {
         parse_binop!(
             self.factor,
             (Token::Plus, BinOpKind::Add),
@@ -446,7 +567,7 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn factor(&mut self) -> Expr {
+    fn factor(&mut self) -> ParseResult<Expr> {
         parse_binop!(
             self.unop,
             (Token::Star, BinOpKind::Mul),
@@ -455,53 +576,61 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn unop(&mut self) -> Expr {
-        if self.eat(&Token::Tilde) {
-            let expr = self.unop();
-            Expr::UnOp {
+    fn unop(&mut self) -> ParseResult<Expr> {
+        if self.eat(&Token::Tilde)? {
+            let expr = self.nested(Self::unop)?;
+            Ok(Expr::UnOp {
                 kind: UnOpKind::Not,
                 expr: expr.into(),
-            }
-        } else if self.eat(&Token::Minus) {
-            let expr = self.unop();
-            Expr::UnOp {
+            })
+        } else if self.eat(&Token::Minus)? {
+            let expr = self.nested(Self::unop)?;
+            Ok(Expr::UnOp {
                 kind: UnOpKind::Neg,
                 expr: expr.into(),
-            }
+            })
         } else {
             // Eat unary `+` if exists.
-            self.eat(&Token::Plus);
+            self.eat(&Token::Plus)?;
             self.pow()
         }
     }
 
-    fn pow(&mut self) -> Expr {
-        let expr = self.primary();
-        if self.eat(&Token::DoubleStar) {
-            let rhs = self.unop();
-            Expr::BinOp {
+    fn pow(&mut self) -> ParseResult<Expr> {
+        let expr = self.primary()?;
+        if self.eat(&Token::DoubleStar)? {
+            let rhs = self.nested(Self::unop)?;
+            Ok(Expr::BinOp {
                 kind: BinOpKind::Pow,
                 lhs: expr.into(),
                 rhs: rhs.into(),
-            }
+            })
         } else {
-            expr
+            Ok(expr)
         }
     }
 
-    fn primary(&mut self) -> Expr {
-        if self.eat(&Token::LParen) {
-            let expr = self.expr();
-            self.expect(&Token::RParen);
-            expr
-        } else if let Some(i) = self.next_integer() {
-            Expr::Integer(i)
-        } else if let Some(f) = self.next_float() {
-            Expr::Float(f)
+    fn primary(&mut self) -> ParseResult<Expr> {
+        if self.eat(&Token::LParen)? {
+            let expr = self.nested(Self::expr)?;
+            self.expect(&Token::RParen)?;
+            Ok(expr)
+        } else if let Some(i) = self.next_integer()? {
+            Ok(Expr::Integer(i))
+        } else if let Some(f) = self.next_float()? {
+            Ok(Expr::Float(f))
         } else {
-            let s = self.next_ident().unwrap();
-            if self.eat(&Token::LParen) {
-                let op = match s.as_str() {
+            let s = match self.next_ident()? {
+                Some(s) => s,
+                None => {
+                    return Err(match self.lexer.next()? {
+                        Some(tok) => invalid_formula(format!("unexpected token `{:?}`", tok)),
+                        None => invalid_formula("unexpected end of formula"),
+                    })
+                }
+            };
+            if self.eat(&Token::LParen)? {
+                let op = match s {
                     "NEG" => UnOpKind::Neg,
                     "SIN" => UnOpKind::Sin,
                     "COS" => UnOpKind::Cos,
@@ -518,73 +647,93 @@ impl<'a> Parser<'a> {
                     "FLOOR" => UnOpKind::Floor,
                     "CEIL" => UnOpKind::Ceil,
                     "ROUND" => UnOpKind::Round,
-                    other => panic!("{} is not a keyword or function name", other),
+                    other => {
+                        return Err(invalid_formula(format!(
+                            "`{}` is not a keyword or function name",
+                            other
+                        )))
+                    }
                 };
-                let expr = self.expr();
-                self.expect(&Token::RParen);
-                Expr::UnOp {
+                let expr = self.nested(Self::expr)?;
+                self.expect(&Token::RParen)?;
+                Ok(Expr::UnOp {
                     kind: op,
                     expr: expr.into(),
-                }
+                })
             } else {
-                Expr::Ident(s)
+                Ok(Expr::Ident(s.to_string()))
             }
         }
     }
 
-    fn eat(&mut self, tok: &Token) -> bool {
-        match self.lexer.peek() {
+    /// Parses a nested expression with `f`, returns an error if the formula is nested too deeply.
+    fn nested(&mut self, f: impl FnOnce(&mut Self) -> ParseResult<Expr>) -> ParseResult<Expr> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(invalid_formula("formula is nested too deeply"));
+        }
+        self.depth += 1;
+        let expr = f(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn eat(&mut self, tok: &Token) -> ParseResult<bool> {
+        match self.lexer.peek()? {
             Some(peek) if peek == tok => {
-                self.lexer.next();
-                true
+                self.lexer.next()?;
+                Ok(true)
             }
-            _ => false,
+            _ => Ok(false),
         }
     }
 
-    fn next_integer(&mut self) -> Option<i64> {
-        if let Some(&Token::Integer(i)) = self.lexer.peek() {
-            self.lexer.next();
-            Some(i)
+    fn next_integer(&mut self) -> ParseResult<Option<i64>> {
+        if let Some(&Token::Integer(i)) = self.lexer.peek()? {
+            self.lexer.next()?;
+            Ok(Some(i))
         } else {
-            None
+            Ok(None)
         }
     }
 
-    fn next_float(&mut self) -> Option<f64> {
-        if let Some(&Token::Float(f)) = self.lexer.peek() {
-            self.lexer.next();
-            Some(f)
-        } else if let Some(Token::Ident(s)) = self.lexer.peek() {
-            let f = match s.as_str() {
-                "PI" => std::f64::consts::PI,
-                "E" => std::f64::consts::E,
-                _ => return None,
+    fn next_float(&mut self) -> ParseResult<Option<f64>> {
+        if let Some(&Token::Float(f)) = self.lexer.peek()? {
+            self.lexer.next()?;
+            Ok(Some(f))
+        } else if let Some(&Token::Ident(s)) = self.lexer.peek()? {
+            let f = match s {
+                "PI" => core::f64::consts::PI,
+                "E" => core::f64::consts::E,
+                _ => return Ok(None),
             };
-            self.lexer.next();
-            Some(f)
+            self.lexer.next()?;
+            Ok(Some(f))
         } else {
-            None
+            Ok(None)
         }
     }
 
-    fn next_ident(&mut self) -> Option<String> {
-        if let Some(Token::Ident(s)) = self.lexer.peek() {
-            let s = s.to_string();
-            self.lexer.next();
-            Some(s)
+    fn next_ident(&mut self) -> ParseResult<Option<&'a str>> {
+        if let Some(&Token::Ident(s)) = self.lexer.peek()? {
+            self.lexer.next()?;
+            Ok(Some(s))
         } else {
-            None
+            Ok(None)
         }
     }
 
-    fn expect(&mut self, tok: &Token) {
-        assert!(self.eat(tok))
+    fn expect(&mut self, tok: &Token) -> ParseResult<()> {
+        if self.eat(tok)? {
+            Ok(())
+        } else {
+            Err(invalid_formula(format!("expected `{:?}`", tok)))
+        }
     }
 }
 
+/// A token of formula. Identifiers borrow from the source to avoid allocation while lexing.
 #[derive(Debug, Clone, PartialEq)]
-enum Token {
+enum Token<'a> {
     LParen,
     RParen,
     Plus,
@@ -609,14 +758,14 @@ enum Token {
     Ge,
     Shl,
     Shr,
-    Ident(String),
+    Ident(&'a str),
     Float(f64),
     Integer(i64),
 }
 
 struct Lexer<'a> {
     src: &'a [u8],
-    peek: Option<Token>,
+    peek: Option<Token<'a>>,
     cur: usize,
     peek_char: Option<(char, usize)>,
 }
@@ -632,19 +781,23 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn next(&mut self) -> Option<Token> {
-        self.peek();
-        self.peek.take()
+    fn next(&mut self) -> ParseResult<Option<Token<'a>>> {
+        self.peek()?;
+        Ok(self.peek.take())
     }
 
-    fn peek(&mut self) -> Option<&Token> {
-        if let Some(ref peek) = self.peek {
-            return Some(peek);
+    fn peek(&mut self) -> ParseResult<Option<&Token<'a>>> {
+        if self.peek.is_some() {
+            return Ok(self.peek.as_ref());
         }
 
         while self.eat_char(|c| c.is_whitespace() || c.is_ascii_control()) {}
 
-        self.peek = Some(match self.next_char()? {
+        let c = match self.next_char() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        self.peek = Some(match c {
             '(' => Token::LParen,
             ')' => Token::RParen,
             '+' => Token::Plus,
@@ -701,15 +854,17 @@ impl<'a> Lexer<'a> {
                 let start_pos = self.cur - 1;
                 while self.eat_char(char::is_numeric) {}
                 let end_pos = self.cur;
-                let f = f64::from_str(self.sub_string(start_pos, end_pos)).unwrap();
-                Token::Float(f)
+                Token::Float(parse_number(
+                    self.sub_string(start_pos, end_pos)?,
+                    f64::from_str,
+                )?)
             }
 
             c if c.is_alphabetic() => {
                 let start_pos = self.cur - 1;
                 while self.eat_char(|c| c.is_alphanumeric() || c == '.' || c == '_') {}
                 let end_pos = self.cur;
-                Token::Ident(self.sub_string(start_pos, end_pos).into())
+                Token::Ident(self.sub_string(start_pos, end_pos)?)
             }
 
             c if c.is_numeric() => {
@@ -717,8 +872,8 @@ impl<'a> Lexer<'a> {
                     let start_pos = self.cur;
                     while self.eat_char(|c| c.is_ascii_hexdigit()) {}
                     let end_pos = self.cur;
-                    let i = i64::from_str_radix(self.sub_string(start_pos, end_pos), 16).unwrap();
-                    Token::Integer(i)
+                    let s = self.sub_string(start_pos, end_pos)?;
+                    Token::Integer(parse_number(s, |s| i64::from_str_radix(s, 16))?)
                 } else {
                     let start_pos = self.cur - 1;
                     let mut is_integer = true;
@@ -732,19 +887,19 @@ impl<'a> Lexer<'a> {
                     };
                     while self.eat_char(&mut check_digit) {}
                     let end_pos = self.cur;
-                    let s = self.sub_string(start_pos, end_pos);
+                    let s = self.sub_string(start_pos, end_pos)?;
                     if is_integer {
-                        Token::Integer(i64::from_str(s).unwrap())
+                        Token::Integer(parse_number(s, i64::from_str)?)
                     } else {
-                        Token::Float(f64::from_str(s).unwrap())
+                        Token::Float(parse_number(s, f64::from_str)?)
                     }
                 }
             }
 
-            c => panic!("unexpected character `{}` in formula", c),
+            c => return Err(invalid_formula(format!("unexpected character `{}`", c))),
         });
 
-        self.peek.as_ref()
+        Ok(self.peek.as_ref())
     }
 
     fn next_char(&mut self) -> Option<char> {
@@ -807,55 +962,59 @@ impl<'a> Lexer<'a> {
             .map_or(false, |next| c == *next as char)
     }
 
-    fn sub_string(&self, start_pos: usize, end_pos: usize) -> &str {
-        std::str::from_utf8(&self.src[start_pos..end_pos]).unwrap()
+    fn sub_string(&self, start_pos: usize, end_pos: usize) -> ParseResult<&'a str> {
+        Ok(core::str::from_utf8(&self.src[start_pos..end_pos])?)
     }
 }
 
+fn parse_number<T, E>(s: &str, f: impl FnOnce(&str) -> Result<T, E>) -> ParseResult<T> {
+    f(s).map_err(|_| invalid_formula(format!("invalid number `{}`", s)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_lexer() {
-        let t = Lexer::new("&amp;").next().unwrap();
+        let t = Lexer::new("&amp;").next().unwrap().unwrap();
         assert_eq!(Token::And, t);
 
-        let t = Lexer::new("&lt;").next().unwrap();
+        let t = Lexer::new("&lt;").next().unwrap().unwrap();
         assert_eq!(Token::Lt, t);
 
-        let t = Lexer::new("&gt;").next().unwrap();
+        let t = Lexer::new("&gt;").next().unwrap().unwrap();
         assert_eq!(Token::Gt, t);
 
-        let t = Lexer::new("Foo1.Max").next().unwrap();
-        assert_eq!(Token::Ident("Foo1.Max".into()), t);
+        let t = Lexer::new("Foo1.Max").next().unwrap().unwrap();
+        assert_eq!(Token::Ident("Foo1.Max"), t);
 
-        let t = Lexer::new("0xa").next().unwrap();
+        let t = Lexer::new("0xa").next().unwrap().unwrap();
         assert_eq!(Token::Integer(0xa), t);
 
-        let t = Lexer::new("10").next().unwrap();
+        let t = Lexer::new("10").next().unwrap().unwrap();
         assert_eq!(Token::Integer(10), t);
 
-        let t = Lexer::new("0.1").next().unwrap();
+        let t = Lexer::new("0.1").next().unwrap().unwrap();
         assert!(matches!(t, Token::Float(_)));
 
-        let t = Lexer::new(".1").next().unwrap();
+        let t = Lexer::new(".1").next().unwrap().unwrap();
         assert!(matches!(t, Token::Float(_)));
 
-        let t = Lexer::new("  10 ").next().unwrap();
+        let t = Lexer::new("  10 ").next().unwrap().unwrap();
         assert_eq!(Token::Integer(10), t);
 
         let mut lexer = Lexer::new("&&||<>**>><<");
-        assert_eq!(Token::DoubleAnd, lexer.next().unwrap());
-        assert_eq!(Token::DoubleOr, lexer.next().unwrap());
-        assert_eq!(Token::Ne, lexer.next().unwrap());
-        assert_eq!(Token::DoubleStar, lexer.next().unwrap());
-        assert_eq!(Token::Shr, lexer.next().unwrap());
-        assert_eq!(Token::Shl, lexer.next().unwrap());
+        assert_eq!(Token::DoubleAnd, lexer.next().unwrap().unwrap());
+        assert_eq!(Token::DoubleOr, lexer.next().unwrap().unwrap());
+        assert_eq!(Token::Ne, lexer.next().unwrap().unwrap());
+        assert_eq!(Token::DoubleStar, lexer.next().unwrap().unwrap());
+        assert_eq!(Token::Shr, lexer.next().unwrap().unwrap());
+        assert_eq!(Token::Shl, lexer.next().unwrap().unwrap());
     }
 
     fn test_eval_impl(expr: &str, var_env: &HashMap<&str, Expr>) {
-        let expr = parse(expr);
+        let expr = parse(expr).unwrap();
         assert!(matches!(
             expr.eval(var_env).unwrap(),
             EvaluationResult::Integer(1)
@@ -902,4 +1061,79 @@ mod tests {
         test_eval_impl("ABS(VAR1 + 1 / 4 - 1.25) < EPS", &env);
         test_eval_impl("( EXP = 1 ) ? 1 : 0", &env);
     }
+
+    #[test]
+    fn test_parse_error() {
+        for src in [
+            "",
+            "1 +",
+            "(1 + 2",
+            "FOO(1)",
+            "1 # 2",
+            ".",
+            "0x",
+            "0x10000000000000000",
+            "99999999999999999999",
+            "1.2.3",
+        ] {
+            assert!(
+                matches!(parse(src), Err(ParseError::InvalidFormula(..))),
+                "{}",
+                src
+            );
+        }
+
+        let nested = "(".repeat(MAX_NESTING_DEPTH + 1);
+        assert!(parse(&nested).is_err());
+        let negated = "-".repeat(MAX_NESTING_DEPTH + 1) + "1";
+        assert!(parse(&negated).is_err());
+    }
+
+    #[test]
+    fn test_eval_error() {
+        let env: HashMap<&str, Expr> = HashMap::new();
+        assert!(parse("1 % 0").unwrap().eval(&env).is_err());
+        assert!(matches!(
+            parse("ABS(-9223372036854775807 - 1)")
+                .unwrap()
+                .eval(&env)
+                .unwrap(),
+            EvaluationResult::Integer(i64::MIN)
+        ));
+    }
+
+    #[test]
+    fn test_eval_traced() {
+        let mut env = HashMap::new();
+        env.insert("TO", Expr::Integer(100));
+        env.insert("SCALE", parse("2 * 2").unwrap());
+
+        let mut trace = vec![];
+        let res = parse("TO / SCALE + 1")
+            .unwrap()
+            .eval_traced(&env, &mut trace)
+            .unwrap();
+        assert_eq!(res, EvaluationResult::Float(26.0));
+
+        // `TO`, `2 * 2`, `SCALE`, `TO / SCALE` and the whole expression.
+        assert_eq!(trace.len(), 5);
+        assert_eq!(
+            trace[0],
+            TraceStep::Variable {
+                name: "TO".into(),
+                value: EvaluationResult::Integer(100)
+            }
+        );
+        assert_eq!(
+            trace[2],
+            TraceStep::Variable {
+                name: "SCALE".into(),
+                value: EvaluationResult::Integer(4)
+            }
+        );
+        assert!(matches!(
+            trace[4],
+            TraceStep::Intermediate { value, .. } if value == res
+        ));
+    }
 }
@@ -11,7 +11,10 @@ use super::{
     utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntConverterNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -100,7 +103,12 @@ impl IInteger for IntConverterNode {
         collector.insert("TO", self.p_value(), device, store, cx)?;
         let var_env = collector.collect(device, store, cx)?;
 
-        let eval_result = self.formula_from.eval(&var_env)?;
+        let eval_result = cx.eval_formula(
+            self.node_base().id(),
+            "FormulaFrom",
+            &self.formula_from,
+            &var_env,
+        )?;
         Ok(eval_result.as_integer())
     }
 
@@ -114,6 +122,7 @@ impl IInteger for IntConverterNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
         cx.invalidate_cache_by(self.node_base().id());
 
         let mut collector =
@@ -121,7 +130,12 @@ impl IInteger for IntConverterNode {
         collector.insert_imm("FROM", value);
         let var_env = collector.collect(device, store, cx)?;
 
-        let eval_result = self.formula_to.eval(&var_env)?;
+        let eval_result = cx.eval_formula(
+            self.node_base().id(),
+            "FormulaTo",
+            &self.formula_to,
+            &var_env,
+        )?;
         utils::set_eval_result(self.p_value, eval_result, device, store, cx)?;
         Ok(())
     }
@@ -2,6 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use core::convert::TryFrom;
+
 use super::{
     elem_type::{Endianness, IntegerRepresentation, Sign},
     interface::{IInteger, INode, IRegister, ISelector, IncrementMode},
@@ -11,7 +13,10 @@ use super::{
     utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntRegNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) register_base: RegisterBase,
@@ -53,6 +58,46 @@ impl IntRegNode {
     pub fn p_selected(&self) -> &[NodeId] {
         &self.p_selected
     }
+
+    /// Returns the value of the register of any length up to 16 bytes, e.g. a `GUID` register.
+    #[tracing::instrument(skip(self, device, store, cx),
+                          level = "trace",
+                          fields(node = store.name_by_id(self.node_base().id()).unwrap()))]
+    pub fn value_i128<T: ValueStore, U: CacheStore>(
+        &self,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<i128> {
+        let nid = self.node_base().id();
+        let reg = self.register_base();
+        reg.with_cache_or_read(nid, device, store, cx, |data| {
+            utils::i128_from_slice(data, self.endianness, self.sign)
+        })
+    }
+
+    /// Sets the value to the register of any length up to 16 bytes. The value is truncated to
+    /// the register length.
+    #[tracing::instrument(skip(self, device, store, cx),
+                          level = "trace",
+                          fields(node = store.name_by_id(self.node_base().id()).unwrap()))]
+    pub fn set_value_i128<T: ValueStore, U: CacheStore>(
+        &self,
+        value: i128,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<()> {
+        let nid = self.node_base().id();
+        cx.invalidate_cache_by(nid);
+
+        let reg = self.register_base();
+        let len = reg.length(device, store, cx)?;
+        let mut buf = vec![0; len as usize];
+        utils::bytes_from_i128(value, &mut buf, self.endianness)?;
+        reg.write_and_cache(nid, &buf, device, store, cx)?;
+        Ok(())
+    }
 }
 
 impl INode for IntRegNode {
@@ -79,7 +124,14 @@ impl IInteger for IntRegNode {
         let nid = self.node_base().id();
         let reg = self.register_base();
         reg.with_cache_or_read(nid, device, store, cx, |data| {
-            utils::int_from_slice(data, self.endianness, self.sign)
+            if data.len() > 8 {
+                let value = utils::i128_from_slice(data, self.endianness, self.sign)?;
+                i64::try_from(value).map_err(|_| {
+                    GenApiError::invalid_data("the value of the register exceeds i64".into())
+                })
+            } else {
+                utils::int_from_slice(data, self.endianness, self.sign)
+            }
         })
     }
 
@@ -99,7 +151,11 @@ impl IInteger for IntRegNode {
         let reg = self.register_base();
         let len = reg.length(device, store, cx)?;
         let mut buf = vec![0; len as usize];
-        utils::bytes_from_int(value, &mut buf, self.endianness, self.sign)?;
+        if len > 8 {
+            utils::bytes_from_i128(value.into(), &mut buf, self.endianness)?;
+        } else {
+            utils::bytes_from_int(value, &mut buf, self.endianness, self.sign)?;
+        }
         reg.write_and_cache(nid, &buf, device, store, cx)?;
         Ok(())
     }
@@ -244,6 +300,10 @@ impl IRegister for IntRegNode {
     ) -> GenApiResult<i64> {
         self.register_base().length(device, store, cx)
     }
+
+    fn endianness(&self) -> Endianness {
+        self.endianness
+    }
 }
 
 impl ISelector for IntRegNode {
@@ -11,7 +11,10 @@ use super::{
     utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntSwissKnifeNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -80,7 +83,8 @@ impl IInteger for IntSwissKnifeNode {
         let var_env =
             utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions)
                 .collect(device, store, cx)?;
-        let eval_result = self.formula.eval(&var_env)?;
+        let eval_result =
+            cx.eval_formula(self.node_base().id(), "Formula", &self.formula, &var_env)?;
         Ok(eval_result.as_integer())
     }
 
@@ -8,10 +8,13 @@ use super::{
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, IntegerId, NodeId, NodeStore, ValueStore},
-    Device, GenApiResult, ValueCtxt,
+    utils, Device, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntegerNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -21,6 +24,7 @@ pub struct IntegerNode {
     pub(crate) min: ImmOrPNode<IntegerId>,
     pub(crate) max: ImmOrPNode<IntegerId>,
     pub(crate) inc: ImmOrPNode<i64>,
+    pub(crate) valid_value_set: Vec<i64>,
     pub(crate) unit: Option<String>,
     pub(crate) representation: IntegerRepresentation,
     pub(crate) p_selected: Vec<NodeId>,
@@ -47,6 +51,12 @@ impl IntegerNode {
         self.inc
     }
 
+    /// Returns the sorted list of valid values, empty if the node doesn't restrict its value to a set.
+    #[must_use]
+    pub fn valid_value_set_elem(&self) -> &[i64] {
+        &self.valid_value_set
+    }
+
     #[must_use]
     pub fn unit_elem(&self) -> Option<&str> {
         self.unit.as_deref()
@@ -96,6 +106,15 @@ impl IInteger for IntegerNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
+        let value = utils::constrain_int(
+            value,
+            self.min(device, store, cx)?,
+            self.max(device, store, cx)?,
+            self.inc(device, store, cx)?,
+            &self.valid_value_set,
+            cx.value_policy(),
+        )?;
         cx.invalidate_cache_by(self.node_base().id());
         self.value_kind().set_value(value, device, store, cx)
     }
@@ -125,7 +144,11 @@ impl IInteger for IntegerNode {
     }
 
     fn inc_mode(&self, _: &impl NodeStore) -> Option<IncrementMode> {
-        Some(IncrementMode::FixedIncrement)
+        if self.valid_value_set.is_empty() {
+            Some(IncrementMode::FixedIncrement)
+        } else {
+            Some(IncrementMode::ListIncrement)
+        }
     }
 
     #[tracing::instrument(skip(self, device, store, cx),
@@ -141,7 +164,7 @@ impl IInteger for IntegerNode {
     }
 
     fn valid_value_set(&self, _: &impl NodeStore) -> &[i64] {
-        &[]
+        self.valid_value_set_elem()
     }
 
     fn representation(&self, _: &impl NodeStore) -> IntegerRepresentation {
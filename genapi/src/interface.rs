@@ -2,19 +2,24 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use core::convert::TryFrom;
+
 use ambassador::{delegatable_trait, Delegate};
 
 use super::{
-    elem_type::{DisplayNotation, FloatRepresentation, IntegerRepresentation},
+    bytes::{FromBytes, ToBytes},
+    elem_type::{DisplayNotation, Endianness, FloatRepresentation, IntegerRepresentation},
     node_base::NodeBase,
     store::{CacheStore, NodeData, NodeId, NodeStore, ValueStore},
-    {Device, GenApiResult, ValueCtxt},
+    {Device, GenApiError, GenApiResult, ValueCtxt},
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Clone, Debug)]
 pub enum IncrementMode {
     FixedIncrement,
-    /// NOTE: `ListIncrement` is not supported in `GenApiSchema Version 1.1` yet.
+    /// Valid values are listed in `ValidValueSet` element.
     ListIncrement,
 }
 
@@ -68,7 +73,8 @@ pub trait IInteger {
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<Option<i64>>;
 
-    /// NOTE: `ValidValueSet` is not supported in `GenApiSchema Version 1.1` yet.
+    /// Returns the sorted list of valid values. Empty if the node doesn't restrict its value
+    /// with `ValidValueSet` element.
     fn valid_value_set(&self, store: &impl NodeStore) -> &[i64];
 
     fn representation(&self, store: &impl NodeStore) -> IntegerRepresentation;
@@ -146,6 +152,10 @@ pub trait IFloat {
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<Option<f64>>;
 
+    /// Returns the sorted list of valid values. Empty if the node doesn't restrict its value
+    /// with `ValidValueSet` element.
+    fn valid_value_set(&self, store: &impl NodeStore) -> &[f64];
+
     fn representation(&self, store: &impl NodeStore) -> FloatRepresentation;
 
     fn unit(&self, store: &impl NodeStore) -> Option<&str>;
@@ -253,6 +263,64 @@ pub trait IEnumeration {
         None
     }
 
+    /// Get [`NodeId`] of enum entry whose `NumericValue` is the nearest to `value` within
+    /// `tolerance`.
+    ///
+    /// The integer value of an entry is used if the entry has no `NumericValue`.
+    fn entry_by_numeric(
+        &self,
+        value: f64,
+        tolerance: f64,
+        store: &impl NodeStore,
+    ) -> Option<NodeId> {
+        let mut nearest: Option<(NodeId, f64)> = None;
+        for nid in self.entries(store) {
+            let ent = nid.expect_enum_entry(store).unwrap(); // Never fail when parse is succeeded.
+            let diff = (ent.numeric_value() - value).abs();
+            if diff <= tolerance && nearest.is_none_or(|(_, nearest_diff)| diff < nearest_diff) {
+                nearest = Some((*nid, diff));
+            }
+        }
+        nearest.map(|(nid, _)| nid)
+    }
+
+    /// Returns `NumericValue` of the current entry.
+    ///
+    /// The integer value of the entry is returned if the entry has no `NumericValue`.
+    fn current_numeric<T: ValueStore, U: CacheStore>(
+        &self,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<f64> {
+        let nid = self.current_entry(device, store, cx)?;
+        Ok(nid.expect_enum_entry(store).unwrap().numeric_value())
+    }
+
+    /// Sets the entry whose `NumericValue` is the nearest to `value` within `tolerance`.
+    fn set_entry_by_numeric<T: ValueStore, U: CacheStore>(
+        &self,
+        value: f64,
+        tolerance: f64,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<()> {
+        let nid = self
+            .entry_by_numeric(value, tolerance, store)
+            .ok_or_else(|| {
+                GenApiError::invalid_data(
+                    format!(
+                        "not found entry with the numeric value `{}` within `{}`",
+                        value, tolerance
+                    )
+                    .into(),
+                )
+            })?;
+        let value = nid.expect_enum_entry(store).unwrap().value();
+        self.set_entry_by_value(value, device, store, cx)
+    }
+
     fn set_entry_by_symbolic<T: ValueStore, U: CacheStore>(
         &self,
         name: &str,
@@ -325,6 +393,12 @@ pub trait IBoolean {
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()>;
 
+    /// Returns the integer value which represents `true`.
+    fn on_value(&self, store: &impl NodeStore) -> i64;
+
+    /// Returns the integer value which represents `false`.
+    fn off_value(&self, store: &impl NodeStore) -> i64;
+
     fn is_readable<T: ValueStore, U: CacheStore>(
         &self,
         device: &mut impl Device,
@@ -377,12 +451,71 @@ pub trait IRegister {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<i64>;
+
+    /// Endianness of the register used by [`IRegister::read_as`] and [`IRegister::write_as`].
+    ///
+    /// Registers without `Endianness` element are little endian.
+    fn endianness(&self) -> Endianness {
+        Endianness::LE
+    }
+
+    /// Read the register as a typed value.
+    ///
+    /// The register length must be same as `V::SIZE`.
+    fn read_as<V: FromBytes, T: ValueStore, U: CacheStore>(
+        &self,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<V> {
+        verify_typed_length(self.length(device, store, cx)?, V::SIZE)?;
+        let mut buf = vec![0; V::SIZE];
+        self.read(&mut buf, device, store, cx)?;
+        Ok(V::from_bytes(&buf, self.endianness()))
+    }
+
+    /// Write a typed value to the register.
+    ///
+    /// The register length must be same as `V::SIZE`.
+    fn write_as<V: ToBytes, T: ValueStore, U: CacheStore>(
+        &self,
+        value: &V,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<()> {
+        verify_typed_length(self.length(device, store, cx)?, V::SIZE)?;
+        let mut buf = vec![0; V::SIZE];
+        value.to_bytes(&mut buf, self.endianness());
+        self.write(&buf, device, store, cx)
+    }
+}
+
+fn verify_typed_length(reg_len: i64, size: usize) -> GenApiResult<()> {
+    if reg_len == size as i64 {
+        Ok(())
+    } else {
+        Err(GenApiError::invalid_buffer(
+            format!(
+                "register length is {} bytes, but the type requires {} bytes",
+                reg_len, size
+            )
+            .into(),
+        ))
+    }
 }
 
 #[delegatable_trait]
 pub trait ICategory {
     /// Return nodes in the category.
     fn nodes(&self, store: &impl NodeStore) -> &[NodeId];
+
+    /// Return all nodes in the subtree of the category in depth-first pre-order, where children
+    /// of a category are ordered as its `pFeature` elements.
+    ///
+    /// Each node appears only once at its first occurrence, and the category itself is never
+    /// included, so a cyclic category in a malformed xml doesn't loop forever.
+    fn features_recursive(&self, store: &impl NodeStore) -> Vec<NodeId>;
 }
 
 #[delegatable_trait]
@@ -509,6 +642,41 @@ impl<'a> IIntegerKind<'a> {
             _ => None,
         }
     }
+
+    /// Same as [`IInteger::value`], but reads a register of any length up to 16 bytes as is if
+    /// the node is `IntReg` or `MaskedIntReg`.
+    pub fn value_i128<T: ValueStore, U: CacheStore>(
+        &self,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<i128> {
+        match self {
+            Self::IntReg(n) => n.value_i128(device, store, cx),
+            Self::MaskedIntReg(n) => n.value_i128(device, store, cx),
+            _ => self.value(device, store, cx).map(i128::from),
+        }
+    }
+
+    /// Same as [`IInteger::set_value`], but writes to a register of any length up to 16 bytes
+    /// if the node is `IntReg` or `MaskedIntReg`.
+    pub fn set_value_i128<T: ValueStore, U: CacheStore>(
+        &self,
+        value: i128,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<()> {
+        match self {
+            Self::IntReg(n) => n.set_value_i128(value, device, store, cx),
+            Self::MaskedIntReg(n) => n.set_value_i128(value, device, store, cx),
+            _ => {
+                let value = i64::try_from(value)
+                    .map_err(|_| GenApiError::invalid_data("the value exceeds i64".into()))?;
+                self.set_value(value, device, store, cx)
+            }
+        }
+    }
 }
 
 #[derive(Delegate, Clone, Copy, Debug)]
@@ -562,6 +730,14 @@ impl<'a> ICommandKind<'a> {
             _ => None,
         }
     }
+
+    /// Returns `PollingTime` in milliseconds to use when waiting for the command to complete.
+    #[must_use]
+    pub fn polling_time(self, store: &impl NodeStore) -> Option<u64> {
+        match self {
+            Self::Command(n) => n.effective_polling_time(store),
+        }
+    }
 }
 
 #[derive(Delegate, Clone, Copy, Debug)]
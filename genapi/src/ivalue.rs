@@ -11,6 +11,8 @@ use super::{
     Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::String;
+
 #[delegatable_trait]
 pub(super) trait IValue<T> {
     fn value<U: ValueStore, S: CacheStore>(
@@ -109,6 +111,7 @@ macro_rules! impl_ivalue_for_vid {
                 _: &impl NodeStore,
                 cx: &mut ValueCtxt<U, S>,
             ) -> GenApiResult<()> {
+                cx.verify_writable()?;
                 cx.value_store_mut().update(*self, value);
                 Ok(())
             }
@@ -154,6 +157,7 @@ impl IValue<String> for StringId {
         _: &impl NodeStore,
         cx: &mut ValueCtxt<U, S>,
     ) -> GenApiResult<()> {
+        cx.verify_writable()?;
         cx.value_store_mut().update(*self, value);
         Ok(())
     }
@@ -11,17 +11,25 @@
     clippy::cast_sign_loss,
     clippy::cast_possible_truncation
 )]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg_attr(not(feature = "std"), macro_use)]
+extern crate alloc;
 
 pub mod builder;
+pub mod debug;
 pub mod elem_type;
 pub mod formula;
 pub mod interface;
 pub mod parser;
+pub mod simulation;
 pub mod store;
 
 mod boolean;
+mod bytes;
 mod category;
 mod command;
+mod compat;
 mod converter;
 mod enumeration;
 mod float;
@@ -44,6 +52,7 @@ mod swiss_knife;
 mod utils;
 
 pub use boolean::BooleanNode;
+pub use bytes::{FromBytes, ToBytes};
 pub use category::CategoryNode;
 pub use command::CommandNode;
 pub use converter::ConverterNode;
@@ -60,17 +69,19 @@ pub use node_base::NodeBase;
 pub use port::PortNode;
 pub use register::RegisterNode;
 pub use register_base::RegisterBase;
-pub use register_description::RegisterDescription;
+pub use register_description::{RegisterDescription, SchemaVersion};
 pub use store::{CacheStore, NodeId, NodeStore, ValueStore};
 pub use string::StringNode;
 pub use string_reg::StringRegNode;
 pub use swiss_knife::SwissKnifeNode;
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use auto_impl::auto_impl;
 use tracing::error;
 
+use compat::{Box, HashMap, String, ToString, Vec};
+
 pub mod prelude {
     pub use super::interface::{
         IBoolean, ICategory, ICommand, IEnumeration, IFloat, IInteger, INode, IPort, IRegister,
@@ -84,24 +95,54 @@ pub trait Device {
         &mut self,
         address: i64,
         buf: &mut [u8],
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    ) -> Result<(), Box<dyn core::error::Error + Send + Sync>>;
 
     fn write_mem(
         &mut self,
         address: i64,
         data: &[u8],
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    ) -> Result<(), Box<dyn core::error::Error + Send + Sync>>;
 }
 
+/// An error type of `GenApi` operations.
+///
+/// Errors returned through the node accessors of higher level crates may be wrapped in
+/// [`GenApiError::Node`] to tell which node failed. Use [`GenApiError::kind`] or
+/// [`GenApiError::root_cause`] instead of matching the variants directly to handle an error
+/// regardless of the node context.
 #[derive(Debug, thiserror::Error)]
 pub enum GenApiError {
-    #[error("device I/O error: {0}")]
-    Device(Box<dyn std::error::Error + Send + Sync>),
+    /// An error occurred while accessing the register of the device.
+    #[error("device I/O error at address {address:#x}: {source}")]
+    Device {
+        /// Address of the register.
+        address: i64,
+        /// The error returned from the device.
+        #[source]
+        source: Box<dyn core::error::Error + Send + Sync>,
+    },
+
+    /// An error occurred while operating on the node.
+    #[error("{name}: {source}")]
+    Node {
+        /// Id of the node.
+        id: NodeId,
+        /// Name of the node.
+        name: String,
+        /// The cause of the error.
+        #[source]
+        source: Box<GenApiError>,
+    },
 
     /// The node is not writable.
     #[error("attempt to write a value to non writable node")]
     NotWritable,
 
+    /// The node is temporarily locked, e.g. transport layer parameters are locked by
+    /// `TLParamsLocked` while streaming.
+    #[error("access denied: {0}")]
+    AccessDenied(Cow<'static, str>),
+
     /// Invalid node.
     #[error("invalid node: {0}")]
     InvalidNode(Cow<'static, str>),
@@ -119,11 +160,151 @@ pub enum GenApiError {
     /// Invalid buffer.
     #[error("invalid buffer: {0}")]
     InvalidBuffer(Cow<'static, str>),
+
+    /// Try to write a string which the string node can't hold.
+    #[error("invalid string: {0}")]
+    InvalidString(StringError),
+}
+
+/// Kind of [`GenApiError`], which doesn't change when the node context is attached to the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GenApiErrorKind {
+    /// See [`GenApiError::Device`].
+    Device,
+    /// See [`GenApiError::NotWritable`].
+    NotWritable,
+    /// See [`GenApiError::AccessDenied`].
+    AccessDenied,
+    /// See [`GenApiError::InvalidNode`].
+    InvalidNode,
+    /// See [`GenApiError::InvalidData`].
+    InvalidData,
+    /// See [`GenApiError::ChunkDataMissing`].
+    ChunkDataMissing,
+    /// See [`GenApiError::InvalidBuffer`].
+    InvalidBuffer,
+    /// See [`GenApiError::InvalidString`].
+    InvalidString,
+}
+
+/// Reason why a string can't be written to a string node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum StringError {
+    /// The string is longer than the maximum length of the node.
+    #[error("the length {length} exceeds the maximum length {max_length}")]
+    TooLong {
+        /// Length of the string in bytes.
+        length: usize,
+        /// Maximum length of the node in bytes.
+        max_length: usize,
+    },
+
+    /// The string contains a non ASCII character.
+    #[error("non ASCII character at byte {position}")]
+    NonAscii {
+        /// Byte offset of the character.
+        position: usize,
+    },
+
+    /// The string contains a NUL character, which would terminate the string in the register.
+    #[error("NUL character at byte {position}")]
+    ContainsNul {
+        /// Byte offset of the character.
+        position: usize,
+    },
+}
+
+impl StringError {
+    /// Checks that `value` can be written to a string node whose maximum length is
+    /// `max_length` bytes.
+    pub fn check(value: &str, max_length: usize) -> Result<(), Self> {
+        if let Some(position) = value.bytes().position(|b| !b.is_ascii()) {
+            return Err(Self::NonAscii { position });
+        }
+        if let Some(position) = value.bytes().position(|b| b == 0) {
+            return Err(Self::ContainsNul { position });
+        }
+        if value.len() > max_length {
+            return Err(Self::TooLong {
+                length: value.len(),
+                max_length,
+            });
+        }
+        Ok(())
+    }
 }
 
 impl GenApiError {
-    fn device(inner: Box<dyn std::error::Error + Send + Sync>) -> Self {
-        let err = GenApiError::Device(inner);
+    /// Attaches the node to the error as context.
+    ///
+    /// If the error already has a node context, the error is returned as is so that the
+    /// innermost node where the error occurred is kept.
+    #[must_use]
+    pub fn with_node(self, id: NodeId, store: &impl NodeStore) -> Self {
+        match self {
+            Self::Node { .. } => self,
+            _ => Self::Node {
+                id,
+                name: store.name_by_id(id).unwrap_or_default().to_string(),
+                source: Box::new(self),
+            },
+        }
+    }
+
+    /// Returns the innermost error stripping the node context.
+    #[must_use]
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            Self::Node { source, .. } => source.root_cause(),
+            _ => self,
+        }
+    }
+
+    /// Returns the kind of the innermost error.
+    ///
+    /// # Examples
+    /// ```
+    /// use cameleon_genapi::{GenApiError, GenApiErrorKind};
+    ///
+    /// fn is_locked(err: &GenApiError) -> bool {
+    ///     err.kind() == GenApiErrorKind::AccessDenied
+    /// }
+    /// ```
+    #[must_use]
+    pub fn kind(&self) -> GenApiErrorKind {
+        match self {
+            Self::Device { .. } => GenApiErrorKind::Device,
+            Self::Node { source, .. } => source.kind(),
+            Self::NotWritable => GenApiErrorKind::NotWritable,
+            Self::AccessDenied(_) => GenApiErrorKind::AccessDenied,
+            Self::InvalidNode(_) => GenApiErrorKind::InvalidNode,
+            Self::InvalidData(_) => GenApiErrorKind::InvalidData,
+            Self::ChunkDataMissing => GenApiErrorKind::ChunkDataMissing,
+            Self::InvalidBuffer(_) => GenApiErrorKind::InvalidBuffer,
+            Self::InvalidString(_) => GenApiErrorKind::InvalidString,
+        }
+    }
+
+    /// Returns the id of the node where the error occurred if known.
+    #[must_use]
+    pub fn node_id(&self) -> Option<NodeId> {
+        match self {
+            Self::Node { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Returns the address of the register where the error occurred if known.
+    #[must_use]
+    pub fn address(&self) -> Option<i64> {
+        match self.root_cause() {
+            Self::Device { address, .. } => Some(*address),
+            _ => None,
+        }
+    }
+
+    fn device(address: i64, source: Box<dyn core::error::Error + Send + Sync>) -> Self {
+        let err = GenApiError::Device { address, source };
         error!("{}", err);
         err
     }
@@ -134,6 +315,12 @@ impl GenApiError {
         err
     }
 
+    fn access_denied(inner: Cow<'static, str>) -> Self {
+        let err = GenApiError::AccessDenied(inner);
+        error!("{}", err);
+        err
+    }
+
     fn invalid_node(inner: Cow<'static, str>) -> Self {
         let err = GenApiError::InvalidNode(inner);
         error!("{}", err);
@@ -157,14 +344,49 @@ impl GenApiError {
         error!("{}", err);
         err
     }
+
+    fn invalid_string(inner: StringError) -> Self {
+        let err = GenApiError::InvalidString(inner);
+        error!("{}", err);
+        err
+    }
 }
 
-pub type GenApiResult<T> = std::result::Result<T, GenApiError>;
+pub type GenApiResult<T> = core::result::Result<T, GenApiError>;
+
+/// Policy applied to a value passed to `IInteger::set_value` or `IFloat::set_value` when the value
+/// violates `Min`, `Max`, `Inc` or `ValidValueSet` constraints of the node.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValuePolicy {
+    /// Returns [`GenApiError::InvalidData`] on any violation. This is the behavior of the
+    /// GenICam reference implementation.
+    #[default]
+    Strict,
+    /// Clamps the value to the `[min, max]` range, then rounds it down to the nearest valid value.
+    RoundDown,
+    /// Clamps the value to the `[min, max]` range, then rounds it to the nearest valid value.
+    RoundNearest,
+    /// Clamps the value to the `[min, max]` range. Other violations are errors as [`Self::Strict`].
+    Clamp,
+}
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueCtxt<T, U> {
     pub value_store: T,
     pub cache_store: U,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub value_policy: ValuePolicy,
+    /// If `true`, all writes are rejected with [`GenApiError::AccessDenied`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_only: bool,
+    /// Recorded formula evaluations. `None` unless formula tracing is enabled.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub formula_traces: Option<Vec<formula::FormulaTrace>>,
+    /// Chunk data attached to the context, keyed by the chunk id.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub chunk_data: HashMap<u64, Vec<u8>>,
 }
 
 impl<T, U> ValueCtxt<T, U> {
@@ -172,9 +394,119 @@ impl<T, U> ValueCtxt<T, U> {
         Self {
             value_store,
             cache_store,
+            value_policy: ValuePolicy::default(),
+            read_only: false,
+            formula_traces: None,
+            chunk_data: HashMap::new(),
         }
     }
 
+    #[must_use]
+    pub fn with_value_policy(mut self, value_policy: ValuePolicy) -> Self {
+        self.value_policy = value_policy;
+        self
+    }
+
+    pub fn value_policy(&self) -> ValuePolicy {
+        self.value_policy
+    }
+
+    pub fn set_value_policy(&mut self, value_policy: ValuePolicy) {
+        self.value_policy = value_policy;
+    }
+
+    /// Sets read-only mode. In read-only mode, all writes to nodes are rejected with
+    /// [`GenApiError::AccessDenied`] before reaching the device, and no node is writable.
+    #[must_use]
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Returns [`GenApiError::AccessDenied`] if the context is in read-only mode.
+    pub(crate) fn verify_writable(&self) -> GenApiResult<()> {
+        if self.read_only {
+            Err(GenApiError::access_denied(
+                "the context is read only".into(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Starts recording formula evaluations of `SwissKnife`, `IntSwissKnife`, `Converter` and
+    /// `IntConverter` nodes.
+    pub fn enable_formula_trace(&mut self) {
+        self.formula_traces.get_or_insert_with(Vec::new);
+    }
+
+    /// Stops recording formula evaluations and discards recorded traces.
+    pub fn disable_formula_trace(&mut self) {
+        self.formula_traces = None;
+    }
+
+    /// Returns formula evaluations recorded since the last call. Tracing stays enabled.
+    pub fn take_formula_traces(&mut self) -> Vec<formula::FormulaTrace> {
+        self.formula_traces
+            .as_mut()
+            .map(core::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Attaches chunk data of `chunk_id`, which is accessed through `Port` nodes whose `ChunkID`
+    /// is `chunk_id`. Chunk data previously attached with the same id is replaced.
+    pub fn attach_chunk(&mut self, chunk_id: u64, data: Vec<u8>) {
+        self.chunk_data.insert(chunk_id, data);
+    }
+
+    /// Returns chunk data of `chunk_id` if attached.
+    pub fn chunk(&self, chunk_id: u64) -> Option<&[u8]> {
+        self.chunk_data.get(&chunk_id).map(Vec::as_slice)
+    }
+
+    /// Detaches all chunk data.
+    ///
+    /// Data read through `Port` nodes with `CacheChunkData` remains readable from the cache until
+    /// the cache is invalidated.
+    pub fn detach_chunks(&mut self) {
+        self.chunk_data.clear();
+    }
+
+    /// Evaluates `formula` of `nid`, recording the evaluation if formula tracing is enabled.
+    pub(crate) fn eval_formula<K, V>(
+        &mut self,
+        nid: store::NodeId,
+        formula_name: &'static str,
+        formula: &formula::Formula,
+        var_env: &HashMap<K, V>,
+    ) -> GenApiResult<formula::EvaluationResult>
+    where
+        K: core::borrow::Borrow<str> + Eq + core::hash::Hash + core::fmt::Debug,
+        V: core::borrow::Borrow<formula::Expr> + core::fmt::Debug,
+    {
+        let traces = match &mut self.formula_traces {
+            Some(traces) => traces,
+            None => return formula.eval(var_env),
+        };
+        let mut steps = vec![];
+        let res = formula.eval_traced(var_env, &mut steps);
+        traces.push(formula::FormulaTrace {
+            node: nid,
+            formula_name,
+            steps,
+            result: res.as_ref().ok().copied(),
+        });
+        res
+    }
+
     pub fn value_store(&self) -> &T {
         &self.value_store
     }
@@ -202,7 +534,7 @@ impl<T, U> ValueCtxt<T, U> {
         self.cache_store.cache(nid, address, length, value);
     }
 
-    pub fn get_cache(&self, nid: store::NodeId, address: i64, length: i64) -> Option<&[u8]>
+    pub fn get_cache(&self, nid: store::NodeId, address: i64, length: i64) -> Option<Cow<'_, [u8]>>
     where
         U: store::CacheStore,
     {
@@ -230,3 +562,21 @@ impl<T, U> ValueCtxt<T, U> {
         self.cache_store.clear()
     }
 }
+
+#[cfg(feature = "std")]
+impl ValueCtxt<store::ShardedValueStore, store::ShardedCacheStore> {
+    /// Returns a context which shares the stores with `self`.
+    ///
+    /// Contexts returned by this method can be used on multiple threads at the same time, e.g. to
+    /// read independent nodes concurrently.
+    pub fn share(&self) -> ValueCtxt<&store::ShardedValueStore, &store::ShardedCacheStore> {
+        ValueCtxt {
+            value_store: &self.value_store,
+            cache_store: &self.cache_store,
+            value_policy: self.value_policy,
+            read_only: self.read_only,
+            formula_traces: None,
+            chunk_data: self.chunk_data.clone(),
+        }
+    }
+}
@@ -2,6 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use core::convert::TryFrom;
+
 use super::{
     elem_type::{BitMask, Endianness, IntegerRepresentation, Sign},
     interface::{IInteger, INode, IRegister, ISelector, IncrementMode},
@@ -11,7 +13,10 @@ use super::{
     utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MaskedIntRegNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) register_base: RegisterBase,
@@ -59,6 +64,63 @@ impl MaskedIntRegNode {
     pub fn p_selected(&self) -> &[NodeId] {
         &self.p_selected
     }
+
+    /// Returns the masked value of the register of any length up to 16 bytes.
+    #[tracing::instrument(skip(self, device, store, cx),
+                          level = "trace",
+                          fields(node = store.name_by_id(self.node_base().id()).unwrap()))]
+    pub fn value_i128<T: ValueStore, U: CacheStore>(
+        &self,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<i128> {
+        let nid = self.node_base().id();
+        let reg = self.register_base();
+
+        let reg_value = reg.with_cache_or_read(nid, device, store, cx, |data| {
+            utils::i128_from_slice(data, self.endianness, self.sign)
+        })?;
+
+        let len = reg.length(device, store, cx)? as usize;
+        Ok(self
+            .bit_mask
+            .apply_mask_i128(reg_value, len, self.endianness, self.sign))
+    }
+
+    /// Sets the value to the masked bits of the register of any length up to 16 bytes.
+    #[tracing::instrument(skip(self, device, store, cx),
+                          level = "trace",
+                          fields(node = store.name_by_id(self.node_base().id()).unwrap()))]
+    pub fn set_value_i128<T: ValueStore, U: CacheStore>(
+        &self,
+        value: i128,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<()> {
+        let nid = self.node_base().id();
+        cx.invalidate_cache_by(nid);
+
+        let reg = self.register_base();
+        let old_reg_value = reg.with_cache_or_read(nid, device, store, cx, |data| {
+            utils::i128_from_slice(data, self.endianness, self.sign)
+        })?;
+
+        let length = reg.length(device, store, cx)? as usize;
+        let new_reg_value = self.bit_mask.masked_value_i128(
+            old_reg_value,
+            value,
+            length,
+            self.endianness,
+            self.sign,
+        )?;
+        let mut buf = vec![0; length];
+        utils::bytes_from_i128(new_reg_value, &mut buf, self.endianness)?;
+        reg.write_and_cache(nid, &buf, device, store, cx)?;
+
+        Ok(())
+    }
 }
 
 impl INode for MaskedIntRegNode {
@@ -84,6 +146,12 @@ impl IInteger for MaskedIntRegNode {
     ) -> GenApiResult<i64> {
         let nid = self.node_base().id();
         let reg = self.register_base();
+        if reg.length(device, store, cx)? > 8 {
+            let value = self.value_i128(device, store, cx)?;
+            return i64::try_from(value).map_err(|_| {
+                GenApiError::invalid_data("the masked value of the register exceeds i64".into())
+            });
+        }
 
         // Get register value.
         let reg_value = reg.with_cache_or_read(nid, device, store, cx, |data| {
@@ -110,9 +178,12 @@ impl IInteger for MaskedIntRegNode {
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
         let nid = self.node_base().id();
+        let reg = self.register_base();
+        if reg.length(device, store, cx)? > 8 {
+            return self.set_value_i128(value.into(), device, store, cx);
+        }
         cx.invalidate_cache_by(nid);
 
-        let reg = self.register_base();
         let old_reg_value = reg.with_cache_or_read(nid, device, store, cx, |data| {
             utils::int_from_slice(data, self.endianness, self.sign)
         })?;
@@ -291,6 +362,10 @@ impl IRegister for MaskedIntRegNode {
     ) -> GenApiResult<i64> {
         self.register_base().length(device, store, cx)
     }
+
+    fn endianness(&self) -> Endianness {
+        self.endianness
+    }
 }
 
 impl ISelector for MaskedIntRegNode {
@@ -377,7 +452,7 @@ impl BitMask {
         );
         match sign {
             Sign::Signed => {
-                if msb - lsb == 63 {
+                if msb - lsb >= 63 {
                     i64::MIN
                 } else {
                     let value = 1 << (msb - lsb) as i64;
@@ -393,7 +468,7 @@ impl BitMask {
             self.lsb(reg_byte_len, endianness),
             self.msb(reg_byte_len, endianness),
         );
-        if msb - lsb == 63 {
+        if msb - lsb >= 63 {
             return i64::MAX;
         }
         match sign {
@@ -408,6 +483,69 @@ impl BitMask {
         }
     }
 
+    fn apply_mask_i128(
+        &self,
+        reg_value: i128,
+        reg_byte_len: usize,
+        endianness: Endianness,
+        sign: Sign,
+    ) -> i128 {
+        let mask = self.mask_i128(reg_byte_len, endianness);
+        let (lsb, msb) = (
+            self.lsb(reg_byte_len, endianness),
+            self.msb(reg_byte_len, endianness),
+        );
+        // Shift logically so that the bit 127 isn't extended.
+        let res = ((reg_value & mask) as u128 >> lsb) as i128;
+
+        let width = msb - lsb + 1;
+        match sign {
+            Sign::Signed if width < 128 && (res >> (width - 1)) & 1 == 1 => {
+                // Do sign extension.
+                res | (-1 << width)
+            }
+            _ => res,
+        }
+    }
+
+    fn masked_value_i128(
+        &self,
+        old_reg_value: i128,
+        value: i128,
+        reg_byte_len: usize,
+        endianness: Endianness,
+        sign: Sign,
+    ) -> GenApiResult<i128> {
+        let (lsb, msb) = (
+            self.lsb(reg_byte_len, endianness),
+            self.msb(reg_byte_len, endianness),
+        );
+        let width = msb - lsb + 1;
+        let (min, max) = match sign {
+            Sign::Signed if width < 128 => (-(1 << (width - 1)), (1 << (width - 1)) - 1),
+            Sign::Unsigned if width < 127 => (0, (1 << width) - 1),
+            Sign::Signed => (i128::MIN, i128::MAX),
+            Sign::Unsigned => (0, i128::MAX),
+        };
+        if value > max || value < min {
+            return Err(GenApiError::invalid_data(
+                "given value doesn't fit into the bit range".into(),
+            ));
+        }
+
+        let mask = self.mask_i128(reg_byte_len, endianness);
+        Ok((old_reg_value & !mask) | ((value << lsb) & mask))
+    }
+
+    fn mask_i128(&self, reg_byte_len: usize, endianness: Endianness) -> i128 {
+        let (lsb, msb) = (
+            self.lsb(reg_byte_len, endianness),
+            self.msb(reg_byte_len, endianness),
+        );
+        let width = msb - lsb + 1;
+        ((u128::MAX >> (128 - width)) << lsb) as i128
+    }
+
     fn mask(&self, reg_byte_len: usize, endianness: Endianness) -> i64 {
         let (lsb, msb) = (
             self.lsb(reg_byte_len, endianness),
@@ -535,4 +673,36 @@ mod tests {
             .unwrap();
         assert_eq!(new_value, i64::MIN);
     }
+
+    #[test]
+    fn test_bit_mask_128bit() {
+        let reg_len = 16;
+        let reg_value = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210;
+        let endianness = Endianness::LE;
+
+        let mask = BitMask::Range { lsb: 64, msb: 127 };
+        let sign = Sign::Unsigned;
+        let value = mask.apply_mask_i128(reg_value, reg_len, endianness, sign);
+        assert_eq!(value, 0x0123_4567_89ab_cdef);
+        let new_value = mask
+            .masked_value_i128(reg_value, 0x42, reg_len, endianness, sign)
+            .unwrap();
+        assert_eq!(new_value, 0x42_fedc_ba98_7654_3210);
+        assert!(mask
+            .masked_value_i128(reg_value, 1 << 64, reg_len, endianness, sign)
+            .is_err());
+
+        let mask = BitMask::Range { lsb: 60, msb: 67 };
+        let sign = Sign::Signed;
+        let value = mask.apply_mask_i128(reg_value, reg_len, endianness, sign);
+        assert_eq!(value, -1);
+        let new_value = mask
+            .masked_value_i128(reg_value, -2, reg_len, endianness, sign)
+            .unwrap();
+        assert_eq!(new_value, 0x0123_4567_89ab_cdef_eedc_ba98_7654_3210);
+
+        let mask = BitMask::Range { lsb: 0, msb: 127 };
+        let value = mask.apply_mask_i128(-5, reg_len, endianness, sign);
+        assert_eq!(value, -5);
+    }
 }
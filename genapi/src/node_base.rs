@@ -6,9 +6,11 @@ use super::{
     elem_type::{AccessMode, MergePriority, NameSpace, Visibility},
     store::{CacheStore, NodeId, NodeStore, ValueStore},
     utils::bool_from_id,
-    Device, GenApiResult, ValueCtxt,
+    Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 pub struct NodeBase<'a> {
     pub(crate) attr: &'a NodeAttributeBase,
     pub(crate) elem: &'a NodeElementBase,
@@ -88,11 +90,23 @@ impl<'a> NodeBase<'a> {
         &self.elem.p_errors
     }
 
+    #[must_use]
+    pub fn p_invalidators(&self) -> &'a [NodeId] {
+        &self.elem.p_invalidators
+    }
+
     #[must_use]
     pub fn event_id(&self) -> Option<u64> {
         self.elem.event_id
     }
 
+    /// Returns raw XML of the `Extension` element and unknown child elements of the node, e.g.
+    /// vendor specific elements, in the order they appear in the document.
+    #[must_use]
+    pub fn extensions(&self) -> &'a [String] {
+        &self.elem.extensions
+    }
+
     optional_string_elem_getter! {description}
     optional_string_elem_getter! {tooltip}
     optional_string_elem_getter! {docu_url}
@@ -105,6 +119,7 @@ impl<'a> NodeBase<'a> {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct NodeAttributeBase {
     pub(crate) id: NodeId,
     pub(crate) name_space: NameSpace,
@@ -113,6 +128,7 @@ pub(crate) struct NodeAttributeBase {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct NodeElementBase {
     pub(crate) tooltip: Option<String>,
     pub(crate) description: Option<String>,
@@ -132,6 +148,9 @@ pub(crate) struct NodeElementBase {
     /// `pInvalidator` works only for `Register` kind nodes. It is not used in this crate.
     /// See https://github.com/cameleon-rs/cameleon/issues/138 for more details.
     pub(crate) p_invalidators: Vec<NodeId>,
+    /// Raw XML of the `Extension` element and unknown child elements, which are kept as is so
+    /// that they can be written back.
+    pub(crate) extensions: Vec<String>,
 }
 
 impl NodeElementBase {
@@ -154,6 +173,7 @@ impl NodeElementBase {
     ) -> GenApiResult<bool> {
         Ok(self.is_implemented(device, store, cx)?
             && self.is_available(device, store, cx)?
+            && !cx.is_read_only()
             && !self.is_locked(device, store, cx)?
             && matches!(self.imposed_access_mode, AccessMode::WO | AccessMode::RW))
     }
@@ -168,6 +188,22 @@ impl NodeElementBase {
             .map_or(Ok(false), |nid| bool_from_id(nid, device, store, cx))
     }
 
+    /// Returns [`GenApiError::AccessDenied`] if the node is locked.
+    pub(super) fn verify_unlocked<T: ValueStore, U: CacheStore>(
+        &self,
+        device: &mut impl Device,
+        store: &impl NodeStore,
+        cx: &mut ValueCtxt<T, U>,
+    ) -> GenApiResult<()> {
+        if self.is_locked(device, store, cx)? {
+            Err(GenApiError::access_denied(
+                "the node is locked, transport layer parameters are locked while streaming".into(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     pub(super) fn is_implemented<T: ValueStore, U: CacheStore>(
         &self,
         device: &mut impl Device,
@@ -11,8 +11,9 @@ use crate::{
 };
 
 use super::{
-    elem_name::{BOOLEAN, OFF_VALUE, ON_VALUE, P_SELECTED, STREAMABLE},
-    xml, Parse,
+    elem_name::{BOOLEAN, OFF_VALUE, ON_VALUE, P_SELECTED, STREAMABLE, VALUE},
+    elem_type::{convert_to_bool_opt, convert_to_int},
+    xml, Parse, ParseResult,
 };
 
 impl Parse for BooleanNode {
@@ -22,35 +23,51 @@ impl Parse for BooleanNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `BooleanNode`");
         debug_assert_eq!(node.tag_name(), BOOLEAN);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let value: ImmOrPNode<bool> = node.parse(node_builder, value_builder, cache_builder);
+        // `Value` is either a boolean literal or the raw integer compared with `OnValue` and
+        // `OffValue`.
+        let value: ImmOrPNode<Result<bool, i64>> =
+            if node.peek().is_some_and(|next| next.tag_name() == VALUE) {
+                let text = node.next_text_required()?.view();
+                ImmOrPNode::Imm(match convert_to_bool_opt(&text) {
+                    Some(b) => Ok(b),
+                    None => Err(convert_to_int(&text)?),
+                })
+            } else {
+                ImmOrPNode::PNode(node.parse(node_builder, value_builder, cache_builder)?)
+            };
         let on_value: i64 = node
-            .parse_if(ON_VALUE, node_builder, value_builder, cache_builder)
+            .parse_if(ON_VALUE, node_builder, value_builder, cache_builder)?
             .unwrap_or(1);
         let off_value: i64 = node
-            .parse_if(OFF_VALUE, node_builder, value_builder, cache_builder)
+            .parse_if(OFF_VALUE, node_builder, value_builder, cache_builder)?
             .unwrap_or(0);
-        let p_selected = node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder);
+        let p_selected =
+            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder)?;
 
         let value = match value {
             ImmOrPNode::Imm(imm) => {
-                let i = if imm { on_value } else { off_value };
-                let id = value_builder.store(i);
+                let i = match imm {
+                    Ok(true) => on_value,
+                    Ok(false) => off_value,
+                    Err(raw) => raw,
+                };
+                let id = value_builder.store_with_info(i, node.value_info(VALUE, node_builder));
                 ImmOrPNode::Imm(id)
             }
             ImmOrPNode::PNode(pnode) => ImmOrPNode::PNode(pnode),
         };
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -58,7 +75,7 @@ impl Parse for BooleanNode {
             on_value,
             off_value,
             p_selected,
-        }
+        })
     }
 }
 
@@ -83,8 +100,8 @@ mod tests {
             node.value_elem(),
             ImmOrPNode::PNode(node_builder.get_or_intern("Node"))
         );
-        assert_eq!(node.on_value(), 10);
-        assert_eq!(node.off_value(), 0);
+        assert_eq!(node.on_value, 10);
+        assert_eq!(node.off_value, 0);
     }
 
     #[test]
@@ -101,7 +118,7 @@ mod tests {
         let value1 = value_builder
             .integer_value(node.value_elem().imm().unwrap())
             .unwrap();
-        assert_eq!(value1, node.on_value());
+        assert_eq!(value1, node.on_value);
 
         let xml2 = r#"
             <Boolean Name="TestNode">
@@ -115,6 +132,25 @@ mod tests {
         let value2 = value_builder2
             .integer_value(node.value_elem().imm().unwrap())
             .unwrap();
-        assert_eq!(value2, node.off_value());
+        assert_eq!(value2, node.off_value);
+    }
+
+    #[test]
+    fn test_boolean_node_with_raw_imm() {
+        let xml = r#"
+            <Boolean Name="TestNode">
+                <Value>0x10</Value>
+                <OnValue>0x10</OnValue>
+                <OffValue>0x20</OffValue>
+            </Boolean>
+            "#;
+
+        let (node, _, value_builder, ..): (BooleanNode, _, _, _) = parse_default(xml);
+        let value = value_builder
+            .integer_value(node.value_elem().imm().unwrap())
+            .unwrap();
+        assert_eq!(value, 0x10);
+        assert_eq!(node.on_value, 0x10);
+        assert_eq!(node.off_value, 0x20);
     }
 }
@@ -11,10 +11,10 @@ use crate::{
 
 use super::{
     elem_name::{
-        CONSTANT, CONVERTER, DISPLAY_NOTATION, DISPLAY_PRECISION, EXPRESSION, IS_LINEAR,
-        P_VARIABLE, REPRESENTATION, SLOPE, STREAMABLE, UNIT,
+        CONSTANT, CONVERTER, DISPLAY_NOTATION, DISPLAY_PRECISION, EXPRESSION, FORMULA_TO,
+        IS_LINEAR, P_VARIABLE, REPRESENTATION, SLOPE, STREAMABLE, UNIT,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
 impl Parse for ConverterNode {
@@ -24,28 +24,30 @@ impl Parse for ConverterNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `ConverterNode`");
         debug_assert_eq!(node.tag_name(), CONVERTER);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let p_variables = node.parse_while(P_VARIABLE, node_builder, value_builder, cache_builder);
-        let constants = node.parse_while(CONSTANT, node_builder, value_builder, cache_builder);
-        let expressions = node.parse_while(EXPRESSION, node_builder, value_builder, cache_builder);
-        let formula_to = node.parse(node_builder, value_builder, cache_builder);
-        let formula_from = node.parse(node_builder, value_builder, cache_builder);
-        let p_value = node.parse(node_builder, value_builder, cache_builder);
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let p_variables =
+            node.parse_while(P_VARIABLE, node_builder, value_builder, cache_builder)?;
+        let constants = node.parse_while(CONSTANT, node_builder, value_builder, cache_builder)?;
+        let expressions =
+            node.parse_while(EXPRESSION, node_builder, value_builder, cache_builder)?;
+        let formula_to = node.parse_if(FORMULA_TO, node_builder, value_builder, cache_builder)?;
+        let formula_from = node.parse(node_builder, value_builder, cache_builder)?;
+        let p_value = node.parse(node_builder, value_builder, cache_builder)?;
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_notation = node
-            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)
+            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_precision = node
             .parse_if(
@@ -53,16 +55,16 @@ impl Parse for ConverterNode {
                 node_builder,
                 value_builder,
                 cache_builder,
-            )
+            )?
             .unwrap_or(6);
         let slope = node
-            .parse_if(SLOPE, node_builder, value_builder, cache_builder)
+            .parse_if(SLOPE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let is_linear = node
-            .parse_if(IS_LINEAR, node_builder, value_builder, cache_builder)
+            .parse_if(IS_LINEAR, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -78,7 +80,7 @@ impl Parse for ConverterNode {
             display_precision,
             slope,
             is_linear,
-        }
+        })
     }
 }
 
@@ -131,4 +133,19 @@ mod tests {
         assert_eq!(node.slope(), Slope::Increasing);
         assert!(node.is_linear());
     }
+
+    #[test]
+    fn test_converter_without_formula_to() {
+        let xml = r#"
+            <Converter Name="Testnode">
+                <FormulaFrom>TO*2</FormulaFrom>
+                <pValue>Target</pValue>
+                <Slope>Automatic</Slope>
+             </Converter>
+             "#;
+
+        let (node, ..): (ConverterNode, _, _, _) = parse_default(xml);
+        assert!(node.formula_to().is_none());
+        assert_eq!(node.slope(), Slope::Automatic);
+    }
 }
@@ -0,0 +1,211 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Non-fatal problems found while parsing.
+
+use core::fmt;
+
+use crate::{
+    compat::{String, Vec},
+    SchemaVersion,
+};
+
+/// A non-fatal problem found while parsing. The parser recovers from it and continues.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// An element is skipped because the parser doesn't know it or it appears at an unexpected
+    /// position.
+    UnknownElement {
+        /// Name of the node which has the element, `None` for top-level elements.
+        owner: Option<String>,
+        /// Tag name of the element.
+        element: String,
+    },
+
+    /// A node is marked as deprecated by `IsDeprecated` element.
+    DeprecatedNode {
+        /// Name of the node.
+        node: String,
+    },
+
+    /// The immediate value of a node is out of its immediate `Min` and `Max`.
+    OutOfRange {
+        /// Name of the node.
+        node: String,
+        /// The value.
+        value: String,
+        /// The minimum of the node.
+        min: String,
+        /// The maximum of the node.
+        max: String,
+    },
+
+    /// The text of an element doesn't exactly match any value the schema specifies, but matches
+    /// one after trimming whitespaces and ignoring ASCII case, e.g. `linear ` for `Linear`.
+    NonCanonicalText {
+        /// Name of the node which has the element.
+        owner: Option<String>,
+        /// Tag name of the element.
+        element: String,
+        /// The text of the element.
+        text: String,
+    },
+
+    /// An element isn't available in the schema version of the document. It's parsed anyway.
+    UnavailableElement {
+        /// Name of the node which has the element.
+        owner: Option<String>,
+        /// Tag name of the element.
+        element: String,
+        /// The schema version which introduced the element.
+        since: SchemaVersion,
+    },
+
+    /// The parser doesn't know the schema version of the document. It's parsed as
+    /// [`SchemaVersion::LATEST`].
+    UnsupportedSchemaVersion {
+        /// The schema version of the document.
+        version: SchemaVersion,
+    },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownElement {
+                owner: Some(owner),
+                element,
+            } => write!(f, "element `{}` in node `{}` is skipped", element, owner),
+            Self::UnknownElement {
+                owner: None,
+                element,
+            } => write!(f, "top-level element `{}` is skipped", element),
+            Self::DeprecatedNode { node } => write!(f, "node `{}` is deprecated", node),
+            Self::OutOfRange {
+                node,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "value `{}` of node `{}` is out of range [{}, {}]",
+                value, node, min, max
+            ),
+            Self::NonCanonicalText {
+                owner,
+                element,
+                text,
+            } => write!(
+                f,
+                "text `{}` of element `{}` in node `{}` is not canonical",
+                text,
+                element,
+                owner.as_deref().unwrap_or_default()
+            ),
+            Self::UnavailableElement {
+                owner,
+                element,
+                since,
+            } => write!(
+                f,
+                "element `{}` in node `{}` is only available since schema version {}",
+                element,
+                owner.as_deref().unwrap_or_default(),
+                since
+            ),
+            Self::UnsupportedSchemaVersion { version } => {
+                write!(f, "schema version {} is not supported", version)
+            }
+        }
+    }
+}
+
+/// Collects [`ParseWarning`]s reported while parsing, e.g. to log them or to attach them to a
+/// bug report for the device vendor.
+///
+/// The collector also decides how strictly texts of enumerated elements, e.g. `Representation`,
+/// are parsed. By default, a text which matches a value after trimming whitespaces and ignoring
+/// ASCII case is accepted and reported as [`ParseWarning::NonCanonicalText`]. A collector
+/// constructed by [`Self::strict`] accepts only exact matches, and parsing fails with
+/// [`super::ParseError::InvalidLiteral`] otherwise.
+///
+/// See [`super::parse_with_diagnostics`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseDiagnostics {
+    warnings: Vec<ParseWarning>,
+    strict: bool,
+}
+
+impl ParseDiagnostics {
+    /// Constructs an empty collector.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs an empty collector for strict parsing.
+    #[must_use]
+    pub fn strict() -> Self {
+        Self {
+            strict: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if the collector is constructed for strict parsing.
+    #[must_use]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Returns the warnings in the order they are reported.
+    #[must_use]
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Returns `true` if no warning is reported.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Removes all warnings.
+    pub fn clear(&mut self) {
+        self.warnings.clear();
+    }
+
+    pub(crate) fn extend(&mut self, warnings: impl IntoIterator<Item = ParseWarning>) {
+        self.warnings.extend(warnings);
+    }
+}
+
+/// Buffer of warnings shared by nodes of a document.
+///
+/// `Mutex` is used with `std` so that nodes can be parsed in parallel.
+#[derive(Debug, Default)]
+pub(super) struct WarningSink {
+    #[cfg(feature = "std")]
+    warnings: std::sync::Mutex<Vec<ParseWarning>>,
+    #[cfg(not(feature = "std"))]
+    warnings: core::cell::RefCell<Vec<ParseWarning>>,
+}
+
+impl WarningSink {
+    pub(super) fn push(&self, warning: ParseWarning) {
+        self.with(|warnings| warnings.push(warning));
+    }
+
+    pub(super) fn take(&self) -> Vec<ParseWarning> {
+        self.with(core::mem::take)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut Vec<ParseWarning>) -> R) -> R {
+        #[cfg(feature = "std")]
+        let mut warnings = self.warnings.lock().unwrap();
+        #[cfg(not(feature = "std"))]
+        let mut warnings = self.warnings.borrow_mut();
+        f(&mut warnings)
+    }
+}
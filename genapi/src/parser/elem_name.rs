@@ -29,6 +29,7 @@ pub(super) const SMART_FEATURE: &str = "SmartFeature";
 pub(super) const STRUCT_REG: &str = "StructReg";
 pub(super) const STRUCT_ENTRY: &str = "StructEntry";
 pub(super) const GROUP: &str = "Group";
+pub(super) const P_INCLUDE: &str = "pInclude";
 
 pub(super) const P_INVALIDATOR: &str = "pInvalidator";
 pub(super) const P_SELECTED: &str = "pSelected";
@@ -53,7 +54,9 @@ pub(super) const MAX: &str = "Max";
 pub(super) const P_MAX: &str = "pMax";
 pub(super) const INC: &str = "Inc";
 pub(super) const P_INC: &str = "pInc";
+pub(super) const VALID_VALUE_SET: &str = "ValidValueSet";
 pub(super) const CONSTANT: &str = "Constant";
+pub(super) const FORMULA_TO: &str = "FormulaTo";
 pub(super) const EXPRESSION: &str = "Expression";
 pub(super) const SIGN: &str = "Sign";
 pub(super) const UNIT: &str = "Unit";
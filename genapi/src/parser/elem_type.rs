@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 use crate::{
     builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
@@ -22,21 +22,20 @@ use super::{
         ADDRESS, BIT, INDEX, INT_SWISS_KNIFE, NAME, OFFSET, P_ADDRESS, P_INDEX, P_OFFSET, P_VALUE,
         P_VALUE_COPY, P_VALUE_INDEXED, VALUE, VALUE_INDEXED,
     },
-    xml, Parse,
+    literal, xml, Parse, ParseError, ParseResult,
 };
 
-macro_rules! match_text_view{
-    ($text:expr,
-        $s1:expr => $var1:expr,
-        $($s:expr => $var:expr,)*
+use crate::compat::{String, ToString, Vec};
+
+/// Parses the text of the next child element of `$node` into one of the variants.
+macro_rules! match_text {
+    ($node:expr,
+        $($s:literal => $var:expr,)+
     ) => {
-        if $text == $s1 {
-            $var1
-        } $(else if $text == $s {
-            $var
-        })* else {
-            unreachable!()
-        }
+        Ok(match $node.next_text_of(&[$($s),+])? {
+            $($s => $var,)+
+            _ => unreachable!(),
+        })
     }
 }
 
@@ -46,25 +45,14 @@ impl Default for NameSpace {
     }
 }
 
-impl From<&str> for NameSpace {
-    fn from(value: &str) -> Self {
-        match value {
-            "Standard" => Self::Standard,
-            "Custom" => Self::Custom,
-            _ => unreachable!(),
-        }
-    }
-}
-
 impl Parse for NameSpace {
     fn parse(
         node: &mut xml::Node,
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view!(text,
+    ) -> ParseResult<Self> {
+        match_text!(node,
             "Standard" => Self::Standard,
             "Custom" => Self::Custom,
         )
@@ -83,9 +71,8 @@ impl Parse for Visibility {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view!(text,
+    ) -> ParseResult<Self> {
+        match_text!(node,
             "Beginner" => Self::Beginner,
             "Expert" => Self::Expert,
             "Guru" => Self::Guru,
@@ -94,17 +81,6 @@ impl Parse for Visibility {
     }
 }
 
-impl From<&str> for MergePriority {
-    fn from(value: &str) -> Self {
-        match value {
-            "1" => Self::High,
-            "0" => Self::Mid,
-            "-1" => Self::Low,
-            _ => unreachable!(),
-        }
-    }
-}
-
 impl Default for MergePriority {
     fn default() -> Self {
         Self::Mid
@@ -117,9 +93,8 @@ impl Parse for MergePriority {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view!(text,
+    ) -> ParseResult<Self> {
+        match_text!(node,
             "1" => Self::High,
             "0" => Self::Mid,
             "-1" => Self::Low,
@@ -133,9 +108,8 @@ impl Parse for AccessMode {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view!(text,
+    ) -> ParseResult<Self> {
+        match_text!(node,
             "RO" => Self::RO,
             "WO" => Self::WO,
             "RW" => Self::RW,
@@ -149,12 +123,14 @@ impl Parse for ImmOrPNode<i64> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peeked_text = node.peek().unwrap().text();
-        if peeked_text.view().chars().next().unwrap().is_alphabetic() {
-            Self::PNode(node.parse(node_builder, value_builder, cache_builder))
+    ) -> ParseResult<Self> {
+        let peeked_text = node.peek_required()?.text().view();
+        if peeked_text.chars().next().is_some_and(char::is_alphabetic) {
+            node.parse(node_builder, value_builder, cache_builder)
+                .map(Self::PNode)
         } else {
-            Self::Imm(node.parse(node_builder, value_builder, cache_builder))
+            node.parse(node_builder, value_builder, cache_builder)
+                .map(Self::Imm)
         }
     }
 }
@@ -165,17 +141,17 @@ impl Parse for ImmOrPNode<f64> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peeked_text = node.peek().unwrap().text();
+    ) -> ParseResult<Self> {
+        let peeked_text = node.peek_required()?.text().view();
 
-        if peeked_text == "INF"
-            || peeked_text == "-INF"
-            || peeked_text == "NaN"
-            || !peeked_text.view().chars().next().unwrap().is_alphabetic()
+        if literal::parse_float(&peeked_text).is_ok()
+            || !peeked_text.chars().next().is_some_and(char::is_alphabetic)
         {
-            Self::Imm(node.parse(node_builder, value_builder, cache_builder))
+            node.parse(node_builder, value_builder, cache_builder)
+                .map(Self::Imm)
         } else {
-            Self::PNode(node.parse(node_builder, value_builder, cache_builder))
+            node.parse(node_builder, value_builder, cache_builder)
+                .map(Self::PNode)
         }
     }
 }
@@ -186,11 +162,13 @@ impl Parse for ImmOrPNode<bool> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        if convert_to_bool_opt(&node.peek().unwrap().text().view()).is_some() {
-            Self::Imm(node.parse(node_builder, value_builder, cache_builder))
+    ) -> ParseResult<Self> {
+        if convert_to_bool_opt(&node.peek_required()?.text().view()).is_some() {
+            node.parse(node_builder, value_builder, cache_builder)
+                .map(Self::Imm)
         } else {
-            Self::PNode(node.parse(node_builder, value_builder, cache_builder))
+            node.parse(node_builder, value_builder, cache_builder)
+                .map(Self::PNode)
         }
     }
 }
@@ -203,16 +181,17 @@ macro_rules! impl_parse_for_imm_or_pnode_id {
                 node_builder: &mut impl NodeStoreBuilder,
                 value_builder: &mut impl ValueStoreBuilder,
                 cache_builder: &mut impl CacheStoreBuilder,
-            ) -> Self {
+            ) -> ParseResult<Self> {
+                let info = node.next_value_info(node_builder)?;
                 let node: ImmOrPNode<$value_ty> =
-                    node.parse(node_builder, value_builder, cache_builder);
-                match node {
+                    node.parse(node_builder, value_builder, cache_builder)?;
+                Ok(match node {
                     ImmOrPNode::Imm(i) => {
-                        let id = value_builder.store(i);
+                        let id = value_builder.store_with_info(i, info);
                         ImmOrPNode::Imm(id)
                     }
                     ImmOrPNode::PNode(id) => ImmOrPNode::PNode(id),
-                }
+                })
             }
         }
     };
@@ -233,13 +212,12 @@ impl Parse for IntegerRepresentation {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         use IntegerRepresentation::{
             Boolean, HexNumber, IpV4Address, Linear, Logarithmic, MacAddress, PureNumber,
         };
 
-        let value = node.next_text().unwrap();
-        match_text_view!(value,
+        match_text!(node,
             "Linear" => Linear,
             "Logarithmic" => Logarithmic,
             "Boolean" => Boolean,
@@ -257,9 +235,8 @@ impl Parse for FloatRepresentation {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view! {text,
+    ) -> ParseResult<Self> {
+        match_text! {node,
             "Linear" => Self::Linear,
             "Logarithmic" => Self::Logarithmic,
             "PureNumber" => Self::PureNumber,
@@ -279,9 +256,8 @@ impl Parse for Slope {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view! {text,
+    ) -> ParseResult<Self> {
+        match_text! {node,
             "Increasing" => Self::Increasing,
             "Decreasing" => Self::Decreasing,
             "Varying" => Self::Varying,
@@ -308,9 +284,8 @@ impl Parse for DisplayNotation {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view! {text,
+    ) -> ParseResult<Self> {
+        match_text! {node,
             "Automatic" => Self::Automatic,
             "Fixed" => Self::Fixed,
             "Scientific" => Self::Scientific,
@@ -318,19 +293,6 @@ impl Parse for DisplayNotation {
     }
 }
 
-impl From<&str> for StandardNameSpace {
-    fn from(value: &str) -> Self {
-        match value {
-            "None" => Self::None,
-            "IIDC" => Self::IIDC,
-            "GEV" => Self::GEV,
-            "CL" => Self::CL,
-            "USB" => Self::USB,
-            _ => unreachable!(),
-        }
-    }
-}
-
 impl Default for CachingMode {
     fn default() -> Self {
         Self::WriteThrough
@@ -343,9 +305,8 @@ impl Parse for CachingMode {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view! {text,
+    ) -> ParseResult<Self> {
+        match_text! {node,
             "WriteThrough" => Self::WriteThrough,
             "WriteAround" => Self::WriteAround,
             "NoCache" => Self::NoCache,
@@ -362,18 +323,16 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let name = node.peek().unwrap().attribute_of(NAME).unwrap().into();
-        let value = node.parse(node_builder, value_builder, cache_builder);
-        Self { name, value }
+    ) -> ParseResult<Self> {
+        let name = node.peek_required()?.required_attribute_of(NAME)?.into();
+        let value = node.parse(node_builder, value_builder, cache_builder)?;
+        Ok(Self { name, value })
     }
 }
 
-pub(super) fn convert_to_bool(value: &str) -> bool {
-    match convert_to_bool_opt(value) {
-        Some(b) => b,
-        _ => unreachable!(),
-    }
+pub(super) fn convert_to_bool(value: &str) -> ParseResult<bool> {
+    convert_to_bool_opt(value)
+        .ok_or_else(|| invalid_literal(value, "expected `Yes`, `No`, `true` or `false`"))
 }
 
 pub(super) fn convert_to_bool_opt(value: &str) -> Option<bool> {
@@ -390,26 +349,33 @@ impl Parse for bool {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let text = node.next_text_required()?;
         convert_to_bool(&text.view())
     }
 }
 
-pub(super) fn convert_to_int(value: &str) -> i64 {
-    if value.starts_with("0x") || value.starts_with("0X") {
-        i64::from_str_radix(&value[2..], 16).unwrap()
-    } else {
-        value.parse().unwrap()
-    }
+/// Converts an integer literal, see [`literal`] for the syntax.
+pub(super) fn convert_to_int(value: &str) -> ParseResult<i64> {
+    literal::parse_int(value).map_err(|e| invalid_literal(value, e))
 }
 
-pub(super) fn convert_to_uint(value: &str) -> u64 {
-    if value.starts_with("0x") || value.starts_with("0X") {
-        u64::from_str_radix(&value[2..], 16).unwrap()
-    } else {
-        value.parse().unwrap()
-    }
+/// Converts semicolon separated integers, e.g. `1;2;0x10`, into a sorted list.
+pub(super) fn convert_to_int_list(value: &str) -> ParseResult<Vec<i64>> {
+    let mut list = value
+        .split(';')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(convert_to_int)
+        .collect::<ParseResult<Vec<i64>>>()?;
+    list.sort_unstable();
+    list.dedup();
+    Ok(list)
+}
+
+/// Converts an unsigned integer literal, see [`literal`] for the syntax.
+pub(super) fn convert_to_uint(value: &str) -> ParseResult<u64> {
+    literal::parse_uint(value).map_err(|e| invalid_literal(value, e))
 }
 
 impl Parse for i64 {
@@ -418,8 +384,8 @@ impl Parse for i64 {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let value = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let value = node.next_text_required()?;
         convert_to_int(&value.view())
     }
 }
@@ -430,8 +396,8 @@ impl Parse for u64 {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let value = node.next_text().unwrap();
+    ) -> ParseResult<Self> {
+        let value = node.next_text_required()?;
         convert_to_uint(&value.view())
     }
 }
@@ -442,15 +408,69 @@ impl Parse for f64 {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let value = node.next_text().unwrap().view();
-        if value == "INF" {
-            f64::INFINITY
-        } else if value == "-INF" {
-            f64::NEG_INFINITY
-        } else {
-            value.parse().unwrap()
-        }
+    ) -> ParseResult<Self> {
+        let value = node.next_text_required()?.view();
+        convert_to_float(&value)
+    }
+}
+
+/// Converts a float literal, see [`literal`] for the syntax.
+pub(super) fn convert_to_float(value: &str) -> ParseResult<f64> {
+    literal::parse_float(value).map_err(|e| invalid_literal(value, e))
+}
+
+/// Converts semicolon separated floats, e.g. `0.5;1;INF`, into a sorted list.
+pub(super) fn convert_to_float_list(value: &str) -> ParseResult<Vec<f64>> {
+    let mut list = value
+        .split(';')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(convert_to_float)
+        .collect::<ParseResult<Vec<f64>>>()?;
+    list.retain(|v| !v.is_nan());
+    list.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    list.dedup();
+    Ok(list)
+}
+
+/// Converts the value of `NameSpace` attribute.
+pub(super) fn convert_to_name_space(value: &str) -> ParseResult<NameSpace> {
+    match value {
+        "Standard" => Ok(NameSpace::Standard),
+        "Custom" => Ok(NameSpace::Custom),
+        _ => Err(invalid_literal(value, "expected `Standard` or `Custom`")),
+    }
+}
+
+/// Converts the value of `MergePriority` attribute.
+pub(super) fn convert_to_merge_priority(value: &str) -> ParseResult<MergePriority> {
+    match value {
+        "1" => Ok(MergePriority::High),
+        "0" => Ok(MergePriority::Mid),
+        "-1" => Ok(MergePriority::Low),
+        _ => Err(invalid_literal(value, "expected `1`, `0` or `-1`")),
+    }
+}
+
+/// Converts the value of `StandardNameSpace` attribute.
+pub(super) fn convert_to_standard_name_space(value: &str) -> ParseResult<StandardNameSpace> {
+    match value {
+        "None" => Ok(StandardNameSpace::None),
+        "IIDC" => Ok(StandardNameSpace::IIDC),
+        "GEV" => Ok(StandardNameSpace::GEV),
+        "CL" => Ok(StandardNameSpace::CL),
+        "USB" => Ok(StandardNameSpace::USB),
+        _ => Err(invalid_literal(
+            value,
+            "expected `None`, `IIDC`, `GEV`, `CL` or `USB`",
+        )),
+    }
+}
+
+pub(super) fn invalid_literal(value: &str, reason: impl ToString) -> ParseError {
+    ParseError::InvalidLiteral {
+        literal: value.into(),
+        reason: reason.to_string(),
     }
 }
 
@@ -460,8 +480,8 @@ impl Parse for String {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        node.next_text().unwrap().view().into()
+    ) -> ParseResult<Self> {
+        Ok(node.next_text_required()?.view().into())
     }
 }
 
@@ -471,9 +491,9 @@ impl Parse for NodeId {
         node_builder: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        node_builder.get_or_intern(text.view())
+    ) -> ParseResult<Self> {
+        let text = node.next_text_required()?;
+        Ok(node_builder.get_or_intern(text.view()))
     }
 }
 
@@ -485,10 +505,10 @@ macro_rules! impl_parse_for_value_id {
                 node_builder: &mut impl NodeStoreBuilder,
                 value_builder: &mut impl ValueStoreBuilder,
                 cache_builder: &mut impl CacheStoreBuilder,
-            ) -> Self {
-                let value: $value_ty = node.parse(node_builder, value_builder, cache_builder);
-                let id = value_builder.store(value);
-                id
+            ) -> ParseResult<Self> {
+                let info = node.next_value_info(node_builder)?;
+                let value: $value_ty = node.parse(node_builder, value_builder, cache_builder)?;
+                Ok(value_builder.store_with_info(value, info))
             }
         }
     };
@@ -507,19 +527,21 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peek = node.peek().unwrap();
+    ) -> ParseResult<Self> {
+        let peek = node.peek_required()?;
         match peek.tag_name() {
-            VALUE => ValueKind::Value(node.parse(node_builder, value_builder, cache_builder)),
+            VALUE => node
+                .parse(node_builder, value_builder, cache_builder)
+                .map(ValueKind::Value),
             P_VALUE_COPY | P_VALUE => {
-                let p_value = node.parse(node_builder, value_builder, cache_builder);
-                ValueKind::PValue(p_value)
+                let p_value = node.parse(node_builder, value_builder, cache_builder)?;
+                Ok(ValueKind::PValue(p_value))
             }
             P_INDEX => {
-                let p_index = node.parse(node_builder, value_builder, cache_builder);
-                ValueKind::PIndex(p_index)
+                let p_index = node.parse(node_builder, value_builder, cache_builder)?;
+                Ok(ValueKind::PIndex(p_index))
             }
-            _ => unreachable!(),
+            _ => Err(node.missing_element()),
         }
     }
 }
@@ -530,22 +552,22 @@ impl<T> Parse for PValue<T> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         // NOTE: The pValue can be sandwiched between two pValueCopy sequence.
         let mut p_value_copies =
-            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder);
+            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder)?;
 
-        let p_value = node.parse(node_builder, value_builder, cache_builder);
+        let p_value = node.parse(node_builder, value_builder, cache_builder)?;
 
         let node_ids: Vec<NodeId> =
-            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder);
+            node.parse_while(P_VALUE_COPY, node_builder, value_builder, cache_builder)?;
         p_value_copies.extend(node_ids);
 
-        Self {
+        Ok(Self {
             p_value,
             p_value_copies,
             phantom: PhantomData,
-        }
+        })
     }
 }
 
@@ -559,24 +581,26 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let p_index = node.parse(node_builder, value_builder, cache_builder);
+    ) -> ParseResult<Self> {
+        let p_index = node.parse(node_builder, value_builder, cache_builder)?;
 
         let mut value_indexed = vec![];
-        while let Some(indexed) = node
-            .parse_if(VALUE_INDEXED, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_VALUE_INDEXED, node_builder, value_builder, cache_builder))
-        {
+        while let Some(indexed) = node.parse_if_any(
+            &[VALUE_INDEXED, P_VALUE_INDEXED],
+            node_builder,
+            value_builder,
+            cache_builder,
+        )? {
             value_indexed.push(indexed);
         }
 
-        let value_default = node.parse(node_builder, value_builder, cache_builder);
+        let value_default = node.parse(node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             p_index,
             value_indexed,
             value_default,
-        }
+        })
     }
 }
 
@@ -590,10 +614,10 @@ where
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let index = convert_to_int(node.peek().unwrap().attribute_of(INDEX).unwrap());
-        let indexed = node.parse(node_builder, value_builder, cache_builder);
-        Self { index, indexed }
+    ) -> ParseResult<Self> {
+        let index = convert_to_int(node.peek_required()?.required_attribute_of(INDEX)?)?;
+        let indexed = node.parse(node_builder, value_builder, cache_builder)?;
+        Ok(Self { index, indexed })
     }
 }
 
@@ -603,23 +627,24 @@ impl Parse for AddressKind {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let peeked_node = node.peek().unwrap();
+    ) -> ParseResult<Self> {
+        let peeked_node = node.peek_required()?;
         match peeked_node.tag_name() {
-            ADDRESS | P_ADDRESS => {
-                Self::Address(node.parse(node_builder, value_builder, cache_builder))
-            }
+            ADDRESS | P_ADDRESS => node
+                .parse(node_builder, value_builder, cache_builder)
+                .map(Self::Address),
             INT_SWISS_KNIFE => {
                 let swiss_knife: IntSwissKnifeNode =
-                    node.next()
-                        .unwrap()
-                        .parse(node_builder, value_builder, cache_builder);
+                    node.next_required()?
+                        .parse(node_builder, value_builder, cache_builder)?;
                 let id = swiss_knife.node_base().id();
                 node_builder.store_node(id, NodeData::IntSwissKnife(swiss_knife.into()));
-                Self::IntSwissKnife(id)
+                Ok(Self::IntSwissKnife(id))
             }
-            P_INDEX => Self::PIndex(node.parse(node_builder, value_builder, cache_builder)),
-            _ => unreachable!(),
+            P_INDEX => node
+                .parse(node_builder, value_builder, cache_builder)
+                .map(Self::PIndex),
+            _ => Err(node.missing_element()),
         }
     }
 }
@@ -630,20 +655,22 @@ impl Parse for RegPIndex {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let next_node = node.peek().unwrap();
+    ) -> ParseResult<Self> {
+        let next_node = node.peek_required()?;
 
         let imm_offset = next_node
             .attribute_of(OFFSET)
-            .map(|s| ImmOrPNode::Imm(convert_to_int(s)));
+            .map(convert_to_int)
+            .transpose()?
+            .map(ImmOrPNode::Imm);
         let pnode_offset = next_node
             .attribute_of(P_OFFSET)
             .map(|s| ImmOrPNode::PNode(node_builder.get_or_intern(s)));
         let offset = imm_offset.xor(pnode_offset);
 
-        let p_index = node.parse(node_builder, value_builder, cache_builder);
+        let p_index = node.parse(node_builder, value_builder, cache_builder)?;
 
-        Self { offset, p_index }
+        Ok(Self { offset, p_index })
     }
 }
 
@@ -659,9 +686,8 @@ impl Parse for Endianness {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view! {text,
+    ) -> ParseResult<Self> {
+        match_text! {node,
             "LittleEndian" => Self::LE,
             "BigEndian" => Self::BE,
         }
@@ -680,9 +706,8 @@ impl Parse for Sign {
         _: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let text = node.next_text().unwrap();
-        match_text_view! {text,
+    ) -> ParseResult<Self> {
+        match_text! {node,
             "Signed" => Self::Signed,
             "Unsigned" => Self::Unsigned,
         }
@@ -695,15 +720,12 @@ impl Parse for BitMask {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        node.parse_if(BIT, node_builder, value_builder, cache_builder)
-            .map_or_else(
-                || {
-                    let lsb = node.parse(node_builder, value_builder, cache_builder);
-                    let msb = node.parse(node_builder, value_builder, cache_builder);
-                    Self::Range { lsb, msb }
-                },
-                Self::SingleBit,
-            )
+    ) -> ParseResult<Self> {
+        if let Some(bit) = node.parse_if(BIT, node_builder, value_builder, cache_builder)? {
+            return Ok(Self::SingleBit(bit));
+        }
+        let lsb = node.parse(node_builder, value_builder, cache_builder)?;
+        let msb = node.parse(node_builder, value_builder, cache_builder)?;
+        Ok(Self::Range { lsb, msb })
     }
 }
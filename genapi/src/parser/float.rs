@@ -11,13 +11,17 @@ use crate::{
 };
 
 use super::{
+    diagnostics::ParseWarning,
     elem_name::{
         DISPLAY_NOTATION, DISPLAY_PRECISION, FLOAT, INC, MAX, MIN, P_INC, P_MAX, P_MIN,
-        REPRESENTATION, STREAMABLE, UNIT,
+        REPRESENTATION, STREAMABLE, UNIT, VALID_VALUE_SET, VALUE,
     },
-    xml, Parse,
+    elem_type::{convert_to_float, convert_to_float_list},
+    xml, Parse, ParseResult,
 };
 
+use crate::compat::ToString;
+
 impl Parse for FloatNode {
     #[tracing::instrument(level = "trace", skip(node_builder, value_builder, cache_builder))]
     fn parse(
@@ -25,40 +29,67 @@ impl Parse for FloatNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `FloatNode`");
         debug_assert_eq!(node.tag_name(), FLOAT);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let value_kind = node.parse(node_builder, value_builder, cache_builder);
+        let imm_value = node
+            .peek_text_if(VALUE)
+            .map(|text| convert_to_float(&text.view()))
+            .transpose()?;
+        let value_kind = node.parse(node_builder, value_builder, cache_builder)?;
+        let imm_min = node
+            .peek_text_if(MIN)
+            .map(|text| convert_to_float(&text.view()))
+            .transpose()?;
         let min = node
-            .parse_if(MIN, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MIN, node_builder, value_builder, cache_builder))
+            .parse_if_any(&[MIN, P_MIN], node_builder, value_builder, cache_builder)?
             .unwrap_or_else(|| {
-                let id = value_builder.store(f64::MIN);
+                let id =
+                    value_builder.store_with_info(f64::MIN, node.value_info(MIN, node_builder));
                 ImmOrPNode::Imm(id)
             });
+        let imm_max = node
+            .peek_text_if(MAX)
+            .map(|text| convert_to_float(&text.view()))
+            .transpose()?;
         let max = node
-            .parse_if(MAX, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MAX, node_builder, value_builder, cache_builder))
+            .parse_if_any(&[MAX, P_MAX], node_builder, value_builder, cache_builder)?
             .unwrap_or_else(|| {
-                let id = value_builder.store(f64::MAX);
+                let id =
+                    value_builder.store_with_info(f64::MAX, node.value_info(MAX, node_builder));
                 ImmOrPNode::Imm(id)
             });
-        let inc = node
-            .parse_if(INC, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_INC, node_builder, value_builder, cache_builder));
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        if let Some(value) = imm_value {
+            let min = imm_min.unwrap_or(f64::MIN);
+            let max = imm_max.unwrap_or(f64::MAX);
+            if value < min || value > max {
+                node.report(ParseWarning::OutOfRange {
+                    node: node.owner_name().unwrap_or_default().into(),
+                    value: value.to_string(),
+                    min: min.to_string(),
+                    max: max.to_string(),
+                });
+            }
+        }
+        let inc = node.parse_if_any(&[INC, P_INC], node_builder, value_builder, cache_builder)?;
+        let valid_value_set = node
+            .next_if(VALID_VALUE_SET)
+            .map(|n| convert_to_float_list(&n.text().view()))
+            .transpose()?
+            .unwrap_or_default();
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_notation = node
-            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)
+            .parse_if(DISPLAY_NOTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let display_precision = node
             .parse_if(
@@ -66,10 +97,10 @@ impl Parse for FloatNode {
                 node_builder,
                 value_builder,
                 cache_builder,
-            )
+            )?
             .unwrap_or(6);
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -77,11 +108,12 @@ impl Parse for FloatNode {
             min,
             max,
             inc,
+            valid_value_set,
             unit,
             representation,
             display_notation,
             display_precision,
-        }
+        })
     }
 }
 
@@ -90,10 +122,29 @@ mod test {
     use crate::{
         elem_type::{DisplayNotation, FloatRepresentation, ValueKind},
         interface::INode,
-        store::ValueStore,
+        store::{DefaultCacheStore, DefaultNodeStore, DefaultValueStore, ValueStore},
     };
 
-    use super::{super::utils::tests::parse_default, *};
+    use super::{
+        super::{
+            utils::tests::{parse_default, try_parse_default},
+            ParseError,
+        },
+        *,
+    };
+
+    #[test]
+    fn test_float_node_with_valid_value_set() {
+        let xml = r#"
+            <Float Name = "TestNode">
+                <Value>1.0</Value>
+                <ValidValueSet>2.5;0.5; 1.0;INF</ValidValueSet>
+            </Float>
+            "#;
+
+        let (node, ..): (FloatNode, _, _, _) = parse_default(xml);
+        assert_eq!(node.valid_value_set_elem(), &[0.5, 1.0, 2.5, f64::INFINITY]);
+    }
 
     #[test]
     fn test_float_node() {
@@ -129,4 +180,65 @@ mod test {
         assert_eq!(node.display_notation_elem(), DisplayNotation::Fixed);
         assert_eq!(node.display_precision_elem(), 10);
     }
+
+    #[test]
+    fn test_float_value_info() {
+        let xml = r#"
+            <Float Name = "TestNode">
+                <Value>0.5</Value>
+                <Max>10</Max>
+            </Float>
+            "#;
+
+        let (node, _, value_builder, _): (FloatNode, _, _, _) = parse_default(xml);
+        // Value info is only kept by the store on request.
+        assert!(value_builder
+            .value_info(node.max_elem().imm().unwrap())
+            .is_none());
+
+        let document = xml::Document::from_str(xml).unwrap();
+        let mut node_builder = DefaultNodeStore::new();
+        let mut value_builder = DefaultValueStore::with_value_info();
+        let node: FloatNode = document
+            .root_node()
+            .parse(
+                &mut node_builder,
+                &mut value_builder,
+                &mut DefaultCacheStore::new(),
+            )
+            .unwrap();
+
+        let max_info = value_builder
+            .value_info(node.max_elem().imm().unwrap())
+            .unwrap();
+        assert_eq!(max_info.elem_name, "Max");
+        assert_eq!(max_info.owner.unwrap().name(&node_builder), "TestNode");
+
+        // Default min value is also tagged.
+        let min_info = value_builder
+            .value_info(node.min_elem().imm().unwrap())
+            .unwrap();
+        assert_eq!(min_info.elem_name, "Min");
+
+        let elem_names: Vec<_> = value_builder
+            .iter()
+            .map(|(id, _)| value_builder.value_info(id).unwrap().elem_name.as_str())
+            .collect();
+        assert_eq!(elem_names, &["Value", "Min", "Max"]);
+    }
+
+    #[test]
+    fn test_float_node_with_invalid_literal() {
+        let xml = r#"
+            <Float Name="TestNode">
+                <Value>1.2.3</Value>
+            </Float>
+            "#;
+
+        assert!(matches!(
+            try_parse_default::<FloatNode>(xml),
+            Err(ParseError::InvalidLiteral { literal, reason })
+                if literal == "1.2.3" && reason == "malformed literal"
+        ));
+    }
 }
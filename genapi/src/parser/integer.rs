@@ -12,12 +12,17 @@ use crate::{
 };
 
 use super::{
+    diagnostics::ParseWarning,
     elem_name::{
         INC, INTEGER, MAX, MIN, P_INC, P_MAX, P_MIN, P_SELECTED, REPRESENTATION, STREAMABLE, UNIT,
+        VALID_VALUE_SET, VALUE,
     },
-    xml, Parse,
+    elem_type::{convert_to_int, convert_to_int_list},
+    xml, Parse, ParseResult,
 };
 
+use crate::compat::{ToString, Vec};
+
 impl Parse for IntegerNode {
     #[tracing::instrument(level = "trace", skip(node_builder, value_builder, cache_builder))]
     fn parse(
@@ -25,45 +30,71 @@ impl Parse for IntegerNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `IntegerNode`");
         debug_assert_eq!(node.tag_name(), INTEGER);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let value_kind = node.parse(node_builder, value_builder, cache_builder);
-        let min = node
-            .parse_if(MIN, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MIN, node_builder, value_builder, cache_builder));
-        let max = node
-            .parse_if(MAX, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_MAX, node_builder, value_builder, cache_builder));
+        let imm_value = node
+            .peek_text_if(VALUE)
+            .map(|text| convert_to_int(&text.view()))
+            .transpose()?;
+        let value_kind = node.parse(node_builder, value_builder, cache_builder)?;
+        let imm_min = node
+            .peek_text_if(MIN)
+            .map(|text| convert_to_int(&text.view()))
+            .transpose()?;
+        let min = node.parse_if_any(&[MIN, P_MIN], node_builder, value_builder, cache_builder)?;
+        let imm_max = node
+            .peek_text_if(MAX)
+            .map(|text| convert_to_int(&text.view()))
+            .transpose()?;
+        let max = node.parse_if_any(&[MAX, P_MAX], node_builder, value_builder, cache_builder)?;
+        if let Some(value) = imm_value {
+            let min = imm_min.unwrap_or(i64::MIN);
+            let max = imm_max.unwrap_or(i64::MAX);
+            if value < min || value > max {
+                node.report(ParseWarning::OutOfRange {
+                    node: node.owner_name().unwrap_or_default().into(),
+                    value: value.to_string(),
+                    min: min.to_string(),
+                    max: max.to_string(),
+                });
+            }
+        }
         let inc = node
-            .parse_if(INC, node_builder, value_builder, cache_builder)
-            .or_else(|| node.parse_if(P_INC, node_builder, value_builder, cache_builder))
+            .parse_if_any(&[INC, P_INC], node_builder, value_builder, cache_builder)?
             .unwrap_or(ImmOrPNode::Imm(1));
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let valid_value_set = node
+            .next_if(VALID_VALUE_SET)
+            .map(|n| convert_to_int_list(&n.text().view()))
+            .transpose()?
+            .unwrap_or_default();
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation: IntegerRepresentation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let p_selected: Vec<NodeId> =
-            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder);
+            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder)?;
 
         // Deduce min and max value based on representation if not specified.
         let min = min.unwrap_or_else(|| {
-            let id = value_builder.store(representation.deduce_min());
+            let info = node.value_info(MIN, node_builder);
+            let id = value_builder.store_with_info(representation.deduce_min(), info);
             ImmOrPNode::Imm(id)
         });
         let max = max.unwrap_or_else(|| {
-            let id = value_builder.store(representation.deduce_max());
+            let info = node.value_info(MAX, node_builder);
+            let id = value_builder.store_with_info(representation.deduce_max(), info);
             ImmOrPNode::Imm(id)
         });
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             streamable,
@@ -71,10 +102,11 @@ impl Parse for IntegerNode {
             min,
             max,
             inc,
+            valid_value_set,
             unit,
             representation,
             p_selected,
-        }
+        })
     }
 }
 
@@ -82,7 +114,13 @@ impl Parse for IntegerNode {
 mod tests {
     use crate::{elem_type::ValueKind, interface::INode, store::ValueStore};
 
-    use super::{super::utils::tests::parse_default, *};
+    use super::{
+        super::{
+            utils::tests::{parse_default, try_parse_default},
+            ParseError,
+        },
+        *,
+    };
 
     #[test]
     fn test_integer_node_with_immediate() {
@@ -129,6 +167,21 @@ mod tests {
         assert_eq!(p_selected[1], node_builder.get_or_intern("Selected1"));
     }
 
+    #[test]
+    fn test_integer_node_with_valid_value_set() {
+        let xml = r#"
+            <Integer Name = "TestNode">
+                <Value>4</Value>
+                <Min>1</Min>
+                <Max>16</Max>
+                <ValidValueSet>8;1; 0x4;2</ValidValueSet>
+            </Integer>
+            "#;
+
+        let (node, ..): (IntegerNode, _, _, _) = parse_default(xml);
+        assert_eq!(node.valid_value_set_elem(), &[1, 2, 4, 8]);
+    }
+
     #[test]
     fn test_integer_node_with_p_value() {
         let xml = r#"
@@ -213,4 +266,32 @@ mod tests {
             ImmOrPNode::PNode(node_builder.get_or_intern("pValueDefaultNode"))
         );
     }
+
+    #[test]
+    fn test_integer_node_with_invalid_literal() {
+        let parse = |elem: &str| {
+            let xml = format!(r#"<Integer Name="TestNode">{}</Integer>"#, elem);
+            match try_parse_default::<IntegerNode>(&xml) {
+                Err(ParseError::InvalidLiteral { literal, reason }) => (literal, reason),
+                _ => panic!("`{}` must be rejected", elem),
+            }
+        };
+
+        assert_eq!(
+            parse("<Value>9223372036854775808</Value>"),
+            ("9223372036854775808".into(), "value out of range".into())
+        );
+        assert_eq!(
+            parse("<Value>0x1_</Value>"),
+            ("0x1_".into(), "digit separator at invalid position".into())
+        );
+        assert_eq!(
+            parse("<Value>0</Value><Min>+</Min>"),
+            ("+".into(), "no digit".into())
+        );
+        assert_eq!(
+            parse("<Value>0</Value><ValidValueSet>1;x</ValidValueSet>").0,
+            "x"
+        );
+    }
 }
@@ -0,0 +1,251 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Indexing of node elements for lazy materialization.
+//!
+//! [`index_nodes`] scans the document once with the tokenizer and records the span of each node
+//! element without building the XML tree nor [`NodeData`], then [`parse_node_element`] parses a
+//! recorded span on demand.
+
+use core::ops::Range;
+
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+use crate::{
+    builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
+    store::{DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeData},
+    RegisterDescription, SchemaVersion,
+};
+
+use super::{
+    elem_name::{GROUP, NAME, P_INCLUDE, REGISTER_DESCRIPTION, STRUCT_ENTRY},
+    xml, ParseError, ParseResult, ParseWarning,
+};
+
+use crate::compat::{String, Vec};
+
+/// Result of [`index_nodes`].
+pub(crate) struct NodeIndex<'a> {
+    pub(crate) reg_desc: RegisterDescription,
+    /// Names of nodes defined by each node element and the span of the element.
+    pub(crate) nodes: Vec<(Vec<&'a str>, Range<usize>)>,
+}
+
+/// An element whose start tag is being read.
+struct Pending<'a> {
+    tag: &'a str,
+    start: usize,
+    is_node: bool,
+}
+
+/// A node element which is being read.
+struct Current<'a> {
+    tag: &'a str,
+    names: Vec<&'a str>,
+    start: usize,
+    depth: usize,
+}
+
+/// Records spans of node elements, i.e. children of `RegisterDescription` and `Group`.
+///
+/// A node element may define multiple nodes, e.g. `StructReg` defines a node for each
+/// `StructEntry`. Returns [`ParseError::UnresolvedDocument`] if the document includes external
+/// documents because they aren't indexed.
+pub(crate) fn index_nodes(xml: &str) -> ParseResult<NodeIndex<'_>> {
+    let mut stack: Vec<&str> = vec![];
+    let mut pending: Option<Pending> = None;
+    let mut current: Option<Current> = None;
+    let mut root: Option<String> = None;
+    let mut nodes = vec![];
+
+    for token in Tokenizer::from(xml) {
+        match token? {
+            Token::ElementStart { local, span, .. } => {
+                let depth = stack.len();
+                let is_node = depth == 1 || (depth == 2 && stack[1] == GROUP);
+                if is_node {
+                    current = Some(Current {
+                        tag: local.as_str(),
+                        names: vec![],
+                        start: span.start(),
+                        depth,
+                    });
+                }
+                pending = Some(Pending {
+                    tag: local.as_str(),
+                    start: span.start(),
+                    is_node,
+                });
+            }
+
+            Token::Attribute { local, value, .. } if local.as_str() == NAME => {
+                if let (Some(pending), Some(current)) = (&pending, &mut current) {
+                    if pending.is_node || pending.tag == STRUCT_ENTRY {
+                        current.names.push(value.as_str());
+                    }
+                }
+            }
+
+            Token::ElementEnd { end, span } => {
+                let end_pos = span.end();
+                match end {
+                    ElementEnd::Open => {
+                        let pending = pending.take().unwrap();
+                        if stack.is_empty() {
+                            root = Some(format!(
+                                "{}</{}>",
+                                &xml[pending.start..end_pos],
+                                pending.tag
+                            ));
+                        }
+                        stack.push(pending.tag);
+                    }
+                    ElementEnd::Empty => {
+                        let pending = pending.take().unwrap();
+                        if stack.is_empty() {
+                            root = Some(xml[pending.start..end_pos].into());
+                        }
+                    }
+                    ElementEnd::Close(_, local) => {
+                        let tag = stack
+                            .pop()
+                            .ok_or_else(|| ParseError::UnmatchedCloseTag(local.as_str().into()))?;
+                        if tag != local.as_str() {
+                            return Err(ParseError::UnexpectedCloseTag {
+                                expected: tag.into(),
+                                actual: local.as_str().into(),
+                            });
+                        }
+                    }
+                }
+
+                if matches!(&current, Some(current) if current.depth == stack.len()) {
+                    let current = current.take().unwrap();
+                    if current.tag == P_INCLUDE {
+                        let element = &xml[current.start..end_pos];
+                        let reference = element
+                            .find('>')
+                            .zip(element.rfind('<'))
+                            .and_then(|(start, end)| element.get(start + 1..end))
+                            .unwrap_or_default();
+                        return Err(ParseError::UnresolvedDocument(reference.trim().into()));
+                    }
+                    nodes.push((current.names, current.start..end_pos));
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    if let Some(tag) = stack.pop() {
+        return Err(ParseError::UnclosedElement(tag.into()));
+    }
+    let root = root.ok_or(ParseError::NoRootElement)?;
+    let document = xml::Document::from_str(&root)?;
+    let mut root_node = document.root_node();
+    if root_node.tag_name() != REGISTER_DESCRIPTION {
+        return Err(ParseError::NoRootElement);
+    }
+    let reg_desc: RegisterDescription = root_node.parse(
+        &mut DefaultNodeStore::new(),
+        &mut DefaultValueStore::new(),
+        &mut DefaultCacheStore::new(),
+    )?;
+
+    Ok(NodeIndex { reg_desc, nodes })
+}
+
+/// Parses a node element indexed by [`index_nodes`].
+///
+/// Nodes nested in the element, e.g. `EnumEntry`, are stored to `node_builder`, and the nodes
+/// defined by the element itself are returned along with the warnings reported while parsing.
+/// The element is parsed by the rules of `schema_version`, i.e. the version of the whole
+/// document.
+pub(crate) fn parse_node_element(
+    xml: &str,
+    node_builder: &mut impl NodeStoreBuilder,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+    schema_version: SchemaVersion,
+    strict: bool,
+) -> ParseResult<(Vec<NodeData>, Vec<ParseWarning>)> {
+    let mut document = xml::Document::from_str(xml)?;
+    document.set_schema_version(schema_version);
+    document.set_strict(strict);
+    let mut node = document.root_node();
+    let nodes = node.parse(node_builder, value_builder, cache_builder)?;
+    Ok((nodes, document.take_warnings()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_nodes() {
+        let xml = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Enumeration Name="Mode">
+                <EnumEntry Name="Off">
+                    <Value>0</Value>
+                </EnumEntry>
+                <Value>0</Value>
+            </Enumeration>
+
+            <Group Comment="Registers">
+                <StructReg Comment="Flags">
+                    <Address>0x10</Address>
+                    <Length>4</Length>
+                    <pPort>Device</pPort>
+                    <Endianess>LittleEndian</Endianess>
+                    <StructEntry Name="FlagA">
+                        <Bit>0</Bit>
+                    </StructEntry>
+                    <StructEntry Name="FlagB">
+                        <Bit>1</Bit>
+                    </StructEntry>
+                </StructReg>
+                <Port Name="Device" />
+            </Group>
+        </RegisterDescription>
+        "#;
+
+        let index = index_nodes(xml).unwrap();
+        assert_eq!(index.reg_desc.model_name(), "CameleonModel");
+
+        let names: Vec<_> = index.nodes.iter().map(|(names, _)| names.clone()).collect();
+        assert_eq!(
+            names,
+            [vec!["Mode"], vec!["FlagA", "FlagB"], vec!["Device"]]
+        );
+
+        let (_, span) = &index.nodes[2];
+        assert_eq!(&xml[span.clone()], r#"<Port Name="Device" />"#);
+
+        let xml = xml.replace(
+            r#"<Port Name="Device" />"#,
+            "<Port Name=\"Device\" />\n<pInclude> Common.xml </pInclude>",
+        );
+        assert!(matches!(
+            index_nodes(&xml),
+            Err(ParseError::UnresolvedDocument(reference)) if reference == "Common.xml"
+        ));
+    }
+}
@@ -0,0 +1,369 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Numeric literals in `GenApi` documents.
+//!
+//! Integer literals are decimal, hexadecimal with `0x` prefix or binary with `0b` prefix, and may
+//! have a leading sign. Digits may be grouped with `_`, and digits of decimal integers may also
+//! be grouped by three with `,`, e.g. `1,000,000`.
+//!
+//! Float literals are decimal, hexadecimal with `0x` prefix and an optional binary exponent, e.g.
+//! `0x1.8p3`, or one of `INF`, `-INF` and `NaN`. Digits may be grouped with `_`, and `,` is either
+//! a thousands separator or a decimal comma, see [`parse_float`].
+//!
+//! Parsing and formatting never depend on the locale.
+
+use core::fmt;
+
+use crate::compat::String;
+
+/// An error of a numeric literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum LiteralError {
+    /// The literal has no digit.
+    Empty,
+
+    /// The literal has an invalid character.
+    InvalidDigit(char),
+
+    /// Digits are grouped at invalid positions.
+    InvalidSeparator,
+
+    /// The value doesn't fit in the type.
+    Overflow,
+
+    /// A negative value is given for an unsigned type.
+    Negative,
+
+    /// The literal consists of valid characters, but isn't well-formed, e.g. `1.2.3`.
+    Malformed,
+}
+
+impl fmt::Display for LiteralError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no digit"),
+            Self::InvalidDigit(c) => write!(f, "invalid digit `{}`", c),
+            Self::InvalidSeparator => write!(f, "digit separator at invalid position"),
+            Self::Overflow => write!(f, "value out of range"),
+            Self::Negative => write!(f, "negative value for unsigned type"),
+            Self::Malformed => write!(f, "malformed literal"),
+        }
+    }
+}
+
+type LiteralResult<T> = core::result::Result<T, LiteralError>;
+
+/// Parses an integer literal.
+///
+/// Hexadecimal and binary literals without sign are regarded as bit patterns, so values above
+/// `i64::MAX`, e.g. `0xFFFFFFFFFFFFFFFF`, wrap around to negative values.
+pub(super) fn parse_int(value: &str) -> LiteralResult<i64> {
+    let (negative, radix, digits) = split_int(value)?;
+    let magnitude = parse_magnitude(digits, radix)?;
+    if negative {
+        if magnitude > i64::MIN.unsigned_abs() {
+            Err(LiteralError::Overflow)
+        } else {
+            Ok((magnitude as i64).wrapping_neg())
+        }
+    } else if radix == 10 && magnitude > i64::MAX as u64 {
+        Err(LiteralError::Overflow)
+    } else {
+        Ok(magnitude as i64)
+    }
+}
+
+/// Parses an unsigned integer literal.
+pub(super) fn parse_uint(value: &str) -> LiteralResult<u64> {
+    let (negative, radix, digits) = split_int(value)?;
+    let magnitude = parse_magnitude(digits, radix)?;
+    if negative && magnitude != 0 {
+        Err(LiteralError::Negative)
+    } else {
+        Ok(magnitude)
+    }
+}
+
+/// Parses a float literal.
+///
+/// Vendor quirks are tolerated: a decimal comma, e.g. `1,5`, Fortran style exponents, e.g.
+/// `1.5D3`, an empty exponent, e.g. `1.5e`, and `INF`, `Infinity` and `NaN` in any case.
+pub(super) fn parse_float(value: &str) -> LiteralResult<f64> {
+    let (negative, rest) = split_sign(value.trim());
+    let magnitude = if rest.eq_ignore_ascii_case("INF") || rest.eq_ignore_ascii_case("Infinity") {
+        f64::INFINITY
+    } else if rest.eq_ignore_ascii_case("NaN") {
+        return Ok(f64::NAN);
+    } else if let Some(hex) = strip_prefix_ignore_case(rest, "0x") {
+        parse_hex_float(hex)?
+    } else {
+        let digits = normalize_decimal_float(rest)?;
+        if !digits.bytes().any(|b| b.is_ascii_digit()) {
+            return Err(LiteralError::Empty);
+        }
+        if let Some(c) = digits
+            .chars()
+            .find(|c| !matches!(c, '0'..='9' | '.' | 'e' | '+' | '-'))
+        {
+            return Err(LiteralError::InvalidDigit(c));
+        }
+        // `str::parse` accepts only `.` as the decimal point regardless of the locale.
+        digits.parse().map_err(|_| LiteralError::Malformed)?
+    };
+    Ok(if negative { -magnitude } else { magnitude })
+}
+
+/// Formats a float so that the parser reads back exactly the same value regardless of the locale,
+/// e.g. for writing documents.
+///
+/// Infinities and NaN are formatted as `INF`, `-INF` and `NaN`. Very large or small values are
+/// formatted in exponential notation, e.g. `1.5e-7`.
+#[must_use]
+pub fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value.is_infinite() {
+        if value.is_sign_positive() {
+            "INF".into()
+        } else {
+            "-INF".into()
+        }
+    } else if value == 0.0 || (1e-5..1e16).contains(&value.abs()) {
+        format!("{}", value)
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+/// Normalizes a decimal float for `str::parse`.
+///
+/// Commas are thousands separators if the literal has a decimal point or more than one comma,
+/// otherwise a comma is the decimal point, e.g. `1,500` is `1.5`.
+fn normalize_decimal_float(value: &str) -> LiteralResult<String> {
+    let value = value.to_ascii_lowercase().replace('d', "e");
+    let (mantissa, exponent) = match value.find('e') {
+        Some(pos) => value.split_at(pos),
+        None => (value.as_str(), ""),
+    };
+
+    let mantissa = if mantissa.matches(',').count() == 1 && !mantissa.contains('.') {
+        remove_separators(&mantissa.replace(',', "."), 10, false)?
+    } else {
+        let (int_part, frac_part) = match mantissa.find('.') {
+            Some(pos) => mantissa.split_at(pos),
+            None => (mantissa, ""),
+        };
+        let mut mantissa = remove_separators(int_part, 10, true)?;
+        mantissa.push_str(&remove_separators(frac_part, 10, false)?);
+        mantissa
+    };
+
+    // An exponent without digits, e.g. `e` or `e+`, is ignored.
+    let exponent = if exponent.bytes().any(|b| b.is_ascii_digit()) {
+        exponent
+    } else {
+        ""
+    };
+    Ok(mantissa + exponent)
+}
+
+/// Splits an integer literal into its sign, radix and digits.
+fn split_int(value: &str) -> LiteralResult<(bool, u32, &str)> {
+    let (negative, rest) = split_sign(value.trim());
+    if let Some(digits) = strip_prefix_ignore_case(rest, "0x") {
+        Ok((negative, 16, digits))
+    } else if let Some(digits) = strip_prefix_ignore_case(rest, "0b") {
+        Ok((negative, 2, digits))
+    } else {
+        Ok((negative, 10, rest))
+    }
+}
+
+fn split_sign(value: &str) -> (bool, &str) {
+    if let Some(rest) = value.strip_prefix('-') {
+        (true, rest)
+    } else {
+        (false, value.strip_prefix('+').unwrap_or(value))
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(value: &'a str, prefix: &str) -> Option<&'a str> {
+    if value.len() >= prefix.len() && value[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        Some(&value[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn parse_magnitude(digits: &str, radix: u32) -> LiteralResult<u64> {
+    let digits = remove_separators(digits, radix, radix == 10)?;
+    if digits.is_empty() {
+        return Err(LiteralError::Empty);
+    }
+    digits.chars().try_fold(0_u64, |acc, c| {
+        let digit = c.to_digit(radix).ok_or(LiteralError::InvalidDigit(c))?;
+        acc.checked_mul(u64::from(radix))
+            .and_then(|acc| acc.checked_add(u64::from(digit)))
+            .ok_or(LiteralError::Overflow)
+    })
+}
+
+/// Removes digit separators. `_` must be placed between digits, and `,` must group the integer
+/// part by three digits.
+fn remove_separators(digits: &str, radix: u32, allow_comma: bool) -> LiteralResult<String> {
+    let is_digit = |c: char| c.is_digit(radix);
+
+    let bytes = digits.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'_' {
+            let prev = i.checked_sub(1).map(|i| char::from(bytes[i]));
+            let next = bytes.get(i + 1).map(|&b| char::from(b));
+            if !prev.is_some_and(is_digit) || !next.is_some_and(is_digit) {
+                return Err(LiteralError::InvalidSeparator);
+            }
+        }
+    }
+
+    if digits.contains(',') {
+        if !allow_comma {
+            return Err(LiteralError::InvalidDigit(','));
+        }
+        let mut groups = digits.split(',');
+        let first = groups.next().unwrap_or_default();
+        let valid = (1..=3).contains(&first.len())
+            && groups.all(|group| group.len() == 3)
+            && !digits.contains('_');
+        if !valid {
+            return Err(LiteralError::InvalidSeparator);
+        }
+    }
+
+    Ok(digits.chars().filter(|&c| c != '_' && c != ',').collect())
+}
+
+/// Parses a hexadecimal float without its prefix, e.g. `1.8p3`.
+fn parse_hex_float(value: &str) -> LiteralResult<f64> {
+    let (mantissa, exponent) = match value.find(['p', 'P']) {
+        Some(pos) => (&value[..pos], parse_int(&value[pos + 1..])?),
+        None => (value, 0),
+    };
+    let mantissa = remove_separators(mantissa, 16, false)?;
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(pos) => (&mantissa[..pos], &mantissa[pos + 1..]),
+        None => (mantissa.as_str(), ""),
+    };
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(LiteralError::Empty);
+    }
+
+    let mut result = 0.0;
+    for c in int_part.chars() {
+        let digit = c.to_digit(16).ok_or(LiteralError::InvalidDigit(c))?;
+        result = result * 16.0 + f64::from(digit);
+    }
+    let mut scale = 1.0 / 16.0;
+    for c in frac_part.chars() {
+        let digit = c.to_digit(16).ok_or(LiteralError::InvalidDigit(c))?;
+        result += f64::from(digit) * scale;
+        scale /= 16.0;
+    }
+
+    // Exponents beyond the range of `f64` saturate to zero or infinity anyway.
+    let exponent = exponent.clamp(-1100, 1100);
+    let factor = if exponent < 0 { 0.5 } else { 2.0 };
+    for _ in 0..exponent.unsigned_abs() {
+        result *= factor;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_int() {
+        assert_eq!(parse_int("10"), Ok(10));
+        assert_eq!(parse_int(" +5 "), Ok(5));
+        assert_eq!(parse_int("-0x10"), Ok(-16));
+        assert_eq!(parse_int("0b1010"), Ok(10));
+        assert_eq!(parse_int("1,000,000"), Ok(1_000_000));
+        assert_eq!(parse_int("0xFFFF_FFFF"), Ok(0xFFFF_FFFF));
+        assert_eq!(parse_int("0xFFFFFFFFFFFFFFFF"), Ok(-1));
+        assert_eq!(parse_int("-9223372036854775808"), Ok(i64::MIN));
+
+        assert_eq!(
+            parse_int("9223372036854775808"),
+            Err(LiteralError::Overflow)
+        );
+        assert_eq!(
+            parse_int("0x1FFFFFFFFFFFFFFFF"),
+            Err(LiteralError::Overflow)
+        );
+        assert_eq!(parse_int("10,00"), Err(LiteralError::InvalidSeparator));
+        assert_eq!(parse_int("_1"), Err(LiteralError::InvalidSeparator));
+        assert_eq!(parse_int("0b102"), Err(LiteralError::InvalidDigit('2')));
+        assert_eq!(parse_int("0x"), Err(LiteralError::Empty));
+    }
+
+    #[test]
+    fn test_parse_uint() {
+        assert_eq!(parse_uint("0xFFFFFFFFFFFFFFFF"), Ok(u64::MAX));
+        assert_eq!(parse_uint("18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(parse_uint("-0"), Ok(0));
+        assert_eq!(parse_uint("-1"), Err(LiteralError::Negative));
+    }
+
+    #[test]
+    fn test_parse_float() {
+        assert_eq!(parse_float("1.5"), Ok(1.5));
+        assert_eq!(parse_float("+1e3"), Ok(1000.0));
+        assert_eq!(parse_float("1_000.5"), Ok(1000.5));
+        assert_eq!(parse_float("0x1.8p3"), Ok(12.0));
+        assert_eq!(parse_float("-0x10"), Ok(-16.0));
+        assert_eq!(parse_float("0x1p-2"), Ok(0.25));
+        assert_eq!(parse_float("INF"), Ok(f64::INFINITY));
+        assert_eq!(parse_float("-INF"), Ok(f64::NEG_INFINITY));
+        assert!(parse_float("NaN").unwrap().is_nan());
+
+        // Vendor quirks.
+        assert_eq!(parse_float("1,5"), Ok(1.5));
+        assert_eq!(parse_float("1,000.5"), Ok(1000.5));
+        assert_eq!(parse_float("1,000,000"), Ok(1_000_000.0));
+        assert_eq!(parse_float("1.5D3"), Ok(1500.0));
+        assert_eq!(parse_float("1.5E+03"), Ok(1500.0));
+        assert_eq!(parse_float("1.5e"), Ok(1.5));
+        assert_eq!(parse_float("-inf"), Ok(f64::NEG_INFINITY));
+        assert_eq!(parse_float("Infinity"), Ok(f64::INFINITY));
+
+        assert_eq!(parse_float("abc"), Err(LiteralError::Empty));
+        assert_eq!(parse_float("1x"), Err(LiteralError::InvalidDigit('x')));
+        assert_eq!(parse_float("1.2.3"), Err(LiteralError::Malformed));
+        assert_eq!(parse_float("10,00.5"), Err(LiteralError::InvalidSeparator));
+    }
+
+    #[test]
+    fn test_format_float() {
+        assert_eq!(format_float(1.5), "1.5");
+        assert_eq!(format_float(-1000.0), "-1000");
+        assert_eq!(format_float(1.5e-7), "1.5e-7");
+        assert_eq!(format_float(1e300), "1e300");
+        assert_eq!(format_float(f64::INFINITY), "INF");
+        assert_eq!(format_float(f64::NEG_INFINITY), "-INF");
+        assert_eq!(format_float(f64::NAN), "NaN");
+
+        for &value in &[
+            0.1,
+            -0.0,
+            1.0 / 3.0,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            123_456.789,
+        ] {
+            let parsed = parse_float(&format_float(value)).unwrap();
+            assert_eq!(parsed.to_bits(), value.to_bits());
+        }
+    }
+}
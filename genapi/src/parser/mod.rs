@@ -6,6 +6,7 @@ mod boolean;
 mod category;
 mod command;
 mod converter;
+mod diagnostics;
 mod elem_name;
 mod elem_type;
 mod enumeration;
@@ -17,13 +18,21 @@ mod int_converter;
 mod int_reg;
 mod int_swiss_knife;
 mod integer;
+#[cfg(feature = "std")]
+mod lazy;
+mod literal;
 mod masked_int_reg;
 mod node;
 mod node_base;
+#[cfg(feature = "parallel")]
+mod parallel;
 mod port;
 mod register;
 mod register_base;
 mod register_description;
+#[cfg(feature = "parallel")]
+mod remap;
+mod schema;
 mod string;
 mod string_reg;
 mod struct_reg;
@@ -31,60 +40,258 @@ mod swiss_knife;
 mod utils;
 mod xml;
 
+pub use diagnostics::{ParseDiagnostics, ParseWarning};
+#[cfg(feature = "std")]
+pub(crate) use lazy::{index_nodes, parse_node_element};
+pub use literal::format_float;
+#[cfg(feature = "parallel")]
+pub use parallel::{parse_parallel, parse_parallel_with_diagnostics};
+
 use group::GroupNode;
 use struct_reg::StructRegNode;
 use thiserror::Error;
 
 use crate::{
     builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
-    store::NodeData,
+    store::{DuplicateNode, DuplicatePolicy, NodeData},
     RegisterDescription,
 };
 
 use elem_name::{
     ADV_FEATURE_LOCK, BOOLEAN, CATEGORY, COMMAND, CONF_ROM, CONVERTER, ENUMERATION, FLOAT,
     FLOAT_REG, GROUP, INTEGER, INT_CONVERTER, INT_KEY, INT_REG, INT_SWISS_KNIFE, MASKED_INT_REG,
-    NODE, PORT, REGISTER, SMART_FEATURE, STRING, STRING_REG, STRUCT_REG, SWISS_KNIFE, TEXT_DESC,
+    NODE, PORT, P_INCLUDE, REGISTER, SMART_FEATURE, STRING, STRING_REG, STRUCT_REG, SWISS_KNIFE,
+    TEXT_DESC,
 };
 
+use crate::compat::{Box, String, ToOwned, Vec};
+
 #[derive(Debug, Error)]
 pub enum ParseError {
     #[error("encodings must be UTF8: {0}")]
-    Utf8Error(#[from] std::str::Utf8Error),
+    Utf8Error(#[from] core::str::Utf8Error),
 
+    /// `xmlparser::Error` implements `Error` only when `std` feature is enabled.
     #[error("invalid XML syntax: {0}")]
-    InvalidSyntax(#[from] roxmltree::Error),
+    InvalidSyntax(#[cfg_attr(feature = "std", source)] xmlparser::Error),
+
+    #[error("expected close tag of `{expected}`, but got `{actual}`")]
+    UnexpectedCloseTag { expected: String, actual: String },
+
+    #[error("element `{0}` is not closed")]
+    UnclosedElement(String),
+
+    #[error("unknown entity reference `{0}`")]
+    UnknownEntity(String),
+
+    #[error("document has no root element")]
+    NoRootElement,
+
+    #[error("unexpected close tag `{0}`")]
+    UnmatchedCloseTag(String),
+
+    #[error("invalid formula: {0}")]
+    InvalidFormula(String),
+
+    #[error(
+        "node `{}` is defined in both document {} and document {}",
+        .0.name, .0.kept_document, .0.discarded_document
+    )]
+    DuplicateNode(DuplicateNode),
+
+    #[error("failed to resolve the external document `{0}`")]
+    UnresolvedDocument(String),
+
+    /// A mandatory child element is absent or has an unexpected tag name. Has the name of the
+    /// node, or the tag name of the element if it isn't a node.
+    #[error("mandatory element is missing in `{0}`")]
+    MissingElement(String),
+
+    #[error("mandatory attribute `{attribute}` is missing in element `{element}`")]
+    MissingAttribute { element: String, attribute: String },
+
+    #[error("invalid literal `{literal}`: {reason}")]
+    InvalidLiteral { literal: String, reason: String },
 }
 
-pub type ParseResult<T> = std::result::Result<T, ParseError>;
+impl From<xmlparser::Error> for ParseError {
+    fn from(err: xmlparser::Error) -> Self {
+        Self::InvalidSyntax(err)
+    }
+}
+
+pub type ParseResult<T> = core::result::Result<T, ParseError>;
+
+/// Resolves references to external documents, i.e. `pInclude` elements.
+///
+/// A reference may be a URL, a local path or a file in the device manifest depending on the
+/// document, so resolution is left to the user. Closures of `FnMut(&str) -> Option<String>`
+/// implement this trait.
+pub trait DocumentResolver {
+    /// Returns the content of the document referred by `reference`, or `None` if it can't be
+    /// resolved.
+    fn resolve(&mut self, reference: &str) -> Option<String>;
+}
+
+impl<F> DocumentResolver for F
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    fn resolve(&mut self, reference: &str) -> Option<String> {
+        self(reference)
+    }
+}
 
+/// A [`DocumentResolver`] which resolves nothing.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoResolver;
+
+impl DocumentResolver for NoResolver {
+    fn resolve(&mut self, _: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Parses `xml`. Returns [`ParseError::UnresolvedDocument`] if the document includes external
+/// documents, use [`parse_with_resolver`] to parse such documents.
 pub fn parse(
     xml: &impl AsRef<str>,
     node_builder: &mut impl NodeStoreBuilder,
     value_builder: &mut impl ValueStoreBuilder,
     cache_builder: &mut impl CacheStoreBuilder,
 ) -> ParseResult<RegisterDescription> {
-    let document = xml::Document::from_str(xml.as_ref())?;
+    parse_with_resolver(
+        xml,
+        &mut NoResolver,
+        node_builder,
+        value_builder,
+        cache_builder,
+    )
+}
+
+/// Parses `xml` along with the external documents it includes, merging all nodes into the same
+/// store.
+///
+/// Included documents are parsed before the nodes of the including document, so the including
+/// document overrides included nodes of the same `MergePriority`. Each document is parsed only
+/// once even if it's included more than once. The returned [`RegisterDescription`] is the one of
+/// `xml`.
+pub fn parse_with_resolver(
+    xml: &impl AsRef<str>,
+    resolver: &mut impl DocumentResolver,
+    node_builder: &mut impl NodeStoreBuilder,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+) -> ParseResult<RegisterDescription> {
+    parse_with_diagnostics(
+        xml,
+        resolver,
+        &mut ParseDiagnostics::new(),
+        node_builder,
+        value_builder,
+        cache_builder,
+    )
+}
+
+/// Same as [`parse_with_resolver`], but non-fatal problems found while parsing are reported to
+/// `diagnostics` instead of being ignored.
+pub fn parse_with_diagnostics(
+    xml: &impl AsRef<str>,
+    resolver: &mut impl DocumentResolver,
+    diagnostics: &mut ParseDiagnostics,
+    node_builder: &mut impl NodeStoreBuilder,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+) -> ParseResult<RegisterDescription> {
+    let mut included = vec![];
+    let reg_desc = parse_document(
+        xml.as_ref(),
+        resolver,
+        &mut included,
+        diagnostics,
+        node_builder,
+        value_builder,
+        cache_builder,
+    )?;
+    check_duplicates(node_builder)?;
+    node_builder.set_schema_version(reg_desc.schema_version());
+
+    Ok(reg_desc)
+}
+
+fn parse_document(
+    xml: &str,
+    resolver: &mut impl DocumentResolver,
+    included: &mut Vec<String>,
+    diagnostics: &mut ParseDiagnostics,
+    node_builder: &mut impl NodeStoreBuilder,
+    value_builder: &mut impl ValueStoreBuilder,
+    cache_builder: &mut impl CacheStoreBuilder,
+) -> ParseResult<RegisterDescription> {
+    let mut document = xml::Document::from_str(xml)?;
+    document.set_strict(diagnostics.is_strict());
     let mut node = document.root_node();
-    let reg_desc = node.parse(node_builder, value_builder, cache_builder);
-    while let Some(ref mut child) = node.next() {
-        let children: Vec<NodeData> = child.parse(node_builder, value_builder, cache_builder);
+    let reg_desc: RegisterDescription = node.parse(node_builder, value_builder, cache_builder)?;
+    let version = reg_desc.schema_version();
+    if !version.is_supported() {
+        node.report(ParseWarning::UnsupportedSchemaVersion { version });
+    }
+
+    let mut children = vec![];
+    while let Some(child) = node.next() {
+        if child.tag_name() == P_INCLUDE {
+            let reference = child.text().view().trim().to_owned();
+            if included.contains(&reference) {
+                continue;
+            }
+            let included_xml = resolver
+                .resolve(&reference)
+                .ok_or_else(|| ParseError::UnresolvedDocument(reference.clone()))?;
+            included.push(reference);
+            parse_document(
+                &included_xml,
+                resolver,
+                included,
+                diagnostics,
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?;
+        } else {
+            children.push(child);
+        }
+    }
+
+    node_builder.begin_document();
+    for mut child in children {
+        let children: Vec<NodeData> = child.parse(node_builder, value_builder, cache_builder)?;
         for child in children {
             let id = child.node_base().id();
             node_builder.store_node(id, child);
         }
     }
+    diagnostics.extend(document.take_warnings());
 
     Ok(reg_desc)
 }
 
-trait Parse {
+/// Returns an error if a duplicated node is found and the policy doesn't allow it.
+fn check_duplicates(node_builder: &impl NodeStoreBuilder) -> ParseResult<()> {
+    match node_builder.duplicates().first() {
+        Some(duplicate) if node_builder.duplicate_policy() == DuplicatePolicy::Error => {
+            Err(ParseError::DuplicateNode(duplicate.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+trait Parse: Sized {
     fn parse(
         node: &mut xml::Node,
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self;
+    ) -> ParseResult<Self>;
 }
 
 impl Parse for Vec<NodeData> {
@@ -94,112 +301,502 @@ impl Parse for Vec<NodeData> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        match node.tag_name() {
+    ) -> ParseResult<Self> {
+        let tag_name = node.tag_name();
+        let mut nodes = match tag_name {
             NODE => vec![NodeData::Node(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             CATEGORY => vec![NodeData::Category(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INTEGER => vec![NodeData::Integer(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INT_REG => vec![NodeData::IntReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             MASKED_INT_REG => vec![NodeData::MaskedIntReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             BOOLEAN => vec![NodeData::Boolean(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             COMMAND => vec![NodeData::Command(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             ENUMERATION => vec![NodeData::Enumeration(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             FLOAT => vec![NodeData::Float(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             FLOAT_REG => vec![NodeData::FloatReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             STRING => vec![NodeData::String(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             STRING_REG => vec![NodeData::StringReg(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             REGISTER => vec![NodeData::Register(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             CONVERTER => vec![NodeData::Converter(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INT_CONVERTER => vec![NodeData::IntConverter(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             SWISS_KNIFE => vec![NodeData::SwissKnife(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             INT_SWISS_KNIFE => vec![NodeData::IntSwissKnife(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             PORT => vec![NodeData::Port(Box::new(node.parse(
                 node_builder,
                 value_builder,
                 cache_builder,
-            )))],
+            )?))],
             STRUCT_REG => {
-                let node: StructRegNode = node.parse(node_builder, value_builder, cache_builder);
+                let node: StructRegNode = node.parse(node_builder, value_builder, cache_builder)?;
                 node.into_masked_int_regs(cache_builder)
                     .into_iter()
                     .map(|node| NodeData::MaskedIntReg(node.into()))
                     .collect()
             }
             GROUP => {
-                let node: GroupNode = node.parse(node_builder, value_builder, cache_builder);
+                let node: GroupNode = node.parse(node_builder, value_builder, cache_builder)?;
                 node.nodes
             }
-            // TODO: Implement DCAM specific ndoes.
-            CONF_ROM | TEXT_DESC | INT_KEY | ADV_FEATURE_LOCK | SMART_FEATURE => todo!(),
-            _ => unreachable!(),
+            // TODO: Implement DCAM specific nodes. They are skipped as unknown elements until then.
+            CONF_ROM | TEXT_DESC | INT_KEY | ADV_FEATURE_LOCK | SMART_FEATURE => {
+                node.report(ParseWarning::UnknownElement {
+                    owner: None,
+                    element: tag_name.into(),
+                });
+                return Ok(vec![]);
+            }
+            unknown => {
+                node.report(ParseWarning::UnknownElement {
+                    owner: None,
+                    element: unknown.into(),
+                });
+                return Ok(vec![]);
+            }
+        };
+        // Unknown elements of a node are kept as its extensions. Those of `Group` and `StructReg`
+        // aren't attached because the elements don't belong to each of the resulting nodes.
+        let extensions = node.skip_rest();
+        if !extensions.is_empty() && !matches!(tag_name, GROUP | STRUCT_REG) {
+            if let Some(elem_base) = nodes.first_mut().and_then(NodeData::elem_base_mut) {
+                elem_base.extensions.extend(extensions);
+            }
+        }
+        Ok(nodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        elem_type::MergePriority,
+        store::{DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeStore},
+        SchemaVersion,
+    };
+
+    const WIDTH: &str = r#"<Integer Name="Width"><Value>1</Value></Integer>"#;
+    const LOW_WIDTH: &str =
+        r#"<Integer Name="Width" MergePriority="-1"><Value>2</Value></Integer>"#;
+
+    fn document(nodes: &str) -> String {
+        format!(
+            r#"<RegisterDescription
+              ModelName="CameleonModel"
+              VendorName="CameleonVendor"
+              StandardNameSpace="None"
+              SchemaMajorVersion="1"
+              SchemaMinorVersion="1"
+              SchemaSubMinorVersion="0"
+              MajorVersion="1"
+              MinorVersion="2"
+              SubMinorVersion="3"
+              ProductGuid="01234567-0123-0123-0123-0123456789ab"
+              VersionGuid="76543210-3210-3210-3210-ba9876543210"
+              xmlns="http://www.genicam.org/GenApi/Version_1_0"
+              xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+              xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+              {}
+            </RegisterDescription>"#,
+            nodes
+        )
+    }
+
+    fn parse_all(node_store: &mut DefaultNodeStore, nodes: &[&str]) -> ParseResult<()> {
+        let mut value_store = DefaultValueStore::new();
+        let mut cache_store = DefaultCacheStore::new();
+        for nodes in nodes {
+            let xml = document(nodes);
+            parse(&xml, node_store, &mut value_store, &mut cache_store)?;
         }
+        Ok(())
+    }
+
+    fn width_priority(node_store: &DefaultNodeStore) -> MergePriority {
+        let width = node_store.id_by_name("Width").unwrap();
+        node_store.node(width).node_base().merge_priority()
+    }
+
+    #[test]
+    fn test_duplicate_policy() {
+        let mut node_store = DefaultNodeStore::new();
+        parse_all(&mut node_store, &[WIDTH, LOW_WIDTH]).unwrap();
+        assert_eq!(width_priority(&node_store), MergePriority::Mid);
+        assert_eq!(
+            node_store.duplicates(),
+            &[DuplicateNode {
+                name: "Width".into(),
+                kept_document: 0,
+                discarded_document: 1,
+            }]
+        );
+
+        let mut node_store = DefaultNodeStore::new();
+        parse_all(&mut node_store, &[LOW_WIDTH, WIDTH]).unwrap();
+        assert_eq!(width_priority(&node_store), MergePriority::Mid);
+        assert_eq!(node_store.duplicates()[0].kept_document, 1);
+
+        let mut node_store =
+            DefaultNodeStore::new().with_duplicate_policy(DuplicatePolicy::FirstWins);
+        parse_all(&mut node_store, &[LOW_WIDTH, WIDTH]).unwrap();
+        assert_eq!(width_priority(&node_store), MergePriority::Low);
+        assert_eq!(node_store.duplicates().len(), 1);
+
+        let mut node_store = DefaultNodeStore::new().with_duplicate_policy(DuplicatePolicy::Error);
+        assert!(matches!(
+            parse_all(&mut node_store, &[WIDTH, WIDTH]),
+            Err(ParseError::DuplicateNode(_))
+        ));
+    }
+
+    #[test]
+    fn test_include() {
+        let main = document(
+            r#"<pInclude>features.xml</pInclude>
+            <Category Name="Root"><pFeature>Width</pFeature></Category>
+            <Integer Name="Width"><Value>1</Value></Integer>"#,
+        );
+        let features = document(
+            r#"<pInclude>features.xml</pInclude>
+            <Integer Name="Width"><Value>2</Value></Integer>
+            <Integer Name="Height"><Value>3</Value></Integer>"#,
+        );
+        let mut requested = vec![];
+        let mut resolver = |reference: &str| {
+            requested.push(reference.to_owned());
+            Some(features.clone())
+        };
+
+        let mut node_store = DefaultNodeStore::new();
+        parse_with_resolver(
+            &main,
+            &mut resolver,
+            &mut node_store,
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+        // A document is requested only once even if it includes itself.
+        assert_eq!(requested, ["features.xml"]);
+        assert!(node_store.id_by_name("Height").is_some());
+        // The including document overrides the included one.
+        assert_eq!(node_store.duplicates()[0].kept_document, 1);
+
+        let mut node_store = DefaultNodeStore::new();
+        assert!(matches!(
+            parse(
+                &main,
+                &mut node_store,
+                &mut DefaultValueStore::new(),
+                &mut DefaultCacheStore::new(),
+            ),
+            Err(ParseError::UnresolvedDocument(reference)) if reference == "features.xml"
+        ));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let xml = document(
+            r#"<VendorExtension><Foo/></VendorExtension>
+            <Integer Name="Width">
+                <IsDeprecated>Yes</IsDeprecated>
+                <Value>100</Value>
+                <Min>1</Min>
+                <Max>10</Max>
+                <VendorElement>1</VendorElement>
+            </Integer>
+            <Float Name="Gain"><Value>0.5</Value><Min>0</Min><Max>1</Max></Float>"#,
+        );
+        let mut diagnostics = ParseDiagnostics::new();
+        let mut node_store = DefaultNodeStore::new();
+        parse_with_diagnostics(
+            &xml,
+            &mut NoResolver,
+            &mut diagnostics,
+            &mut node_store,
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+
+        assert!(node_store.id_by_name("Width").is_some());
+        assert_eq!(
+            diagnostics.warnings(),
+            &[
+                ParseWarning::UnknownElement {
+                    owner: None,
+                    element: "VendorExtension".into(),
+                },
+                ParseWarning::DeprecatedNode {
+                    node: "Width".into(),
+                },
+                ParseWarning::OutOfRange {
+                    node: "Width".into(),
+                    value: "100".into(),
+                    min: "1".into(),
+                    max: "10".into(),
+                },
+                ParseWarning::UnknownElement {
+                    owner: Some("Width".into()),
+                    element: "VendorElement".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extensions() {
+        let xml = document(
+            r#"<Integer Name="Width">
+                <Extension><Vendor Key="A">1</Vendor></Extension>
+                <Value>100</Value>
+                <VendorElement>1</VendorElement>
+            </Integer>"#,
+        );
+        let mut node_store = DefaultNodeStore::new();
+        parse(
+            &xml,
+            &mut node_store,
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+
+        let nid = node_store.id_by_name("Width").unwrap();
+        assert_eq!(
+            node_store.node(nid).node_base().extensions(),
+            &[
+                r#"<Extension><Vendor Key="A">1</Vendor></Extension>"#,
+                "<VendorElement>1</VendorElement>",
+            ]
+        );
+    }
+
+    const LOOSE_WIDTH: &str = r#"<Integer Name="Width">
+            <Value>1</Value>
+            <Representation> linear</Representation>
+        </Integer>"#;
+
+    #[test]
+    fn test_non_canonical_text() {
+        let mut diagnostics = ParseDiagnostics::new();
+        let mut node_store = DefaultNodeStore::new();
+        parse_with_diagnostics(
+            &document(LOOSE_WIDTH),
+            &mut NoResolver,
+            &mut diagnostics,
+            &mut node_store,
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+
+        assert!(node_store.id_by_name("Width").is_some());
+        assert_eq!(
+            diagnostics.warnings(),
+            &[ParseWarning::NonCanonicalText {
+                owner: Some("Width".into()),
+                element: "Representation".into(),
+                text: " linear".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_non_canonical_text_strict() {
+        let err = parse_with_diagnostics(
+            &document(LOOSE_WIDTH),
+            &mut NoResolver,
+            &mut ParseDiagnostics::strict(),
+            &mut DefaultNodeStore::new(),
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidLiteral { literal, .. } if literal == " linear"
+        ));
+    }
+
+    #[test]
+    fn test_malformed_document() {
+        let parse_xml = |xml: &str| {
+            parse(
+                &xml,
+                &mut DefaultNodeStore::new(),
+                &mut DefaultValueStore::new(),
+                &mut DefaultCacheStore::new(),
+            )
+        };
+
+        assert!(matches!(
+            parse_xml("<RegisterDescription/>"),
+            Err(ParseError::MissingAttribute { element, attribute })
+                if element == "RegisterDescription" && attribute == "ModelName"
+        ));
+        assert!(matches!(
+            parse_xml(&document(r#"<Integer><Value>1</Value></Integer>"#)),
+            Err(ParseError::MissingAttribute { element, attribute })
+                if element == "Integer" && attribute == "Name"
+        ));
+        assert!(matches!(
+            parse_xml(&document(r#"<Integer Name="Width"></Integer>"#)),
+            Err(ParseError::MissingElement(owner)) if owner == "Width"
+        ));
+        assert!(matches!(
+            parse_xml(&document(
+                r#"<Integer Name="Width"><Value>1</Value><Unknown/></Integer>
+                <IntReg Name="WidthReg"><Address>0</Address></IntReg>"#
+            )),
+            Err(ParseError::MissingElement(owner)) if owner == "WidthReg"
+        ));
+        assert!(matches!(
+            parse_xml(&document(
+                r#"<Boolean Name="Flag"><Value>Maybe</Value></Boolean>"#
+            )),
+            Err(ParseError::InvalidLiteral { literal, .. }) if literal == "Maybe"
+        ));
+    }
+
+    #[test]
+    fn test_dcam_nodes_are_skipped() {
+        let mut diagnostics = ParseDiagnostics::new();
+        let mut node_store = DefaultNodeStore::new();
+        parse_with_diagnostics(
+            &document(
+                r#"<ConfRom Name="Rom"><Unit>0</Unit></ConfRom>
+                <Integer Name="Width"><Value>1</Value></Integer>"#,
+            ),
+            &mut NoResolver,
+            &mut diagnostics,
+            &mut node_store,
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+
+        assert!(node_store.id_by_name("Width").is_some());
+        assert_eq!(
+            diagnostics.warnings(),
+            &[ParseWarning::UnknownElement {
+                owner: None,
+                element: "ConfRom".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let xml = document(
+            r#"<Integer Name="Width"><pAlias>Height</pAlias><Value>1</Value></Integer>
+            <Integer Name="Height"><Value>1</Value></Integer>"#,
+        );
+        let parse_xml = |xml: &str| {
+            let mut diagnostics = ParseDiagnostics::new();
+            let mut node_store = DefaultNodeStore::new();
+            parse_with_diagnostics(
+                &xml,
+                &mut NoResolver,
+                &mut diagnostics,
+                &mut node_store,
+                &mut DefaultValueStore::new(),
+                &mut DefaultCacheStore::new(),
+            )
+            .unwrap();
+            (node_store, diagnostics)
+        };
+
+        let (node_store, diagnostics) = parse_xml(&xml);
+        assert_eq!(node_store.schema_version(), Some(SchemaVersion::V1_1));
+        assert!(diagnostics.is_empty());
+
+        let (node_store, diagnostics) =
+            parse_xml(&xml.replace(r#"SchemaMinorVersion="1""#, r#"SchemaMinorVersion="0""#));
+        assert_eq!(node_store.schema_version(), Some(SchemaVersion::V1_0));
+        assert_eq!(
+            diagnostics.warnings(),
+            &[ParseWarning::UnavailableElement {
+                owner: Some("Width".into()),
+                element: "pAlias".into(),
+                since: SchemaVersion::V1_1,
+            }]
+        );
+
+        let (_, diagnostics) =
+            parse_xml(&xml.replace(r#"SchemaMajorVersion="1""#, r#"SchemaMajorVersion="2""#));
+        assert_eq!(
+            diagnostics.warnings(),
+            &[ParseWarning::UnsupportedSchemaVersion {
+                version: SchemaVersion::new(2, 1, 0),
+            }]
+        );
     }
 }
@@ -10,7 +10,7 @@ use crate::{
     Node,
 };
 
-use super::{elem_name::NODE, xml, Parse};
+use super::{elem_name::NODE, xml, Parse, ParseResult};
 
 impl Parse for Node {
     #[tracing::instrument(level = "trace", skip(node_builder, value_builder, cache_builder))]
@@ -19,17 +19,17 @@ impl Parse for Node {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `Node`");
         debug_assert_eq!(node.tag_name(), NODE);
 
-        let attr_base = NodeAttributeBase::parse(node, node_builder, value_builder, cache_builder);
-        let elem_base = NodeElementBase::parse(node, node_builder, value_builder, cache_builder);
+        let attr_base = NodeAttributeBase::parse(node, node_builder, value_builder, cache_builder)?;
+        let elem_base = NodeElementBase::parse(node, node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
-        }
+        })
     }
 }
 
@@ -115,6 +115,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_documentation() {
+        let xml = r#"
+            <Node Name = "TestNode">
+                <DisplayName> Test Node </DisplayName>
+                <Description>
+                    The first line.
+                    The second line.
+                </Description>
+                <ToolTip>tooltip</ToolTip>
+                <DocuURL> http://FOO.com </DocuURL>
+            </Node>
+            "#;
+
+        let (node, ..): (Node, _, _, _) = parse_default(xml);
+        let node_base = node.node_base();
+        assert_eq!(node_base.tooltip(), Some("tooltip"));
+        assert_eq!(
+            node_base.description(),
+            Some("The first line.\nThe second line.")
+        );
+        assert_eq!(node_base.display_name(), Some("Test Node"));
+        assert_eq!(node_base.docu_url(), Some("http://FOO.com"));
+    }
+
     #[test]
     fn test_default() {
         let xml = r#"
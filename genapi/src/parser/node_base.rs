@@ -9,41 +9,51 @@ use crate::{
 };
 
 use super::{
+    diagnostics::ParseWarning,
     elem_name::{
         DESCRIPTION, DISPLAY_NAME, DOCU_URL, EVENT_ID, EXPOSE_STATIC, EXTENSION,
         IMPOSED_ACCESS_MODE, IS_DEPRECATED, MERGE_PRIORITY, NAME, NAME_SPACE, P_ALIAS,
         P_BLOCK_POLLING, P_CAST_ALIAS, P_ERROR, P_INVALIDATOR, P_IS_AVAILABLE, P_IS_IMPLEMENTED,
         P_IS_LOCKED, TOOL_TIP, VISIBILITY,
     },
-    elem_type::convert_to_bool,
-    xml, Parse,
+    elem_type::{
+        convert_to_bool, convert_to_merge_priority, convert_to_name_space, invalid_literal,
+    },
+    xml, Parse, ParseResult,
 };
 
+use crate::compat::String;
+
 impl Parse for NodeAttributeBase {
     fn parse(
         node: &mut xml::Node,
         node_builder: &mut impl NodeStoreBuilder,
         _: &mut impl ValueStoreBuilder,
         _: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        let name = node.attribute_of(NAME).unwrap();
+    ) -> ParseResult<Self> {
+        let name = node.required_attribute_of(NAME)?;
         let id = node_builder.get_or_intern(name);
         let name_space = node
             .attribute_of(NAME_SPACE)
-            .map(|text| text.into())
+            .map(convert_to_name_space)
+            .transpose()?
             .unwrap_or_default();
         let merge_priority = node
             .attribute_of(MERGE_PRIORITY)
-            .map(|text| text.into())
+            .map(convert_to_merge_priority)
+            .transpose()?
             .unwrap_or_default();
-        let expose_static = node.attribute_of(EXPOSE_STATIC).map(convert_to_bool);
+        let expose_static = node
+            .attribute_of(EXPOSE_STATIC)
+            .map(convert_to_bool)
+            .transpose()?;
 
-        Self {
+        Ok(Self {
             id,
             name_space,
             merge_priority,
             expose_static,
-        }
+        })
     }
 }
 
@@ -53,46 +63,72 @@ impl Parse for NodeElementBase {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
-        // Ignore Extension element.
-        let _extension: Option<String> =
-            node.parse_if(EXTENSION, node_builder, value_builder, cache_builder);
+    ) -> ParseResult<Self> {
+        // Extension element is kept as raw XML, unknown elements are appended to it later.
+        let extensions = node
+            .next_if(EXTENSION)
+            .map(|extension| extension.raw().into())
+            .into_iter()
+            .collect();
 
-        let tooltip = node.parse_if(TOOL_TIP, node_builder, value_builder, cache_builder);
-        let description = node.parse_if(DESCRIPTION, node_builder, value_builder, cache_builder);
-        let display_name = node.parse_if(DISPLAY_NAME, node_builder, value_builder, cache_builder);
+        // Documentation elements are accepted in any order, since vendors often don't follow the
+        // order of the schema.
+        let mut tooltip = None;
+        let mut description = None;
+        let mut display_name = None;
+        while let Some(next) = node.peek() {
+            let slot = match next.tag_name() {
+                TOOL_TIP => &mut tooltip,
+                DESCRIPTION => &mut description,
+                DISPLAY_NAME => &mut display_name,
+                _ => break,
+            };
+            *slot = node.next().map(|n| normalize_doc_text(&n.text().view()));
+        }
         let visibility = node
-            .parse_if(VISIBILITY, node_builder, value_builder, cache_builder)
+            .parse_if(VISIBILITY, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let docu_url = node.parse_if(DOCU_URL, node_builder, value_builder, cache_builder);
+        let docu_url = node
+            .next_if(DOCU_URL)
+            .map(|n| n.text().view().trim().into());
         let is_deprecated = node
-            .parse_if(IS_DEPRECATED, node_builder, value_builder, cache_builder)
+            .parse_if(IS_DEPRECATED, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
+        if is_deprecated {
+            node.report(ParseWarning::DeprecatedNode {
+                node: node.owner_name().unwrap_or_default().into(),
+            });
+        }
         let event_id = node
             .next_if(EVENT_ID)
-            .map(|n| u64::from_str_radix(&n.text().view(), 16).unwrap());
+            .map(|n| {
+                let text = n.text().view();
+                u64::from_str_radix(&text, 16).map_err(|e| invalid_literal(&text, e))
+            })
+            .transpose()?;
         let p_is_implemented =
-            node.parse_if(P_IS_IMPLEMENTED, node_builder, value_builder, cache_builder);
+            node.parse_if(P_IS_IMPLEMENTED, node_builder, value_builder, cache_builder)?;
         let p_is_available =
-            node.parse_if(P_IS_AVAILABLE, node_builder, value_builder, cache_builder);
-        let p_is_locked = node.parse_if(P_IS_LOCKED, node_builder, value_builder, cache_builder);
+            node.parse_if(P_IS_AVAILABLE, node_builder, value_builder, cache_builder)?;
+        let p_is_locked = node.parse_if(P_IS_LOCKED, node_builder, value_builder, cache_builder)?;
         let p_block_polling =
-            node.parse_if(P_BLOCK_POLLING, node_builder, value_builder, cache_builder);
+            node.parse_if(P_BLOCK_POLLING, node_builder, value_builder, cache_builder)?;
         let imposed_access_mode = node
             .parse_if(
                 IMPOSED_ACCESS_MODE,
                 node_builder,
                 value_builder,
                 cache_builder,
-            )
+            )?
             .unwrap_or(AccessMode::RW);
-        let p_errors = node.parse_while(P_ERROR, node_builder, value_builder, cache_builder);
-        let p_alias = node.parse_if(P_ALIAS, node_builder, value_builder, cache_builder);
-        let p_cast_alias = node.parse_if(P_CAST_ALIAS, node_builder, value_builder, cache_builder);
+        let p_errors = node.parse_while(P_ERROR, node_builder, value_builder, cache_builder)?;
+        let p_alias = node.parse_if(P_ALIAS, node_builder, value_builder, cache_builder)?;
+        let p_cast_alias =
+            node.parse_if(P_CAST_ALIAS, node_builder, value_builder, cache_builder)?;
         let p_invalidators =
-            node.parse_while(P_INVALIDATOR, node_builder, value_builder, cache_builder);
+            node.parse_while(P_INVALIDATOR, node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             tooltip,
             description,
             display_name,
@@ -109,6 +145,20 @@ impl Parse for NodeElementBase {
             p_alias,
             p_cast_alias,
             p_invalidators,
+            extensions,
+        })
+    }
+}
+
+/// Normalizes a documentation text for display. Indentation of each line and blank lines around
+/// the text are removed, which come from the layout of the document.
+fn normalize_doc_text(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for line in text.trim().lines() {
+        if !normalized.is_empty() {
+            normalized.push('\n');
         }
+        normalized.push_str(line.trim());
     }
+    normalized
 }
@@ -0,0 +1,505 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Parallel parsing of top-level nodes.
+//!
+//! Each top-level node element is parsed by a rayon worker with its own builders, which intern
+//! node names and store values locally without sharing anything. Parsed nodes, values and
+//! invalidators are merged into the shared builders in document order at the end, and the ids
+//! local to each worker are remapped to the ids of the shared builders, see [`super::remap`].
+//! Merging in document order makes every id the same as the one assigned by [`super::parse`].
+
+use rayon::prelude::*;
+use string_interner::Symbol;
+
+use crate::{
+    builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
+    store::{NodeData, NodeId, ValueData, ValueId, ValueInfo},
+    RegisterDescription,
+};
+
+use super::{
+    check_duplicates,
+    elem_name::P_INCLUDE,
+    remap::{IdMap, Remap},
+    xml, DocumentResolver, NoResolver, ParseDiagnostics, ParseError, ParseResult, ParseWarning,
+};
+
+use crate::compat::{HashMap, String, ToOwned, Vec};
+
+/// Parses `xml` like [`super::parse`], but parses top-level nodes in parallel.
+///
+/// External documents aren't resolved, so [`ParseError::UnresolvedDocument`] is returned if the
+/// document includes any. Use [`parse_parallel_with_diagnostics`] to parse such documents.
+pub fn parse_parallel<T, U, S>(
+    xml: &impl AsRef<str>,
+    node_builder: &mut T,
+    value_builder: &mut U,
+    cache_builder: &mut S,
+) -> ParseResult<RegisterDescription>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    parse_parallel_with_diagnostics(
+        xml,
+        &mut NoResolver,
+        &mut ParseDiagnostics::new(),
+        node_builder,
+        value_builder,
+        cache_builder,
+    )
+}
+
+/// Same as [`super::parse_with_diagnostics`], but parses top-level nodes of each document in
+/// parallel.
+///
+/// Warnings are reported in the order workers find them, which may differ between runs.
+pub fn parse_parallel_with_diagnostics<T, U, S>(
+    xml: &impl AsRef<str>,
+    resolver: &mut impl DocumentResolver,
+    diagnostics: &mut ParseDiagnostics,
+    node_builder: &mut T,
+    value_builder: &mut U,
+    cache_builder: &mut S,
+) -> ParseResult<RegisterDescription>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    let mut included = vec![];
+    let reg_desc = parse_document(
+        xml.as_ref(),
+        resolver,
+        &mut included,
+        diagnostics,
+        node_builder,
+        value_builder,
+        cache_builder,
+    )?;
+    check_duplicates(node_builder)?;
+    node_builder.set_schema_version(reg_desc.schema_version());
+
+    Ok(reg_desc)
+}
+
+/// Parallel version of [`super::parse_document`].
+fn parse_document<T, U, S>(
+    xml: &str,
+    resolver: &mut impl DocumentResolver,
+    included: &mut Vec<String>,
+    diagnostics: &mut ParseDiagnostics,
+    node_builder: &mut T,
+    value_builder: &mut U,
+    cache_builder: &mut S,
+) -> ParseResult<RegisterDescription>
+where
+    T: NodeStoreBuilder,
+    U: ValueStoreBuilder,
+    S: CacheStoreBuilder,
+{
+    let mut document = xml::Document::from_str(xml)?;
+    document.set_strict(diagnostics.is_strict());
+    let mut node = document.root_node();
+    let reg_desc: RegisterDescription = node.parse(node_builder, value_builder, cache_builder)?;
+    let version = reg_desc.schema_version();
+    if !version.is_supported() {
+        node.report(ParseWarning::UnsupportedSchemaVersion { version });
+    }
+
+    let mut children = vec![];
+    while let Some(child) = node.next() {
+        if child.tag_name() == P_INCLUDE {
+            let reference = child.text().view().trim().to_owned();
+            if included.contains(&reference) {
+                continue;
+            }
+            let included_xml = resolver
+                .resolve(&reference)
+                .ok_or_else(|| ParseError::UnresolvedDocument(reference.clone()))?;
+            included.push(reference);
+            parse_document(
+                &included_xml,
+                resolver,
+                included,
+                diagnostics,
+                node_builder,
+                value_builder,
+                cache_builder,
+            )?;
+        } else {
+            children.push(child);
+        }
+    }
+
+    let forks = children
+        .into_par_iter()
+        .map(|mut child| {
+            let mut node_builder = LocalNodeBuilder::default();
+            let mut value_builder = LocalValueBuilder::default();
+            let mut cache_builder = LocalCacheBuilder::default();
+            let children: Vec<NodeData> =
+                child.parse(&mut node_builder, &mut value_builder, &mut cache_builder)?;
+            node_builder.nodes.extend(children);
+            Ok((node_builder, value_builder, cache_builder))
+        })
+        .collect::<ParseResult<Vec<_>>>()?;
+
+    node_builder.begin_document();
+    for (local_nodes, local_values, local_invalidators) in forks {
+        let mut map = IdMap {
+            nodes: local_nodes.merge_names(node_builder),
+            values: vec![],
+        };
+        map.values = local_values.merge(&map, value_builder);
+        for mut data in local_nodes.nodes {
+            data.remap(&map);
+            node_builder.store_node(data.node_base().id(), data);
+        }
+        for (invalidator, target) in local_invalidators.0 {
+            cache_builder.store_invalidator(map.node(invalidator), map.node(target));
+        }
+    }
+    diagnostics.extend(document.take_warnings());
+
+    Ok(reg_desc)
+}
+
+/// Interns node names and buffers nodes locally.
+#[derive(Default)]
+struct LocalNodeBuilder {
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    nodes: Vec<NodeData>,
+    /// Local fresh ids, and the local id of the name interned right after each of them if the
+    /// name is made of the fresh id, e.g. `$On_0` for an `EnumEntry`.
+    fresh_ids: Vec<(u32, Option<NodeId>)>,
+}
+
+impl LocalNodeBuilder {
+    /// Interns the names to `node_builder` in the order they are interned locally, and returns
+    /// the ids of the shared builder indexed by the local ids.
+    ///
+    /// Fresh ids are also taken from `node_builder` in order, and the names made of local fresh
+    /// ids are renamed with them.
+    fn merge_names(&self, node_builder: &mut impl NodeStoreBuilder) -> Vec<NodeId> {
+        let mut renames = HashMap::new();
+        for (local_fresh_id, nid) in &self.fresh_ids {
+            let fresh_id = node_builder.fresh_id();
+            if let Some(nid) = nid {
+                let name = &self.names[nid.to_usize()];
+                let prefix = &name[..name.len() - local_fresh_id.to_string().len()];
+                renames.insert(*nid, format!("{}{}", prefix, fresh_id));
+            }
+        }
+
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let nid = NodeId::try_from_usize(i).unwrap();
+                match renames.get(&nid) {
+                    Some(name) => node_builder.get_or_intern(name),
+                    None => node_builder.get_or_intern(name),
+                }
+            })
+            .collect()
+    }
+}
+
+impl NodeStoreBuilder for LocalNodeBuilder {
+    type Store = Vec<NodeData>;
+
+    fn build(self) -> Self::Store {
+        self.nodes
+    }
+
+    fn store_node(&mut self, _: NodeId, data: NodeData) {
+        self.nodes.push(data);
+    }
+
+    fn get_or_intern<U>(&mut self, node_name: U) -> NodeId
+    where
+        U: AsRef<str>,
+    {
+        let node_name = node_name.as_ref();
+        if let Some(nid) = self.ids.get(node_name) {
+            return *nid;
+        }
+
+        let nid = NodeId::try_from_usize(self.names.len())
+            .expect("the number of nodes stored in `NodeStore` must not exceed u32::MAX");
+        if let Some((fresh_id, fresh_nid)) = self.fresh_ids.last_mut() {
+            if fresh_nid.is_none() && node_name.ends_with(&fresh_id.to_string()) {
+                *fresh_nid = Some(nid);
+            }
+        }
+        self.names.push(node_name.into());
+        self.ids.insert(node_name.into(), nid);
+        nid
+    }
+
+    fn fresh_id(&mut self) -> u32 {
+        let id = self.fresh_ids.len() as u32;
+        self.fresh_ids.push((id, None));
+        id
+    }
+}
+
+/// Stores values locally.
+#[derive(Default)]
+struct LocalValueBuilder(Vec<(ValueData, Option<ValueInfo>)>);
+
+impl LocalValueBuilder {
+    /// Stores the values to `value_builder` in the order they are stored locally, and returns the
+    /// ids of the shared builder indexed by the local ids. Node ids of `map` must be filled.
+    fn merge(self, map: &IdMap, value_builder: &mut impl ValueStoreBuilder) -> Vec<ValueId> {
+        self.0
+            .into_iter()
+            .map(|(data, info)| match info {
+                Some(mut info) => {
+                    info.remap(map);
+                    value_builder.store_with_info(data, info)
+                }
+                None => value_builder.store(data),
+            })
+            .collect()
+    }
+
+    fn push<V: From<ValueId>>(&mut self, data: ValueData, info: Option<ValueInfo>) -> V {
+        let vid = ValueId::from_u32(self.0.len() as u32);
+        self.0.push((data, info));
+        vid.into()
+    }
+}
+
+impl ValueStoreBuilder for LocalValueBuilder {
+    type Store = Vec<(ValueData, Option<ValueInfo>)>;
+
+    fn build(self) -> Self::Store {
+        self.0
+    }
+
+    fn store<U, V>(&mut self, data: U) -> V
+    where
+        U: Into<ValueData>,
+        V: From<ValueId>,
+    {
+        self.push(data.into(), None)
+    }
+
+    fn store_with_info<U, V>(&mut self, data: U, info: ValueInfo) -> V
+    where
+        U: Into<ValueData>,
+        V: From<ValueId>,
+    {
+        self.push(data.into(), Some(info))
+    }
+}
+
+/// Buffers invalidators locally.
+#[derive(Default)]
+struct LocalCacheBuilder(Vec<(NodeId, NodeId)>);
+
+impl CacheStoreBuilder for LocalCacheBuilder {
+    type Store = Vec<(NodeId, NodeId)>;
+
+    fn build(self) -> Self::Store {
+        self.0
+    }
+
+    fn store_invalidator(&mut self, invalidator: NodeId, target: NodeId) {
+        self.0.push((invalidator, target));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::GenApiBuilder,
+        store::{CacheStore, DefaultCacheStore, DefaultNodeStore, DefaultValueStore, NodeStore},
+    };
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Category Name="Root" NameSpace="Standard">
+                <pFeature>Mode</pFeature>
+                <pFeature>Width</pFeature>
+            </Category>
+
+            <Enumeration Name="Mode">
+                <EnumEntry Name="Off">
+                    <Value>0</Value>
+                </EnumEntry>
+                <EnumEntry Name="On">
+                    <Value>1</Value>
+                </EnumEntry>
+                <Value>0</Value>
+            </Enumeration>
+
+            <Integer Name="Width">
+                <pValue>WidthReg</pValue>
+                <Min>8</Min>
+                <Max>1024</Max>
+            </Integer>
+
+            <IntReg Name="WidthReg">
+                <Address>0x10</Address>
+                <Length>4</Length>
+                <pPort>Device</pPort>
+                <pInvalidator>Mode</pInvalidator>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+
+            <Port Name="Device" NameSpace="Standard">
+            </Port>
+
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_parse_parallel() {
+        let (_, store, _) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+
+        let mut node_builder = DefaultNodeStore::new();
+        let mut value_builder = DefaultValueStore::new();
+        let mut cache_builder = DefaultCacheStore::new();
+        let reg_desc = parse_parallel(
+            &XML,
+            &mut node_builder,
+            &mut value_builder,
+            &mut cache_builder,
+        )
+        .unwrap();
+        assert_eq!(reg_desc.model_name(), "CameleonModel");
+
+        let names = |store: &DefaultNodeStore| {
+            let mut names = vec![];
+            store.visit_nodes(|data| names.push(data.node_base().id().name(store).to_string()));
+            names.sort();
+            names
+        };
+        assert_eq!(names(&node_builder), names(&store));
+
+        // Ids are the same as the ones assigned by the serial parser.
+        for name in ["Root", "Mode", "Width", "WidthReg", "Device"] {
+            assert_eq!(node_builder.id_by_name(name), store.id_by_name(name));
+        }
+
+        let mode = node_builder.id_by_name("Mode").unwrap();
+        let width_reg = node_builder.id_by_name("WidthReg").unwrap();
+        cache_builder.cache(width_reg, 0x10, 4, &[0; 4]);
+        cache_builder.invalidate_by(mode);
+        assert!(cache_builder.get_cache(width_reg, 0x10, 4).is_none());
+        assert!(node_builder
+            .id_by_name("Width")
+            .unwrap()
+            .expect_iinteger_kind(&node_builder)
+            .is_ok());
+    }
+
+    /// Parses `xml` with the serial and the parallel parser, and returns the debug outputs of the
+    /// stores.
+    fn parse_both(xml: &str, resolver: impl DocumentResolver + Clone) -> [[String; 3]; 2] {
+        let dump = |node_store: &DefaultNodeStore,
+                    value_store: &DefaultValueStore,
+                    diagnostics: &ParseDiagnostics| {
+            let mut nodes = vec![];
+            node_store.visit_nodes(|data| {
+                nodes.push(format!(
+                    "{}: {:?}",
+                    data.node_base().id().name(node_store),
+                    data
+                ));
+            });
+            let mut warnings: Vec<_> = diagnostics
+                .warnings()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            warnings.sort();
+            [
+                nodes.join("\n"),
+                format!("{:?}", value_store),
+                warnings.join("\n"),
+            ]
+        };
+
+        let mut node_store = DefaultNodeStore::new();
+        let mut value_store = DefaultValueStore::with_value_info();
+        let mut diagnostics = ParseDiagnostics::new();
+        crate::parser::parse_with_diagnostics(
+            &xml,
+            &mut resolver.clone(),
+            &mut diagnostics,
+            &mut node_store,
+            &mut value_store,
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+        let serial = dump(&node_store, &value_store, &diagnostics);
+
+        let mut node_store = DefaultNodeStore::new();
+        let mut value_store = DefaultValueStore::with_value_info();
+        let mut diagnostics = ParseDiagnostics::new();
+        parse_parallel_with_diagnostics(
+            &xml,
+            &mut resolver.clone(),
+            &mut diagnostics,
+            &mut node_store,
+            &mut value_store,
+            &mut DefaultCacheStore::new(),
+        )
+        .unwrap();
+        let parallel = dump(&node_store, &value_store, &diagnostics);
+
+        [serial, parallel]
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let xml = include_str!("../../tests/conformance/basic.xml");
+        let [serial, parallel] = parse_both(xml, NoResolver);
+        assert_eq!(serial, parallel);
+
+        // Nodes of included documents and warnings are merged as well.
+        let xml = XML.replace(
+            r#"<Port Name="Device" NameSpace="Standard">"#,
+            r#"<pInclude>Extra.xml</pInclude>
+            <Port Name="Device" NameSpace="Standard">"#,
+        );
+        let extra = XML
+            .replace(r#"Name="Root""#, r#"Name="ExtraRoot""#)
+            .replace(
+                r#"<Port Name="Device""#,
+                r#"<VendorExtension/>
+            <Port Name="Device""#,
+            );
+        let resolver = |reference: &str| (reference == "Extra.xml").then(|| extra.clone());
+        let [serial, parallel] = parse_both(&xml, resolver);
+        assert_eq!(serial, parallel);
+        assert!(serial[0].contains("ExtraRoot"));
+        assert!(!serial[2].is_empty());
+    }
+}
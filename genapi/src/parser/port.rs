@@ -12,7 +12,8 @@ use crate::{
 
 use super::{
     elem_name::{CACHE_CHUNK_DATA, CHUNK_ID, PORT, P_CHUNK_ID, SWAP_ENDIANNESS},
-    xml, Parse,
+    elem_type::invalid_literal,
+    xml, Parse, ParseResult,
 };
 
 impl Parse for PortNode {
@@ -22,39 +23,36 @@ impl Parse for PortNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `PortNode`");
         debug_assert_eq!(node.tag_name(), PORT);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
-        let chunk_id = node.next_if(CHUNK_ID).map_or_else(
-            || {
-                node.next_if(P_CHUNK_ID).map(|next_node| {
-                    ImmOrPNode::PNode(node_builder.get_or_intern(next_node.text().view()))
-                })
-            },
-            |next_node| {
-                Some(ImmOrPNode::Imm(
-                    u64::from_str_radix(&next_node.text().view(), 16).unwrap(),
-                ))
-            },
-        );
+        let chunk_id = if let Some(next_node) = node.next_if(CHUNK_ID) {
+            let text = next_node.text().view();
+            let chunk_id = u64::from_str_radix(&text, 16).map_err(|e| invalid_literal(&text, e))?;
+            Some(ImmOrPNode::Imm(chunk_id))
+        } else {
+            node.next_if(P_CHUNK_ID).map(|next_node| {
+                ImmOrPNode::PNode(node_builder.get_or_intern(next_node.text().view()))
+            })
+        };
         let swap_endianness = node
-            .parse_if(SWAP_ENDIANNESS, node_builder, value_builder, cache_builder)
+            .parse_if(SWAP_ENDIANNESS, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let cache_chunk_data = node
-            .parse_if(CACHE_CHUNK_DATA, node_builder, value_builder, cache_builder)
+            .parse_if(CACHE_CHUNK_DATA, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             chunk_id,
             swap_endianness,
             cache_chunk_data,
-        }
+        })
     }
 }
 
@@ -68,7 +66,7 @@ mod tests {
             <Port Name="TestNode">
                 <ChunkID>Fd3219</ChunkID>
                 <SwapEndianess>Yes</SwapEndianess>
-            <Port>
+            </Port>
             "#;
 
         let (node, ..): (PortNode, _, _, _) = parse_default(xml);
@@ -81,7 +79,7 @@ mod tests {
         let xml = r#"
             <Port Name="TestNode">
                 <pChunkID>Fd3219</pChunkID>
-            <Port>
+            </Port>
             "#;
 
         let (node, mut node_builder, ..): (PortNode, _, _, _) = parse_default(xml);
@@ -96,7 +94,7 @@ mod tests {
         let xml = r#"
             <Port Name="TestNode">
                 <CacheChunkData>Yes</CacheChunkData>
-            <Port>
+            </Port>
             "#;
 
         let (node, ..): (PortNode, _, _, _) = parse_default(xml);
@@ -0,0 +1,264 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Rewriting of ids embedded in parsed nodes.
+//!
+//! Nodes parsed by a worker of [`super::parse_parallel`] refer to node names and values by ids
+//! local to the worker. [`IdMap`] maps them to the ids of the shared builders when the nodes
+//! are merged.
+
+use crate::{
+    elem_type::{AddressKind, ImmOrPNode, NamedValue, PIndex, PValue, RegPIndex, ValueKind},
+    node_base::{NodeAttributeBase, NodeElementBase},
+    store::{FloatId, IntegerId, NodeData, NodeId, StringId, ValueId, ValueInfo},
+    BooleanNode, CategoryNode, CommandNode, ConverterNode, EnumEntryNode, EnumerationNode,
+    FloatNode, FloatRegNode, IntConverterNode, IntRegNode, IntSwissKnifeNode, IntegerNode,
+    MaskedIntRegNode, Node, PortNode, RegisterBase, RegisterNode, StringNode, StringRegNode,
+    SwissKnifeNode,
+};
+
+use crate::compat::Vec;
+
+/// Maps local ids, i.e. indices of the vectors, to the ids of the shared builders.
+pub(super) struct IdMap {
+    pub(super) nodes: Vec<NodeId>,
+    pub(super) values: Vec<ValueId>,
+}
+
+impl IdMap {
+    pub(super) fn node(&self, nid: NodeId) -> NodeId {
+        self.nodes[string_interner::Symbol::to_usize(nid)]
+    }
+
+    fn value<T: From<ValueId> + Into<ValueId>>(&self, vid: T) -> T {
+        let vid: ValueId = vid.into();
+        self.values[vid.to_usize()].into()
+    }
+}
+
+/// Rewrites local ids to the ids of the shared builders.
+pub(super) trait Remap {
+    fn remap(&mut self, map: &IdMap);
+}
+
+impl Remap for NodeId {
+    fn remap(&mut self, map: &IdMap) {
+        *self = map.node(*self);
+    }
+}
+
+macro_rules! impl_remap_for_value_id {
+    ($($ty:ty),*) => {
+        $(
+            impl Remap for $ty {
+                fn remap(&mut self, map: &IdMap) {
+                    *self = map.value(*self);
+                }
+            }
+        )*
+    };
+}
+
+impl_remap_for_value_id!(IntegerId, FloatId, StringId);
+
+/// Immediate values which don't refer to anything.
+macro_rules! impl_remap_for_imm {
+    ($($ty:ty),*) => {
+        $(
+            impl Remap for $ty {
+                fn remap(&mut self, _: &IdMap) {}
+            }
+        )*
+    };
+}
+
+impl_remap_for_imm!(i64, u64, f64);
+
+impl<T: Remap> Remap for Option<T> {
+    fn remap(&mut self, map: &IdMap) {
+        if let Some(v) = self {
+            v.remap(map);
+        }
+    }
+}
+
+impl<T: Remap> Remap for Vec<T> {
+    fn remap(&mut self, map: &IdMap) {
+        for v in self {
+            v.remap(map);
+        }
+    }
+}
+
+impl<T: Remap> Remap for ImmOrPNode<T> {
+    fn remap(&mut self, map: &IdMap) {
+        match self {
+            Self::Imm(v) => v.remap(map),
+            Self::PNode(nid) => nid.remap(map),
+        }
+    }
+}
+
+impl<T: Remap> Remap for ValueKind<T> {
+    fn remap(&mut self, map: &IdMap) {
+        match self {
+            Self::Value(v) => v.remap(map),
+            Self::PValue(p_value) => p_value.remap(map),
+            Self::PIndex(p_index) => p_index.remap(map),
+        }
+    }
+}
+
+impl<T> Remap for PValue<T> {
+    fn remap(&mut self, map: &IdMap) {
+        self.p_value.remap(map);
+        self.p_value_copies.remap(map);
+    }
+}
+
+impl<T: Remap> Remap for PIndex<T> {
+    fn remap(&mut self, map: &IdMap) {
+        self.p_index.remap(map);
+        for value_indexed in &mut self.value_indexed {
+            value_indexed.indexed.remap(map);
+        }
+        self.value_default.remap(map);
+    }
+}
+
+impl Remap for NamedValue<NodeId> {
+    fn remap(&mut self, map: &IdMap) {
+        self.value.remap(map);
+    }
+}
+
+impl Remap for AddressKind {
+    fn remap(&mut self, map: &IdMap) {
+        match self {
+            Self::Address(address) => address.remap(map),
+            Self::IntSwissKnife(nid) => nid.remap(map),
+            Self::PIndex(p_index) => p_index.remap(map),
+        }
+    }
+}
+
+impl Remap for RegPIndex {
+    fn remap(&mut self, map: &IdMap) {
+        self.offset.remap(map);
+        self.p_index.remap(map);
+    }
+}
+
+impl Remap for ValueInfo {
+    fn remap(&mut self, map: &IdMap) {
+        self.owner.remap(map);
+    }
+}
+
+impl Remap for NodeAttributeBase {
+    fn remap(&mut self, map: &IdMap) {
+        self.id.remap(map);
+    }
+}
+
+impl Remap for NodeElementBase {
+    fn remap(&mut self, map: &IdMap) {
+        self.p_is_implemented.remap(map);
+        self.p_is_available.remap(map);
+        self.p_is_locked.remap(map);
+        self.p_block_polling.remap(map);
+        self.p_errors.remap(map);
+        self.p_alias.remap(map);
+        self.p_cast_alias.remap(map);
+        self.p_invalidators.remap(map);
+    }
+}
+
+impl Remap for RegisterBase {
+    fn remap(&mut self, map: &IdMap) {
+        self.elem_base.remap(map);
+        self.address_kinds.remap(map);
+        self.length.remap(map);
+        self.p_port.remap(map);
+        self.p_invalidators.remap(map);
+    }
+}
+
+/// Implements [`Remap`] for a node by remapping the listed fields.
+macro_rules! impl_remap_for_node {
+    ($ty:ty, $($field:ident),*) => {
+        impl Remap for $ty {
+            fn remap(&mut self, map: &IdMap) {
+                $(self.$field.remap(map);)*
+            }
+        }
+    };
+}
+
+impl_remap_for_node!(Node, attr_base, elem_base);
+impl_remap_for_node!(CategoryNode, attr_base, elem_base, p_features);
+impl_remap_for_node!(
+    IntegerNode,
+    attr_base,
+    elem_base,
+    value_kind,
+    min,
+    max,
+    inc,
+    p_selected
+);
+impl_remap_for_node!(IntRegNode, attr_base, register_base, p_selected);
+impl_remap_for_node!(MaskedIntRegNode, attr_base, register_base, p_selected);
+impl_remap_for_node!(BooleanNode, attr_base, elem_base, value, p_selected);
+impl_remap_for_node!(CommandNode, attr_base, elem_base, value, command_value);
+impl_remap_for_node!(
+    EnumerationNode,
+    attr_base,
+    elem_base,
+    entries,
+    value,
+    p_selected
+);
+impl_remap_for_node!(EnumEntryNode, attr_base, elem_base);
+impl_remap_for_node!(FloatNode, attr_base, elem_base, value_kind, min, max, inc);
+impl_remap_for_node!(FloatRegNode, attr_base, register_base);
+impl_remap_for_node!(StringNode, attr_base, elem_base, value);
+impl_remap_for_node!(StringRegNode, attr_base, register_base);
+impl_remap_for_node!(RegisterNode, attr_base, register_base);
+impl_remap_for_node!(ConverterNode, attr_base, elem_base, p_variables, p_value);
+impl_remap_for_node!(IntConverterNode, attr_base, elem_base, p_variables, p_value);
+impl_remap_for_node!(SwissKnifeNode, attr_base, elem_base, p_variables);
+impl_remap_for_node!(IntSwissKnifeNode, attr_base, elem_base, p_variables);
+impl_remap_for_node!(PortNode, attr_base, elem_base, chunk_id);
+
+impl Remap for NodeData {
+    fn remap(&mut self, map: &IdMap) {
+        match self {
+            Self::Node(node) => node.remap(map),
+            Self::Category(node) => node.remap(map),
+            Self::Integer(node) => node.remap(map),
+            Self::IntReg(node) => node.remap(map),
+            Self::MaskedIntReg(node) => node.remap(map),
+            Self::Boolean(node) => node.remap(map),
+            Self::Command(node) => node.remap(map),
+            Self::Enumeration(node) => node.remap(map),
+            Self::EnumEntry(node) => node.remap(map),
+            Self::Float(node) => node.remap(map),
+            Self::FloatReg(node) => node.remap(map),
+            Self::String(node) => node.remap(map),
+            Self::StringReg(node) => node.remap(map),
+            Self::Register(node) => node.remap(map),
+            Self::Converter(node) => node.remap(map),
+            Self::IntConverter(node) => node.remap(map),
+            Self::SwissKnife(node) => node.remap(map),
+            Self::IntSwissKnife(node) => node.remap(map),
+            Self::Port(node) => node.remap(map),
+            Self::ConfRom(())
+            | Self::TextDesc(())
+            | Self::IntKey(())
+            | Self::AdvFeatureLock(())
+            | Self::SmartFeature(()) => {}
+        }
+    }
+}
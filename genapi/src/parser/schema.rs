@@ -0,0 +1,50 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Differences of parsing rules between schema versions.
+
+use crate::SchemaVersion;
+
+use super::elem_name::{DOCU_URL, IS_DEPRECATED, P_ALIAS, P_CAST_ALIAS, STREAMABLE};
+
+/// Elements which are not available in all versions, along with the version which introduced
+/// them.
+const INTRODUCED_ELEMENTS: &[(&str, SchemaVersion)] = &[
+    (DOCU_URL, SchemaVersion::V1_1),
+    (IS_DEPRECATED, SchemaVersion::V1_1),
+    (P_ALIAS, SchemaVersion::V1_1),
+    (P_CAST_ALIAS, SchemaVersion::V1_1),
+    (STREAMABLE, SchemaVersion::V1_1),
+];
+
+/// Returns the version which introduced `element` if it's not available in `version`.
+///
+/// The element is still parsed, since documents often use elements of newer versions without
+/// updating their schema version.
+pub(super) fn unavailable_since(element: &str, version: SchemaVersion) -> Option<SchemaVersion> {
+    INTRODUCED_ELEMENTS
+        .iter()
+        .find(|(name, _)| *name == element)
+        .map(|(_, since)| *since)
+        .filter(|since| version < *since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unavailable_since() {
+        assert_eq!(
+            unavailable_since(P_ALIAS, SchemaVersion::V1_0),
+            Some(SchemaVersion::V1_1)
+        );
+        assert_eq!(unavailable_since(P_ALIAS, SchemaVersion::V1_1), None);
+        assert_eq!(
+            unavailable_since(DOCU_URL, SchemaVersion::new(1, 2, 0)),
+            None
+        );
+        assert_eq!(unavailable_since("Value", SchemaVersion::V1_0), None);
+    }
+}
@@ -18,9 +18,11 @@ use super::{
         ACCESS_MODE, CACHEABLE, ENDIANNESS, POLLING_TIME, P_INVALIDATOR, P_SELECTED,
         REPRESENTATION, SIGN, STREAMABLE, STRUCT_ENTRY, STRUCT_REG, UNIT,
     },
-    xml, Parse,
+    xml, Parse, ParseResult,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
 pub(super) struct StructRegNode {
     register_base: RegisterBase,
@@ -51,26 +53,26 @@ impl Parse for StructRegNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug!("start parsing `StructRegNode`");
         debug_assert_eq!(node.tag_name(), STRUCT_REG);
 
-        let register_base = node.parse(node_builder, value_builder, cache_builder);
+        let register_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let endianness = node
-            .parse_if(ENDIANNESS, node_builder, value_builder, cache_builder)
+            .parse_if(ENDIANNESS, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
         let mut entries = vec![];
-        while let Some(mut entry_node) = node.next() {
-            let entry = entry_node.parse(node_builder, value_builder, cache_builder);
+        while let Some(mut entry_node) = node.next_if(STRUCT_ENTRY) {
+            let entry = entry_node.parse(node_builder, value_builder, cache_builder)?;
             entries.push(entry);
         }
 
-        Self {
+        Ok(Self {
             register_base,
             endianness,
             entries,
-        }
+        })
     }
 }
 
@@ -168,6 +170,7 @@ impl NodeElementBase {
         merge_impl!(self, rhs, p_errors, vec);
         merge_impl!(self, rhs, p_alias);
         merge_impl!(self, rhs, p_cast_alias);
+        self.extensions.extend(rhs.extensions);
     }
 }
 
@@ -177,35 +180,37 @@ impl Parse for StructEntryNode {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Self {
+    ) -> ParseResult<Self> {
         debug_assert_eq!(node.tag_name(), STRUCT_ENTRY);
 
-        let attr_base = node.parse(node_builder, value_builder, cache_builder);
-        let elem_base = node.parse(node_builder, value_builder, cache_builder);
+        let attr_base = node.parse(node_builder, value_builder, cache_builder)?;
+        let elem_base = node.parse(node_builder, value_builder, cache_builder)?;
 
         let p_invalidators =
-            node.parse_while(P_INVALIDATOR, node_builder, value_builder, cache_builder);
+            node.parse_while(P_INVALIDATOR, node_builder, value_builder, cache_builder)?;
         let access_mode = node
-            .parse_if(ACCESS_MODE, node_builder, value_builder, cache_builder)
+            .parse_if(ACCESS_MODE, node_builder, value_builder, cache_builder)?
             .unwrap_or(AccessMode::RO);
         let cacheable = node
-            .parse_if(CACHEABLE, node_builder, value_builder, cache_builder)
+            .parse_if(CACHEABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let polling_time = node.parse_if(POLLING_TIME, node_builder, value_builder, cache_builder);
+        let polling_time =
+            node.parse_if(POLLING_TIME, node_builder, value_builder, cache_builder)?;
         let streamable = node
-            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)
+            .parse_if(STREAMABLE, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let bit_mask = node.parse(node_builder, value_builder, cache_builder);
+        let bit_mask = node.parse(node_builder, value_builder, cache_builder)?;
         let sign = node
-            .parse_if(SIGN, node_builder, value_builder, cache_builder)
+            .parse_if(SIGN, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder);
+        let unit = node.parse_if(UNIT, node_builder, value_builder, cache_builder)?;
         let representation = node
-            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)
+            .parse_if(REPRESENTATION, node_builder, value_builder, cache_builder)?
             .unwrap_or_default();
-        let p_selected = node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder);
+        let p_selected =
+            node.parse_while(P_SELECTED, node_builder, value_builder, cache_builder)?;
 
-        Self {
+        Ok(Self {
             attr_base,
             elem_base,
             p_invalidators,
@@ -218,7 +223,7 @@ impl Parse for StructEntryNode {
             unit,
             representation,
             p_selected,
-        }
+        })
     }
 }
 
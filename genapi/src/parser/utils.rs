@@ -4,7 +4,7 @@
 
 #[cfg(test)]
 pub(super) mod tests {
-    use super::super::{xml, Parse};
+    use super::super::{xml, Parse, ParseResult};
     use crate::store::{DefaultCacheStore, DefaultNodeStore, DefaultValueStore};
 
     pub(in super::super) fn parse_default<T: Parse>(
@@ -18,10 +18,20 @@ pub(super) mod tests {
         (
             document
                 .root_node()
-                .parse(&mut node_builder, &mut value_builder, &mut cache_builder),
+                .parse(&mut node_builder, &mut value_builder, &mut cache_builder)
+                .unwrap(),
             node_builder,
             value_builder,
             cache_builder,
         )
     }
+
+    /// Same as [`parse_default`], but returns the error instead of panicking.
+    pub(in super::super) fn try_parse_default<T: Parse>(xml: &str) -> ParseResult<T> {
+        xml::Document::from_str(xml)?.root_node().parse(
+            &mut DefaultNodeStore::new(),
+            &mut DefaultValueStore::new(),
+            &mut DefaultCacheStore::new(),
+        )
+    }
 }
@@ -2,37 +2,264 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{fmt, iter::Peekable};
+//! A minimal read-only XML tree for the GenApi parser.
+//!
+//! The tree is built in a single pass over [`xmlparser::Tokenizer`] and stored in flat arenas.
+//! Element names, attribute values and texts borrow from the input, so no allocation is needed
+//! unless a value contains entity references or a text is split by comments or `CDATA` sections.
 
-use crate::builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder};
+use alloc::borrow::Cow;
+use core::{fmt, ops::Range};
 
-use super::{Parse, ParseResult};
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+use crate::{
+    builder::{CacheStoreBuilder, NodeStoreBuilder, ValueStoreBuilder},
+    store::ValueInfo,
+    SchemaVersion,
+};
+
+use super::{
+    diagnostics::{ParseWarning, WarningSink},
+    elem_name::{
+        REGISTER_DESCRIPTION, SCHEMA_MAJOR_VERSION, SCHEMA_MINOR_VERSION, SCHEMA_SUB_MINOR_VERSION,
+    },
+    literal, schema, Parse, ParseError, ParseResult,
+};
+
+use crate::compat::{String, Vec};
 
 pub(super) struct Document<'input> {
-    document: roxmltree::Document<'input>,
+    src: &'input str,
+    elements: Vec<Element<'input>>,
+    attributes: Vec<Attribute<'input>>,
+    texts: Vec<Text<'input>>,
+    warnings: WarningSink,
+    strict: bool,
+    schema_version: SchemaVersion,
+}
+
+struct Element<'input> {
+    name: &'input str,
+    parent: Option<usize>,
+    first_child: Option<usize>,
+    next_sibling: Option<usize>,
+    first_text: Option<usize>,
+    attributes: Range<usize>,
+    span: Range<usize>,
+}
+
+struct Attribute<'input> {
+    name: &'input str,
+    value: Cow<'input, str>,
+}
+
+/// A text segment of an element. An element has multiple segments if its text is split by
+/// comments or `CDATA` sections.
+struct Text<'input> {
+    text: Cow<'input, str>,
+    next: Option<usize>,
+}
+
+/// An element whose end tag is not reached yet.
+struct OpenElement {
+    id: usize,
+    last_child: Option<usize>,
+    last_text: Option<usize>,
 }
 
 impl<'input> Document<'input> {
     pub(super) fn from_str(s: &'input str) -> ParseResult<Self> {
-        let document = roxmltree::Document::parse(s)?;
-        Ok(Self { document })
+        let mut doc = Self {
+            src: s,
+            elements: Vec::with_capacity(s.len() / 32),
+            attributes: vec![],
+            texts: Vec::with_capacity(s.len() / 32),
+            warnings: WarningSink::default(),
+            strict: false,
+            schema_version: SchemaVersion::LATEST,
+        };
+        doc.build()?;
+        doc.schema_version = doc.detect_schema_version();
+        Ok(doc)
     }
 
     pub(super) fn root_node<'a>(&'a self) -> Node<'a, 'input> {
-        let root = self.document.root_element();
-        Node::from_xmltree_node(root, self.inner_str())
+        Node::new(self, 0)
+    }
+
+    /// Makes texts of enumerated elements match only exactly, see [`Node::next_text_of`].
+    pub(super) fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Overrides the schema version, e.g. for a fragment of a document whose root element isn't
+    /// `RegisterDescription`.
+    pub(super) fn set_schema_version(&mut self, version: SchemaVersion) {
+        self.schema_version = version;
+    }
+
+    /// Returns the warnings reported by nodes of the document so far.
+    pub(super) fn take_warnings(&self) -> Vec<ParseWarning> {
+        self.warnings.take()
+    }
+
+    /// Reads the schema version from the attributes of the root element. Falls back to the latest
+    /// version if the root isn't `RegisterDescription` or the version is malformed.
+    fn detect_schema_version(&self) -> SchemaVersion {
+        let root = self.root_node();
+        if root.tag_name() != REGISTER_DESCRIPTION {
+            return SchemaVersion::LATEST;
+        }
+        let part = |name: &str| root.attribute_of(name).map(literal::parse_uint);
+        match (
+            part(SCHEMA_MAJOR_VERSION),
+            part(SCHEMA_MINOR_VERSION),
+            part(SCHEMA_SUB_MINOR_VERSION),
+        ) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(subminor))) => {
+                SchemaVersion::new(major, minor, subminor)
+            }
+            _ => SchemaVersion::LATEST,
+        }
+    }
+
+    fn build(&mut self) -> ParseResult<()> {
+        let mut stack: Vec<OpenElement> = vec![];
+        // An element whose start tag is not closed yet, i.e. `<Elem attr="..."`.
+        let mut pending: Option<usize> = None;
+
+        for token in Tokenizer::from(self.src) {
+            match token? {
+                Token::ElementStart { local, span, .. } => {
+                    let id = self.elements.len();
+                    let parent = stack.last_mut().map(|parent| {
+                        match parent.last_child.replace(id) {
+                            Some(prev) => self.elements[prev].next_sibling = Some(id),
+                            None => {
+                                self.elements[parent.id].first_child = Some(id);
+                                self.discard_blank_text(parent);
+                            }
+                        }
+                        parent.id
+                    });
+                    self.elements.push(Element {
+                        name: local.as_str(),
+                        parent,
+                        first_child: None,
+                        next_sibling: None,
+                        first_text: None,
+                        attributes: self.attributes.len()..self.attributes.len(),
+                        span: span.start()..span.end(),
+                    });
+                    pending = Some(id);
+                }
+
+                Token::Attribute {
+                    prefix,
+                    local,
+                    value,
+                    ..
+                } => {
+                    // Namespace declarations are not attributes.
+                    if prefix.as_str() == "xmlns" || (prefix.is_empty() && local == "xmlns") {
+                        continue;
+                    }
+                    let id = pending.unwrap();
+                    self.attributes.push(Attribute {
+                        name: local.as_str(),
+                        value: unescape(value.as_str())?,
+                    });
+                    self.elements[id].attributes.end = self.attributes.len();
+                }
+
+                Token::ElementEnd { end, span } => match end {
+                    ElementEnd::Open => stack.push(OpenElement {
+                        id: pending.take().unwrap(),
+                        last_child: None,
+                        last_text: None,
+                    }),
+                    ElementEnd::Empty => {
+                        let id = pending.take().unwrap();
+                        self.elements[id].span.end = span.end();
+                    }
+                    ElementEnd::Close(_, local) => {
+                        let open = stack
+                            .pop()
+                            .ok_or_else(|| ParseError::UnmatchedCloseTag(local.as_str().into()))?;
+                        let elem = &mut self.elements[open.id];
+                        if elem.name != local.as_str() {
+                            return Err(ParseError::UnexpectedCloseTag {
+                                expected: elem.name.into(),
+                                actual: local.as_str().into(),
+                            });
+                        }
+                        elem.span.end = span.end();
+                    }
+                },
+
+                Token::Text { text } => {
+                    if let Some(open) = stack.last_mut() {
+                        // Indentation between child elements is never read.
+                        if open.last_child.is_some() && is_blank(text.as_str()) {
+                            continue;
+                        }
+                        let text = unescape(text.as_str())?;
+                        self.push_text(open, text);
+                    }
+                }
+
+                Token::Cdata { text, .. } => {
+                    if let Some(open) = stack.last_mut() {
+                        self.push_text(open, text.as_str().into());
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        match (
+            self.elements.is_empty(),
+            pending.or_else(|| stack.pop().map(|open| open.id)),
+        ) {
+            (true, _) => Err(ParseError::NoRootElement),
+            (false, Some(id)) => Err(ParseError::UnclosedElement(self.elements[id].name.into())),
+            (false, None) => Ok(()),
+        }
     }
 
-    pub(super) fn inner_str(&self) -> &'input str {
-        self.document.input_text()
+    fn push_text(&mut self, open: &mut OpenElement, text: Cow<'input, str>) {
+        let id = self.texts.len();
+        self.texts.push(Text { text, next: None });
+        match open.last_text.replace(id) {
+            Some(prev) => self.texts[prev].next = Some(id),
+            None => self.elements[open.id].first_text = Some(id),
+        }
+    }
+
+    /// Discards the text of `open` if it consists of whitespaces only. Called when the first child
+    /// of `open` appears.
+    fn discard_blank_text(&mut self, open: &mut OpenElement) {
+        let first = match self.elements[open.id].first_text {
+            Some(first) => first,
+            None => return,
+        };
+        // The text segments of `open` are at the tail of `texts` because no other element has
+        // been opened since `open`.
+        if self.texts[first..].iter().all(|text| is_blank(&text.text)) {
+            self.texts.truncate(first);
+            self.elements[open.id].first_text = None;
+            open.last_text = None;
+        }
     }
 }
 
 pub(super) struct Node<'a, 'input> {
-    inner: roxmltree::Node<'a, 'input>,
-    children: Peekable<roxmltree::Children<'a, 'input>>,
-    attributes: Attributes<'a, 'input>,
-    src: &'input str,
+    doc: &'a Document<'input>,
+    id: usize,
+    /// The next child element to be visited.
+    cursor: Option<usize>,
 }
 
 impl<'a, 'input> Node<'a, 'input> {
@@ -41,7 +268,7 @@ impl<'a, 'input> Node<'a, 'input> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> T {
+    ) -> ParseResult<T> {
         T::parse(self, node_builder, value_builder, cache_builder)
     }
 
@@ -51,11 +278,23 @@ impl<'a, 'input> Node<'a, 'input> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Option<T> {
-        if self.peek()?.tag_name() == tag_name {
-            Some(self.parse(node_builder, value_builder, cache_builder))
-        } else {
-            None
+    ) -> ParseResult<Option<T>> {
+        self.parse_if_any(&[tag_name], node_builder, value_builder, cache_builder)
+    }
+
+    /// Parses the next child element if its tag name is one of `tag_names`.
+    pub(super) fn parse_if_any<T: Parse>(
+        &mut self,
+        tag_names: &[&str],
+        node_builder: &mut impl NodeStoreBuilder,
+        value_builder: &mut impl ValueStoreBuilder,
+        cache_builder: &mut impl CacheStoreBuilder,
+    ) -> ParseResult<Option<T>> {
+        match self.peek() {
+            Some(next) if tag_names.contains(&next.tag_name()) => self
+                .parse(node_builder, value_builder, cache_builder)
+                .map(Some),
+            _ => Ok(None),
         }
     }
 
@@ -65,19 +304,29 @@ impl<'a, 'input> Node<'a, 'input> {
         node_builder: &mut impl NodeStoreBuilder,
         value_builder: &mut impl ValueStoreBuilder,
         cache_builder: &mut impl CacheStoreBuilder,
-    ) -> Vec<T> {
+    ) -> ParseResult<Vec<T>> {
         let mut res = vec![];
-        while let Some(parsed) = self.parse_if(tag_name, node_builder, value_builder, cache_builder)
+        while let Some(parsed) =
+            self.parse_if(tag_name, node_builder, value_builder, cache_builder)?
         {
             res.push(parsed);
         }
-        res
+        Ok(res)
     }
 
+    /// Consumes the next child element. An element which is not available in the schema
+    /// version of the document is reported, but consumed as usual.
     pub(super) fn next(&mut self) -> Option<Self> {
         let node = self.peek()?;
-        self.children.next();
+        self.cursor = self.doc.elements[node.id].next_sibling;
 
+        if let Some(since) = schema::unavailable_since(node.tag_name(), self.doc.schema_version) {
+            self.report(ParseWarning::UnavailableElement {
+                owner: self.owner_name().map(Into::into),
+                element: node.tag_name().into(),
+                since,
+            });
+        }
         Some(node)
     }
 
@@ -89,104 +338,332 @@ impl<'a, 'input> Node<'a, 'input> {
         }
     }
 
-    pub(super) fn next_text(&mut self) -> Option<TextView<'a, 'input>> {
-        Some(self.next()?.text())
+    /// Consumes the next child element, or returns [`ParseError::MissingElement`] if all the
+    /// child elements are consumed.
+    pub(super) fn next_required(&mut self) -> ParseResult<Self> {
+        self.next().ok_or_else(|| self.missing_element())
+    }
+
+    /// Same as [`Self::next_required`], but returns the text of the element.
+    pub(super) fn next_text_required(&mut self) -> ParseResult<TextView<'a, 'input>> {
+        self.next_required().map(|next| next.text())
+    }
+
+    /// Consumes the next child element, and returns the candidate its text matches.
+    ///
+    /// Unless the document is strict, the text also matches a candidate after trimming
+    /// whitespaces and ignoring ASCII case, which is reported as
+    /// [`ParseWarning::NonCanonicalText`].
+    pub(super) fn next_text_of(
+        &mut self,
+        candidates: &[&'static str],
+    ) -> ParseResult<&'static str> {
+        let next = self.next_required()?;
+        let text = next.text();
+        if let Some(candidate) = candidates
+            .iter()
+            .copied()
+            .find(|&candidate| text == candidate)
+        {
+            return Ok(candidate);
+        }
+
+        let view = text.view();
+        if !self.doc.strict {
+            let trimmed = view.trim();
+            if let Some(candidate) = candidates
+                .iter()
+                .copied()
+                .find(|candidate| candidate.eq_ignore_ascii_case(trimmed))
+            {
+                self.report(ParseWarning::NonCanonicalText {
+                    owner: self.owner_name().map(Into::into),
+                    element: next.tag_name().into(),
+                    text: view.as_ref().into(),
+                });
+                return Ok(candidate);
+            }
+        }
+        Err(ParseError::InvalidLiteral {
+            literal: view.as_ref().into(),
+            reason: format!(
+                "`{}` must be one of `{}`",
+                next.tag_name(),
+                candidates.join("`, `")
+            ),
+        })
     }
 
     pub(super) fn peek(&mut self) -> Option<Self> {
-        let mut inner;
-        loop {
-            inner = self.children.peek()?;
-            if inner.node_type() == roxmltree::NodeType::Element {
-                break;
+        Some(Self::new(self.doc, self.cursor?))
+    }
+
+    /// Same as [`Self::peek`], but returns [`ParseError::MissingElement`] if all the child
+    /// elements are consumed.
+    pub(super) fn peek_required(&mut self) -> ParseResult<Self> {
+        self.peek().ok_or_else(|| self.missing_element())
+    }
+
+    /// Returns [`ParseError::MissingElement`] for a mandatory child element which is absent or
+    /// has an unexpected tag name.
+    pub(super) fn missing_element(&self) -> ParseError {
+        ParseError::MissingElement(self.owner_name().unwrap_or_else(|| self.tag_name()).into())
+    }
+
+    /// Returns the text of the next child element without consuming it if its tag name is
+    /// `tag_name`.
+    pub(super) fn peek_text_if(&mut self, tag_name: &str) -> Option<TextView<'a, 'input>> {
+        let next = self.peek()?;
+        if next.tag_name() == tag_name {
+            Some(next.text())
+        } else {
+            None
+        }
+    }
+
+    /// Reports a non-fatal problem found while parsing the document.
+    pub(super) fn report(&self, warning: ParseWarning) {
+        self.doc.warnings.push(warning);
+    }
+
+    /// Skips the child elements which are not consumed yet, and reports them as unknown elements.
+    /// Returns raw XML of the skipped elements.
+    pub(super) fn skip_rest(&mut self) -> Vec<String> {
+        let owner = self.owner_name();
+        let mut skipped = vec![];
+        while let Some(child) = self.next() {
+            self.report(ParseWarning::UnknownElement {
+                owner: owner.map(Into::into),
+                element: child.tag_name().into(),
+            });
+            skipped.push(child.raw().into());
+        }
+        skipped
+    }
+
+    pub(super) fn tag_name(&self) -> &'a str {
+        self.elem().name
+    }
+
+    /// Returns the name of the node that owns the element, i.e. the nearest element that has `Name` attribute.
+    pub(super) fn owner_name(&self) -> Option<&'a str> {
+        let mut id = Some(self.id);
+        while let Some(cur) = id {
+            let node = Self::new(self.doc, cur);
+            if let Some(name) = node.attribute_of("Name") {
+                return Some(name);
             }
-            self.children.next();
+            id = node.elem().parent;
         }
-        let node = Self::from_xmltree_node(*inner, self.src);
+        None
+    }
 
-        Some(node)
+    /// Returns [`ValueInfo`] of a value parsed from the child element `elem_name`.
+    pub(super) fn value_info(
+        &self,
+        elem_name: &str,
+        node_builder: &mut impl NodeStoreBuilder,
+    ) -> ValueInfo {
+        let owner = self
+            .owner_name()
+            .map(|name| node_builder.get_or_intern(name));
+        ValueInfo::new(owner, elem_name)
     }
 
-    pub(super) fn tag_name(&self) -> &str {
-        self.inner.tag_name().name()
+    /// Returns [`ValueInfo`] of a value parsed from the next child element.
+    pub(super) fn next_value_info(
+        &mut self,
+        node_builder: &mut impl NodeStoreBuilder,
+    ) -> ParseResult<ValueInfo> {
+        let next = self.peek_required()?;
+        Ok(self.value_info(next.tag_name(), node_builder))
     }
 
-    pub(super) fn attribute_of(&self, name: &str) -> Option<&str> {
-        self.attributes.attribute_of(name)
+    pub(super) fn attribute_of(&self, name: &str) -> Option<&'a str> {
+        self.doc.attributes[self.elem().attributes.clone()]
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.value.as_ref())
     }
 
-    pub(super) fn text(&self) -> TextView<'a, 'input> {
-        TextView { inner: self.inner }
+    /// Same as [`Self::attribute_of`], but returns [`ParseError::MissingAttribute`] if the element
+    /// doesn't have the attribute.
+    pub(super) fn required_attribute_of(&self, name: &str) -> ParseResult<&'a str> {
+        self.attribute_of(name)
+            .ok_or_else(|| ParseError::MissingAttribute {
+                element: self.tag_name().into(),
+                attribute: name.into(),
+            })
+    }
+
+    /// Returns the source of the element including its tags.
+    pub(super) fn raw(&self) -> &'a str {
+        &self.doc.src[self.elem().span.clone()]
     }
 
-    fn from_xmltree_node(node: roxmltree::Node<'a, 'input>, src: &'input str) -> Self {
-        debug_assert!(node.node_type() == roxmltree::NodeType::Element);
-        let children = node.children().peekable();
-        let attributes = Attributes::from_xmltree_attrs(node.attributes());
+    pub(super) fn text(&self) -> TextView<'a, 'input> {
+        TextView {
+            doc: self.doc,
+            first: self.elem().first_text,
+        }
+    }
 
+    fn new(doc: &'a Document<'input>, id: usize) -> Self {
         Self {
-            inner: node,
-            children,
-            attributes,
-            src,
+            doc,
+            id,
+            cursor: doc.elements[id].first_child,
         }
     }
+
+    fn elem(&self) -> &'a Element<'input> {
+        &self.doc.elements[self.id]
+    }
 }
 
 impl<'a, 'input> fmt::Debug for Node<'a, 'input> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let span = self.inner.range();
-        let node_src = std::str::from_utf8(&self.src.as_bytes()[span]).unwrap();
-        write!(f, "{}", node_src)
+        write!(f, "{}", self.raw())
     }
 }
 
-struct Attributes<'a, 'input> {
-    attrs: &'a [roxmltree::Attribute<'input>],
+pub(super) struct TextView<'a, 'input> {
+    doc: &'a Document<'input>,
+    first: Option<usize>,
 }
 
-impl<'a, 'input> Attributes<'a, 'input> {
-    fn from_xmltree_attrs(attrs: &'a [roxmltree::Attribute<'input>]) -> Self {
-        Self { attrs }
+impl<'a, 'input> TextView<'a, 'input> {
+    pub(super) fn view(&self) -> Cow<'a, str> {
+        let first = match self.first {
+            Some(first) => &self.doc.texts[first],
+            None => return "".into(),
+        };
+        if first.next.is_none() {
+            first.text.as_ref().into()
+        } else {
+            self.segments().collect::<String>().into()
+        }
     }
 
-    fn attribute_of(&self, name: &str) -> Option<&str> {
-        self.attrs.iter().find_map(|attr| {
-            if attr.name() == name {
-                Some(roxmltree::Attribute::value(attr))
-            } else {
-                None
-            }
+    fn segments(&self) -> impl Iterator<Item = &'a str> {
+        let texts = &self.doc.texts;
+        core::iter::successors(self.first.map(|id| &texts[id]), move |text| {
+            text.next.map(|id| &texts[id])
         })
+        .map(|text| text.text.as_ref())
     }
 }
 
-pub(super) struct TextView<'a, 'input> {
-    inner: roxmltree::Node<'a, 'input>,
-}
-
-impl<'a, 'input> TextView<'a, 'input> {
-    pub(super) fn view(&self) -> std::borrow::Cow<'a, str> {
-        let first_child = self.inner.first_child().unwrap();
-        if first_child.has_siblings() {
-            let mut s = String::new();
-            for child in self.inner.children() {
-                let child = if child.is_text() {
-                    child.text().unwrap()
-                } else {
-                    continue;
-                };
-                s.push_str(child);
+impl<'a, 'input> PartialEq<&str> for TextView<'a, 'input> {
+    fn eq(&self, rhs: &&str) -> bool {
+        let mut rest = *rhs;
+        for segment in self.segments() {
+            match rest.strip_prefix(segment) {
+                Some(r) => rest = r,
+                None => return false,
             }
-            s.into()
-        } else {
-            first_child.text().unwrap().into()
         }
+        rest.is_empty()
     }
 }
 
-impl<'a, 'input> PartialEq<&str> for TextView<'a, 'input> {
-    fn eq(&self, rhs: &&str) -> bool {
-        &self.view() == rhs
+fn is_blank(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_whitespace())
+}
+
+/// Replaces predefined entities and character references in `raw`.
+/// Allocates only if `raw` contains a reference.
+fn unescape(raw: &str) -> ParseResult<Cow<'_, str>> {
+    if !raw.contains('&') {
+        return Ok(raw.into());
+    }
+
+    let mut s = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        s.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest
+            .find(';')
+            .ok_or_else(|| ParseError::UnknownEntity(rest.into()))?;
+        let entity = &rest[..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "apos" => '\'',
+            "quot" => '"',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(core::char::from_u32)
+                .ok_or_else(|| ParseError::UnknownEntity(entity.into()))?,
+        };
+        s.push(c);
+        rest = &rest[end + 1..];
+    }
+    s.push_str(rest);
+    Ok(s.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        let xml = r#"<?xml version="1.0"?>
+            <Root xmlns="urn:x" Name="Root">
+                <!-- comment -->
+                <Child Name="A&amp;B"><Value>0x10</Value></Child>
+                <Child Name="Empty"/>
+                <Formula>A &gt; B<!-- comment --> &amp;&amp; <![CDATA[C < D]]></Formula>
+            </Root>"#;
+        let doc = Document::from_str(xml).unwrap();
+
+        let mut root = doc.root_node();
+        assert_eq!(root.tag_name(), "Root");
+        assert_eq!(root.attribute_of("xmlns"), None);
+        assert_eq!(root.attribute_of("Name"), Some("Root"));
+
+        let mut child = root.next_if("Child").unwrap();
+        assert_eq!(child.attribute_of("Name"), Some("A&B"));
+        let value = child.next().unwrap();
+        assert_eq!(value.owner_name(), Some("A&B"));
+        assert!(value.text() == "0x10");
+        assert!(matches!(value.text().view(), Cow::Borrowed("0x10")));
+        assert!(child.next().is_none());
+
+        let empty = root.next().unwrap();
+        assert_eq!(format!("{:?}", empty), r#"<Child Name="Empty"/>"#);
+        assert_eq!(empty.text().view(), "");
+
+        let formula = root.next().unwrap();
+        assert_eq!(formula.text().view(), "A > B && C < D");
+        assert!(formula.text() == "A > B && C < D");
+        assert!(formula.text() != "A > B");
+        assert!(root.next().is_none());
+    }
+
+    #[test]
+    fn test_invalid_document() {
+        assert!(matches!(
+            Document::from_str("<A><B></A></B>"),
+            Err(ParseError::UnexpectedCloseTag { .. })
+        ));
+        assert!(matches!(
+            Document::from_str("<A>"),
+            Err(ParseError::UnclosedElement(_))
+        ));
+        assert!(Document::from_str("<A/><B/>").is_err());
+        assert!(matches!(
+            Document::from_str("<A>&unknown;</A>"),
+            Err(ParseError::UnknownEntity(_))
+        ));
+        assert!(Document::from_str("<A").is_err());
+        assert!(Document::from_str("<A/></A>").is_err());
+        assert!(Document::from_str("").is_err());
     }
 }
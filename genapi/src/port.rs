@@ -2,15 +2,19 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use core::{convert::TryFrom, ops::Range};
+
 use super::{
-    elem_type::ImmOrPNode,
+    elem_type::{AccessMode, ImmOrPNode},
     interface::{INode, IPort},
+    ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, NodeStore, ValueStore},
     Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PortNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -48,7 +52,7 @@ impl INode for PortNode {
 }
 
 impl IPort for PortNode {
-    #[tracing::instrument(skip(self, device, store),
+    #[tracing::instrument(skip(self, device, store, cx),
                           level = "trace",
                           fields(node = store.name_by_id(self.node_base().id()).unwrap()))]
     fn read<T: ValueStore, U: CacheStore>(
@@ -57,13 +61,39 @@ impl IPort for PortNode {
         buf: &mut [u8],
         device: &mut impl Device,
         store: &impl NodeStore,
-        _: &mut ValueCtxt<T, U>,
+        cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
-        if self.chunk_id.is_some() {
-            Err(GenApiError::chunk_data_missing())
+        if self.elem_base.imposed_access_mode == AccessMode::WO {
+            return Err(GenApiError::access_denied("the port is write only".into()));
+        }
+
+        if let Some(chunk_id) = &self.chunk_id {
+            let chunk_id = resolve_chunk_id(chunk_id, device, store, cx)?;
+            let nid = self.node_base().id();
+            let length = buf.len() as i64;
+            if let Some(chunk) = cx.chunk(chunk_id) {
+                buf.copy_from_slice(&chunk[chunk_range(address, buf.len(), chunk.len())?]);
+                if self.cache_chunk_data {
+                    cx.cache_data(nid, address, length, buf);
+                }
+            } else {
+                let cache = if self.cache_chunk_data {
+                    cx.get_cache(nid, address, length)
+                } else {
+                    None
+                };
+                buf.copy_from_slice(&cache.ok_or_else(GenApiError::chunk_data_missing)?);
+            }
         } else {
-            device.read_mem(address, buf).map_err(GenApiError::device)
+            device
+                .read_mem(address, buf)
+                .map_err(|e| GenApiError::device(address, e))?;
+        }
+
+        if self.swap_endianness {
+            swap_endianness(buf)?;
         }
+        Ok(())
     }
 
     #[tracing::instrument(skip(self, device, store, cx),
@@ -77,13 +107,261 @@ impl IPort for PortNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        cx.verify_writable()?;
+        if self.elem_base.imposed_access_mode == AccessMode::RO {
+            return Err(GenApiError::access_denied("the port is read only".into()));
+        }
         cx.invalidate_cache_by(self.node_base().id());
 
-        if self.chunk_id.is_some() {
-            // TODO: Implement chunk parser.
-            todo!()
+        let swapped;
+        let buf = if self.swap_endianness {
+            let mut data = buf.to_vec();
+            swap_endianness(&mut data)?;
+            swapped = data;
+            &swapped
+        } else {
+            buf
+        };
+
+        if let Some(chunk_id) = &self.chunk_id {
+            let chunk_id = resolve_chunk_id(chunk_id, device, store, cx)?;
+            let chunk = cx
+                .chunk_data
+                .get_mut(&chunk_id)
+                .ok_or_else(GenApiError::chunk_data_missing)?;
+            let range = chunk_range(address, buf.len(), chunk.len())?;
+            chunk[range].copy_from_slice(buf);
+            if self.cache_chunk_data {
+                cx.cache_data(self.node_base().id(), address, buf.len() as i64, buf);
+            }
+            Ok(())
         } else {
-            device.write_mem(address, buf).map_err(GenApiError::device)
+            device
+                .write_mem(address, buf)
+                .map_err(|e| GenApiError::device(address, e))
         }
     }
 }
+
+/// Returns the id of the chunk data accessed through the port.
+fn resolve_chunk_id<T: ValueStore, U: CacheStore>(
+    chunk_id: &ImmOrPNode<u64>,
+    device: &mut impl Device,
+    store: &impl NodeStore,
+    cx: &mut ValueCtxt<T, U>,
+) -> GenApiResult<u64> {
+    match chunk_id {
+        ImmOrPNode::Imm(id) => Ok(*id),
+        ImmOrPNode::PNode(nid) => {
+            let id: i64 = nid.value(device, store, cx)?;
+            Ok(id as u64)
+        }
+    }
+}
+
+/// Returns the range of the chunk data accessed with `address` and `len`.
+fn chunk_range(address: i64, len: usize, chunk_len: usize) -> GenApiResult<Range<usize>> {
+    usize::try_from(address)
+        .ok()
+        .and_then(|start| Some(start..start.checked_add(len)?))
+        .filter(|range| range.end <= chunk_len)
+        .ok_or_else(|| {
+            GenApiError::invalid_data(
+                format!(
+                    "address {:#x} with length {} is out of the chunk data of length {}",
+                    address, len, chunk_len
+                )
+                .into(),
+            )
+        })
+}
+
+/// Swaps byte order of each 4 byte word as `SwapEndianess` requires.
+fn swap_endianness(buf: &mut [u8]) -> GenApiResult<()> {
+    if !buf.len().is_multiple_of(4) {
+        return Err(GenApiError::invalid_buffer(
+            "the length of data must be a multiple of 4 to swap endianness".into(),
+        ));
+    }
+    for word in buf.chunks_exact_mut(4) {
+        word.reverse();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::GenApiBuilder, interface::IInteger, store::DefaultNodeStore, GenApiErrorKind,
+    };
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <IntReg Name="SwappedReg">
+                <Address>0x0</Address>
+                <Length>4</Length>
+                <pPort>SwappedPort</pPort>
+                <Cachable>NoCache</Cachable>
+                <Endianess>BigEndian</Endianess>
+            </IntReg>
+
+            <IntReg Name="ChunkReg">
+                <Address>0x4</Address>
+                <Length>4</Length>
+                <pPort>ChunkPort</pPort>
+                <Cachable>NoCache</Cachable>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+
+            <Port Name="SwappedPort">
+                <SwapEndianess>Yes</SwapEndianess>
+            </Port>
+
+            <Port Name="ChunkPort">
+                <ChunkID>10</ChunkID>
+                <CacheChunkData>Yes</CacheChunkData>
+            </Port>
+
+            <Port Name="ReadOnlyPort">
+                <ImposedAccessMode>RO</ImposedAccessMode>
+            </Port>
+
+        </RegisterDescription>
+        "#;
+
+    struct Memory(Vec<u8>);
+
+    impl Device for Memory {
+        fn read_mem(
+            &mut self,
+            address: i64,
+            buf: &mut [u8],
+        ) -> Result<(), Box<dyn core::error::Error + Send + Sync>> {
+            let start = address as usize;
+            buf.copy_from_slice(&self.0[start..start + buf.len()]);
+            Ok(())
+        }
+
+        fn write_mem(
+            &mut self,
+            address: i64,
+            data: &[u8],
+        ) -> Result<(), Box<dyn core::error::Error + Send + Sync>> {
+            let start = address as usize;
+            self.0[start..start + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_swap_endianness() {
+        let (_, store, mut cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        let mut device = Memory(vec![0x01, 0x02, 0x03, 0x04]);
+        let reg = store
+            .id_by_name("SwappedReg")
+            .unwrap()
+            .expect_iinteger_kind(&store)
+            .unwrap();
+
+        assert_eq!(
+            reg.value(&mut device, &store, &mut cx).unwrap(),
+            0x0403_0201
+        );
+        reg.set_value(0x0a0b_0c0d, &mut device, &store, &mut cx)
+            .unwrap();
+        assert_eq!(device.0, [0x0d, 0x0c, 0x0b, 0x0a]);
+    }
+
+    #[test]
+    fn test_chunk_port() {
+        let (_, store, mut cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        let mut device = Memory(vec![]);
+        let reg = store
+            .id_by_name("ChunkReg")
+            .unwrap()
+            .expect_iinteger_kind(&store)
+            .unwrap();
+
+        assert!(matches!(
+            reg.value(&mut device, &store, &mut cx),
+            Err(GenApiError::ChunkDataMissing)
+        ));
+
+        cx.attach_chunk(0x10, vec![0, 0, 0, 0, 0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(
+            reg.value(&mut device, &store, &mut cx).unwrap(),
+            0x1234_5678
+        );
+        reg.set_value(0x42, &mut device, &store, &mut cx).unwrap();
+        assert_eq!(cx.chunk(0x10).unwrap()[4..], [0x42, 0, 0, 0]);
+
+        // `CacheChunkData` keeps the value readable after the chunk is detached.
+        cx.detach_chunks();
+        assert_eq!(reg.value(&mut device, &store, &mut cx).unwrap(), 0x42);
+    }
+
+    #[test]
+    fn test_read_only_ctxt() {
+        let (_, store, cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        let mut cx = cx.with_read_only(true);
+        let mut device = Memory(vec![0x01, 0x02, 0x03, 0x04]);
+        let reg = store
+            .id_by_name("SwappedReg")
+            .unwrap()
+            .expect_iinteger_kind(&store)
+            .unwrap();
+
+        assert_eq!(
+            reg.value(&mut device, &store, &mut cx).unwrap(),
+            0x0403_0201
+        );
+        assert!(!reg.is_writable(&mut device, &store, &mut cx).unwrap());
+        assert_eq!(
+            reg.set_value(0, &mut device, &store, &mut cx)
+                .unwrap_err()
+                .kind(),
+            GenApiErrorKind::AccessDenied
+        );
+        assert_eq!(device.0, [0x01, 0x02, 0x03, 0x04]);
+    }
+
+    #[test]
+    fn test_imposed_access_mode() {
+        let (_, store, mut cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        let mut device = Memory(vec![0; 4]);
+        let port = store
+            .id_by_name("ReadOnlyPort")
+            .unwrap()
+            .expect_iport_kind(&store)
+            .unwrap();
+
+        let mut buf = [0; 4];
+        port.read(0, &mut buf, &mut device, &store, &mut cx)
+            .unwrap();
+        assert!(port.write(0, &buf, &mut device, &store, &mut cx).is_err());
+    }
+}
@@ -11,7 +11,10 @@ use super::{
     Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::Vec;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegisterBase {
     pub(crate) elem_base: NodeElementBase,
 
@@ -77,7 +80,7 @@ impl RegisterBase {
         let length = self.length(device, store, cx)?;
         let address = self.address(device, store, cx)?;
         if let Some(cache) = cx.get_cache(nid, address, length) {
-            f(cache)
+            f(&cache)
         } else {
             let mut buf = vec![0; length as usize];
             self.read_and_cache(nid, address, length, &mut buf, device, store, cx)?;
@@ -119,6 +122,7 @@ impl RegisterBase {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
         let length = self.length(device, store, cx)?;
 
         if buf.len() != length as usize {
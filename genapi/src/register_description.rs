@@ -2,9 +2,14 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use core::fmt;
+
 use super::elem_type::StandardNameSpace;
 
+use crate::compat::String;
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RegisterDescription {
     pub(crate) model_name: String,
     pub(crate) vendor_name: String,
@@ -41,6 +46,16 @@ impl RegisterDescription {
         self.standard_name_space
     }
 
+    /// Returns the version of the `GenApi` schema the document conforms to.
+    #[must_use]
+    pub fn schema_version(&self) -> SchemaVersion {
+        SchemaVersion::new(
+            self.schema_major_version,
+            self.schema_minor_version,
+            self.schema_subminor_version,
+        )
+    }
+
     #[must_use]
     pub fn schema_major_version(&self) -> u64 {
         self.schema_major_version
@@ -81,3 +96,53 @@ impl RegisterDescription {
         &self.version_guid
     }
 }
+
+/// Version of the `GenApi` schema, e.g. `1.1.0`.
+///
+/// Versions are ordered by major, minor and subminor version in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SchemaVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub subminor: u64,
+}
+
+impl SchemaVersion {
+    /// Schema version `1.0.0`.
+    pub const V1_0: Self = Self::new(1, 0, 0);
+
+    /// Schema version `1.1.0`.
+    pub const V1_1: Self = Self::new(1, 1, 0);
+
+    /// The latest version the parser knows. Documents are parsed as this version if their
+    /// version is unknown.
+    pub const LATEST: Self = Self::V1_1;
+
+    #[must_use]
+    pub const fn new(major: u64, minor: u64, subminor: u64) -> Self {
+        Self {
+            major,
+            minor,
+            subminor,
+        }
+    }
+
+    /// Returns `true` if the parser supports the version, i.e. its major version is known.
+    #[must_use]
+    pub fn is_supported(self) -> bool {
+        self.major == Self::LATEST.major
+    }
+}
+
+impl Default for SchemaVersion {
+    fn default() -> Self {
+        Self::LATEST
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.subminor)
+    }
+}
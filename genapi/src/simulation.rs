@@ -0,0 +1,436 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Simulation of writes which doesn't touch the device.
+//!
+//! [`simulate_set`] sets a node in a sandbox built on top of the current values and cache, then
+//! reports nodes whose range or access mode would change, e.g. to grey out invalid combinations in
+//! a UI. Registers which aren't cached can't be read in the sandbox, so states which depend on
+//! them are reported as unknown.
+
+use core::fmt;
+
+use super::{
+    debug::p_invalidators,
+    interface::{IBoolean, IEnumeration, IFloat, IInteger, IString},
+    store::{CacheStore, NodeId, NodeStore, ValueData, ValueId, ValueInfo, ValueStore},
+    Device, GenApiError, GenApiResult, ValueCtxt,
+};
+
+use crate::compat::{Box, Cow, HashMap, HashSet, ToOwned, Vec};
+
+/// A bound of a numeric node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Integer(i64),
+    Float(f64),
+}
+
+/// State of a node observed in the sandbox.
+///
+/// `None` means the state can't be determined without accessing the device.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NodeState {
+    pub is_readable: Option<bool>,
+    pub is_writable: Option<bool>,
+    /// Minimum value. Always `None` for nodes which don't have `IInteger` nor `IFloat` interface.
+    pub min: Option<Bound>,
+    /// Maximum value. Always `None` for nodes which don't have `IInteger` nor `IFloat` interface.
+    pub max: Option<Bound>,
+}
+
+/// A node whose state would be changed by the simulated write.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChange {
+    pub node: NodeId,
+    pub before: NodeState,
+    pub after: NodeState,
+}
+
+/// Simulates setting `value` to `nid` without touching the device nor modifying `cx`.
+///
+/// `ValueData::Str` sets an enumeration by its symbolic name or a string node, and
+/// `ValueData::Integer` sets an enumeration by its value. States of `watched` nodes are compared
+/// before and after the write. If `watched` is `None`, all nodes which have `IInteger`, `IFloat`,
+/// `IEnumeration`, `IBoolean` or `IString` interface are watched.
+///
+/// Returns an error if the write itself fails, e.g. the value violates the range of the node.
+pub fn simulate_set<T: ValueStore, U: CacheStore>(
+    nid: NodeId,
+    value: impl Into<ValueData>,
+    store: &impl NodeStore,
+    cx: &ValueCtxt<T, U>,
+    watched: Option<&[NodeId]>,
+) -> GenApiResult<Vec<StateChange>> {
+    let watched = match watched {
+        Some(watched) => watched.to_vec(),
+        None => {
+            let mut nids = vec![];
+            store.visit_nodes(|data| nids.push(data.node_base().id()));
+            nids.retain(|nid| has_value(*nid, store));
+            nids
+        }
+    };
+
+    let mut invalidators: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+    store.visit_nodes(|data| {
+        for invalidator in p_invalidators(data) {
+            invalidators
+                .entry(invalidator)
+                .or_default()
+                .push(data.node_base().id());
+        }
+    });
+
+    let mut device = SandboxDevice::default();
+    let mut sandbox = ValueCtxt::new(
+        OverlayValueStore {
+            base: &cx.value_store,
+            overlay: HashMap::new(),
+        },
+        OverlayCacheStore {
+            base: &cx.cache_store,
+            invalidators: &invalidators,
+            overlay: HashMap::new(),
+            stale: HashSet::new(),
+            cleared: false,
+        },
+    )
+    .with_value_policy(cx.value_policy);
+    sandbox.chunk_data = cx.chunk_data.clone();
+
+    let before: Vec<_> = watched
+        .iter()
+        .map(|nid| observe(*nid, &mut device, store, &mut sandbox))
+        .collect();
+    set(nid, &value.into(), &mut device, store, &mut sandbox)?;
+    let after = watched
+        .iter()
+        .map(|nid| observe(*nid, &mut device, store, &mut sandbox));
+
+    Ok(watched
+        .iter()
+        .zip(before)
+        .zip(after)
+        .filter(|((_, before), after)| before != after)
+        .map(|((nid, before), after)| StateChange {
+            node: *nid,
+            before,
+            after,
+        })
+        .collect())
+}
+
+fn has_value(nid: NodeId, store: &impl NodeStore) -> bool {
+    nid.as_iinteger_kind(store).is_some()
+        || nid.as_ifloat_kind(store).is_some()
+        || nid.as_ienumeration_kind(store).is_some()
+        || nid.as_iboolean_kind(store).is_some()
+        || nid.as_istring_kind(store).is_some()
+}
+
+fn observe<T: ValueStore, U: CacheStore>(
+    nid: NodeId,
+    device: &mut impl Device,
+    store: &impl NodeStore,
+    cx: &mut ValueCtxt<T, U>,
+) -> NodeState {
+    if let Some(node) = nid.as_iinteger_kind(store) {
+        NodeState {
+            is_readable: node.is_readable(device, store, cx).ok(),
+            is_writable: node.is_writable(device, store, cx).ok(),
+            min: node.min(device, store, cx).ok().map(Bound::Integer),
+            max: node.max(device, store, cx).ok().map(Bound::Integer),
+        }
+    } else if let Some(node) = nid.as_ifloat_kind(store) {
+        NodeState {
+            is_readable: node.is_readable(device, store, cx).ok(),
+            is_writable: node.is_writable(device, store, cx).ok(),
+            min: node.min(device, store, cx).ok().map(Bound::Float),
+            max: node.max(device, store, cx).ok().map(Bound::Float),
+        }
+    } else if let Some(node) = nid.as_ienumeration_kind(store) {
+        NodeState {
+            is_readable: node.is_readable(device, store, cx).ok(),
+            is_writable: node.is_writable(device, store, cx).ok(),
+            ..NodeState::default()
+        }
+    } else if let Some(node) = nid.as_iboolean_kind(store) {
+        NodeState {
+            is_readable: node.is_readable(device, store, cx).ok(),
+            is_writable: node.is_writable(device, store, cx).ok(),
+            ..NodeState::default()
+        }
+    } else if let Some(node) = nid.as_istring_kind(store) {
+        NodeState {
+            is_readable: node.is_readable(device, store, cx).ok(),
+            is_writable: node.is_writable(device, store, cx).ok(),
+            ..NodeState::default()
+        }
+    } else {
+        NodeState::default()
+    }
+}
+
+fn set<T: ValueStore, U: CacheStore>(
+    nid: NodeId,
+    value: &ValueData,
+    device: &mut impl Device,
+    store: &impl NodeStore,
+    cx: &mut ValueCtxt<T, U>,
+) -> GenApiResult<()> {
+    match value {
+        ValueData::Integer(i) => {
+            if let Some(node) = nid.as_iinteger_kind(store) {
+                return node.set_value(*i, device, store, cx);
+            } else if let Some(node) = nid.as_ienumeration_kind(store) {
+                return node.set_entry_by_value(*i, device, store, cx);
+            } else if let Some(node) = nid.as_ifloat_kind(store) {
+                return node.set_value(*i as f64, device, store, cx);
+            }
+        }
+        ValueData::Float(f) => {
+            if let Some(node) = nid.as_ifloat_kind(store) {
+                return node.set_value(*f, device, store, cx);
+            }
+        }
+        ValueData::Boolean(b) => {
+            if let Some(node) = nid.as_iboolean_kind(store) {
+                return node.set_value(*b, device, store, cx);
+            }
+        }
+        ValueData::Str(s) => {
+            if let Some(node) = nid.as_ienumeration_kind(store) {
+                return node.set_entry_by_symbolic(s, device, store, cx);
+            } else if let Some(node) = nid.as_istring_kind(store) {
+                return node.set_value(s.clone(), device, store, cx);
+            }
+        }
+    }
+
+    Err(GenApiError::invalid_data(
+        format!("{} can't be set to {:?}", nid.name(store), value).into(),
+    ))
+}
+
+/// A device which only holds data written in the sandbox.
+#[derive(Default)]
+struct SandboxDevice {
+    memory: HashMap<i64, u8>,
+}
+
+impl Device for SandboxDevice {
+    fn read_mem(
+        &mut self,
+        address: i64,
+        buf: &mut [u8],
+    ) -> Result<(), Box<dyn core::error::Error + Send + Sync>> {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = *self.memory.get(&(address + i as i64)).ok_or(NotInSandbox)?;
+        }
+        Ok(())
+    }
+
+    fn write_mem(
+        &mut self,
+        address: i64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn core::error::Error + Send + Sync>> {
+        for (i, b) in data.iter().enumerate() {
+            self.memory.insert(address + i as i64, *b);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct NotInSandbox;
+
+impl fmt::Display for NotInSandbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the register is neither cached nor written in the sandbox")
+    }
+}
+
+impl core::error::Error for NotInSandbox {}
+
+/// Keeps values updated in the sandbox on top of `base`.
+struct OverlayValueStore<'a, S> {
+    base: &'a S,
+    overlay: HashMap<ValueId, ValueData>,
+}
+
+impl<S: ValueStore> ValueStore for OverlayValueStore<'_, S> {
+    fn value_opt<T>(&self, id: T) -> Option<Cow<'_, ValueData>>
+    where
+        T: Into<ValueId>,
+    {
+        let id = id.into();
+        match self.overlay.get(&id) {
+            Some(value) => Some(Cow::Borrowed(value)),
+            None => self.base.value_opt(id),
+        }
+    }
+
+    fn update<T, U>(&mut self, id: T, value: U) -> Option<ValueData>
+    where
+        T: Into<ValueId>,
+        U: Into<ValueData>,
+    {
+        let id = id.into();
+        let old = self.value_opt(id).map(Cow::into_owned);
+        self.overlay.insert(id, value.into());
+        old
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (ValueId, Cow<'_, ValueData>)> + '_> {
+        Box::new(
+            self.base
+                .iter()
+                .map(move |(id, value)| match self.overlay.get(&id) {
+                    Some(value) => (id, Cow::Borrowed(value)),
+                    None => (id, value),
+                }),
+        )
+    }
+
+    fn value_info<T>(&self, id: T) -> Option<&ValueInfo>
+    where
+        T: Into<ValueId>,
+    {
+        self.base.value_info(id)
+    }
+}
+
+/// Keeps cache filled in the sandbox on top of `base`, hiding cache of `base` which is
+/// invalidated in the sandbox.
+struct OverlayCacheStore<'a, S> {
+    base: &'a S,
+    invalidators: &'a HashMap<NodeId, Vec<NodeId>>,
+    overlay: HashMap<NodeId, HashMap<(i64, i64), Vec<u8>>>,
+    /// Nodes whose cache in `base` is invalidated.
+    stale: HashSet<NodeId>,
+    cleared: bool,
+}
+
+impl<S: CacheStore> CacheStore for OverlayCacheStore<'_, S> {
+    fn cache(&mut self, nid: NodeId, address: i64, length: i64, data: &[u8]) {
+        self.overlay
+            .entry(nid)
+            .or_default()
+            .insert((address, length), data.to_owned());
+    }
+
+    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<Cow<'_, [u8]>> {
+        if let Some(data) = self
+            .overlay
+            .get(&nid)
+            .and_then(|cache| cache.get(&(address, length)))
+        {
+            Some(Cow::Borrowed(data))
+        } else if self.cleared || self.stale.contains(&nid) {
+            None
+        } else {
+            self.base.get_cache(nid, address, length)
+        }
+    }
+
+    fn invalidate_by(&mut self, nid: NodeId) {
+        let invalidators = self.invalidators;
+        if let Some(targets) = invalidators.get(&nid) {
+            for target in targets {
+                self.invalidate_of(*target);
+            }
+        }
+    }
+
+    fn invalidate_of(&mut self, nid: NodeId) {
+        self.overlay.remove(&nid);
+        self.stale.insert(nid);
+    }
+
+    fn clear(&mut self) {
+        self.overlay.clear();
+        self.cleared = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builder::GenApiBuilder, store::DefaultNodeStore};
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Integer Name="Width">
+                <Value>16</Value>
+                <Min>1</Min>
+                <Max>100</Max>
+            </Integer>
+
+            <Integer Name="OffsetX">
+                <Value>0</Value>
+                <Min>0</Min>
+                <pMax>OffsetXMax</pMax>
+            </Integer>
+
+            <IntSwissKnife Name="OffsetXMax">
+                <pVariable Name="W">Width</pVariable>
+                <Formula>100 - W</Formula>
+            </IntSwissKnife>
+
+            <IntReg Name="GainReg">
+                <Address>0x10</Address>
+                <Length>4</Length>
+                <pPort>Device</pPort>
+                <pInvalidator>Width</pInvalidator>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+
+            <Port Name="Device">
+            </Port>
+
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_simulate_set() {
+        let (_, store, mut cx) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+        let width = store.id_by_name("Width").unwrap();
+        let offset_x = store.id_by_name("OffsetX").unwrap();
+        let gain_reg = store.id_by_name("GainReg").unwrap();
+        cx.cache_data(gain_reg, 0x10, 4, &[0; 4]);
+
+        let changes = simulate_set(width, 40_i64, &store, &cx, None).unwrap();
+        let offset_x_change = changes.iter().find(|c| c.node == offset_x).unwrap();
+        assert_eq!(offset_x_change.before.max, Some(Bound::Integer(84)));
+        assert_eq!(offset_x_change.after.max, Some(Bound::Integer(60)));
+        assert!(changes.iter().all(|c| c.node != width));
+
+        // The context isn't modified even though `Width` invalidates `GainReg` in the sandbox.
+        let mut device = SandboxDevice::default();
+        let width_node = width.as_iinteger_kind(&store).unwrap();
+        assert_eq!(width_node.value(&mut device, &store, &mut cx).unwrap(), 16);
+        assert!(cx.get_cache(gain_reg, 0x10, 4).is_some());
+
+        assert!(simulate_set(width, 200_i64, &store, &cx, Some(&[offset_x])).is_err());
+    }
+}
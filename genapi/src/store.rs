@@ -2,28 +2,41 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{collections::HashMap, convert::TryFrom};
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::sync::PoisonError;
 
 use auto_impl::auto_impl;
-use string_interner::{DefaultBackend, StringInterner, Symbol};
+use string_interner::{backend::BucketBackend, StringInterner, Symbol};
 
 use super::{
     builder,
+    elem_type::MergePriority,
     interface::{
         IBooleanKind, ICategoryKind, ICommandKind, IEnumerationKind, IFloatKind, IIntegerKind,
         INode, INodeKind, IPortKind, IRegisterKind, ISelectorKind, IStringKind,
     },
-    node_base::NodeBase,
+    node_base::{NodeBase, NodeElementBase},
     BooleanNode, CategoryNode, CommandNode, ConverterNode, EnumEntryNode, EnumerationNode,
     FloatNode, FloatRegNode, GenApiError, GenApiResult, IntConverterNode, IntRegNode,
-    IntSwissKnifeNode, IntegerNode, MaskedIntRegNode, Node, PortNode, RegisterNode, StringNode,
-    StringRegNode, SwissKnifeNode,
+    IntSwissKnifeNode, IntegerNode, MaskedIntRegNode, Node, PortNode, RegisterNode, SchemaVersion,
+    StringNode, StringRegNode, SwissKnifeNode,
 };
 
+use crate::compat::{Box, Cow, HashMap, String, ToOwned, Vec};
+
+#[cfg(feature = "std")]
+mod lazy;
+
+#[cfg(feature = "std")]
+pub use lazy::{LazyCacheStore, LazyNodeStore, LazyValueStore};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeId(u32);
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NodeData {
     Node(Box<Node>),
     Category(Box<CategoryNode>),
@@ -70,11 +83,21 @@ pub trait NodeStore {
     fn visit_nodes<F>(&self, f: F)
     where
         F: FnMut(&NodeData);
+
+    /// Returns the schema version of the document from which the nodes are parsed, if the store
+    /// keeps it.
+    fn schema_version(&self) -> Option<SchemaVersion> {
+        None
+    }
 }
 
+/// A store of values of nodes.
+///
+/// Values are returned as [`Cow`] so that stores with interior mutability, e.g.
+/// [`ShardedValueStore`], can hand out copies instead of references into locked data.
 #[auto_impl(&mut, Box)]
 pub trait ValueStore {
-    fn value_opt<T>(&self, id: T) -> Option<&ValueData>
+    fn value_opt<T>(&self, id: T) -> Option<Cow<'_, ValueData>>
     where
         T: Into<ValueId>;
 
@@ -83,7 +106,26 @@ pub trait ValueStore {
         T: Into<ValueId>,
         U: Into<ValueData>;
 
-    fn value(&self, id: impl Into<ValueId>) -> &ValueData {
+    /// Returns an iterator over all stored values.
+    ///
+    /// The default implementation looks up ids from zero until [`ValueStore::value_opt`] returns
+    /// `None`, which covers stores whose ids are assigned sequentially by their builders.
+    fn iter(&self) -> Box<dyn Iterator<Item = (ValueId, Cow<'_, ValueData>)> + '_> {
+        Box::new((0..=u32::MAX).map_while(move |i| {
+            let id = ValueId(i);
+            Some((id, self.value_opt(id)?))
+        }))
+    }
+
+    /// Returns [`ValueInfo`] of the value if the store keeps it.
+    fn value_info<T>(&self, _id: T) -> Option<&ValueInfo>
+    where
+        T: Into<ValueId>,
+    {
+        None
+    }
+
+    fn value(&self, id: impl Into<ValueId>) -> Cow<'_, ValueData> {
         self.value_opt(id).unwrap()
     }
 
@@ -91,9 +133,9 @@ pub trait ValueStore {
     where
         T: Into<ValueId>,
     {
-        match self.value_opt(id)? {
-            ValueData::Integer(i) => Some(*i),
-            ValueData::Float(f) => Some(*f as i64),
+        match *self.value_opt(id)? {
+            ValueData::Integer(i) => Some(i),
+            ValueData::Float(f) => Some(f as i64),
             _ => None,
         }
     }
@@ -102,21 +144,21 @@ pub trait ValueStore {
     where
         T: Into<ValueId>,
     {
-        match self.value_opt(id)? {
-            ValueData::Integer(i) => Some(*i as f64),
-            ValueData::Float(f) => Some(*f),
+        match *self.value_opt(id)? {
+            ValueData::Integer(i) => Some(i as f64),
+            ValueData::Float(f) => Some(f),
             _ => None,
         }
     }
 
-    fn str_value<T>(&self, id: T) -> Option<&String>
+    fn str_value<T>(&self, id: T) -> Option<Cow<'_, str>>
     where
         T: Into<ValueId>,
     {
-        if let ValueData::Str(s) = self.value_opt(id)? {
-            Some(s)
-        } else {
-            None
+        match self.value_opt(id)? {
+            Cow::Borrowed(ValueData::Str(s)) => Some(Cow::Borrowed(s)),
+            Cow::Owned(ValueData::Str(s)) => Some(Cow::Owned(s)),
+            _ => None,
         }
     }
 }
@@ -125,7 +167,7 @@ pub trait ValueStore {
 pub trait CacheStore {
     fn cache(&mut self, nid: NodeId, address: i64, length: i64, data: &[u8]);
 
-    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<&[u8]>;
+    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<Cow<'_, [u8]>>;
 
     fn invalidate_by(&mut self, nid: NodeId);
 
@@ -290,6 +332,7 @@ impl NodeData {
             Self::Boolean(node) => node.node_base(),
             Self::Command(node) => node.node_base(),
             Self::Enumeration(node) => node.node_base(),
+            Self::EnumEntry(node) => node.node_base(),
             Self::Float(node) => node.node_base(),
             Self::FloatReg(node) => node.node_base(),
             Self::String(node) => node.node_base(),
@@ -303,14 +346,90 @@ impl NodeData {
             _ => todo!(),
         }
     }
+
+    pub(crate) fn elem_base_mut(&mut self) -> Option<&mut NodeElementBase> {
+        match self {
+            Self::Node(node) => Some(&mut node.elem_base),
+            Self::Category(node) => Some(&mut node.elem_base),
+            Self::Integer(node) => Some(&mut node.elem_base),
+            Self::IntReg(node) => Some(&mut node.register_base.elem_base),
+            Self::MaskedIntReg(node) => Some(&mut node.register_base.elem_base),
+            Self::Boolean(node) => Some(&mut node.elem_base),
+            Self::Command(node) => Some(&mut node.elem_base),
+            Self::Enumeration(node) => Some(&mut node.elem_base),
+            Self::EnumEntry(node) => Some(&mut node.elem_base),
+            Self::Float(node) => Some(&mut node.elem_base),
+            Self::FloatReg(node) => Some(&mut node.register_base.elem_base),
+            Self::String(node) => Some(&mut node.elem_base),
+            Self::StringReg(node) => Some(&mut node.register_base.elem_base),
+            Self::Register(node) => Some(&mut node.register_base.elem_base),
+            Self::Converter(node) => Some(&mut node.elem_base),
+            Self::IntConverter(node) => Some(&mut node.elem_base),
+            Self::SwissKnife(node) => Some(&mut node.elem_base),
+            Self::IntSwissKnife(node) => Some(&mut node.elem_base),
+            Self::Port(node) => Some(&mut node.elem_base),
+            _ => None,
+        }
+    }
+}
+
+/// Policy to resolve nodes which have the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Parsing fails with [`ParseError::DuplicateNode`](crate::parser::ParseError::DuplicateNode).
+    Error,
+    /// The node stored first is kept.
+    FirstWins,
+    /// The node with the higher `MergePriority` is kept. The node stored later is kept if both
+    /// have the same priority.
+    #[default]
+    LastWinsByMergePriority,
+}
+
+/// Diagnostics of a node whose name is defined more than once.
+///
+/// Documents are indexed in the order they are parsed into the node store, starting from `0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateNode {
+    /// Name of the node.
+    pub name: String,
+    /// Index of the document which defines the kept node.
+    pub kept_document: usize,
+    /// Index of the document which defines the discarded node.
+    pub discarded_document: usize,
+}
+
+impl core::fmt::Display for DuplicateNode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "`{}` is defined more than once: kept the node from document {}, discarded the node \
+             from document {}",
+            self.name, self.kept_document, self.discarded_document
+        )
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefaultNodeStore {
-    pub(super) interner: StringInterner<DefaultBackend<NodeId>>,
+    pub(super) interner: StringInterner<BucketBackend<NodeId>>,
     pub(super) store: Vec<Option<NodeData>>,
 
     fresh_id: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    schema_version: Option<SchemaVersion>,
+
+    /// Build-time diagnostics which aren't serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    duplicate_policy: DuplicatePolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    duplicates: Vec<DuplicateNode>,
+    /// Index of the document from which each node is stored.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sources: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    document_count: usize,
 }
 
 impl DefaultNodeStore {
@@ -320,6 +439,46 @@ impl DefaultNodeStore {
             interner: StringInterner::new(),
             store: Vec::new(),
             fresh_id: 0,
+            schema_version: None,
+            duplicate_policy: DuplicatePolicy::default(),
+            duplicates: Vec::new(),
+            sources: Vec::new(),
+            document_count: 0,
+        }
+    }
+
+    /// Sets the policy to resolve nodes which have the same name.
+    #[must_use]
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Returns nodes which are defined more than once in the parsed documents.
+    #[must_use]
+    pub fn duplicates(&self) -> &[DuplicateNode] {
+        &self.duplicates
+    }
+
+    fn current_document(&self) -> usize {
+        self.document_count.saturating_sub(1)
+    }
+
+    /// Returns `true` if `new` should replace `old` according to the duplicate policy.
+    fn should_replace(&self, old: &NodeData, new: &NodeData) -> bool {
+        fn rank(priority: MergePriority) -> u8 {
+            match priority {
+                MergePriority::High => 2,
+                MergePriority::Mid => 1,
+                MergePriority::Low => 0,
+            }
+        }
+
+        match self.duplicate_policy {
+            DuplicatePolicy::Error | DuplicatePolicy::FirstWins => false,
+            DuplicatePolicy::LastWinsByMergePriority => {
+                rank(new.node_base().merge_priority()) >= rank(old.node_base().merge_priority())
+            }
         }
     }
 }
@@ -348,6 +507,10 @@ impl NodeStore for DefaultNodeStore {
             f(data);
         }
     }
+
+    fn schema_version(&self) -> Option<SchemaVersion> {
+        self.schema_version
+    }
 }
 
 impl builder::NodeStoreBuilder for DefaultNodeStore {
@@ -367,10 +530,48 @@ impl builder::NodeStoreBuilder for DefaultNodeStore {
     fn store_node(&mut self, nid: NodeId, data: NodeData) {
         let id = nid.to_usize();
         if self.store.len() <= id {
-            self.store.resize(id + 1, None)
+            self.store.resize(id + 1, None);
+        }
+        // `sources` is empty if the store is deserialized.
+        self.sources.resize(self.store.len(), 0);
+        let document = self.current_document();
+
+        if let Some(old) = &self.store[id] {
+            let replace = self.should_replace(old, &data);
+            let old_document = self.sources[id];
+            let (kept_document, discarded_document) = if replace {
+                (document, old_document)
+            } else {
+                (old_document, document)
+            };
+            self.duplicates.push(DuplicateNode {
+                name: self.interner.resolve(nid).unwrap_or_default().into(),
+                kept_document,
+                discarded_document,
+            });
+            if !replace {
+                return;
+            }
         }
-        debug_assert!(self.store[id].is_none());
+
         self.store[id] = Some(data);
+        self.sources[id] = document;
+    }
+
+    fn begin_document(&mut self) {
+        self.document_count += 1;
+    }
+
+    fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicate_policy
+    }
+
+    fn duplicates(&self) -> &[DuplicateNode] {
+        &self.duplicates
+    }
+
+    fn set_schema_version(&mut self, version: SchemaVersion) {
+        self.schema_version = Some(version);
     }
 
     fn fresh_id(&mut self) -> u32 {
@@ -387,6 +588,7 @@ impl Default for DefaultNodeStore {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ValueId(u32);
 
 impl ValueId {
@@ -394,11 +596,36 @@ impl ValueId {
     pub fn from_u32(i: u32) -> Self {
         Self(i)
     }
+
+    pub(crate) fn to_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Debug information of a value stored in [`ValueStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValueInfo {
+    /// The node that owns the value.
+    /// The node name can be obtained by [`NodeId::name`].
+    pub owner: Option<NodeId>,
+    /// Name of the element from which the value is parsed, e.g. `Min` or `Value`.
+    pub elem_name: String,
+}
+
+impl ValueInfo {
+    pub fn new(owner: Option<NodeId>, elem_name: impl Into<String>) -> Self {
+        Self {
+            owner,
+            elem_name: elem_name.into(),
+        }
+    }
 }
 
 macro_rules! declare_value_id {
     ($name:ident) => {
         #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub struct $name(u32);
 
         impl From<$name> for ValueId {
@@ -419,6 +646,7 @@ declare_value_id!(FloatId);
 declare_value_id!(StringId);
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueData {
     Integer(i64),
     Float(f64),
@@ -441,13 +669,37 @@ impl_value_data_conversion!(String, Self::Str);
 impl_value_data_conversion!(bool, Self::Boolean);
 
 #[derive(Debug, Default)]
-pub struct DefaultValueStore(Vec<ValueData>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DefaultValueStore {
+    values: Vec<ValueData>,
+    /// `None` unless the store is created by [`DefaultValueStore::with_value_info`].
+    infos: Option<Vec<Option<ValueInfo>>>,
+}
 
 impl DefaultValueStore {
     #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Creates a store which keeps [`ValueInfo`] of stored values for debugging.
+    #[must_use]
+    pub fn with_value_info() -> Self {
+        Self {
+            values: Vec::new(),
+            infos: Some(Vec::new()),
+        }
+    }
+
+    fn push(&mut self, data: ValueData, info: Option<ValueInfo>) -> ValueId {
+        let id = u32::try_from(self.values.len())
+            .expect("the number of value stored in `ValueStore` must not exceed u32::MAX");
+        self.values.push(data);
+        if let Some(infos) = &mut self.infos {
+            infos.push(info);
+        }
+        ValueId(id)
+    }
 }
 
 impl builder::ValueStoreBuilder for DefaultValueStore {
@@ -462,20 +714,24 @@ impl builder::ValueStoreBuilder for DefaultValueStore {
         T: Into<ValueData>,
         U: From<ValueId>,
     {
-        let id = u32::try_from(self.0.len())
-            .expect("the number of value stored in `ValueStore` must not exceed u32::MAX");
-        let id = ValueId(id);
-        self.0.push(data.into());
-        id.into()
+        self.push(data.into(), None).into()
+    }
+
+    fn store_with_info<T, U>(&mut self, data: T, info: ValueInfo) -> U
+    where
+        T: Into<ValueData>,
+        U: From<ValueId>,
+    {
+        self.push(data.into(), Some(info)).into()
     }
 }
 
 impl ValueStore for DefaultValueStore {
-    fn value_opt<T>(&self, id: T) -> Option<&ValueData>
+    fn value_opt<T>(&self, id: T) -> Option<Cow<'_, ValueData>>
     where
         T: Into<ValueId>,
     {
-        self.0.get(id.into().0 as usize)
+        self.values.get(id.into().to_usize()).map(Cow::Borrowed)
     }
 
     fn update<T, U>(&mut self, id: T, value: U) -> Option<ValueData>
@@ -483,14 +739,33 @@ impl ValueStore for DefaultValueStore {
         T: Into<ValueId>,
         U: Into<ValueData>,
     {
-        self.0
-            .get_mut(id.into().0 as usize)
-            .map(|old| std::mem::replace(old, value.into()))
+        self.values
+            .get_mut(id.into().to_usize())
+            .map(|old| core::mem::replace(old, value.into()))
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (ValueId, Cow<'_, ValueData>)> + '_> {
+        Box::new(
+            self.values
+                .iter()
+                .enumerate()
+                .map(|(i, data)| (ValueId(i as u32), Cow::Borrowed(data))),
+        )
+    }
+
+    fn value_info<T>(&self, id: T) -> Option<&ValueInfo>
+    where
+        T: Into<ValueId>,
+    {
+        self.infos.as_ref()?.get(id.into().to_usize())?.as_ref()
     }
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefaultCacheStore {
+    /// Cached values are not serialized because they are only valid while the device is opened.
+    #[cfg_attr(feature = "serde", serde(skip))]
     store: HashMap<NodeId, HashMap<(i64, i64), Vec<u8>>>,
     invalidators: HashMap<NodeId, Vec<NodeId>>,
 }
@@ -532,8 +807,10 @@ impl CacheStore for DefaultCacheStore {
             });
     }
 
-    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<&[u8]> {
-        Some(self.store.get(&nid)?.get(&(address, length))?.as_ref())
+    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<Cow<'_, [u8]>> {
+        Some(Cow::Borrowed(
+            self.store.get(&nid)?.get(&(address, length))?,
+        ))
     }
 
     fn invalidate_by(&mut self, nid: NodeId) {
@@ -558,6 +835,7 @@ impl CacheStore for DefaultCacheStore {
 }
 
 #[derive(Default, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CacheSink {
     _priv: (),
 }
@@ -583,7 +861,7 @@ impl builder::CacheStoreBuilder for CacheSink {
 impl CacheStore for CacheSink {
     fn cache(&mut self, _: NodeId, _: i64, _: i64, _: &[u8]) {}
 
-    fn get_cache(&self, _: NodeId, _: i64, _: i64) -> Option<&[u8]> {
+    fn get_cache(&self, _: NodeId, _: i64, _: i64) -> Option<Cow<'_, [u8]>> {
         None
     }
 
@@ -593,3 +871,441 @@ impl CacheStore for CacheSink {
 
     fn clear(&mut self) {}
 }
+
+/// Number of shards of [`ShardedCacheStore`].
+#[cfg(feature = "std")]
+const CACHE_SHARD_NUM: usize = 16;
+
+#[cfg(feature = "std")]
+type CacheShard = HashMap<NodeId, HashMap<(i64, i64), Vec<u8>>>;
+
+/// A [`ValueStore`] with interior mutability.
+///
+/// Each value is guarded by its own lock, so writes to a value don't block reads of other values.
+/// [`ValueStore`] is implemented for `&ShardedValueStore` and `Arc<ShardedValueStore>` as well,
+/// so that [`ValueCtxt`](crate::ValueCtxt)s on multiple threads can share the store.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct ShardedValueStore {
+    values: Vec<std::sync::RwLock<ValueData>>,
+    /// `None` unless the store is created by [`ShardedValueStore::with_value_info`].
+    infos: Option<Vec<Option<ValueInfo>>>,
+}
+
+#[cfg(feature = "std")]
+impl ShardedValueStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a store which keeps [`ValueInfo`] of stored values for debugging.
+    #[must_use]
+    pub fn with_value_info() -> Self {
+        Self {
+            values: Vec::new(),
+            infos: Some(Vec::new()),
+        }
+    }
+
+    fn get(&self, id: ValueId) -> Option<ValueData> {
+        let value = self.values.get(id.to_usize())?;
+        Some(value.read().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+
+    fn replace(&self, id: ValueId, value: ValueData) -> Option<ValueData> {
+        let old = self.values.get(id.to_usize())?;
+        let mut old = old.write().unwrap_or_else(PoisonError::into_inner);
+        Some(core::mem::replace(&mut *old, value))
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<DefaultValueStore> for ShardedValueStore {
+    fn from(store: DefaultValueStore) -> Self {
+        Self {
+            values: store
+                .values
+                .into_iter()
+                .map(std::sync::RwLock::new)
+                .collect(),
+            infos: store.infos,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl builder::ValueStoreBuilder for ShardedValueStore {
+    type Store = Self;
+
+    fn build(self) -> Self {
+        self
+    }
+
+    fn store<T, U>(&mut self, data: T) -> U
+    where
+        T: Into<ValueData>,
+        U: From<ValueId>,
+    {
+        self.store_info(data.into(), None).into()
+    }
+
+    fn store_with_info<T, U>(&mut self, data: T, info: ValueInfo) -> U
+    where
+        T: Into<ValueData>,
+        U: From<ValueId>,
+    {
+        self.store_info(data.into(), Some(info)).into()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ShardedValueStore {
+    fn store_info(&mut self, data: ValueData, info: Option<ValueInfo>) -> ValueId {
+        let id = u32::try_from(self.values.len())
+            .expect("the number of value stored in `ValueStore` must not exceed u32::MAX");
+        self.values.push(std::sync::RwLock::new(data));
+        if let Some(infos) = &mut self.infos {
+            infos.push(info);
+        }
+        ValueId(id)
+    }
+}
+
+macro_rules! impl_value_store_for_sharded {
+    ($($ty:ty),*) => {
+        $(
+            #[cfg(feature = "std")]
+            impl ValueStore for $ty {
+                fn value_opt<T>(&self, id: T) -> Option<Cow<'_, ValueData>>
+                where
+                    T: Into<ValueId>,
+                {
+                    self.get(id.into()).map(Cow::Owned)
+                }
+
+                fn update<T, U>(&mut self, id: T, value: U) -> Option<ValueData>
+                where
+                    T: Into<ValueId>,
+                    U: Into<ValueData>,
+                {
+                    self.replace(id.into(), value.into())
+                }
+
+                fn iter(&self) -> Box<dyn Iterator<Item = (ValueId, Cow<'_, ValueData>)> + '_> {
+                    Box::new((0..self.values.len()).filter_map(move |i| {
+                        let id = ValueId(i as u32);
+                        Some((id, Cow::Owned(self.get(id)?)))
+                    }))
+                }
+
+                fn value_info<T>(&self, id: T) -> Option<&ValueInfo>
+                where
+                    T: Into<ValueId>,
+                {
+                    self.infos.as_ref()?.get(id.into().to_usize())?.as_ref()
+                }
+            }
+        )*
+    };
+}
+
+impl_value_store_for_sharded!(
+    ShardedValueStore,
+    &ShardedValueStore,
+    std::sync::Arc<ShardedValueStore>
+);
+
+/// A [`CacheStore`] with interior mutability.
+///
+/// Cache is split into shards by [`NodeId`], each of which is guarded by its own lock, so that
+/// caching values of a node doesn't block reads of nodes in other shards. [`CacheStore`] is
+/// implemented for `&ShardedCacheStore` and `Arc<ShardedCacheStore>` as well, so that
+/// [`ValueCtxt`](crate::ValueCtxt)s on multiple threads can share the store.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ShardedCacheStore {
+    shards: Vec<std::sync::RwLock<CacheShard>>,
+    invalidators: HashMap<NodeId, Vec<NodeId>>,
+}
+
+#[cfg(feature = "std")]
+impl Default for ShardedCacheStore {
+    fn default() -> Self {
+        Self {
+            shards: (0..CACHE_SHARD_NUM).map(|_| Default::default()).collect(),
+            invalidators: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ShardedCacheStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn shard(&self, nid: NodeId) -> &std::sync::RwLock<CacheShard> {
+        &self.shards[nid.0 as usize % self.shards.len()]
+    }
+
+    fn cache_data(&self, nid: NodeId, address: i64, length: i64, data: &[u8]) {
+        let mut shard = self
+            .shard(nid)
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        shard
+            .entry(nid)
+            .or_default()
+            .insert((address, length), data.to_owned());
+    }
+
+    fn cached(&self, nid: NodeId, address: i64, length: i64) -> Option<Vec<u8>> {
+        let shard = self
+            .shard(nid)
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        shard.get(&nid)?.get(&(address, length)).cloned()
+    }
+
+    fn invalidate(&self, nid: NodeId) {
+        let mut shard = self
+            .shard(nid)
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        shard.remove(&nid);
+    }
+
+    fn invalidate_targets(&self, nid: NodeId) {
+        if let Some(target_nodes) = self.invalidators.get(&nid) {
+            for nid in target_nodes {
+                self.invalidate(*nid);
+            }
+        }
+    }
+
+    fn clear_all(&self) {
+        for shard in &self.shards {
+            shard
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<DefaultCacheStore> for ShardedCacheStore {
+    /// Cached values are discarded, only invalidators are taken over.
+    fn from(store: DefaultCacheStore) -> Self {
+        Self {
+            invalidators: store.invalidators,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl builder::CacheStoreBuilder for ShardedCacheStore {
+    type Store = Self;
+
+    fn build(self) -> Self {
+        self
+    }
+
+    fn store_invalidator(&mut self, invalidator: NodeId, target: NodeId) {
+        let entry = self.invalidators.entry(invalidator).or_default();
+        entry.push(target)
+    }
+}
+
+macro_rules! impl_cache_store_for_sharded {
+    ($($ty:ty),*) => {
+        $(
+            #[cfg(feature = "std")]
+            impl CacheStore for $ty {
+                fn cache(&mut self, nid: NodeId, address: i64, length: i64, data: &[u8]) {
+                    self.cache_data(nid, address, length, data);
+                }
+
+                fn get_cache(
+                    &self,
+                    nid: NodeId,
+                    address: i64,
+                    length: i64,
+                ) -> Option<Cow<'_, [u8]>> {
+                    self.cached(nid, address, length).map(Cow::Owned)
+                }
+
+                fn invalidate_by(&mut self, nid: NodeId) {
+                    self.invalidate_targets(nid);
+                }
+
+                fn invalidate_of(&mut self, nid: NodeId) {
+                    self.invalidate(nid);
+                }
+
+                fn clear(&mut self) {
+                    self.clear_all();
+                }
+            }
+        )*
+    };
+}
+
+impl_cache_store_for_sharded!(
+    ShardedCacheStore,
+    &ShardedCacheStore,
+    std::sync::Arc<ShardedCacheStore>
+);
+
+#[cfg(all(test, feature = "std"))]
+mod sharded_tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+    use crate::builder::{CacheStoreBuilder, ValueStoreBuilder};
+
+    #[test]
+    fn test_sharded_value_store() {
+        let mut store = ShardedValueStore::new();
+        let id: ValueId = store.store(1_i64);
+        let store = Arc::new(store);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let mut store = store.clone();
+                thread::spawn(move || {
+                    assert!(store.integer_value(id).is_some());
+                    store.update(id, 2_i64);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(store.integer_value(id), Some(2));
+        assert_eq!(store.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_sharded_cache_store() {
+        let mut store = ShardedCacheStore::new();
+        let (invalidator, target) = (NodeId(0), NodeId(1));
+        store.store_invalidator(invalidator, target);
+
+        let mut shared = &store;
+        shared.cache(target, 0, 4, &[1, 2, 3, 4]);
+        shared.cache(invalidator, 0, 4, &[0; 4]);
+        assert_eq!(
+            shared.get_cache(target, 0, 4).unwrap().as_ref(),
+            &[1, 2, 3, 4]
+        );
+        assert!(shared.get_cache(target, 4, 4).is_none());
+
+        shared.invalidate_by(invalidator);
+        assert!(shared.get_cache(target, 0, 4).is_none());
+        assert!(shared.get_cache(invalidator, 0, 4).is_some());
+
+        shared.clear();
+        assert!(shared.get_cache(invalidator, 0, 4).is_none());
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::builder::GenApiBuilder;
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Enumeration Name="Mode">
+                <EnumEntry Name="Off">
+                    <Value>0</Value>
+                </EnumEntry>
+                <EnumEntry Name="On">
+                    <Value>1</Value>
+                </EnumEntry>
+                <Value>0</Value>
+            </Enumeration>
+
+            <IntSwissKnife Name="Gain">
+                <pVariable Name="MODE">Mode</pVariable>
+                <Formula>MODE * 2 + 1</Formula>
+            </IntSwissKnife>
+
+            <IntReg Name="GainReg">
+                <Address>0x10</Address>
+                <Length>4</Length>
+                <pPort>Device</pPort>
+                <pInvalidator>Mode</pInvalidator>
+                <Endianess>LittleEndian</Endianess>
+            </IntReg>
+
+            <Port Name="Device" NameSpace="Standard">
+            </Port>
+
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let (reg_desc, node_store, value_ctxt) = GenApiBuilder::<DefaultNodeStore>::default()
+            .build(&XML)
+            .unwrap();
+
+        let bytes = bincode::serialize(&(&reg_desc, &node_store, &value_ctxt)).unwrap();
+        let (restored_reg_desc, restored_node_store, restored_value_ctxt): (
+            crate::RegisterDescription,
+            DefaultNodeStore,
+            crate::ValueCtxt<DefaultValueStore, DefaultCacheStore>,
+        ) = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(restored_reg_desc.model_name(), reg_desc.model_name());
+        assert_eq!(restored_node_store.store.len(), node_store.store.len());
+        for (i, data) in node_store.store.iter().enumerate() {
+            let nid = NodeId(i as u32);
+            assert_eq!(
+                restored_node_store.name_by_id(nid),
+                node_store.name_by_id(nid)
+            );
+            assert_eq!(
+                restored_node_store.store[i].is_some(),
+                data.is_some(),
+                "{:?}",
+                node_store.name_by_id(nid)
+            );
+        }
+        assert_eq!(
+            restored_value_ctxt.value_store.values,
+            value_ctxt.value_store.values
+        );
+        assert_eq!(
+            restored_value_ctxt.cache_store.invalidators,
+            value_ctxt.cache_store.invalidators
+        );
+
+        let gain = restored_node_store.id_by_name("Gain").unwrap();
+        assert!(matches!(
+            restored_node_store.node(gain),
+            NodeData::IntSwissKnife(_)
+        ));
+    }
+}
@@ -0,0 +1,530 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Stores which parse nodes on first access.
+//!
+//! [`LazyNodeStore`] only indexes node elements when it's built, and parses an element into
+//! [`NodeData`] when one of the nodes defined by the element is accessed for the first time. This
+//! reduces the startup latency of large description files of which only a few nodes are used.
+
+use core::{convert::TryFrom, ops::Range};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use string_interner::Symbol;
+use tracing::error;
+
+use super::{
+    CacheStore, DefaultCacheStore, DefaultValueStore, DuplicateNode, NodeData, NodeId, NodeStore,
+    ValueData, ValueId, ValueStore,
+};
+use crate::{
+    builder::{CacheStoreBuilder, NodeStoreBuilder},
+    parser::{self, ParseDiagnostics, ParseError, ParseResult, ParseWarning},
+    RegisterDescription, SchemaVersion,
+};
+
+use crate::compat::{Box, Cow, HashMap, String, Vec};
+
+/// Length of the first chunk of [`Slots`], each subsequent chunk is twice as long as the previous.
+const FIRST_CHUNK_LEN: usize = 64;
+
+/// Number of chunks of [`Slots`], which is enough to hold `u32::MAX` items.
+const CHUNK_NUM: usize = 26;
+
+/// An append-only arena whose items can be set through a shared reference.
+///
+/// Items are never moved once they are set, so references to them are valid as long as the arena
+/// is alive.
+#[derive(Debug)]
+struct Slots<T> {
+    chunks: Vec<OnceLock<Box<[OnceLock<T>]>>>,
+}
+
+impl<T> Slots<T> {
+    fn new() -> Self {
+        Self {
+            chunks: (0..CHUNK_NUM).map(|_| OnceLock::new()).collect(),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        let (chunk, offset) = Self::locate(index);
+        self.chunks.get(chunk)?.get()?.get(offset)?.get()
+    }
+
+    /// Sets the item at `index`. The item is discarded if `index` is already set.
+    fn set(&self, index: usize, item: T) {
+        let (chunk, offset) = Self::locate(index);
+        let chunk = self.chunks[chunk].get_or_init(|| {
+            (0..FIRST_CHUNK_LEN << chunk)
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+        chunk[offset].set(item).ok();
+    }
+
+    fn locate(index: usize) -> (usize, usize) {
+        let n = index / FIRST_CHUNK_LEN + 1;
+        let chunk = (usize::BITS - 1 - n.leading_zeros()) as usize;
+        (chunk, index - FIRST_CHUNK_LEN * ((1 << chunk) - 1))
+    }
+}
+
+type PendingInvalidators = Arc<Mutex<Vec<(NodeId, NodeId)>>>;
+
+/// A [`NodeStore`] which parses nodes on first access.
+///
+/// Use [`build_lazy`](crate::builder::build_lazy) to build the store along with
+/// [`LazyValueStore`] and [`LazyCacheStore`], which receive values and invalidators of nodes as
+/// they are parsed.
+///
+/// [`NodeStore::visit_nodes`] parses all remaining nodes.
+///
+/// Building fails with [`ParseError::DuplicateNode`] if a node is defined more than once, and
+/// with [`ParseError::UnresolvedDocument`] if the document includes external documents by
+/// `pInclude`. Use [`GenApiBuilder`](crate::builder::GenApiBuilder) to parse such documents.
+///
+/// A node whose element fails to be parsed isn't found by [`NodeStore::node_opt`], use
+/// [`Self::try_node_opt`] to get the error. Warnings reported while parsing elements are
+/// collected in the store, see [`Self::take_warnings`].
+#[derive(Debug)]
+pub struct LazyNodeStore {
+    src: String,
+    /// Span of the element which defines each node.
+    spans: HashMap<NodeId, Range<usize>>,
+    names: Slots<String>,
+    ids: Mutex<HashMap<String, NodeId>>,
+    nodes: Slots<NodeData>,
+    schema_version: SchemaVersion,
+    strict: bool,
+    warnings: Mutex<Vec<ParseWarning>>,
+    /// Serializes parsing of elements.
+    materializer: Mutex<Materializer>,
+}
+
+#[derive(Debug)]
+struct Materializer {
+    values: Arc<Mutex<DefaultValueStore>>,
+    invalidators: PendingInvalidators,
+    fresh_id: u32,
+}
+
+impl LazyNodeStore {
+    /// Indexes node elements of `xml`. Returns the stores along with the register description
+    /// of the document.
+    ///
+    /// Elements are parsed strictly if `diagnostics` is strict, and warnings found while indexing
+    /// are reported to `diagnostics`.
+    pub(crate) fn index(
+        xml: String,
+        diagnostics: &mut ParseDiagnostics,
+    ) -> ParseResult<(RegisterDescription, Self, LazyValueStore, LazyCacheStore)> {
+        let values = Arc::new(Mutex::new(DefaultValueStore::new()));
+        let invalidators = PendingInvalidators::default();
+        let mut store = Self {
+            src: String::new(),
+            spans: HashMap::new(),
+            names: Slots::new(),
+            ids: Mutex::default(),
+            nodes: Slots::new(),
+            schema_version: SchemaVersion::LATEST,
+            strict: diagnostics.is_strict(),
+            warnings: Mutex::default(),
+            materializer: Mutex::new(Materializer {
+                values: values.clone(),
+                invalidators: invalidators.clone(),
+                fresh_id: 0,
+            }),
+        };
+
+        let index = parser::index_nodes(&xml)?;
+        let reg_desc = index.reg_desc;
+        store.schema_version = reg_desc.schema_version();
+        if !store.schema_version.is_supported() {
+            diagnostics.extend(Some(ParseWarning::UnsupportedSchemaVersion {
+                version: store.schema_version,
+            }));
+        }
+        for (names, span) in index.nodes {
+            for name in names {
+                let nid = store.intern(name);
+                if store.spans.insert(nid, span.clone()).is_some() {
+                    return Err(ParseError::DuplicateNode(DuplicateNode {
+                        name: name.into(),
+                        kept_document: 0,
+                        discarded_document: 0,
+                    }));
+                }
+            }
+        }
+        store.src = xml;
+
+        Ok((
+            reg_desc,
+            store,
+            LazyValueStore(values),
+            LazyCacheStore {
+                store: DefaultCacheStore::new(),
+                pending: invalidators,
+            },
+        ))
+    }
+
+    /// Returns the node with `nid`, parsing its element if it isn't parsed yet.
+    ///
+    /// Returns `Ok(None)` if no element defines the node, or an error if the element fails to be
+    /// parsed.
+    pub fn try_node_opt(&self, nid: NodeId) -> ParseResult<Option<&NodeData>> {
+        match self.nodes.get(nid.to_usize()) {
+            Some(data) => Ok(Some(data)),
+            None => self.materialize(nid),
+        }
+    }
+
+    /// Takes the warnings reported while parsing elements so far.
+    pub fn take_warnings(&self) -> Vec<ParseWarning> {
+        core::mem::take(&mut *self.warnings.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Returns the number of parsed nodes.
+    #[must_use]
+    pub fn materialized_count(&self) -> usize {
+        (0..self.len())
+            .filter(|i| self.nodes.get(*i).is_some())
+            .count()
+    }
+
+    fn len(&self) -> usize {
+        self.ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    fn intern(&self, name: &str) -> NodeId {
+        let mut ids = self.ids.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(nid) = ids.get(name) {
+            return *nid;
+        }
+        let nid = NodeId(
+            u32::try_from(ids.len())
+                .expect("the number of nodes stored in `NodeStore` must not exceed u32::MAX"),
+        );
+        self.names.set(nid.to_usize(), name.into());
+        ids.insert(name.into(), nid);
+        nid
+    }
+
+    fn materialize(&self, nid: NodeId) -> ParseResult<Option<&NodeData>> {
+        let span = match self.spans.get(&nid) {
+            Some(span) => span,
+            None => return Ok(None),
+        };
+        let mut materializer = self
+            .materializer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Another thread may have parsed the element while waiting for the lock.
+        if let Some(data) = self.nodes.get(nid.to_usize()) {
+            return Ok(Some(data));
+        }
+
+        let values = materializer.values.clone();
+        let invalidators = materializer.invalidators.clone();
+        let mut values = values.lock().unwrap_or_else(PoisonError::into_inner);
+        let (nodes, warnings) = parser::parse_node_element(
+            &self.src[span.clone()],
+            &mut LazyNodeBuilder {
+                store: self,
+                fresh_id: &mut materializer.fresh_id,
+            },
+            &mut *values,
+            &mut InvalidatorSink(&invalidators),
+            self.schema_version,
+            self.strict,
+        )?;
+        for data in nodes {
+            self.nodes.set(data.node_base().id().to_usize(), data);
+        }
+        self.warnings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(warnings);
+
+        Ok(self.nodes.get(nid.to_usize()))
+    }
+}
+
+impl NodeStore for LazyNodeStore {
+    fn name_by_id(&self, nid: NodeId) -> Option<&str> {
+        self.names.get(nid.to_usize()).map(String::as_str)
+    }
+
+    fn id_by_name<T>(&self, s: T) -> Option<NodeId>
+    where
+        T: AsRef<str>,
+    {
+        let ids = self.ids.lock().unwrap_or_else(PoisonError::into_inner);
+        ids.get(s.as_ref()).copied()
+    }
+
+    fn node_opt(&self, nid: NodeId) -> Option<&NodeData> {
+        match self.try_node_opt(nid) {
+            Ok(data) => data,
+            Err(err) => {
+                error!(node = self.name_by_id(nid), %err, "failed to parse node");
+                None
+            }
+        }
+    }
+
+    fn visit_nodes<F>(&self, mut f: F)
+    where
+        F: FnMut(&NodeData),
+    {
+        for nid in self.spans.keys() {
+            self.node_opt(*nid);
+        }
+        for i in 0..self.len() {
+            if let Some(data) = self.nodes.get(i) {
+                f(data);
+            }
+        }
+    }
+
+    fn schema_version(&self) -> Option<SchemaVersion> {
+        Some(self.schema_version)
+    }
+}
+
+/// Stores nodes nested in an element being parsed, e.g. `EnumEntry`, to [`LazyNodeStore`].
+struct LazyNodeBuilder<'a> {
+    store: &'a LazyNodeStore,
+    fresh_id: &'a mut u32,
+}
+
+impl NodeStoreBuilder for LazyNodeBuilder<'_> {
+    type Store = ();
+
+    fn build(self) {}
+
+    fn store_node(&mut self, nid: NodeId, data: NodeData) {
+        self.store.nodes.set(nid.to_usize(), data);
+    }
+
+    fn get_or_intern<T>(&mut self, node_name: T) -> NodeId
+    where
+        T: AsRef<str>,
+    {
+        self.store.intern(node_name.as_ref())
+    }
+
+    fn fresh_id(&mut self) -> u32 {
+        let id = *self.fresh_id;
+        *self.fresh_id += 1;
+        id
+    }
+}
+
+/// Queues invalidators of parsed nodes until [`LazyCacheStore`] takes them over.
+struct InvalidatorSink<'a>(&'a Mutex<Vec<(NodeId, NodeId)>>);
+
+impl CacheStoreBuilder for InvalidatorSink<'_> {
+    type Store = ();
+
+    fn build(self) {}
+
+    fn store_invalidator(&mut self, invalidator: NodeId, target: NodeId) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((invalidator, target));
+    }
+}
+
+/// A [`ValueStore`] which shares values with [`LazyNodeStore`].
+///
+/// Values of a node are stored when the node is parsed.
+#[derive(Debug, Clone)]
+pub struct LazyValueStore(Arc<Mutex<DefaultValueStore>>);
+
+impl ValueStore for LazyValueStore {
+    fn value_opt<T>(&self, id: T) -> Option<Cow<'_, ValueData>>
+    where
+        T: Into<ValueId>,
+    {
+        let values = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        Some(Cow::Owned(values.value_opt(id)?.into_owned()))
+    }
+
+    fn update<T, U>(&mut self, id: T, value: U) -> Option<ValueData>
+    where
+        T: Into<ValueId>,
+        U: Into<ValueData>,
+    {
+        let mut values = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        values.update(id, value)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (ValueId, Cow<'_, ValueData>)> + '_> {
+        let values = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let values: Vec<_> = values
+            .iter()
+            .map(|(id, value)| (id, Cow::Owned(value.into_owned())))
+            .collect();
+        Box::new(values.into_iter())
+    }
+}
+
+/// A [`CacheStore`] which takes over invalidators of nodes parsed by [`LazyNodeStore`].
+#[derive(Debug)]
+pub struct LazyCacheStore {
+    store: DefaultCacheStore,
+    pending: PendingInvalidators,
+}
+
+impl LazyCacheStore {
+    fn sync_invalidators(&mut self) {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        for (invalidator, target) in pending.drain(..) {
+            self.store.store_invalidator(invalidator, target);
+        }
+    }
+}
+
+impl CacheStore for LazyCacheStore {
+    fn cache(&mut self, nid: NodeId, address: i64, length: i64, data: &[u8]) {
+        self.store.cache(nid, address, length, data);
+    }
+
+    fn get_cache(&self, nid: NodeId, address: i64, length: i64) -> Option<Cow<'_, [u8]>> {
+        self.store.get_cache(nid, address, length)
+    }
+
+    fn invalidate_by(&mut self, nid: NodeId) {
+        self.sync_invalidators();
+        self.store.invalidate_by(nid);
+    }
+
+    fn invalidate_of(&mut self, nid: NodeId) {
+        self.store.invalidate_of(nid);
+    }
+
+    fn clear(&mut self) {
+        self.store.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::IEnumeration;
+
+    const XML: &str = r#"
+        <RegisterDescription
+          ModelName="CameleonModel"
+          VendorName="CameleonVendor"
+          StandardNameSpace="None"
+          SchemaMajorVersion="1"
+          SchemaMinorVersion="1"
+          SchemaSubMinorVersion="0"
+          MajorVersion="1"
+          MinorVersion="2"
+          SubMinorVersion="3"
+          ProductGuid="01234567-0123-0123-0123-0123456789ab"
+          VersionGuid="76543210-3210-3210-3210-ba9876543210"
+          xmlns="http://www.genicam.org/GenApi/Version_1_0"
+          xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+          xsi:schemaLocation="http://www.genicam.org/GenApi/Version_1_0 GenApiSchema.xsd">
+
+            <Enumeration Name="Mode">
+                <EnumEntry Name="Off">
+                    <Value>0</Value>
+                </EnumEntry>
+                <EnumEntry Name="On">
+                    <Value>1</Value>
+                </EnumEntry>
+                <Value>0</Value>
+            </Enumeration>
+
+            <Group Comment="Registers">
+                <IntReg Name="GainReg">
+                    <Address>0x10</Address>
+                    <Length>4</Length>
+                    <pPort>Device</pPort>
+                    <pInvalidator>Mode</pInvalidator>
+                    <Endianess>LittleEndian</Endianess>
+                </IntReg>
+            </Group>
+
+            <Port Name="Device" NameSpace="Standard">
+            </Port>
+
+        </RegisterDescription>
+        "#;
+
+    #[test]
+    fn test_slots() {
+        let slots = Slots::new();
+        for i in [0, 63, 64, 191, 192, 100_000] {
+            assert!(slots.get(i).is_none());
+            slots.set(i, i);
+            slots.set(i, 0);
+            assert_eq!(slots.get(i), Some(&i));
+        }
+        assert_eq!(Slots::<()>::locate(64), (1, 0));
+        assert_eq!(Slots::<()>::locate(191), (1, 127));
+        assert_eq!(Slots::<()>::locate(192), (2, 0));
+    }
+
+    #[test]
+    fn test_lazy_node_store() {
+        let (reg_desc, store, values, mut cache) =
+            LazyNodeStore::index(XML.into(), &mut ParseDiagnostics::new()).unwrap();
+        assert_eq!(reg_desc.model_name(), "CameleonModel");
+        assert_eq!(store.materialized_count(), 0);
+
+        let gain_reg = store.id_by_name("GainReg").unwrap();
+        assert!(matches!(store.node(gain_reg), NodeData::IntReg(_)));
+        assert_eq!(store.materialized_count(), 1);
+
+        let mode = store.id_by_name("Mode").unwrap();
+        let entries = match store.node(mode) {
+            NodeData::Enumeration(node) => node.entries(&store).to_vec(),
+            _ => panic!("`Mode` must be an enumeration"),
+        };
+        assert_eq!(entries.len(), 2);
+        assert!(values.iter().count() > 0);
+
+        // The invalidator of `GainReg` is registered when it's parsed.
+        cache.cache(gain_reg, 0x10, 4, &[0; 4]);
+        cache.invalidate_by(mode);
+        assert!(cache.get_cache(gain_reg, 0x10, 4).is_none());
+
+        let mut count = 0;
+        store.visit_nodes(|_| count += 1);
+        assert_eq!(count, store.materialized_count());
+        assert_eq!(count, 5);
+        assert!(store.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_lazy_node_store_errors() {
+        let xml = XML.replace(
+            r#"<Port Name="Device" NameSpace="Standard">"#,
+            r#"<Port Name="GainReg" NameSpace="Standard">"#,
+        );
+        assert!(matches!(
+            LazyNodeStore::index(xml, &mut ParseDiagnostics::new()),
+            Err(ParseError::DuplicateNode(duplicate)) if duplicate.name == "GainReg"
+        ));
+
+        // An element which fails to be parsed is reported when the node is accessed.
+        let xml = XML.replace("<Address>0x10</Address>", "<Address>&unknown;</Address>");
+        let (_, store, _, _) = LazyNodeStore::index(xml, &mut ParseDiagnostics::new()).unwrap();
+        let gain_reg = store.id_by_name("GainReg").unwrap();
+        assert!(store.try_node_opt(gain_reg).is_err());
+        assert!(store.node_opt(gain_reg).is_none());
+    }
+}
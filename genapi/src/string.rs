@@ -2,16 +2,21 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use core::convert::TryFrom;
+
 use super::{
     elem_type::ImmOrPNode,
     interface::{INode, IString},
     ivalue::IValue,
     node_base::{NodeAttributeBase, NodeBase, NodeElementBase},
     store::{CacheStore, NodeStore, StringId, ValueStore},
-    Device, GenApiResult, ValueCtxt,
+    Device, GenApiError, GenApiResult, StringError, ValueCtxt,
 };
 
+use crate::compat::String;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -65,6 +70,10 @@ impl IString for StringNode {
         store: &impl NodeStore,
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
+        self.elem_base.verify_unlocked(device, store, cx)?;
+        let max_length = self.max_length(device, store, cx)?;
+        StringError::check(&value, usize::try_from(max_length).unwrap_or(usize::MAX))
+            .map_err(GenApiError::invalid_string)?;
         cx.invalidate_cache_by(self.node_base().id());
         self.value.set_value(value, device, store, cx)
     }
@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use crate::GenApiError;
+use crate::{GenApiError, StringError};
 
 use super::{
     interface::{INode, IRegister, IString},
@@ -12,7 +12,10 @@ use super::{
     Device, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, ToString};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StringRegNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) register_base: RegisterBase,
@@ -65,16 +68,7 @@ impl IString for StringRegNode {
         cx: &mut ValueCtxt<T, U>,
     ) -> GenApiResult<()> {
         let max_length = self.max_length(device, store, cx)? as usize;
-        if !value.is_ascii() {
-            return Err(GenApiError::invalid_data(
-                "the data must be an ascii string".into(),
-            ));
-        }
-        if value.len() > max_length {
-            return Err(GenApiError::invalid_data(
-                "the data length exceeds the maximum length allowed by the node.".into(),
-            ));
-        }
+        StringError::check(&value, max_length).map_err(GenApiError::invalid_string)?;
 
         let nid = self.node_base().id();
         cx.invalidate_cache_by(nid);
@@ -172,3 +166,28 @@ impl IRegister for StringRegNode {
         self.register_base().length(device, store, cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_error() {
+        assert!(StringError::check("Camera", 6).is_ok());
+        assert_eq!(
+            StringError::check("Camera1", 6),
+            Err(StringError::TooLong {
+                length: 7,
+                max_length: 6
+            })
+        );
+        assert_eq!(
+            StringError::check("Kamera\u{e4}", 16),
+            Err(StringError::NonAscii { position: 6 })
+        );
+        assert_eq!(
+            StringError::check("Cam\0era", 16),
+            Err(StringError::ContainsNul { position: 3 })
+        );
+    }
+}
@@ -11,7 +11,10 @@ use super::{
     utils, Device, GenApiError, GenApiResult, ValueCtxt,
 };
 
+use crate::compat::{String, Vec};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SwissKnifeNode {
     pub(crate) attr_base: NodeAttributeBase,
     pub(crate) elem_base: NodeElementBase,
@@ -92,7 +95,8 @@ impl IFloat for SwissKnifeNode {
         let var_env =
             utils::FormulaEnvCollector::new(&self.p_variables, &self.constants, &self.expressions)
                 .collect(device, store, cx)?;
-        let eval_result = self.formula.eval(&var_env)?;
+        let eval_result =
+            cx.eval_formula(self.node_base().id(), "Formula", &self.formula, &var_env)?;
         Ok(eval_result.as_float())
     }
 
@@ -146,6 +150,10 @@ impl IFloat for SwissKnifeNode {
         Ok(None)
     }
 
+    fn valid_value_set(&self, _: &impl NodeStore) -> &[f64] {
+        &[]
+    }
+
     fn representation(&self, _: &impl NodeStore) -> FloatRepresentation {
         self.representation
     }
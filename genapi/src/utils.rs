@@ -2,17 +2,22 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{borrow::Cow, collections::HashMap, convert::TryInto};
+use alloc::borrow::Cow;
+use core::convert::TryInto;
 
 use super::{
-    elem_type::{Endianness, NamedValue, Sign},
+    elem_type::{Endianness, NamedValue, Sign, Slope},
     formula::EvaluationResult,
     formula::Expr,
     interface::{IBoolean, IEnumeration, IFloat, IInteger},
     store::{CacheStore, NodeId, NodeStore, ValueStore},
-    Device, GenApiError, GenApiResult, ValueCtxt,
+    Device, GenApiError, GenApiResult, ValueCtxt, ValuePolicy,
 };
 
+#[cfg(not(feature = "std"))]
+use crate::compat::FloatExt;
+use crate::compat::{HashMap, Vec};
+
 pub(super) fn bool_from_id<T: ValueStore, U: CacheStore>(
     node_id: NodeId,
     device: &mut impl Device,
@@ -75,6 +80,66 @@ pub(super) fn bytes_from_int(
     convert_to_slice!((8, i64, u64), (4, i32, u32), (2, i16, u16), (1, i8, u8))
 }
 
+/// Same as [`int_from_slice`], but accepts a buffer of any length up to 16 bytes.
+///
+/// A value of unsigned 16 bytes register which exceeds `i128::MAX` wraps around.
+pub(super) fn i128_from_slice(
+    slice: &[u8],
+    endianness: Endianness,
+    sign: Sign,
+) -> GenApiResult<i128> {
+    let len = slice.len();
+    if len == 0 || len > 16 {
+        return Err(GenApiError::invalid_buffer(
+            "buffer length must be between 1 and 16 to convert to i128".into(),
+        ));
+    }
+
+    let mut bytes = [0; 16];
+    match endianness {
+        Endianness::LE => bytes[..len].copy_from_slice(slice),
+        Endianness::BE => {
+            for (dst, src) in bytes.iter_mut().zip(slice.iter().rev()) {
+                *dst = *src;
+            }
+        }
+    }
+
+    let value = i128::from_le_bytes(bytes);
+    let shift = 128 - len * 8;
+    match sign {
+        // Do sign extension.
+        Sign::Signed => Ok((value << shift) >> shift),
+        Sign::Unsigned => Ok(value),
+    }
+}
+
+/// Same as [`bytes_from_int`], but accepts a buffer of any length up to 16 bytes. The value is
+/// truncated to the buffer length.
+pub(super) fn bytes_from_i128(
+    value: i128,
+    buf: &mut [u8],
+    endianness: Endianness,
+) -> GenApiResult<()> {
+    let len = buf.len();
+    if len == 0 || len > 16 {
+        return Err(GenApiError::invalid_buffer(
+            "buffer length must be between 1 and 16 to convert from i128".into(),
+        ));
+    }
+
+    let bytes = value.to_le_bytes();
+    match endianness {
+        Endianness::LE => buf.copy_from_slice(&bytes[..len]),
+        Endianness::BE => {
+            for (dst, src) in buf.iter_mut().rev().zip(&bytes[..len]) {
+                *dst = *src;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub(super) fn float_from_slice(slice: &[u8], endianness: Endianness) -> GenApiResult<f64> {
     match (slice.len(), endianness) {
         (8, Endianness::LE) => Ok(f64::from_le_bytes(slice.try_into().unwrap())),
@@ -383,6 +448,260 @@ fn expr_from_nid<T: ValueStore, U: CacheStore>(
     })
 }
 
+/// Applies `policy` to `value` so that it satisfies the constraints of an integer node.
+///
+/// Valid values are `min + n * inc` in `[min, max]`, or the elements of `valid_set` in `[min, max]`
+/// if `valid_set` isn't empty.
+pub(super) fn constrain_int(
+    value: i64,
+    min: i64,
+    max: i64,
+    inc: Option<i64>,
+    valid_set: &[i64],
+    policy: ValuePolicy,
+) -> GenApiResult<i64> {
+    if policy == ValuePolicy::Strict && (value < min || max < value) {
+        return Err(GenApiError::invalid_data(
+            format!("{} is out of range [{}, {}]", value, min, max).into(),
+        ));
+    }
+    let clamped = value.clamp(min, max.max(min));
+
+    if !valid_set.is_empty() {
+        let mut candidates = valid_set
+            .iter()
+            .copied()
+            .filter(|v| (min..=max).contains(v));
+        let nearest = match policy {
+            ValuePolicy::Strict | ValuePolicy::Clamp => candidates.find(|v| *v == clamped),
+            ValuePolicy::RoundDown => candidates
+                .rev()
+                .find(|v| *v <= clamped)
+                .or_else(|| valid_set.iter().copied().find(|v| (min..=max).contains(v))),
+            ValuePolicy::RoundNearest => {
+                candidates.min_by_key(|v| (i128::from(*v) - i128::from(clamped)).abs())
+            }
+        };
+        return nearest.ok_or_else(|| {
+            GenApiError::invalid_data(format!("{} is not in the valid value set", value).into())
+        });
+    }
+
+    let inc = match inc {
+        Some(inc) if inc > 0 => i128::from(inc),
+        _ => return Ok(clamped),
+    };
+    let (min_, clamped_) = (i128::from(min), i128::from(clamped));
+    let rem = (clamped_ - min_) % inc;
+    if rem == 0 {
+        return Ok(clamped);
+    }
+    let rounded = match policy {
+        ValuePolicy::Strict | ValuePolicy::Clamp => {
+            return Err(GenApiError::invalid_data(
+                format!("{} doesn't match the increment {} from {}", value, inc, min).into(),
+            ))
+        }
+        ValuePolicy::RoundDown => clamped_ - rem,
+        ValuePolicy::RoundNearest if rem * 2 >= inc && clamped_ - rem + inc <= i128::from(max) => {
+            clamped_ - rem + inc
+        }
+        ValuePolicy::RoundNearest => clamped_ - rem,
+    };
+    Ok(rounded as i64)
+}
+
+/// Applies `policy` to `value` so that it satisfies the constraints of a float node.
+///
+/// Same as [`constrain_int`], but a value within `inc * FLOAT_INC_TOLERANCE` of a valid value is
+/// regarded as valid to absorb rounding errors of floating point arithmetic.
+pub(super) fn constrain_float(
+    value: f64,
+    min: f64,
+    max: f64,
+    inc: Option<f64>,
+    valid_set: &[f64],
+    policy: ValuePolicy,
+) -> GenApiResult<f64> {
+    const FLOAT_INC_TOLERANCE: f64 = 1e-6;
+
+    if value.is_nan() {
+        return Err(GenApiError::invalid_data(
+            "NaN can't be set to the node".into(),
+        ));
+    }
+    if policy == ValuePolicy::Strict && (value < min || max < value) {
+        return Err(GenApiError::invalid_data(
+            format!("{} is out of range [{}, {}]", value, min, max).into(),
+        ));
+    }
+    let clamped = value.max(min).min(max.max(min));
+
+    if !valid_set.is_empty() {
+        let mut candidates = valid_set.iter().copied().filter(|v| min <= *v && *v <= max);
+        let nearest = match policy {
+            ValuePolicy::Strict | ValuePolicy::Clamp => candidates.find(|v| *v == clamped),
+            ValuePolicy::RoundDown => candidates
+                .rev()
+                .find(|v| *v <= clamped)
+                .or_else(|| valid_set.iter().copied().find(|v| min <= *v && *v <= max)),
+            ValuePolicy::RoundNearest => candidates.fold(None, |acc: Option<f64>, v| match acc {
+                Some(acc) if (acc - clamped).abs() <= (v - clamped).abs() => Some(acc),
+                _ => Some(v),
+            }),
+        };
+        return nearest.ok_or_else(|| {
+            GenApiError::invalid_data(format!("{} is not in the valid value set", value).into())
+        });
+    }
+
+    let inc = match inc {
+        Some(inc) if inc > 0.0 && inc.is_finite() => inc,
+        _ => return Ok(clamped),
+    };
+    // Use zero as the origin of increments if `min` is too far from zero to count increments
+    // from it precisely, e.g. `f64::MIN` which is the default minimum of float nodes.
+    let origin = if min.is_finite() && (min / inc).abs() < 9_007_199_254_740_992.0 {
+        min
+    } else {
+        0.0
+    };
+    let steps = (clamped - origin) / inc;
+    if (steps - steps.round()).abs() <= FLOAT_INC_TOLERANCE {
+        return Ok(clamped);
+    }
+    let rounded = match policy {
+        ValuePolicy::Strict | ValuePolicy::Clamp => {
+            return Err(GenApiError::invalid_data(
+                format!(
+                    "{} doesn't match the increment {} from {}",
+                    value, inc, origin
+                )
+                .into(),
+            ))
+        }
+        ValuePolicy::RoundDown => origin + steps.floor() * inc,
+        ValuePolicy::RoundNearest => {
+            let nearest = origin + steps.round() * inc;
+            if nearest > max {
+                nearest - inc
+            } else {
+                nearest
+            }
+        }
+    };
+    Ok(rounded)
+}
+
+/// Returns `true` if a function is increasing judging from its values at the both ends of the
+/// domain, and verifies the result against `slope`.
+fn verify_slope(slope: Slope, f_lo: f64, f_hi: f64) -> GenApiResult<bool> {
+    match slope {
+        Slope::Increasing if f_lo > f_hi => Err(GenApiError::invalid_node(
+            "`Slope` is `Increasing`, but `FormulaFrom` is decreasing".into(),
+        )),
+        Slope::Decreasing if f_lo < f_hi => Err(GenApiError::invalid_node(
+            "`Slope` is `Decreasing`, but `FormulaFrom` is increasing".into(),
+        )),
+        Slope::Increasing => Ok(true),
+        Slope::Decreasing => Ok(false),
+        Slope::Automatic | Slope::Varying => Ok(f_lo <= f_hi),
+    }
+}
+
+fn verify_bracket(target: f64, f_lo: f64, f_hi: f64) -> GenApiResult<()> {
+    if f_lo.is_nan() || f_hi.is_nan() {
+        return Err(GenApiError::invalid_node(
+            "`FormulaFrom` evaluates to NaN".into(),
+        ));
+    }
+    let (min, max) = if f_lo <= f_hi {
+        (f_lo, f_hi)
+    } else {
+        (f_hi, f_lo)
+    };
+    if target < min || max < target {
+        Err(GenApiError::invalid_data(
+            format!("{} is out of range [{}, {}]", target, min, max).into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Finds `x` in `[lo, hi]` where `f(x)` is the closest to `target` by bisection.
+///
+/// `f` must be monotonic in `[lo, hi]`, or at least `target` must be bracketed by `f(lo)` and
+/// `f(hi)` in case of [`Slope::Varying`].
+pub(super) fn solve_inverse_int(
+    target: f64,
+    lo: i64,
+    hi: i64,
+    slope: Slope,
+    mut f: impl FnMut(i64) -> GenApiResult<f64>,
+) -> GenApiResult<i64> {
+    let (mut f_lo, mut f_hi) = (f(lo)?, f(hi)?);
+    let is_increasing = verify_slope(slope, f_lo, f_hi)?;
+    verify_bracket(target, f_lo, f_hi)?;
+
+    let (mut lo, mut hi) = (i128::from(lo), i128::from(hi));
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        let f_mid = f(mid as i64)?;
+        if (f_mid < target) == is_increasing {
+            lo = mid;
+            f_lo = f_mid;
+        } else {
+            hi = mid;
+            f_hi = f_mid;
+        }
+    }
+
+    if (f_lo - target).abs() <= (f_hi - target).abs() {
+        Ok(lo as i64)
+    } else {
+        Ok(hi as i64)
+    }
+}
+
+/// Same as [`solve_inverse_int`], but for a continuous domain.
+pub(super) fn solve_inverse_float(
+    target: f64,
+    lo: f64,
+    hi: f64,
+    slope: Slope,
+    mut f: impl FnMut(f64) -> GenApiResult<f64>,
+) -> GenApiResult<f64> {
+    let (mut f_lo, mut f_hi) = (f(lo)?, f(hi)?);
+    let is_increasing = verify_slope(slope, f_lo, f_hi)?;
+    verify_bracket(target, f_lo, f_hi)?;
+
+    let (mut lo, mut hi) = (lo, hi);
+    loop {
+        // Avoid overflow of `hi - lo` when the domain is e.g. `[f64::MIN, f64::MAX]`.
+        let mid = lo / 2.0 + hi / 2.0;
+        if mid <= lo || hi <= mid {
+            break;
+        }
+        let f_mid = f(mid)?;
+        if f_mid == target {
+            return Ok(mid);
+        } else if (f_mid < target) == is_increasing {
+            lo = mid;
+            f_lo = f_mid;
+        } else {
+            hi = mid;
+            f_hi = f_mid;
+        }
+    }
+
+    if (f_lo - target).abs() <= (f_hi - target).abs() {
+        Ok(lo)
+    } else {
+        Ok(hi)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -402,4 +721,115 @@ mod tests {
 
         assert!(bytes_from_float(value, &mut [], Endianness::LE).is_err());
     }
+
+    #[test]
+    fn test_i128_from_slice() {
+        let guid = [
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0xfe, 0xdc, 0xba, 0x98, 0x76, 0x54,
+            0x32, 0x10,
+        ];
+        let value = i128_from_slice(&guid, Endianness::BE, Sign::Unsigned).unwrap();
+        assert_eq!(value, 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210);
+        let mut buf = [0; 16];
+        bytes_from_i128(value, &mut buf, Endianness::BE).unwrap();
+        assert_eq!(buf, guid);
+
+        // 12 bytes register.
+        let mut buf = [0; 12];
+        bytes_from_i128(-2, &mut buf, Endianness::LE).unwrap();
+        assert_eq!(buf[0], 0xfe);
+        assert_eq!(
+            i128_from_slice(&buf, Endianness::LE, Sign::Signed).unwrap(),
+            -2
+        );
+        assert_eq!(
+            i128_from_slice(&buf, Endianness::LE, Sign::Unsigned).unwrap(),
+            (1 << 96) - 2
+        );
+
+        assert!(i128_from_slice(&[0; 17], Endianness::LE, Sign::Signed).is_err());
+        assert!(bytes_from_i128(0, &mut [], Endianness::LE).is_err());
+    }
+
+    #[test]
+    fn test_constrain_int() {
+        use ValuePolicy::{Clamp, RoundDown, RoundNearest, Strict};
+
+        // Valid values are 2, 5, 8 and 11.
+        let constrain = |value, policy| constrain_int(value, 2, 12, Some(3), &[], policy);
+        assert_eq!(constrain(5, Strict).unwrap(), 5);
+        assert!(constrain(6, Strict).is_err());
+        assert!(constrain(14, Strict).is_err());
+        assert!(constrain(6, Clamp).is_err());
+        assert_eq!(constrain(0, Clamp).unwrap(), 2);
+        assert_eq!(constrain(7, RoundDown).unwrap(), 5);
+        assert_eq!(constrain(7, RoundNearest).unwrap(), 8);
+        assert_eq!(constrain(6, RoundNearest).unwrap(), 5);
+        // 12 is clamped to 12, then rounded down to 11 since 14 exceeds the max.
+        assert_eq!(constrain(100, RoundNearest).unwrap(), 11);
+
+        let constrain = |value, policy| constrain_int(value, 0, 10, None, &[1, 4, 8], policy);
+        assert_eq!(constrain(4, Strict).unwrap(), 4);
+        assert!(constrain(5, Strict).is_err());
+        assert_eq!(constrain(7, RoundDown).unwrap(), 4);
+        assert_eq!(constrain(0, RoundDown).unwrap(), 1);
+        assert_eq!(constrain(7, RoundNearest).unwrap(), 8);
+        assert_eq!(constrain(100, RoundNearest).unwrap(), 8);
+    }
+
+    #[test]
+    fn test_solve_inverse() {
+        // FROM = TO * 2 + 1.
+        let f = |to: i64| Ok(to as f64 * 2.0 + 1.0);
+        assert_eq!(
+            solve_inverse_int(9.0, 0, 100, Slope::Automatic, f).unwrap(),
+            4
+        );
+        assert_eq!(
+            solve_inverse_int(10.2, 0, 100, Slope::Increasing, f).unwrap(),
+            5
+        );
+        assert!(solve_inverse_int(9.0, 0, 100, Slope::Decreasing, f).is_err());
+        assert!(solve_inverse_int(500.0, 0, 100, Slope::Automatic, f).is_err());
+        let f = |to: i64| Ok(to as f64 * 2.0 + 1.0);
+        assert_eq!(
+            solve_inverse_int(9.0, i64::MIN, i64::MAX, Slope::Automatic, f).unwrap(),
+            4
+        );
+
+        // FROM = -TO / 4.
+        let f = |to: f64| Ok(-to / 4.0);
+        let to = solve_inverse_float(1.5, -100.0, 100.0, Slope::Automatic, f).unwrap();
+        assert!((to + 6.0).abs() < 1e-9);
+        let to = solve_inverse_float(1.5, f64::MIN, f64::MAX, Slope::Decreasing, f).unwrap();
+        assert!((to + 6.0).abs() < 1e-9);
+        assert!(solve_inverse_float(1.5, -100.0, 100.0, Slope::Increasing, f).is_err());
+    }
+
+    #[test]
+    fn test_constrain_float() {
+        use ValuePolicy::{Clamp, RoundDown, RoundNearest, Strict};
+
+        let constrain = |value, policy| constrain_float(value, 1.0, 2.0, Some(0.25), &[], policy);
+        assert_eq!(constrain(1.5, Strict).unwrap(), 1.5);
+        assert!(constrain(1.6, Strict).is_err());
+        assert!(constrain(2.5, Strict).is_err());
+        assert!(constrain(f64::NAN, RoundNearest).is_err());
+        assert_eq!(constrain(2.5, Clamp).unwrap(), 2.0);
+        assert_eq!(constrain(1.6, RoundDown).unwrap(), 1.5);
+        assert_eq!(constrain(1.7, RoundNearest).unwrap(), 1.75);
+
+        // Increment is relative to zero if min is too far from zero.
+        let constrain =
+            |value, policy| constrain_float(value, f64::MIN, f64::MAX, Some(0.1), &[], policy);
+        assert_eq!(constrain(0.3, Strict).unwrap(), 0.3);
+        assert!((constrain(0.34, RoundDown).unwrap() - 0.3).abs() < 1e-9);
+
+        let constrain =
+            |value, policy| constrain_float(value, 0.0, 10.0, None, &[0.5, 1.0, 2.0], policy);
+        assert_eq!(constrain(1.0, Strict).unwrap(), 1.0);
+        assert!(constrain(1.2, Strict).is_err());
+        assert_eq!(constrain(1.2, RoundDown).unwrap(), 1.0);
+        assert_eq!(constrain(1.8, RoundNearest).unwrap(), 2.0);
+    }
 }
@@ -0,0 +1,316 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Conformance tests against reference outputs of `GenApi` XMLs.
+//!
+//! Each case consists of `<name>.xml` and `<name>.ref` in a fixture directory. The `.ref` file
+//! records the outputs of the reference implementation, see `tests/conformance/README.md` for the
+//! format. Fixtures bundled in `tests/conformance` are always run. Set `GENAPI_CONFORMANCE_DIR`
+//! to a directory of additional fixtures, e.g. converted from the official `GenApiTest` suite
+//! which can't be redistributed, to run them as well.
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use cameleon_genapi::{
+    builder::GenApiBuilder,
+    formula,
+    prelude::*,
+    store::{CacheStore, DefaultNodeStore, NodeStore, ValueStore},
+    Device, ValueCtxt,
+};
+
+/// Tolerance used to compare floating point values.
+const FLOAT_TOLERANCE: f64 = 1e-9;
+
+/// A device backed by memory.
+#[derive(Default)]
+struct MemoryDevice(HashMap<i64, u8>);
+
+impl Device for MemoryDevice {
+    fn read_mem(
+        &mut self,
+        address: i64,
+        buf: &mut [u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = self
+                .0
+                .get(&(address + i as i64))
+                .copied()
+                .unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    fn write_mem(
+        &mut self,
+        address: i64,
+        data: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for (i, b) in data.iter().enumerate() {
+            self.0.insert(address + i as i64, *b);
+        }
+        Ok(())
+    }
+}
+
+/// A case of the conformance tests.
+struct Case<T: ValueStore, U: CacheStore> {
+    ns: DefaultNodeStore,
+    vc: ValueCtxt<T, U>,
+    device: MemoryDevice,
+}
+
+impl<T: ValueStore, U: CacheStore> Case<T, U> {
+    /// Runs a directive of `.ref` file.
+    fn run(&mut self, directive: &str, args: &str) -> Result<(), String> {
+        match directive {
+            "memory" => self.memory(args),
+            "value" => {
+                let (name, expected) = split_first(args)?;
+                let actual = self.value(name)?;
+                if values_match(&actual, expected) {
+                    Ok(())
+                } else {
+                    Err(format!("expected {}, but got {}", expected, actual))
+                }
+            }
+            "access" => {
+                let (name, expected) = split_first(args)?;
+                let actual = self.access(name)?;
+                if actual == expected {
+                    Ok(())
+                } else {
+                    Err(format!("expected {}, but got {}", expected, actual))
+                }
+            }
+            "set" => {
+                let (name, value) = split_first(args)?;
+                self.set(name, value)
+            }
+            "reject" => {
+                let (name, value) = split_first(args)?;
+                match self.set(name, value) {
+                    Ok(()) => Err(format!("expected setting {} to fail", value)),
+                    Err(_) => Ok(()),
+                }
+            }
+            "formula" => {
+                let (expected, expr) = split_first(args)?;
+                let expr = formula::parse(expr).map_err(|e| e.to_string())?;
+                let env: HashMap<&str, formula::Expr> = HashMap::new();
+                let actual = expr.eval(&env).map_err(|e| e.to_string())?.as_float();
+                if values_match(&actual.to_string(), expected) {
+                    Ok(())
+                } else {
+                    Err(format!("expected {}, but got {}", expected, actual))
+                }
+            }
+            _ => Err(format!("unknown directive `{}`", directive)),
+        }
+    }
+
+    fn memory(&mut self, args: &str) -> Result<(), String> {
+        let (address, bytes) = split_first(args)?;
+        let address = parse_int(address)?;
+        let bytes = bytes
+            .split_whitespace()
+            .map(|b| u8::from_str_radix(b, 16).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.device
+            .write_mem(address, &bytes)
+            .map_err(|e| e.to_string())
+    }
+
+    fn value(&mut self, name: &str) -> Result<String, String> {
+        let nid = self.node_id(name)?;
+        let (ns, vc, device) = (&self.ns, &mut self.vc, &mut self.device);
+        let res = if let Ok(node) = nid.expect_ienumeration_kind(ns) {
+            node.current_entry(device, ns, vc)
+                .and_then(|entry| Ok(entry.expect_enum_entry(ns)?.symbolic().to_string()))
+        } else if let Ok(node) = nid.expect_iboolean_kind(ns) {
+            node.value(device, ns, vc).map(|v| v.to_string())
+        } else if let Ok(node) = nid.expect_iinteger_kind(ns) {
+            node.value(device, ns, vc).map(|v| v.to_string())
+        } else if let Ok(node) = nid.expect_ifloat_kind(ns) {
+            node.value(device, ns, vc).map(|v| v.to_string())
+        } else if let Ok(node) = nid.expect_istring_kind(ns) {
+            node.value(device, ns, vc)
+        } else {
+            return Err(format!("{} doesn't have a value interface", name));
+        };
+        res.map_err(|e| e.to_string())
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let nid = self.node_id(name)?;
+        let (ns, vc, device) = (&self.ns, &mut self.vc, &mut self.device);
+        let res = if let Ok(node) = nid.expect_ienumeration_kind(ns) {
+            node.set_entry_by_symbolic(value, device, ns, vc)
+        } else if let Ok(node) = nid.expect_iboolean_kind(ns) {
+            let value = value
+                .parse()
+                .map_err(|_| format!("invalid bool: {}", value))?;
+            node.set_value(value, device, ns, vc)
+        } else if let Ok(node) = nid.expect_iinteger_kind(ns) {
+            node.set_value(parse_int(value)?, device, ns, vc)
+        } else if let Ok(node) = nid.expect_ifloat_kind(ns) {
+            let value = value
+                .parse()
+                .map_err(|_| format!("invalid float: {}", value))?;
+            node.set_value(value, device, ns, vc)
+        } else if let Ok(node) = nid.expect_istring_kind(ns) {
+            node.set_value(value.to_string(), device, ns, vc)
+        } else {
+            return Err(format!("{} doesn't have a value interface", name));
+        };
+        res.map_err(|e| e.to_string())
+    }
+
+    /// Returns the access mode of the node, i.e. one of `RW`, `RO`, `WO` and `NA`.
+    fn access(&mut self, name: &str) -> Result<&'static str, String> {
+        let nid = self.node_id(name)?;
+        let (ns, vc, device) = (&self.ns, &mut self.vc, &mut self.device);
+        let res = if let Ok(node) = nid.expect_ienumeration_kind(ns) {
+            node.is_readable(device, ns, vc)
+                .and_then(|r| Ok((r, node.is_writable(device, ns, vc)?)))
+        } else if let Ok(node) = nid.expect_iboolean_kind(ns) {
+            node.is_readable(device, ns, vc)
+                .and_then(|r| Ok((r, node.is_writable(device, ns, vc)?)))
+        } else if let Ok(node) = nid.expect_iinteger_kind(ns) {
+            node.is_readable(device, ns, vc)
+                .and_then(|r| Ok((r, node.is_writable(device, ns, vc)?)))
+        } else if let Ok(node) = nid.expect_ifloat_kind(ns) {
+            node.is_readable(device, ns, vc)
+                .and_then(|r| Ok((r, node.is_writable(device, ns, vc)?)))
+        } else if let Ok(node) = nid.expect_istring_kind(ns) {
+            node.is_readable(device, ns, vc)
+                .and_then(|r| Ok((r, node.is_writable(device, ns, vc)?)))
+        } else {
+            return Err(format!("{} doesn't have a value interface", name));
+        };
+        Ok(match res.map_err(|e| e.to_string())? {
+            (true, true) => "RW",
+            (true, false) => "RO",
+            (false, true) => "WO",
+            (false, false) => "NA",
+        })
+    }
+
+    fn node_id(&self, name: &str) -> Result<cameleon_genapi::NodeId, String> {
+        self.ns
+            .id_by_name(name)
+            .ok_or_else(|| format!("{} is not found", name))
+    }
+}
+
+fn split_first(args: &str) -> Result<(&str, &str), String> {
+    let args = args.trim();
+    match args.find(char::is_whitespace) {
+        Some(pos) => Ok((&args[..pos], args[pos..].trim())),
+        None => Err(format!("missing argument: {}", args)),
+    }
+}
+
+fn parse_int(s: &str) -> Result<i64, String> {
+    let res = if let Some(hex) = s.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+    } else {
+        s.parse()
+    };
+    res.map_err(|e| format!("invalid integer {}: {}", s, e))
+}
+
+/// Compares values as floats if both are numbers, otherwise as strings.
+fn values_match(actual: &str, expected: &str) -> bool {
+    match (actual.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(actual), Ok(expected)) => {
+            (actual - expected).abs() <= FLOAT_TOLERANCE * expected.abs().max(1.0)
+        }
+        _ => actual == expected,
+    }
+}
+
+/// Runs a case and returns failures.
+fn run_case(xml_path: &Path) -> Vec<String> {
+    let ref_path = xml_path.with_extension("ref");
+    let case_name = xml_path.display();
+    let xml = match fs::read_to_string(xml_path) {
+        Ok(xml) => xml,
+        Err(e) => return vec![format!("{}: failed to read XML: {}", case_name, e)],
+    };
+    let reference = match fs::read_to_string(&ref_path) {
+        Ok(reference) => reference,
+        Err(e) => return vec![format!("{}: failed to read reference: {}", case_name, e)],
+    };
+
+    let (_, ns, vc) = match GenApiBuilder::<DefaultNodeStore>::default().build(&xml) {
+        Ok(built) => built,
+        Err(e) => return vec![format!("{}: failed to parse XML: {}", case_name, e)],
+    };
+    let mut case = Case {
+        ns,
+        vc,
+        device: MemoryDevice::default(),
+    };
+
+    let mut failures = vec![];
+    for (i, line) in reference.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (directive, args) = line.split_at(line.find(char::is_whitespace).unwrap_or(line.len()));
+        if let Err(e) = case.run(directive, args) {
+            failures.push(format!(
+                "{}:{}: `{}`: {}",
+                ref_path.display(),
+                i + 1,
+                line,
+                e
+            ));
+        }
+    }
+    failures
+}
+
+fn fixture_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance")];
+    if let Some(dir) = env::var_os("GENAPI_CONFORMANCE_DIR") {
+        dirs.push(dir.into());
+    }
+    dirs
+}
+
+#[test]
+fn test_conformance() {
+    let mut failures = vec![];
+    let mut case_count = 0;
+    for dir in fixture_dirs() {
+        let mut xmls: Vec<_> = fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("failed to read {}: {}", dir.display(), e))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "xml"))
+            .collect();
+        xmls.sort();
+
+        for xml in xmls {
+            case_count += 1;
+            failures.extend(run_case(&xml));
+        }
+    }
+
+    assert!(case_count > 0, "no conformance case is found");
+    assert!(
+        failures.is_empty(),
+        "{} conformance failures in {} cases:\n{}",
+        failures.len(),
+        case_count,
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,581 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{convert::TryInto, ops::Deref, sync::Mutex};
+
+use cameleon::payload::PayloadType;
+
+use crate::imp::data_stream::{BufferId, BufferMemory, DataStream, FlushOperation, UserPointer};
+
+use super::{
+    bool8_t, copy_info, device, event, imp, GenTlError, GenTlResult, ModuleHandle, GC_ERROR,
+    GENTL_INFINITE, INFO_DATATYPE,
+};
+
+pub(super) type DS_HANDLE = *mut libc::c_void;
+pub(super) type BUFFER_HANDLE = *mut libc::c_void;
+
+#[derive(Clone, Copy)]
+pub(super) struct DataStreamModuleRef<'a> {
+    inner: &'a Mutex<dyn DataStream>,
+    parent_dev: device::DEV_HANDLE,
+    /// Handle of `NewBuffer` event, null if the event is not registered.
+    pub(super) new_buffer_event: event::EVENT_HANDLE,
+}
+
+impl<'a> DataStreamModuleRef<'a> {
+    pub(super) fn new(inner: &'a Mutex<dyn DataStream>, parent_dev: device::DEV_HANDLE) -> Self {
+        Self {
+            inner,
+            parent_dev,
+            new_buffer_event: std::ptr::null_mut(),
+        }
+    }
+}
+
+impl<'a> Deref for DataStreamModuleRef<'a> {
+    type Target = Mutex<dyn DataStream>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+fn into_buffer_handle(id: BufferId) -> BUFFER_HANDLE {
+    id as BUFFER_HANDLE
+}
+
+fn from_buffer_handle(handle: BUFFER_HANDLE) -> GenTlResult<BufferId> {
+    if handle.is_null() {
+        Err(GenTlError::InvalidHandle)
+    } else {
+        Ok(handle as BufferId)
+    }
+}
+
+newtype_enum! {
+    pub enum STREAM_INFO_CMD {
+        STREAM_INFO_ID = 0,
+        STREAM_INFO_NUM_DELIVERED = 1,
+        STREAM_INFO_NUM_UNDERRUN = 2,
+        STREAM_INFO_NUM_ANNOUNCED = 3,
+        STREAM_INFO_NUM_QUEUED = 4,
+        STREAM_INFO_NUM_AWAIT_DELIVERY = 5,
+        STREAM_INFO_NUM_STARTED = 6,
+        STREAM_INFO_PAYLOAD_SIZE = 7,
+        STREAM_INFO_IS_GRABBING = 8,
+        STREAM_INFO_DEFINES_PAYLOADSIZE = 9,
+        STREAM_INFO_TLTYPE = 10,
+        STREAM_INFO_NUM_CHUNKS_MAX = 11,
+        STREAM_INFO_BUF_ANNOUNCE_MIN = 12,
+        STREAM_INFO_BUF_ALIGNMENT = 13,
+        STREAM_INFO_CUSTOM_ID = 1000,
+    }
+}
+
+newtype_enum! {
+    pub enum BUFFER_INFO_CMD {
+        BUFFER_INFO_BASE = 0,
+        BUFFER_INFO_SIZE = 1,
+        BUFFER_INFO_USER_PTR = 2,
+        BUFFER_INFO_TIMESTAMP = 3,
+        BUFFER_INFO_NEW_DATA = 4,
+        BUFFER_INFO_IS_QUEUED = 5,
+        BUFFER_INFO_IS_ACQUIRING = 6,
+        BUFFER_INFO_IS_INCOMPLETE = 7,
+        BUFFER_INFO_TLTYPE = 8,
+        BUFFER_INFO_SIZE_FILLED = 9,
+        BUFFER_INFO_WIDTH = 10,
+        BUFFER_INFO_HEIGHT = 11,
+        BUFFER_INFO_XOFFSET = 12,
+        BUFFER_INFO_YOFFSET = 13,
+        BUFFER_INFO_XPADDING = 14,
+        BUFFER_INFO_YPADDING = 15,
+        BUFFER_INFO_FRAMEID = 16,
+        BUFFER_INFO_IMAGEPRESENT = 17,
+        BUFFER_INFO_IMAGEOFFSET = 18,
+        BUFFER_INFO_PAYLOADTYPE = 19,
+        BUFFER_INFO_PIXELFORMAT = 20,
+        BUFFER_INFO_PIXELFORMAT_NAMESPACE = 21,
+        BUFFER_INFO_DELIVERED_IMAGEHEIGHT = 22,
+        BUFFER_INFO_DELIVERED_CHUNKPAYLOADSIZE = 23,
+        BUFFER_INFO_CHUNKLAYOUTID = 24,
+        BUFFER_INFO_FILENAME = 25,
+        BUFFER_INFO_PIXEL_ENDIANNESS = 26,
+        BUFFER_INFO_DATA_SIZE = 27,
+        BUFFER_INFO_TIMESTAMP_NS = 28,
+        BUFFER_INFO_DATA_LARGER_THAN_BUFFER = 29,
+        BUFFER_INFO_CONTAINS_CHUNKDATA = 30,
+        BUFFER_INFO_CUSTOM_ID = 1000,
+    }
+}
+
+newtype_enum! {
+    pub enum PAYLOADTYPE_INFO_IDS {
+        PAYLOAD_TYPE_UNKNOWN = 0,
+        PAYLOAD_TYPE_IMAGE = 1,
+        PAYLOAD_TYPE_RAW_DATA = 2,
+        PAYLOAD_TYPE_FILE = 3,
+        PAYLOAD_TYPE_CHUNK_DATA = 4,
+        PAYLOAD_TYPE_JPEG = 5,
+        PAYLOAD_TYPE_JPEG2000 = 6,
+        PAYLOAD_TYPE_H264 = 7,
+        PAYLOAD_TYPE_CHUNK_ONLY = 8,
+        PAYLOAD_TYPE_DEVICE_SPECIFIC = 9,
+        PAYLOAD_TYPE_MULTI_PART = 10,
+        PAYLOAD_TYPE_CUSTOM_ID = 1000,
+    }
+}
+
+newtype_enum! {
+    pub enum PIXELFORMAT_NAMESPACE_IDS {
+        PIXELFORMAT_NAMESPACE_UNKNOWN = 0,
+        PIXELFORMAT_NAMESPACE_GEV = 1,
+        PIXELFORMAT_NAMESPACE_IIDC = 2,
+        PIXELFORMAT_NAMESPACE_PFNC_16BIT = 3,
+        PIXELFORMAT_NAMESPACE_PFNC_32BIT = 4,
+        PIXELFORMAT_NAMESPACE_CUSTOM_ID = 1000,
+    }
+}
+
+newtype_enum! {
+    pub enum ACQ_START_FLAGS {
+        ACQ_START_FLAGS_DEFAULT = 0,
+        ACQ_START_FLAGS_CUSTOM_ID = 1000,
+    }
+}
+
+newtype_enum! {
+    pub enum ACQ_STOP_FLAGS {
+        ACQ_STOP_FLAGS_DEFAULT = 0,
+        ACQ_STOP_FLAGS_KILL = 1,
+        ACQ_STOP_FLAGS_CUSTOM_ID = 1000,
+    }
+}
+
+newtype_enum! {
+    pub enum ACQ_QUEUE_TYPE {
+        ACQ_QUEUE_INPUT_TO_OUTPUT = 0,
+        ACQ_QUEUE_OUTPUT_DISCARD = 1,
+        ACQ_QUEUE_ALL_TO_INPUT = 2,
+        ACQ_QUEUE_UNQUEUED_TO_INPUT = 3,
+        ACQ_QUEUE_ALL_DISCARD = 4,
+        ACQ_QUEUE_CUSTOM_ID = 1000,
+    }
+}
+
+impl TryInto<FlushOperation> for ACQ_QUEUE_TYPE {
+    type Error = GenTlError;
+
+    fn try_into(self) -> GenTlResult<FlushOperation> {
+        use FlushOperation::{
+            AllDiscard, AllToInput, InputToOutput, OutputDiscard, UnqueuedToInput,
+        };
+        match self {
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_INPUT_TO_OUTPUT => Ok(InputToOutput),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_OUTPUT_DISCARD => Ok(OutputDiscard),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_ALL_TO_INPUT => Ok(AllToInput),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_UNQUEUED_TO_INPUT => Ok(UnqueuedToInput),
+            ACQ_QUEUE_TYPE::ACQ_QUEUE_ALL_DISCARD => Ok(AllDiscard),
+            _ => Err(GenTlError::InvalidParameter),
+        }
+    }
+}
+
+gentl_api! {
+    pub fn DSAnnounceBuffer(
+        hDataStream: DS_HANDLE,
+        pBuffer: *mut libc::c_void,
+        iSize: libc::size_t,
+        pPrivate: *mut libc::c_void,
+        phBuffer: *mut BUFFER_HANDLE,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        let memory = unsafe { BufferMemory::from_raw(pBuffer.cast::<u8>(), iSize)? };
+        let id = strm
+            .lock()
+            .unwrap()
+            .buffer_pool()
+            .announce(memory, UserPointer(pPrivate))?;
+
+        unsafe {
+            *phBuffer = into_buffer_handle(id);
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSAllocAndAnnounceBuffer(
+        hDataStream: DS_HANDLE,
+        iSize: libc::size_t,
+        pPrivate: *mut libc::c_void,
+        phBuffer: *mut BUFFER_HANDLE,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        let id = strm
+            .lock()
+            .unwrap()
+            .buffer_pool()
+            .announce(BufferMemory::alloc(iSize), UserPointer(pPrivate))?;
+
+        unsafe {
+            *phBuffer = into_buffer_handle(id);
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSRevokeBuffer(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        pBuffer: *mut *mut libc::c_void,
+        pPrivate: *mut *mut libc::c_void,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        let id = from_buffer_handle(hBuffer)?;
+        let (memory, user_ptr) = strm.lock().unwrap().buffer_pool().revoke(id)?;
+
+        // Memory allocated by the producer is freed here, so null is returned for it.
+        let base = if memory.is_owned() {
+            std::ptr::null_mut()
+        } else {
+            memory.as_ptr().cast::<libc::c_void>()
+        };
+        unsafe {
+            if !pBuffer.is_null() {
+                *pBuffer = base;
+            }
+            if !pPrivate.is_null() {
+                *pPrivate = user_ptr.0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSQueueBuffer(hDataStream: DS_HANDLE, hBuffer: BUFFER_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        let id = from_buffer_handle(hBuffer)?;
+        strm.lock().unwrap().buffer_pool().queue(id)?;
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSFlushQueue(hDataStream: DS_HANDLE, iOperation: ACQ_QUEUE_TYPE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        strm.lock().unwrap().buffer_pool().flush(iOperation.try_into()?);
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSStartAcquisition(
+        hDataStream: DS_HANDLE,
+        iStartFlags: ACQ_START_FLAGS,
+        iNumToAcquire: u64,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        let num_to_acquire = if iNumToAcquire == GENTL_INFINITE {
+            None
+        } else {
+            Some(iNumToAcquire)
+        };
+        strm.lock().unwrap().start_acquisition(num_to_acquire)?;
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSStopAcquisition(hDataStream: DS_HANDLE, iStopFlags: ACQ_STOP_FLAGS) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        // The acquisition engine is always stopped immediately, so `ACQ_STOP_FLAGS_KILL` has no
+        // special effect.
+        strm.lock().unwrap().stop_acquisition()?;
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferID(
+        hDataStream: DS_HANDLE,
+        iIndex: u32,
+        phBuffer: *mut BUFFER_HANDLE,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        let id = strm.lock().unwrap().buffer_pool().buffer_id(iIndex as usize)?;
+        unsafe {
+            *phBuffer = into_buffer_handle(id);
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSGetInfo(
+        hDataStream: DS_HANDLE,
+        iInfoCmd: STREAM_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+        let strm_guard = strm.lock().unwrap();
+        let stats = strm_guard.buffer_pool().statistics();
+
+        let info_data_type = match iInfoCmd {
+            STREAM_INFO_CMD::STREAM_INFO_ID => copy_info(strm_guard.stream_id(), pBuffer, piSize),
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_DELIVERED => {
+                copy_info(stats.num_delivered, pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_UNDERRUN => {
+                copy_info(stats.num_underrun, pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_ANNOUNCED => {
+                copy_info(stats.num_announced, pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_QUEUED => copy_info(stats.num_queued, pBuffer, piSize),
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_AWAIT_DELIVERY => {
+                copy_info(stats.num_await_delivery, pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_NUM_STARTED => {
+                copy_info(stats.num_started, pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_PAYLOAD_SIZE => {
+                copy_info(strm_guard.payload_size()?, pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_IS_GRABBING => {
+                copy_info(bool8_t::from(stats.is_grabbing), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_DEFINES_PAYLOADSIZE => {
+                copy_info(bool8_t::true_(), pBuffer, piSize)
+            }
+
+            STREAM_INFO_CMD::STREAM_INFO_TLTYPE => copy_info(strm_guard.tl_type(), pBuffer, piSize),
+
+            STREAM_INFO_CMD::STREAM_INFO_BUF_ANNOUNCE_MIN => copy_info(1_usize, pBuffer, piSize),
+
+            STREAM_INFO_CMD::STREAM_INFO_BUF_ALIGNMENT => copy_info(1_usize, pBuffer, piSize),
+
+            _ => Err(GenTlError::InvalidParameter),
+        }?;
+
+        unsafe {
+            *piType = info_data_type;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSGetBufferInfo(
+        hDataStream: DS_HANDLE,
+        hBuffer: BUFFER_HANDLE,
+        iInfoCmd: BUFFER_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+        let strm_guard = strm.lock().unwrap();
+
+        let id = from_buffer_handle(hBuffer)?;
+        let info = strm_guard.buffer_pool().buffer_info(id)?;
+        let frame = info.frame.as_ref();
+        let image = frame.and_then(|frame| frame.image.as_ref());
+
+        let info_data_type = match iInfoCmd {
+            BUFFER_INFO_CMD::BUFFER_INFO_BASE => {
+                copy_info(info.base.cast::<libc::c_void>(), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_SIZE => copy_info(info.size, pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_USER_PTR => copy_info(info.user_ptr.0, pBuffer, piSize),
+
+            // Timestamp of U3V device is in nanoseconds.
+            BUFFER_INFO_CMD::BUFFER_INFO_TIMESTAMP | BUFFER_INFO_CMD::BUFFER_INFO_TIMESTAMP_NS => {
+                let frame = frame.ok_or(GenTlError::NoData)?;
+                copy_info(frame.timestamp_ns, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_NEW_DATA => {
+                copy_info(bool8_t::from(info.new_data), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IS_QUEUED => {
+                copy_info(bool8_t::from(info.is_queued), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IS_ACQUIRING => {
+                copy_info(bool8_t::from(info.is_acquiring), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IS_INCOMPLETE
+            | BUFFER_INFO_CMD::BUFFER_INFO_DATA_LARGER_THAN_BUFFER => {
+                let is_incomplete = frame.is_some_and(imp::data_stream::FrameInfo::is_incomplete);
+                copy_info(bool8_t::from(is_incomplete), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_TLTYPE => copy_info(strm_guard.tl_type(), pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_SIZE_FILLED => {
+                copy_info(frame.map_or(0, |frame| frame.size_filled), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_DATA_SIZE => {
+                copy_info(frame.map_or(0, |frame| frame.data_size), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_WIDTH => {
+                copy_info(image.ok_or(GenTlError::NoData)?.width, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_HEIGHT => {
+                copy_info(image.ok_or(GenTlError::NoData)?.max_height, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_DELIVERED_IMAGEHEIGHT => {
+                copy_info(image.ok_or(GenTlError::NoData)?.height, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_XOFFSET => {
+                copy_info(image.ok_or(GenTlError::NoData)?.x_offset, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_YOFFSET => {
+                copy_info(image.ok_or(GenTlError::NoData)?.y_offset, pBuffer, piSize)
+            }
+
+            // Payload sent from U3V device never contains padding.
+            BUFFER_INFO_CMD::BUFFER_INFO_XPADDING
+            | BUFFER_INFO_CMD::BUFFER_INFO_YPADDING
+            | BUFFER_INFO_CMD::BUFFER_INFO_IMAGEOFFSET => copy_info(0_usize, pBuffer, piSize),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_FRAMEID => {
+                copy_info(frame.ok_or(GenTlError::NoData)?.frame_id, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_IMAGEPRESENT => {
+                copy_info(bool8_t::from(image.is_some()), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_PAYLOADTYPE => {
+                let payload_type = match frame.and_then(|frame| frame.payload_type) {
+                    Some(PayloadType::Image | PayloadType::ImageExtendedChunk) => {
+                        PAYLOADTYPE_INFO_IDS::PAYLOAD_TYPE_IMAGE
+                    }
+                    Some(PayloadType::Chunk) => PAYLOADTYPE_INFO_IDS::PAYLOAD_TYPE_CHUNK_ONLY,
+                    None => PAYLOADTYPE_INFO_IDS::PAYLOAD_TYPE_UNKNOWN,
+                };
+                copy_info(payload_type.0 as usize, pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_CONTAINS_CHUNKDATA => {
+                let contains_chunk = matches!(
+                    frame.and_then(|frame| frame.payload_type),
+                    Some(PayloadType::ImageExtendedChunk | PayloadType::Chunk)
+                );
+                copy_info(bool8_t::from(contains_chunk), pBuffer, piSize)
+            }
+
+            BUFFER_INFO_CMD::BUFFER_INFO_PIXELFORMAT => copy_info(
+                u64::from(image.ok_or(GenTlError::NoData)?.pixel_format),
+                pBuffer,
+                piSize,
+            ),
+
+            BUFFER_INFO_CMD::BUFFER_INFO_PIXELFORMAT_NAMESPACE => copy_info(
+                PIXELFORMAT_NAMESPACE_IDS::PIXELFORMAT_NAMESPACE_PFNC_32BIT.0 as u64,
+                pBuffer,
+                piSize,
+            ),
+
+            _ => Err(GenTlError::InvalidParameter),
+        }?;
+
+        unsafe {
+            *piType = info_data_type;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSClose(hDataStream: DS_HANDLE) -> GenTlResult<()> {
+        let mut handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        // Close the data stream module, this also revokes all announced buffers.
+        strm.lock().unwrap().close()?;
+
+        // Unregister the event and drop its handle.
+        if !strm.new_buffer_event.is_null() {
+            strm.lock().unwrap().buffer_pool().unregister_event()?;
+            unsafe {
+                let mut event_handle = ModuleHandle::from_raw_manually_drop(strm.new_buffer_event)?;
+                std::mem::ManuallyDrop::drop(&mut event_handle);
+            }
+        }
+
+        // Drop the data stream handle.
+        unsafe {
+            std::mem::ManuallyDrop::drop(&mut handle);
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn DSGetParentDev(hDataStream: DS_HANDLE, phDevice: *mut device::DEV_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDataStream)? };
+        let strm = handle.data_stream()?;
+
+        unsafe {
+            *phDevice = strm.parent_dev;
+        }
+
+        Ok(())
+    }
+}
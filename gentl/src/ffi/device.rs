@@ -2,15 +2,17 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use std::{convert::TryInto, ops::Deref, sync::Mutex};
+use std::{convert::TryInto, ffi::CStr, ops::Deref, sync::Mutex};
+
+use super::data_stream::DS_HANDLE;
 
 use super::{
-    copy_info, imp, interface, GenTlError, GenTlResult, ModuleHandle, GC_ERROR, INFO_DATATYPE,
+    copy_info, data_stream::DataStreamModuleRef, imp, interface, GenTlError, GenTlResult,
+    ModuleHandle, GC_ERROR, INFO_DATATYPE,
 };
 
 pub(super) type DEV_HANDLE = *mut libc::c_void;
 pub(super) type PORT_HANDLE = *mut libc::c_void;
-pub(super) type DS_HANDLE = *mut libc::c_void;
 
 #[derive(Clone, Copy)]
 pub(super) struct DeviceModuleRef<'a> {
@@ -204,14 +206,34 @@ gentl_api! {
         sDataStreamID: *mut libc::c_char,
         piSize: *mut libc::size_t,
     ) -> GenTlResult<()> {
-        todo!()
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDevice)? };
+        let dev_handle = handle.device()?;
+
+        let dev_guard = dev_handle.lock().unwrap();
+        let strms = dev_guard.data_streams()?;
+        let strm = strms.get(iIndex as usize).ok_or(GenTlError::InvalidIndex)?;
+        copy_info(
+            strm.lock().unwrap().stream_id(),
+            sDataStreamID.cast::<libc::c_void>(),
+            piSize,
+        )?;
+
+        Ok(())
     }
 }
 
 gentl_api! {
     pub fn DevGetNumDataStreams(hDevice: DEV_HANDLE, piNumDataStreams: *mut u32) -> GenTlResult<()>
     {
-        todo!()
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDevice)? };
+        let dev_handle = handle.device()?;
+
+        let num = dev_handle.lock().unwrap().data_streams()?.len();
+        unsafe {
+            *piNumDataStreams = num as u32;
+        }
+
+        Ok(())
     }
 }
 
@@ -231,10 +253,23 @@ gentl_api! {
 gentl_api! {
     pub fn DevOpenDataStream(
         hDevice: DEV_HANDLE,
-        sDataStreamID: *const ::std::os::raw::c_char,
+        sDataStreamID: *const libc::c_char,
         phDataStream: *mut DS_HANDLE,
     ) -> GenTlResult<()> {
-        todo!()
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hDevice)? };
+        let dev_handle = handle.device()?;
+
+        let dev_guard = dev_handle.lock().unwrap();
+        let id = unsafe { CStr::from_ptr(sDataStreamID) }.to_string_lossy();
+        let strm = dev_guard.data_stream_by_id(&id)?;
+
+        strm.lock().unwrap().open()?;
+        let strm_handle = Box::new(ModuleHandle::DataStream(DataStreamModuleRef::new(strm, hDevice)));
+        unsafe {
+            *phDataStream = strm_handle.into_raw();
+        }
+
+        Ok(())
     }
 }
 
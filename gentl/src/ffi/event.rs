@@ -0,0 +1,203 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{sync::Arc, time::Duration};
+
+use crate::imp::data_stream::BufferPool;
+
+use super::{
+    copy_info, data_stream, GenTlError, GenTlResult, ModuleHandle, GC_ERROR, GENTL_INFINITE,
+    INFO_DATATYPE,
+};
+
+pub(super) type EVENTSRC_HANDLE = *mut libc::c_void;
+pub(super) type EVENT_HANDLE = *mut libc::c_void;
+
+/// Only `NewBuffer` event of data stream module is supported for now.
+pub(super) enum EventRef {
+    NewBuffer(Arc<BufferPool>),
+}
+
+newtype_enum! {
+    pub enum EVENT_TYPE {
+        EVENT_ERROR = 0,
+        EVENT_NEW_BUFFER = 1,
+        EVENT_FEATURE_INVALIDATE = 2,
+        EVENT_FEATURE_CHANGE = 3,
+        EVENT_REMOTE_DEVICE = 4,
+        EVENT_MODULE = 5,
+        EVENT_CUSTOM_ID = 1000,
+    }
+}
+
+newtype_enum! {
+    pub enum EVENT_INFO_CMD {
+        EVENT_EVENT_TYPE = 0,
+        EVENT_NUM_IN_QUEUE = 1,
+        EVENT_NUM_FIRED = 2,
+        EVENT_SIZE_MAX = 3,
+        EVENT_INFO_DATA_SIZE_MAX = 4,
+        EVENT_INFO_CUSTOM_ID = 1000,
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct EVENT_NEW_BUFFER_DATA {
+    BufferHandle: data_stream::BUFFER_HANDLE,
+    pUserPointer: *mut libc::c_void,
+}
+
+gentl_api! {
+    pub fn GCRegisterEvent(
+        hEventSrc: EVENTSRC_HANDLE,
+        iEventID: EVENT_TYPE,
+        phEvent: *mut EVENT_HANDLE,
+    ) -> GenTlResult<()> {
+        let mut handle = unsafe { ModuleHandle::from_raw_manually_drop(hEventSrc)? };
+
+        match (&mut **handle, iEventID) {
+            (ModuleHandle::DataStream(strm), EVENT_TYPE::EVENT_NEW_BUFFER) => {
+                let pool = strm.lock().unwrap().buffer_pool().clone();
+                pool.register_event()?;
+
+                let event = unsafe { Box::new(ModuleHandle::Event(EventRef::NewBuffer(pool))).into_raw() };
+                strm.new_buffer_event = event;
+                unsafe {
+                    *phEvent = event;
+                }
+                Ok(())
+            }
+
+            (ModuleHandle::Event(..), _) => Err(GenTlError::InvalidHandle),
+
+            _ => Err(GenTlError::NotImplemented),
+        }
+    }
+}
+
+gentl_api! {
+    pub fn GCUnregisterEvent(hEventSrc: EVENTSRC_HANDLE, iEventID: EVENT_TYPE) -> GenTlResult<()> {
+        let mut handle = unsafe { ModuleHandle::from_raw_manually_drop(hEventSrc)? };
+
+        match (&mut **handle, iEventID) {
+            (ModuleHandle::DataStream(strm), EVENT_TYPE::EVENT_NEW_BUFFER) => {
+                strm.lock().unwrap().buffer_pool().unregister_event()?;
+
+                // Drop the event handle.
+                unsafe {
+                    let mut event_handle = ModuleHandle::from_raw_manually_drop(strm.new_buffer_event)?;
+                    std::mem::ManuallyDrop::drop(&mut event_handle);
+                }
+                strm.new_buffer_event = std::ptr::null_mut();
+                Ok(())
+            }
+
+            (ModuleHandle::Event(..), _) => Err(GenTlError::InvalidHandle),
+
+            _ => Err(GenTlError::NotImplemented),
+        }
+    }
+}
+
+gentl_api! {
+    pub fn EventGetData(
+        hEvent: EVENT_HANDLE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+        iTimeout: u64,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hEvent)? };
+        // Clone the pool so that the handle isn't touched while waiting.
+        let EventRef::NewBuffer(pool) = handle.event()?;
+        let pool = pool.clone();
+
+        let data_size = std::mem::size_of::<EVENT_NEW_BUFFER_DATA>();
+        unsafe {
+            if pBuffer.is_null() || *piSize < data_size {
+                *piSize = data_size;
+                return Err(GenTlError::BufferTooSmall);
+            }
+        }
+
+        let timeout = if iTimeout == GENTL_INFINITE {
+            None
+        } else {
+            Some(Duration::from_millis(iTimeout))
+        };
+        let (id, user_ptr) = pool.wait_new_buffer(timeout)?;
+
+        unsafe {
+            *pBuffer.cast::<EVENT_NEW_BUFFER_DATA>() = EVENT_NEW_BUFFER_DATA {
+                BufferHandle: id as data_stream::BUFFER_HANDLE,
+                pUserPointer: user_ptr.0,
+            };
+            *piSize = data_size;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn EventGetInfo(
+        hEvent: EVENT_HANDLE,
+        iInfoCmd: EVENT_INFO_CMD,
+        piType: *mut INFO_DATATYPE,
+        pBuffer: *mut libc::c_void,
+        piSize: *mut libc::size_t,
+    ) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hEvent)? };
+        let EventRef::NewBuffer(pool) = handle.event()?;
+
+        let info_data_type = match iInfoCmd {
+            EVENT_INFO_CMD::EVENT_EVENT_TYPE => {
+                copy_info(EVENT_TYPE::EVENT_NEW_BUFFER.0, pBuffer, piSize)
+            }
+
+            EVENT_INFO_CMD::EVENT_NUM_IN_QUEUE => {
+                copy_info(pool.num_event_in_queue(), pBuffer, piSize)
+            }
+
+            EVENT_INFO_CMD::EVENT_NUM_FIRED => copy_info(pool.num_event_fired(), pBuffer, piSize),
+
+            EVENT_INFO_CMD::EVENT_SIZE_MAX => copy_info(
+                std::mem::size_of::<EVENT_NEW_BUFFER_DATA>(),
+                pBuffer,
+                piSize,
+            ),
+
+            // `NewBuffer` event has no data to be queried with `EventGetDataInfo`.
+            EVENT_INFO_CMD::EVENT_INFO_DATA_SIZE_MAX => copy_info(0_usize, pBuffer, piSize),
+
+            _ => Err(GenTlError::InvalidParameter),
+        }?;
+
+        unsafe {
+            *piType = info_data_type;
+        }
+
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn EventFlush(hEvent: EVENT_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hEvent)? };
+        let EventRef::NewBuffer(pool) = handle.event()?;
+
+        pool.flush_event();
+        Ok(())
+    }
+}
+
+gentl_api! {
+    pub fn EventKill(hEvent: EVENT_HANDLE) -> GenTlResult<()> {
+        let handle = unsafe { ModuleHandle::from_raw_manually_drop(hEvent)? };
+        let EventRef::NewBuffer(pool) = handle.event()?;
+
+        pool.kill_event();
+        Ok(())
+    }
+}
@@ -79,6 +79,11 @@ macro_rules! with_port {
                 let mut $port = handle.lock().unwrap();
                 $body
             }
+
+            // Data stream module doesn't provide its port yet.
+            ModuleHandle::DataStream(..) => return Err(GenTlError::NotImplemented),
+
+            ModuleHandle::Event(..) => return Err(GenTlError::InvalidHandle),
         }
     };
 }
@@ -0,0 +1,653 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ptr::NonNull,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use cameleon::{
+    payload::{Payload, PayloadReceiver, PayloadType},
+    StreamError,
+};
+
+use crate::{imp::port::TlType, GenTlError, GenTlResult};
+
+pub(crate) mod u3v;
+
+/// Identifier of a buffer announced to a data stream module, the value is never zero.
+pub(crate) type BufferId = usize;
+
+pub(crate) trait DataStream {
+    /// Open the data stream.
+    fn open(&mut self) -> GenTlResult<()>;
+
+    /// Close the data stream, all announced buffers are revoked.
+    fn close(&mut self) -> GenTlResult<()>;
+
+    /// Device unique ID of the data stream.
+    fn stream_id(&self) -> &str;
+
+    /// Transport layer type of the data stream.
+    fn tl_type(&self) -> TlType;
+
+    /// Buffer pool which holds buffers announced to the data stream.
+    fn buffer_pool(&self) -> &Arc<BufferPool>;
+
+    /// Start the acquisition engine.
+    /// The engine stops delivering buffers after `num_to_acquire` buffers are filled if it's
+    /// `Some`.
+    fn start_acquisition(&mut self, num_to_acquire: Option<u64>) -> GenTlResult<()>;
+
+    /// Stop the acquisition engine.
+    fn stop_acquisition(&mut self) -> GenTlResult<()>;
+
+    /// Size of the payload the device currently sends.
+    fn payload_size(&self) -> GenTlResult<usize>;
+}
+
+/// Memory of a buffer.
+pub(crate) struct BufferMemory {
+    ptr: NonNull<u8>,
+    len: usize,
+
+    /// `Some` if the memory is allocated by the producer.
+    owned: Option<Box<[u8]>>,
+}
+
+impl BufferMemory {
+    /// Memory allocated by the consumer.
+    ///
+    /// # Safety
+    /// `ptr` must be valid for writes of `len` bytes until the buffer is revoked.
+    pub(crate) unsafe fn from_raw(ptr: *mut u8, len: usize) -> GenTlResult<Self> {
+        let ptr = NonNull::new(ptr).ok_or(GenTlError::InvalidParameter)?;
+        Ok(Self {
+            ptr,
+            len,
+            owned: None,
+        })
+    }
+
+    /// Memory allocated by the producer.
+    pub(crate) fn alloc(len: usize) -> Self {
+        let mut owned = vec![0; len].into_boxed_slice();
+        let ptr = NonNull::new(owned.as_mut_ptr()).unwrap_or_else(NonNull::dangling);
+        Self {
+            ptr,
+            len,
+            owned: Some(owned),
+        }
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_owned(&self) -> bool {
+        self.owned.is_some()
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // Safety: See `BufferMemory::from_raw`.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+// Safety: The memory is accessed only while the lock of the pool is held.
+unsafe impl Send for BufferMemory {}
+
+/// Opaque pointer the consumer associates with a buffer.
+#[derive(Clone, Copy)]
+pub(crate) struct UserPointer(pub(crate) *mut std::ffi::c_void);
+
+// Safety: The producer never dereferences the pointer.
+unsafe impl Send for UserPointer {}
+
+/// Information of the data filled in a buffer.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FrameInfo {
+    /// ID of the frame, this is sequentially incremented by the device.
+    pub(crate) frame_id: u64,
+
+    /// Timestamp of the device in nanoseconds when the frame is generated.
+    pub(crate) timestamp_ns: u64,
+
+    /// Number of bytes written to the buffer.
+    pub(crate) size_filled: usize,
+
+    /// Size of the payload sent from the device, this may exceed the buffer size.
+    pub(crate) data_size: usize,
+
+    pub(crate) payload_type: Option<PayloadType>,
+
+    pub(crate) image: Option<ImageInfo>,
+}
+
+impl FrameInfo {
+    pub(crate) fn is_incomplete(&self) -> bool {
+        self.size_filled < self.data_size
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ImageInfo {
+    pub(crate) width: usize,
+    /// Height of the delivered image.
+    pub(crate) height: usize,
+    /// Height announced in the leader.
+    pub(crate) max_height: usize,
+    pub(crate) x_offset: usize,
+    pub(crate) y_offset: usize,
+
+    /// `PFNC` code of the pixel format.
+    pub(crate) pixel_format: u32,
+}
+
+/// Snapshot of a buffer state.
+#[derive(Clone)]
+pub(crate) struct BufferInfo {
+    pub(crate) base: *mut u8,
+    pub(crate) size: usize,
+    pub(crate) user_ptr: UserPointer,
+    pub(crate) is_queued: bool,
+    pub(crate) is_acquiring: bool,
+    pub(crate) new_data: bool,
+    pub(crate) frame: Option<FrameInfo>,
+}
+
+/// Snapshot of the counters of a buffer pool.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PoolStatistics {
+    pub(crate) num_announced: usize,
+    pub(crate) num_queued: usize,
+    pub(crate) num_await_delivery: usize,
+    pub(crate) num_delivered: u64,
+    pub(crate) num_underrun: u64,
+    pub(crate) num_started: u64,
+    pub(crate) is_grabbing: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BufferState {
+    /// The buffer is announced, but not queued to the input pool.
+    Announced,
+    /// The buffer is in the input pool waiting to be filled.
+    Queued,
+    /// The buffer is filled and waiting to be delivered to the consumer.
+    Filled,
+}
+
+struct Buffer {
+    memory: BufferMemory,
+    user_ptr: UserPointer,
+    state: BufferState,
+    new_data: bool,
+    frame: Option<FrameInfo>,
+}
+
+/// How queued buffers are moved by [`BufferPool::flush`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FlushOperation {
+    /// Move buffers from the input pool to the output queue.
+    InputToOutput,
+    /// Discard buffers in the output queue.
+    OutputDiscard,
+    /// Move all announced buffers to the input pool.
+    AllToInput,
+    /// Move buffers which are neither queued nor filled to the input pool.
+    UnqueuedToInput,
+    /// Discard buffers in the input pool and the output queue.
+    AllDiscard,
+}
+
+#[derive(Default)]
+struct PoolState {
+    buffers: HashMap<BufferId, Buffer>,
+    /// Buffer ids in the announced order.
+    announced: Vec<BufferId>,
+    next_id: BufferId,
+
+    input_pool: VecDeque<BufferId>,
+    output_queue: VecDeque<BufferId>,
+
+    num_delivered: u64,
+    num_underrun: u64,
+    num_started: u64,
+    num_fired: u64,
+
+    is_grabbing: bool,
+    num_to_acquire: Option<u64>,
+    num_acquired: u64,
+
+    is_event_registered: bool,
+    is_event_killed: bool,
+}
+
+/// Buffers announced to a data stream module.
+///
+/// The pool is shared between the data stream module, the acquisition engine and
+/// `NewBuffer` events, so that the consumer can wait for a filled buffer without locking the
+/// data stream module.
+#[derive(Default)]
+pub(crate) struct BufferPool {
+    state: Mutex<PoolState>,
+    new_buffer: Condvar,
+}
+
+impl BufferPool {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn announce(
+        &self,
+        memory: BufferMemory,
+        user_ptr: UserPointer,
+    ) -> GenTlResult<BufferId> {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+
+        state.buffers.insert(
+            id,
+            Buffer {
+                memory,
+                user_ptr,
+                state: BufferState::Announced,
+                new_data: false,
+                frame: None,
+            },
+        );
+        state.announced.push(id);
+        Ok(id)
+    }
+
+    /// Revoke the buffer, the buffer must be neither queued nor waiting for delivery.
+    pub(crate) fn revoke(&self, id: BufferId) -> GenTlResult<(BufferMemory, UserPointer)> {
+        let mut state = self.state.lock().unwrap();
+        if state.buffer(id)?.state != BufferState::Announced {
+            return Err(GenTlError::Busy);
+        }
+
+        state.announced.retain(|announced| *announced != id);
+        let buffer = state.buffers.remove(&id).unwrap();
+        Ok((buffer.memory, buffer.user_ptr))
+    }
+
+    /// Revoke all buffers regardless of their state.
+    pub(crate) fn revoke_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.buffers.clear();
+        state.announced.clear();
+        state.input_pool.clear();
+        state.output_queue.clear();
+    }
+
+    /// Queue the buffer to the input pool.
+    pub(crate) fn queue(&self, id: BufferId) -> GenTlResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let buffer = state.buffer_mut(id)?;
+        if buffer.state != BufferState::Announced {
+            return Err(GenTlError::InvalidParameter);
+        }
+
+        buffer.state = BufferState::Queued;
+        buffer.new_data = false;
+        state.input_pool.push_back(id);
+        Ok(())
+    }
+
+    pub(crate) fn flush(&self, op: FlushOperation) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        match op {
+            FlushOperation::InputToOutput => {
+                let ids: Vec<_> = state.input_pool.drain(..).collect();
+                for id in ids {
+                    let buffer = state.buffers.get_mut(&id).unwrap();
+                    buffer.state = BufferState::Filled;
+                    buffer.new_data = false;
+                    state.output_queue.push_back(id);
+                }
+                self.new_buffer.notify_all();
+            }
+
+            FlushOperation::OutputDiscard => state.discard_output(),
+
+            FlushOperation::AllToInput => {
+                state.input_pool.clear();
+                state.output_queue.clear();
+                for id in &state.announced {
+                    state.buffers.get_mut(id).unwrap().state = BufferState::Queued;
+                    state.input_pool.push_back(*id);
+                }
+            }
+
+            FlushOperation::UnqueuedToInput => {
+                for id in &state.announced {
+                    let buffer = state.buffers.get_mut(id).unwrap();
+                    if buffer.state == BufferState::Announced {
+                        buffer.state = BufferState::Queued;
+                        state.input_pool.push_back(*id);
+                    }
+                }
+            }
+
+            FlushOperation::AllDiscard => {
+                state.discard_output();
+                for id in state.input_pool.drain(..) {
+                    state.buffers.get_mut(&id).unwrap().state = BufferState::Announced;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn buffer_id(&self, index: usize) -> GenTlResult<BufferId> {
+        let state = self.state.lock().unwrap();
+        state
+            .announced
+            .get(index)
+            .copied()
+            .ok_or(GenTlError::InvalidIndex)
+    }
+
+    pub(crate) fn buffer_info(&self, id: BufferId) -> GenTlResult<BufferInfo> {
+        let state = self.state.lock().unwrap();
+        let buffer = state.buffer(id)?;
+
+        Ok(BufferInfo {
+            base: buffer.memory.as_ptr(),
+            size: buffer.memory.len(),
+            user_ptr: buffer.user_ptr,
+            is_queued: buffer.state != BufferState::Announced,
+            is_acquiring: state.is_grabbing && state.input_pool.front() == Some(&id),
+            new_data: buffer.new_data,
+            frame: buffer.frame.clone(),
+        })
+    }
+
+    pub(crate) fn statistics(&self) -> PoolStatistics {
+        let state = self.state.lock().unwrap();
+        PoolStatistics {
+            num_announced: state.announced.len(),
+            num_queued: state.input_pool.len(),
+            num_await_delivery: state.output_queue.len(),
+            num_delivered: state.num_delivered,
+            num_underrun: state.num_underrun,
+            num_started: state.num_started,
+            is_grabbing: state.is_grabbing,
+        }
+    }
+
+    /// Mark the pool as grabbing. Returns [`GenTlError::InvalidBuffer`] if no buffer is announced.
+    pub(crate) fn start(&self, num_to_acquire: Option<u64>) -> GenTlResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_grabbing {
+            return Err(GenTlError::ResourceInUse);
+        }
+        if state.announced.is_empty() {
+            return Err(GenTlError::InvalidBuffer);
+        }
+
+        state.is_grabbing = true;
+        state.num_to_acquire = num_to_acquire;
+        state.num_acquired = 0;
+        Ok(())
+    }
+
+    pub(crate) fn stop(&self) {
+        self.state.lock().unwrap().is_grabbing = false;
+    }
+
+    /// Copy the payload to the first buffer in the input pool and move the buffer to the output
+    /// queue.
+    ///
+    /// Returns `false` if the pool doesn't accept payloads anymore.
+    pub(crate) fn deliver(&self, payload: &Payload) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.is_grabbing {
+            return false;
+        }
+
+        state.num_started += 1;
+        let id = if let Some(id) = state.input_pool.pop_front() {
+            id
+        } else {
+            state.num_underrun += 1;
+            return true;
+        };
+
+        let buffer = state.buffers.get_mut(&id).unwrap();
+        let data = payload.payload();
+        let size_filled = data.len().min(buffer.memory.len());
+        buffer.memory.as_mut_slice()[..size_filled].copy_from_slice(&data[..size_filled]);
+
+        buffer.frame = Some(FrameInfo {
+            frame_id: payload.id(),
+            timestamp_ns: payload.timestamp().as_nanos() as u64,
+            size_filled,
+            data_size: data.len(),
+            payload_type: Some(payload.payload_type()),
+            image: payload.image_info().map(|info| ImageInfo {
+                width: info.width,
+                height: info.height,
+                max_height: info.max_height,
+                x_offset: info.x_offset,
+                y_offset: info.y_offset,
+                pixel_format: info.pixel_format.into(),
+            }),
+        });
+        buffer.state = BufferState::Filled;
+        buffer.new_data = true;
+
+        state.output_queue.push_back(id);
+        state.num_delivered += 1;
+        state.num_acquired += 1;
+        if state.is_event_registered {
+            state.num_fired += 1;
+        }
+        self.new_buffer.notify_all();
+
+        if Some(state.num_acquired) == state.num_to_acquire {
+            state.is_grabbing = false;
+            false
+        } else {
+            true
+        }
+    }
+
+    pub(crate) fn register_event(&self) -> GenTlResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_event_registered {
+            Err(GenTlError::ResourceInUse)
+        } else {
+            state.is_event_registered = true;
+            state.is_event_killed = false;
+            Ok(())
+        }
+    }
+
+    pub(crate) fn unregister_event(&self) -> GenTlResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_event_registered {
+            state.is_event_registered = false;
+            // Wake up the waiting consumer.
+            state.is_event_killed = true;
+            self.new_buffer.notify_all();
+            Ok(())
+        } else {
+            Err(GenTlError::NotInitialized)
+        }
+    }
+
+    /// Wait for a filled buffer and deliver it to the consumer. `None` means infinite timeout.
+    pub(crate) fn wait_new_buffer(
+        &self,
+        timeout: Option<Duration>,
+    ) -> GenTlResult<(BufferId, UserPointer)> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if !state.is_event_registered {
+                return Err(GenTlError::NotInitialized);
+            }
+            if state.is_event_killed {
+                state.is_event_killed = false;
+                return Err(GenTlError::Abort);
+            }
+            if let Some(id) = state.output_queue.pop_front() {
+                let buffer = state.buffers.get_mut(&id).unwrap();
+                buffer.state = BufferState::Announced;
+                return Ok((id, buffer.user_ptr));
+            }
+
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(GenTlError::Timeout);
+                    }
+                    self.new_buffer
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.new_buffer.wait(state).unwrap(),
+            };
+        }
+    }
+
+    /// Abort a wait in [`BufferPool::wait_new_buffer`].
+    pub(crate) fn kill_event(&self) {
+        self.state.lock().unwrap().is_event_killed = true;
+        self.new_buffer.notify_all();
+    }
+
+    /// Discard buffers waiting for delivery.
+    pub(crate) fn flush_event(&self) {
+        self.state.lock().unwrap().discard_output();
+    }
+
+    pub(crate) fn num_event_in_queue(&self) -> usize {
+        self.state.lock().unwrap().output_queue.len()
+    }
+
+    pub(crate) fn num_event_fired(&self) -> u64 {
+        self.state.lock().unwrap().num_fired
+    }
+}
+
+impl PoolState {
+    fn buffer(&self, id: BufferId) -> GenTlResult<&Buffer> {
+        self.buffers.get(&id).ok_or(GenTlError::InvalidHandle)
+    }
+
+    fn buffer_mut(&mut self, id: BufferId) -> GenTlResult<&mut Buffer> {
+        self.buffers.get_mut(&id).ok_or(GenTlError::InvalidHandle)
+    }
+
+    fn discard_output(&mut self) {
+        for id in self.output_queue.drain(..) {
+            self.buffers.get_mut(&id).unwrap().state = BufferState::Announced;
+        }
+    }
+}
+
+/// Deliver payloads sent from the device to the pool until the channel is closed or the pool
+/// stops grabbing.
+pub(crate) fn deliver_payloads(pool: &BufferPool, receiver: &PayloadReceiver) {
+    loop {
+        match receiver.recv_blocking() {
+            Ok(payload) => {
+                let is_grabbing = pool.deliver(&payload);
+                receiver.send_back(payload);
+                if !is_grabbing {
+                    break;
+                }
+            }
+            // The channel is closed, i.e. the streaming loop is stopped.
+            Err(StreamError::ReceiveError(..)) => break,
+            // Broken payloads are dropped, they are never delivered to the consumer.
+            Err(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announce(pool: &BufferPool) -> BufferId {
+        pool.announce(BufferMemory::alloc(16), UserPointer(std::ptr::null_mut()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_queue_and_flush() {
+        let pool = BufferPool::new();
+        let id0 = announce(&pool);
+        let id1 = announce(&pool);
+        assert_eq!(pool.buffer_id(1).unwrap(), id1);
+
+        pool.queue(id0).unwrap();
+        assert!(pool.queue(id0).is_err());
+        assert!(matches!(pool.revoke(id0), Err(GenTlError::Busy)));
+
+        pool.flush(FlushOperation::UnqueuedToInput);
+        assert_eq!(pool.statistics().num_queued, 2);
+
+        pool.flush(FlushOperation::InputToOutput);
+        let stats = pool.statistics();
+        assert_eq!(stats.num_queued, 0);
+        assert_eq!(stats.num_await_delivery, 2);
+
+        pool.flush(FlushOperation::OutputDiscard);
+        assert_eq!(pool.statistics().num_await_delivery, 0);
+        pool.revoke(id0).unwrap();
+        assert_eq!(pool.statistics().num_announced, 1);
+    }
+
+    #[test]
+    fn test_new_buffer_event() {
+        let pool = BufferPool::new();
+        let id = announce(&pool);
+        assert!(pool.wait_new_buffer(None).is_err());
+
+        pool.register_event().unwrap();
+        assert!(pool.register_event().is_err());
+        assert!(matches!(
+            pool.wait_new_buffer(Some(Duration::from_millis(1))),
+            Err(GenTlError::Timeout)
+        ));
+
+        pool.kill_event();
+        assert!(matches!(pool.wait_new_buffer(None), Err(GenTlError::Abort)));
+
+        pool.queue(id).unwrap();
+        pool.flush(FlushOperation::InputToOutput);
+        assert_eq!(pool.wait_new_buffer(None).unwrap().0, id);
+        assert!(!pool.buffer_info(id).unwrap().is_queued);
+    }
+
+    #[test]
+    fn test_start_without_buffer() {
+        let pool = BufferPool::new();
+        assert!(matches!(pool.start(None), Err(GenTlError::InvalidBuffer)));
+
+        announce(&pool);
+        pool.start(Some(1)).unwrap();
+        assert!(pool.statistics().is_grabbing);
+        pool.stop();
+        assert!(!pool.statistics().is_grabbing);
+    }
+}
@@ -0,0 +1,148 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::{sync::Arc, thread};
+
+use cameleon::{
+    payload,
+    u3v::{register_map::Abrm, SharedControlHandle, StreamHandle},
+    DeviceControl, PayloadStream,
+};
+
+use crate::{imp::port::TlType, GenTlError, GenTlResult};
+
+use super::{deliver_payloads, BufferPool, DataStream};
+
+/// U3V device has only one stream channel.
+pub(crate) const STREAM_ID: &str = "Stream0";
+
+pub(crate) struct U3VDataStreamModule {
+    ctrl: SharedControlHandle,
+    strm: StreamHandle,
+    pool: Arc<BufferPool>,
+    /// Thread which moves payloads sent from the device to the buffer pool.
+    delivery: Option<thread::JoinHandle<()>>,
+    is_opened: bool,
+}
+
+impl U3VDataStreamModule {
+    pub(crate) fn new(ctrl: SharedControlHandle, strm: StreamHandle) -> Self {
+        Self {
+            ctrl,
+            strm,
+            pool: Arc::new(BufferPool::new()),
+            delivery: None,
+            is_opened: false,
+        }
+    }
+
+    pub(crate) fn is_opened(&self) -> bool {
+        self.is_opened
+    }
+
+    fn assert_open(&self) -> GenTlResult<()> {
+        if self.is_opened {
+            Ok(())
+        } else {
+            Err(GenTlError::NotInitialized)
+        }
+    }
+}
+
+impl Drop for U3VDataStreamModule {
+    fn drop(&mut self) {
+        self.close().ok();
+    }
+}
+
+impl DataStream for U3VDataStreamModule {
+    fn open(&mut self) -> GenTlResult<()> {
+        if self.is_opened {
+            return Err(GenTlError::ResourceInUse);
+        }
+
+        self.strm.open()?;
+        self.is_opened = true;
+        Ok(())
+    }
+
+    fn close(&mut self) -> GenTlResult<()> {
+        if !self.is_opened {
+            return Ok(());
+        }
+
+        self.stop_acquisition()?;
+        self.pool.revoke_all();
+        self.strm.close()?;
+        self.is_opened = false;
+        Ok(())
+    }
+
+    fn stream_id(&self) -> &str {
+        STREAM_ID
+    }
+
+    fn tl_type(&self) -> TlType {
+        TlType::USB3Vision
+    }
+
+    fn buffer_pool(&self) -> &Arc<BufferPool> {
+        &self.pool
+    }
+
+    fn start_acquisition(&mut self, num_to_acquire: Option<u64>) -> GenTlResult<()> {
+        self.assert_open()?;
+        if self.delivery.is_some() {
+            return Err(GenTlError::ResourceInUse);
+        }
+
+        self.pool.start(num_to_acquire)?;
+        // The channel never holds more payloads than the announced buffers.
+        let cap = self.pool.statistics().num_announced;
+        let (sender, receiver) = payload::channel(cap, cap);
+
+        let res = self
+            .ctrl
+            .enable_streaming()
+            .map_err(GenTlError::from)
+            .and_then(|_| Ok(self.strm.start_streaming_loop(sender, &mut self.ctrl)?));
+        if let Err(e) = res {
+            self.pool.stop();
+            return Err(e);
+        }
+
+        let pool = self.pool.clone();
+        self.delivery = Some(thread::spawn(move || deliver_payloads(&pool, &receiver)));
+        Ok(())
+    }
+
+    fn stop_acquisition(&mut self) -> GenTlResult<()> {
+        let delivery = if let Some(delivery) = self.delivery.take() {
+            delivery
+        } else {
+            return Ok(());
+        };
+
+        self.pool.stop();
+        // The delivery thread exits when the streaming loop drops the sender.
+        self.strm.stop_streaming_loop()?;
+        delivery
+            .join()
+            .map_err(|_| GenTlError::Error("delivery thread panicked".into()))?;
+        self.ctrl.disable_streaming()?;
+
+        Ok(())
+    }
+
+    fn payload_size(&self) -> GenTlResult<usize> {
+        self.assert_open()?;
+
+        let mut ctrl = self.ctrl.clone();
+        let sirm = Abrm::new(&mut ctrl)?
+            .sbrm(&mut ctrl)?
+            .sirm(&mut ctrl)?
+            .ok_or(GenTlError::NotAvailable)?;
+        Ok(sirm.required_payload_size(&mut ctrl)? as usize)
+    }
+}
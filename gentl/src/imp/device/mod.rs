@@ -8,7 +8,10 @@ use crate::{GenTlError, GenTlResult};
 
 pub(crate) mod u3v;
 
-use crate::imp::port::{Port, TlType};
+use crate::imp::{
+    data_stream::DataStream,
+    port::{Port, TlType},
+};
 
 mod u3v_genapi;
 
@@ -131,4 +134,14 @@ pub(crate) trait Device: Port {
 
     /// Tick frequency of the device’s timestamp counter in ticks per second
     fn timespamp_frequency(&self) -> GenTlResult<u64>;
+
+    /// Data streams of the device.
+    fn data_streams(&self) -> GenTlResult<Vec<&Mutex<dyn DataStream>>>;
+
+    fn data_stream_by_id(&self, id: &str) -> GenTlResult<&Mutex<dyn DataStream>> {
+        self.data_streams()?
+            .into_iter()
+            .find(|strm| strm.lock().unwrap().stream_id() == id)
+            .ok_or_else(|| GenTlError::InvalidId(id.into()))
+    }
 }
@@ -5,26 +5,39 @@
 use std::{convert::TryFrom, sync::Mutex};
 
 use cameleon::{
-    genapi::{CompressionType, SharedDefaultGenApiCtxt},
-    u3v::{self, SharedControlHandle, StreamHandle},
+    genapi::CompressionType,
+    u3v::{
+        self,
+        register_map::{Abrm, GenICamFileType},
+        SharedControlHandle, StreamHandle,
+    },
+    DeviceControl,
 };
 use cameleon_impl::memory::prelude::*;
 
 use crate::{
     imp::{
+        data_stream::{
+            u3v::{U3VDataStreamModule, STREAM_ID},
+            DataStream,
+        },
         genapi_common,
         port::{Endianness, ModuleType, Port, PortAccess, PortInfo, TlType, XmlInfo, XmlLocation},
     },
     GenTlError, GenTlResult,
 };
 
-use super::{u3v_genapi as genapi, Device, DeviceAccessStatus};
+use super::{u3v_genapi as genapi, Device, DeviceAccessFlag, DeviceAccessStatus};
 use genapi::GenApiReg;
 
-type Camera = cameleon::Camera<SharedControlHandle, StreamHandle, SharedDefaultGenApiCtxt>;
+/// Tick frequency of U3V timestamp, U3V devices report timestamps in nanoseconds.
+const TIMESTAMP_FREQUENCY: u64 = 1_000_000_000;
 
 pub(crate) fn enumerate_u3v_device() -> GenTlResult<Vec<U3VDeviceModule>> {
-    todo!()
+    u3v::enumerate_cameras()?
+        .into_iter()
+        .map(|camera| U3VDeviceModule::new(camera.ctrl.into(), camera.strm))
+        .collect()
 }
 
 pub(crate) struct U3VDeviceModule {
@@ -32,8 +45,10 @@ pub(crate) struct U3VDeviceModule {
     port_info: PortInfo,
     xml_infos: Vec<XmlInfo>,
 
-    camera: Camera,
+    ctrl: SharedControlHandle,
+    device_info: u3v::DeviceInfo,
     remote_device: Option<Box<Mutex<U3VRemoteDevice>>>,
+    data_stream: Box<Mutex<U3VDataStreamModule>>,
 
     /// Current status of the device.  
     /// `DeviceAccessStatus` and `DeviceAccessStatusReg` in VM doesn't reflect this value while
@@ -41,17 +56,17 @@ pub(crate) struct U3VDeviceModule {
     current_status: super::DeviceAccessStatus,
 }
 
-// TODO: Implement methods for stream and event channel.
+// TODO: Implement methods for event channel.
 impl U3VDeviceModule {
-    pub(crate) fn new(camera: Camera) -> GenTlResult<Self> {
-        let device_info = camera.ctrl.device_info();
+    pub(crate) fn new(ctrl: SharedControlHandle, strm: StreamHandle) -> GenTlResult<Self> {
+        let device_info = ctrl.device_info();
 
         let port_info = PortInfo {
-            id: device_info.guid,
+            id: device_info.guid.clone(),
             vendor: genapi::VENDOR_NAME.into(),
             model: genapi::MODEL_NAME.into(),
             tl_type: genapi::DEVICE_TYPE,
-            module_type: ModuleType::Interface,
+            module_type: ModuleType::Device,
             endianness: Endianness::LE,
             access: PortAccess::RW,
             version: semver::Version::new(
@@ -86,7 +101,9 @@ impl U3VDeviceModule {
             port_info,
             xml_infos: vec![xml_info],
 
-            camera,
+            data_stream: Box::new(Mutex::new(U3VDataStreamModule::new(ctrl.clone(), strm))),
+            ctrl,
+            device_info,
             remote_device: None,
 
             current_status: super::DeviceAccessStatus::Unknown,
@@ -97,7 +114,7 @@ impl U3VDeviceModule {
     }
 
     pub(crate) fn device_info(&self) -> &u3v::DeviceInfo {
-        todo!()
+        &self.device_info
     }
 
     /// Reflect current_status to `DeviceAccessStatusReg` in VM.
@@ -147,7 +164,19 @@ impl U3VDeviceModule {
     }
 
     fn initialize_vm(&mut self) -> GenTlResult<()> {
-        todo!()
+        self.vm
+            .write::<GenApiReg::DeviceID>(self.device_info.guid.clone())?;
+        self.vm
+            .write::<GenApiReg::DeviceVendorName>(self.device_info.vendor_name.clone())?;
+        self.vm
+            .write::<GenApiReg::DeviceModelName>(self.device_info.model_name.clone())?;
+        self.reflect_status();
+
+        self.vm.write::<GenApiReg::StreamSelectorMax>(0)?;
+        self.vm.write::<GenApiReg::StreamSelector>(0)?;
+        self.vm.write::<GenApiReg::StreamID>(STREAM_ID.into())?;
+
+        Ok(())
     }
 }
 
@@ -193,12 +222,40 @@ impl Port for U3VDeviceModule {
 }
 
 impl Device for U3VDeviceModule {
-    fn open(&mut self, access_flag: super::DeviceAccessFlag) -> GenTlResult<()> {
-        todo!()
+    fn open(&mut self, access_flag: DeviceAccessFlag) -> GenTlResult<()> {
+        if self.is_opened() {
+            return Err(GenTlError::ResourceInUse);
+        }
+
+        self.ctrl.open()?;
+        let remote_device = match U3VRemoteDevice::new(self.ctrl.clone(), access_flag) {
+            Ok(remote_device) => remote_device,
+            Err(e) => {
+                self.ctrl.close().ok();
+                return Err(e);
+            }
+        };
+        self.remote_device = Some(Box::new(Mutex::new(remote_device)));
+
+        self.current_status = match access_flag {
+            DeviceAccessFlag::ReadOnly => DeviceAccessStatus::OpenReadOnly,
+            DeviceAccessFlag::Control | DeviceAccessFlag::Exclusive => {
+                DeviceAccessStatus::OpenReadWrite
+            }
+        };
+        Ok(())
     }
 
     fn close(&mut self) -> GenTlResult<()> {
-        todo!()
+        if !self.is_opened() {
+            return Ok(());
+        }
+
+        self.data_stream.lock().unwrap().close()?;
+        self.remote_device = None;
+        self.ctrl.close()?;
+        self.current_status = DeviceAccessStatus::ReadWrite;
+        Ok(())
     }
 
     fn device_id(&self) -> &str {
@@ -212,11 +269,11 @@ impl Device for U3VDeviceModule {
     }
 
     fn vendor_name(&self) -> GenTlResult<String> {
-        todo!()
+        Ok(self.device_info.vendor_name.clone())
     }
 
     fn model_name(&self) -> GenTlResult<String> {
-        todo!()
+        Ok(self.device_info.model_name.clone())
     }
 
     fn display_name(&self) -> GenTlResult<String> {
@@ -231,11 +288,14 @@ impl Device for U3VDeviceModule {
     }
 
     fn device_access_status(&self) -> DeviceAccessStatus {
-        todo!()
+        self.current_status
     }
 
     fn user_defined_name(&self) -> GenTlResult<String> {
-        todo!()
+        self.device_info
+            .user_defined_name
+            .clone()
+            .ok_or(GenTlError::NotAvailable)
     }
 
     fn serial_number(&self) -> GenTlResult<String> {
@@ -243,44 +303,107 @@ impl Device for U3VDeviceModule {
     }
 
     fn device_version(&self) -> GenTlResult<String> {
-        todo!()
+        Ok(self.device_info.device_version.clone())
     }
 
     fn timespamp_frequency(&self) -> GenTlResult<u64> {
-        todo!()
+        Ok(TIMESTAMP_FREQUENCY)
+    }
+
+    fn data_streams(&self) -> GenTlResult<Vec<&Mutex<dyn DataStream>>> {
+        self.assert_open()?;
+
+        Ok(vec![self.data_stream.as_ref()])
     }
 }
 
-pub(crate) struct U3VRemoteDevice {}
+pub(crate) struct U3VRemoteDevice {
+    ctrl: SharedControlHandle,
+    port_info: PortInfo,
+    xml_infos: Vec<XmlInfo>,
+}
 
 impl U3VRemoteDevice {
-    fn new(handle: u3v::ControlHandle) -> GenTlResult<Self> {
-        todo!()
+    fn new(mut handle: SharedControlHandle, access_flag: DeviceAccessFlag) -> GenTlResult<Self> {
+        let port_info = Self::port_info(&handle, access_flag);
+        let xml_infos = Self::xml_infos(&mut handle)?;
+
+        Ok(Self {
+            ctrl: handle,
+            port_info,
+            xml_infos,
+        })
     }
 
-    fn port_info(handle: &u3v::ControlHandle) -> GenTlResult<PortInfo> {
-        todo!()
+    fn port_info(handle: &SharedControlHandle, access_flag: DeviceAccessFlag) -> PortInfo {
+        let device_info = handle.device_info();
+        let access = match access_flag {
+            DeviceAccessFlag::ReadOnly => PortAccess::RO,
+            DeviceAccessFlag::Control | DeviceAccessFlag::Exclusive => PortAccess::RW,
+        };
+
+        PortInfo {
+            id: device_info.guid,
+            vendor: device_info.vendor_name,
+            model: device_info.model_name,
+            tl_type: TlType::USB3Vision,
+            module_type: ModuleType::RemoteDevice,
+            endianness: Endianness::LE,
+            access,
+            version: device_info.u3v_version,
+            port_name: "Device".into(),
+        }
     }
 
-    fn xml_infos(handle: &u3v::ControlHandle) -> GenTlResult<Vec<XmlInfo>> {
-        todo!()
+    /// Collect `GenApi` XML descriptions of the remote device from its manifest table.
+    fn xml_infos(handle: &mut SharedControlHandle) -> GenTlResult<Vec<XmlInfo>> {
+        let abrm = Abrm::new(handle)?;
+        let entries = abrm.manifest_table(handle)?.entries(handle)?;
+
+        let mut xml_infos = vec![];
+        for entry in entries {
+            let file_info = entry.file_info(handle)?;
+            if file_info.file_type()? != GenICamFileType::DeviceXml {
+                continue;
+            }
+
+            xml_infos.push(XmlInfo {
+                location: XmlLocation::RegisterMap {
+                    address: entry.file_address(handle)?,
+                    size: entry.file_size(handle)? as usize,
+                },
+                schema_version: file_info.schema_version(),
+                file_version: entry.genicam_file_version(handle)?,
+                sha1_hash: entry.sha1_hash(handle)?,
+                compressed: file_info.compression_type()?,
+            });
+        }
+
+        Ok(xml_infos)
     }
 }
 
 impl Port for U3VRemoteDevice {
     fn read(&self, address: u64, buf: &mut [u8]) -> GenTlResult<usize> {
-        todo!()
+        // `SharedControlHandle` is a reference counted handle, so cloning it is cheap.
+        self.ctrl.clone().read(address, buf)?;
+        Ok(buf.len())
     }
 
     fn write(&mut self, address: u64, data: &[u8]) -> GenTlResult<usize> {
-        todo!()
+        if !self.port_info.access.is_writable() {
+            return Err(GenTlError::AccessDenied);
+        }
+
+        self.ctrl.write(address, data)?;
+        Ok(data.len())
     }
 
     fn port_info(&self) -> GenTlResult<&PortInfo> {
-        todo!()
+        Ok(&self.port_info)
     }
 
     fn xml_infos(&self) -> GenTlResult<&[XmlInfo]> {
-        todo!()
+        Ok(&self.xml_infos)
     }
 }
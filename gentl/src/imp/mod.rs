@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+pub(super) mod data_stream;
 pub(super) mod device;
 pub(super) mod interface;
 pub(super) mod port;
@@ -9,7 +10,7 @@ pub(super) mod system;
 
 mod genapi_common;
 
-use cameleon::ControlError;
+use cameleon::{CameleonError, ControlError, StreamError};
 use cameleon_impl::memory::MemoryError;
 
 use super::GenTlError;
@@ -27,22 +28,63 @@ impl From<MemoryError> for GenTlError {
 impl From<ControlError> for GenTlError {
     fn from(err: ControlError) -> Self {
         use GenTlError::{
-            BufferTooSmall, InvalidValue, Io, NotInitialized, ResourceInUse, Timeout,
+            Abort, AccessDenied, BufferTooSmall, InvalidAddress, InvalidValue, Io, NotInitialized,
+            ResourceInUse, Timeout,
         };
 
         match err {
             ControlError::Busy => ResourceInUse,
-            ControlError::Disconnected | ControlError::Io(..) | ControlError::InvalidDevice(..) => {
-                Io(err.into())
-            }
+            ControlError::Disconnected
+            | ControlError::Io(..)
+            | ControlError::InvalidDevice(..)
+            | ControlError::RequestIdMismatch { .. }
+            | ControlError::ProtocolViolation { .. } => Io(err.into()),
+            // `GenCP` status codes.
+            ControlError::Status { code: 0x8003, .. } => InvalidAddress,
+            ControlError::Status {
+                code: 0x8004 | 0x8006,
+                ..
+            } => AccessDenied,
+            ControlError::Status { code: 0x8007, .. } => ResourceInUse,
+            ControlError::Status { .. } => Io(err.into()),
             ControlError::NotOpened => NotInitialized,
             ControlError::InvalidData(..) => InvalidValue(format!("{}", err).into()),
             ControlError::Timeout => Timeout,
+            ControlError::Cancelled => Abort,
             ControlError::BufferTooSmall => BufferTooSmall,
         }
     }
 }
 
+impl From<StreamError> for GenTlError {
+    fn from(err: StreamError) -> Self {
+        use GenTlError::{BufferTooSmall, Error, InvalidValue, Io, ResourceInUse, Timeout};
+
+        match err {
+            StreamError::Disconnected | StreamError::Io(..) => Io(err.into()),
+            StreamError::InvalidPayload(..) | StreamError::PayloadSizeChanged(..) => {
+                InvalidValue(format!("{}", err).into())
+            }
+            StreamError::Timeout => Timeout,
+            StreamError::BufferTooSmall => BufferTooSmall,
+            StreamError::InStreaming => ResourceInUse,
+            StreamError::ReceiveError(..)
+            | StreamError::SendError(..)
+            | StreamError::Poisoned(..) => Error(format!("{}", err)),
+        }
+    }
+}
+
+impl From<CameleonError> for GenTlError {
+    fn from(err: CameleonError) -> Self {
+        match err {
+            CameleonError::ControlError(err) => err.into(),
+            CameleonError::StreamError(err) => err.into(),
+            _ => Self::Error(format!("{}", err)),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub(crate) enum CharEncoding {
     Ascii,
@@ -71,6 +71,7 @@ impl MemoryStruct {
                 raw: Vec<u8>,
                 protection: cameleon_impl::memory::MemoryProtection,
                 observers: std::vec::Vec<(std::ops::Range<usize>, std::boxed::Box<dyn cameleon_impl::memory::MemoryObserver>)>,
+                journal: std::option::Option<cameleon_impl::memory::WriteJournal>,
             }
         }
     }
@@ -85,14 +86,15 @@ impl MemoryStruct {
                 #new
 
                 #[doc(hidden)]
-                fn notify_all(&self, written_range: std::ops::Range<usize>) {
+                fn notify_all(&self, written_ranges: &[std::ops::Range<usize>]) {
 
                     for (reg_range, observer) in &self.observers {
-
-                        if written_range.start >= reg_range.end || written_range.end <= reg_range.start {
-                            continue;
+                        let is_overlapped = written_ranges.iter().any(|written_range| {
+                            written_range.start < reg_range.end && reg_range.start < written_range.end
+                        });
+                        if is_overlapped {
+                            observer.update();
                         }
-                        observer.update();
                     }
                 }
 
@@ -147,8 +149,13 @@ impl MemoryStruct {
                         return Err(cameleon_impl::memory::MemoryError::AddressNotWritable);
                     }
 
-                    self.raw[range].copy_from_slice(buf);
-                    self.notify_all(start..end);
+                    if let Some(journal) = &mut self.journal {
+                        journal.record(start, &self.raw[range.clone()]);
+                        self.raw[range].copy_from_slice(buf);
+                    } else {
+                        self.raw[range.clone()].copy_from_slice(buf);
+                        self.notify_all(&[range]);
+                    }
 
                     Ok(())
                 }
@@ -159,8 +166,14 @@ impl MemoryStruct {
 
 
                 fn write<T: cameleon_impl::memory::Register>(&mut self, data: T::Ty) -> cameleon_impl::memory::MemoryResult<()>{
-                    T::write(data, &mut self.raw)?;
-                    self.notify_all(T::range());
+                    let range = T::range();
+                    if let Some(journal) = &mut self.journal {
+                        journal.record(range.start, &self.raw[range.clone()]);
+                        T::write(data, &mut self.raw)?;
+                    } else {
+                        T::write(data, &mut self.raw)?;
+                        self.notify_all(&[range]);
+                    }
 
                     Ok(())
                 }
@@ -177,6 +190,26 @@ impl MemoryStruct {
                     self.observers.push((reg_range, Box::new(observer)));
                 }
 
+                fn transaction<F, R>(&mut self, f: F) -> cameleon_impl::memory::MemoryResult<R>
+                    where F: FnOnce(&mut Self) -> cameleon_impl::memory::MemoryResult<R>
+                {
+                    if self.journal.is_some() {
+                        return f(self);
+                    }
+
+                    self.journal = Some(cameleon_impl::memory::WriteJournal::new());
+                    let res = f(self);
+                    let journal = self.journal.take().unwrap();
+
+                    if res.is_ok() {
+                        self.notify_all(&journal.written_ranges());
+                    } else {
+                        journal.rollback(&mut self.raw);
+                    }
+
+                    res
+                }
+
             }
         }
     }
@@ -202,6 +235,7 @@ impl MemoryStruct {
                     raw,
                     protection,
                     observers: std::vec::Vec::new(),
+                    journal: None,
                 }
             }
         }
@@ -52,6 +52,19 @@ pub trait MemoryWrite {
     where
         T: Register,
         U: MemoryObserver + 'static;
+
+    /// Execute `f` as a single transaction.
+    ///
+    /// Writes done in `f` are applied to the memory immediately, but observers are notified only
+    /// after `f` returns `Ok`, so they never see partially applied writes.
+    /// Each observer is notified at most once per transaction even if several of its registers are written.
+    /// If `f` returns an error, all writes done in `f` are rolled back and no observer is notified.
+    ///
+    /// Nested transactions are merged into the outermost one.
+    /// Access rights changed in `f` are NOT rolled back.
+    fn transaction<F, R>(&mut self, f: F) -> MemoryResult<R>
+    where
+        F: FnOnce(&mut Self) -> MemoryResult<R>;
 }
 
 pub trait MemoryObserver: Send {
@@ -214,6 +227,41 @@ impl MemoryProtection {
     }
 }
 
+/// Journal of writes made during a transaction.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct WriteJournal {
+    /// Start address and original contents of each written range.
+    entries: Vec<(usize, Vec<u8>)>,
+}
+
+impl WriteJournal {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record original contents of the memory located at `addr` before it's overwritten.
+    pub fn record(&mut self, addr: usize, original: &[u8]) {
+        self.entries.push((addr, original.to_vec()));
+    }
+
+    #[must_use]
+    pub fn written_ranges(&self) -> Vec<std::ops::Range<usize>> {
+        self.entries
+            .iter()
+            .map(|(addr, original)| *addr..*addr + original.len())
+            .collect()
+    }
+
+    /// Restore the original contents of all recorded ranges.
+    pub fn rollback(self, raw: &mut [u8]) {
+        for (addr, original) in self.entries.into_iter().rev() {
+            raw[addr..addr + original.len()].copy_from_slice(&original);
+        }
+    }
+}
+
 pub trait Register {
     type Ty;
 
@@ -279,4 +327,20 @@ mod tests {
         assert!(protection.verify_address_with_range(2..5).is_ok());
         assert!(protection.verify_address_with_range(2..6).is_err());
     }
+
+    #[test]
+    fn test_write_journal() {
+        let mut raw = vec![0, 1, 2, 3, 4];
+        let mut journal = WriteJournal::new();
+
+        journal.record(1, &raw[1..3]);
+        raw[1..3].copy_from_slice(&[10, 20]);
+        journal.record(2, &raw[2..4]);
+        raw[2..4].copy_from_slice(&[30, 40]);
+        assert_eq!(raw, &[0, 10, 30, 40, 4]);
+        assert_eq!(journal.written_ranges(), vec![1..3, 2..4]);
+
+        journal.rollback(&mut raw);
+        assert_eq!(raw, &[0, 1, 2, 3, 4]);
+    }
 }
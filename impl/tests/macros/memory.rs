@@ -2,7 +2,14 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use cameleon_impl::memory::{memory, prelude::*, register_map, AccessRight};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use cameleon_impl::memory::{
+    memory, prelude::*, register_map, AccessRight, MemoryError, MemoryObserver,
+};
 
 const SBRM_ADDRESS: u64 = 0x1000;
 const SIRM_ADDRESS: u64 = 0x2000;
@@ -71,6 +78,14 @@ enum SBRM {
     TestF64 = 0.27,
 }
 
+struct CountObserver(Arc<AtomicUsize>);
+
+impl MemoryObserver for CountObserver {
+    fn update(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 fn main() {
     let mut memory = Memory::new();
 
@@ -118,4 +133,34 @@ fn main() {
     assert_eq!(memory.access_right::<SBRM::EIRMLength>(), AccessRight::NA);
 
     assert!(memory.read_raw(1000..1004).is_err());
+
+    // Test transaction.
+    let count = Arc::new(AtomicUsize::new(0));
+    memory.register_observer::<SBRM::TestI32, _>(CountObserver(count.clone()));
+    memory.register_observer::<SBRM::TestI64, _>(CountObserver(count.clone()));
+
+    memory
+        .transaction(|memory| {
+            memory.write::<SBRM::TestI32>(1)?;
+            memory.write::<SBRM::TestI32>(2)?;
+            memory.write::<SBRM::TestI64>(3)?;
+            assert_eq!(count.load(Ordering::Relaxed), 0);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(count.load(Ordering::Relaxed), 2);
+    assert_eq!(memory.read::<SBRM::TestI32>().unwrap(), 2);
+    assert_eq!(memory.read::<SBRM::TestI64>().unwrap(), 3);
+
+    let res: Result<(), _> = memory.transaction(|memory| {
+        memory.write::<SBRM::TestI32>(10)?;
+        memory.write_raw(ABRM::ManufacturerName::ADDRESS, b"Broken")?;
+        Err(MemoryError::InvalidAddress)
+    });
+    assert!(res.is_err());
+    assert_eq!(count.load(Ordering::Relaxed), 2);
+    assert_eq!(memory.read::<SBRM::TestI32>().unwrap(), 2);
+    assert_eq!(memory.read::<SBRM::TestI64>().unwrap(), 3);
+    let manufacturer_name = memory.read::<ABRM::ManufacturerName>().unwrap();
+    assert_eq!(&manufacturer_name, "New name");
 }